@@ -0,0 +1,25 @@
+//! Build script: generates the C header for the `ffi` feature.
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_c_header();
+}
+
+/// Writes `include/ironshield.h` from the `extern "C"` items in `src/ffi.rs`.
+#[cfg(feature = "ffi")]
+fn generate_c_header() {
+    let crate_dir: String = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("cbindgen.toml is valid");
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate C bindings")
+        .write_to_file(format!("{}/include/ironshield.h", crate_dir));
+}
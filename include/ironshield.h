@@ -0,0 +1,227 @@
+#ifndef IRONSHIELD_H
+#define IRONSHIELD_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result code of every fallible FFI call.
+ */
+typedef enum IronShieldStatus {
+  /**
+   * The call succeeded.
+   */
+  IRON_SHIELD_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  IRON_SHIELD_STATUS_NULL_POINTER = 1,
+  /**
+   * A string argument was not valid UTF-8.
+   */
+  IRON_SHIELD_STATUS_INVALID_UTF8 = 2,
+  /**
+   * A header value could not be decoded.
+   */
+  IRON_SHIELD_STATUS_PARSE_ERROR = 3,
+  /**
+   * The public key is not a valid Ed25519 key.
+   */
+  IRON_SHIELD_STATUS_INVALID_KEY = 4,
+  /**
+   * A signature did not verify.
+   */
+  IRON_SHIELD_STATUS_VERIFICATION_FAILED = 5,
+  /**
+   * The challenge or token has expired.
+   */
+  IRON_SHIELD_STATUS_EXPIRED = 6,
+  /**
+   * The proof-of-work solution does not meet the target.
+   */
+  IRON_SHIELD_STATUS_INVALID_SOLUTION = 7,
+} IronShieldStatus;
+
+/**
+ * A set of protocol features, one bit each.
+ */
+typedef struct CapabilityBits CapabilityBits;
+
+/**
+ * Optional protocol features a peer implements.
+ *
+ * * `compact_encoding`: The compact binary wire format.
+ * * `blake3`:           BLAKE3 hashing.
+ * * `bundles`:          Several challenges issued and answered in one
+ *                       bundle.
+ * * `payment_bypass`:   Payment receipts in place of proof-of-work.
+ */
+typedef struct FeatureFlags FeatureFlags;
+
+/**
+ * IronShield Challenge structure for the proof-of-work algorithm
+ *
+ * * `random_nonce`:         The SHA-256 hash of a random number (hex string).
+ * * `created_time`:         Unix milli timestamp for the challenge.
+ * * `expiration_time`:      Unix milli timestamp for the challenge expiration time.
+ * * `challenge_param`:      Target threshold - hash must be less than this value.
+ * * `recommended_attempts`: Expected number of attempts for user guidance (3x difficulty).
+ * * `website_id`:           The identifier of the website.
+ * * `public_key`:           Ed25519 public key for signature verification.
+ * * `challenge_signature`:  Ed25519 signature over the challenge data.
+ * * `submission_deadline`:  Optional Unix milli timestamp after which solutions
+ *                           are no longer accepted (signed extension).
+ * * `request_binding`:      Optional SHA-256 of the `RequestDescriptor` the
+ *                           challenge may be redeemed for (signed extension).
+ * * `issuer_node`:          Optional identifier of the edge node (colo, region
+ *                           or worker id) that issued the challenge (signed extension).
+ * * `min_age_ms`:           Optional minimum milliseconds between `created_time`
+ *                           and redemption; makes this a wait challenge
+ *                           (signed extension).
+ * * `issuance_context`:     Optional SHA-256 commitment to the `IssuanceContext`
+ *                           the difficulty was based on (signed extension).
+ * * `nonce_proof`:          Optional VRF proof that `random_nonce` was derived
+ *                           from `website_id` and a counter (signed extension).
+ * * `time_attestation`:     Optional time authority attestation of
+ *                           `created_time` (signed extension).
+ * * `required_solutions`:   Optional number of distinct nonces that must each
+ *                           meet the target (signed extension).
+ * * `time_lock`:            Optional time-lock puzzle; makes this a time-lock
+ *                           challenge (signed extension).
+ * * `vdf`:                  Optional VDF modulus and iterations; makes this a
+ *                           VDF challenge (signed extension).
+ */
+typedef struct ironshield_challenge_t ironshield_challenge_t;
+
+typedef struct ironshield_response_t ironshield_response_t;
+
+typedef struct ironshield_token_t ironshield_token_t;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns a static, NUL-terminated description of a status code.
+ */
+const char *ironshield_status_message(enum IronShieldStatus status);
+
+/**
+ * Decodes an `X-IronShield-Challenge` header value.
+ *
+ * # Safety
+ * `header` must be a valid NUL-terminated string and `out` a valid
+ * pointer. On success `*out` owns a handle that must be released with
+ * `ironshield_challenge_free`.
+ */
+enum IronShieldStatus ironshield_challenge_parse_header(const char *header,
+                                                        struct ironshield_challenge_t **out);
+
+/**
+ * Releases a challenge handle. Null is ignored.
+ *
+ * # Safety
+ * `challenge` must be null or a handle returned by this library that
+ * has not been freed yet.
+ */
+void ironshield_challenge_free(struct ironshield_challenge_t *challenge);
+
+/**
+ * Verifies the signature of a challenge and that it has not expired.
+ *
+ * # Safety
+ * `challenge` must be a valid handle and `public_key` must point to
+ * 32 readable bytes.
+ */
+enum IronShieldStatus ironshield_challenge_verify(const struct ironshield_challenge_t *challenge,
+                                                  const uint8_t *public_key);
+
+/**
+ * Checks a proof-of-work solution against a challenge.
+ *
+ * # Safety
+ * `challenge` must be a valid handle.
+ */
+enum IronShieldStatus ironshield_challenge_check_solution(const struct ironshield_challenge_t *challenge,
+                                                          int64_t nonce);
+
+/**
+ * Decodes an `X-IronShield-Challenge-Response` header value.
+ *
+ * # Safety
+ * `header` must be a valid NUL-terminated string and `out` a valid
+ * pointer. On success `*out` owns a handle that must be released with
+ * `ironshield_response_free`.
+ */
+enum IronShieldStatus ironshield_response_parse_header(const char *header,
+                                                       struct ironshield_response_t **out);
+
+/**
+ * Releases a response handle. Null is ignored.
+ *
+ * # Safety
+ * `response` must be null or a handle returned by this library that
+ * has not been freed yet.
+ */
+void ironshield_response_free(struct ironshield_response_t *response);
+
+/**
+ * Fully verifies a challenge response: the challenge signature, the
+ * submission deadline and the proof-of-work solution.
+ *
+ * # Safety
+ * `response` must be a valid handle and `public_key` must point to
+ * 32 readable bytes.
+ */
+enum IronShieldStatus ironshield_response_verify(const struct ironshield_response_t *response,
+                                                 const uint8_t *public_key);
+
+/**
+ * Decodes an `X-IronShield-Token` header value.
+ *
+ * # Safety
+ * `header` must be a valid NUL-terminated string and `out` a valid
+ * pointer. On success `*out` owns a handle that must be released with
+ * `ironshield_token_free`.
+ */
+enum IronShieldStatus ironshield_token_parse_header(const char *header,
+                                                    struct ironshield_token_t **out);
+
+/**
+ * Releases a token handle. Null is ignored.
+ *
+ * # Safety
+ * `token` must be null or a handle returned by this library that has
+ * not been freed yet.
+ */
+void ironshield_token_free(struct ironshield_token_t *token);
+
+/**
+ * Verifies the signature of a token and that it has not expired.
+ *
+ * # Safety
+ * `token` must be a valid handle and `public_key` must point to
+ * 32 readable bytes.
+ */
+enum IronShieldStatus ironshield_token_verify(const struct ironshield_token_t *token,
+                                              const uint8_t *public_key);
+
+/**
+ * Returns the Unix milli timestamp until which a token is valid, or
+ * `0` for a null handle.
+ *
+ * # Safety
+ * `token` must be null or a valid handle.
+ */
+int64_t ironshield_token_valid_for(const struct ironshield_token_t *token);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* IRONSHIELD_H */
@@ -0,0 +1,152 @@
+//! # Proof-of-work and signature scheme identifiers.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Proof-of-work algorithm a challenge is issued for.
+///
+/// * `Sha256`:   SHA256(random_nonce_bytes + nonce_bytes) must be
+///               less than `challenge_param`.
+/// * `Wait`:     No work; the challenge is redeemable once
+///               `min_age_ms` has passed since `created_time`.
+/// * `TimeLock`: `time_lock.iterations` sequential squarings modulo an
+///               RSA modulus, which extra cores cannot speed up.
+/// * `Vdf`:      The same squarings with a publicly verifiable
+///               Wesolowski proof.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowAlgorithm {
+    /// Hashcash-style SHA-256 target search.
+    #[default]
+    Sha256,
+    /// Proof of elapsed time for clients that cannot afford hashing.
+    Wait,
+    /// Rivest-Shamir-Wagner time-lock puzzle.
+    TimeLock,
+    /// Wesolowski verifiable delay function.
+    Vdf,
+}
+
+impl PowAlgorithm {
+    /// # Returns
+    /// * `&'static str`: The stable wire identifier of the algorithm.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PowAlgorithm::Sha256   => "sha256",
+            PowAlgorithm::Wait     => "wait",
+            PowAlgorithm::TimeLock => "time_lock",
+            PowAlgorithm::Vdf      => "vdf",
+        }
+    }
+}
+
+impl std::fmt::Display for PowAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PowAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256"    => Ok(PowAlgorithm::Sha256),
+            "wait"      => Ok(PowAlgorithm::Wait),
+            "time_lock" => Ok(PowAlgorithm::TimeLock),
+            "vdf"       => Ok(PowAlgorithm::Vdf),
+            other => Err(format!("Unknown proof-of-work algorithm: {}", other)),
+        }
+    }
+}
+
+/// Scheme used to sign challenges and tokens.
+///
+/// * `Ed25519`:    Asymmetric signatures; anyone holding the public key
+///                 can verify.
+/// * `HmacSha256`: A 32-byte HMAC-SHA256 tag under a key shared by the
+///                 issuer and verifier, for single-operator deployments
+///                 that issue and verify on the same box. The tag is
+///                 stored in the first half of the signature field and
+///                 the challenge's `public_key` is all zeros.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureScheme {
+    #[default]
+    Ed25519,
+    HmacSha256,
+}
+
+impl SignatureScheme {
+    /// # Returns
+    /// * `&'static str`: The stable wire identifier of the scheme.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureScheme::Ed25519    => "ed25519",
+            SignatureScheme::HmacSha256 => "hmac_sha256",
+        }
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if signatures can be checked without the
+    ///           issuer's secret, e.g. by a client.
+    pub fn is_publicly_verifiable(&self) -> bool {
+        matches!(self, SignatureScheme::Ed25519)
+    }
+}
+
+impl std::fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SignatureScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ed25519"     => Ok(SignatureScheme::Ed25519),
+            "hmac_sha256" => Ok(SignatureScheme::HmacSha256),
+            other => Err(format!("Unknown signature scheme: {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithm_string_roundtrip() {
+        let algorithm: PowAlgorithm = "sha256".parse().unwrap();
+        assert_eq!(algorithm, PowAlgorithm::Sha256);
+        assert_eq!(algorithm.to_string(), "sha256");
+        assert_eq!("wait".parse::<PowAlgorithm>().unwrap(), PowAlgorithm::Wait);
+    }
+
+    #[test]
+    fn test_algorithm_unknown_identifier() {
+        let result = "scrypt".parse::<PowAlgorithm>();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("Unknown proof-of-work algorithm"));
+    }
+
+    #[test]
+    fn test_algorithm_serde_matches_wire_identifier() {
+        let json = serde_json::to_string(&PowAlgorithm::Sha256).unwrap();
+        assert_eq!(json, "\"sha256\"");
+    }
+
+    #[test]
+    fn test_signature_scheme_identifiers() {
+        assert_eq!("hmac_sha256".parse::<SignatureScheme>().unwrap(), SignatureScheme::HmacSha256);
+        assert_eq!(serde_json::to_string(&SignatureScheme::HmacSha256).unwrap(), "\"hmac_sha256\"");
+        assert!("rsa".parse::<SignatureScheme>().unwrap_err().contains("Unknown signature scheme"));
+        assert!(!SignatureScheme::HmacSha256.is_publicly_verifiable());
+    }
+}
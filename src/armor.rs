@@ -0,0 +1,117 @@
+//! # ASCII armor for pasting binary values into text.
+//!
+//! Same layout as OpenPGP armor (RFC 4880, section 6): a
+//! `-----BEGIN <LABEL>-----` line, the base64 body wrapped at 64
+//! columns, a `=` line with the base64 CRC-24 of the data, and a
+//! matching `-----END <LABEL>-----` line. The checksum catches values
+//! mangled by mail clients, ticket systems or terminals.
+
+use base64::{
+    Engine,
+    engine::general_purpose::STANDARD
+};
+
+/// Maximum number of base64 characters per body line.
+const LINE_LEN:  usize = 64;
+/// CRC-24 initial value and generator polynomial from RFC 4880.
+const CRC24_INIT: u32 = 0x00B7_04CE;
+const CRC24_POLY: u32 = 0x0186_4CFB;
+
+/// CRC-24 checksum as used by OpenPGP armor.
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = CRC24_INIT;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0x00FF_FFFF
+}
+
+/// Wraps `data` in an armor block labelled `label`.
+pub(crate) fn encode_armor(label: &str, data: &[u8]) -> String {
+    let body: String = STANDARD.encode(data);
+    let checksum: String = STANDARD.encode(&crc24(data).to_be_bytes()[1..]);
+
+    let mut armored: String = format!("-----BEGIN {}-----\n", label);
+    // Base64 output is ASCII, so splitting at byte offsets is safe.
+    for line in body.as_bytes().chunks(LINE_LEN) {
+        armored.push_str(std::str::from_utf8(line).unwrap_or_default());
+        armored.push('\n');
+    }
+    armored.push('=');
+    armored.push_str(&checksum);
+    armored.push('\n');
+    armored.push_str(&format!("-----END {}-----\n", label));
+    armored
+}
+
+/// Unwraps an armor block labelled `label` and checks its CRC-24.
+/// Surrounding whitespace and `\r\n` line endings are accepted.
+pub(crate) fn decode_armor(label: &str, armored: &str) -> Result<Vec<u8>, String> {
+    let begin: String = format!("-----BEGIN {}-----", label);
+    let end: String = format!("-----END {}-----", label);
+    let lines: Vec<&str> = armored.trim().lines().map(str::trim).collect();
+
+    if lines.first() != Some(&begin.as_str()) {
+        return Err(format!("Missing armor header: {}", begin));
+    }
+    if lines.len() < 2 || lines[lines.len() - 1] != end {
+        return Err(format!("Missing armor footer: {}", end));
+    }
+
+    let inner: &[&str] = &lines[1..lines.len() - 1];
+    let (checksum_line, body_lines) = inner.split_last()
+        .filter(|(checksum_line, _)| checksum_line.starts_with('='))
+        .ok_or("Missing armor checksum")?;
+
+    let data: Vec<u8> = STANDARD.decode(body_lines.concat())
+        .map_err(|e| format!("Failed to decode armor body: {}", e))?;
+    let checksum: Vec<u8> = STANDARD.decode(&checksum_line[1..])
+        .map_err(|e| format!("Failed to decode armor checksum: {}", e))?;
+
+    if checksum.as_slice() != &crc24(&data).to_be_bytes()[1..] {
+        return Err("Armor checksum mismatch; the block was altered or truncated".to_string());
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc24_known_value() {
+        // CRC-24/OPENPGP check value.
+        assert_eq!(crc24(b"123456789"), 0x21CF02);
+    }
+
+    #[test]
+    fn test_roundtrip_and_wrapping() {
+        let data: Vec<u8> = (0..=255).collect();
+        let armored: String = encode_armor("TEST BLOCK", &data);
+
+        assert!(armored.lines().all(|line| line.len() <= LINE_LEN || line.starts_with("-----")));
+        assert_eq!(decode_armor("TEST BLOCK", &armored).unwrap(), data);
+        assert_eq!(decode_armor("TEST BLOCK", &format!("\n  {}  ", armored.replace('\n', "\r\n"))).unwrap(), data);
+        assert!(decode_armor("OTHER BLOCK", &armored).unwrap_err().contains("header"));
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let armored: String = encode_armor("TEST BLOCK", b"some payload that spans a little");
+        let corrupted: String = armored.replacen("c29tZ", "c29uZ", 1);
+        assert!(decode_armor("TEST BLOCK", &corrupted).unwrap_err().contains("checksum mismatch"));
+
+        let without_checksum: String = armored.lines().filter(|line| !line.starts_with('=')).collect::<Vec<_>>().join("\n");
+        assert!(decode_armor("TEST BLOCK", &without_checksum).unwrap_err().contains("Missing armor checksum"));
+
+        let truncated: String = armored.lines().take(2).collect::<Vec<_>>().join("\n");
+        assert!(decode_armor("TEST BLOCK", &truncated).unwrap_err().contains("footer"));
+    }
+}
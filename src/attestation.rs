@@ -0,0 +1,242 @@
+//! # Device attestation as an alternative to proof-of-work.
+//!
+//! Private Access Tokens (RFC 9577) let an attester such as Apple vouch
+//! for a client without the client solving anything. `DeviceAttestation`
+//! carries the raw token bytes together with the challenge they answer;
+//! `AttestationToken` is the parsed token. Checking the issuer's
+//! authenticator is left to a `BypassVerifier`, since it needs the
+//! issuer's RSA key.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::IronShieldChallenge;
+
+/// Token type of publicly verifiable (blind RSA) Private Access Tokens.
+pub const PAT_TOKEN_TYPE_BLIND_RSA: u16 = 0x0002;
+/// Length of the token key id and authenticator for blind RSA tokens.
+const BLIND_RSA_KEY_ID_LEN:        usize = 32;
+const BLIND_RSA_AUTHENTICATOR_LEN: usize = 256;
+/// token_type (2) + nonce (32) + challenge_digest (32).
+const TOKEN_HEADER_LEN:            usize = 66;
+
+/// Parsed RFC 9577 token.
+///
+/// * `token_type`:       Token type, e.g. `PAT_TOKEN_TYPE_BLIND_RSA`.
+/// * `nonce`:            Client-chosen nonce.
+/// * `challenge_digest`: SHA-256 of the `TokenChallenge` the token answers.
+/// * `token_key_id`:     SHA-256 of the issuer public key.
+/// * `authenticator`:    Issuer signature over all other fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationToken {
+    pub token_type:       u16,
+    pub nonce:            [u8; 32],
+    pub challenge_digest: [u8; 32],
+    pub token_key_id:     [u8; 32],
+    pub authenticator:    Vec<u8>,
+}
+
+impl AttestationToken {
+    /// Parses the wire encoding of a token.
+    ///
+    /// # Arguments
+    /// * `bytes`: The token as sent in the `Authorization: PrivateToken` header.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed token, or an error if the type
+    ///                           is unsupported or the length is wrong.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 2 {
+            return Err("Attestation token is too short".to_string());
+        }
+
+        let token_type: u16 = u16::from_be_bytes([bytes[0], bytes[1]]);
+        if token_type != PAT_TOKEN_TYPE_BLIND_RSA {
+            return Err(format!("Unsupported attestation token type: {:#06x}", token_type));
+        }
+
+        let expected_len: usize = TOKEN_HEADER_LEN + BLIND_RSA_KEY_ID_LEN + BLIND_RSA_AUTHENTICATOR_LEN;
+        if bytes.len() != expected_len {
+            return Err(format!("Attestation token must be {} bytes, got {}", expected_len, bytes.len()));
+        }
+
+        let array = |start: usize| -> [u8; 32] {
+            let mut out: [u8; 32] = [0u8; 32];
+            out.copy_from_slice(&bytes[start..start + 32]);
+            out
+        };
+
+        Ok(Self {
+            token_type,
+            nonce:            array(2),
+            challenge_digest: array(34),
+            token_key_id:     array(TOKEN_HEADER_LEN),
+            authenticator:    bytes[TOKEN_HEADER_LEN + BLIND_RSA_KEY_ID_LEN..].to_vec(),
+        })
+    }
+
+    /// # Returns
+    /// * `Vec<u8>`: The bytes covered by `authenticator`: every field
+    ///              before it in wire order.
+    pub fn authenticator_input(&self) -> Vec<u8> {
+        let mut input: Vec<u8> = Vec::with_capacity(TOKEN_HEADER_LEN + BLIND_RSA_KEY_ID_LEN);
+        input.extend_from_slice(&self.token_type.to_be_bytes());
+        input.extend_from_slice(&self.nonce);
+        input.extend_from_slice(&self.challenge_digest);
+        input.extend_from_slice(&self.token_key_id);
+        input
+    }
+}
+
+/// Device attestation presented in place of a proof-of-work solution.
+///
+/// * `challenge_signature`: Signature of the challenge being bypassed.
+/// * `token`:               Raw RFC 9577 token bytes.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Private Access Token accepted in place of a proof-of-work solution"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceAttestation {
+    /// Signature of the challenge this attestation bypasses (64 bytes)
+    #[serde(
+        serialize_with = "crate::serialize_signature",
+        deserialize_with = "crate::deserialize_signature"
+    )]
+    pub challenge_signature: [u8; 64],
+    /// Raw RFC 9577 token bytes
+    pub token:               Vec<u8>,
+}
+
+impl DeviceAttestation {
+    /// Constructor for creating a new `DeviceAttestation`.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge being bypassed.
+    /// * `token`:     Raw token bytes obtained from the attester.
+    pub fn new(challenge: &IronShieldChallenge, token: Vec<u8>) -> Self {
+        Self {
+            challenge_signature: challenge.challenge_signature,
+            token,
+        }
+    }
+
+    /// # Returns
+    /// * `Result<AttestationToken, String>`: The parsed token.
+    pub fn parse_token(&self) -> Result<AttestationToken, String> {
+        AttestationToken::parse(&self.token)
+    }
+
+    /// Checks the structure of the token and that it answers the
+    /// `TokenChallenge` the issuer sent with the challenge.
+    ///
+    /// Does not check the authenticator.
+    ///
+    /// # Arguments
+    /// * `token_challenge`: The encoded `TokenChallenge` from the
+    ///                      `WWW-Authenticate: PrivateToken` header.
+    ///
+    /// # Returns
+    /// * `Result<AttestationToken, String>`: The parsed token if it
+    ///                                       matches `token_challenge`.
+    pub fn check_token_challenge(&self, token_challenge: &[u8]) -> Result<AttestationToken, String> {
+        let token: AttestationToken = self.parse_token()?;
+        let expected: [u8; 32] = Sha256::digest(token_challenge).into();
+        if token.challenge_digest != expected {
+            return Err("Attestation token answers a different token challenge".to_string());
+        }
+        Ok(token)
+    }
+
+    /// Concatenates the attestation into a string: the challenge
+    /// signature and the token, both as lowercase hex strings.
+    pub fn concat_struct(&self) -> String {
+        format!("{}|{}", hex::encode(self.challenge_signature), hex::encode(&self.token))
+    }
+
+    /// Creates a `DeviceAttestation` from a concatenated string.
+    ///
+    /// This function reverses the operation of
+    /// `DeviceAttestation::concat_struct`.
+    ///
+    /// # Arguments
+    /// * `concat_str`: The concatenated string to parse.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed attestation or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+
+        if parts.len() != 2 {
+            return Err(format!("Expected 2 parts, got {}", parts.len()));
+        }
+
+        let challenge_signature: [u8; 64] = hex::decode(parts[0])
+            .map_err(|_| "Failed to decode challenge_signature hex string")?
+            .try_into()
+            .map_err(|_| "Challenge signature must be exactly 64 bytes")?;
+
+        let token: Vec<u8> = hex::decode(parts[1])
+            .map_err(|_| "Failed to decode token hex string")?;
+
+        Ok(Self {
+            challenge_signature,
+            token,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_bytes(challenge_digest: [u8; 32]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = PAT_TOKEN_TYPE_BLIND_RSA.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&[1; 32]);
+        bytes.extend_from_slice(&challenge_digest);
+        bytes.extend_from_slice(&[2; 32]);
+        bytes.extend_from_slice(&[3; 256]);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_token() {
+        let token = AttestationToken::parse(&token_bytes([9; 32])).unwrap();
+        assert_eq!(token.token_type, PAT_TOKEN_TYPE_BLIND_RSA);
+        assert_eq!(token.nonce, [1; 32]);
+        assert_eq!(token.challenge_digest, [9; 32]);
+        assert_eq!(token.token_key_id, [2; 32]);
+        assert_eq!(token.authenticator, vec![3; 256]);
+        assert_eq!(token.authenticator_input(), token_bytes([9; 32])[..98].to_vec());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_tokens() {
+        let mut wrong_type: Vec<u8> = token_bytes([0; 32]);
+        wrong_type[1] = 0x01;
+        assert!(AttestationToken::parse(&wrong_type).unwrap_err().contains("Unsupported"));
+
+        let short: Vec<u8> = token_bytes([0; 32])[..100].to_vec();
+        assert!(AttestationToken::parse(&short).unwrap_err().contains("must be 354 bytes"));
+        assert!(AttestationToken::parse(&[0]).is_err());
+    }
+
+    #[test]
+    fn test_check_token_challenge() {
+        let token_challenge: &[u8] = b"encoded token challenge";
+        let digest: [u8; 32] = Sha256::digest(token_challenge).into();
+        let attestation = DeviceAttestation { challenge_signature: [0; 64], token: token_bytes(digest) };
+
+        assert!(attestation.check_token_challenge(token_challenge).is_ok());
+        assert!(attestation.check_token_challenge(b"other").unwrap_err().contains("different token challenge"));
+
+        let parsed = DeviceAttestation::from_concat_struct(&attestation.concat_struct()).unwrap();
+        assert_eq!(parsed, attestation);
+    }
+}
@@ -0,0 +1,306 @@
+//! # Tamper-evident log of issued challenges.
+//!
+//! Every `AuditRecord` commits to the hash of the record before it, so
+//! editing, dropping or reordering a record breaks every later link.
+//! Every `checkpoint_interval` records the issuer also signs the current
+//! record hash, which pins the whole history up to that point to the
+//! issuer's key: rewriting it would need the private key, not just
+//! recomputed hashes.
+//!
+//! Records after the last checkpoint are only hash-linked. Call
+//! `AuditChain::checkpoint` before publishing or rotating a log to
+//! cover them too.
+
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
+    SigningKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_optional_signature,
+    serialize_32_bytes,
+    serialize_optional_signature
+};
+use crate::{
+    CryptoError,
+    ErrorDetail
+};
+#[cfg(feature = "sign")]
+use crate::IronShieldChallenge;
+
+/// `previous_hash` of the first record.
+pub const AUDIT_GENESIS_HASH: [u8; 32] = [0u8; 32];
+
+/// Domain separation prefix of checkpoint signatures.
+const CHECKPOINT_CONTEXT: &[u8] = b"ironshield-audit-checkpoint-v1";
+
+/// One issued challenge in an `AuditChain`.
+///
+/// * `sequence`:             Position in the chain, starting at 0.
+/// * `challenge_id`:         `challenge_id()` of the issued challenge.
+/// * `website_id`:           The website the challenge was issued for.
+/// * `issued_at`:            `created_time` of the challenge.
+/// * `previous_hash`:        `record_hash()` of the previous record, or
+///                           `AUDIT_GENESIS_HASH` for the first one.
+/// * `checkpoint_signature`: Issuer signature over this record's hash,
+///                           present on checkpoint records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sequence:             u64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub challenge_id:         [u8; 32],
+    pub website_id:           String,
+    pub issued_at:            i64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub previous_hash:        [u8; 32],
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_signature",
+        deserialize_with = "deserialize_optional_signature"
+    )]
+    pub checkpoint_signature: Option<[u8; 64]>,
+}
+
+impl AuditRecord {
+    /// SHA-256 over every field except `checkpoint_signature`, so a
+    /// checkpoint can be added without changing the chain.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: The record hash the next record links to.
+    pub fn record_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.sequence.to_be_bytes());
+        hasher.update(self.challenge_id);
+        hasher.update((self.website_id.len() as u64).to_be_bytes());
+        hasher.update(self.website_id.as_bytes());
+        hasher.update(self.issued_at.to_be_bytes());
+        hasher.update(self.previous_hash);
+        hasher.finalize().into()
+    }
+
+    /// # Returns
+    /// * `Vec<u8>`: The bytes covered by `checkpoint_signature`.
+    fn checkpoint_message(&self) -> Vec<u8> {
+        [CHECKPOINT_CONTEXT, &self.record_hash()].concat()
+    }
+}
+
+/// Append-only, hash-linked log of issued challenges.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditChain {
+    checkpoint_interval: u64,
+    records:             Vec<AuditRecord>,
+}
+
+impl AuditChain {
+    /// Constructor for creating an empty chain.
+    ///
+    /// # Arguments
+    /// * `checkpoint_interval`: Number of records per signed checkpoint
+    ///                          (at least 1).
+    pub fn new(checkpoint_interval: u64) -> Self {
+        Self {
+            checkpoint_interval: checkpoint_interval.max(1),
+            records:             Vec::new(),
+        }
+    }
+
+    /// # Returns
+    /// * `u64`: The number of records per signed checkpoint.
+    pub fn checkpoint_interval(&self) -> u64 {
+        self.checkpoint_interval
+    }
+
+    /// # Returns
+    /// * `&[AuditRecord]`: The records, oldest first.
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+
+    /// # Returns
+    /// * `usize`: The number of records.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if nothing was logged yet.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: Hash of the newest record, or `AUDIT_GENESIS_HASH`
+    ///               for an empty chain.
+    pub fn head_hash(&self) -> [u8; 32] {
+        self.records.last().map_or(AUDIT_GENESIS_HASH, AuditRecord::record_hash)
+    }
+
+    /// Logs an issued challenge, signing a checkpoint when the interval
+    /// is reached.
+    ///
+    /// # Arguments
+    /// * `challenge`:   The issued challenge.
+    /// * `signing_key`: The issuer key used for checkpoints.
+    ///
+    /// # Returns
+    /// * `&AuditRecord`: The appended record.
+    #[cfg(feature = "sign")]
+    pub fn append(&mut self, challenge: &IronShieldChallenge, signing_key: &SigningKey) -> &AuditRecord {
+        let mut record = AuditRecord {
+            sequence:             self.records.len() as u64,
+            challenge_id:         challenge.challenge_id(),
+            website_id:           challenge.website_id.to_string(),
+            issued_at:            challenge.created_time,
+            previous_hash:        self.head_hash(),
+            checkpoint_signature: None,
+        };
+        if (record.sequence + 1).is_multiple_of(self.checkpoint_interval) {
+            record.checkpoint_signature = Some(signing_key.sign(&record.checkpoint_message()).to_bytes());
+        }
+        self.records.push(record);
+        &self.records[self.records.len() - 1]
+    }
+
+    /// Signs a checkpoint on the newest record ahead of the interval,
+    /// e.g. before publishing the log. Does nothing on an empty chain.
+    ///
+    /// # Arguments
+    /// * `signing_key`: The issuer key.
+    #[cfg(feature = "sign")]
+    pub fn checkpoint(&mut self, signing_key: &SigningKey) {
+        if let Some(record) = self.records.last_mut() {
+            record.checkpoint_signature = Some(signing_key.sign(&record.checkpoint_message()).to_bytes());
+        }
+    }
+
+    /// Verifies sequence numbers, hash links and checkpoint signatures.
+    ///
+    /// # Arguments
+    /// * `public_key_bytes`: The issuer's Ed25519 public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the chain is intact, or an
+    ///                              error naming the first bad record.
+    pub fn verify(&self, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        let broken = |sequence: u64, reason: &str| {
+            CryptoError::VerificationFailed(format!("Audit record {}: {}", sequence, reason).into())
+        };
+
+        let mut previous_hash: [u8; 32] = AUDIT_GENESIS_HASH;
+        for (index, record) in self.records.iter().enumerate() {
+            if record.sequence != index as u64 {
+                return Err(broken(index as u64, "sequence number out of order"));
+            }
+            if record.previous_hash != previous_hash {
+                return Err(broken(record.sequence, "does not link to the previous record"));
+            }
+
+            match record.checkpoint_signature {
+                Some(signature) => verifying_key
+                    .verify(&record.checkpoint_message(), &Signature::from_bytes(&signature))
+                    .map_err(|_| broken(record.sequence, "invalid checkpoint signature"))?,
+                None if (record.sequence + 1).is_multiple_of(self.checkpoint_interval) => {
+                    return Err(broken(record.sequence, "missing checkpoint signature"));
+                }
+                None => {}
+            }
+
+            previous_hash = record.record_hash();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+
+    fn chain_of(count: usize, interval: u64, key: &SigningKey) -> AuditChain {
+        let mut chain = AuditChain::new(interval);
+        for i in 0..count {
+            let challenge = IronShieldChallenge::new(format!("site-{}.example", i).parse().unwrap(), 1_000, key.clone(), [0x34; 32]);
+            chain.append(&challenge, key);
+        }
+        chain
+    }
+
+    #[test]
+    fn test_append_and_verify() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let mut chain = chain_of(5, 2, &key);
+
+        let signed: Vec<bool> = chain.records().iter().map(|r| r.checkpoint_signature.is_some()).collect();
+        assert_eq!(signed, [false, true, false, true, false]);
+        assert_eq!(chain.records()[1].previous_hash, chain.records()[0].record_hash());
+        chain.verify(&public_key).unwrap();
+
+        let head: [u8; 32] = chain.head_hash();
+        chain.checkpoint(&key);
+        assert_eq!(chain.head_hash(), head);
+        assert!(chain.records()[4].checkpoint_signature.is_some());
+        chain.verify(&public_key).unwrap();
+
+        let json: String = serde_json::to_string(&chain).unwrap();
+        let restored: AuditChain = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, chain);
+        restored.verify(&public_key).unwrap();
+
+        assert!(chain.verify(&SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes()).is_err());
+        assert_eq!(AuditChain::new(3).head_hash(), AUDIT_GENESIS_HASH);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let chain = chain_of(4, 2, &key);
+        let error = |chain: &AuditChain| chain.verify(&public_key).unwrap_err().to_string();
+
+        let mut edited = chain.clone();
+        edited.records[0].website_id = "evil.example".to_string();
+        assert!(error(&edited).contains("Audit record 1: does not link"));
+
+        let mut dropped = chain.clone();
+        dropped.records.remove(2);
+        assert!(error(&dropped).contains("Audit record 2: sequence number"));
+
+        // Relinking everything after an edit still breaks the checkpoint.
+        let mut relinked = chain.clone();
+        relinked.records[0].issued_at += 1;
+        for i in 1..relinked.records.len() {
+            relinked.records[i].previous_hash = relinked.records[i - 1].record_hash();
+        }
+        assert!(error(&relinked).contains("Audit record 1: invalid checkpoint signature"));
+
+        let mut unsigned = chain;
+        unsigned.records[3].checkpoint_signature = None;
+        assert!(error(&unsigned).contains("Audit record 3: missing checkpoint signature"));
+    }
+}
@@ -0,0 +1,289 @@
+//! # Rolling-window difficulty auto-scaling.
+//!
+//! `DifficultyAutoScaler` keeps the median solve time near a target and
+//! raises difficulty while the request rate is above its baseline. It
+//! looks at a sliding window of samples, moves only part of the way
+//! toward the computed difficulty on each adjustment, and ignores
+//! changes inside a dead band, so the difficulty does not oscillate.
+//!
+//! The arithmetic uses only basic floating-point operations, which are
+//! exact under IEEE 754, so every deployment fed the same samples
+//! computes the same difficulties.
+
+use std::collections::VecDeque;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Default sliding window (1 minute).
+pub const DEFAULT_AUTO_SCALE_WINDOW_MS: i64 = 60_000;
+
+/// Tuning of a `DifficultyAutoScaler`.
+///
+/// * `window_ms`:             Age of the oldest sample considered.
+/// * `target_solve_time_ms`:  Desired median solve time.
+/// * `baseline_request_rate`: Requests per second considered normal;
+///                            higher rates scale difficulty up
+///                            proportionally.
+/// * `min_difficulty`:        Lower bound of emitted difficulties.
+/// * `max_difficulty`:        Upper bound of emitted difficulties.
+/// * `smoothing`:             Fraction of the way (0 to 1] moved toward
+///                            the computed difficulty per adjustment.
+/// * `hysteresis`:            Relative change below which the current
+///                            difficulty is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoScalerConfig {
+    pub window_ms:             i64,
+    pub target_solve_time_ms:  u64,
+    pub baseline_request_rate: f64,
+    pub min_difficulty:        u64,
+    pub max_difficulty:        u64,
+    pub smoothing:             f64,
+    pub hysteresis:            f64,
+}
+
+impl Default for AutoScalerConfig {
+    fn default() -> Self {
+        Self {
+            window_ms:             DEFAULT_AUTO_SCALE_WINDOW_MS,
+            target_solve_time_ms:  1_000,
+            baseline_request_rate: 10.0,
+            min_difficulty:        10_000,
+            max_difficulty:        10_000_000,
+            smoothing:             0.5,
+            hysteresis:            0.1,
+        }
+    }
+}
+
+impl AutoScalerConfig {
+    /// Checks that every setting is usable.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid setting.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_ms <= 0 {
+            return Err("window_ms must be positive".to_string());
+        }
+        if self.target_solve_time_ms == 0 {
+            return Err("target_solve_time_ms must be positive".to_string());
+        }
+        if !(self.baseline_request_rate > 0.0 && self.baseline_request_rate.is_finite()) {
+            return Err("baseline_request_rate must be positive".to_string());
+        }
+        if self.min_difficulty == 0 || self.min_difficulty > self.max_difficulty {
+            return Err("min_difficulty must be positive and at most max_difficulty".to_string());
+        }
+        if !(self.smoothing > 0.0 && self.smoothing <= 1.0) {
+            return Err("smoothing must be in (0, 1]".to_string());
+        }
+        if !(self.hysteresis >= 0.0 && self.hysteresis < 1.0) {
+            return Err("hysteresis must be in [0, 1)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Sliding-window difficulty controller.
+///
+/// Feed it solve-time histograms and request counts as they are
+/// collected, then call `adjust` periodically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyAutoScaler {
+    config:         AutoScalerConfig,
+    difficulty:     u64,
+    /// `(recorded_at, solve_time_ms, count)` histogram buckets.
+    solve_times:    VecDeque<(i64, u64, u64)>,
+    /// `(recorded_at, request_count)` samples.
+    request_counts: VecDeque<(i64, u64)>,
+}
+
+impl DifficultyAutoScaler {
+    /// # Arguments
+    /// * `config`:     The tuning.
+    /// * `difficulty`: The starting difficulty; clamped to the
+    ///                 configured bounds.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The scaler, or an error if `config`
+    ///                           is invalid.
+    pub fn new(config: AutoScalerConfig, difficulty: u64) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            difficulty:     difficulty.clamp(config.min_difficulty, config.max_difficulty),
+            solve_times:    VecDeque::new(),
+            request_counts: VecDeque::new(),
+        })
+    }
+
+    /// # Returns
+    /// * `u64`: The difficulty to issue challenges with.
+    pub fn difficulty(&self) -> u64 {
+        self.difficulty
+    }
+
+    /// # Returns
+    /// * `&AutoScalerConfig`: The tuning.
+    pub fn config(&self) -> &AutoScalerConfig {
+        &self.config
+    }
+
+    /// Records a solve-time histogram.
+    ///
+    /// # Arguments
+    /// * `at`:        Unix milli timestamp of the histogram.
+    /// * `histogram`: `(solve_time_ms, count)` buckets, e.g. bucket
+    ///                midpoints and the number of solves in each.
+    pub fn record_solve_times(&mut self, at: i64, histogram: &[(u64, u64)]) {
+        self.solve_times.extend(
+            histogram.iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(solve_time_ms, count)| (at, *solve_time_ms, *count))
+        );
+    }
+
+    /// Records the number of challenge requests seen since the last
+    /// sample.
+    ///
+    /// # Arguments
+    /// * `at`:    Unix milli timestamp of the sample.
+    /// * `count`: Number of requests.
+    pub fn record_requests(&mut self, at: i64, count: u64) {
+        self.request_counts.push_back((at, count));
+    }
+
+    /// Median solve time over the samples in the window.
+    fn median_solve_time_ms(&self) -> Option<u64> {
+        let total: u64 = self.solve_times.iter().map(|(_, _, count)| count).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut buckets: Vec<(u64, u64)> = self.solve_times.iter().map(|(_, time, count)| (*time, *count)).collect();
+        buckets.sort_unstable();
+        let middle: u64 = total.div_ceil(2);
+        let mut seen: u64 = 0;
+        buckets.into_iter().find_map(|(time, count)| {
+            seen += count;
+            (seen >= middle).then_some(time)
+        })
+    }
+
+    /// Drops samples that left the window and recomputes the difficulty.
+    ///
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Option<u64>`: The new difficulty, or `None` if the change is
+    ///                  inside the hysteresis band and the difficulty
+    ///                  was kept.
+    pub fn adjust(&mut self, now: i64) -> Option<u64> {
+        let cutoff: i64 = now.saturating_sub(self.config.window_ms);
+        self.solve_times.retain(|(at, _, _)| *at > cutoff);
+        self.request_counts.retain(|(at, _)| *at > cutoff);
+
+        let current: f64 = self.difficulty as f64;
+
+        // Solve time scales linearly with difficulty.
+        let solve_factor: f64 = self.median_solve_time_ms()
+            .map_or(1.0, |median| self.config.target_solve_time_ms as f64 / median.max(1) as f64);
+
+        let requests: u64 = self.request_counts.iter().map(|(_, count)| count).sum();
+        let request_rate: f64 = requests as f64 * 1_000.0 / self.config.window_ms as f64;
+        let load_factor: f64 = (request_rate / self.config.baseline_request_rate).max(1.0);
+
+        let desired: f64 = current * solve_factor * load_factor;
+        let smoothed: f64 = current + self.config.smoothing * (desired - current);
+        let bounded: u64 = (smoothed.round() as u64).clamp(self.config.min_difficulty, self.config.max_difficulty);
+
+        let relative_change: f64 = (bounded as f64 - current).abs() / current;
+        if bounded == self.difficulty || relative_change < self.config.hysteresis {
+            return None;
+        }
+        self.difficulty = bounded;
+        Some(bounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaler(difficulty: u64) -> DifficultyAutoScaler {
+        DifficultyAutoScaler::new(AutoScalerConfig::default(), difficulty).unwrap()
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(AutoScalerConfig::default().validate().is_ok());
+        let invalid = [
+            AutoScalerConfig { window_ms: 0, ..Default::default() },
+            AutoScalerConfig { baseline_request_rate: f64::NAN, ..Default::default() },
+            AutoScalerConfig { min_difficulty: 20, max_difficulty: 10, ..Default::default() },
+            AutoScalerConfig { smoothing: 0.0, ..Default::default() },
+            AutoScalerConfig { hysteresis: 1.0, ..Default::default() },
+        ];
+        for config in invalid {
+            assert!(DifficultyAutoScaler::new(config, 100_000).is_err());
+        }
+        assert_eq!(scaler(1).difficulty(), 10_000);
+    }
+
+    #[test]
+    fn test_fast_solves_raise_difficulty_smoothly() {
+        let mut scaler = scaler(100_000);
+        scaler.record_solve_times(1_000, &[(200, 8), (250, 10), (5_000, 1)]);
+
+        // Median 250 ms against a 1 s target: desired 400k, halfway is 250k.
+        assert_eq!(scaler.adjust(2_000), Some(250_000));
+        // The same samples are still in the window and keep pushing up.
+        assert_eq!(scaler.adjust(3_000), Some(625_000));
+    }
+
+    #[test]
+    fn test_hysteresis_keeps_difficulty() {
+        let mut scaler = scaler(100_000);
+        scaler.record_solve_times(1_000, &[(900, 5)]);
+        assert_eq!(scaler.adjust(2_000), None);
+        assert_eq!(scaler.difficulty(), 100_000);
+    }
+
+    #[test]
+    fn test_load_raises_and_window_expires() {
+        let mut scaler = scaler(100_000);
+        scaler.record_solve_times(1_000, &[(1_000, 20)]);
+        // 2400 requests per minute is 40/s, four times the baseline.
+        scaler.record_requests(1_000, 2_400);
+        assert_eq!(scaler.adjust(2_000), Some(250_000));
+
+        // Once the burst leaves the window only the solve times count,
+        // and those are now too slow for the raised difficulty.
+        scaler.record_solve_times(70_000, &[(2_500, 20)]);
+        assert_eq!(scaler.adjust(70_000), Some(175_000));
+    }
+
+    #[test]
+    fn test_bounds_and_determinism() {
+        let mut high = scaler(9_000_000);
+        high.record_solve_times(0, &[(1, 10)]);
+        assert_eq!(high.adjust(1), Some(10_000_000));
+
+        let mut low = scaler(20_000);
+        low.record_solve_times(0, &[(60_000, 10)]);
+        assert_eq!(low.adjust(1), Some(10_167));
+        assert_eq!(low.adjust(1), None);
+
+        let json: String = serde_json::to_string(&low).unwrap();
+        let mut restored: DifficultyAutoScaler = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, low);
+        restored.record_requests(2, 100_000);
+        low.record_requests(2, 100_000);
+        assert_eq!(restored.adjust(3), low.adjust(3));
+    }
+}
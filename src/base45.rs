@@ -0,0 +1,85 @@
+//! # Base45 encoding (RFC 9285).
+//!
+//! Base45 output uses only the QR code alphanumeric character set, so a
+//! compact challenge fits in a noticeably smaller QR code than its
+//! base64 encoding would.
+
+/// The base45 alphabet, in digit order.
+const ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Encodes bytes as base45.
+///
+/// # Arguments
+/// * `data`: The bytes to encode.
+///
+/// # Returns
+/// * `String`: Three characters per two input bytes, two for a trailing
+///             odd byte.
+pub fn base45_encode(data: &[u8]) -> String {
+    let mut encoded: String = String::with_capacity(data.len().div_ceil(2) * 3);
+    for chunk in data.chunks(2) {
+        let (mut value, digits): (usize, usize) = match chunk {
+            [a, b] => ((*a as usize) << 8 | *b as usize, 3),
+            [a]    => (*a as usize, 2),
+            _      => unreachable!(),
+        };
+        for _ in 0..digits {
+            encoded.push(ALPHABET[value % 45] as char);
+            value /= 45;
+        }
+    }
+    encoded
+}
+
+/// Reverses `base45_encode`.
+///
+/// # Arguments
+/// * `encoded`: The base45 string.
+///
+/// # Returns
+/// * `Result<Vec<u8>, String>`: The decoded bytes, or an error for
+///                              characters outside the alphabet, a
+///                              dangling character or an out-of-range
+///                              group.
+pub fn base45_decode(encoded: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<usize> = encoded.bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c)
+            .ok_or_else(|| format!("Invalid base45 character: {:?}", c as char)))
+        .collect::<Result<_, _>>()?;
+
+    let mut decoded: Vec<u8> = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+    for group in digits.chunks(3) {
+        let value: usize = group.iter().rev().fold(0, |value, digit| value * 45 + digit);
+        match group.len() {
+            3 if value <= 0xFFFF => decoded.extend_from_slice(&(value as u16).to_be_bytes()),
+            2 if value <= 0xFF   => decoded.push(value as u8),
+            1 => return Err("Invalid base45 length: dangling character".to_string()),
+            _ => return Err("Invalid base45 group: value out of range".to_string()),
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc9285_examples() {
+        assert_eq!(base45_encode(b"AB"), "BB8");
+        assert_eq!(base45_encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(base45_encode(b"base-45"), "UJCLQE7W581");
+        assert_eq!(base45_decode("QED8WEX0").unwrap(), b"ietf!");
+        assert_eq!(base45_decode("").unwrap(), b"");
+    }
+
+    #[test]
+    fn test_roundtrip_and_errors() {
+        let data: Vec<u8> = (0..=255).chain([0xFF, 0xFF]).collect();
+        assert_eq!(base45_decode(&base45_encode(&data)).unwrap(), data);
+
+        assert!(base45_decode("bb8").unwrap_err().contains("character"));
+        assert!(base45_decode("BB8B").unwrap_err().contains("dangling"));
+        assert!(base45_decode(":::").unwrap_err().contains("out of range"));
+    }
+}
@@ -0,0 +1,142 @@
+//! # Base58check encoding for human-transcribed values.
+//!
+//! Short identifiers such as key fingerprints and challenge ids are
+//! sometimes read aloud or typed by hand. Base58check (as used by
+//! Bitcoin addresses) drops look-alike characters (`0`, `O`, `I`, `l`)
+//! and appends a 4-byte double SHA-256 checksum, so a mistyped value is
+//! rejected instead of silently decoding to something else.
+
+use sha2::{
+    Digest,
+    Sha256
+};
+
+/// The Bitcoin base58 alphabet, in digit order.
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Number of checksum bytes appended before encoding.
+const CHECKSUM_LEN: usize = 4;
+
+/// First `CHECKSUM_LEN` bytes of SHA-256(SHA-256(data)).
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest: [u8; 32] = Sha256::digest(Sha256::digest(data)).into();
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Plain base58; each leading zero byte becomes a leading `1`.
+fn base58_encode(data: &[u8]) -> String {
+    let zeros: usize = data.iter().take_while(|&&byte| byte == 0).count();
+
+    // Little-endian base58 digits of the big-endian input number.
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in &data[zeros..] {
+        let mut carry: u32 = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    std::iter::repeat_n('1', zeros)
+        .chain(digits.iter().rev().map(|&digit| ALPHABET[digit as usize] as char))
+        .collect()
+}
+
+/// Reverses `base58_encode`.
+fn base58_decode(encoded: &str) -> Result<Vec<u8>, String> {
+    let zeros: usize = encoded.bytes().take_while(|&c| c == b'1').count();
+
+    // Little-endian bytes of the decoded number.
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len() * 733 / 1000 + 1);
+    for c in encoded.bytes().skip(zeros) {
+        let mut carry: u32 = ALPHABET.iter().position(|&a| a == c)
+            .ok_or_else(|| format!("Invalid base58 character: {:?}", c as char))? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut decoded: Vec<u8> = vec![0u8; zeros];
+    decoded.extend(bytes.iter().rev());
+    Ok(decoded)
+}
+
+/// Encodes `payload` as base58check.
+///
+/// # Arguments
+/// * `payload`: The bytes to encode, e.g. a key fingerprint.
+///
+/// # Returns
+/// * `String`: Base58 of `payload` followed by its 4-byte checksum.
+pub fn base58check_encode(payload: &[u8]) -> String {
+    let mut data: Vec<u8> = Vec::with_capacity(payload.len() + CHECKSUM_LEN);
+    data.extend_from_slice(payload);
+    data.extend_from_slice(&checksum(payload));
+    base58_encode(&data)
+}
+
+/// Reverses `base58check_encode`, verifying the checksum.
+///
+/// # Arguments
+/// * `encoded`: The base58check string. Surrounding whitespace is ignored.
+///
+/// # Returns
+/// * `Result<Vec<u8>, String>`: The payload, or an error if the string
+///                              contains invalid characters or the
+///                              checksum does not match (e.g. a typo).
+pub fn base58check_decode(encoded: &str) -> Result<Vec<u8>, String> {
+    let data: Vec<u8> = base58_decode(encoded.trim())?;
+    if data.len() < CHECKSUM_LEN {
+        return Err("Base58check value is too short to contain a checksum".to_string());
+    }
+
+    let (payload, expected) = data.split_at(data.len() - CHECKSUM_LEN);
+    if expected != checksum(payload) {
+        return Err("Base58check checksum mismatch; the value was mistyped or altered".to_string());
+    }
+    Ok(payload.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base58_known_values() {
+        assert_eq!(base58_encode(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(base58_encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+        assert_eq!(base58_decode("11233QC4").unwrap(), [0, 0, 0x28, 0x7f, 0xb4, 0xcd]);
+        assert_eq!(base58_encode(&[]), "");
+    }
+
+    #[test]
+    fn test_base58check_known_value() {
+        // Bitcoin address of the all-zero HASH160 on version byte 0.
+        assert_eq!(base58check_encode(&[0u8; 21]), "1111111111111111111114oLvT2");
+        assert_eq!(base58check_decode("1111111111111111111114oLvT2").unwrap(), [0u8; 21]);
+    }
+
+    #[test]
+    fn test_base58check_detects_typos() {
+        let encoded: String = base58check_encode(b"ironshield fingerprint");
+        assert_eq!(base58check_decode(&format!(" {}\n", encoded)).unwrap(), b"ironshield fingerprint");
+
+        let mut typo: Vec<u8> = encoded.into_bytes();
+        typo[5] = if typo[5] == b'2' { b'3' } else { b'2' };
+        let typo: String = String::from_utf8(typo).unwrap();
+        assert!(base58check_decode(&typo).unwrap_err().contains("checksum mismatch"));
+
+        assert!(base58check_decode("0OIl").unwrap_err().contains("Invalid base58 character"));
+        assert!(base58check_decode("2").unwrap_err().contains("too short"));
+    }
+}
@@ -0,0 +1,487 @@
+//! # Blind-signed (Privacy Pass-style) tokens.
+//!
+//! A regular `IronShieldToken` names the challenge it was issued for,
+//! so the issuer can link a redemption to the solve. With blind tokens
+//! the client instead obtains an evaluation of a verifiable oblivious
+//! PRF (VOPRF) over ristretto255 on a blinded random input:
+//!
+//! 1. The client calls `BlindTokenRequest::generate()` and sends the
+//!    request along with its solution.
+//! 2. Once the solution checks out, the issuer answers with
+//!    `BlindTokenKey::issue()`. The response carries a DLEQ proof that
+//!    the issuer's published key was used, so it cannot tag clients
+//!    with per-client keys.
+//! 3. The client checks the proof and unblinds the response with
+//!    `BlindTokenState::finalize()`.
+//! 4. The verifier, holding the same key, accepts the token with
+//!    `BlindTokenKey::verify_redemption()`.
+//!
+//! The issuer never sees the token input or output, so a redemption
+//! cannot be linked to the issuance. Verifiers must still reject
+//! repeated `redemption_id()`s to stop double spending.
+
+use curve25519_dalek::ristretto::{
+    CompressedRistretto,
+    RistrettoPoint
+};
+use curve25519_dalek::scalar::Scalar;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::Sha512;
+use subtle::ConstantTimeEq;
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    serialize_32_bytes
+};
+use crate::CryptoError;
+
+/// Domain separation tag for hashing token inputs to the group.
+const HASH_TO_GROUP_DST: &[u8] = b"ironshield-blind-token-v1-hash-to-group";
+/// Domain separation tag of the DLEQ proof challenge.
+const PROOF_DST:         &[u8] = b"ironshield-blind-token-v1-dleq";
+/// Domain separation tag of the deterministic DLEQ proof nonce.
+const PROOF_NONCE_DST:   &[u8] = b"ironshield-blind-token-v1-dleq-nonce";
+
+/// Parses a 32-byte value from lowercase or uppercase hex.
+fn parse_hex_32(value: &str, field: &str) -> Result<[u8; 32], String> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(value, &mut bytes)
+        .map_err(|_| format!("{} must be 32 bytes of hex", field))?;
+    Ok(bytes)
+}
+
+/// Decompresses a group element, rejecting invalid encodings and the
+/// identity.
+fn decode_point(bytes: &[u8; 32], field: &str) -> Result<RistrettoPoint, CryptoError> {
+    CompressedRistretto(*bytes).decompress()
+        .filter(|point| *point != RistrettoPoint::default())
+        .ok_or_else(|| CryptoError::InvalidKeyFormat(format!("{} is not a valid group element", field).into()))
+}
+
+/// Uniformly random nonzero scalar.
+fn random_scalar() -> Scalar {
+    loop {
+        let mut wide = [0u8; 64];
+        OsRng.fill_bytes(&mut wide);
+        let scalar: Scalar = Scalar::from_bytes_mod_order_wide(&wide);
+        if scalar != Scalar::ZERO {
+            return scalar;
+        }
+    }
+}
+
+fn hash_to_group(input: &[u8; 32]) -> RistrettoPoint {
+    RistrettoPoint::hash_from_bytes::<Sha512>(&[HASH_TO_GROUP_DST, input].concat())
+}
+
+/// Fiat-Shamir challenge of the DLEQ proof over all public values.
+fn proof_challenge(points: [&RistrettoPoint; 5]) -> Scalar {
+    let mut transcript: Vec<u8> = PROOF_DST.to_vec();
+    for point in points {
+        transcript.extend_from_slice(point.compress().as_bytes());
+    }
+    Scalar::hash_from_bytes::<Sha512>(&transcript)
+}
+
+/// Issuer and verifier secret of the blind token scheme.
+#[derive(Clone)]
+pub struct BlindTokenKey {
+    secret: Scalar,
+}
+
+/// Leaves out the secret scalar.
+impl std::fmt::Debug for BlindTokenKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlindTokenKey")
+            .field("public_key", &hex::encode(self.public_key()))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Blinded token input sent by the client with its solution.
+///
+/// * `blinded_element`: `blind * H(input)`, compressed.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindTokenRequest {
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub blinded_element: [u8; 32],
+}
+
+/// Issuer's evaluation of a `BlindTokenRequest`.
+///
+/// * `evaluated_element`: `secret * blinded_element`, compressed.
+/// * `proof_challenge`:   Challenge scalar of the DLEQ proof.
+/// * `proof_response`:    Response scalar of the DLEQ proof.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindTokenResponse {
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub evaluated_element: [u8; 32],
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub proof_challenge:   [u8; 32],
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub proof_response:    [u8; 32],
+}
+
+/// Client-side state kept between request and response. Never sent.
+#[derive(Clone)]
+pub struct BlindTokenState {
+    input:   [u8; 32],
+    blind:   Scalar,
+    blinded: RistrettoPoint,
+}
+
+/// Unblinded token presented at redemption.
+///
+/// * `input`:  The client's random token input.
+/// * `output`: `secret * H(input)`, compressed.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindRedemptionToken {
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub input:  [u8; 32],
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub output: [u8; 32],
+}
+
+impl BlindTokenKey {
+    /// # Returns
+    /// * `Self`: A fresh random key.
+    pub fn generate() -> Self {
+        Self { secret: random_scalar() }
+    }
+
+    /// # Arguments
+    /// * `bytes`: A canonical, nonzero scalar from `to_bytes()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, CryptoError>`: The key, or an error if the bytes
+    ///                                are not a valid secret.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, CryptoError> {
+        Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes))
+            .filter(|secret| *secret != Scalar::ZERO)
+            .map(|secret| Self { secret })
+            .ok_or_else(|| CryptoError::InvalidKeyFormat("Blind token key is not a canonical nonzero scalar".into()))
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The secret scalar, for storage.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The public key clients check issuance proofs against.
+    pub fn public_key(&self) -> [u8; 32] {
+        RistrettoPoint::mul_base(&self.secret).compress().to_bytes()
+    }
+
+    /// Evaluates a blinded request and proves the evaluation used this
+    /// key.
+    ///
+    /// Call it only after the client's solution has been verified. The
+    /// proof nonce is derived from the key and request, so issuance
+    /// needs no randomness.
+    ///
+    /// # Arguments
+    /// * `request`: The client's blinded request.
+    ///
+    /// # Returns
+    /// * `Result<BlindTokenResponse, CryptoError>`: The evaluation and
+    ///                                              proof, or an error if
+    ///                                              the request is not a
+    ///                                              valid group element.
+    pub fn issue(&self, request: &BlindTokenRequest) -> Result<BlindTokenResponse, CryptoError> {
+        let blinded: RistrettoPoint = decode_point(&request.blinded_element, "Blinded element")?;
+        let evaluated: RistrettoPoint = self.secret * blinded;
+        let public_key: RistrettoPoint = RistrettoPoint::mul_base(&self.secret);
+
+        let nonce: Scalar = Scalar::hash_from_bytes::<Sha512>(
+            &[PROOF_NONCE_DST, self.secret.as_bytes(), &request.blinded_element].concat()
+        );
+        let challenge: Scalar = proof_challenge([
+            &public_key,
+            &blinded,
+            &evaluated,
+            &RistrettoPoint::mul_base(&nonce),
+            &(nonce * blinded),
+        ]);
+
+        Ok(BlindTokenResponse {
+            evaluated_element: evaluated.compress().to_bytes(),
+            proof_challenge:   challenge.to_bytes(),
+            proof_response:    (nonce - challenge * self.secret).to_bytes(),
+        })
+    }
+
+    /// Checks a redeemed token against this key.
+    ///
+    /// # Arguments
+    /// * `token`: The unblinded token presented by the client.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the token was issued
+    ///                              with this key.
+    pub fn verify_redemption(&self, token: &BlindRedemptionToken) -> Result<(), CryptoError> {
+        let expected: [u8; 32] = (self.secret * hash_to_group(&token.input)).compress().to_bytes();
+        if !bool::from(expected.ct_eq(&token.output)) {
+            return Err(CryptoError::VerificationFailed("Blind token was not issued with this key".into()));
+        }
+        Ok(())
+    }
+}
+
+impl BlindTokenRequest {
+    /// Draws a random token input and blinds it.
+    ///
+    /// # Returns
+    /// * `(Self, BlindTokenState)`: The request to send and the state to
+    ///                              keep for `BlindTokenState::finalize()`.
+    pub fn generate() -> (Self, BlindTokenState) {
+        let mut input = [0u8; 32];
+        OsRng.fill_bytes(&mut input);
+        let blind: Scalar = random_scalar();
+        let blinded: RistrettoPoint = blind * hash_to_group(&input);
+
+        let request = Self { blinded_element: blinded.compress().to_bytes() };
+        (request, BlindTokenState { input, blind, blinded })
+    }
+
+    /// # Returns
+    /// * `String`: The blinded element as a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        hex::encode(self.blinded_element)
+    }
+
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed request or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        Ok(Self { blinded_element: parse_hex_32(concat_str, "Blinded element")? })
+    }
+
+    /// # Returns
+    /// * `String`: The request, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded request or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+impl BlindTokenResponse {
+    /// Concatenates the evaluated element, proof challenge and proof
+    /// response as lowercase hex strings.
+    pub fn concat_struct(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            hex::encode(self.evaluated_element),
+            hex::encode(self.proof_challenge),
+            hex::encode(self.proof_response)
+        )
+    }
+
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed response or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+        let [evaluated_element, proof_challenge, proof_response] = parts[..] else {
+            return Err(format!("Expected 3 parts, got {}", parts.len()));
+        };
+
+        Ok(Self {
+            evaluated_element: parse_hex_32(evaluated_element, "Evaluated element")?,
+            proof_challenge:   parse_hex_32(proof_challenge, "Proof challenge")?,
+            proof_response:    parse_hex_32(proof_response, "Proof response")?,
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The response, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded response or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+impl BlindTokenState {
+    /// Checks the issuer's proof and unblinds its evaluation.
+    ///
+    /// # Arguments
+    /// * `response`:          The issuer's response to this state's
+    ///                        request.
+    /// * `issuer_public_key`: The issuer's published
+    ///                        `BlindTokenKey::public_key()`.
+    ///
+    /// # Returns
+    /// * `Result<BlindRedemptionToken, CryptoError>`: The token to redeem,
+    ///                                                or an error if the
+    ///                                                proof does not
+    ///                                                verify.
+    pub fn finalize(
+        self,
+        response: &BlindTokenResponse,
+        issuer_public_key: &[u8; 32]
+    ) -> Result<BlindRedemptionToken, CryptoError> {
+        let public_key: RistrettoPoint = decode_point(issuer_public_key, "Issuer public key")?;
+        let evaluated: RistrettoPoint = decode_point(&response.evaluated_element, "Evaluated element")?;
+        let challenge: Scalar = Option::from(Scalar::from_canonical_bytes(response.proof_challenge))
+            .ok_or_else(|| CryptoError::VerificationFailed("Proof challenge is not a canonical scalar".into()))?;
+        let proof_response: Scalar = Option::from(Scalar::from_canonical_bytes(response.proof_response))
+            .ok_or_else(|| CryptoError::VerificationFailed("Proof response is not a canonical scalar".into()))?;
+
+        let expected: Scalar = proof_challenge([
+            &public_key,
+            &self.blinded,
+            &evaluated,
+            &(RistrettoPoint::mul_base(&proof_response) + challenge * public_key),
+            &(proof_response * self.blinded + challenge * evaluated),
+        ]);
+        if !bool::from(expected.ct_eq(&challenge)) {
+            return Err(CryptoError::VerificationFailed("Blind token issuance proof does not verify".into()));
+        }
+
+        Ok(BlindRedemptionToken {
+            input:  self.input,
+            output: (self.blind.invert() * evaluated).compress().to_bytes(),
+        })
+    }
+}
+
+impl BlindRedemptionToken {
+    /// # Returns
+    /// * `[u8; 32]`: Identifier to record in a spent-token store; equal
+    ///               for every presentation of the same token.
+    pub fn redemption_id(&self) -> [u8; 32] {
+        self.input
+    }
+
+    /// Concatenates the input and output as lowercase hex strings.
+    pub fn concat_struct(&self) -> String {
+        format!("{}|{}", hex::encode(self.input), hex::encode(self.output))
+    }
+
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed token or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+        let [input, output] = parts[..] else {
+            return Err(format!("Expected 2 parts, got {}", parts.len()));
+        };
+
+        Ok(Self {
+            input:  parse_hex_32(input, "Token input")?,
+            output: parse_hex_32(output, "Token output")?,
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The token, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded token or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_finalize_and_redeem() {
+        let key = BlindTokenKey::generate();
+        let (request, state) = BlindTokenRequest::generate();
+        let request = BlindTokenRequest::from_base64url_header(&request.to_base64url_header()).unwrap();
+
+        let response: BlindTokenResponse = key.issue(&request).unwrap();
+        let response = BlindTokenResponse::from_base64url_header(&response.to_base64url_header()).unwrap();
+        let token: BlindRedemptionToken = state.clone().finalize(&response, &key.public_key()).unwrap();
+
+        // The issuer saw neither the input nor the output.
+        assert_ne!(token.output, response.evaluated_element);
+        assert_eq!(BlindRedemptionToken::from_base64url_header(&token.to_base64url_header()).unwrap(), token);
+        key.verify_redemption(&token).unwrap();
+
+        let other = BlindTokenKey::from_bytes(&BlindTokenKey::generate().to_bytes()).unwrap();
+        assert!(other.verify_redemption(&token).is_err());
+        let forged = BlindRedemptionToken { input: [1; 32], ..token.clone() };
+        assert!(key.verify_redemption(&forged).is_err());
+
+        // A response made with a different key fails the proof.
+        assert!(state.finalize(&other.issue(&request).unwrap(), &key.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let key = BlindTokenKey::generate();
+        assert!(key.issue(&BlindTokenRequest { blinded_element: [0; 32] }).is_err());
+        assert!(key.issue(&BlindTokenRequest { blinded_element: [0xFF; 32] }).is_err());
+        assert!(BlindTokenKey::from_bytes(&[0; 32]).is_err());
+        assert!(BlindTokenKey::from_bytes(&[0xFF; 32]).is_err());
+        assert!(!format!("{:?}", key).contains(&hex::encode(key.to_bytes())));
+
+        let (request, state) = BlindTokenRequest::generate();
+        let mut response: BlindTokenResponse = key.issue(&request).unwrap();
+        response.proof_response[0] ^= 1;
+        assert!(state.finalize(&response, &key.public_key()).is_err());
+        assert!(BlindTokenResponse::from_concat_struct("00|11").is_err());
+    }
+}
@@ -0,0 +1,343 @@
+//! # Serializable Bloom filter for distributed replay protection.
+//!
+//! Edge nodes need to exchange the set of solutions they have already
+//! redeemed without shipping every signature. A `BloomFilter` is a fixed
+//! size bit array that answers "definitely not seen" or "probably seen",
+//! and two filters with the same shape can be merged with a bitwise OR.
+//!
+//! Items are hashed with SHA-256 and the filter positions are derived by
+//! double hashing, so filters built on different platforms agree.
+
+use base64::{
+    Engine,
+    engine::general_purpose::URL_SAFE_NO_PAD
+};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::IronShieldChallengeResponse;
+
+/// Upper bound on the filter size (16 MiB of bits).
+const MAX_FILTER_BITS: u64 = 128 * 1024 * 1024;
+/// Upper bound on the number of hash functions.
+const MAX_HASHES:      u32 = 32;
+
+/// Bloom filter over byte strings.
+///
+/// * `num_bits`:   Size of the bit array.
+/// * `num_hashes`: Number of positions set per item.
+/// * `bits`:       The bit array, serialized as base64url.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Bloom filter of redeemed solutions exchanged between edge nodes"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BloomFilterData")]
+pub struct BloomFilter {
+    /// Number of bits in the filter
+    #[cfg_attr(feature = "openapi", schema(example = 958506u64))]
+    num_bits:   u64,
+    /// Number of hash functions applied per item
+    #[cfg_attr(feature = "openapi", schema(example = 7u32))]
+    num_hashes: u32,
+    /// Bit array, base64url-encoded without padding
+    #[serde(serialize_with = "serialize_bits")]
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    bits:       Vec<u8>,
+}
+
+/// Unvalidated wire form of `BloomFilter`.
+#[derive(Deserialize)]
+struct BloomFilterData {
+    num_bits:   u64,
+    num_hashes: u32,
+    #[serde(deserialize_with = "deserialize_bits")]
+    bits:       Vec<u8>,
+}
+
+impl TryFrom<BloomFilterData> for BloomFilter {
+    type Error = String;
+
+    fn try_from(data: BloomFilterData) -> Result<Self, Self::Error> {
+        let mut filter: BloomFilter = BloomFilter::new(data.num_bits, data.num_hashes)?;
+        if data.bits.len() != filter.bits.len() {
+            return Err(format!(
+                "Bloom filter bit array is {} bytes, expected {}",
+                data.bits.len(),
+                filter.bits.len()
+            ));
+        }
+        filter.bits = data.bits;
+        Ok(filter)
+    }
+}
+
+impl BloomFilter {
+    /// Constructor for creating an empty `BloomFilter` with an explicit shape.
+    ///
+    /// # Arguments
+    /// * `num_bits`:   Size of the bit array.
+    /// * `num_hashes`: Number of positions set per item.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The empty filter, or an error if the
+    ///                           shape is out of range.
+    pub fn new(num_bits: u64, num_hashes: u32) -> Result<Self, String> {
+        if num_bits == 0 || num_bits > MAX_FILTER_BITS {
+            return Err(format!("Bloom filter size must be 1 to {} bits", MAX_FILTER_BITS));
+        }
+        if num_hashes == 0 || num_hashes > MAX_HASHES {
+            return Err(format!("Bloom filter hash count must be 1 to {}", MAX_HASHES));
+        }
+
+        Ok(Self {
+            num_bits,
+            num_hashes,
+            bits: vec![0u8; num_bits.div_ceil(8) as usize],
+        })
+    }
+
+    /// Constructor sizing the filter for a target false-positive rate.
+    ///
+    /// # Arguments
+    /// * `expected_items`:      Number of items the filter should hold.
+    /// * `false_positive_rate`: Acceptable false-positive probability
+    ///                          once `expected_items` are inserted.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The empty filter, or an error if the
+    ///                           parameters are out of range.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::BloomFilter;
+    /// let mut seen = BloomFilter::with_false_positive_rate(100_000, 0.01).unwrap();
+    /// seen.insert(b"signature|solution");
+    /// assert!(seen.contains(b"signature|solution"));
+    /// ```
+    pub fn with_false_positive_rate(expected_items: u64, false_positive_rate: f64) -> Result<Self, String> {
+        if expected_items == 0 {
+            return Err("expected_items must be positive".to_string());
+        }
+        if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+            return Err("false_positive_rate must be between 0 and 1".to_string());
+        }
+
+        let ln2: f64 = std::f64::consts::LN_2;
+        let num_bits: f64 = -(expected_items as f64) * false_positive_rate.ln() / (ln2 * ln2);
+        let num_hashes: f64 = (num_bits / expected_items as f64) * ln2;
+
+        Self::new(num_bits.ceil() as u64, (num_hashes.round() as u32).clamp(1, MAX_HASHES))
+    }
+
+    /// # Returns
+    /// * `u64`: Size of the bit array.
+    pub fn num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// # Returns
+    /// * `u32`: Number of positions set per item.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Derives the bit positions of an item by double hashing the
+    /// two halves of its SHA-256 digest.
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = u64> + use<> {
+        let digest: [u8; 32] = Sha256::digest(item).into();
+        let h1: u64 = u64::from_be_bytes(digest[0..8].try_into().unwrap_or_default());
+        let h2: u64 = u64::from_be_bytes(digest[8..16].try_into().unwrap_or_default()) | 1;
+        let num_bits: u64 = self.num_bits;
+
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    /// Adds an item to the filter.
+    ///
+    /// # Arguments
+    /// * `item`: The item bytes.
+    pub fn insert(&mut self, item: &[u8]) {
+        for position in self.positions(item) {
+            self.bits[(position / 8) as usize] |= 1 << (position % 8);
+        }
+    }
+
+    /// # Arguments
+    /// * `item`: The item bytes.
+    ///
+    /// # Returns
+    /// * `bool`: `false` if the item was definitely never inserted,
+    ///           `true` if it probably was.
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item)
+            .all(|position| self.bits[(position / 8) as usize] & (1 << (position % 8)) != 0)
+    }
+
+    /// Inserts an item and reports whether it was probably present.
+    ///
+    /// # Arguments
+    /// * `item`: The item bytes.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the item was probably already present.
+    pub fn check_and_insert(&mut self, item: &[u8]) -> bool {
+        let present: bool = self.contains(item);
+        self.insert(item);
+        present
+    }
+
+    /// Adds every item of `other` to this filter.
+    ///
+    /// # Arguments
+    /// * `other`: A filter with the same shape.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: An error if the shapes differ.
+    pub fn merge(&mut self, other: &BloomFilter) -> Result<(), String> {
+        if self.num_bits != other.num_bits || self.num_hashes != other.num_hashes {
+            return Err(format!(
+                "Cannot merge Bloom filters of different shapes ({}x{} and {}x{})",
+                self.num_bits, self.num_hashes, other.num_bits, other.num_hashes
+            ));
+        }
+        for (byte, other_byte) in self.bits.iter_mut().zip(&other.bits) {
+            *byte |= other_byte;
+        }
+        Ok(())
+    }
+
+    /// Removes every item from the filter.
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+
+    /// # Returns
+    /// * `f64`: The false-positive probability implied by the
+    ///          current fill ratio.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let set_bits: u64 = self.bits.iter().map(|byte| byte.count_ones() as u64).sum();
+        (set_bits as f64 / self.num_bits as f64).powi(self.num_hashes as i32)
+    }
+
+    /// Key under which a redeemed response is recorded: the challenge
+    /// signature followed by the big-endian solution.
+    ///
+    /// # Arguments
+    /// * `response`: The redeemed challenge response.
+    ///
+    /// # Returns
+    /// * `Vec<u8>`: The 72-byte replay-protection key.
+    pub fn response_key(response: &IronShieldChallengeResponse) -> Vec<u8> {
+        let mut key: Vec<u8> = response.solved_challenge.challenge_signature.to_vec();
+        key.extend_from_slice(&response.solution.to_be_bytes());
+        key
+    }
+}
+
+fn serialize_bits<S>(bits: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&URL_SAFE_NO_PAD.encode(bits))
+}
+
+fn deserialize_bits<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded: String = String::deserialize(deserializer)?;
+    URL_SAFE_NO_PAD.decode(encoded).map_err(serde::de::Error::custom)
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        IronShieldChallenge,
+        SigningKey
+    };
+
+    #[test]
+    fn test_sizing_from_false_positive_rate() {
+        let filter = BloomFilter::with_false_positive_rate(100_000, 0.01).unwrap();
+        assert_eq!(filter.num_bits(), 958_506);
+        assert_eq!(filter.num_hashes(), 7);
+
+        assert!(BloomFilter::with_false_positive_rate(0, 0.01).is_err());
+        assert!(BloomFilter::with_false_positive_rate(10, 1.0).is_err());
+        assert!(BloomFilter::new(0, 1).is_err());
+        assert!(BloomFilter::new(8, 0).is_err());
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut filter = BloomFilter::with_false_positive_rate(1_000, 0.001).unwrap();
+        assert!(!filter.check_and_insert(b"first"));
+        assert!(filter.check_and_insert(b"first"));
+
+        for i in 0u32..1_000 {
+            filter.insert(&i.to_be_bytes());
+        }
+        for i in 0u32..1_000 {
+            assert!(filter.contains(&i.to_be_bytes()));
+        }
+
+        let false_positives: usize = (1_000u32..11_000)
+            .filter(|i| filter.contains(&i.to_be_bytes()))
+            .count();
+        assert!(false_positives < 50, "{} false positives", false_positives);
+        assert!(filter.estimated_false_positive_rate() < 0.01);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = BloomFilter::new(1_024, 4).unwrap();
+        let mut b = BloomFilter::new(1_024, 4).unwrap();
+        a.insert(b"edge-a");
+        b.insert(b"edge-b");
+
+        a.merge(&b).unwrap();
+        assert!(a.contains(b"edge-a"));
+        assert!(a.contains(b"edge-b"));
+
+        let other_shape = BloomFilter::new(2_048, 4).unwrap();
+        assert!(a.merge(&other_shape).unwrap_err().contains("different shapes"));
+
+        a.clear();
+        assert!(!a.contains(b"edge-a"));
+    }
+
+    #[test]
+    fn test_serde_roundtrip_and_validation() {
+        let mut filter = BloomFilter::new(100, 3).unwrap();
+        filter.insert(b"seen");
+
+        let json: String = serde_json::to_string(&filter).unwrap();
+        let parsed: BloomFilter = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, filter);
+        assert!(parsed.contains(b"seen"));
+
+        let truncated = r#"{"num_bits":100,"num_hashes":3,"bits":"AAAA"}"#;
+        assert!(serde_json::from_str::<BloomFilter>(truncated).is_err());
+    }
+
+    #[test]
+    fn test_response_key() {
+        let signing_key = SigningKey::from_bytes(&[5; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 100, signing_key.clone(), signing_key.verifying_key().to_bytes());
+        let response = IronShieldChallengeResponse::new(challenge, 77);
+
+        let key: Vec<u8> = BloomFilter::response_key(&response);
+        assert_eq!(key.len(), 72);
+        assert_eq!(&key[64..], &77i64.to_be_bytes());
+    }
+}
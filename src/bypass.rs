@@ -0,0 +1,325 @@
+//! # Ways of getting past a challenge.
+//!
+//! A client answers a challenge either by solving it or by presenting
+//! another accepted proof, such as a `PaymentReceipt` or a
+//! `DeviceAttestation`. `BypassProof`
+//! carries whichever proof the client chose and `BypassVerifier` holds
+//! the checks an issuer runs for each kind. The proof-of-work hash check
+//! itself lives in ironshield-core, so it is supplied by the verifier.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    DeviceAttestation,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    PaymentReceipt,
+    PaymentRequirements
+};
+
+const PROOF_OF_WORK_TAG: &str = "pow";
+const PAYMENT_TAG:       &str = "payment";
+const ATTESTATION_TAG:   &str = "attestation";
+
+/// Proof presented to get past a challenge.
+///
+/// * `ProofOfWork`:       A solved challenge.
+/// * `Payment`:           A facilitator-signed payment receipt.
+/// * `DeviceAttestation`: A Private Access Token from a trusted attester.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Proof-of-work solution or alternative proof accepted in its place"
+))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "proof", rename_all = "snake_case")]
+pub enum BypassProof {
+    ProofOfWork(Box<IronShieldChallengeResponse>),
+    Payment(PaymentReceipt),
+    DeviceAttestation(DeviceAttestation),
+}
+
+/// Checks an issuer runs for each kind of `BypassProof`.
+///
+/// Kinds other than proof-of-work are rejected unless the verifier
+/// overrides the matching method.
+pub trait BypassVerifier {
+    /// Checks the proof-of-work solution of a response.
+    ///
+    /// # Arguments
+    /// * `response`: The response, already matched to the challenge.
+    fn verify_proof_of_work(&self, response: &IronShieldChallengeResponse) -> Result<(), String>;
+
+    /// Checks a payment receipt.
+    ///
+    /// # Arguments
+    /// * `receipt`:   The presented receipt.
+    /// * `challenge`: The challenge being bypassed.
+    fn verify_payment(&self, receipt: &PaymentReceipt, challenge: &IronShieldChallenge) -> Result<(), String> {
+        let _ = (receipt, challenge);
+        Err("Payment is not accepted for this challenge".to_string())
+    }
+
+    /// Checks a device attestation, typically with
+    /// `DeviceAttestation::check_token_challenge` followed by verifying
+    /// the token's authenticator against the attester's key.
+    ///
+    /// # Arguments
+    /// * `attestation`: The presented attestation.
+    /// * `challenge`:   The challenge being bypassed.
+    fn verify_device_attestation(&self, attestation: &DeviceAttestation, challenge: &IronShieldChallenge) -> Result<(), String> {
+        let _ = (attestation, challenge);
+        Err("Device attestation is not accepted for this challenge".to_string())
+    }
+}
+
+/// Verifier that checks payments against fixed `PaymentRequirements`
+/// and delegates proof-of-work checks to a closure.
+pub struct PaymentBypassVerifier<F> {
+    pub requirements:  PaymentRequirements,
+    pub proof_of_work: F,
+}
+
+impl<F> BypassVerifier for PaymentBypassVerifier<F>
+where
+    F: Fn(&IronShieldChallengeResponse) -> Result<(), String>,
+{
+    fn verify_proof_of_work(&self, response: &IronShieldChallengeResponse) -> Result<(), String> {
+        (self.proof_of_work)(response)
+    }
+
+    fn verify_payment(&self, receipt: &PaymentReceipt, challenge: &IronShieldChallenge) -> Result<(), String> {
+        self.requirements.check(receipt, challenge)
+    }
+}
+
+impl BypassProof {
+    /// # Returns
+    /// * `&'static str`: The tag of the proof kind, as used in the
+    ///                   concatenated format.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BypassProof::ProofOfWork(_)       => PROOF_OF_WORK_TAG,
+            BypassProof::Payment(_)           => PAYMENT_TAG,
+            BypassProof::DeviceAttestation(_) => ATTESTATION_TAG,
+        }
+    }
+
+    /// # Returns
+    /// * `&[u8; 64]`: Signature of the challenge this proof answers.
+    pub fn challenge_signature(&self) -> &[u8; 64] {
+        match self {
+            BypassProof::ProofOfWork(response)          => &response.solved_challenge.challenge_signature,
+            BypassProof::Payment(receipt)               => &receipt.challenge_signature,
+            BypassProof::DeviceAttestation(attestation) => &attestation.challenge_signature,
+        }
+    }
+
+    /// Verifies that this proof answers `challenge`.
+    ///
+    /// The challenge itself should already have been validated, e.g.
+    /// with `validate_challenge`.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge the issuer handed out.
+    /// * `verifier`:  The checks for each proof kind.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the proof is accepted.
+    pub fn verify<V>(&self, challenge: &IronShieldChallenge, verifier: &V) -> Result<(), String>
+    where
+        V: BypassVerifier + ?Sized,
+    {
+        if self.challenge_signature() != &challenge.challenge_signature {
+            return Err("Proof is for a different challenge".to_string());
+        }
+
+        match self {
+            BypassProof::ProofOfWork(response)          => verifier.verify_proof_of_work(response),
+            BypassProof::Payment(receipt)               => verifier.verify_payment(receipt, challenge),
+            BypassProof::DeviceAttestation(attestation) => verifier.verify_device_attestation(attestation, challenge),
+        }
+    }
+
+    /// Concatenates the proof into a string: the kind tag followed by
+    /// the concatenated form of the proof.
+    pub fn concat_struct(&self) -> String {
+        let inner: String = match self {
+            BypassProof::ProofOfWork(response)          => response.concat_struct(),
+            BypassProof::Payment(receipt)               => receipt.concat_struct(),
+            BypassProof::DeviceAttestation(attestation) => attestation.concat_struct(),
+        };
+        format!("{}|{}", self.kind(), inner)
+    }
+
+    /// Creates a `BypassProof` from a concatenated string.
+    ///
+    /// This function reverses the operation of
+    /// `BypassProof::concat_struct`.
+    ///
+    /// # Arguments
+    /// * `concat_str`: The concatenated string to parse.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed proof or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let (kind, inner) = concat_str.split_once('|')
+            .ok_or("Missing proof kind")?;
+
+        match kind {
+            PROOF_OF_WORK_TAG => IronShieldChallengeResponse::from_concat_struct(inner).map(|response| BypassProof::ProofOfWork(Box::new(response))),
+            PAYMENT_TAG       => PaymentReceipt::from_concat_struct(inner).map(BypassProof::Payment),
+            ATTESTATION_TAG   => DeviceAttestation::from_concat_struct(inner).map(BypassProof::DeviceAttestation),
+            other             => Err(format!("Unknown proof kind: {}", other)),
+        }
+    }
+
+    /// Encodes the proof as a base64url string for HTTP header transport.
+    ///
+    /// # Returns
+    /// * `String`: Base64url-encoded string ready for HTTP header use.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// Decodes a base64url-encoded proof from an HTTP header.
+    ///
+    /// # Arguments
+    /// * `encoded_header`: The base64url-encoded string from the HTTP header.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded proof or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+impl From<IronShieldChallengeResponse> for BypassProof {
+    fn from(response: IronShieldChallengeResponse) -> Self {
+        BypassProof::ProofOfWork(Box::new(response))
+    }
+}
+
+impl From<PaymentReceipt> for BypassProof {
+    fn from(receipt: PaymentReceipt) -> Self {
+        BypassProof::Payment(receipt)
+    }
+}
+
+impl From<DeviceAttestation> for BypassProof {
+    fn from(attestation: DeviceAttestation) -> Self {
+        BypassProof::DeviceAttestation(attestation)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    struct PowOnly;
+
+    impl BypassVerifier for PowOnly {
+        fn verify_proof_of_work(&self, response: &IronShieldChallengeResponse) -> Result<(), String> {
+            if response.solution == 42 { Ok(()) } else { Err("Invalid solution".to_string()) }
+        }
+    }
+
+    fn challenge(seed: u8) -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes())
+    }
+
+    fn receipt(challenge: &IronShieldChallenge, facilitator_key: &SigningKey) -> PaymentReceipt {
+        PaymentReceipt::new("pay_1".to_string(), 100, "USDC".to_string(), vec![7; 4], challenge, facilitator_key).unwrap()
+    }
+
+    #[test]
+    fn test_default_verifier_rejects_payment() {
+        let challenge = challenge(1);
+        let facilitator_key = SigningKey::from_bytes(&[2; 32]);
+
+        let solved: BypassProof = IronShieldChallengeResponse::new(challenge.clone(), 42).into();
+        let wrong: BypassProof = IronShieldChallengeResponse::new(challenge.clone(), 41).into();
+        let paid: BypassProof = receipt(&challenge, &facilitator_key).into();
+
+        assert!(solved.verify(&challenge, &PowOnly).is_ok());
+        assert_eq!(wrong.verify(&challenge, &PowOnly).unwrap_err(), "Invalid solution");
+        assert!(paid.verify(&challenge, &PowOnly).unwrap_err().contains("not accepted"));
+    }
+
+    #[test]
+    fn test_payment_verifier_accepts_either_proof() {
+        let challenge = challenge(1);
+        let facilitator_key = SigningKey::from_bytes(&[2; 32]);
+        let verifier = PaymentBypassVerifier {
+            requirements: PaymentRequirements {
+                min_amount:              100,
+                currency:                "USDC".to_string(),
+                facilitator_public_keys: vec![facilitator_key.verifying_key().to_bytes()],
+            },
+            proof_of_work: |response: &IronShieldChallengeResponse| PowOnly.verify_proof_of_work(response),
+        };
+
+        let solved: BypassProof = IronShieldChallengeResponse::new(challenge.clone(), 42).into();
+        let paid: BypassProof = receipt(&challenge, &facilitator_key).into();
+        assert!(solved.verify(&challenge, &verifier).is_ok());
+        assert!(paid.verify(&challenge, &verifier).is_ok());
+
+        let other: BypassProof = receipt(&self::challenge(3), &facilitator_key).into();
+        assert_eq!(other.verify(&challenge, &verifier).unwrap_err(), "Proof is for a different challenge");
+    }
+
+    #[test]
+    fn test_device_attestation_hook() {
+        struct AttestationOnly;
+
+        impl BypassVerifier for AttestationOnly {
+            fn verify_proof_of_work(&self, _: &IronShieldChallengeResponse) -> Result<(), String> {
+                Err("Proof-of-work disabled".to_string())
+            }
+
+            fn verify_device_attestation(&self, attestation: &DeviceAttestation, _: &IronShieldChallenge) -> Result<(), String> {
+                attestation.parse_token().map(|_| ())
+            }
+        }
+
+        let challenge = challenge(1);
+        let mut token: Vec<u8> = crate::PAT_TOKEN_TYPE_BLIND_RSA.to_be_bytes().to_vec();
+        token.resize(354, 0);
+
+        let attested: BypassProof = DeviceAttestation::new(&challenge, token.clone()).into();
+        let truncated: BypassProof = DeviceAttestation::new(&challenge, token[..10].to_vec()).into();
+        assert!(attested.verify(&challenge, &AttestationOnly).is_ok());
+        assert!(truncated.verify(&challenge, &AttestationOnly).is_err());
+        assert!(attested.verify(&challenge, &PowOnly).unwrap_err().contains("not accepted"));
+
+        let decoded = BypassProof::from_concat_struct(&attested.concat_struct()).unwrap();
+        assert_eq!(decoded.kind(), "attestation");
+    }
+
+    #[test]
+    fn test_header_and_serde_roundtrip() {
+        let challenge = challenge(1);
+        let facilitator_key = SigningKey::from_bytes(&[2; 32]);
+
+        for proof in [
+            BypassProof::from(IronShieldChallengeResponse::new(challenge.clone(), 42)),
+            BypassProof::from(receipt(&challenge, &facilitator_key)),
+        ] {
+            let decoded = BypassProof::from_base64url_header(&proof.to_base64url_header()).unwrap();
+            assert_eq!(decoded.concat_struct(), proof.concat_struct());
+
+            let json: String = serde_json::to_string(&proof).unwrap();
+            let parsed: BypassProof = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.concat_struct(), proof.concat_struct());
+        }
+
+        let json: String = serde_json::to_string(&BypassProof::from(receipt(&challenge, &facilitator_key))).unwrap();
+        assert!(json.starts_with(r#"{"type":"payment","proof":{"#));
+        assert!(BypassProof::from_concat_struct("captcha|x").unwrap_err().contains("Unknown proof kind"));
+    }
+}
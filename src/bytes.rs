@@ -0,0 +1,103 @@
+//! # Byte order of targets and nonces.
+//!
+//! The proof-of-work check mixes two conventions, and mixing them up is
+//! silent: a wrong byte order still produces a hash, just not the one
+//! the issuer expects.
+//!
+//! * Targets (`challenge_param`) and hashes are 256-bit unsigned
+//!   integers in big-endian order, so comparing the byte arrays
+//!   lexicographically compares the numbers.
+//! * Nonces are hashed as 8 little-endian bytes of the wire `i64`, as
+//!   defined by `Solution::to_bytes`.
+//!
+//! Every algorithm mode and every downstream solver should go through
+//! these helpers instead of calling `to_le_bytes` or comparing slices
+//! by hand.
+
+use std::cmp::Ordering;
+
+use crate::{
+    Solution,
+    SOLUTION_NONCE_LEN
+};
+
+/// Compares two 256-bit big-endian integers.
+///
+/// # Arguments
+/// * `a`: The left-hand value.
+/// * `b`: The right-hand value.
+///
+/// # Returns
+/// * `Ordering`: How `a` compares to `b` as numbers.
+pub fn cmp_be(a: &[u8; 32], b: &[u8; 32]) -> Ordering {
+    a.cmp(b)
+}
+
+/// # Arguments
+/// * `hash`:   A SHA-256 output.
+/// * `target`: The challenge's `challenge_param`.
+///
+/// # Returns
+/// * `bool`: `true` if `hash` is strictly less than `target`, both
+///           read as big-endian integers.
+///
+/// # Example
+/// ```
+/// use ironshield_types::bytes::meets_target;
+/// let mut target = [0u8; 32];
+/// target[0] = 0x10;
+/// assert!(meets_target(&[0x0F; 32], &target));
+/// assert!(!meets_target(&target, &target));
+/// ```
+pub fn meets_target(hash: &[u8; 32], target: &[u8; 32]) -> bool {
+    cmp_be(hash, target) == Ordering::Less
+}
+
+/// # Arguments
+/// * `nonce`: A nonce as carried on the wire.
+///
+/// # Returns
+/// * `[u8; 8]`: The bytes hashed after the `random_nonce`.
+pub fn nonce_to_le_bytes(nonce: i64) -> [u8; SOLUTION_NONCE_LEN] {
+    Solution::from_wire(nonce).to_bytes()
+}
+
+/// # Arguments
+/// * `bytes`: Exactly 8 bytes produced by `nonce_to_le_bytes`.
+///
+/// # Returns
+/// * `Result<i64, String>`: The nonce, or an error if `bytes` is not
+///                          8 bytes long.
+pub fn nonce_from_le_bytes(bytes: &[u8]) -> Result<i64, String> {
+    let bytes: [u8; SOLUTION_NONCE_LEN] = bytes.try_into()
+        .map_err(|_| format!("Nonce must be {} bytes, got {}", SOLUTION_NONCE_LEN, bytes.len()))?;
+    Ok(Solution::from_bytes(bytes).to_wire())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_order_is_numeric() {
+        let mut low: [u8; 32] = [0xFF; 32];
+        low[0] = 0x00;
+        let mut high: [u8; 32] = [0x00; 32];
+        high[0] = 0x01;
+
+        // Only the most significant byte decides; little-endian reading would invert this.
+        assert_eq!(cmp_be(&low, &high), Ordering::Less);
+        assert!(meets_target(&low, &high) && !meets_target(&high, &low));
+        assert!(!meets_target(&[0xFF; 32], &[0xFF; 32]));
+    }
+
+    #[test]
+    fn test_nonce_roundtrip() {
+        assert_eq!(nonce_to_le_bytes(0x0102), [0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(nonce_to_le_bytes(-2), [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        for nonce in [0i64, -1, i64::MIN, i64::MAX] {
+            assert_eq!(nonce_from_le_bytes(&nonce_to_le_bytes(nonce)), Ok(nonce));
+        }
+        assert!(nonce_from_le_bytes(&[0; 7]).unwrap_err().contains("8 bytes"));
+    }
+}
@@ -0,0 +1,372 @@
+//! # Canonical CBOR signing encoding for challenges.
+//!
+//! The binary signing mode signs a CBOR map instead of the `|`-joined
+//! text from `signing_message()`. The map is encoded with the RFC 8949
+//! core deterministic encoding (section 4.2.1): definite lengths,
+//! shortest-form integer heads, and keys sorted by their encoded bytes.
+//! Any conforming encoder therefore produces exactly the same bytes.
+//!
+//! Keys are unsigned integers:
+//!
+//! | Key | Field                 | Type                            |
+//! |-----|-----------------------|---------------------------------|
+//! | 0   | format version        | uint, always `1`                |
+//! | 1   | `random_nonce`        | text                            |
+//! | 2   | `created_time`        | int                             |
+//! | 3   | `expiration_time`     | int                             |
+//! | 4   | `website_id`          | text                            |
+//! | 5   | `challenge_param`     | bytes (32)                      |
+//! | 6   | `public_key`          | bytes (32)                      |
+//! | 7   | `submission_deadline` | int, only when present          |
+//! | 8   | `request_binding`     | bytes (32), only when present   |
+//! | 9   | `issuer_node`         | text, only when present         |
+//! | 10  | `min_age_ms`          | int, only when present          |
+//! | 11  | `issuance_context`    | bytes (32), only when present   |
+//! | 12  | `nonce_proof`         | text, only when present         |
+//! | 13  | `time_attestation`    | text, only when present         |
+//! | 14  | `required_solutions`  | int, only when present          |
+//! | 15  | `time_lock`           | text, only when present         |
+//! | 16  | `vdf`                 | text, only when present         |
+//!
+//! These are the fields covered by the text signing message. Test
+//! vectors for other implementations live in
+//! `test-vectors/canonical-cbor.json`.
+
+#[cfg(feature = "sign")]
+use ed25519_dalek::SigningKey;
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+
+use crate::{
+    ChallengeRef,
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge
+};
+
+/// Value of the format version entry (key 0).
+const CANONICAL_CBOR_VERSION: u64 = 1;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES:    u8 = 2;
+const MAJOR_TEXT:     u8 = 3;
+const MAJOR_MAP:      u8 = 5;
+
+const KEY_VERSION:             u64 = 0;
+const KEY_RANDOM_NONCE:        u64 = 1;
+const KEY_CREATED_TIME:        u64 = 2;
+const KEY_EXPIRATION_TIME:     u64 = 3;
+const KEY_WEBSITE_ID:          u64 = 4;
+const KEY_CHALLENGE_PARAM:     u64 = 5;
+const KEY_PUBLIC_KEY:          u64 = 6;
+const KEY_SUBMISSION_DEADLINE: u64 = 7;
+const KEY_REQUEST_BINDING:     u64 = 8;
+const KEY_ISSUER_NODE:         u64 = 9;
+const KEY_MIN_AGE:             u64 = 10;
+const KEY_ISSUANCE_CONTEXT:    u64 = 11;
+const KEY_NONCE_PROOF:         u64 = 12;
+const KEY_TIME_ATTESTATION:    u64 = 13;
+const KEY_REQUIRED_SOLUTIONS:  u64 = 14;
+const KEY_TIME_LOCK:           u64 = 15;
+const KEY_VDF:                 u64 = 16;
+
+/// Number of map entries every challenge has (keys 0 to 6).
+const BASE_ENTRY_COUNT: u64 = 7;
+
+/// Writes a data item head in its shortest form.
+fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major: u8 = major << 5;
+    if value < 24 {
+        out.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, value: i64) {
+    if value >= 0 {
+        write_head(out, MAJOR_UNSIGNED, value as u64);
+    } else {
+        // CBOR encodes a negative integer n as -1 - n.
+        write_head(out, MAJOR_NEGATIVE, !(value as u64));
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_head(out, MAJOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_head(out, MAJOR_TEXT, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+impl ChallengeRef<'_> {
+    /// Encodes the signed fields as canonical CBOR.
+    ///
+    /// # Returns
+    /// * `Vec<u8>`: The bytes covered by a binary-mode signature.
+    pub fn canonical_cbor(&self) -> Vec<u8> {
+        let entry_count: u64 = BASE_ENTRY_COUNT
+            + self.submission_deadline.is_some() as u64
+            + self.request_binding.is_some() as u64
+            + self.issuer_node.is_some() as u64
+            + self.min_age_ms.is_some() as u64
+            + self.issuance_context.is_some() as u64
+            + self.nonce_proof.is_some() as u64
+            + self.time_attestation.is_some() as u64
+            + self.required_solutions.is_some() as u64
+            + self.time_lock.is_some() as u64
+            + self.vdf.is_some() as u64;
+
+        let mut out: Vec<u8> = Vec::with_capacity(128 + self.random_nonce.len() + self.website_id.len());
+        write_head(&mut out, MAJOR_MAP, entry_count);
+
+        // Single-byte keys sort bytewise in numeric order.
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_VERSION);
+        write_head(&mut out, MAJOR_UNSIGNED, CANONICAL_CBOR_VERSION);
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_RANDOM_NONCE);
+        write_text(&mut out, self.random_nonce);
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_CREATED_TIME);
+        write_int(&mut out, self.created_time);
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_EXPIRATION_TIME);
+        write_int(&mut out, self.expiration_time);
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_WEBSITE_ID);
+        write_text(&mut out, self.website_id);
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_CHALLENGE_PARAM);
+        write_bytes(&mut out, self.challenge_param);
+        write_head(&mut out, MAJOR_UNSIGNED, KEY_PUBLIC_KEY);
+        write_bytes(&mut out, self.public_key);
+
+        if let Some(deadline) = self.submission_deadline {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_SUBMISSION_DEADLINE);
+            write_int(&mut out, deadline);
+        }
+        if let Some(binding) = &self.request_binding {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_REQUEST_BINDING);
+            write_bytes(&mut out, binding);
+        }
+        if let Some(issuer_node) = self.issuer_node {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_ISSUER_NODE);
+            write_text(&mut out, issuer_node);
+        }
+        if let Some(min_age_ms) = self.min_age_ms {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_MIN_AGE);
+            write_int(&mut out, min_age_ms);
+        }
+        if let Some(commitment) = &self.issuance_context {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_ISSUANCE_CONTEXT);
+            write_bytes(&mut out, commitment);
+        }
+        if let Some(proof) = &self.nonce_proof {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_NONCE_PROOF);
+            write_text(&mut out, &proof.to_wire_string());
+        }
+        if let Some(attestation) = &self.time_attestation {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_TIME_ATTESTATION);
+            write_text(&mut out, &attestation.to_wire_string());
+        }
+        if let Some(required) = self.required_solutions {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_REQUIRED_SOLUTIONS);
+            write_int(&mut out, required as i64);
+        }
+        if let Some(puzzle) = &self.time_lock {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_TIME_LOCK);
+            write_text(&mut out, &puzzle.to_wire_string());
+        }
+        if let Some(params) = &self.vdf {
+            write_head(&mut out, MAJOR_UNSIGNED, KEY_VDF);
+            write_text(&mut out, &params.to_wire_string());
+        }
+
+        out
+    }
+
+    /// Verifies a binary-mode `challenge_signature`, made over
+    /// `canonical_cbor()`, using a provided public key.
+    ///
+    /// # Arguments
+    /// * `public_key_bytes`: The Ed25519 public key bytes to use for verification.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify_cbor_signature_with_key(&self, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        self.validate().map_err(|e| CryptoError::VerificationFailed(format!("Invalid challenge: {}", e).into()))?;
+
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        let signature: Signature = Signature::from_bytes(self.challenge_signature);
+
+        verifying_key.verify(&self.canonical_cbor(), &signature)
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Signature verification failed: {}", e), e)))?;
+
+        Ok(())
+    }
+}
+
+impl IronShieldChallenge {
+    /// Encodes the signed fields as canonical CBOR.
+    ///
+    /// See `ChallengeRef::canonical_cbor`.
+    pub fn canonical_cbor(&self) -> Vec<u8> {
+        self.as_challenge_ref().canonical_cbor()
+    }
+
+    /// Signs the challenge in place in binary mode, over
+    /// `canonical_cbor()` instead of `signing_message()`.
+    ///
+    /// # Arguments
+    /// * `private_key`: Ed25519 private key for signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` once `challenge_signature`
+    ///                              is replaced.
+    #[cfg(feature = "sign")]
+    pub fn sign_cbor(&mut self, private_key: &SigningKey) -> Result<(), CryptoError> {
+        use ed25519_dalek::Signer;
+
+        self.challenge_signature = private_key.try_sign(&self.canonical_cbor())
+            .map_err(|e| CryptoError::SigningFailed(ErrorDetail::caused_by(format!("Failed to sign message: {}", e), e)))?
+            .to_bytes();
+        Ok(())
+    }
+
+    /// Verifies a binary-mode `challenge_signature`.
+    ///
+    /// See `ChallengeRef::verify_cbor_signature_with_key`.
+    pub fn verify_cbor_signature_with_key(&self, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        self.as_challenge_ref().verify_cbor_signature_with_key(public_key_bytes)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::{
+        TimeAttestation,
+        TimeLockPuzzle,
+        VdfParams,
+        VrfNonceProof
+    };
+    use ed25519_dalek::Signer;
+    use serde::Deserialize;
+
+    /// One entry of `test-vectors/canonical-cbor.json`.
+    #[derive(Deserialize)]
+    struct Vector {
+        description:         String,
+        private_key_seed:    String,
+        public_key:          String,
+        random_nonce:        String,
+        created_time:        i64,
+        expiration_time:     i64,
+        website_id:          String,
+        challenge_param:     String,
+        submission_deadline: Option<i64>,
+        request_binding:     Option<String>,
+        issuer_node:         Option<String>,
+        min_age_ms:          Option<i64>,
+        issuance_context:    Option<String>,
+        nonce_proof:         Option<String>,
+        time_attestation:    Option<String>,
+        required_solutions:  Option<u8>,
+        time_lock:           Option<String>,
+        vdf:                 Option<String>,
+        canonical_cbor:      String,
+        signature:           String,
+    }
+
+    fn hex_array<const N: usize>(value: &str) -> [u8; N] {
+        hex::decode(value).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn test_encoding_primitives() {
+        let encode = |value: i64| { let mut out = Vec::new(); write_int(&mut out, value); hex::encode(out) };
+        // Examples from RFC 8949 appendix A.
+        assert_eq!(encode(0), "00");
+        assert_eq!(encode(23), "17");
+        assert_eq!(encode(24), "1818");
+        assert_eq!(encode(1000), "1903e8");
+        assert_eq!(encode(1000000), "1a000f4240");
+        assert_eq!(encode(1000000000000), "1b000000e8d4a51000");
+        assert_eq!(encode(-1), "20");
+        assert_eq!(encode(-1000), "3903e7");
+        assert_eq!(encode(i64::MIN), "3b7fffffffffffffff");
+
+        let mut text = Vec::new();
+        write_text(&mut text, "IETF");
+        assert_eq!(hex::encode(text), "6449455446");
+    }
+
+    #[test]
+    fn test_published_vectors() {
+        let vectors: Vec<Vector> = serde_json::from_str(include_str!("../test-vectors/canonical-cbor.json")).unwrap();
+        assert!(!vectors.is_empty());
+
+        for vector in vectors {
+            let private_key = SigningKey::from_bytes(&hex_array(&vector.private_key_seed));
+            assert_eq!(hex::encode(private_key.verifying_key().to_bytes()), vector.public_key, "{}", vector.description);
+            let challenge_param: [u8; 32] = hex_array(&vector.challenge_param);
+            let public_key: [u8; 32] = hex_array(&vector.public_key);
+            let time_lock: Option<TimeLockPuzzle> = vector.time_lock.as_deref().map(|puzzle| TimeLockPuzzle::from_wire_string(puzzle).unwrap());
+            let vdf: Option<VdfParams> = vector.vdf.as_deref().map(|params| VdfParams::from_wire_string(params).unwrap());
+            let view = ChallengeRef {
+                random_nonce:         &vector.random_nonce,
+                created_time:         vector.created_time,
+                expiration_time:      vector.expiration_time,
+                website_id:           &vector.website_id,
+                challenge_param:      &challenge_param,
+                recommended_attempts: 0,
+                public_key:           &public_key,
+                challenge_signature:  &[0; 64],
+                submission_deadline:  vector.submission_deadline,
+                request_binding:      vector.request_binding.as_deref().map(hex_array),
+                issuer_node:          vector.issuer_node.as_deref(),
+                min_age_ms:           vector.min_age_ms,
+                issuance_context:     vector.issuance_context.as_deref().map(hex_array),
+                nonce_proof:          vector.nonce_proof.as_deref().map(|proof| VrfNonceProof::from_wire_string(proof).unwrap()),
+                time_attestation:     vector.time_attestation.as_deref().map(|attestation| TimeAttestation::from_wire_string(attestation).unwrap()),
+                required_solutions:   vector.required_solutions,
+                time_lock:            time_lock.as_ref().map(TimeLockPuzzle::as_puzzle_ref),
+                vdf:                  vdf.as_ref().map(VdfParams::as_params_ref),
+            };
+
+            assert_eq!(hex::encode(view.canonical_cbor()), vector.canonical_cbor, "{}", vector.description);
+            let signature: [u8; 64] = private_key.sign(&view.canonical_cbor()).to_bytes();
+            assert_eq!(hex::encode(signature), vector.signature, "{}", vector.description);
+
+            if view.validate().is_err() {
+                // Encoding vectors with out-of-range fields still fail verification.
+                let signed = ChallengeRef { challenge_signature: &signature, ..view };
+                let err = signed.verify_cbor_signature_with_key(&public_key).unwrap_err();
+                assert!(err.to_string().contains("Invalid challenge"), "{}", vector.description);
+                continue;
+            }
+            let mut challenge: IronShieldChallenge = view.to_owned_challenge().unwrap();
+            challenge.sign_cbor(&private_key).unwrap();
+            assert_eq!(challenge.challenge_signature, signature, "{}", vector.description);
+
+            challenge.verify_cbor_signature_with_key(&challenge.public_key).unwrap();
+            assert!(crate::verify_challenge_signature_with_key(&challenge, &challenge.public_key).is_err());
+
+            challenge.created_time += 1;
+            assert!(challenge.verify_cbor_signature_with_key(&challenge.public_key).is_err());
+        }
+    }
+}
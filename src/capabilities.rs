@@ -0,0 +1,261 @@
+//! # Client capability negotiation.
+//!
+//! A client lists what its solver can do in `ClientCapabilities` and
+//! sends it with the challenge request. The issuer then calls
+//! `ClientCapabilities::negotiate` to pick the first algorithm from its
+//! own preference list the client can run, and a difficulty scaled to
+//! the solver's expected throughput, lowered further for clients that
+//! report running under constrained conditions such as low battery or
+//! thermal throttling. `negotiate_signature_scheme` picks
+//! how challenges are signed, keeping clients that verify signatures
+//! themselves on a publicly verifiable scheme.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    PowAlgorithm,
+    SignatureScheme
+};
+
+/// Difficulty divisor applied for each missing WASM speed-up (SIMD,
+/// threads). Each roughly doubles the hash rate of the solver.
+const MISSING_FEATURE_DIVISOR: u64 = 2;
+/// Difficulty divisor applied for constrained clients, which solve on
+/// throttled cores or are asked to spare their battery.
+const CONSTRAINED_DIVISOR:     u64 = 4;
+
+/// What a client's solver supports.
+///
+/// * `algorithms`:          Algorithms the solver implements, most
+///                          preferred first. Empty means `sha256` only,
+///                          as sent by clients that predate negotiation.
+/// * `wasm_simd`:           WASM SIMD is available.
+/// * `wasm_threads`:        WASM threads (shared memory workers) are
+///                          available.
+/// * `verifies_signatures`: The client checks challenge signatures
+///                          itself, so it needs a publicly verifiable
+///                          scheme.
+/// * `constrained`:         The device is low on battery, in power
+///                          saving mode or thermally throttled; see
+///                          `MobileSolverPolicy::is_constrained`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientCapabilities {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub algorithms:          Vec<PowAlgorithm>,
+    #[serde(default)]
+    pub wasm_simd:           bool,
+    #[serde(default)]
+    pub wasm_threads:        bool,
+    #[serde(default)]
+    pub verifies_signatures: bool,
+    #[serde(default)]
+    pub constrained:         bool,
+}
+
+/// Parameters the issuer settled on for a client.
+///
+/// * `algorithm`:  The algorithm to issue the challenge for.
+/// * `difficulty`: The expected number of attempts, or of squarings
+///                 for `time_lock` and `vdf`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NegotiatedParameters {
+    pub algorithm:  PowAlgorithm,
+    pub difficulty: u64,
+}
+
+impl ClientCapabilities {
+    /// # Arguments
+    /// * `algorithm`: A candidate algorithm.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the client implements `algorithm`.
+    pub fn supports(&self, algorithm: PowAlgorithm) -> bool {
+        if self.algorithms.is_empty() {
+            algorithm == PowAlgorithm::Sha256
+        } else {
+            self.algorithms.contains(&algorithm)
+        }
+    }
+
+    /// Picks the algorithm and difficulty for a challenge.
+    ///
+    /// # Arguments
+    /// * `issuer_preference`: Algorithms the issuer accepts, most
+    ///                        preferred first.
+    /// * `difficulty`:        The difficulty for a client with every
+    ///                        speed-up available.
+    ///
+    /// # Returns
+    /// * `Result<NegotiatedParameters, String>`: The first preferred
+    ///                                           algorithm the client
+    ///                                           supports, or an error
+    ///                                           if there is none.
+    pub fn negotiate(
+        &self,
+        issuer_preference: &[PowAlgorithm],
+        difficulty:        u64,
+    ) -> Result<NegotiatedParameters, String> {
+        let algorithm: PowAlgorithm = issuer_preference.iter()
+            .copied()
+            .find(|algorithm| self.supports(*algorithm))
+            .ok_or("Client supports none of the accepted proof-of-work algorithms")?;
+
+        let difficulty: u64 = if self.constrained { difficulty / CONSTRAINED_DIVISOR } else { difficulty };
+        let difficulty: u64 = match algorithm {
+            PowAlgorithm::Wait => 1,
+            // Sequential squarings take the same wall-clock time
+            // whatever the client's parallelism.
+            PowAlgorithm::TimeLock | PowAlgorithm::Vdf => difficulty.max(1),
+            PowAlgorithm::Sha256 => {
+                let missing: u32 = u32::from(!self.wasm_simd) + u32::from(!self.wasm_threads);
+                (difficulty / MISSING_FEATURE_DIVISOR.pow(missing)).max(1)
+            }
+        };
+
+        Ok(NegotiatedParameters { algorithm, difficulty })
+    }
+
+    /// Picks the scheme challenges and tokens are signed with.
+    ///
+    /// # Arguments
+    /// * `issuer_preference`: Schemes the issuer can sign with, most
+    ///                        preferred first.
+    ///
+    /// # Returns
+    /// * `Result<SignatureScheme, String>`: The first preferred scheme
+    ///                                      the client can work with, or
+    ///                                      an error if there is none.
+    pub fn negotiate_signature_scheme(
+        &self,
+        issuer_preference: &[SignatureScheme],
+    ) -> Result<SignatureScheme, String> {
+        issuer_preference.iter()
+            .copied()
+            .find(|scheme| scheme.is_publicly_verifiable() || !self.verifies_signatures)
+            .ok_or_else(|| "Client verifies signatures but no publicly verifiable scheme is accepted".to_string())
+    }
+
+    /// Formats the capabilities as `algorithm,...;feature,...`, with
+    /// empty fields for absent values.
+    ///
+    /// # Returns
+    /// * `String`: The wire form used by `IronShieldChallengeRequest`.
+    pub fn to_wire_string(&self) -> String {
+        let algorithms: Vec<&str> = self.algorithms.iter().map(PowAlgorithm::as_str).collect();
+        let mut features: Vec<&str> = Vec::new();
+        if self.wasm_simd {
+            features.push("simd");
+        }
+        if self.wasm_threads {
+            features.push("threads");
+        }
+        if self.verifies_signatures {
+            features.push("verify");
+        }
+        if self.constrained {
+            features.push("constrained");
+        }
+
+        format!("{};{}", algorithms.join(","), features.join(","))
+    }
+
+    /// Parses the form produced by `to_wire_string()`.
+    ///
+    /// # Arguments
+    /// * `wire`: The wire string.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The capabilities or an error describing
+    ///                           the malformed field.
+    pub fn from_wire_string(wire: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = wire.split(';').collect();
+        let [algorithms, features] = fields[..] else {
+            return Err(format!("Expected 2 capability fields, got {}", fields.len()));
+        };
+
+        let algorithms: Vec<PowAlgorithm> = algorithms.split(',')
+            .filter(|algorithm| !algorithm.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, String>>()?;
+
+        let mut capabilities = Self { algorithms, ..Self::default() };
+        for feature in features.split(',').filter(|feature| !feature.is_empty()) {
+            match feature {
+                "simd"        => capabilities.wasm_simd = true,
+                "threads"     => capabilities.wasm_threads = true,
+                "verify"      => capabilities.verifies_signatures = true,
+                "constrained" => capabilities.constrained = true,
+                other         => return Err(format!("Unknown client capability: {}", other)),
+            }
+        }
+
+        Ok(capabilities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let full = ClientCapabilities {
+            algorithms:          vec![PowAlgorithm::Sha256, PowAlgorithm::Wait],
+            wasm_simd:           true,
+            wasm_threads:        true,
+            verifies_signatures: false,
+            constrained:         false,
+        };
+        let negotiated = full.negotiate(&[PowAlgorithm::Sha256], 100_000).unwrap();
+        assert_eq!(negotiated, NegotiatedParameters { algorithm: PowAlgorithm::Sha256, difficulty: 100_000 });
+        assert_eq!(full.negotiate(&[PowAlgorithm::Wait, PowAlgorithm::Sha256], 100_000).unwrap().algorithm, PowAlgorithm::Wait);
+
+        let legacy = ClientCapabilities::default();
+        assert!(legacy.supports(PowAlgorithm::Sha256) && !legacy.supports(PowAlgorithm::Wait));
+        assert_eq!(legacy.negotiate(&[PowAlgorithm::Wait, PowAlgorithm::Sha256], 100_000).unwrap().difficulty, 25_000);
+        assert!(legacy.negotiate(&[PowAlgorithm::Wait], 100_000).is_err());
+
+        let no_threads = ClientCapabilities { wasm_threads: false, ..full.clone() };
+        assert_eq!(no_threads.negotiate(&[PowAlgorithm::Sha256], 1).unwrap().difficulty, 1);
+
+        let constrained = ClientCapabilities { constrained: true, ..full };
+        assert_eq!(constrained.negotiate(&[PowAlgorithm::Sha256], 100_000).unwrap().difficulty, 25_000);
+        assert_eq!(constrained.negotiate(&[PowAlgorithm::Wait], 100_000).unwrap().difficulty, 1);
+    }
+
+    #[test]
+    fn test_negotiate_signature_scheme() {
+        let preference = [SignatureScheme::HmacSha256, SignatureScheme::Ed25519];
+        let legacy = ClientCapabilities::default();
+        assert_eq!(legacy.negotiate_signature_scheme(&preference).unwrap(), SignatureScheme::HmacSha256);
+
+        let verifying = ClientCapabilities { verifies_signatures: true, ..Default::default() };
+        assert_eq!(verifying.negotiate_signature_scheme(&preference).unwrap(), SignatureScheme::Ed25519);
+        assert!(verifying.negotiate_signature_scheme(&[SignatureScheme::HmacSha256]).is_err());
+    }
+
+    #[test]
+    fn test_wire_string_roundtrip() {
+        let capabilities = ClientCapabilities {
+            algorithms:          vec![PowAlgorithm::Wait, PowAlgorithm::Sha256],
+            wasm_simd:           false,
+            wasm_threads:        true,
+            verifies_signatures: true,
+            constrained:         true,
+        };
+        assert_eq!(capabilities.to_wire_string(), "wait,sha256;threads,verify,constrained");
+        assert_eq!(ClientCapabilities::from_wire_string("wait,sha256;threads,verify,constrained").unwrap(), capabilities);
+        assert_eq!(ClientCapabilities::from_wire_string(";").unwrap(), ClientCapabilities::default());
+
+        assert!(ClientCapabilities::from_wire_string("sha256;").is_ok());
+        assert!(ClientCapabilities::from_wire_string("md5;").unwrap_err().contains("Unknown proof-of-work algorithm"));
+        assert!(ClientCapabilities::from_wire_string(";gpu").unwrap_err().contains("Unknown client capability"));
+        assert!(ClientCapabilities::from_wire_string("sha256").unwrap_err().contains("Expected 2 capability fields"));
+        assert!(ClientCapabilities::from_wire_string("sha256;;simd").unwrap_err().contains("Expected 2 capability fields"));
+    }
+}
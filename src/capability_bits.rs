@@ -0,0 +1,199 @@
+//! # Compact encoding of the protocol feature set.
+//!
+//! `FeatureFlags` is convenient in code but verbose on the wire. In
+//! headers the set travels as `CapabilityBits`: a `u64` with one bit per
+//! feature, written as its big-endian bytes without leading zero bytes,
+//! base64url-encoded. Today's feature sets fit in two characters.
+//!
+//! Bit assignments are stable: a bit is never reused for another
+//! feature. Bits this version does not know are kept when parsing and
+//! re-encoding, and ignored when converting to `FeatureFlags`, so a
+//! newer peer's set passes through older code unchanged.
+//!
+//! | Bit | Feature            |
+//! |-----|--------------------|
+//! | 0   | `compact_encoding` |
+//! | 1   | `blake3`           |
+//! | 2   | `bundles`          |
+//! | 3   | `payment_bypass`   |
+
+use base64::{
+    Engine,
+    engine::general_purpose::URL_SAFE_NO_PAD
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::FeatureFlags;
+
+/// A set of protocol features, one bit each.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    value_type = String,
+    description = "Protocol feature bits as unpadded base64url"
+))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CapabilityBits(pub u64);
+
+impl CapabilityBits {
+    pub const COMPACT_ENCODING: Self = Self(1 << 0);
+    pub const BLAKE3:           Self = Self(1 << 1);
+    pub const BUNDLES:          Self = Self(1 << 2);
+    pub const PAYMENT_BYPASS:   Self = Self(1 << 3);
+
+    /// Every bit this version assigns.
+    pub const KNOWN: Self = Self(
+        Self::COMPACT_ENCODING.0 | Self::BLAKE3.0 | Self::BUNDLES.0 | Self::PAYMENT_BYPASS.0
+    );
+
+    /// # Arguments
+    /// * `required`: Bits that must be set.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if every bit of `required` is set here.
+    pub fn contains(&self, required: Self) -> bool {
+        self.0 & required.0 == required.0
+    }
+
+    /// # Arguments
+    /// * `other`: The peer's bits.
+    ///
+    /// # Returns
+    /// * `Self`: The bits set on both sides.
+    pub fn intersect(&self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if no bit is set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// # Returns
+    /// * `String`: The base64url form of the big-endian bytes with
+    ///             leading zero bytes removed; `"AA"` for no bits.
+    pub fn to_header_value(&self) -> String {
+        let bytes: [u8; 8] = self.0.to_be_bytes();
+        let skip: usize = (self.0.leading_zeros() / 8).min(7) as usize;
+        URL_SAFE_NO_PAD.encode(&bytes[skip..])
+    }
+
+    /// Parses the form produced by `to_header_value()`.
+    ///
+    /// # Arguments
+    /// * `value`: The encoded bits.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The bits, or an error if `value` is not
+    ///                           base64url of 1 to 8 bytes without
+    ///                           leading zero bytes.
+    pub fn from_header_value(value: &str) -> Result<Self, String> {
+        let bytes: Vec<u8> = URL_SAFE_NO_PAD.decode(value)
+            .map_err(|e| format!("Invalid capability bits encoding: {}", e))?;
+        if bytes.is_empty() || bytes.len() > 8 {
+            return Err(format!("Capability bits must be 1 to 8 bytes, got {}", bytes.len()));
+        }
+        if bytes.len() > 1 && bytes[0] == 0 {
+            return Err("Capability bits must not have leading zero bytes".to_string());
+        }
+
+        let mut padded = [0u8; 8];
+        padded[8 - bytes.len()..].copy_from_slice(&bytes);
+        Ok(Self(u64::from_be_bytes(padded)))
+    }
+}
+
+impl From<FeatureFlags> for CapabilityBits {
+    fn from(flags: FeatureFlags) -> Self {
+        let mut bits = Self::default();
+        for (set, bit) in [
+            (flags.compact_encoding, Self::COMPACT_ENCODING),
+            (flags.blake3,           Self::BLAKE3),
+            (flags.bundles,          Self::BUNDLES),
+            (flags.payment_bypass,   Self::PAYMENT_BYPASS),
+        ] {
+            if set {
+                bits.0 |= bit.0;
+            }
+        }
+        bits
+    }
+}
+
+impl From<CapabilityBits> for FeatureFlags {
+    fn from(bits: CapabilityBits) -> Self {
+        Self {
+            compact_encoding: bits.contains(CapabilityBits::COMPACT_ENCODING),
+            blake3:           bits.contains(CapabilityBits::BLAKE3),
+            bundles:          bits.contains(CapabilityBits::BUNDLES),
+            payment_bypass:   bits.contains(CapabilityBits::PAYMENT_BYPASS),
+        }
+    }
+}
+
+impl std::fmt::Display for CapabilityBits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_header_value())
+    }
+}
+
+impl std::str::FromStr for CapabilityBits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_header_value(s)
+    }
+}
+
+impl TryFrom<String> for CapabilityBits {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_header_value(&value)
+    }
+}
+
+impl From<CapabilityBits> for String {
+    fn from(bits: CapabilityBits) -> Self {
+        bits.to_header_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_value_roundtrip() {
+        assert_eq!(CapabilityBits::default().to_header_value(), "AA");
+        assert_eq!(CapabilityBits::KNOWN.to_header_value(), "Dw");
+        assert_eq!(CapabilityBits(u64::MAX).to_header_value(), "__________8");
+        for bits in [0, 1, 0x0f, 0x100, 1 << 40, u64::MAX] {
+            let bits = CapabilityBits(bits);
+            assert_eq!(CapabilityBits::from_header_value(&bits.to_header_value()).unwrap(), bits);
+        }
+
+        assert!(CapabilityBits::from_header_value("").is_err());
+        assert!(CapabilityBits::from_header_value("AA8").unwrap_err().contains("leading zero"));
+        assert!(CapabilityBits::from_header_value("AAAAAAAAAAAA").is_err());
+        assert_eq!(serde_json::to_string(&CapabilityBits::BLAKE3).unwrap(), r#""Ag""#);
+    }
+
+    #[test]
+    fn test_feature_flags_conversion() {
+        let flags = FeatureFlags { blake3: true, payment_bypass: true, ..Default::default() };
+        let bits = CapabilityBits::from(flags);
+        assert_eq!(bits, CapabilityBits(0b1010));
+        assert_eq!(FeatureFlags::from(bits), flags);
+        assert_eq!(CapabilityBits::from(FeatureFlags::ALL), CapabilityBits::KNOWN);
+
+        let newer_peer = CapabilityBits(CapabilityBits::BUNDLES.0 | 1 << 20);
+        assert_eq!(CapabilityBits::from_header_value(&newer_peer.to_header_value()).unwrap(), newer_peer);
+        assert_eq!(FeatureFlags::from(newer_peer), FeatureFlags { bundles: true, ..Default::default() });
+        assert_eq!(newer_peer.intersect(CapabilityBits::KNOWN), CapabilityBits::BUNDLES);
+    }
+}
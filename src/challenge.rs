@@ -1,18 +1,59 @@
+use crate::trace::traced;
 use crate::serde_utils::{
     deserialize_32_bytes,
+    deserialize_optional_32_bytes,
     deserialize_signature,
     serialize_32_bytes,
+    serialize_optional_32_bytes,
     serialize_signature
 };
+use crate::compact::{
+    CompactReader,
+    CompactWriter
+};
+use crate::epoch::now_millis;
+use crate::{
+    ChallengeRef,
+    IssuanceContext,
+    PowAlgorithm,
+    RandomNonce,
+    RequestDescriptor,
+    SignatureScheme,
+    SolutionVerifier,
+    TimeAttestation,
+    TimeAuthorities,
+    TimeLockPuzzle,
+    VdfParams,
+    VrfNonceProof,
+    WebsiteId
+};
+use crate::website_id::deserialize_verbatim_website_id;
+#[cfg(feature = "sign")]
+use crate::{
+    LifetimePolicy,
+    NonceSource,
+    RANDOM_NONCE_LEN
+};
+#[cfg(feature = "vrf")]
+use crate::VrfNonceKey;
+#[cfg(all(feature = "sign", feature = "timelock"))]
+use crate::TimeLockKey;
 
-use chrono::Utc;
+#[cfg(feature = "sign")]
 use ed25519_dalek::SigningKey;
 use hex;
+#[cfg(feature = "rng")]
 use rand;
 use serde::{
     Deserialize,
     Serialize
 };
+use sha2::{
+    Digest,
+    Sha256
+};
+use std::cmp::Ordering;
+use std::time::Duration;
 
 const                HASH_BITS: usize = 256;
 const               ARRAY_SIZE: usize = 32;
@@ -22,6 +63,29 @@ const           MAX_BYTE_VALUE:    u8 = 0xFF;
 const         MAX_BIT_POSITION: usize = 255;
 const                LSB_INDEX: usize = ARRAY_SIZE - 1;
 const                LSB_VALUE:    u8 = 1;
+const         BASE_PART_COUNT: usize = 8;
+
+/// Maximum length of an `issuer_node` identifier.
+const MAX_ISSUER_NODE_LEN:     usize = 64;
+/// Largest accepted `required_solutions`.
+pub(crate) const MAX_REQUIRED_SOLUTIONS:    u8 = 32;
+/// Label of the `to_armored_string()` block.
+const ARMOR_LABEL:              &str = "IRONSHIELD CHALLENGE";
+/// Domain separation prefix of `time_attestation_nonce()`.
+const TIME_ATTESTATION_CONTEXT: &str = "ironshield-challenge-time-v1";
+
+/// Deserializes an optional `issuer_node`, rejecting identifiers that
+/// `IronShieldChallenge::validate_issuer_node` does not accept.
+pub(crate) fn deserialize_issuer_node<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let issuer_node: Option<String> = Option::deserialize(deserializer)?;
+    if let Some(issuer_node) = &issuer_node {
+        IronShieldChallenge::validate_issuer_node(issuer_node).map_err(serde::de::Error::custom)?;
+    }
+    Ok(issuer_node)
+}
 
 /// IronShield Challenge structure for the proof-of-work algorithm
 ///
@@ -33,6 +97,27 @@ const                LSB_VALUE:    u8 = 1;
 /// * `website_id`:           The identifier of the website.
 /// * `public_key`:           Ed25519 public key for signature verification.
 /// * `challenge_signature`:  Ed25519 signature over the challenge data.
+/// * `submission_deadline`:  Optional Unix milli timestamp after which solutions
+///                           are no longer accepted (signed extension).
+/// * `request_binding`:      Optional SHA-256 of the `RequestDescriptor` the
+///                           challenge may be redeemed for (signed extension).
+/// * `issuer_node`:          Optional identifier of the edge node (colo, region
+///                           or worker id) that issued the challenge (signed extension).
+/// * `min_age_ms`:           Optional minimum milliseconds between `created_time`
+///                           and redemption; makes this a wait challenge
+///                           (signed extension).
+/// * `issuance_context`:     Optional SHA-256 commitment to the `IssuanceContext`
+///                           the difficulty was based on (signed extension).
+/// * `nonce_proof`:          Optional VRF proof that `random_nonce` was derived
+///                           from `website_id` and a counter (signed extension).
+/// * `time_attestation`:     Optional time authority attestation of
+///                           `created_time` (signed extension).
+/// * `required_solutions`:   Optional number of distinct nonces that must each
+///                           meet the target (signed extension).
+/// * `time_lock`:            Optional time-lock puzzle; makes this a time-lock
+///                           challenge (signed extension).
+/// * `vdf`:                  Optional VDF modulus and iterations; makes this a
+///                           VDF challenge (signed extension).
 
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "openapi", schema(
@@ -41,17 +126,19 @@ const                LSB_VALUE:    u8 = 1;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IronShieldChallenge {
     /// Random nonce used in the proof-of-work challenge
-    #[cfg_attr(feature = "openapi", schema(example = "a6e5f14c9622c88af274ec7247f028eb"))]
-    pub random_nonce:        String,
+    #[cfg_attr(feature = "openapi", schema(value_type = String, example = "a6e5f14c9622c88af274ec7247f028eb"))]
+    pub random_nonce:        RandomNonce,
     /// Unix timestamp in milliseconds when the challenge was created
     #[cfg_attr(feature = "openapi", schema(example = 1755401345880i64))]
     pub created_time:        i64,
     /// Unix timestamp in milliseconds when the challenge expires
     #[cfg_attr(feature = "openapi", schema(example = 1755401375880i64))]
     pub expiration_time:     i64,
-    /// The website or endpoint identifier for this challenge
-    #[cfg_attr(feature = "openapi", schema(example = "https://example.com"))]
-    pub website_id:          String,
+    /// The website or endpoint identifier for this challenge, kept
+    /// exactly as signed
+    #[serde(deserialize_with = "deserialize_verbatim_website_id")]
+    #[cfg_attr(feature = "openapi", schema(value_type = String, example = "https://example.com"))]
+    pub website_id:          WebsiteId,
     /// Target threshold - proof-of-work hash must be less than this value
     #[serde(
         serialize_with = "serialize_32_bytes",
@@ -76,6 +163,58 @@ pub struct IronShieldChallenge {
     )]
     #[cfg_attr(feature = "openapi", schema(example = json!([98, 41, 139, 179, 132, 76, 72, 255, 157, 174, 50, 115, 247, 136, 169, 81, 207, 103, 221, 56, 94, 132, 116, 223, 79, 98, 252, 141, 170, 30, 149, 30, 97, 132, 148, 134, 199, 198, 122, 254, 103, 224, 178, 167, 177, 23, 99, 146, 0, 107, 22, 102, 124, 10, 38, 38, 2, 227, 218, 87, 204, 135, 44, 10])))]
     pub challenge_signature: [u8; 64],
+    /// Unix timestamp in milliseconds after which solutions are rejected,
+    /// when it differs from `expiration_time`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = 1755401380880i64))]
+    pub submission_deadline: Option<i64>,
+    /// SHA-256 hash of the request descriptor this challenge is bound to (32 bytes)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_32_bytes",
+        deserialize_with = "deserialize_optional_32_bytes"
+    )]
+    pub request_binding:     Option<[u8; 32]>,
+    /// Identifier of the edge node that issued this challenge
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_issuer_node"
+    )]
+    #[cfg_attr(feature = "openapi", schema(example = "fra/worker-3"))]
+    pub issuer_node:         Option<String>,
+    /// Milliseconds the client must hold a wait challenge before redeeming it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = 5000i64))]
+    pub min_age_ms:          Option<i64>,
+    /// SHA-256 commitment to the `IssuanceContext` the difficulty was based on (32 bytes)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_32_bytes",
+        deserialize_with = "deserialize_optional_32_bytes"
+    )]
+    pub issuance_context:    Option<[u8; 32]>,
+    /// VRF proof that `random_nonce` was derived honestly
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce_proof:         Option<VrfNonceProof>,
+    /// Time authority attestation of `created_time`
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_attestation:    Option<TimeAttestation>,
+    /// Number of distinct solutions required, when more than one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_solutions:  Option<u8>,
+    /// Time-lock puzzle answered instead of a proof-of-work nonce
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_lock:           Option<TimeLockPuzzle>,
+    /// VDF parameters answered with a Wesolowski proof instead of a nonce
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vdf:                 Option<VdfParams>,
 }
 
 
@@ -95,32 +234,222 @@ impl IronShieldChallenge {
     ///
     /// # Returns
     /// * `Self`:            A new, properly signed IronShieldChallenge.
+    #[cfg(feature = "rng")]
     pub fn new(
-        website_id:  String,
+        website_id:  WebsiteId,
+        difficulty:  u64,
+        private_key: SigningKey,
+        public_key:  [u8; 32],
+    ) -> Self {
+        Self::new_with_policy(
+            website_id,
+            difficulty,
+            private_key,
+            public_key,
+            &LifetimePolicy::default(),
+        )
+    }
+
+    /// Constructor for creating a new `IronShieldChallenge` instance
+    /// whose expiration is taken from a `LifetimePolicy`.
+    ///
+    /// # Arguments
+    /// * `website_id`:      The identifier of the website.
+    /// * `difficulty`:      The target difficulty (expected number of attempts).
+    /// * `private_key`:     Ed25519 private key for signing the challenge.
+    /// * `public_key`:      Ed25519 public key corresponding to the private key.
+    /// * `policy`:          The lifetime policy defining the validity window.
+    ///
+    /// # Returns
+    /// * `Self`:            A new, properly signed IronShieldChallenge.
+    #[cfg(feature = "rng")]
+    pub fn new_with_policy(
+        website_id:  WebsiteId,
         difficulty:  u64,
         private_key: SigningKey,
         public_key:  [u8; 32],
+        policy:      &LifetimePolicy,
+    ) -> Self {
+        Self::new_signed(Self::generate_random_nonce(), website_id, difficulty, &private_key, public_key, policy)
+    }
+
+    /// Same as `new_with_policy`, drawing the random nonce from
+    /// `nonce_source` instead of the system RNG. Use it on runtimes
+    /// without a getrandom backend.
+    ///
+    /// # Arguments
+    /// * `website_id`:      The identifier of the website.
+    /// * `difficulty`:      The target difficulty (expected number of attempts).
+    /// * `private_key`:     Ed25519 private key for signing the challenge.
+    /// * `public_key`:      Ed25519 public key corresponding to the private key.
+    /// * `policy`:          The lifetime policy defining the validity window.
+    /// * `nonce_source`:    Where the random nonce bytes come from.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: A new, properly signed IronShieldChallenge,
+    ///                           or the nonce source's error.
+    #[cfg(feature = "sign")]
+    pub fn new_with_nonce_source(
+        website_id:   WebsiteId,
+        difficulty:   u64,
+        private_key:  &SigningKey,
+        public_key:   [u8; 32],
+        policy:       &LifetimePolicy,
+        nonce_source: &impl NonceSource,
+    ) -> Result<Self, String> {
+        let random_nonce: RandomNonce = Self::generate_random_nonce_with(nonce_source)?;
+        Ok(Self::new_signed(random_nonce, website_id, difficulty, private_key, public_key, policy))
+    }
+
+    /// Same as `new_with_policy`, deriving the nonce from a VRF over
+    /// `(website_id, counter)` so auditors can check it with
+    /// `verify_nonce_proof`.
+    ///
+    /// # Arguments
+    /// * `website_id`:      The identifier of the website.
+    /// * `difficulty`:      The target difficulty (expected number of attempts).
+    /// * `private_key`:     Ed25519 private key for signing the challenge.
+    /// * `public_key`:      Ed25519 public key corresponding to the private key.
+    /// * `policy`:          The lifetime policy defining the validity window.
+    /// * `vrf_key`:         The issuer's VRF key.
+    /// * `counter`:         A sequence number never reused with `vrf_key`
+    ///                      and `website_id`.
+    ///
+    /// # Returns
+    /// * `Self`:            A new, properly signed IronShieldChallenge
+    ///                      carrying its nonce proof.
+    #[cfg(feature = "vrf")]
+    pub fn new_with_vrf_nonce(
+        website_id:  WebsiteId,
+        difficulty:  u64,
+        private_key: &SigningKey,
+        public_key:  [u8; 32],
+        policy:      &LifetimePolicy,
+        vrf_key:     &VrfNonceKey,
+        counter:     u64,
+    ) -> Self {
+        let (random_nonce, proof) = vrf_key.evaluate(&website_id, counter);
+        let mut challenge = Self::new_signed(random_nonce, website_id, difficulty, private_key, public_key, policy);
+        challenge.nonce_proof = Some(proof);
+        challenge.sign(private_key);
+        challenge
+    }
+
+    /// Checks that `random_nonce` is the VRF output proven by
+    /// `nonce_proof`.
+    ///
+    /// # Arguments
+    /// * `vrf_public_key`: The issuer's published VRF public key.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the nonce was derived honestly.
+    #[cfg(feature = "vrf")]
+    pub fn verify_nonce_proof(&self, vrf_public_key: &[u8; 32]) -> Result<(), String> {
+        let proof: &VrfNonceProof = self.nonce_proof.as_ref()
+            .ok_or("Challenge carries no nonce proof")?;
+        let nonce: RandomNonce = proof.verify(&self.website_id, vrf_public_key)
+            .map_err(|e| e.to_string())?;
+
+        if nonce != self.random_nonce {
+            return Err("Challenge nonce does not match its VRF proof".to_string());
+        }
+        Ok(())
+    }
+
+    /// Nonce a time authority signs when attesting `created_time`.
+    ///
+    /// It commits to the nonce, website and claimed creation time, so an
+    /// attestation cannot be moved to another challenge.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: SHA-256 of the domain-separated binding.
+    pub fn time_attestation_nonce(&self) -> [u8; 32] {
+        Sha256::digest(format!(
+            "{}|{}|{}|{}",
+            TIME_ATTESTATION_CONTEXT,
+            self.random_nonce,
+            self.website_id,
+            self.created_time
+        ).as_bytes()).into()
+    }
+
+    /// Embeds a time authority's attestation of `created_time` and
+    /// re-signs the challenge.
+    ///
+    /// # Arguments
+    /// * `attestation`: Attestation of `time_attestation_nonce()`.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error if
+    ///                           the attestation is not bound to it.
+    #[cfg(feature = "sign")]
+    pub fn with_time_attestation(
+        mut self,
+        attestation: TimeAttestation,
+        private_key: &SigningKey,
+    ) -> Result<Self, String> {
+        attestation.verify(&self.time_attestation_nonce()).map_err(|e| e.to_string())?;
+        self.time_attestation = Some(attestation);
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Checks that `created_time` is attested by a trusted authority.
+    ///
+    /// # Arguments
+    /// * `authorities`: The trusted time authorities.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if `created_time` is attested,
+    ///                         or an error describing the failed check.
+    pub fn verify_time_attestation(&self, authorities: &TimeAuthorities) -> Result<(), String> {
+        let attestation: &TimeAttestation = self.time_attestation.as_ref()
+            .ok_or("Challenge carries no time attestation")?;
+        authorities.check(attestation, &self.time_attestation_nonce(), self.created_time)
+    }
+
+    /// Expiration grounded in attested time: the challenge's lifetime
+    /// counted from the latest instant the attestation allows, if that
+    /// is earlier than `expiration_time`. An issuer clock running ahead
+    /// therefore cannot extend the validity window.
+    ///
+    /// # Arguments
+    /// * `authorities`: The trusted time authorities.
+    ///
+    /// # Returns
+    /// * `Result<i64, String>`: The attested expiration time in unix
+    ///                          millis, or an error if `created_time`
+    ///                          is not attested.
+    pub fn attested_expiration_time(&self, authorities: &TimeAuthorities) -> Result<i64, String> {
+        self.verify_time_attestation(authorities)?;
+        let attestation: &TimeAttestation = self.time_attestation.as_ref()
+            .ok_or("Challenge carries no time attestation")?;
+        let lifetime_ms: i64 = self.expiration_time.saturating_sub(self.created_time);
+
+        Ok(self.expiration_time.min(attestation.latest().saturating_add(lifetime_ms)))
+    }
+
+    /// Builds and signs a challenge around an already drawn nonce.
+    #[cfg(feature = "sign")]
+    fn new_signed(
+        random_nonce: RandomNonce,
+        website_id:   WebsiteId,
+        difficulty:   u64,
+        private_key:  &SigningKey,
+        public_key:   [u8; 32],
+        policy:       &LifetimePolicy,
     ) -> Self {
-        let    random_nonce:   String = Self::generate_random_nonce();
         let    created_time:      i64 = Self::generate_created_time();
-        let expiration_time:      i64 = created_time + 30_000; // 30-second expiration.
+        let expiration_time:      i64 = policy.challenge_expiration(created_time);
         let challenge_param: [u8; 32] = Self::difficulty_to_challenge_param(difficulty);
-        
-        // Create the signing message from the challenge components
-        let signing_message = crate::crypto::create_signing_message(
-            &random_nonce,
-            created_time,
-            expiration_time,
-            &website_id,
-            &challenge_param,
-            &public_key
-        );
 
-        // Generate the signature using the reusable generate_signature function.
-        let challenge_signature: [u8; 64] = crate::crypto::generate_signature(&private_key, &signing_message)
-            .unwrap_or([0u8; 64]);
+        // Only pin a separate deadline when the policy grants a grace period,
+        // so default challenges keep the original wire format.
+        let submission_deadline: Option<i64> = (policy.submission_grace_ms > 0)
+            .then(|| expiration_time.saturating_add(policy.submission_grace_ms));
 
-        Self {
+        let mut challenge = Self {
             random_nonce,
             created_time,
             website_id,
@@ -128,7 +457,443 @@ impl IronShieldChallenge {
             challenge_param,
             recommended_attempts: Self::recommended_attempts(difficulty),
             public_key,
-            challenge_signature,
+            challenge_signature: [0u8; 64],
+            submission_deadline,
+            request_binding:     None,
+            issuer_node:         None,
+            min_age_ms:          None,
+            issuance_context:    None,
+            nonce_proof:         None,
+            time_attestation:    None,
+            required_solutions:  None,
+            time_lock:           None,
+            vdf:                 None,
+        };
+        challenge.sign(private_key);
+
+        challenge
+    }
+
+    /// Binds the challenge to a specific request and re-signs it.
+    ///
+    /// The descriptor hash is committed into the signed data, so a
+    /// pre-fetched challenge can only be redeemed against the request
+    /// it was issued for.
+    ///
+    /// # Arguments
+    /// * `descriptor`:  The request the challenge is bound to.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Self`:        The bound, re-signed challenge.
+    #[cfg(feature = "sign")]
+    pub fn with_request_binding(
+        mut self,
+        descriptor:  &RequestDescriptor,
+        private_key: &SigningKey,
+    ) -> Self {
+        self.request_binding = Some(descriptor.hash());
+        self.sign(private_key);
+        self
+    }
+
+    /// Commits the signals the difficulty was based on into the
+    /// challenge and re-signs it.
+    ///
+    /// Only `IssuanceContext::commitment()` is embedded, so the
+    /// challenge does not reveal the client's network or scores. An
+    /// auditor holding the context can check it with
+    /// `verify_issuance_context`.
+    ///
+    /// # Arguments
+    /// * `context`:     The signals behind the issuance decision.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error
+    ///                           if the context is invalid.
+    #[cfg(feature = "sign")]
+    pub fn with_issuance_context(
+        mut self,
+        context:     &IssuanceContext,
+        private_key: &SigningKey,
+    ) -> Result<Self, String> {
+        context.validate()?;
+        self.issuance_context = Some(context.commitment());
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Checks that the challenge commits to the given issuance context.
+    ///
+    /// # Arguments
+    /// * `context`: The context the issuer logged for this challenge.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the commitment matches, or an
+    ///                         error if it is absent or differs.
+    pub fn verify_issuance_context(&self, context: &IssuanceContext) -> Result<(), String> {
+        match self.issuance_context {
+            Some(commitment) if commitment == context.commitment() => Ok(()),
+            Some(_) => Err("Challenge commits to a different issuance context".to_string()),
+            None => Err("Challenge does not commit to an issuance context".to_string()),
+        }
+    }
+
+    /// Records the edge node issuing the challenge and re-signs it.
+    ///
+    /// # Arguments
+    /// * `issuer_node`: Colo, region or worker identifier, e.g. `"fra/worker-3"`.
+    ///                  At most 64 ASCII letters, digits or `-_.:/`.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error
+    ///                           if the identifier is invalid.
+    #[cfg(feature = "sign")]
+    pub fn with_issuer_node(
+        mut self,
+        issuer_node: &str,
+        private_key: &SigningKey,
+    ) -> Result<Self, String> {
+        Self::validate_issuer_node(issuer_node)?;
+        self.issuer_node = Some(issuer_node.to_string());
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Turns the challenge into a wait challenge and re-signs it.
+    ///
+    /// Instead of solving anything, the client holds the challenge for
+    /// `min_age_ms` before redeeming it, which suits clients that cannot
+    /// afford proof-of-work. The hash target is opened up so that any
+    /// solution passes a proof-of-work check.
+    ///
+    /// # Arguments
+    /// * `min_age_ms`:  Milliseconds to wait after `created_time`.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error if
+    ///                           the wait is not positive or would end
+    ///                           after the submission deadline.
+    #[cfg(feature = "sign")]
+    pub fn with_min_age(
+        mut self,
+        min_age_ms:  i64,
+        private_key: &SigningKey,
+    ) -> Result<Self, String> {
+        if min_age_ms <= 0 {
+            return Err("min_age_ms must be positive".to_string());
+        }
+        if self.created_time.saturating_add(min_age_ms) > self.effective_submission_deadline() {
+            return Err("min_age_ms ends after the submission deadline".to_string());
+        }
+
+        self.min_age_ms           = Some(min_age_ms);
+        self.challenge_param      = Self::difficulty_to_challenge_param(1);
+        self.recommended_attempts = 0;
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Requires `required` distinct solutions instead of one and
+    /// re-signs the challenge.
+    ///
+    /// Each nonce must meet the unchanged target, so the expected work
+    /// grows `required`-fold with much less variance than a single
+    /// solution at a harder target. `recommended_attempts` is scaled
+    /// accordingly.
+    ///
+    /// # Arguments
+    /// * `required`:    Number of distinct solutions, from 2 to 32.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error if
+    ///                           `required` is out of range.
+    #[cfg(feature = "sign")]
+    pub fn with_required_solutions(
+        mut self,
+        required:    u8,
+        private_key: &SigningKey,
+    ) -> Result<Self, String> {
+        if !(2..=MAX_REQUIRED_SOLUTIONS).contains(&required) {
+            return Err(format!("required_solutions must be between 2 and {}", MAX_REQUIRED_SOLUTIONS));
+        }
+
+        self.required_solutions   = Some(required);
+        self.recommended_attempts = self.recommended_attempts.saturating_mul(required as u64);
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Replaces the target with the Hashcash-style target for
+    /// `leading_zero_bits` and re-signs the challenge, for issuers
+    /// interoperating with systems that count leading zero bits.
+    /// `recommended_attempts` is recomputed for the new target.
+    ///
+    /// # Arguments
+    /// * `leading_zero_bits`: Number of leading zero bits the hash must
+    ///                        have, from 0 to 256.
+    /// * `private_key`:       Ed25519 private key for re-signing the
+    ///                        challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error if
+    ///                           `leading_zero_bits` is out of range or
+    ///                           the challenge is a wait, time-lock or
+    ///                           VDF challenge.
+    #[cfg(feature = "sign")]
+    pub fn with_leading_zero_bits(
+        mut self,
+        leading_zero_bits: u32,
+        private_key:       &SigningKey,
+    ) -> Result<Self, String> {
+        if self.min_age_ms.is_some() || self.time_lock.is_some() || self.vdf.is_some() {
+            return Err("Wait, time-lock and VDF challenges have no hash target".to_string());
+        }
+
+        let difficulty: u64 = 1u64.checked_shl(leading_zero_bits).unwrap_or(u64::MAX);
+        self.challenge_param      = Self::leading_zero_bits_to_challenge_param(leading_zero_bits)?;
+        self.recommended_attempts = Self::recommended_attempts(difficulty)
+            .saturating_mul(self.solution_count() as u64);
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Turns the challenge into a time-lock challenge and re-signs it.
+    ///
+    /// The client answers with the output of `iterations` sequential
+    /// squarings in a `TimeLockResponse` instead of a nonce, so the
+    /// wall-clock cost does not shrink with the client's core count.
+    /// Nonce solutions are rejected from then on.
+    ///
+    /// # Arguments
+    /// * `time_lock_key`: The issuer's time-lock trapdoor.
+    /// * `iterations`:    Number of squarings the client must perform.
+    /// * `private_key`:   Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error if
+    ///                           `iterations` is zero or the challenge is
+    ///                           already a wait, k-of-n or VDF challenge.
+    #[cfg(all(feature = "sign", feature = "timelock"))]
+    pub fn with_time_lock(
+        mut self,
+        time_lock_key: &TimeLockKey,
+        iterations:    u64,
+        private_key:   &SigningKey,
+    ) -> Result<Self, String> {
+        if self.min_age_ms.is_some() || self.required_solutions.is_some() || self.vdf.is_some() {
+            return Err("Wait, k-of-n and VDF challenges cannot carry a time-lock puzzle".to_string());
+        }
+
+        self.time_lock            = Some(time_lock_key.puzzle(self.random_nonce.as_str(), iterations)?);
+        self.recommended_attempts = 0;
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// Turns the challenge into a VDF challenge and re-signs it.
+    ///
+    /// The client answers with a `VdfResponse` carrying the output of
+    /// `iterations` sequential squarings and a Wesolowski proof of it.
+    /// Issuing costs nothing beyond the signature; nonce solutions are
+    /// rejected from then on.
+    ///
+    /// # Arguments
+    /// * `modulus`:     RSA modulus as lowercase hex, whose factorization
+    ///                  the client must not know.
+    /// * `iterations`:  Number of squarings the client must perform.
+    /// * `private_key`: Ed25519 private key for re-signing the challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The re-signed challenge, or an error if
+    ///                           the parameters are invalid or the
+    ///                           challenge is already a wait, k-of-n or
+    ///                           time-lock challenge.
+    #[cfg(feature = "sign")]
+    pub fn with_vdf(
+        mut self,
+        modulus:     &str,
+        iterations:  u64,
+        private_key: &SigningKey,
+    ) -> Result<Self, String> {
+        if self.min_age_ms.is_some() || self.required_solutions.is_some() || self.time_lock.is_some() {
+            return Err("Wait, k-of-n and time-lock challenges cannot carry VDF parameters".to_string());
+        }
+
+        self.vdf                  = Some(VdfParams::new(modulus, iterations)?);
+        self.recommended_attempts = 0;
+        self.sign(private_key);
+        Ok(self)
+    }
+
+    /// # Returns
+    /// * `usize`: The number of distinct solutions the challenge
+    ///            requires, 1 unless `required_solutions` is set.
+    pub fn solution_count(&self) -> usize {
+        self.required_solutions.map_or(1, usize::from)
+    }
+
+    /// # Returns
+    /// * `PowAlgorithm`: `TimeLock` if the challenge carries a
+    ///                   `time_lock` puzzle, `Vdf` if it carries `vdf`
+    ///                   parameters, `Wait` if it carries `min_age_ms`,
+    ///                   otherwise `Sha256`.
+    pub fn algorithm(&self) -> PowAlgorithm {
+        if self.time_lock.is_some() {
+            PowAlgorithm::TimeLock
+        } else if self.vdf.is_some() {
+            PowAlgorithm::Vdf
+        } else if self.min_age_ms.is_some() {
+            PowAlgorithm::Wait
+        } else {
+            PowAlgorithm::Sha256
+        }
+    }
+
+    /// Checks that a wait challenge has been held long enough.
+    ///
+    /// Challenges without `min_age_ms` always pass.
+    ///
+    /// # Arguments
+    /// * `now`: The redemption time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the minimum age has passed.
+    pub fn check_min_age_at(&self, now: i64) -> Result<(), String> {
+        let Some(min_age_ms) = self.min_age_ms else {
+            return Ok(());
+        };
+
+        let redeemable_at: i64 = self.created_time.saturating_add(min_age_ms);
+        if now < redeemable_at {
+            return Err(format!("Wait challenge redeemed {} ms too early", redeemable_at - now));
+        }
+        Ok(())
+    }
+
+    /// Checks that an issuer node identifier is non-empty, short and
+    /// free of characters that are meaningful in the concatenated format.
+    pub(crate) fn validate_issuer_node(issuer_node: &str) -> Result<(), String> {
+        if issuer_node.is_empty() || issuer_node.len() > MAX_ISSUER_NODE_LEN {
+            return Err(format!("issuer_node must be 1 to {} characters", MAX_ISSUER_NODE_LEN));
+        }
+        if !issuer_node.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:/".contains(c)) {
+            return Err(format!("Invalid character in issuer_node: {}", issuer_node));
+        }
+        Ok(())
+    }
+
+    /// Checks the fields of a challenge received from untrusted input:
+    /// lengths, character sets and the ordering of timestamps. This
+    /// does not verify the signature.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid field.
+    pub fn validate(&self) -> Result<(), String> {
+        self.as_challenge_ref().validate()
+    }
+
+    /// Checks that the challenge is bound to the given request.
+    ///
+    /// This does not verify the signature; call it alongside the
+    /// signature checks in `crypto`.
+    ///
+    /// # Arguments
+    /// * `descriptor`: The request the solution is being redeemed for.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the binding matches, or an
+    ///                         error if the challenge is unbound or
+    ///                         bound to a different request.
+    pub fn verify_request_binding(&self, descriptor: &RequestDescriptor) -> Result<(), String> {
+        match self.request_binding {
+            Some(binding) if binding == descriptor.hash() => Ok(()),
+            Some(_) => Err("Challenge is bound to a different request".to_string()),
+            None => Err("Challenge is not bound to a request".to_string()),
+        }
+    }
+
+    /// Signs the challenge in place, covering every present
+    /// signed extension.
+    ///
+    /// # Arguments
+    /// * `private_key`: Ed25519 private key for signing the challenge.
+    #[cfg(feature = "sign")]
+    pub fn sign(&mut self, private_key: &SigningKey) {
+        // Generate the signature using the reusable generate_signature function.
+        self.challenge_signature = crate::crypto::generate_signature(private_key, &self.signing_message())
+            .unwrap_or([0u8; 64]);
+    }
+
+    /// Signs the challenge in place with a shared HMAC key instead of
+    /// an Ed25519 key, for deployments that issue and verify on the
+    /// same box.
+    ///
+    /// `public_key` is cleared to all zeros, which marks the challenge
+    /// as `SignatureScheme::HmacSha256`, and `challenge_signature`
+    /// holds the 32-byte tag followed by zeros. Call it after any
+    /// `with_*` builder, since those re-sign with Ed25519.
+    ///
+    /// # Arguments
+    /// * `hmac_key`: The key shared by issuer and verifier.
+    #[cfg(feature = "sign")]
+    pub fn sign_hmac(&mut self, hmac_key: &[u8; 32]) {
+        self.public_key          = [0u8; 32];
+        self.challenge_signature = crate::crypto::generate_hmac_tag(hmac_key, &self.signing_message());
+    }
+
+    /// # Returns
+    /// * `SignatureScheme`: `HmacSha256` if `public_key` is all zeros,
+    ///                      otherwise `Ed25519`.
+    pub fn signature_scheme(&self) -> SignatureScheme {
+        if self.public_key == [0u8; 32] {
+            SignatureScheme::HmacSha256
+        } else {
+            SignatureScheme::Ed25519
+        }
+    }
+
+    /// Creates the message covered by `challenge_signature`.
+    ///
+    /// This is the base message from `create_signing_message` followed by
+    /// each present signed extension (e.g. `submission_deadline`) as a
+    /// `|key=value` suffix, with `%` and `|` in values escaped as `%25` and
+    /// `%7C`. Challenges without extensions therefore produce exactly the
+    /// same message as before extensions existed.
+    ///
+    /// # Returns
+    /// * `String`: Canonical string representation for signing.
+    pub fn signing_message(&self) -> String {
+        self.as_challenge_ref().signing_message()
+    }
+
+    /// # Returns
+    /// * `ChallengeRef`: A view borrowing every field of the challenge.
+    pub fn as_challenge_ref(&self) -> ChallengeRef<'_> {
+        ChallengeRef {
+            random_nonce:         self.random_nonce.as_str(),
+            created_time:         self.created_time,
+            expiration_time:      self.expiration_time,
+            website_id:           &self.website_id,
+            challenge_param:      &self.challenge_param,
+            recommended_attempts: self.recommended_attempts,
+            public_key:           &self.public_key,
+            challenge_signature:  &self.challenge_signature,
+            submission_deadline:  self.submission_deadline,
+            request_binding:      self.request_binding,
+            issuer_node:          self.issuer_node.as_deref(),
+            min_age_ms:           self.min_age_ms,
+            issuance_context:     self.issuance_context,
+            nonce_proof:          self.nonce_proof,
+            time_attestation:     self.time_attestation,
+            required_solutions:   self.required_solutions,
+            time_lock:            self.time_lock.as_ref().map(TimeLockPuzzle::as_puzzle_ref),
+            vdf:                  self.vdf.as_ref().map(VdfParams::as_params_ref),
         }
     }
 
@@ -231,29 +996,267 @@ impl IronShieldChallenge {
         result
     }
 
+    /// Converts a Hashcash-style "N leading zero bits" difficulty to a
+    /// challenge_param.
+    ///
+    /// A hash has at least N leading zero bits exactly when it is less
+    /// than 2^(256 - N), so that is the target. Powers of two passed to
+    /// `difficulty_to_challenge_param` give the same targets: 2^N
+    /// expected attempts is N leading zero bits.
+    ///
+    /// # Arguments
+    /// * `leading_zero_bits`: Number of leading zero bits, from 0 to 256.
+    ///
+    /// # Returns
+    /// * `Result<[u8; 32], String>`: The challenge_param bytes in
+    ///                               big-endian format, or an error if
+    ///                               `leading_zero_bits` exceeds 256.
+    ///
+    /// # Examples
+    /// * leading_zero_bits = 0 ->  challenge_param = [0xFF; 32].
+    /// * leading_zero_bits = 1 ->  challenge_param = [0x80, 0x00, ...].
+    /// * leading_zero_bits = 20 -> challenge_param = [0x00, 0x00, 0x10, ...].
+    pub fn leading_zero_bits_to_challenge_param(leading_zero_bits: u32) -> Result<[u8; 32], String> {
+        match leading_zero_bits as usize {
+            0 => Ok([MAX_BYTE_VALUE; ARRAY_SIZE]),
+            bits @ 1..=HASH_BITS => Ok(Self::create_challenge_param_with_bit_set(HASH_BITS - bits)),
+            _ => Err(format!("leading_zero_bits must be at most {}", HASH_BITS)),
+        }
+    }
+
+    /// Converts a challenge_param to the number of leading zero bits
+    /// every accepted hash has.
+    ///
+    /// Exact for targets from `leading_zero_bits_to_challenge_param`
+    /// and for power-of-two difficulties. Other targets fall between
+    /// two bit counts and are rounded down, so the result is a lower
+    /// bound on the work a Hashcash-style verifier would credit.
+    ///
+    /// # Arguments
+    /// * `challenge_param`: The target in big-endian format.
+    ///
+    /// # Returns
+    /// * `Option<u32>`: The leading zero bits of `challenge_param - 1`,
+    ///                  or `None` for an all-zero target, which no hash
+    ///                  meets.
+    pub fn challenge_param_to_leading_zero_bits(challenge_param: &[u8; 32]) -> Option<u32> {
+        let mut largest_hash: [u8; 32] = *challenge_param;
+        let borrow_end: usize = largest_hash.iter().rposition(|byte| *byte != 0)?;
+        largest_hash[borrow_end] -= 1;
+        largest_hash[borrow_end + 1..].fill(MAX_BYTE_VALUE);
+
+        let zero_bytes: usize = largest_hash.iter().take_while(|byte| **byte == 0).count();
+        let zero_bits: u32 = largest_hash.get(zero_bytes).map_or(0, |byte| byte.leading_zeros());
+        Some(zero_bytes as u32 * BITS_PER_BYTE as u32 + zero_bits)
+    }
+
     /// # Returns
     /// * `bool`: `true` if the challenge is expired,
     ///           `false` otherwise.
     pub fn is_expired(&self) -> bool {
-        Utc::now().timestamp_millis() > self.expiration_time
+        now_millis() > self.expiration_time
     }
 
     /// # Returns
-    /// * `i64`: `created_time` **plus** 30 seconds.
+    /// * `i64`: Milliseconds until `expiration_time`; negative once
+    ///          the challenge has expired. See `remaining_validity`.
     pub fn time_until_expiration(&self) -> i64 {
-        self.expiration_time - Utc::now().timestamp_millis()
+        self.expiration_time - now_millis()
+    }
+
+    /// # Returns
+    /// * `Option<Duration>`: Time left until `expiration_time`, or
+    ///                       `None` if the challenge has expired.
+    pub fn remaining_validity(&self) -> Option<Duration> {
+        self.remaining_validity_at(now_millis())
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Option<Duration>`: Time left until `expiration_time` at
+    ///                       `now`, or `None` if the challenge is
+    ///                       expired at `now`. A challenge is still
+    ///                       valid, with zero time left, at exactly
+    ///                       `expiration_time`.
+    pub fn remaining_validity_at(&self, now: i64) -> Option<Duration> {
+        let remaining_ms: i64 = self.expiration_time.checked_sub(now)?;
+        u64::try_from(remaining_ms).ok().map(Duration::from_millis)
+    }
+
+    /// # Returns
+    /// * `Duration`: Time elapsed since `created_time`.
+    pub fn age(&self) -> Duration {
+        self.age_at(now_millis())
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Duration`: Time elapsed between `created_time` and `now`.
+    ///               Zero if `created_time` lies after `now`, e.g.
+    ///               because of clock skew between issuer and caller.
+    ///               Keeps growing after the challenge expires.
+    pub fn age_at(&self, now: i64) -> Duration {
+        let age_ms: u64 = now.saturating_sub(self.created_time).try_into().unwrap_or(0);
+        Duration::from_millis(age_ms)
+    }
+
+    /// # Returns
+    /// * `i64`: The last instant (unix millis) at which a solution
+    ///          is accepted: `submission_deadline` when present,
+    ///          otherwise `expiration_time`.
+    pub fn effective_submission_deadline(&self) -> i64 {
+        self.submission_deadline.unwrap_or(self.expiration_time)
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if a solution submitted at `now` is
+    ///           still accepted, `false` otherwise.
+    pub fn accepts_submission_at(&self, now: i64) -> bool {
+        now <= self.effective_submission_deadline()
+    }
+
+    /// Checks a proof-of-work solution:
+    /// SHA256(random_nonce_bytes + nonce_bytes) < challenge_param,
+    /// where `random_nonce_bytes` is the hex-decoded `random_nonce`
+    /// and `nonce_bytes` is `nonce` in little-endian order. Both sides
+    /// are compared as big-endian 256-bit numbers.
+    ///
+    /// To check many solutions of the same challenge, build a
+    /// `SolutionVerifier` once instead.
+    ///
+    /// # Arguments
+    /// * `nonce`: The candidate solution.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the solution meets the target.
+    pub fn check_solution(&self, nonce: i64) -> Result<(), String> {
+        SolutionVerifier::new(self)?.verify(nonce)
+    }
+
+    /// Checks the solutions of a challenge requiring more than one:
+    /// exactly `solution_count()` distinct nonces, each meeting the
+    /// target. Also accepts the single solution of a plain challenge.
+    /// Time-lock and VDF challenges have no nonce solutions and always
+    /// fail.
+    ///
+    /// # Arguments
+    /// * `nonces`: The candidate solutions.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if all solutions are valid.
+    pub fn check_solutions(&self, nonces: &[i64]) -> Result<(), String> {
+        if self.time_lock.is_some() {
+            return Err("Time-lock challenges are answered with a TimeLockResponse".to_string());
+        }
+        if self.vdf.is_some() {
+            return Err("VDF challenges are answered with a VdfResponse".to_string());
+        }
+        if nonces.len() != self.solution_count() {
+            return Err(format!(
+                "Challenge requires {} solutions, got {}",
+                self.solution_count(),
+                nonces.len()
+            ));
+        }
+        for (position, nonce) in nonces.iter().enumerate() {
+            if nonces[..position].contains(nonce) {
+                return Err(format!("Duplicate solution {}", nonce));
+            }
+        }
+
+        let verifier: SolutionVerifier = SolutionVerifier::new(self)?;
+        nonces.iter().try_for_each(|nonce| verifier.verify(*nonce))
+    }
+
+    /// Challenge identifier used by stores and for ordering.
+    ///
+    /// The identifier is the SHA-256 of `concat_struct()`, so it covers
+    /// every field: a challenge altered after issuance never shares the
+    /// identifier of the genuine one. `concat_struct()` is the canonical
+    /// form every challenge hash in this crate is taken over.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: The challenge identifier.
+    pub fn challenge_id(&self) -> [u8; 32] {
+        Sha256::digest(self.concat_struct().as_bytes()).into()
+    }
+
+    /// Hash of the challenge content for caches, logs and dedup stores.
+    ///
+    /// It is the `challenge_id()` of the challenge with
+    /// `challenge_signature` set to zeros: the SHA-256 of `concat_struct()`
+    /// covering every field, signed extensions and `recommended_attempts`
+    /// included, except the signature. The same challenge therefore hashes
+    /// the same however often it was signed.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: The content hash.
+    pub fn content_hash(&self) -> [u8; 32] {
+        Self { challenge_signature: [0u8; 64], ..self.clone() }.challenge_id()
+    }
+
+    /// Content-addressed challenge identifier: the BLAKE3 hash of
+    /// `concat_struct()`, the same bytes `challenge_id()` hashes with
+    /// SHA-256, for stores that key challenges by BLAKE3. Like
+    /// `challenge_id()`, and unlike `content_hash()`, it covers the
+    /// signature, so a re-signed copy gets its own id. The id is the same
+    /// whichever format the challenge was decoded from.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: The challenge identifier.
+    #[cfg(feature = "blake3")]
+    pub fn content_id(&self) -> [u8; 32] {
+        blake3::hash(self.concat_struct().as_bytes()).into()
+    }
+
+    /// Checks that an identifier claimed for this challenge, e.g. by a
+    /// client or a replica, is its `content_id()`, so a challenge
+    /// cannot be stored or looked up under another challenge's id.
+    ///
+    /// # Arguments
+    /// * `claimed_id`: The identifier received with the challenge.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the identifier matches.
+    #[cfg(feature = "blake3")]
+    pub fn verify_content_id(&self, claimed_id: &[u8; 32]) -> Result<(), String> {
+        if self.content_id() != *claimed_id {
+            return Err(format!("Challenge id {} does not match its content", hex::encode(claimed_id)));
+        }
+        Ok(())
     }
 
     /// # Returns
     /// * `i64`: The current time in millis.
     pub fn generate_created_time() -> i64 {
-        Utc::now().timestamp_millis()
+        now_millis()
+    }
+
+    /// # Returns
+    /// * `RandomNonce`: A random hex-encoded value.
+    #[cfg(feature = "rng")]
+    pub fn generate_random_nonce() -> RandomNonce {
+        RandomNonce::from_bytes(&rand::random::<[u8; RANDOM_NONCE_LEN]>())
     }
 
+    /// # Arguments
+    /// * `nonce_source`: Where the random bytes come from.
+    ///
     /// # Returns
-    /// * `String`: A random hex-encoded value.
-    pub fn generate_random_nonce() -> String {
-        hex::encode(&rand::random::<[u8; 16]>())
+    /// * `Result<RandomNonce, String>`: A random hex-encoded value, or
+    ///                                  the nonce source's error.
+    #[cfg(feature = "sign")]
+    pub fn generate_random_nonce_with(nonce_source: &impl NonceSource) -> Result<RandomNonce, String> {
+        let mut nonce: [u8; RANDOM_NONCE_LEN] = [0u8; RANDOM_NONCE_LEN];
+        nonce_source.fill_nonce(&mut nonce)?;
+        Ok(RandomNonce::from_bytes(&nonce))
     }
 
     /// Returns the recommended number of attempts to expect for a given difficulty.
@@ -284,8 +1287,22 @@ impl IronShieldChallenge {
     /// * `website_id`       as a string.
     /// * `public_key`       as a lowercase hex string.
     /// * `challenge_params` as a lowercase hex string.
+    ///
+    /// Followed by every present optional extension as `key=value`:
+    /// * `sd`               `submission_deadline` as `i64`.
+    /// * `rb`               `request_binding` as a lowercase hex string.
+    /// * `in`               `issuer_node` as a string.
+    /// * `ma`               `min_age_ms` as `i64`.
+    /// * `ic`               `issuance_context` as a lowercase hex string.
+    /// * `vn`               `nonce_proof` as `<counter>,<hex proof>`.
+    /// * `ta`               `time_attestation` as
+    ///                      `<hex authority key>,<midpoint>,<radius_ms>,<hex signature>`.
+    /// * `ks`               `required_solutions` as `u8`.
+    /// * `tl`               `time_lock` as
+    ///                      `<hex modulus>,<iterations>,<hex target>`.
+    /// * `vd`               `vdf` as `<hex modulus>,<iterations>`.
     pub fn concat_struct(&self) -> String {
-        format!(
+        let mut concat: String = format!(
             "{}|{}|{}|{}|{}|{}|{}|{}",
             self.random_nonce,
             self.created_time,
@@ -296,7 +1313,14 @@ impl IronShieldChallenge {
             self.recommended_attempts,
             hex::encode(self.public_key),
             hex::encode(self.challenge_signature)
-        )
+        );
+
+        for extension in self.as_challenge_ref().extension_parts() {
+            concat.push('|');
+            concat.push_str(&extension);
+        }
+
+        concat
     }
 
     /// Creates an `IronShieldChallenge` from a concatenated string.
@@ -305,6 +1329,7 @@ impl IronShieldChallenge {
     /// `IronShieldChallenge::concat_struct`.
     /// Expects a string in the format:
     /// "random_nonce|created_time|expiration_time|website_id|challenge_params|public_key|challenge_signature"
+    /// optionally followed by `|key=value` extension parts.
     ///
     /// # Arguments
     ///
@@ -319,11 +1344,11 @@ impl IronShieldChallenge {
     pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
         let parts: Vec<&str> = concat_str.split('|').collect();
 
-        if parts.len() != 8 {
-            return Err(format!("Expected 8 parts, got {}", parts.len()));
+        if parts.len() < BASE_PART_COUNT {
+            return Err(format!("Expected {} parts, got {}", BASE_PART_COUNT, parts.len()));
         }
 
-        let random_nonce: String = parts[0].to_string();
+        let random_nonce: &str = parts[0];
 
         let created_time: i64 = parts[1].parse::<i64>()
             .map_err(|_| "Failed to parse created_time as i64")?;
@@ -331,7 +1356,7 @@ impl IronShieldChallenge {
         let expiration_time: i64 = parts[2].parse::<i64>()
             .map_err(|_| "Failed to parse expiration_time as i64")?;
 
-        let website_id: String = parts[3].to_string();
+        let website_id: &str = parts[3];
 
         let challenge_param_bytes: Vec<u8> = hex::decode(parts[4])
             .map_err(|_| "Failed to decode challenge_params hex string")?;
@@ -353,16 +1378,32 @@ impl IronShieldChallenge {
             .try_into()
             .map_err(|_| "Signature must be exactly 64 bytes")?;
 
-        Ok(Self {
+        let mut challenge = ChallengeRef {
             random_nonce,
             created_time,
             expiration_time,
             website_id,
-            challenge_param,
+            challenge_param:     &challenge_param,
             recommended_attempts,
-            public_key,
-            challenge_signature,
-        })
+            public_key:          &public_key,
+            challenge_signature: &challenge_signature,
+            submission_deadline: None,
+            request_binding:     None,
+            issuer_node:         None,
+            min_age_ms:          None,
+            issuance_context:    None,
+            nonce_proof:         None,
+            time_attestation:    None,
+            required_solutions:  None,
+            time_lock:           None,
+            vdf:                 None,
+        };
+
+        for part in &parts[BASE_PART_COUNT..] {
+            challenge.apply_extension_part(part)?;
+        }
+
+        challenge.to_owned_challenge()
     }
 
     /// Encodes the challenge as a base64url string for HTTP header transport.
@@ -374,12 +1415,13 @@ impl IronShieldChallenge {
     /// * `String`: Base64url-encoded string ready for HTTP header use.
     ///
     /// # Example
-    /// ```
+    #[cfg_attr(feature = "rng", doc = " ```")]
+    #[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
     /// use ironshield_types::IronShieldChallenge;
     /// use ed25519_dalek::SigningKey;
     /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
     /// let challenge = IronShieldChallenge::new(
-    ///     "test_website".to_string(),
+    ///     "test_website".parse().unwrap(),
     ///     100_000,
     ///     dummy_key,
     ///     [0x34; 32],
@@ -403,13 +1445,14 @@ impl IronShieldChallenge {
     /// * `Result<Self, String>`: Decoded challenge or detailed error message.
     ///
     /// # Example
-    /// ```
+    #[cfg_attr(feature = "rng", doc = " ```")]
+    #[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
     /// use ironshield_types::IronShieldChallenge;
     /// use ed25519_dalek::SigningKey;
     /// // Create a challenge and encode it
     /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
     /// let original = IronShieldChallenge::new(
-    ///     "test_website".to_string(),
+    ///     "test_website".parse().unwrap(),
     ///     100_000,
     ///     dummy_key,
     ///     [0x34; 32],
@@ -420,15 +1463,151 @@ impl IronShieldChallenge {
     /// assert_eq!(original.random_nonce, decoded.random_nonce);
     /// ```
     pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
-        // Decode using the existing serde_utils function.
-        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        traced!("decode_challenge_header", Result<Self, String>, {
+            header_len = encoded_header.len(),
+            website_id = tracing::field::Empty,
+        }, |challenge| { website_id = &challenge.website_id }, {
+            // Decode using the existing serde_utils function.
+            let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+
+            // Parse using the existing concat_struct format.
+            Self::from_concat_struct(&concat_str)
+        })
+    }
 
-        // Parse using the existing concat_struct format.
-        Self::from_concat_struct(&concat_str)
+    /// Encodes the challenge in the compact binary format.
+    ///
+    /// Fixed-width fields are written as raw big-endian bytes and the
+    /// signed extensions as length-prefixed `key=value` strings, so the
+    /// result is considerably smaller than `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Vec<u8>, String>`: The encoded bytes, or an error if a
+    ///                              string field is too long to encode.
+    pub fn to_compact_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut writer = CompactWriter::new();
+        self.write_compact(&mut writer)?;
+        Ok(writer.finish())
+    }
+
+    /// Decodes a challenge produced by `to_compact_bytes()`.
+    ///
+    /// # Arguments
+    /// * `bytes`: The compact binary payload.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The decoded challenge or an error
+    ///                           describing the malformed field.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = CompactReader::new(bytes)?;
+        let challenge: Self = Self::read_compact(&mut reader)?;
+        reader.finish()?;
+        Ok(challenge)
+    }
+
+    /// Encodes the challenge as an ASCII-armored block for pasting into
+    /// tickets, emails or CLI tools.
+    ///
+    /// The body is the base64 of `to_compact_bytes()`, wrapped at 64
+    /// columns and followed by a CRC-24 checksum line, as in OpenPGP
+    /// armor.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The `-----BEGIN IRONSHIELD CHALLENGE-----`
+    ///                             block, or an error if the challenge
+    ///                             cannot be encoded.
+    pub fn to_armored_string(&self) -> Result<String, String> {
+        Ok(crate::armor::encode_armor(ARMOR_LABEL, &self.to_compact_bytes()?))
+    }
+
+    /// Decodes a block produced by `to_armored_string()`. Surrounding
+    /// whitespace and `\r\n` line endings are accepted.
+    ///
+    /// # Arguments
+    /// * `armored`: The armored challenge block.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The decoded challenge, or an error if the
+    ///                           armor is malformed or the checksum does
+    ///                           not match.
+    pub fn from_armored_string(armored: &str) -> Result<Self, String> {
+        Self::from_compact_bytes(&crate::armor::decode_armor(ARMOR_LABEL, armored)?)
+    }
+
+    /// Encodes the compact binary form as base45 (RFC 9285), which fits
+    /// QR codes in alphanumeric mode more densely than base64.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The base45 string, or an error if the
+    ///                             challenge cannot be encoded.
+    pub fn to_base45(&self) -> Result<String, String> {
+        Ok(crate::base45_encode(&self.to_compact_bytes()?))
+    }
+
+    /// Decodes a challenge produced by `to_base45()`.
+    ///
+    /// # Arguments
+    /// * `encoded`: The base45 string, e.g. as read from a QR code.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The decoded challenge or an error message.
+    pub fn from_base45(encoded: &str) -> Result<Self, String> {
+        Self::from_compact_bytes(&crate::base45_decode(encoded)?)
+    }
+
+    /// Writes the challenge fields into an open compact payload.
+    pub(crate) fn write_compact(&self, writer: &mut CompactWriter) -> Result<(), String> {
+        writer.write_str(self.random_nonce.as_str())?;
+        writer.write_i64(self.created_time);
+        writer.write_i64(self.expiration_time);
+        writer.write_str(&self.website_id)?;
+        writer.write_bytes(&self.challenge_param);
+        writer.write_u64(self.recommended_attempts);
+        writer.write_bytes(&self.public_key);
+        writer.write_bytes(&self.challenge_signature);
+
+        let extensions: Vec<String> = self.as_challenge_ref().extension_parts();
+        writer.write_u8(extensions.len() as u8);
+        for extension in &extensions {
+            writer.write_str(extension)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the challenge fields from an open compact payload.
+    pub(crate) fn read_compact(reader: &mut CompactReader) -> Result<Self, String> {
+        ChallengeRef::read_compact(reader).and_then(|challenge: ChallengeRef| challenge.to_owned_challenge())
     }
 }
 
-#[cfg(test)]
+/// Orders challenges by `created_time`, then by `challenge_id()`.
+///
+/// The identifier is only computed for challenges created in the same
+/// millisecond. Since it covers every field, two challenges compare
+/// equal exactly when they are the same challenge.
+impl Ord for IronShieldChallenge {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.created_time.cmp(&other.created_time)
+            .then_with(|| self.challenge_id().cmp(&other.challenge_id()))
+    }
+}
+
+impl PartialOrd for IronShieldChallenge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IronShieldChallenge {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IronShieldChallenge {}
+
+#[cfg(all(test, feature = "rng"))]
 mod tests {
     use super::*;
 
@@ -622,6 +1801,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_leading_zero_bits_conversions() {
+        for bits in 0..=256u32 {
+            let challenge_param: [u8; 32] = IronShieldChallenge::leading_zero_bits_to_challenge_param(bits).unwrap();
+            assert_eq!(IronShieldChallenge::challenge_param_to_leading_zero_bits(&challenge_param), Some(bits));
+        }
+        for bits in 0..64u32 {
+            assert_eq!(
+                IronShieldChallenge::difficulty_to_challenge_param(1u64 << bits),
+                IronShieldChallenge::leading_zero_bits_to_challenge_param(bits).unwrap()
+            );
+        }
+        assert!(IronShieldChallenge::leading_zero_bits_to_challenge_param(257).is_err());
+        assert_eq!(IronShieldChallenge::challenge_param_to_leading_zero_bits(&[0; 32]), None);
+
+        // A target between two powers of two rounds down.
+        let mut challenge_param: [u8; 32] = [0; 32];
+        challenge_param[2] = 0x18;
+        assert_eq!(IronShieldChallenge::challenge_param_to_leading_zero_bits(&challenge_param), Some(19));
+
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key)
+            .with_leading_zero_bits(20, &private_key)
+            .unwrap();
+        assert_eq!(challenge.challenge_param[..3], [0x00, 0x00, 0x10]);
+        assert_eq!(challenge.recommended_attempts, 2 << 20);
+        assert!(crate::verify_challenge_signature_with_key(&challenge, &public_key).is_ok());
+        let wait = challenge.with_min_age(1, &private_key).unwrap();
+        assert!(wait.with_leading_zero_bits(20, &private_key).is_err());
+    }
+
     #[test]
     fn test_recommended_attempts() {
         // Test recommended_attempts function
@@ -643,7 +1854,7 @@ mod tests {
         let private_key = SigningKey::from_bytes(&[0; 32]);
         let public_key = private_key.verifying_key().to_bytes();
         let original_challenge = IronShieldChallenge::new(
-            "test-site".to_string(),
+            "test-site".parse().unwrap(),
             100_000,
             private_key,
             public_key,
@@ -706,6 +1917,413 @@ mod tests {
         assert!(above_max <= max_param); // Should be the same or smaller
     }
 
+    #[test]
+    fn test_submission_deadline_roundtrip_and_signing() {
+        let private_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let policy = LifetimePolicy::new(30_000, 15_000, 60_000).unwrap();
+        let challenge = IronShieldChallenge::new_with_policy(
+            "test-site".parse().unwrap(),
+            1_000,
+            private_key,
+            public_key,
+            &policy,
+        );
+
+        assert_eq!(challenge.submission_deadline, Some(challenge.expiration_time + 15_000));
+        assert!(challenge.concat_struct().ends_with(&format!("|sd={}", challenge.expiration_time + 15_000)));
+        assert!(challenge.signing_message().ends_with(&format!("|sd={}", challenge.expiration_time + 15_000)));
+
+        let decoded = IronShieldChallenge::from_base64url_header(&challenge.to_base64url_header()).unwrap();
+        assert_eq!(decoded.submission_deadline, challenge.submission_deadline);
+        crate::verify_challenge_signature_with_key(&decoded, &public_key).unwrap();
+
+        // Moving the deadline must break the signature.
+        let mut tampered = decoded.clone();
+        tampered.submission_deadline = Some(i64::MAX);
+        assert!(crate::verify_challenge_signature_with_key(&tampered, &public_key).is_err());
+    }
+
+    #[test]
+    fn test_website_id_is_signed_verbatim() {
+        let private_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let website_id = WebsiteId::verbatim("https://Example.COM/api").unwrap();
+        let challenge = IronShieldChallenge::new(website_id, 1_000, private_key, public_key);
+
+        let from_json: IronShieldChallenge = serde_json::from_str(&serde_json::to_string(&challenge).unwrap()).unwrap();
+        let from_header = IronShieldChallenge::from_base64url_header(&challenge.to_base64url_header()).unwrap();
+        for decoded in [from_json, from_header] {
+            assert_eq!(decoded.website_id, "https://Example.COM/api");
+            crate::verify_challenge_signature_with_key(&decoded, &public_key).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_default_challenge_has_no_extensions() {
+        let private_key = SigningKey::from_bytes(&[0; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key, public_key);
+
+        assert_eq!(challenge.submission_deadline, None);
+        assert_eq!(challenge.concat_struct().split('|').count(), 8);
+        assert_eq!(challenge.effective_submission_deadline(), challenge.expiration_time);
+        assert!(challenge.accepts_submission_at(challenge.expiration_time));
+        assert!(!challenge.accepts_submission_at(challenge.expiration_time + 1));
+    }
+
+    #[test]
+    fn test_request_binding() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let descriptor = RequestDescriptor::new("POST", "https://example.com/login", Some(b"user=a")).unwrap();
+        let other = RequestDescriptor::new("POST", "https://example.com/admin", Some(b"user=a")).unwrap();
+
+        let unbound = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        assert!(unbound.verify_request_binding(&descriptor).unwrap_err().contains("not bound"));
+
+        let bound = unbound.with_request_binding(&descriptor, &private_key);
+        assert_eq!(bound.request_binding, Some(descriptor.hash()));
+        assert!(bound.verify_request_binding(&descriptor).is_ok());
+        assert!(bound.verify_request_binding(&other).unwrap_err().contains("different request"));
+        crate::verify_challenge_signature_with_key(&bound, &public_key).unwrap();
+
+        // The binding survives the header round-trip and is signed.
+        let decoded = IronShieldChallenge::from_base64url_header(&bound.to_base64url_header()).unwrap();
+        assert_eq!(decoded.request_binding, bound.request_binding);
+        let mut rebound = decoded.clone();
+        rebound.request_binding = Some(other.hash());
+        assert!(crate::verify_challenge_signature_with_key(&rebound, &public_key).is_err());
+    }
+
+    #[test]
+    fn test_issuance_context() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let context = IssuanceContext { asn: Some(64_496), bot_score: Some(12), ..Default::default() };
+        let other = IssuanceContext { bot_score: Some(80), ..context.clone() };
+
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        assert!(challenge.verify_issuance_context(&context).unwrap_err().contains("does not commit"));
+        assert!(challenge.clone().with_issuance_context(&IssuanceContext { bot_score: Some(0), ..Default::default() }, &private_key).is_err());
+
+        let committed = challenge.with_issuance_context(&context, &private_key).unwrap();
+        assert!(committed.verify_issuance_context(&context).is_ok());
+        assert!(committed.verify_issuance_context(&other).unwrap_err().contains("different issuance context"));
+        crate::verify_challenge_signature_with_key(&committed, &public_key).unwrap();
+
+        let decoded = IronShieldChallenge::from_compact_bytes(&committed.to_compact_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.issuance_context, Some(context.commitment()));
+        let mut recommitted = decoded;
+        recommitted.issuance_context = Some(other.commitment());
+        assert!(crate::verify_challenge_signature_with_key(&recommitted, &public_key).is_err());
+    }
+
+    #[test]
+    fn test_time_attestation() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let authority = SigningKey::from_bytes(&[4; 32]);
+        let authorities = crate::TimeAuthorities::new(vec![authority.verifying_key().to_bytes()], 1_000, 500);
+
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        let lifetime_ms: i64 = challenge.expiration_time - challenge.created_time;
+        let attest = |midpoint: i64| TimeAttestation::sign(&authority, &challenge.time_attestation_nonce(), midpoint, 200);
+        assert!(challenge.verify_time_attestation(&authorities).is_err());
+
+        let attested = challenge.clone().with_time_attestation(attest(challenge.created_time), &private_key).unwrap();
+        crate::verify_challenge_signature_with_key(&attested, &public_key).unwrap();
+        assert_eq!(attested.attested_expiration_time(&authorities), Ok(attested.expiration_time));
+        let decoded = IronShieldChallenge::from_base64url_header(&attested.to_base64url_header()).unwrap();
+        assert_eq!(decoded.time_attestation, attested.time_attestation);
+        assert!(attested.concat_struct().contains("|ta="));
+
+        // The issuer clock ran 600ms ahead of the authority.
+        let ahead = challenge.clone().with_time_attestation(attest(challenge.created_time - 600), &private_key).unwrap();
+        assert_eq!(
+            ahead.attested_expiration_time(&authorities),
+            Ok(challenge.created_time - 400 + lifetime_ms)
+        );
+        let far_ahead = challenge.clone().with_time_attestation(attest(challenge.created_time - 2_000), &private_key).unwrap();
+        assert!(far_ahead.attested_expiration_time(&authorities).is_err());
+
+        let foreign = TimeAttestation::sign(&authority, &[0; 32], challenge.created_time, 200);
+        assert!(challenge.clone().with_time_attestation(foreign, &private_key).is_err());
+        let mut moved = attested.clone();
+        moved.created_time += 1;
+        assert!(moved.verify_time_attestation(&authorities).is_err());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_content_id() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+
+        let id: [u8; 32] = challenge.content_id();
+        assert_eq!(id, <[u8; 32]>::from(blake3::hash(challenge.concat_struct().as_bytes())));
+        assert_ne!(id, challenge.challenge_id());
+        challenge.verify_content_id(&id).unwrap();
+
+        let other = IronShieldChallenge::new("other-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        assert!(other.verify_content_id(&id).unwrap_err().contains("does not match"));
+
+        let extended = challenge.with_issuer_node("fra/worker-3", &private_key).unwrap();
+        let from_json: IronShieldChallenge = serde_json::from_str(&serde_json::to_string(&extended).unwrap()).unwrap();
+        let from_concat = IronShieldChallenge::from_concat_struct(&from_json.concat_struct()).unwrap();
+        let from_header = IronShieldChallenge::from_base64url_header(&from_concat.to_base64url_header()).unwrap();
+        for decoded in [&from_json, &from_concat, &from_header] {
+            decoded.verify_content_id(&extended.content_id()).unwrap();
+        }
+    }
+
+    #[cfg(feature = "vrf")]
+    #[test]
+    fn test_vrf_nonce() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let vrf_key = VrfNonceKey::generate();
+
+        let challenge = IronShieldChallenge::new_with_vrf_nonce(
+            "test-site".parse().unwrap(),
+            1_000,
+            &private_key,
+            public_key,
+            &LifetimePolicy::default(),
+            &vrf_key,
+            3,
+        );
+        assert!(challenge.verify_nonce_proof(&vrf_key.public_key()).is_ok());
+        crate::verify_challenge_signature_with_key(&challenge, &public_key).unwrap();
+        assert!(challenge.concat_struct().contains("|vn=3,"));
+
+        let decoded = IronShieldChallenge::from_base64url_header(&challenge.to_base64url_header()).unwrap();
+        assert_eq!(decoded.nonce_proof, challenge.nonce_proof);
+        let decoded = IronShieldChallenge::from_compact_bytes(&challenge.to_compact_bytes().unwrap()).unwrap();
+        assert!(decoded.verify_nonce_proof(&vrf_key.public_key()).is_ok());
+
+        let mut swapped = decoded.clone();
+        swapped.random_nonce = IronShieldChallenge::generate_random_nonce();
+        assert!(swapped.verify_nonce_proof(&vrf_key.public_key()).unwrap_err().contains("does not match"));
+        let plain = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key, public_key);
+        assert!(plain.verify_nonce_proof(&vrf_key.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_issuer_node() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key)
+            .with_issuer_node("fra/worker-3", &private_key)
+            .unwrap();
+
+        assert!(challenge.concat_struct().ends_with("|in=fra/worker-3"));
+        crate::verify_challenge_signature_with_key(&challenge, &public_key).unwrap();
+
+        let decoded = IronShieldChallenge::from_base64url_header(&challenge.to_base64url_header()).unwrap();
+        assert_eq!(decoded.issuer_node.as_deref(), Some("fra/worker-3"));
+        let decoded = IronShieldChallenge::from_compact_bytes(&challenge.to_compact_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.issuer_node.as_deref(), Some("fra/worker-3"));
+
+        // The issuer node is covered by the signature.
+        let mut relabeled = decoded.clone();
+        relabeled.issuer_node = Some("ams/worker-1".to_string());
+        assert!(crate::verify_challenge_signature_with_key(&relabeled, &public_key).is_err());
+
+        let unlabeled = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        assert!(unlabeled.clone().with_issuer_node("", &private_key).unwrap_err().contains("1 to 64"));
+        assert!(unlabeled.with_issuer_node("fra|ams", &private_key).unwrap_err().contains("Invalid character"));
+    }
+
+    #[test]
+    fn test_content_hash_excludes_signature() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+
+        let mut resigned = challenge.clone();
+        resigned.challenge_signature = [0x11; 64];
+        assert_eq!(resigned.content_hash(), challenge.content_hash());
+        assert_ne!(resigned.challenge_id(), challenge.challenge_id());
+        let mut unsigned = challenge.clone();
+        unsigned.challenge_signature = [0; 64];
+        assert_eq!(challenge.content_hash(), unsigned.challenge_id());
+
+        let mut altered = challenge.clone();
+        altered.expiration_time += 1;
+        assert_ne!(altered.content_hash(), challenge.content_hash());
+
+        // A value shifted between extensions is a different challenge.
+        let mut waiting = challenge.clone().with_issuer_node("edge-1", &private_key).unwrap();
+        waiting.min_age_ms = Some(5_000);
+        let mut shifted = waiting.clone();
+        shifted.issuer_node = Some("edge-1|ma=5000".to_string());
+        shifted.min_age_ms = None;
+        assert_ne!(shifted.content_hash(), waiting.content_hash());
+    }
+
+    #[test]
+    fn test_check_solution() {
+        use sha2::{Digest, Sha256};
+
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 16, private_key, public_key);
+
+        let solution: i64 = (0..10_000).find(|nonce| challenge.check_solution(*nonce).is_ok()).unwrap();
+        let mut hasher = Sha256::new();
+        hasher.update(challenge.random_nonce.decode().unwrap());
+        hasher.update(crate::bytes::nonce_to_le_bytes(solution));
+        assert!(crate::bytes::meets_target(&hasher.finalize().into(), &challenge.challenge_param));
+
+        let impossible = IronShieldChallenge { challenge_param: [0; 32], ..challenge.clone() };
+        assert!(impossible.check_solution(solution).unwrap_err().contains("target"));
+
+        let malformed = IronShieldChallenge { random_nonce: "abc".parse().unwrap(), ..challenge };
+        assert!(malformed.check_solution(solution).unwrap_err().contains("random_nonce"));
+    }
+
+    #[test]
+    fn test_duration_helpers() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, private_key.clone(), private_key.verifying_key().to_bytes());
+        let created: i64 = challenge.created_time;
+        let expires: i64 = challenge.expiration_time;
+
+        assert_eq!(challenge.remaining_validity_at(expires - 1_500), Some(Duration::from_millis(1_500)));
+        assert_eq!(challenge.remaining_validity_at(expires), Some(Duration::ZERO));
+        assert_eq!(challenge.remaining_validity_at(expires + 1), None);
+        assert!(challenge.remaining_validity().is_some());
+
+        assert_eq!(challenge.age_at(created + 250), Duration::from_millis(250));
+        assert_eq!(challenge.age_at(created - 250), Duration::ZERO);
+        assert_eq!(challenge.age_at(expires + 1), Duration::from_millis((expires + 1 - created) as u64));
+    }
+
+    #[test]
+    fn test_wait_challenge() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let plain = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        assert_eq!(plain.algorithm(), PowAlgorithm::Sha256);
+        assert!(plain.check_min_age_at(plain.created_time).is_ok());
+
+        let challenge = plain.clone().with_min_age(5_000, &private_key).unwrap();
+        assert_eq!(challenge.algorithm(), PowAlgorithm::Wait);
+        assert_eq!(challenge.challenge_param, [0xFF; 32]);
+        assert!(challenge.concat_struct().ends_with("|ma=5000"));
+        crate::verify_challenge_signature_with_key(&challenge, &public_key).unwrap();
+
+        assert!(challenge.check_min_age_at(challenge.created_time + 4_999).unwrap_err().contains("1 ms too early"));
+        assert!(challenge.check_min_age_at(challenge.created_time + 5_000).is_ok());
+
+        let decoded = IronShieldChallenge::from_compact_bytes(&challenge.to_compact_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.min_age_ms, Some(5_000));
+        let decoded = IronShieldChallenge::from_concat_struct(&challenge.concat_struct()).unwrap();
+        assert_eq!(decoded.min_age_ms, Some(5_000));
+
+        // The wait is covered by the signature.
+        let mut shortened = decoded.clone();
+        shortened.min_age_ms = Some(1);
+        assert!(crate::verify_challenge_signature_with_key(&shortened, &public_key).is_err());
+
+        assert!(plain.clone().with_min_age(0, &private_key).unwrap_err().contains("positive"));
+        assert!(plain.with_min_age(60_000, &private_key).unwrap_err().contains("submission deadline"));
+    }
+
+    #[test]
+    fn test_compact_bytes_roundtrip() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let descriptor = RequestDescriptor::new("GET", "https://example.com/ws", None).unwrap();
+        let policy = LifetimePolicy::new(30_000, 5_000, 60_000).unwrap();
+
+        let plain = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key);
+        let extended = IronShieldChallenge::new_with_policy("test-site".parse().unwrap(), 1_000, private_key.clone(), public_key, &policy)
+            .with_request_binding(&descriptor, &private_key);
+
+        for challenge in [plain, extended] {
+            let bytes: Vec<u8> = challenge.to_compact_bytes().unwrap();
+            assert!(bytes.len() < challenge.concat_struct().len());
+
+            let decoded = IronShieldChallenge::from_compact_bytes(&bytes).unwrap();
+            assert_eq!(decoded.concat_struct(), challenge.concat_struct());
+            crate::verify_challenge_signature_with_key(&decoded, &public_key).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_from_compact_bytes_errors() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key.clone(), [0x34; 32]);
+        let bytes: Vec<u8> = challenge.to_compact_bytes().unwrap();
+
+        let truncated = IronShieldChallenge::from_compact_bytes(&bytes[..bytes.len() - 10]);
+        assert!(truncated.unwrap_err().starts_with(crate::CORRUPTED_PAYLOAD_ERROR));
+
+        let mut trailing: Vec<u8> = bytes.clone();
+        trailing.push(0);
+        assert!(IronShieldChallenge::from_compact_bytes(&trailing).unwrap_err().starts_with(crate::CORRUPTED_PAYLOAD_ERROR));
+    }
+
+    #[test]
+    fn test_base45_roundtrip() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key, public_key);
+
+        let encoded: String = challenge.to_base45().unwrap();
+        assert!(encoded.bytes().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || b" $%*+-./:".contains(&c)));
+
+        let decoded = IronShieldChallenge::from_base45(&encoded).unwrap();
+        assert_eq!(decoded.concat_struct(), challenge.concat_struct());
+        assert!(IronShieldChallenge::from_base45(&encoded.to_lowercase()).is_err());
+    }
+
+    #[test]
+    fn test_armored_string_roundtrip() {
+        let private_key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("test-site".parse().unwrap(), 1_000, private_key, public_key);
+
+        let armored: String = challenge.to_armored_string().unwrap();
+        assert!(armored.starts_with("-----BEGIN IRONSHIELD CHALLENGE-----\n"));
+        assert!(armored.ends_with("-----END IRONSHIELD CHALLENGE-----\n"));
+
+        let pasted: String = format!("  {}\n", armored.replace('\n', "\r\n"));
+        let decoded = IronShieldChallenge::from_armored_string(&pasted).unwrap();
+        assert_eq!(decoded.concat_struct(), challenge.concat_struct());
+        crate::verify_challenge_signature_with_key(&decoded, &public_key).unwrap();
+
+        let mut lines: Vec<String> = armored.lines().map(str::to_string).collect();
+        lines[1] = lines[1].chars().rev().collect();
+        assert!(IronShieldChallenge::from_armored_string(&lines.join("\n")).is_err());
+        assert!(IronShieldChallenge::from_armored_string(&challenge.export_detached_signature()).unwrap_err().contains("header"));
+    }
+
+    #[test]
+    fn test_from_concat_struct_extension_errors() {
+        let zero_32 = "0".repeat(64);
+        let zero_64 = "0".repeat(128);
+        let base = format!("n|1|2|site|{}|0|{}|{}", zero_32, zero_32, zero_64);
+
+        let result = IronShieldChallenge::from_concat_struct(&format!("{}|sd=abc", base));
+        assert!(result.unwrap_err().contains("Failed to parse submission_deadline"));
+
+        let result = IronShieldChallenge::from_concat_struct(&format!("{}|zz=1", base));
+        assert!(result.unwrap_err().contains("Unknown challenge extension"));
+
+        let result = IronShieldChallenge::from_concat_struct(&format!("{}|sd=1|sd=2", base));
+        assert!(result.unwrap_err().contains("Duplicate challenge extension"));
+
+        let result = IronShieldChallenge::from_concat_struct(&format!("{}|garbage", base));
+        assert!(result.unwrap_err().contains("Malformed challenge extension"));
+
+        let result = IronShieldChallenge::from_concat_struct(&format!("{}|rb=abcd", base));
+        assert!(result.unwrap_err().contains("Request binding must be exactly 32 bytes"));
+    }
+
     #[test]
     fn test_from_concat_struct_edge_cases() {
         // Test with all zero values
@@ -714,13 +2332,14 @@ mod tests {
         let valid_64_byte_hex = "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
         assert_eq!(valid_64_byte_hex.len(), 128, "64-byte hex string should be exactly 128 characters");
 
-        let input = format!("test_nonce|1000000|1030000|test_website|{}|0|{}|{}",
+        let input = format!("7e57a0ce|1000000|1030000|test_website|{}|0|{}|{}",
                             valid_32_byte_hex, valid_32_byte_hex, valid_64_byte_hex);
         let result = IronShieldChallenge::from_concat_struct(&input);
 
         assert!(result.is_ok(), "Should parse valid zero-value data");
         let parsed = result.unwrap();
-        assert_eq!(parsed.random_nonce, "test_nonce");
+        assert_eq!(parsed.random_nonce, "7e57a0ce");
+        assert!(IronShieldChallenge::from_concat_struct(&input.replacen("7e57a0ce", "test_nonce", 1)).unwrap_err().contains("random_nonce"));
         assert_eq!(parsed.created_time, 1000000);
         assert_eq!(parsed.expiration_time, 1030000);
         assert_eq!(parsed.website_id, "test_website");
@@ -735,13 +2354,13 @@ mod tests {
         let all_f_64_hex = "f".repeat(128);
         assert_eq!(all_f_64_hex.len(), 128, "All F's 64-byte hex string should be exactly 128 characters");
 
-        let input = format!("max_nonce|{}|{}|max_website|{}|{}|{}|{}",
-                            i64::MAX, i64::MAX, all_f_32_hex, u64::MAX, all_f_32_hex, all_f_64_hex);
+        let input = format!("{}|{}|{}|max_website|{}|{}|{}|{}",
+                            all_f_64_hex, i64::MAX, i64::MAX, all_f_32_hex, u64::MAX, all_f_32_hex, all_f_64_hex);
         let result = IronShieldChallenge::from_concat_struct(&input);
 
         assert!(result.is_ok(), "Should parse valid max-value data");
         let parsed = result.unwrap();
-        assert_eq!(parsed.random_nonce, "max_nonce");
+        assert_eq!(parsed.random_nonce, all_f_64_hex);
         assert_eq!(parsed.created_time, i64::MAX);
         assert_eq!(parsed.expiration_time, i64::MAX);
         assert_eq!(parsed.website_id, "max_website");
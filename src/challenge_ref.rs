@@ -0,0 +1,738 @@
+//! # Borrowed challenge view for the verification hot path.
+//!
+//! `ChallengeRef` borrows every field of a challenge from the buffer it
+//! was decoded from, so an edge node can check the signature, deadline
+//! and solution of a compact payload without building an owned
+//! `IronShieldChallenge`. The signed extension codec lives here and is
+//! shared with the owned type, so both always agree on the wire format.
+//!
+//! `ChallengeDto` is the serde counterpart: it deserializes from JSON
+//! (or any other serde format) borrowing `random_nonce` and
+//! `website_id` from the input buffer whenever they need no unescaping.
+
+use std::borrow::Cow;
+
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+
+use crate::challenge::{
+    deserialize_issuer_node,
+    MAX_REQUIRED_SOLUTIONS
+};
+use crate::compact::CompactReader;
+use crate::metrics::{
+    count_outcome,
+    CHALLENGE_SIGNATURES_REJECTED,
+    CHALLENGE_SIGNATURES_VERIFIED
+};
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_optional_32_bytes,
+    deserialize_signature,
+    serialize_32_bytes,
+    serialize_optional_32_bytes,
+    serialize_signature
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge,
+    RandomNonce,
+    SolutionVerifier,
+    TimeAttestation,
+    TimeLockPuzzle,
+    TimeLockPuzzleRef,
+    VdfParams,
+    VdfParamsRef,
+    VrfNonceProof,
+    WebsiteId
+};
+
+/// Extension key for `submission_deadline` in the concatenated format.
+const EXT_SUBMISSION_DEADLINE: &str = "sd";
+/// Extension key for `request_binding` in the concatenated format.
+const EXT_REQUEST_BINDING:     &str = "rb";
+/// Extension key for `issuer_node` in the concatenated format.
+const EXT_ISSUER_NODE:         &str = "in";
+/// Extension key for `min_age_ms` in the concatenated format.
+const EXT_MIN_AGE:             &str = "ma";
+/// Extension key for `issuance_context` in the concatenated format.
+const EXT_ISSUANCE_CONTEXT:    &str = "ic";
+/// Extension key for `nonce_proof` in the concatenated format.
+const EXT_NONCE_PROOF:         &str = "vn";
+/// Extension key for `time_attestation` in the concatenated format.
+const EXT_TIME_ATTESTATION:    &str = "ta";
+/// Extension key for `required_solutions` in the concatenated format.
+const EXT_REQUIRED_SOLUTIONS:  &str = "ks";
+/// Extension key for `time_lock` in the concatenated format.
+const EXT_TIME_LOCK:           &str = "tl";
+/// Extension key for `vdf` in the concatenated format.
+const EXT_VDF:                 &str = "vd";
+
+/// Borrowed view of an `IronShieldChallenge`.
+///
+/// Fields mirror `IronShieldChallenge`; see there for their meaning.
+/// Strings and byte arrays are borrowed, the rest is copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChallengeRef<'a> {
+    pub random_nonce:         &'a str,
+    pub created_time:         i64,
+    pub expiration_time:      i64,
+    pub website_id:           &'a str,
+    pub challenge_param:      &'a [u8; 32],
+    pub recommended_attempts: u64,
+    pub public_key:           &'a [u8; 32],
+    pub challenge_signature:  &'a [u8; 64],
+    pub submission_deadline:  Option<i64>,
+    pub request_binding:      Option<[u8; 32]>,
+    pub issuer_node:          Option<&'a str>,
+    pub min_age_ms:           Option<i64>,
+    pub issuance_context:     Option<[u8; 32]>,
+    pub nonce_proof:          Option<VrfNonceProof>,
+    pub time_attestation:     Option<TimeAttestation>,
+    pub required_solutions:   Option<u8>,
+    pub time_lock:            Option<TimeLockPuzzleRef<'a>>,
+    pub vdf:                  Option<VdfParamsRef<'a>>,
+}
+
+impl<'a> ChallengeRef<'a> {
+    /// Decodes a challenge produced by
+    /// `IronShieldChallenge::to_compact_bytes()` without copying its
+    /// strings or byte arrays.
+    ///
+    /// # Arguments
+    /// * `bytes`: The compact binary payload.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The view or an error describing the
+    ///                           malformed field.
+    pub fn from_compact_bytes(bytes: &'a [u8]) -> Result<Self, String> {
+        let mut reader = CompactReader::new(bytes)?;
+        let challenge: Self = Self::read_compact(&mut reader)?;
+        reader.finish()?;
+        Ok(challenge)
+    }
+
+    /// Reads the challenge fields from an open compact payload.
+    pub(crate) fn read_compact(reader: &mut CompactReader<'a>) -> Result<Self, String> {
+        let mut challenge = Self {
+            random_nonce:         reader.read_str("random_nonce")?,
+            created_time:         reader.read_i64("created_time")?,
+            expiration_time:      reader.read_i64("expiration_time")?,
+            website_id:           reader.read_str("website_id")?,
+            challenge_param:      reader.read_array_ref::<32>("challenge_param")?,
+            recommended_attempts: reader.read_u64("recommended_attempts")?,
+            public_key:           reader.read_array_ref::<32>("public_key")?,
+            challenge_signature:  reader.read_array_ref::<64>("challenge_signature")?,
+            submission_deadline:  None,
+            request_binding:      None,
+            issuer_node:          None,
+            min_age_ms:           None,
+            issuance_context:     None,
+            nonce_proof:          None,
+            time_attestation:     None,
+            required_solutions:   None,
+            time_lock:            None,
+            vdf:                  None,
+        };
+
+        let extension_count: u8 = reader.read_u8("extension count")?;
+        for _ in 0..extension_count {
+            let part: &'a str = reader.read_str("extension")?;
+            challenge.apply_extension_part(part)?;
+        }
+
+        Ok(challenge)
+    }
+
+    /// # Returns
+    /// * `Result<IronShieldChallenge, String>`: An owned copy of the
+    ///                                          challenge, or an error if
+    ///                                          `random_nonce` or
+    ///                                          `website_id` is malformed.
+    pub fn to_owned_challenge(&self) -> Result<IronShieldChallenge, String> {
+        Ok(IronShieldChallenge {
+            random_nonce:         RandomNonce::try_from(self.random_nonce)?,
+            created_time:         self.created_time,
+            expiration_time:      self.expiration_time,
+            website_id:           WebsiteId::verbatim(self.website_id)?,
+            challenge_param:      *self.challenge_param,
+            recommended_attempts: self.recommended_attempts,
+            public_key:           *self.public_key,
+            challenge_signature:  *self.challenge_signature,
+            submission_deadline:  self.submission_deadline,
+            request_binding:      self.request_binding,
+            issuer_node:          self.issuer_node.map(str::to_string),
+            min_age_ms:           self.min_age_ms,
+            issuance_context:     self.issuance_context,
+            nonce_proof:          self.nonce_proof,
+            time_attestation:     self.time_attestation,
+            required_solutions:   self.required_solutions,
+            time_lock:            self.time_lock.map(|puzzle| puzzle.to_owned_puzzle()),
+            vdf:                  self.vdf.map(|params| params.to_owned_params()),
+        })
+    }
+
+    /// Creates the message covered by `challenge_signature`.
+    ///
+    /// See `IronShieldChallenge::signing_message`.
+    ///
+    /// # Returns
+    /// * `String`: Canonical string representation for signing.
+    pub fn signing_message(&self) -> String {
+        let mut message: String = crate::crypto::create_signing_message(
+            self.random_nonce,
+            self.created_time,
+            self.expiration_time,
+            self.website_id,
+            self.challenge_param,
+            self.public_key
+        );
+
+        for extension in self.extension_parts() {
+            message.push('|');
+            message.push_str(&extension);
+        }
+
+        message
+    }
+
+    /// Verifies the challenge signature using a provided public key.
+    ///
+    /// # Arguments
+    /// * `public_key_bytes`: The Ed25519 public key bytes to use for verification.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify_signature_with_key(&self, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+
+        self.verify_signature(&verifying_key)
+    }
+
+    /// Verifies the challenge signature against an already decoded key.
+    ///
+    /// # Arguments
+    /// * `verifying_key`: The Ed25519 public key to use for verification.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify_signature(&self, verifying_key: &VerifyingKey) -> Result<(), CryptoError> {
+        self.validate().map_err(|e| CryptoError::VerificationFailed(format!("Invalid challenge: {}", e).into()))?;
+
+        let message: String = self.signing_message();
+        let signature: Signature = Signature::from_bytes(self.challenge_signature);
+
+        count_outcome(
+            verifying_key.verify(message.as_bytes(), &signature),
+            CHALLENGE_SIGNATURES_VERIFIED,
+            CHALLENGE_SIGNATURES_REJECTED,
+        ).map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Signature verification failed: {}", e), e)))?;
+
+        Ok(())
+    }
+
+    /// Checks the fields of a challenge received from untrusted input.
+    ///
+    /// See `IronShieldChallenge::validate`.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid field.
+    pub fn validate(&self) -> Result<(), String> {
+        RandomNonce::validate_str(self.random_nonce)?;
+        WebsiteId::validate_str(self.website_id)?;
+        if self.created_time < 0 || self.expiration_time <= self.created_time {
+            return Err("expiration_time must be after a non-negative created_time".to_string());
+        }
+        if self.submission_deadline.is_some_and(|deadline| deadline < self.created_time) {
+            return Err("submission_deadline must not precede created_time".to_string());
+        }
+        if let Some(issuer_node) = self.issuer_node {
+            IronShieldChallenge::validate_issuer_node(issuer_node)?;
+        }
+        if let Some(min_age_ms) = self.min_age_ms {
+            if min_age_ms <= 0 {
+                return Err("min_age_ms must be positive".to_string());
+            }
+            if self.created_time.saturating_add(min_age_ms) > self.effective_submission_deadline() {
+                return Err("min_age_ms ends after the submission deadline".to_string());
+            }
+        }
+        if self.required_solutions.is_some_and(|required| !(2..=MAX_REQUIRED_SOLUTIONS).contains(&required)) {
+            return Err(format!("required_solutions must be between 2 and {}", MAX_REQUIRED_SOLUTIONS));
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `i64`: The last instant (unix millis) at which a solution
+    ///          is accepted: `submission_deadline` when present,
+    ///          otherwise `expiration_time`.
+    pub fn effective_submission_deadline(&self) -> i64 {
+        self.submission_deadline.unwrap_or(self.expiration_time)
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if a solution submitted at `now` is
+    ///           still accepted, `false` otherwise.
+    pub fn accepts_submission_at(&self, now: i64) -> bool {
+        now <= self.effective_submission_deadline()
+    }
+
+    /// Checks a proof-of-work solution.
+    ///
+    /// See `IronShieldChallenge::check_solution`.
+    ///
+    /// # Arguments
+    /// * `nonce`: The candidate solution.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the solution meets the target.
+    pub fn check_solution(&self, nonce: i64) -> Result<(), String> {
+        SolutionVerifier::from_parts(self.random_nonce, self.challenge_param)?.verify(nonce)
+    }
+
+    /// Builds the `key=value` parts for every present optional extension,
+    /// in canonical order. Values are escaped with `escape_extension_value`,
+    /// so a value can never be read back as further parts.
+    pub(crate) fn extension_parts(&self) -> Vec<String> {
+        let mut parts: Vec<String> = Vec::new();
+        let mut push = |key: &str, value: &str| {
+            parts.push(format!("{}={}", key, escape_extension_value(value)));
+        };
+
+        if let Some(deadline) = self.submission_deadline {
+            push(EXT_SUBMISSION_DEADLINE, &deadline.to_string());
+        }
+        if let Some(binding) = self.request_binding {
+            push(EXT_REQUEST_BINDING, &hex::encode(binding));
+        }
+        if let Some(issuer_node) = self.issuer_node {
+            push(EXT_ISSUER_NODE, issuer_node);
+        }
+        if let Some(min_age_ms) = self.min_age_ms {
+            push(EXT_MIN_AGE, &min_age_ms.to_string());
+        }
+        if let Some(commitment) = self.issuance_context {
+            push(EXT_ISSUANCE_CONTEXT, &hex::encode(commitment));
+        }
+        if let Some(proof) = self.nonce_proof {
+            push(EXT_NONCE_PROOF, &proof.to_wire_string());
+        }
+        if let Some(attestation) = self.time_attestation {
+            push(EXT_TIME_ATTESTATION, &attestation.to_wire_string());
+        }
+        if let Some(required) = self.required_solutions {
+            push(EXT_REQUIRED_SOLUTIONS, &required.to_string());
+        }
+        if let Some(puzzle) = self.time_lock {
+            push(EXT_TIME_LOCK, &puzzle.to_wire_string());
+        }
+        if let Some(params) = self.vdf {
+            push(EXT_VDF, &params.to_wire_string());
+        }
+
+        parts
+    }
+
+    /// Applies a single `key=value` extension part parsed from the
+    /// concatenated or compact format.
+    pub(crate) fn apply_extension_part(&mut self, part: &'a str) -> Result<(), String> {
+        let (key, value) = part.split_once('=')
+            .ok_or_else(|| format!("Malformed challenge extension: {}", part))?;
+
+        match key {
+            EXT_SUBMISSION_DEADLINE => {
+                if self.submission_deadline.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                let deadline: i64 = value.parse::<i64>()
+                    .map_err(|_| "Failed to parse submission_deadline as i64")?;
+                self.submission_deadline = Some(deadline);
+            }
+            EXT_REQUEST_BINDING => {
+                if self.request_binding.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                let mut binding: [u8; 32] = [0u8; 32];
+                hex::decode_to_slice(value, &mut binding).map_err(|e: hex::FromHexError| match e {
+                    hex::FromHexError::InvalidStringLength => "Request binding must be exactly 32 bytes",
+                    _                                      => "Failed to decode request_binding hex string",
+                })?;
+                self.request_binding = Some(binding);
+            }
+            EXT_ISSUER_NODE => {
+                if self.issuer_node.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                IronShieldChallenge::validate_issuer_node(value)?;
+                self.issuer_node = Some(value);
+            }
+            EXT_MIN_AGE => {
+                if self.min_age_ms.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                let min_age_ms: i64 = value.parse::<i64>()
+                    .map_err(|_| "Failed to parse min_age_ms as i64")?;
+                self.min_age_ms = Some(min_age_ms);
+            }
+            EXT_ISSUANCE_CONTEXT => {
+                if self.issuance_context.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                let mut commitment: [u8; 32] = [0u8; 32];
+                hex::decode_to_slice(value, &mut commitment).map_err(|e: hex::FromHexError| match e {
+                    hex::FromHexError::InvalidStringLength => "Issuance context commitment must be exactly 32 bytes",
+                    _                                      => "Failed to decode issuance_context hex string",
+                })?;
+                self.issuance_context = Some(commitment);
+            }
+            EXT_NONCE_PROOF => {
+                if self.nonce_proof.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                self.nonce_proof = Some(VrfNonceProof::from_wire_string(value)?);
+            }
+            EXT_TIME_ATTESTATION => {
+                if self.time_attestation.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                self.time_attestation = Some(TimeAttestation::from_wire_string(value)?);
+            }
+            EXT_REQUIRED_SOLUTIONS => {
+                if self.required_solutions.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                let required: u8 = value.parse::<u8>()
+                    .map_err(|_| "Failed to parse required_solutions as u8")?;
+                self.required_solutions = Some(required);
+            }
+            EXT_TIME_LOCK => {
+                if self.time_lock.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                self.time_lock = Some(TimeLockPuzzleRef::from_wire_string(value)?);
+            }
+            EXT_VDF => {
+                if self.vdf.is_some() {
+                    return Err(format!("Duplicate challenge extension: {}", key));
+                }
+                self.vdf = Some(VdfParamsRef::from_wire_string(value)?);
+            }
+            _ => return Err(format!("Unknown challenge extension: {}", key)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes `%` and `|` in an extension value as `%25` and `%7C`.
+///
+/// Extension parts are joined with `|`, so without escaping a value
+/// containing `|key=value` would sign the same bytes as a challenge
+/// carrying that extension. Valid values contain neither character and
+/// are returned unchanged, so existing signatures stay valid; values
+/// that needed escaping fail validation when parsed back.
+///
+/// # Arguments
+/// * `value`: The extension value.
+///
+/// # Returns
+/// * `Cow<str>`: The escaped value.
+fn escape_extension_value(value: &str) -> Cow<'_, str> {
+    if value.contains(['%', '|']) {
+        Cow::Owned(value.replace('%', "%25").replace('|', "%7C"))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+impl<'a> From<&'a IronShieldChallenge> for ChallengeRef<'a> {
+    fn from(challenge: &'a IronShieldChallenge) -> Self {
+        challenge.as_challenge_ref()
+    }
+}
+
+/// Serde form of an `IronShieldChallenge` that borrows its strings.
+///
+/// Has the same serialized shape as `IronShieldChallenge`. Deserializing
+/// from a buffer that outlives the value borrows `random_nonce` and
+/// `website_id` instead of copying them; strings containing escape
+/// sequences fall back to owned copies.
+///
+/// # Example
+#[cfg_attr(feature = "rng", doc = " ```")]
+#[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
+/// use ironshield_types::{ChallengeDto, IronShieldChallenge, SigningKey};
+/// let key = SigningKey::from_bytes(&[1; 32]);
+/// let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+/// let json: String = serde_json::to_string(&challenge).unwrap();
+///
+/// let dto: ChallengeDto = serde_json::from_str(&json).unwrap();
+/// assert!(dto.as_challenge_ref().verify_signature_with_key(&key.verifying_key().to_bytes()).is_ok());
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeDto<'a> {
+    #[serde(borrow)]
+    pub random_nonce:         Cow<'a, str>,
+    pub created_time:         i64,
+    pub expiration_time:      i64,
+    #[serde(borrow)]
+    pub website_id:           Cow<'a, str>,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub challenge_param:      [u8; 32],
+    pub recommended_attempts: u64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub public_key:           [u8; 32],
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub challenge_signature:  [u8; 64],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submission_deadline:  Option<i64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_32_bytes",
+        deserialize_with = "deserialize_optional_32_bytes"
+    )]
+    pub request_binding:      Option<[u8; 32]>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_issuer_node"
+    )]
+    pub issuer_node:          Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age_ms:           Option<i64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_32_bytes",
+        deserialize_with = "deserialize_optional_32_bytes"
+    )]
+    pub issuance_context:     Option<[u8; 32]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce_proof:          Option<VrfNonceProof>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_attestation:     Option<TimeAttestation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_solutions:   Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_lock:            Option<TimeLockPuzzle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vdf:                  Option<VdfParams>,
+}
+
+impl ChallengeDto<'_> {
+    /// # Returns
+    /// * `ChallengeRef`: A view borrowing every field of the DTO, for
+    ///                   verification without an owned challenge.
+    pub fn as_challenge_ref(&self) -> ChallengeRef<'_> {
+        ChallengeRef {
+            random_nonce:         &self.random_nonce,
+            created_time:         self.created_time,
+            expiration_time:      self.expiration_time,
+            website_id:           &self.website_id,
+            challenge_param:      &self.challenge_param,
+            recommended_attempts: self.recommended_attempts,
+            public_key:           &self.public_key,
+            challenge_signature:  &self.challenge_signature,
+            submission_deadline:  self.submission_deadline,
+            request_binding:      self.request_binding,
+            issuer_node:          self.issuer_node.as_deref(),
+            min_age_ms:           self.min_age_ms,
+            issuance_context:     self.issuance_context,
+            nonce_proof:          self.nonce_proof,
+            time_attestation:     self.time_attestation,
+            required_solutions:   self.required_solutions,
+            time_lock:            self.time_lock.as_ref().map(TimeLockPuzzle::as_puzzle_ref),
+            vdf:                  self.vdf.as_ref().map(VdfParams::as_params_ref),
+        }
+    }
+
+    /// # Returns
+    /// * `Result<IronShieldChallenge, String>`: The owned challenge, or an
+    ///                                          error if `random_nonce` or
+    ///                                          `website_id` is malformed.
+    ///                                          Strings that were
+    ///                                          already owned are moved,
+    ///                                          not copied.
+    pub fn into_owned_challenge(self) -> Result<IronShieldChallenge, String> {
+        Ok(IronShieldChallenge {
+            random_nonce:         RandomNonce::try_from(self.random_nonce.into_owned())?,
+            created_time:         self.created_time,
+            expiration_time:      self.expiration_time,
+            website_id:           WebsiteId::verbatim(self.website_id)?,
+            challenge_param:      self.challenge_param,
+            recommended_attempts: self.recommended_attempts,
+            public_key:           self.public_key,
+            challenge_signature:  self.challenge_signature,
+            submission_deadline:  self.submission_deadline,
+            request_binding:      self.request_binding,
+            issuer_node:          self.issuer_node,
+            min_age_ms:           self.min_age_ms,
+            issuance_context:     self.issuance_context,
+            nonce_proof:          self.nonce_proof,
+            time_attestation:     self.time_attestation,
+            required_solutions:   self.required_solutions,
+            time_lock:            self.time_lock,
+            vdf:                  self.vdf,
+        })
+    }
+}
+
+impl<'a> From<&'a IronShieldChallenge> for ChallengeDto<'a> {
+    fn from(challenge: &'a IronShieldChallenge) -> Self {
+        Self {
+            random_nonce:         Cow::Borrowed(challenge.random_nonce.as_str()),
+            created_time:         challenge.created_time,
+            expiration_time:      challenge.expiration_time,
+            website_id:           Cow::Borrowed(&challenge.website_id),
+            challenge_param:      challenge.challenge_param,
+            recommended_attempts: challenge.recommended_attempts,
+            public_key:           challenge.public_key,
+            challenge_signature:  challenge.challenge_signature,
+            submission_deadline:  challenge.submission_deadline,
+            request_binding:      challenge.request_binding,
+            issuer_node:          challenge.issuer_node.clone(),
+            min_age_ms:           challenge.min_age_ms,
+            issuance_context:     challenge.issuance_context,
+            nonce_proof:          challenge.nonce_proof,
+            time_attestation:     challenge.time_attestation,
+            required_solutions:   challenge.required_solutions,
+            time_lock:            challenge.time_lock.clone(),
+            vdf:                  challenge.vdf.clone(),
+        }
+    }
+}
+
+impl TryFrom<ChallengeDto<'_>> for IronShieldChallenge {
+    type Error = String;
+
+    fn try_from(dto: ChallengeDto<'_>) -> Result<Self, Self::Error> {
+        dto.into_owned_challenge()
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    fn extended_challenge() -> (IronShieldChallenge, SigningKey) {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let mut challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 10, key.clone(), key.verifying_key().to_bytes());
+        challenge.submission_deadline = Some(challenge.expiration_time - 1_000);
+        challenge.request_binding = Some([7; 32]);
+        challenge = challenge.with_issuer_node("edge-1", &key).unwrap();
+        (challenge, key)
+    }
+
+    #[test]
+    fn test_borrowed_compact_view_matches_owned() {
+        let (challenge, key) = extended_challenge();
+        let bytes: Vec<u8> = challenge.to_compact_bytes().unwrap();
+
+        let view = ChallengeRef::from_compact_bytes(&bytes).unwrap();
+        assert_eq!(view, challenge.as_challenge_ref());
+        assert_eq!(view.to_owned_challenge().unwrap().concat_struct(), challenge.concat_struct());
+        assert_eq!(view.issuer_node, Some("edge-1"));
+        assert_eq!(view.signing_message(), challenge.signing_message());
+        view.verify_signature_with_key(&key.verifying_key().to_bytes()).unwrap();
+        assert!(view.verify_signature_with_key(&[0; 32]).is_err());
+        assert!(view.accepts_submission_at(challenge.expiration_time - 1_000));
+        assert!(!view.accepts_submission_at(challenge.expiration_time));
+    }
+
+    #[test]
+    fn test_check_solution_matches_owned() {
+        let (challenge, _) = extended_challenge();
+        let view = ChallengeRef::from(&challenge);
+        for nonce in 0i64..64 {
+            assert_eq!(view.check_solution(nonce), challenge.check_solution(nonce));
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_extensions() {
+        let (challenge, _) = extended_challenge();
+        let mut view = challenge.as_challenge_ref();
+        assert!(view.apply_extension_part("sd=1").unwrap_err().contains("Duplicate"));
+        assert!(view.apply_extension_part("xx=1").unwrap_err().contains("Unknown"));
+
+        view.request_binding = None;
+        assert!(view.apply_extension_part("rb=abcd").is_err());
+    }
+
+    #[test]
+    fn test_issuer_node_cannot_absorb_signed_extensions() {
+        let (mut challenge, key) = extended_challenge();
+        challenge.min_age_ms = Some(5_000);
+        challenge.required_solutions = Some(4);
+        challenge.sign(&key);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+
+        // Move `|ma=..|ks=..` into issuer_node to drop the wait and k-of-n.
+        let mut forged: IronShieldChallenge = challenge.clone();
+        forged.issuer_node = Some("edge-1|ma=5000|ks=4".to_string());
+        forged.min_age_ms = None;
+        forged.required_solutions = None;
+        assert_ne!(forged.signing_message(), challenge.signing_message());
+        assert!(forged.as_challenge_ref().verify_signature_with_key(&public_key).is_err());
+        assert!(crate::verify_challenge_signature_with_key(&forged, &public_key).is_err());
+
+        let json: String = serde_json::to_string(&forged).unwrap();
+        assert!(serde_json::from_str::<IronShieldChallenge>(&json).unwrap_err().to_string().contains("issuer_node"));
+        assert!(serde_json::from_str::<ChallengeDto>(&json).is_err());
+        assert!(IronShieldChallenge::from_concat_struct(&forged.concat_struct()).is_err());
+        crate::verify_challenge_signature_with_key(&challenge, &public_key).unwrap();
+    }
+
+    #[test]
+    fn test_dto_borrows_from_json() {
+        let (challenge, key) = extended_challenge();
+        let json: String = serde_json::to_string(&challenge).unwrap();
+        assert_eq!(serde_json::to_string(&ChallengeDto::from(&challenge)).unwrap(), json);
+
+        let dto: ChallengeDto = serde_json::from_str(&json).unwrap();
+        assert!(matches!(dto.random_nonce, Cow::Borrowed(_)));
+        assert!(matches!(dto.website_id, Cow::Borrowed(_)));
+        assert_eq!(dto.as_challenge_ref(), challenge.as_challenge_ref());
+        dto.as_challenge_ref().verify_signature_with_key(&key.verifying_key().to_bytes()).unwrap();
+
+        let owned = IronShieldChallenge::try_from(dto).unwrap();
+        assert_eq!(owned.concat_struct(), challenge.concat_struct());
+    }
+
+    #[test]
+    fn test_dto_falls_back_to_owned_for_escaped_strings() {
+        let (mut challenge, _) = extended_challenge();
+        challenge.website_id = WebsiteId::verbatim("example.com/\"quoted\"").unwrap();
+        let json: String = serde_json::to_string(&challenge).unwrap();
+
+        let dto: ChallengeDto = serde_json::from_str(&json).unwrap();
+        assert!(matches!(dto.website_id, Cow::Owned(_)));
+        assert_eq!(dto.website_id, challenge.website_id.as_str());
+    }
+}
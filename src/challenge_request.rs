@@ -0,0 +1,377 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    ClientCapabilities,
+    PowAlgorithm
+};
+
+#[cfg(feature = "openapi")]
+#[allow(unused_imports)]
+use serde_json::json;
+
+/// Coarse class of the device requesting a challenge.
+///
+/// Used by the issuer as a hint when picking a difficulty,
+/// since a low-end phone solves far fewer hashes per second
+/// than a desktop browser.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceClass {
+    /// No hint was provided by the client.
+    #[default]
+    Unknown,
+    /// Desktop or laptop browser.
+    Desktop,
+    /// Phone or other battery-powered handheld.
+    Mobile,
+    /// Tablet-class device.
+    Tablet,
+    /// Headless or embedded client (CLI, IoT, SDK).
+    Embedded,
+}
+
+impl DeviceClass {
+    /// # Returns
+    /// * `&'static str`: The stable wire identifier of the device class.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceClass::Unknown  => "unknown",
+            DeviceClass::Desktop  => "desktop",
+            DeviceClass::Mobile   => "mobile",
+            DeviceClass::Tablet   => "tablet",
+            DeviceClass::Embedded => "embedded",
+        }
+    }
+}
+
+impl std::fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DeviceClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unknown"  => Ok(DeviceClass::Unknown),
+            "desktop"  => Ok(DeviceClass::Desktop),
+            "mobile"   => Ok(DeviceClass::Mobile),
+            "tablet"   => Ok(DeviceClass::Tablet),
+            "embedded" => Ok(DeviceClass::Embedded),
+            other => Err(format!("Unknown device class: {}", other)),
+        }
+    }
+}
+
+/// IronShield Challenge Request structure
+///
+/// Sent by the client when it asks the issuer for a new challenge.
+///
+/// * `website_id`:        The identifier of the website being accessed.
+/// * `algorithm`:         The proof-of-work algorithm the client wants to solve.
+/// * `device_class`:      A coarse device-class hint for difficulty selection.
+/// * `client_public_key`: Optional Ed25519 public key of the client.
+/// * `capabilities`:      Optional description of the client's solver.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Client request for a new IronShield proof-of-work challenge"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IronShieldChallengeRequest {
+    /// The website or endpoint identifier the challenge is requested for
+    #[cfg_attr(feature = "openapi", schema(example = "https://example.com"))]
+    pub website_id:        String,
+    /// The proof-of-work algorithm the client would like to solve
+    #[serde(default)]
+    pub algorithm:         PowAlgorithm,
+    /// Device-class hint used by the issuer to pick a difficulty
+    #[serde(default)]
+    pub device_class:      DeviceClass,
+    /// Optional Ed25519 public key of the client (32 bytes)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(example = json!([71, 15, 1, 1, 7, 64, 28, 152, 78, 88, 44, 175, 57, 103, 175, 203, 107, 65, 139, 247, 54, 246, 169, 209, 116, 166, 25, 71, 174, 193, 66, 191])))]
+    pub client_public_key: Option<[u8; 32]>,
+    /// What the client's solver supports, for algorithm and difficulty negotiation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities:      Option<ClientCapabilities>,
+}
+
+impl IronShieldChallengeRequest {
+    /// Constructor for creating a new `IronShieldChallengeRequest` instance.
+    ///
+    /// # Arguments
+    /// * `website_id`:        The identifier of the website.
+    /// * `algorithm`:         The desired proof-of-work algorithm.
+    /// * `device_class`:      The device-class hint.
+    /// * `client_public_key`: Optional Ed25519 public key of the client.
+    ///
+    /// # Returns
+    /// * `Self`:              A new challenge request.
+    pub fn new(
+        website_id:        String,
+        algorithm:         PowAlgorithm,
+        device_class:      DeviceClass,
+        client_public_key: Option<[u8; 32]>,
+    ) -> Self {
+        Self {
+            website_id,
+            algorithm,
+            device_class,
+            client_public_key,
+            capabilities: None,
+        }
+    }
+
+    /// Attaches the client's solver capabilities to the request.
+    ///
+    /// # Arguments
+    /// * `capabilities`: What the client's solver supports.
+    ///
+    /// # Returns
+    /// * `Self`: The request carrying the capabilities.
+    pub fn with_capabilities(mut self, capabilities: ClientCapabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Concatenates the request data into a string.
+    ///
+    /// Concatenates:
+    /// * `website_id`:        as a string.
+    /// * `algorithm`:         as its wire identifier.
+    /// * `device_class`:      as its wire identifier.
+    /// * `client_public_key`: as a lowercase hex string,
+    ///                        or empty when absent.
+    /// * `capabilities`:      in `ClientCapabilities::to_wire_string()`
+    ///                        form, only when present.
+    pub fn concat_struct(&self) -> String {
+        let mut concat: String = format!(
+            "{}|{}|{}|{}",
+            self.website_id,
+            self.algorithm,
+            self.device_class,
+            self.client_public_key.map(hex::encode).unwrap_or_default()
+        );
+
+        if let Some(capabilities) = &self.capabilities {
+            concat.push('|');
+            concat.push_str(&capabilities.to_wire_string());
+        }
+
+        concat
+    }
+
+    /// Creates an `IronShieldChallengeRequest` from a concatenated string.
+    ///
+    /// This function reverses the operation of
+    /// `IronShieldChallengeRequest::concat_struct`.
+    /// Expects a string in the format:
+    /// "website_id|algorithm|device_class|client_public_key",
+    /// optionally followed by "|capabilities".
+    ///
+    /// # Arguments
+    /// * `concat_str`: The concatenated string to parse, typically
+    ///                 generated by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: A result containing the parsed
+    ///                           `IronShieldChallengeRequest` or an
+    ///                           error message if parsing fails.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+
+        if parts.len() != 4 && parts.len() != 5 {
+            return Err(format!("Expected 4 or 5 parts, got {}", parts.len()));
+        }
+
+        let website_id: String = parts[0].to_string();
+        if website_id.is_empty() {
+            return Err("website_id cannot be empty".to_string());
+        }
+
+        let algorithm: PowAlgorithm = parts[1].parse()?;
+        let device_class: DeviceClass = parts[2].parse()?;
+
+        let client_public_key: Option<[u8; 32]> = if parts[3].is_empty() {
+            None
+        } else {
+            let key_bytes: Vec<u8> = hex::decode(parts[3])
+                .map_err(|_| "Failed to decode client_public_key hex string")?;
+            let key: [u8; 32] = key_bytes.try_into()
+                .map_err(|_| "Client public key must be exactly 32 bytes")?;
+            Some(key)
+        };
+
+        let capabilities: Option<ClientCapabilities> = parts.get(4)
+            .map(|wire| ClientCapabilities::from_wire_string(wire))
+            .transpose()?;
+
+        Ok(Self {
+            website_id,
+            algorithm,
+            device_class,
+            client_public_key,
+            capabilities,
+        })
+    }
+
+    /// Encodes the request as a base64url string for HTTP header transport.
+    ///
+    /// # Returns
+    /// * `String`: Base64url-encoded string ready for HTTP header use.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::{IronShieldChallengeRequest, PowAlgorithm, DeviceClass};
+    /// let request = IronShieldChallengeRequest::new(
+    ///     "example.com".to_string(),
+    ///     PowAlgorithm::Sha256,
+    ///     DeviceClass::Mobile,
+    ///     None,
+    /// );
+    /// let header_value = request.to_base64url_header();
+    /// // Use header_value in HTTP header: "X-IronShield-Challenge-Request: {header_value}"
+    /// ```
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// Decodes a base64url-encoded challenge request from an HTTP header.
+    ///
+    /// # Arguments
+    /// * `encoded_header`: The base64url-encoded string from the HTTP header.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded request or detailed error message.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::{IronShieldChallengeRequest, PowAlgorithm, DeviceClass};
+    /// let original = IronShieldChallengeRequest::new(
+    ///     "example.com".to_string(),
+    ///     PowAlgorithm::Sha256,
+    ///     DeviceClass::Desktop,
+    ///     Some([0x12; 32]),
+    /// );
+    /// let header_value = original.to_base64url_header();
+    /// let decoded = IronShieldChallengeRequest::from_base64url_header(&header_value).unwrap();
+    /// assert_eq!(original, decoded);
+    /// ```
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        // Decode using the existing serde_utils function.
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+
+        // Parse using the existing concat_struct format.
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat_struct_roundtrip_with_key() {
+        let original = IronShieldChallengeRequest::new(
+            "example.com".to_string(),
+            PowAlgorithm::Sha256,
+            DeviceClass::Mobile,
+            Some([0xAB; 32]),
+        );
+
+        let concat: String = original.concat_struct();
+        assert_eq!(concat, format!("example.com|sha256|mobile|{}", "ab".repeat(32)));
+
+        let parsed = IronShieldChallengeRequest::from_concat_struct(&concat).unwrap();
+        assert_eq!(original, parsed);
+    }
+
+    #[test]
+    fn test_concat_struct_roundtrip_without_key() {
+        let original = IronShieldChallengeRequest::new(
+            "example.com".to_string(),
+            PowAlgorithm::Sha256,
+            DeviceClass::Unknown,
+            None,
+        );
+
+        let concat: String = original.concat_struct();
+        assert!(concat.ends_with("|unknown|"));
+
+        let parsed = IronShieldChallengeRequest::from_concat_struct(&concat).unwrap();
+        assert_eq!(parsed.client_public_key, None);
+    }
+
+    #[test]
+    fn test_base64url_header_roundtrip() {
+        let original = IronShieldChallengeRequest::new(
+            "example.com".to_string(),
+            PowAlgorithm::Sha256,
+            DeviceClass::Tablet,
+            Some([0x01; 32]),
+        );
+
+        let encoded: String = original.to_base64url_header();
+        let decoded = IronShieldChallengeRequest::from_base64url_header(&encoded).unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_from_concat_struct_error_cases() {
+        let result = IronShieldChallengeRequest::from_concat_struct("example.com|sha256");
+        assert!(result.unwrap_err().contains("Expected 4 or 5 parts, got 2"));
+
+        let result = IronShieldChallengeRequest::from_concat_struct("|sha256|mobile|");
+        assert!(result.unwrap_err().contains("website_id cannot be empty"));
+
+        let result = IronShieldChallengeRequest::from_concat_struct("example.com|md5|mobile|");
+        assert!(result.unwrap_err().contains("Unknown proof-of-work algorithm"));
+
+        let result = IronShieldChallengeRequest::from_concat_struct("example.com|sha256|watch|");
+        assert!(result.unwrap_err().contains("Unknown device class"));
+
+        let result = IronShieldChallengeRequest::from_concat_struct("example.com|sha256|mobile|abcd");
+        assert!(result.unwrap_err().contains("Client public key must be exactly 32 bytes"));
+    }
+
+    #[test]
+    fn test_capabilities_roundtrip() {
+        let capabilities = ClientCapabilities {
+            algorithms:          vec![PowAlgorithm::Sha256],
+            wasm_simd:           true,
+            wasm_threads:        false,
+            verifies_signatures: false,
+            constrained:         false,
+        };
+        let original = IronShieldChallengeRequest::new(
+            "example.com".to_string(),
+            PowAlgorithm::Sha256,
+            DeviceClass::Desktop,
+            None,
+        ).with_capabilities(capabilities);
+
+        assert!(original.concat_struct().ends_with("|desktop||sha256;simd"));
+        let decoded = IronShieldChallengeRequest::from_base64url_header(&original.to_base64url_header()).unwrap();
+        assert_eq!(original, decoded);
+
+        let result = IronShieldChallengeRequest::from_concat_struct("example.com|sha256|mobile||sha256;1024;simd");
+        assert!(result.unwrap_err().contains("Expected 2 capability fields"));
+    }
+
+    #[test]
+    fn test_json_defaults_for_optional_fields() {
+        let parsed: IronShieldChallengeRequest =
+            serde_json::from_str(r#"{"website_id":"example.com"}"#).unwrap();
+
+        assert_eq!(parsed.algorithm, PowAlgorithm::Sha256);
+        assert_eq!(parsed.device_class, DeviceClass::Unknown);
+        assert_eq!(parsed.client_public_key, None);
+        assert_eq!(parsed.capabilities, None);
+    }
+}
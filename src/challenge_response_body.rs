@@ -0,0 +1,173 @@
+//! # Body of a response that challenges the client.
+//!
+//! Whenever the edge answers a request with a challenge instead of the
+//! protected resource, the body is a `ChallengeResponseBody`. Browsers,
+//! SDKs and someone running `curl` all see the same JSON: why they were
+//! challenged, the challenge itself (the same value as the
+//! `X-IronShield-Challenge-Data` header, for clients that cannot read
+//! response headers), a human-readable message and where to read more.
+//!
+//! ```json
+//! {
+//!   "code": "challenge_required",
+//!   "challenge": "<base64url challenge>",
+//!   "message": "Solve the challenge to continue.",
+//!   "docs_url": "https://docs.example.com/challenges"
+//! }
+//! ```
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::IronShieldChallenge;
+
+/// Why the client was challenged; decides the HTTP status.
+///
+/// * `ChallengeRequired`: No valid token was presented (401).
+/// * `ChallengeFailed`:   The token or solution was rejected (403).
+/// * `RateLimited`:       The client is challenged harder for sending
+///                        too many requests (429).
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeResponseCode {
+    ChallengeRequired,
+    ChallengeFailed,
+    RateLimited,
+}
+
+impl ChallengeResponseCode {
+    /// # Returns
+    /// * `&'static str`: The stable wire identifier of the code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeResponseCode::ChallengeRequired => "challenge_required",
+            ChallengeResponseCode::ChallengeFailed   => "challenge_failed",
+            ChallengeResponseCode::RateLimited       => "rate_limited",
+        }
+    }
+
+    /// # Returns
+    /// * `u16`: The HTTP status the body is sent with.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ChallengeResponseCode::ChallengeRequired => 401,
+            ChallengeResponseCode::ChallengeFailed   => 403,
+            ChallengeResponseCode::RateLimited       => 429,
+        }
+    }
+}
+
+impl std::fmt::Display for ChallengeResponseCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ChallengeResponseCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "challenge_required" => Ok(ChallengeResponseCode::ChallengeRequired),
+            "challenge_failed"   => Ok(ChallengeResponseCode::ChallengeFailed),
+            "rate_limited"       => Ok(ChallengeResponseCode::RateLimited),
+            other => Err(format!("Unknown challenge response code: {}", other)),
+        }
+    }
+}
+
+/// JSON body of a 401, 403 or 429 challenge response.
+///
+/// * `code`:      Why the client was challenged.
+/// * `challenge`: The challenge as `to_base64url_header()` encodes it.
+/// * `message`:   A human-readable explanation.
+/// * `docs_url`:  Where the challenge flow is documented.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeResponseBody {
+    pub code:      ChallengeResponseCode,
+    pub challenge: String,
+    pub message:   String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url:  Option<String>,
+}
+
+impl ChallengeResponseBody {
+    /// Constructor for a body without a documentation link.
+    ///
+    /// # Arguments
+    /// * `code`:      Why the client is challenged.
+    /// * `challenge`: The challenge issued.
+    /// * `message`:   A human-readable explanation.
+    ///
+    /// # Returns
+    /// * `Self`: The body.
+    pub fn new(code: ChallengeResponseCode, challenge: &IronShieldChallenge, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            challenge: challenge.to_base64url_header(),
+            message:   message.into(),
+            docs_url:  None,
+        }
+    }
+
+    /// # Arguments
+    /// * `docs_url`: An absolute `https://` URL.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The body carrying the link, or an
+    ///                           error if the URL is not `https://`.
+    pub fn with_docs_url(mut self, docs_url: impl Into<String>) -> Result<Self, String> {
+        let docs_url: String = docs_url.into();
+        if !docs_url.starts_with("https://") || docs_url.len() == "https://".len() {
+            return Err(format!("docs_url must be an https:// URL: {}", docs_url));
+        }
+        self.docs_url = Some(docs_url);
+        Ok(self)
+    }
+
+    /// # Returns
+    /// * `u16`: The HTTP status the body is sent with.
+    pub fn status_code(&self) -> u16 {
+        self.code.status_code()
+    }
+
+    /// # Returns
+    /// * `Result<IronShieldChallenge, String>`: The decoded challenge, or
+    ///                                          an error if `challenge`
+    ///                                          is malformed.
+    pub fn decode_challenge(&self) -> Result<IronShieldChallenge, String> {
+        IronShieldChallenge::from_base64url_header(&self.challenge)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_body_roundtrip() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+        let body = ChallengeResponseBody::new(ChallengeResponseCode::RateLimited, &challenge, "Too many requests")
+            .with_docs_url("https://docs.example.com/challenges")
+            .unwrap();
+        assert_eq!(body.status_code(), 429);
+
+        let json: String = serde_json::to_string(&body).unwrap();
+        assert!(json.starts_with(r#"{"code":"rate_limited","challenge":""#));
+        assert!(json.ends_with(r#""message":"Too many requests","docs_url":"https://docs.example.com/challenges"}"#));
+        let decoded: ChallengeResponseBody = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.decode_challenge().unwrap(), challenge);
+
+        let plain = ChallengeResponseBody::new(ChallengeResponseCode::ChallengeRequired, &challenge, "");
+        assert_eq!(plain.status_code(), 401);
+        assert!(!serde_json::to_string(&plain).unwrap().contains("docs_url"));
+        assert!(plain.with_docs_url("javascript:alert(1)").is_err());
+        assert_eq!("challenge_failed".parse::<ChallengeResponseCode>().unwrap().status_code(), 403);
+    }
+}
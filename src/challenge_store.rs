@@ -0,0 +1,375 @@
+//! # Storage contract for issued challenges.
+//!
+//! An issuer has to remember the challenges it handed out until they can
+//! no longer be redeemed, and redeem each at most once. `ChallengeStore`
+//! is the contract the Worker's Durable Object and self-hosted servers
+//! implement for that; `MemoryChallengeStore` is the in-process
+//! reference implementation. `TokenConsumptionStore` is the matching
+//! contract for single-use tokens, and `NonceRegistry` the one for
+//! keeping challenge nonces unique while they can be redeemed.
+//!
+//! Challenges are keyed by `challenge_id()`. Since the identifier covers
+//! every field, looking up the challenge a client sent back also proves
+//! it was not altered. A stored challenge stays redeemable until its
+//! `effective_submission_deadline()`, inclusive.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use std::collections::HashMap;
+
+use crate::{
+    IronShieldChallenge,
+    IronShieldToken,
+    TtlCache
+};
+
+/// Storage for issued challenges.
+///
+/// Time-dependent methods take the current time in unix millis, so
+/// every implementation applies the same expiry boundary regardless of
+/// its clock source.
+pub trait ChallengeStore {
+    /// Stores an issued challenge, replacing one with the same id.
+    ///
+    /// # Arguments
+    /// * `challenge`: The issued challenge.
+    ///
+    /// # Returns
+    /// * `Result<[u8; 32], String>`: The challenge id, or a storage error.
+    fn put(&mut self, challenge: IronShieldChallenge) -> Result<[u8; 32], String>;
+
+    /// Looks up a challenge without redeeming it.
+    ///
+    /// # Arguments
+    /// * `id`:  The `challenge_id()` of the challenge.
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<Option<IronShieldChallenge>, String>`: The challenge if
+    ///   it is stored and still redeemable at `now`.
+    fn get_by_id(&self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String>;
+
+    /// Removes a challenge and returns it if it was still redeemable, so
+    /// each challenge is redeemed at most once.
+    ///
+    /// # Arguments
+    /// * `id`:  The `challenge_id()` of the challenge.
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<Option<IronShieldChallenge>, String>`: The challenge if
+    ///   it was stored and still redeemable at `now`.
+    fn take(&mut self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String>;
+
+    /// Drops every challenge that is no longer redeemable at `now`.
+    ///
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<usize, String>`: The number of challenges removed.
+    fn purge_expired(&mut self, now: i64) -> Result<usize, String>;
+}
+
+/// In-memory `ChallengeStore` backed by a bounded `TtlCache`.
+///
+/// When full, the challenges closest to their deadline are evicted
+/// first. The store serializes like the underlying cache, so a Durable
+/// Object can persist and restore it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MemoryChallengeStore {
+    cache: TtlCache<[u8; 32], IronShieldChallenge>,
+}
+
+impl MemoryChallengeStore {
+    /// Constructor for creating an empty store.
+    ///
+    /// # Arguments
+    /// * `max_entries`: Maximum number of challenges held at once
+    ///                  (at least 1).
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            cache: TtlCache::new(max_entries),
+        }
+    }
+
+    /// # Returns
+    /// * `usize`: The number of stored challenges, including expired
+    ///            ones that have not been purged yet.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if the store holds no challenges.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+impl ChallengeStore for MemoryChallengeStore {
+    fn put(&mut self, challenge: IronShieldChallenge) -> Result<[u8; 32], String> {
+        let id: [u8; 32] = challenge.challenge_id();
+        let expires_at: i64 = challenge.effective_submission_deadline();
+        self.cache.insert(id, challenge, expires_at);
+        Ok(id)
+    }
+
+    fn get_by_id(&self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        Ok(self.cache.get_at(id, now).cloned())
+    }
+
+    fn take(&mut self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        Ok(self.cache.take_at(id, now))
+    }
+
+    fn purge_expired(&mut self, now: i64) -> Result<usize, String> {
+        Ok(self.cache.sweep_at(now))
+    }
+}
+
+/// Records which single-use tokens were already redeemed.
+///
+/// Tokens are keyed by `jti()` and remembered until `valid_for`, after
+/// which they are rejected as expired anyway.
+pub trait TokenConsumptionStore {
+    /// Marks a token as used.
+    ///
+    /// # Arguments
+    /// * `token`: The presented token.
+    /// * `now`:   The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<bool, String>`: `true` if the token was valid at `now`
+    ///   and not used before, `false` if it is expired or a replay.
+    fn consume(&mut self, token: &IronShieldToken, now: i64) -> Result<bool, String>;
+
+    /// Forgets tokens that expired before `now`.
+    ///
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<usize, String>`: The number of tokens forgotten.
+    fn purge_expired(&mut self, now: i64) -> Result<usize, String>;
+}
+
+/// In-memory `TokenConsumptionStore`.
+///
+/// Unbounded on purpose: evicting a consumed token before it expires
+/// would allow replaying it. Call `purge_expired` periodically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MemoryTokenConsumptionStore {
+    /// `jti` to `valid_for` of every consumed token.
+    consumed: HashMap<String, i64>,
+}
+
+impl MemoryTokenConsumptionStore {
+    /// Constructor for creating an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Returns
+    /// * `usize`: The number of remembered tokens.
+    pub fn len(&self) -> usize {
+        self.consumed.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if no token is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.consumed.is_empty()
+    }
+}
+
+impl TokenConsumptionStore for MemoryTokenConsumptionStore {
+    fn consume(&mut self, token: &IronShieldToken, now: i64) -> Result<bool, String> {
+        if now > token.valid_for {
+            return Ok(false);
+        }
+        let jti: String = hex::encode(token.jti());
+        if self.consumed.get(&jti).is_some_and(|valid_for| now <= *valid_for) {
+            return Ok(false);
+        }
+        self.consumed.insert(jti, token.valid_for);
+        Ok(true)
+    }
+
+    fn purge_expired(&mut self, now: i64) -> Result<usize, String> {
+        let before: usize = self.consumed.len();
+        self.consumed.retain(|_, valid_for| now <= *valid_for);
+        Ok(before - self.consumed.len())
+    }
+}
+
+/// Records the random nonces of issued challenges, so that no nonce is
+/// handed out twice while a challenge carrying it can still be redeemed.
+///
+/// Nonces are keyed by their hex string and remembered until the
+/// challenge's `effective_submission_deadline()`, inclusive.
+pub trait NonceRegistry {
+    /// Registers the nonce of a challenge about to be issued.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge carrying the nonce.
+    /// * `now`:       The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<bool, String>`: `true` if the nonce was registered,
+    ///   `false` if a still redeemable challenge already uses it.
+    fn register(&mut self, challenge: &IronShieldChallenge, now: i64) -> Result<bool, String>;
+
+    /// Forgets nonces whose challenges can no longer be redeemed.
+    ///
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<usize, String>`: The number of nonces forgotten.
+    fn purge_expired(&mut self, now: i64) -> Result<usize, String>;
+}
+
+/// In-memory `NonceRegistry`.
+///
+/// Unbounded for the same reason as `MemoryTokenConsumptionStore`.
+/// Call `purge_expired` periodically.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MemoryNonceRegistry {
+    /// Nonce to the submission deadline of the challenge using it.
+    nonces: HashMap<String, i64>,
+}
+
+impl MemoryNonceRegistry {
+    /// Constructor for creating an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Returns
+    /// * `usize`: The number of remembered nonces.
+    pub fn len(&self) -> usize {
+        self.nonces.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if no nonce is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.nonces.is_empty()
+    }
+}
+
+impl NonceRegistry for MemoryNonceRegistry {
+    fn register(&mut self, challenge: &IronShieldChallenge, now: i64) -> Result<bool, String> {
+        let nonce: &str = challenge.random_nonce.as_str();
+        if self.nonces.get(nonce).is_some_and(|deadline| now <= *deadline) {
+            return Ok(false);
+        }
+        self.nonces.insert(nonce.to_string(), challenge.effective_submission_deadline());
+        Ok(true)
+    }
+
+    fn purge_expired(&mut self, now: i64) -> Result<usize, String> {
+        let before: usize = self.nonces.len();
+        self.nonces.retain(|_, deadline| now <= *deadline);
+        Ok(before - self.nonces.len())
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    fn challenge(website_id: &str, expiration_time: i64) -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let mut challenge = IronShieldChallenge::new(website_id.parse().unwrap(), 1_000, key, [0x34; 32]);
+        challenge.created_time = 1_000;
+        challenge.expiration_time = expiration_time;
+        challenge
+    }
+
+    #[test]
+    fn test_put_get_and_take_once() {
+        let mut store = MemoryChallengeStore::new(8);
+        let issued = challenge("example.com", 5_000);
+        let id: [u8; 32] = store.put(issued.clone()).unwrap();
+        assert_eq!(id, issued.challenge_id());
+
+        assert_eq!(store.get_by_id(&id, 5_000).unwrap(), Some(issued.clone()));
+        assert_eq!(store.get_by_id(&id, 5_001).unwrap(), None);
+        assert_eq!(store.get_by_id(&[0; 32], 2_000).unwrap(), None);
+
+        assert_eq!(store.take(&id, 2_000).unwrap(), Some(issued));
+        assert_eq!(store.take(&id, 2_000).unwrap(), None);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_altered_challenge_is_not_found() {
+        let mut store = MemoryChallengeStore::new(8);
+        let issued = challenge("example.com", 5_000);
+        store.put(issued.clone()).unwrap();
+
+        let mut altered = issued;
+        altered.recommended_attempts += 1;
+        assert_eq!(store.take(&altered.challenge_id(), 2_000).unwrap(), None);
+    }
+
+    #[test]
+    fn test_purge_expired_and_snapshot() {
+        let mut store = MemoryChallengeStore::new(8);
+        let mut deadline_bound = challenge("a.example", 9_000);
+        deadline_bound.submission_deadline = Some(3_000);
+        store.put(deadline_bound).unwrap();
+        let live = challenge("b.example", 9_000);
+        let live_id: [u8; 32] = store.put(live.clone()).unwrap();
+
+        assert_eq!(store.purge_expired(3_000).unwrap(), 0);
+        assert_eq!(store.purge_expired(3_001).unwrap(), 1);
+        assert_eq!(store.len(), 1);
+
+        let json: String = serde_json::to_string(&store).unwrap();
+        let restored: MemoryChallengeStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get_by_id(&live_id, 4_000).unwrap(), Some(live));
+    }
+
+    #[test]
+    fn test_token_consumed_once() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let mut token = crate::issue_token(&challenge("example.com", 5_000), &key, &crate::LifetimePolicy::default()).unwrap();
+        token.valid_for = 10_000;
+
+        let mut store = MemoryTokenConsumptionStore::new();
+        assert!(!store.consume(&token, 10_001).unwrap());
+        assert!(store.consume(&token, 2_000).unwrap());
+        assert!(!store.consume(&token, 3_000).unwrap());
+
+        assert_eq!(store.purge_expired(10_000).unwrap(), 0);
+        assert_eq!(store.purge_expired(10_001).unwrap(), 1);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_nonce_registered_once_while_redeemable() {
+        let issued = challenge("example.com", 5_000);
+        let mut reissued = challenge("other.example", 9_000);
+        reissued.random_nonce = issued.random_nonce.clone();
+
+        let mut registry = MemoryNonceRegistry::new();
+        assert!(registry.register(&issued, 2_000).unwrap());
+        assert!(!registry.register(&reissued, 5_000).unwrap());
+        assert!(registry.register(&reissued, 5_001).unwrap());
+        assert_eq!(registry.len(), 1);
+
+        assert_eq!(registry.purge_expired(9_000).unwrap(), 0);
+        assert_eq!(registry.purge_expired(9_001).unwrap(), 1);
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+//! # Compact binary wire format helpers.
+//!
+//! The pipe-delimited concat format spells every byte array out in hex,
+//! which roughly doubles its size. The compact format stores integers as
+//! fixed-width big-endian values, byte arrays raw, and strings with a
+//! length prefix. It is used where header space is scarce, such as
+//! WebSocket upgrades.
+//!
+//! Every payload ends in a big-endian CRC-32 (IEEE) of the preceding
+//! bytes. It is checked before any field is parsed, so a truncated or
+//! altered header fails with a `CorruptedPayload` error rather than a
+//! misleading complaint about whichever field happened to break.
+//!
+//! The writer/reader in this module are shared by the types that
+//! implement `to_compact_bytes()` / `from_compact_bytes()`.
+
+/// Version byte written at the start of every compact payload.
+/// Version 2 added the CRC-32 trailer.
+pub const COMPACT_FORMAT_VERSION: u8 = 2;
+
+/// Prefix of the error returned when the CRC-32 trailer is missing or
+/// does not match.
+pub const CORRUPTED_PAYLOAD_ERROR: &str = "CorruptedPayload";
+
+/// Number of trailer bytes holding the checksum.
+const CHECKSUM_LEN: usize = 4;
+/// Reversed IEEE 802.3 polynomial.
+const CRC32_POLY:   u32 = 0xEDB8_8320;
+
+/// CRC-32 (IEEE), as used by zlib and Ethernet.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = !0;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Sequential writer for the compact binary format.
+#[derive(Debug, Default)]
+pub(crate) struct CompactWriter {
+    buffer: Vec<u8>,
+}
+
+impl CompactWriter {
+    /// Starts a new payload with the format version byte.
+    pub(crate) fn new() -> Self {
+        let mut writer = Self::default();
+        writer.write_u8(COMPACT_FORMAT_VERSION);
+        writer
+    }
+
+    pub(crate) fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub(crate) fn write_u64(&mut self, value: u64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub(crate) fn write_i64(&mut self, value: i64) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Writes a string prefixed with its length as a big-endian `u16`.
+    pub(crate) fn write_str(&mut self, value: &str) -> Result<(), String> {
+        let length: u16 = u16::try_from(value.len())
+            .map_err(|_| format!("String of {} bytes is too long for the compact format", value.len()))?;
+        self.buffer.extend_from_slice(&length.to_be_bytes());
+        self.buffer.extend_from_slice(value.as_bytes());
+        Ok(())
+    }
+
+    /// Appends the CRC-32 trailer and returns the payload.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let checksum: u32 = crc32(&self.buffer);
+        self.buffer.extend_from_slice(&checksum.to_be_bytes());
+        self.buffer
+    }
+}
+
+/// Sequential reader for the compact binary format.
+#[derive(Debug)]
+pub(crate) struct CompactReader<'a> {
+    bytes:    &'a [u8],
+    position: usize,
+}
+
+impl<'a> CompactReader<'a> {
+    /// Opens a payload, checks its format version byte and verifies the
+    /// CRC-32 trailer. The reader only sees the bytes before the trailer.
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        let mut reader = Self { bytes, position: 0 };
+        let version: u8 = reader.read_u8("format version")?;
+        if version != COMPACT_FORMAT_VERSION {
+            return Err(format!("Unsupported compact format version: {}", version));
+        }
+
+        let body_len: usize = bytes.len().checked_sub(CHECKSUM_LEN)
+            .filter(|len| *len >= reader.position)
+            .ok_or_else(|| format!("{}: compact payload is too short to hold its checksum", CORRUPTED_PAYLOAD_ERROR))?;
+        let (body, trailer) = bytes.split_at(body_len);
+        if crc32(body).to_be_bytes() != trailer {
+            return Err(format!(
+                "{}: compact payload checksum mismatch; it was truncated or altered",
+                CORRUPTED_PAYLOAD_ERROR
+            ));
+        }
+
+        reader.bytes = body;
+        Ok(reader)
+    }
+
+    fn take(&mut self, length: usize, field: &str) -> Result<&'a [u8], String> {
+        let end: usize = self.position.checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("Compact payload truncated while reading {}", field))?;
+        let slice: &'a [u8] = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn read_u8(&mut self, field: &str) -> Result<u8, String> {
+        Ok(self.take(1, field)?[0])
+    }
+
+    pub(crate) fn read_u64(&mut self, field: &str) -> Result<u64, String> {
+        Ok(u64::from_be_bytes(self.read_array::<8>(field)?))
+    }
+
+    pub(crate) fn read_i64(&mut self, field: &str) -> Result<i64, String> {
+        Ok(i64::from_be_bytes(self.read_array::<8>(field)?))
+    }
+
+    pub(crate) fn read_array<const N: usize>(&mut self, field: &str) -> Result<[u8; N], String> {
+        let slice: &[u8] = self.take(N, field)?;
+        let mut array = [0u8; N];
+        array.copy_from_slice(slice);
+        Ok(array)
+    }
+
+    /// Borrows a fixed-size array straight from the payload.
+    pub(crate) fn read_array_ref<const N: usize>(&mut self, field: &str) -> Result<&'a [u8; N], String> {
+        let slice: &'a [u8] = self.take(N, field)?;
+        slice.try_into().map_err(|_| format!("Compact payload truncated while reading {}", field))
+    }
+
+    /// Reads a string written by `CompactWriter::write_str`.
+    pub(crate) fn read_str(&mut self, field: &str) -> Result<&'a str, String> {
+        let length: u16 = u16::from_be_bytes(self.read_array::<2>(field)?);
+        let bytes: &'a [u8] = self.take(length as usize, field)?;
+        std::str::from_utf8(bytes)
+            .map_err(|_| format!("Invalid UTF-8 in compact field {}", field))
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if bytes are left to read, e.g. an optional
+    ///           trailing block.
+    pub(crate) fn has_remaining(&self) -> bool {
+        self.position < self.bytes.len()
+    }
+
+    /// Fails if any bytes are left after the last field.
+    pub(crate) fn finish(self) -> Result<(), String> {
+        if self.position != self.bytes.len() {
+            return Err(format!(
+                "Compact payload has {} trailing bytes",
+                self.bytes.len() - self.position
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writer_reader_roundtrip() {
+        let mut writer = CompactWriter::new();
+        writer.write_u8(7);
+        writer.write_u64(u64::MAX);
+        writer.write_i64(-42);
+        writer.write_bytes(&[0xAA; 4]);
+        writer.write_str("example.com").unwrap();
+        let bytes: Vec<u8> = writer.finish();
+
+        assert_eq!(bytes[0], COMPACT_FORMAT_VERSION);
+        assert_eq!(bytes.len(), 1 + 1 + 8 + 8 + 4 + 2 + 11 + CHECKSUM_LEN);
+
+        let mut reader = CompactReader::new(&bytes).unwrap();
+        assert_eq!(reader.read_u8("a").unwrap(), 7);
+        assert_eq!(reader.read_u64("b").unwrap(), u64::MAX);
+        assert_eq!(reader.read_i64("c").unwrap(), -42);
+        assert_eq!(reader.read_array::<4>("d").unwrap(), [0xAA; 4]);
+        assert_eq!(reader.read_str("e").unwrap(), "example.com");
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        assert!(CompactReader::new(&[]).unwrap_err().contains("truncated while reading format version"));
+        assert!(CompactReader::new(&[99]).unwrap_err().contains("Unsupported compact format version"));
+
+        let mut writer = CompactWriter::new();
+        writer.write_bytes(&[0, 0]);
+        let bytes: Vec<u8> = writer.finish();
+        let mut reader = CompactReader::new(&bytes).unwrap();
+        assert!(reader.read_i64("created_time").unwrap_err().contains("created_time"));
+
+        let mut writer = CompactWriter::new();
+        writer.write_u8(1);
+        let bytes: Vec<u8> = writer.finish();
+        let reader = CompactReader::new(&bytes).unwrap();
+        assert!(reader.finish().unwrap_err().contains("1 trailing bytes"));
+    }
+
+    #[test]
+    fn test_crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_reader_rejects_corrupted_payloads() {
+        let mut writer = CompactWriter::new();
+        writer.write_str("example.com").unwrap();
+        let bytes: Vec<u8> = writer.finish();
+
+        for length in 1..bytes.len() {
+            let error: String = CompactReader::new(&bytes[..length]).unwrap_err();
+            assert!(error.starts_with(CORRUPTED_PAYLOAD_ERROR), "{}", error);
+        }
+
+        let mut flipped: Vec<u8> = bytes.clone();
+        flipped[5] ^= 0x01;
+        assert!(CompactReader::new(&flipped).unwrap_err().contains("checksum mismatch"));
+
+        let mut extended: Vec<u8> = bytes.clone();
+        extended.push(0);
+        assert!(CompactReader::new(&extended).unwrap_err().starts_with(CORRUPTED_PAYLOAD_ERROR));
+    }
+
+    #[test]
+    fn test_write_str_rejects_oversized_strings() {
+        let mut writer = CompactWriter::new();
+        let long: String = "a".repeat(u16::MAX as usize + 1);
+        assert!(writer.write_str(&long).unwrap_err().contains("too long"));
+    }
+}
@@ -0,0 +1,179 @@
+//! # Request and response of the config-distribution endpoint.
+//!
+//! Edges poll the control plane for their configuration, sending the
+//! version they run. The control plane answers with a newer
+//! `SignedConfig` or, if the edge is up to date, with the version alone.
+//! Both sides use these types so the endpoint has a single schema.
+
+use serde::{
+    Deserialize,
+    Serialize,
+    de::DeserializeOwned
+};
+
+use crate::{
+    CryptoError,
+    SignedConfig
+};
+
+/// Maximum length of `node_id`.
+const MAX_NODE_ID_LEN: usize = 128;
+
+/// Sent by an edge asking for its configuration.
+///
+/// * `node_id`:         Identifier of the edge node.
+/// * `current_version`: Version the edge runs, or `None` on first start.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ConfigRequest {
+    pub node_id:         String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<u64>,
+}
+
+/// Returned by the control plane.
+///
+/// * `signed_config`: The newer configuration, or `None` if the edge
+///                    already runs `version`.
+/// * `version`:       The latest configuration version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ConfigResponse<T> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_config: Option<SignedConfig<T>>,
+    pub version:       u64,
+}
+
+impl ConfigRequest {
+    /// # Arguments
+    /// * `node_id`:         Identifier of the edge node.
+    /// * `current_version`: Version the edge runs, if any.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The request, or an error if `node_id`
+    ///                           is empty, longer than 128 bytes or not
+    ///                           printable ASCII.
+    pub fn new(node_id: impl Into<String>, current_version: Option<u64>) -> Result<Self, String> {
+        let request = Self { node_id: node_id.into(), current_version };
+        request.validate()?;
+        Ok(request)
+    }
+
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if `node_id` is usable.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.node_id.is_empty()
+            || self.node_id.len() > MAX_NODE_ID_LEN
+            || !self.node_id.bytes().all(|b| b.is_ascii_graphic())
+        {
+            return Err(format!("node_id must be 1 to {} printable ASCII characters", MAX_NODE_ID_LEN));
+        }
+        Ok(())
+    }
+}
+
+impl<T> ConfigResponse<T> {
+    /// # Arguments
+    /// * `signed_config`: The configuration to deliver.
+    /// * `version`:       Its version.
+    ///
+    /// # Returns
+    /// * `Self`: A response carrying the configuration.
+    pub fn updated(signed_config: SignedConfig<T>, version: u64) -> Self {
+        Self { signed_config: Some(signed_config), version }
+    }
+
+    /// # Arguments
+    /// * `version`: The version the edge already runs.
+    ///
+    /// # Returns
+    /// * `Self`: A response telling the edge to keep its configuration.
+    pub fn not_modified(version: u64) -> Self {
+        Self { signed_config: None, version }
+    }
+
+    /// Builds the answer to `request`, leaving out the configuration if
+    /// the edge already runs `version`.
+    ///
+    /// # Arguments
+    /// * `request`:       The edge's request.
+    /// * `signed_config`: The latest configuration.
+    /// * `version`:       Its version.
+    ///
+    /// # Returns
+    /// * `Self`: The response.
+    pub fn for_request(request: &ConfigRequest, signed_config: SignedConfig<T>, version: u64) -> Self {
+        if request.current_version.is_some_and(|current| current >= version) {
+            Self::not_modified(version)
+        } else {
+            Self::updated(signed_config, version)
+        }
+    }
+
+    /// Verifies the delivered configuration.
+    ///
+    /// `version` is not covered by the signature, so a configuration is
+    /// only accepted if it is newer than the one the edge runs; replaying
+    /// an older snapshot is further bounded by its `max_age_ms`.
+    ///
+    /// # Arguments
+    /// * `current_version`: Version the edge runs, if any.
+    /// * `public_key`:      The root public key.
+    /// * `now`:             The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<Option<T>, CryptoError>`: The new configuration, `None`
+    ///                                     if there is none, or an
+    ///                                     error if it fails
+    ///                                     `SignedConfig::load` or is
+    ///                                     not newer.
+    pub fn load(&self, current_version: Option<u64>, public_key: &[u8; 32], now: i64) -> Result<Option<T>, CryptoError>
+    where
+        T: DeserializeOwned,
+    {
+        let Some(signed_config) = &self.signed_config else {
+            return Ok(None);
+        };
+        if current_version.is_some_and(|current| current >= self.version) {
+            return Err(CryptoError::VerificationFailed(format!(
+                "Config version {} is not newer than {}",
+                self.version,
+                current_version.unwrap_or_default()
+            ).into()));
+        }
+        signed_config.load(public_key, now).map(Some)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::{
+        JsSolverConfig,
+        SigningKey
+    };
+
+    #[test]
+    fn test_fetch_roundtrip() {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let config = JsSolverConfig { worker_count: Some(4), ..Default::default() };
+        let signed = SignedConfig::sign(&config, 1_000, 60_000, &key).unwrap();
+
+        let first = ConfigRequest::new("edge-fra-1", None).unwrap();
+        assert_eq!(serde_json::to_string(&first).unwrap(), r#"{"node_id":"edge-fra-1"}"#);
+        let response = ConfigResponse::for_request(&first, signed.clone(), 7);
+        let json: String = serde_json::to_string(&response).unwrap();
+        let received: ConfigResponse<JsSolverConfig> = serde_json::from_str(&json).unwrap();
+        assert_eq!(received.load(first.current_version, &public_key, 2_000).unwrap(), Some(config));
+
+        let current = ConfigRequest::new("edge-fra-1", Some(7)).unwrap();
+        let unchanged = ConfigResponse::for_request(&current, signed.clone(), 7);
+        assert_eq!(serde_json::to_string(&unchanged).unwrap(), r#"{"version":7}"#);
+        assert_eq!(unchanged.load(Some(7), &public_key, 2_000).unwrap(), None);
+
+        assert!(ConfigResponse::updated(signed, 6).load(Some(7), &public_key, 2_000).is_err());
+        assert!(ConfigRequest::new("", None).is_err());
+        assert!(ConfigRequest::new("edge fra", None).is_err());
+    }
+}
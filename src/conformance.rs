@@ -0,0 +1,271 @@
+//! # Conformance checks for third-party implementations.
+//!
+//! `check_implementation` runs another implementation's signer and
+//! verifier, wrapped in closures, against the edge cases this crate
+//! handles: the published interop vectors, expired and tampered
+//! challenges, `|` injected into a signed field, signatures under
+//! another key and malleable signatures. Challenges are passed as their
+//! serde JSON, which every client can parse, and the result is a
+//! `ConformanceReport` with one entry per case.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    IronShieldChallenge,
+    InteropVectors,
+    SigningKey,
+    WebsiteId
+};
+
+/// The Ed25519 group order L, little-endian.
+const GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58,
+    0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Signs a challenge: takes the challenge JSON and a 32-byte Ed25519
+/// seed, returns the challenge signature.
+pub type SignCallback<'a> = dyn Fn(&str, &[u8; 32]) -> Result<[u8; 64], String> + 'a;
+
+/// Decides whether a challenge is accepted for redemption: takes the
+/// challenge JSON, the issuer's public key and the current time in unix
+/// millis, returns `Ok(())` to accept or the reason for rejecting.
+pub type VerifyCallback<'a> = dyn Fn(&str, &[u8; 32], i64) -> Result<(), String> + 'a;
+
+/// The implementation under test. Either side may be left out, e.g.
+/// for verify-only clients.
+///
+/// * `sign`:   The implementation's challenge signer.
+/// * `verify`: The implementation's challenge verifier.
+#[derive(Default)]
+pub struct ConformanceCallbacks<'a> {
+    pub sign:   Option<&'a SignCallback<'a>>,
+    pub verify: Option<&'a VerifyCallback<'a>>,
+}
+
+/// Result of one case.
+///
+/// * `name`:   What the case checks.
+/// * `passed`: Whether the implementation behaved as required.
+/// * `detail`: What went wrong, if the case failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformanceCase {
+    pub name:   String,
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Results of `check_implementation`.
+///
+/// * `cases`: One entry per case run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    pub cases: Vec<ConformanceCase>,
+}
+
+impl ConformanceReport {
+    /// # Returns
+    /// * `bool`: `true` if every case passed.
+    pub fn passed(&self) -> bool {
+        self.cases.iter().all(|case| case.passed)
+    }
+
+    /// # Returns
+    /// * `Vec<&ConformanceCase>`: The failed cases.
+    pub fn failures(&self) -> Vec<&ConformanceCase> {
+        self.cases.iter().filter(|case| !case.passed).collect()
+    }
+
+    fn record(&mut self, name: impl Into<String>, result: Result<(), String>) {
+        self.cases.push(ConformanceCase {
+            name:   name.into(),
+            passed: result.is_ok(),
+            detail: result.err(),
+        });
+    }
+}
+
+/// # Arguments
+/// * `challenge`: The challenge to hand to a callback.
+///
+/// # Returns
+/// * `String`: Its JSON.
+fn to_json(challenge: &IronShieldChallenge) -> String {
+    serde_json::to_string(challenge).unwrap_or_default()
+}
+
+/// Adds L to the S half of a signature. The result verifies under
+/// implementations that skip the canonical-S check.
+///
+/// # Arguments
+/// * `signature`: A canonical signature.
+///
+/// # Returns
+/// * `[u8; 64]`: The malleated signature.
+fn malleate(signature: &[u8; 64]) -> [u8; 64] {
+    let mut malleated: [u8; 64] = *signature;
+    let mut carry: u16 = 0;
+    for (byte, order) in malleated[32..].iter_mut().zip(GROUP_ORDER) {
+        let sum: u16 = *byte as u16 + order as u16 + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+    malleated
+}
+
+/// Runs the conformance cases against an implementation.
+///
+/// # Arguments
+/// * `callbacks`: The implementation's signer and verifier.
+///
+/// # Returns
+/// * `ConformanceReport`: The result of every case that applies to the
+///                        callbacks given.
+///
+/// # Example
+/// ```
+/// use ironshield_types::{check_implementation, verify_challenge_signature_with_key, ConformanceCallbacks, IronShieldChallenge};
+///
+/// // A verifier that checks nothing but the signature.
+/// let verify = |json: &str, public_key: &[u8; 32], _now: i64| {
+///     let challenge: IronShieldChallenge = serde_json::from_str(json).map_err(|e| e.to_string())?;
+///     verify_challenge_signature_with_key(&challenge, public_key).map_err(|e| e.to_string())
+/// };
+/// let report = check_implementation(&ConformanceCallbacks { sign: None, verify: Some(&verify) });
+/// assert!(!report.passed());
+/// assert!(report.failures().iter().any(|case| case.name.contains("expired")));
+/// ```
+pub fn check_implementation(callbacks: &ConformanceCallbacks) -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+    let vectors: InteropVectors = match InteropVectors::reference() {
+        Ok(vectors) => vectors,
+        Err(e) => {
+            report.record("Generate reference vectors", Err(e));
+            return report;
+        }
+    };
+
+    // Unsigned challenges and the signatures the vectors expect.
+    let mut cases: Vec<(String, [u8; 32], IronShieldChallenge, [u8; 64])> = Vec::new();
+    for vector in &vectors.vectors {
+        let seed: Option<[u8; 32]> = hex::decode(&vector.private_key_seed).ok().and_then(|seed| seed.try_into().ok());
+        let challenge: Result<IronShieldChallenge, String> = IronShieldChallenge::from_base64url_header(&vector.challenge);
+        match (seed, challenge) {
+            (Some(seed), Ok(mut challenge)) => {
+                let signature: [u8; 64] = challenge.challenge_signature;
+                challenge.challenge_signature = [0; 64];
+                cases.push((vector.description.clone(), seed, challenge, signature));
+            }
+            _ => report.record(vector.description.clone(), Err("Reference vector does not decode".to_string())),
+        }
+    }
+
+    if let Some(sign) = callbacks.sign {
+        for (description, seed, challenge, expected) in &cases {
+            let result: Result<(), String> = sign(&to_json(challenge), seed).and_then(|signature| {
+                if signature == *expected {
+                    Ok(())
+                } else {
+                    Err(format!("Signature {} differs from the reference {}", hex::encode(signature), hex::encode(expected)))
+                }
+            });
+            report.record(format!("Sign: {}", description), result);
+        }
+    }
+
+    let Some(verify) = callbacks.verify else {
+        return report;
+    };
+    let Some((_, seed, unsigned, _)) = cases.first() else {
+        return report;
+    };
+    let private_key = SigningKey::from_bytes(seed);
+    let public_key: [u8; 32] = private_key.verifying_key().to_bytes();
+    let now: i64 = unsigned.created_time + 1_000;
+
+    let signed = |mut challenge: IronShieldChallenge| {
+        challenge.sign(&private_key);
+        challenge
+    };
+    let valid: IronShieldChallenge = signed(unsigned.clone());
+    let accepts = |challenge: &IronShieldChallenge, now: i64| verify(&to_json(challenge), &public_key, now);
+    let expect_reject = |challenge: &IronShieldChallenge, now: i64| match accepts(challenge, now) {
+        Ok(())  => Err("Accepted".to_string()),
+        Err(_) => Ok(()),
+    };
+
+    for (description, _, challenge, signature) in &cases {
+        let mut challenge: IronShieldChallenge = challenge.clone();
+        challenge.challenge_signature = *signature;
+        let key: [u8; 32] = challenge.public_key;
+        report.record(format!("Verify accepts: {}", description), verify(&to_json(&challenge), &key, now));
+    }
+
+    report.record(
+        "Verify rejects an expired challenge",
+        expect_reject(&valid, valid.effective_submission_deadline() + 1),
+    );
+
+    let mut tampered: IronShieldChallenge = valid.clone();
+    tampered.expiration_time += 60_000;
+    report.record("Verify rejects a challenge altered after signing", expect_reject(&tampered, now));
+
+    let injected: IronShieldChallenge = signed(IronShieldChallenge {
+        website_id: WebsiteId::unchecked(format!("{}|{}", valid.website_id, hex::encode(valid.challenge_param))),
+        ..unsigned.clone()
+    });
+    report.record("Verify rejects a signed website_id containing the '|' delimiter", expect_reject(&injected, now));
+
+    let foreign_key = SigningKey::from_bytes(&[0xee; 32]);
+    let mut foreign: IronShieldChallenge = unsigned.clone();
+    foreign.public_key = foreign_key.verifying_key().to_bytes();
+    foreign.sign(&foreign_key);
+    report.record("Verify rejects a challenge signed with another key", expect_reject(&foreign, now));
+
+    let mut malleable: IronShieldChallenge = valid.clone();
+    malleable.challenge_signature = malleate(&valid.challenge_signature);
+    report.record("Verify rejects a malleable signature (S + L)", expect_reject(&malleable, now));
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        verify_challenge_signature_with_key,
+        Validated
+    };
+
+    #[test]
+    fn test_reference_implementation_conforms() {
+        let sign = |json: &str, seed: &[u8; 32]| {
+            let mut challenge: IronShieldChallenge = serde_json::from_str(json).map_err(|e| e.to_string())?;
+            challenge.sign(&SigningKey::from_bytes(seed));
+            Ok(challenge.challenge_signature)
+        };
+        let verify = |json: &str, public_key: &[u8; 32], now: i64| {
+            let challenge: Validated<IronShieldChallenge> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+            verify_challenge_signature_with_key(&challenge, public_key).map_err(|e| e.to_string())?;
+            if !challenge.accepts_submission_at(now) {
+                return Err("Expired".to_string());
+            }
+            Ok(())
+        };
+
+        let report = check_implementation(&ConformanceCallbacks { sign: Some(&sign), verify: Some(&verify) });
+        assert!(report.passed(), "{:?}", report.failures());
+        assert_eq!(report.cases.len(), 11);
+
+        let accept_all = |_: &str, _: &[u8; 32], _: i64| Ok(());
+        let report = check_implementation(&ConformanceCallbacks { sign: None, verify: Some(&accept_all) });
+        assert_eq!(report.failures().len(), 5);
+        assert_eq!(report.failures()[0].detail.as_deref(), Some("Accepted"));
+    }
+}
@@ -11,6 +11,8 @@
 //!
 //! For PGP keys, a simple heuristic scans the binary data to find valid Ed25519 key material.
 //! This approach is simpler and more reliable than using complex PGP parsing libraries.
+//! The heuristic is only compiled with the `pgp-keys` feature (on by default); without it
+//! only raw Ed25519 keys are accepted, which keeps the scanner out of browser builds.
 //!
 //! ## Features
 //!
@@ -30,6 +32,32 @@
 //! * `verify_challenge_signature_with_key()`:  Verify using provided public key
 //! * `validate_challenge()`:                   Comprehensive challenge validation
 //!                                             (signature + expiration)
+//! * `validate_challenge_with_policy()`:       Validation honoring a `LifetimePolicy`
+//!                                             submission grace period
+//!
+//! ### Token Issuance
+//! * `issue_token()`:                          Sign a token with the policy's TTL
+//! * `issue_token_with_body_digest()`:         Sign a token that attests to a request body
+//! * `verify_token_signature_with_key()`:      Verify a token's authentication signature
+//! * `verify_token_with_revocations()`:        Reject revoked tokens, then verify the signature
+//!
+//! ### Symmetric (HMAC) Mode
+//! * `generate_hmac_tag()`:                    HMAC-SHA256 tag in the signature field layout
+//! * `verify_challenge_hmac()`:                Verify a challenge signed with `sign_hmac()`
+//! * `issue_token_hmac()`:                     Issue a token tagged with a shared key
+//! * `verify_token_hmac()`:                    Verify a token's HMAC tag
+//! * `verify_challenge_signature_with_scheme()`: Verify under a negotiated `SignatureScheme`
+//!
+//! ### Rejection Receipts
+//! * `issue_rejection_receipt()`:              Sign a receipt for a rejected solution
+//! * `verify_rejection_receipt_with_key()`:    Verify a receipt's signature
+//!
+//! ## Cargo Features
+//!
+//! Everything that touches a private key or the environment (key
+//! loading, signing, `validate_challenge()` and token issuance) requires
+//! the `sign` feature; `generate_test_keypair()` additionally needs `rng`.
+//! Verification against an explicit public key is always available.
 //!
 //! ## Environment Variables
 //!
@@ -40,13 +68,14 @@
 //! ## Examples
 //!
 //! ### Basic Usage with Raw Keys
-//! ```no_run
+#![cfg_attr(feature = "rng", doc = " ```no_run")]
+#![cfg_attr(not(feature = "rng"), doc = " ```ignore")]
 //! use ironshield_types::{load_private_key_from_env, generate_test_keypair};
 //!
 //! // Generate test keys
 //! let (private_b64, public_b64) = generate_test_keypair();
-//! std::env::set_var("IRONSHIELD_PRIVATE_KEY", private_b64);
-//! std::env::set_var("IRONSHIELD_PUBLIC_KEY", public_b64);
+//! unsafe { std::env::set_var("IRONSHIELD_PRIVATE_KEY", private_b64); }
+//! unsafe { std::env::set_var("IRONSHIELD_PUBLIC_KEY", public_b64); }
 //!
 //! // Load keys from environment
 //! let signing_key = load_private_key_from_env().unwrap();
@@ -68,20 +97,56 @@ use base64::{
 };
 use ed25519_dalek::{
     Signature,
-    Signer,
     Verifier,
-    SigningKey,
     VerifyingKey,
-    PUBLIC_KEY_LENGTH,
+    PUBLIC_KEY_LENGTH
+};
+#[cfg(any(feature = "sign", feature = "pgp-keys"))]
+use ed25519_dalek::SigningKey;
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
     SECRET_KEY_LENGTH
 };
+#[cfg(feature = "rng")]
 use rand::rngs::OsRng;
 
-use crate::IronShieldChallenge;
-
+use crate::metrics::{
+    count_outcome,
+    TOKEN_SIGNATURES_REJECTED,
+    TOKEN_SIGNATURES_VERIFIED
+};
+#[cfg(feature = "sign")]
+use crate::metrics::{
+    metrics,
+    CHALLENGES_REJECTED,
+    CHALLENGES_VALIDATED,
+    SIGNATURES_CREATED,
+    VALIDATED_CHALLENGE_AGE_MS
+};
+use crate::trace::traced;
+use crate::{
+    IronShieldChallenge,
+    IronShieldToken,
+    RejectionReceipt,
+    RevokedTokens,
+    SignatureScheme
+};
+#[cfg(feature = "sign")]
+use crate::ErrorCode;
+#[cfg(feature = "sign")]
+use crate::LifetimePolicy;
+#[cfg(feature = "sign")]
+use crate::epoch::now_millis;
+
+#[cfg(feature = "sign")]
 use std::env;
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 /// Debug logging helper that works across different compilation targets
+#[cfg(feature = "pgp-keys")]
 macro_rules! debug_log {
     ($($arg:tt)*) => {
         #[cfg(all(target_arch = "wasm32", feature = "wasm-logging"))]
@@ -99,14 +164,122 @@ macro_rules! debug_log {
     };
 }
 
+/// Message of a `CryptoError`, with the error that caused it if any.
+///
+/// The message already includes the cause's text, so `Display` output
+/// is unchanged whether a source is attached or not. The source is
+/// reachable through `std::error::Error::source` on the `CryptoError`
+/// and is only kept with the `std` feature.
+///
+/// `CryptoError` variants used to carry a bare `String`. `ErrorDetail`
+/// derefs to `str`, displays as the message and converts from `String`
+/// and `&str`, so `CryptoError::X(msg.into())` and code reading `msg`
+/// as text keep working; matching on `String` patterns does not.
+///
+/// The source is held in an `Arc` rather than a `Box` so that
+/// `CryptoError` stays `Clone`.
+#[derive(Debug, Clone)]
+pub struct ErrorDetail {
+    message: String,
+    #[cfg(feature = "std")]
+    source:  Option<Arc<dyn Error + Send + Sync>>,
+}
+
+impl ErrorDetail {
+    /// # Arguments
+    /// * `message`: What went wrong.
+    ///
+    /// # Returns
+    /// * `Self`: A detail without a source.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            #[cfg(feature = "std")]
+            source:  None,
+        }
+    }
+
+    /// # Arguments
+    /// * `message`: What went wrong.
+    /// * `source`:  The underlying error, e.g. from ed25519-dalek or
+    ///              base64. Dropped without the `std` feature.
+    ///
+    /// # Returns
+    /// * `Self`: A detail chaining to `source`.
+    pub fn caused_by(message: impl Into<String>, source: impl Error + Send + Sync + 'static) -> Self {
+        #[cfg(not(feature = "std"))]
+        let _ = source;
+        Self {
+            message: message.into(),
+            #[cfg(feature = "std")]
+            source:  Some(Arc::new(source)),
+        }
+    }
+
+    /// # Returns
+    /// * `&str`: The message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// # Returns
+    /// * `Option<&(dyn Error + Send + Sync + 'static)>`: The underlying
+    ///                                                   error, if any.
+    #[cfg(feature = "std")]
+    pub fn source(&self) -> Option<&(dyn Error + Send + Sync + 'static)> {
+        self.source.as_deref()
+    }
+}
+
+impl std::ops::Deref for ErrorDetail {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for ErrorDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<String> for ErrorDetail {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ErrorDetail {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum CryptoError {
-    MissingEnvironmentVariable(String),
-    InvalidKeyFormat(String),
-    SigningFailed(String),
-    VerificationFailed(String),
-    Base64DecodingFailed(String),
-    PgpParsingFailed(String),
+    MissingEnvironmentVariable(ErrorDetail),
+    InvalidKeyFormat(ErrorDetail),
+    SigningFailed(ErrorDetail),
+    VerificationFailed(ErrorDetail),
+    Base64DecodingFailed(ErrorDetail),
+    PgpParsingFailed(ErrorDetail),
+}
+
+impl CryptoError {
+    /// # Returns
+    /// * `&ErrorDetail`: The message and source of the error.
+    pub fn detail(&self) -> &ErrorDetail {
+        match self {
+            CryptoError::MissingEnvironmentVariable(detail)
+            | CryptoError::InvalidKeyFormat(detail)
+            | CryptoError::SigningFailed(detail)
+            | CryptoError::VerificationFailed(detail)
+            | CryptoError::Base64DecodingFailed(detail)
+            | CryptoError::PgpParsingFailed(detail) => detail,
+        }
+    }
 }
 
 impl std::fmt::Display for CryptoError {
@@ -122,7 +295,12 @@ impl std::fmt::Display for CryptoError {
     }
 }
 
-impl std::error::Error for CryptoError {}
+impl Error for CryptoError {
+    #[cfg(feature = "std")]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.detail().source().map(|source| source as &(dyn Error + 'static))
+    }
+}
 
 /// Parse key data with simple heuristic approach (handles PGP and raw Ed25519)
 ///
@@ -136,6 +314,7 @@ impl std::error::Error for CryptoError {}
 ///
 /// # Returns
 /// * `Result<[u8; 32], CryptoError>`: The 32-byte Ed25519 key
+#[cfg(feature = "pgp-keys")]
 fn parse_key_simple(key_data: &str, is_private: bool) -> Result<[u8; 32], CryptoError> {
     // Clean the key data by removing all whitespace, line breaks, and common PGP formatting
     let cleaned_data = key_data
@@ -193,7 +372,7 @@ fn parse_key_simple(key_data: &str, is_private: bool) -> Result<[u8; 32], Crypto
                 test_data.pop();
             }
 
-            return Err(CryptoError::Base64DecodingFailed(format!("Failed to decode cleaned key data: {}", e)));
+            return Err(CryptoError::Base64DecodingFailed(ErrorDetail::caused_by(format!("Failed to decode cleaned key data: {}", e), e)));
         }
     };
 
@@ -201,6 +380,7 @@ fn parse_key_simple(key_data: &str, is_private: bool) -> Result<[u8; 32], Crypto
 }
 
 /// Extract Ed25519 key material from decoded bytes
+#[cfg(feature = "pgp-keys")]
 fn try_extract_ed25519_key(key_bytes: &[u8], is_private: bool) -> Result<[u8; 32], CryptoError> {
     debug_log!("🔑 Extracting Ed25519 key from {} bytes", key_bytes.len());
 
@@ -215,7 +395,7 @@ fn try_extract_ed25519_key(key_bytes: &[u8], is_private: bool) -> Result<[u8; 32
             debug_log!("✅ Raw Ed25519 private key validated");
         } else {
             let _verifying_key = VerifyingKey::from_bytes(&key_array)
-                .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid raw public key: {}", e)))?;
+                .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid raw public key: {}", e), e)))?;
             debug_log!("✅ Raw Ed25519 public key validated");
         }
 
@@ -334,7 +514,7 @@ fn try_extract_ed25519_key(key_bytes: &[u8], is_private: bool) -> Result<[u8; 32
     Err(CryptoError::PgpParsingFailed(format!(
         "Could not find valid Ed25519 key material in {} bytes of PGP data using multiple strategies",
         key_bytes.len()
-    )))
+    ).into()))
 }
 
 /// Loads the private key from the IRONSHIELD_PRIVATE_KEY environment variable
@@ -350,40 +530,46 @@ fn try_extract_ed25519_key(key_bytes: &[u8], is_private: bool) -> Result<[u8; 32
 ///                                      (without -----BEGIN/END----- lines)
 ///                                      or raw base64-encoded Ed25519 private
 ///                                      key (legacy format)
+#[cfg(feature = "sign")]
 pub fn load_private_key_from_env() -> Result<SigningKey, CryptoError> {
-    let key_str: String = env::var("IRONSHIELD_PRIVATE_KEY")
-        .map_err(|_| CryptoError::MissingEnvironmentVariable("IRONSHIELD_PRIVATE_KEY".to_string()))?;
-
-    // Try PGP format first
-    match parse_key_simple(&key_str, true) {
-        Ok(key_array) => {
-            let signing_key: SigningKey = SigningKey::from_bytes(&key_array);
-            return Ok(signing_key);
-        }
-        Err(CryptoError::PgpParsingFailed(_)) | Err(CryptoError::Base64DecodingFailed(_)) => {
-            // Fall back to raw base64 format
+    traced!("load_private_key_from_env", Result<SigningKey, CryptoError>, {
+        key_fingerprint = tracing::field::Empty,
+    }, |key| { key_fingerprint = crate::key_fingerprint(&key.verifying_key().to_bytes()) }, {
+        let key_str: String = env::var("IRONSHIELD_PRIVATE_KEY")
+            .map_err(|_| CryptoError::MissingEnvironmentVariable("IRONSHIELD_PRIVATE_KEY".into()))?;
+
+        // Try PGP format first
+        #[cfg(feature = "pgp-keys")]
+        match parse_key_simple(&key_str, true) {
+            Ok(key_array) => {
+                let signing_key: SigningKey = SigningKey::from_bytes(&key_array);
+                return Ok(signing_key);
+            }
+            Err(CryptoError::PgpParsingFailed(_)) | Err(CryptoError::Base64DecodingFailed(_)) => {
+                // Fall back to raw base64 format
+            }
+            Err(e) => return Err(e), // Return other errors immediately
         }
-        Err(e) => return Err(e), // Return other errors immediately
-    }
 
-    // Fallback: try raw base64-encoded Ed25519 key (legacy format)
-    let key_bytes: Vec<u8> = STANDARD.decode(key_str.trim())
-        .map_err(|e| CryptoError::Base64DecodingFailed(format!("Private key (legacy fallback): {}", e)))?;
+        // Fallback: try raw base64-encoded Ed25519 key (legacy format)
+        let key_bytes: Vec<u8> = STANDARD.decode(key_str.trim())
+            .map_err(|e| CryptoError::Base64DecodingFailed(ErrorDetail::caused_by(format!("Private key (legacy fallback): {}", e), e)))?;
 
-    // Verify length for raw Ed25519 key
-    if key_bytes.len() != SECRET_KEY_LENGTH {
-        return Err(CryptoError::InvalidKeyFormat(
-            format!("Private key must be {} bytes (raw Ed25519) or valid PGP format, got {} bytes",
-                   SECRET_KEY_LENGTH, key_bytes.len())
-        ));
-    }
+        // Verify length for raw Ed25519 key
+        if key_bytes.len() != SECRET_KEY_LENGTH {
+            return Err(CryptoError::InvalidKeyFormat(
+                format!("Private key must be {} bytes (raw Ed25519) or valid PGP format, got {} bytes",
+                       SECRET_KEY_LENGTH, key_bytes.len())
+            .into()));
+        }
 
-    // Create signing key from raw bytes
-    let key_array: [u8; SECRET_KEY_LENGTH] = key_bytes.try_into()
-        .map_err(|_| CryptoError::InvalidKeyFormat("Failed to convert private key bytes".to_string()))?;
+        // Create signing key from raw bytes
+        let key_array: [u8; SECRET_KEY_LENGTH] = key_bytes.try_into()
+            .map_err(|_| CryptoError::InvalidKeyFormat("Failed to convert private key bytes".into()))?;
 
-    let signing_key: SigningKey = SigningKey::from_bytes(&key_array);
-    Ok(signing_key)
+        let signing_key: SigningKey = SigningKey::from_bytes(&key_array);
+        Ok(signing_key)
+    })
 }
 
 /// Loads the public key from the IRONSHIELD_PUBLIC_KEY environment variable
@@ -399,43 +585,49 @@ pub fn load_private_key_from_env() -> Result<SigningKey, CryptoError> {
 ///                            (without -----BEGIN/END----- lines)
 ///                            or raw base64-encoded Ed25519 public key
 ///                            (legacy format)
+#[cfg(feature = "sign")]
 pub fn load_public_key_from_env() -> Result<VerifyingKey, CryptoError> {
-    let key_str: String = env::var("IRONSHIELD_PUBLIC_KEY")
-        .map_err(|_| CryptoError::MissingEnvironmentVariable("IRONSHIELD_PUBLIC_KEY".to_string()))?;
-
-    // Try PGP format first
-    match parse_key_simple(&key_str, false) {
-        Ok(key_array) => {
-            let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&key_array)
-                .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid public key: {}", e)))?;
-            return Ok(verifying_key);
-        }
-        Err(CryptoError::PgpParsingFailed(_)) | Err(CryptoError::Base64DecodingFailed(_)) => {
-            // Fall back to raw base64 format
+    traced!("load_public_key_from_env", Result<VerifyingKey, CryptoError>, {
+        key_fingerprint = tracing::field::Empty,
+    }, |key| { key_fingerprint = crate::key_fingerprint(key.as_bytes()) }, {
+        let key_str: String = env::var("IRONSHIELD_PUBLIC_KEY")
+            .map_err(|_| CryptoError::MissingEnvironmentVariable("IRONSHIELD_PUBLIC_KEY".into()))?;
+
+        // Try PGP format first
+        #[cfg(feature = "pgp-keys")]
+        match parse_key_simple(&key_str, false) {
+            Ok(key_array) => {
+                let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&key_array)
+                    .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+                return Ok(verifying_key);
+            }
+            Err(CryptoError::PgpParsingFailed(_)) | Err(CryptoError::Base64DecodingFailed(_)) => {
+                // Fall back to raw base64 format
+            }
+            Err(e) => return Err(e), // Return other errors immediately
         }
-        Err(e) => return Err(e), // Return other errors immediately
-    }
 
-    // Fallback: try raw base64-encoded Ed25519 key (legacy format)
-    let key_bytes: Vec<u8> = STANDARD.decode(key_str.trim())
-        .map_err(|e| CryptoError::Base64DecodingFailed(format!("Public key (legacy fallback): {}", e)))?;
+        // Fallback: try raw base64-encoded Ed25519 key (legacy format)
+        let key_bytes: Vec<u8> = STANDARD.decode(key_str.trim())
+            .map_err(|e| CryptoError::Base64DecodingFailed(ErrorDetail::caused_by(format!("Public key (legacy fallback): {}", e), e)))?;
 
-    // Verify length for raw Ed25519 key
-    if key_bytes.len() != PUBLIC_KEY_LENGTH {
-        return Err(CryptoError::InvalidKeyFormat(
-            format!("Public key must be {} bytes (raw Ed25519) or valid PGP format, got {} bytes",
-                   PUBLIC_KEY_LENGTH, key_bytes.len())
-        ));
-    }
+        // Verify length for raw Ed25519 key
+        if key_bytes.len() != PUBLIC_KEY_LENGTH {
+            return Err(CryptoError::InvalidKeyFormat(
+                format!("Public key must be {} bytes (raw Ed25519) or valid PGP format, got {} bytes",
+                       PUBLIC_KEY_LENGTH, key_bytes.len())
+            .into()));
+        }
 
-    // Create verifying key from raw bytes
-    let key_array: [u8; PUBLIC_KEY_LENGTH] = key_bytes.try_into()
-        .map_err(|_| CryptoError::InvalidKeyFormat("Failed to convert public key bytes".to_string()))?;
+        // Create verifying key from raw bytes
+        let key_array: [u8; PUBLIC_KEY_LENGTH] = key_bytes.try_into()
+            .map_err(|_| CryptoError::InvalidKeyFormat("Failed to convert public key bytes".into()))?;
 
-    let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&key_array)
-        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid public key: {}", e)))?;
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&key_array)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
 
-    Ok(verifying_key)
+        Ok(verifying_key)
+    })
 }
 
 /// Creates a message to be signed from challenge data components
@@ -445,7 +637,7 @@ pub fn load_public_key_from_env() -> Result<VerifyingKey, CryptoError> {
 /// allowing it to be used during challenge creation.
 ///
 /// # Arguments
-/// * `random_nonce`:    The random nonce string
+/// * `random_nonce`:    The random nonce
 /// * `created_time`:    The challenge creation timestamp
 /// * `expiration_time`: The challenge expiration timestamp
 /// * `website_id`:      The website identifier
@@ -493,8 +685,10 @@ pub fn create_signing_message(
 /// let signature = generate_signature(&signing_key, "message to sign")?;
 /// # Ok::<(), ironshield_types::CryptoError>(())
 /// ```
+#[cfg(feature = "sign")]
 pub fn generate_signature(signing_key: &SigningKey, message: &str) -> Result<[u8; 64], CryptoError> {
     let signature: Signature = signing_key.sign(message.as_bytes());
+    metrics().incr(SIGNATURES_CREATED);
     Ok(signature.to_bytes())
 }
 
@@ -510,12 +704,13 @@ pub fn generate_signature(signing_key: &SigningKey, message: &str) -> Result<[u8
 /// * `Result<[u8; 64], CryptoError>`: The Ed25519 signature bytes or an error.
 ///
 /// # Example
-/// ```no_run
+#[cfg_attr(feature = "rng", doc = " ```no_run")]
+#[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
 /// use ironshield_types::{IronShieldChallenge, sign_challenge, SigningKey};
 ///
 /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
 /// let mut challenge = IronShieldChallenge::new(
-///     "test_website".to_string(),
+///     "test_website".parse().unwrap(),
 ///     100_000,
 ///     dummy_key,
 ///     [0x34; 32],
@@ -525,17 +720,16 @@ pub fn generate_signature(signing_key: &SigningKey, message: &str) -> Result<[u8
 /// let signature = sign_challenge(&challenge).unwrap();
 /// challenge.challenge_signature = signature;
 /// ```
+#[cfg(feature = "sign")]
 pub fn sign_challenge(challenge: &IronShieldChallenge) -> Result<[u8; 64], CryptoError> {
-    let signing_key: SigningKey = load_private_key_from_env()?;
-    let message: String = create_signing_message(
-        &challenge.random_nonce,
-        challenge.created_time,
-        challenge.expiration_time,
-        &challenge.website_id,
-        &challenge.challenge_param,
-        &challenge.public_key
-    );
-    generate_signature(&signing_key, &message)
+    traced!("sign_challenge", Result<[u8; 64], CryptoError>, {
+        website_id = %challenge.website_id,
+        key_fingerprint = %crate::key_fingerprint(&challenge.public_key),
+    }, {
+        let signing_key: SigningKey = load_private_key_from_env()?;
+        let message: String = challenge.signing_message();
+        generate_signature(&signing_key, &message)
+    })
 }
 
 /// Verifies a challenge signature using the public key from environment variables
@@ -551,12 +745,13 @@ pub fn sign_challenge(challenge: &IronShieldChallenge) -> Result<[u8; 64], Crypt
 /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
 ///
 /// # Example
-/// ```no_run
+#[cfg_attr(feature = "rng", doc = " ```no_run")]
+#[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
 /// use ironshield_types::{IronShieldChallenge, verify_challenge_signature, SigningKey};
 ///
 /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
 /// let challenge = IronShieldChallenge::new(
-///     "test_website".to_string(),
+///     "test_website".parse().unwrap(),
 ///     100_000,
 ///     dummy_key,
 ///     [0x34; 32],
@@ -565,24 +760,18 @@ pub fn sign_challenge(challenge: &IronShieldChallenge) -> Result<[u8; 64], Crypt
 /// // Verify the challenge (requires IRONSHIELD_PUBLIC_KEY environment variable)
 /// verify_challenge_signature(&challenge).unwrap();
 /// ```
+#[cfg(feature = "sign")]
 pub fn verify_challenge_signature(challenge: &IronShieldChallenge) -> Result<(), CryptoError> {
-    let verifying_key: VerifyingKey = load_public_key_from_env()?;
-
-    let message: String = create_signing_message(
-        &challenge.random_nonce,
-        challenge.created_time,
-        challenge.expiration_time,
-        &challenge.website_id,
-        &challenge.challenge_param,
-        &challenge.public_key
-    );
-    let signature: Signature = Signature::from_slice(&challenge.challenge_signature)
-        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid signature format: {}", e)))?;
-
-    verifying_key.verify(message.as_bytes(), &signature)
-        .map_err(|e| CryptoError::VerificationFailed(format!("Signature verification failed: {}", e)))?;
-
-    Ok(())
+    traced!("verify_challenge_signature", Result<(), CryptoError>, {
+        website_id = %challenge.website_id,
+        key_fingerprint = tracing::field::Empty,
+    }, {
+        let verifying_key: VerifyingKey = load_public_key_from_env()?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("key_fingerprint", tracing::field::display(crate::key_fingerprint(verifying_key.as_bytes())));
+
+        challenge.as_challenge_ref().verify_signature(&verifying_key)
+    })
 }
 
 /// Verifies a challenge signature using a provided public key
@@ -601,24 +790,12 @@ pub fn verify_challenge_signature_with_key(
     challenge: &IronShieldChallenge,
     public_key_bytes: &[u8; 32]
 ) -> Result<(), CryptoError> {
-    let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
-        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid public key: {}", e)))?;
-
-    let message: String = create_signing_message(
-        &challenge.random_nonce,
-        challenge.created_time,
-        challenge.expiration_time,
-        &challenge.website_id,
-        &challenge.challenge_param,
-        &challenge.public_key
-    );
-    let signature: Signature = Signature::from_slice(&challenge.challenge_signature)
-        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid signature format: {}", e)))?;
-
-    verifying_key.verify(message.as_bytes(), &signature)
-        .map_err(|e| CryptoError::VerificationFailed(format!("Signature verification failed: {}", e)))?;
-
-    Ok(())
+    traced!("verify_challenge_signature_with_key", Result<(), CryptoError>, {
+        website_id = %challenge.website_id,
+        key_fingerprint = %crate::key_fingerprint(public_key_bytes),
+    }, {
+        challenge.as_challenge_ref().verify_signature_with_key(public_key_bytes)
+    })
 }
 
 /// Generates a new Ed25519 keypair for testing purposes
@@ -634,9 +811,10 @@ pub fn verify_challenge_signature_with_key(
 /// use ironshield_types::generate_test_keypair;
 ///
 /// let (private_key_b64, public_key_b64) = generate_test_keypair();
-/// std::env::set_var("IRONSHIELD_PRIVATE_KEY", private_key_b64);
-/// std::env::set_var("IRONSHIELD_PUBLIC_KEY", public_key_b64);
+/// unsafe { std::env::set_var("IRONSHIELD_PRIVATE_KEY", private_key_b64); }
+/// unsafe { std::env::set_var("IRONSHIELD_PUBLIC_KEY", public_key_b64); }
 /// ```
+#[cfg(feature = "rng")]
 pub fn generate_test_keypair() -> (String, String) {
     let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
     let verifying_key: VerifyingKey = signing_key.verifying_key();
@@ -652,6 +830,7 @@ pub fn generate_test_keypair() -> (String, String) {
 /// This is a comprehensive validation function that checks:
 /// - Signature validity
 /// - Challenge expiration
+/// - Minimum age of wait challenges
 /// - Basic format validation
 ///
 /// # Arguments
@@ -659,22 +838,390 @@ pub fn generate_test_keypair() -> (String, String) {
 ///
 /// # Returns
 /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if invalid
+#[cfg(feature = "sign")]
 pub fn validate_challenge(challenge: &IronShieldChallenge) -> Result<(), CryptoError> {
-    // Check signature first
-    verify_challenge_signature(challenge)?;
+    validate_challenge_with_policy(challenge, &LifetimePolicy::default())
+}
+
+/// Verifies a challenge and checks it against a `LifetimePolicy`
+///
+/// Same checks as `validate_challenge`, except that a solution is
+/// still accepted during the policy's submission grace period after
+/// `expiration_time`.
+///
+/// # Arguments
+/// * `challenge`: The challenge to validate
+/// * `policy`:    The lifetime policy with the grace period to honor
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if invalid
+#[cfg(feature = "sign")]
+pub fn validate_challenge_with_policy(
+    challenge: &IronShieldChallenge,
+    policy: &LifetimePolicy
+) -> Result<(), CryptoError> {
+    let now: i64 = now_millis();
+    let result: Result<(), CryptoError> = verify_challenge_signature(challenge)
+        .and_then(|()| check_challenge_at(challenge, policy, now));
+    record_validation(challenge, now, result)
+}
 
-    // Check expiration
-    if challenge.is_expired() {
-        return Err(CryptoError::VerificationFailed("Challenge has expired".to_string()));
+/// Same checks as `validate_challenge_with_policy`, against a key the
+/// caller already loaded instead of `IRONSHIELD_PUBLIC_KEY`.
+///
+/// # Arguments
+/// * `challenge`:     The challenge to validate
+/// * `verifying_key`: The key the challenge must be signed with
+/// * `policy`:        The lifetime policy with the grace period to honor
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if invalid
+#[cfg(feature = "parallel")]
+pub(crate) fn validate_challenge_with_verifying_key(
+    challenge: &IronShieldChallenge,
+    verifying_key: &VerifyingKey,
+    policy: &LifetimePolicy
+) -> Result<(), CryptoError> {
+    let now: i64 = now_millis();
+    let result: Result<(), CryptoError> = challenge.as_challenge_ref()
+        .verify_signature(verifying_key)
+        .and_then(|()| check_challenge_at(challenge, policy, now));
+    record_validation(challenge, now, result)
+}
+
+#[cfg(feature = "sign")]
+fn record_validation(
+    challenge: &IronShieldChallenge,
+    now: i64,
+    result: Result<(), CryptoError>
+) -> Result<(), CryptoError> {
+    if result.is_ok() {
+        metrics().observe(VALIDATED_CHALLENGE_AGE_MS, (now - challenge.created_time) as f64);
+    }
+    count_outcome(result, CHALLENGES_VALIDATED, CHALLENGES_REJECTED)
+}
+
+/// The checks of `validate_challenge_with_policy` after the signature,
+/// at a given time.
+#[cfg(feature = "sign")]
+fn check_challenge_at(
+    challenge: &IronShieldChallenge,
+    policy: &LifetimePolicy,
+    now: i64
+) -> Result<(), CryptoError> {
+    // Check expiration, allowing for the submission grace period
+    if !policy.accepts_submission_at(challenge, now) {
+        return Err(CryptoError::VerificationFailed("Challenge has expired".into()));
     }
 
+    // Wait challenges must have been held for their minimum age
+    challenge.check_min_age_at(now).map_err(|e| CryptoError::VerificationFailed(e.into()))?;
+
     if challenge.website_id.is_empty() {
-        return Err(CryptoError::VerificationFailed("Empty website_id".to_string()));
+        return Err(CryptoError::VerificationFailed("Empty website_id".into()));
     }
 
     Ok(())
 }
 
+/// Creates the message covered by a token's `auth_signature`
+///
+/// # Arguments
+/// * `challenge_signature`: The signature of the solved challenge
+/// * `valid_for`:           The token expiration timestamp
+///
+/// # Returns
+/// * `String`: Canonical string representation for signing
+pub fn create_token_signing_message(
+    challenge_signature: &[u8; 64],
+    valid_for: i64
+) -> String {
+    format!(
+        "{}|{}",
+        hex::encode(challenge_signature),
+        valid_for
+    )
+}
+
+/// Issues a signed token for a solved challenge
+///
+/// The token expiration is derived from the policy's `token_ttl_ms`
+/// and the current time.
+///
+/// # Arguments
+/// * `challenge`:   The challenge that was solved
+/// * `signing_key`: The Ed25519 key used to sign the token
+/// * `policy`:      The lifetime policy defining the token TTL
+///
+/// # Returns
+/// * `Result<IronShieldToken, CryptoError>`: The signed token or an error
+#[cfg(feature = "sign")]
+pub fn issue_token(
+    challenge: &IronShieldChallenge,
+    signing_key: &SigningKey,
+    policy: &LifetimePolicy
+) -> Result<IronShieldToken, CryptoError> {
+    issue_token_with_body_digest(challenge, signing_key, policy, None)
+}
+
+/// Issues a signed token that also attests to a request body
+///
+/// The body digest is covered by the token's `auth_signature`, so the
+/// verifier can check the received body with `IronShieldToken::verify_body`.
+///
+/// # Arguments
+/// * `challenge`:   The challenge that was solved
+/// * `signing_key`: The Ed25519 key used to sign the token
+/// * `policy`:      The lifetime policy defining the token TTL
+/// * `body_digest`: SHA-256 of the body the client intends to send, if any
+///
+/// # Returns
+/// * `Result<IronShieldToken, CryptoError>`: The signed token or an error
+#[cfg(feature = "sign")]
+pub fn issue_token_with_body_digest(
+    challenge: &IronShieldChallenge,
+    signing_key: &SigningKey,
+    policy: &LifetimePolicy,
+    body_digest: Option<[u8; 32]>
+) -> Result<IronShieldToken, CryptoError> {
+    let valid_for: i64 = policy.token_expiration(now_millis());
+    let mut token = IronShieldToken::new(
+        challenge.challenge_signature,
+        valid_for,
+        signing_key.verifying_key().to_bytes(),
+        [0u8; 64],
+    );
+    token.body_digest = body_digest;
+    token.auth_signature = generate_signature(signing_key, &token.signing_message())?;
+
+    Ok(token)
+}
+
+/// Verifies a token's authentication signature using a provided public key
+///
+/// # Arguments
+/// * `token`:            The token to verify
+/// * `public_key_bytes`: The Ed25519 public key bytes to use for verification
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails
+pub fn verify_token_signature_with_key(
+    token: &IronShieldToken,
+    public_key_bytes: &[u8; 32]
+) -> Result<(), CryptoError> {
+    let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+
+    verify_token_signature_with_verifying_key(token, &verifying_key)
+}
+
+/// Verifies a token's authentication signature against an already
+/// decoded public key.
+pub(crate) fn verify_token_signature_with_verifying_key(
+    token: &IronShieldToken,
+    verifying_key: &VerifyingKey
+) -> Result<(), CryptoError> {
+    let message: String = token.signing_message();
+    let signature: Signature = Signature::from_slice(&token.auth_signature)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid signature format: {}", e), e)))?;
+
+    count_outcome(
+        verifying_key.verify(message.as_bytes(), &signature),
+        TOKEN_SIGNATURES_VERIFIED,
+        TOKEN_SIGNATURES_REJECTED,
+    ).map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Token signature verification failed: {}", e), e)))?;
+
+    Ok(())
+}
+
+/// Verifies a token's authentication signature after checking that it
+/// has not been revoked.
+///
+/// # Arguments
+/// * `token`:            The token to verify
+/// * `public_key_bytes`: The Ed25519 public key bytes to use for verification
+/// * `revoked`:          The current revocation set
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid and not revoked, error otherwise
+pub fn verify_token_with_revocations(
+    token: &IronShieldToken,
+    public_key_bytes: &[u8; 32],
+    revoked: &RevokedTokens
+) -> Result<(), CryptoError> {
+    revoked.check(token).map_err(|e| CryptoError::VerificationFailed(e.into()))?;
+    verify_token_signature_with_key(token, public_key_bytes)
+}
+
+/// Computes an HMAC-SHA256 tag laid out like an Ed25519 signature
+///
+/// The 32-byte tag fills the first half of the returned array and the
+/// second half is zero, so the tag fits the existing signature fields.
+///
+/// # Arguments
+/// * `hmac_key`: The key shared by issuer and verifier
+/// * `message`:  The message to tag
+///
+/// # Returns
+/// * `[u8; 64]`: The tag followed by 32 zero bytes
+pub fn generate_hmac_tag(hmac_key: &[u8; 32], message: &str) -> [u8; 64] {
+    let mut tag = [0u8; 64];
+    tag[..32].copy_from_slice(&crate::key_derivation::hmac_sha256(hmac_key, message.as_bytes()));
+    tag
+}
+
+/// Compares a received tag with the expected one in constant time.
+fn verify_hmac_tag(
+    hmac_key: &[u8; 32],
+    message: &str,
+    tag: &[u8; 64],
+    subject: &str
+) -> Result<(), CryptoError> {
+    let expected: [u8; 64] = generate_hmac_tag(hmac_key, message);
+    let difference: u8 = expected.iter()
+        .zip(tag)
+        .fold(0, |acc, (expected, received)| acc | (expected ^ received));
+
+    if difference != 0 {
+        return Err(CryptoError::VerificationFailed(format!("{} HMAC tag does not match", subject).into()));
+    }
+    Ok(())
+}
+
+/// Verifies a challenge signed with `IronShieldChallenge::sign_hmac()`
+///
+/// # Arguments
+/// * `challenge`: The challenge to verify
+/// * `hmac_key`:  The key shared by issuer and verifier
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails
+pub fn verify_challenge_hmac(
+    challenge: &IronShieldChallenge,
+    hmac_key: &[u8; 32]
+) -> Result<(), CryptoError> {
+    if challenge.signature_scheme() != SignatureScheme::HmacSha256 {
+        return Err(CryptoError::VerificationFailed("Challenge is not signed with HMAC-SHA256".into()));
+    }
+    challenge.validate().map_err(|e| CryptoError::VerificationFailed(format!("Invalid challenge: {}", e).into()))?;
+    verify_hmac_tag(hmac_key, &challenge.signing_message(), &challenge.challenge_signature, "Challenge")
+}
+
+/// Issues a token for a solved challenge, tagged with a shared key
+///
+/// Like `issue_token()`, but the `auth_signature` holds an HMAC-SHA256
+/// tag and the token's `public_key` is all zeros.
+///
+/// # Arguments
+/// * `challenge`: The challenge that was solved
+/// * `hmac_key`:  The key shared by issuer and verifier
+/// * `policy`:    The lifetime policy defining the token TTL
+///
+/// # Returns
+/// * `IronShieldToken`: The tagged token
+#[cfg(feature = "sign")]
+pub fn issue_token_hmac(
+    challenge: &IronShieldChallenge,
+    hmac_key: &[u8; 32],
+    policy: &LifetimePolicy
+) -> IronShieldToken {
+    let valid_for: i64 = policy.token_expiration(now_millis());
+    let mut token = IronShieldToken::new(challenge.challenge_signature, valid_for, [0u8; 32], [0u8; 64]);
+    token.auth_signature = generate_hmac_tag(hmac_key, &token.signing_message());
+    token
+}
+
+/// Verifies a token issued with `issue_token_hmac()`
+///
+/// # Arguments
+/// * `token`:    The token to verify
+/// * `hmac_key`: The key shared by issuer and verifier
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails
+pub fn verify_token_hmac(
+    token: &IronShieldToken,
+    hmac_key: &[u8; 32]
+) -> Result<(), CryptoError> {
+    if token.public_key != [0u8; 32] {
+        return Err(CryptoError::VerificationFailed("Token is not signed with HMAC-SHA256".into()));
+    }
+    verify_hmac_tag(hmac_key, &token.signing_message(), &token.auth_signature, "Token")
+}
+
+/// Verifies a challenge under the scheme chosen during negotiation
+///
+/// # Arguments
+/// * `challenge`: The challenge to verify
+/// * `scheme`:    The negotiated signature scheme
+/// * `key`:       The Ed25519 public key, or the shared HMAC key
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails
+pub fn verify_challenge_signature_with_scheme(
+    challenge: &IronShieldChallenge,
+    scheme: SignatureScheme,
+    key: &[u8; 32]
+) -> Result<(), CryptoError> {
+    match scheme {
+        SignatureScheme::Ed25519    => verify_challenge_signature_with_key(challenge, key),
+        SignatureScheme::HmacSha256 => verify_challenge_hmac(challenge, key),
+    }
+}
+
+/// Issues a signed receipt for a rejected solution
+///
+/// The receipt is timestamped with the current time and signed with
+/// the edge key, so the client can later prove the rejection happened.
+///
+/// # Arguments
+/// * `challenge`:   The challenge whose solution was rejected
+/// * `reason`:      Why the solution was rejected
+/// * `signing_key`: The Ed25519 key used to sign the receipt
+///
+/// # Returns
+/// * `Result<RejectionReceipt, CryptoError>`: The signed receipt or an error
+#[cfg(feature = "sign")]
+pub fn issue_rejection_receipt(
+    challenge: &IronShieldChallenge,
+    reason: ErrorCode,
+    signing_key: &SigningKey
+) -> Result<RejectionReceipt, CryptoError> {
+    let mut receipt = RejectionReceipt {
+        challenge_id: challenge.challenge_id(),
+        reason,
+        rejected_at:  now_millis(),
+        public_key:   signing_key.verifying_key().to_bytes(),
+        signature:    [0u8; 64],
+    };
+    receipt.signature = generate_signature(signing_key, &receipt.signing_message())?;
+
+    Ok(receipt)
+}
+
+/// Verifies a rejection receipt's signature using a provided public key
+///
+/// # Arguments
+/// * `receipt`:          The receipt to verify
+/// * `public_key_bytes`: The Ed25519 public key bytes to use for verification
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails
+pub fn verify_rejection_receipt_with_key(
+    receipt: &RejectionReceipt,
+    public_key_bytes: &[u8; 32]
+) -> Result<(), CryptoError> {
+    let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+    let signature: Signature = Signature::from_slice(&receipt.signature)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid signature format: {}", e), e)))?;
+
+    verifying_key.verify(receipt.signing_message().as_bytes(), &signature)
+        .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Rejection receipt verification failed: {}", e), e)))?;
+
+    Ok(())
+}
+
 /// Loads a private key from raw key data (for Cloudflare Workers)
 ///
 /// This function is designed for use with Cloudflare Workers where secrets
@@ -685,44 +1232,50 @@ pub fn validate_challenge(challenge: &IronShieldChallenge) -> Result<(), CryptoE
 ///
 /// # Returns
 /// * `Result<SigningKey, CryptoError>`: The Ed25519 signing key or an error
+#[cfg(feature = "sign")]
 pub fn load_private_key_from_data(key_data: &str) -> Result<SigningKey, CryptoError> {
-    // Try PGP format first
-    match parse_key_simple(key_data, true) {
-        Ok(key_array) => {
-            let signing_key: SigningKey = SigningKey::from_bytes(&key_array);
-            return Ok(signing_key);
-        }
-        Err(CryptoError::PgpParsingFailed(_msg)) => {
-            // Fall back to raw base64 format
-        }
-        Err(CryptoError::Base64DecodingFailed(_msg)) => {
-            // Fall back to raw base64 format
-        }
-        Err(e) => {
-            return Err(e); // Return other errors immediately
+    traced!("load_private_key_from_data", Result<SigningKey, CryptoError>, {
+        key_fingerprint = tracing::field::Empty,
+    }, |key| { key_fingerprint = crate::key_fingerprint(&key.verifying_key().to_bytes()) }, {
+        // Try PGP format first
+        #[cfg(feature = "pgp-keys")]
+        match parse_key_simple(key_data, true) {
+            Ok(key_array) => {
+                let signing_key: SigningKey = SigningKey::from_bytes(&key_array);
+                return Ok(signing_key);
+            }
+            Err(CryptoError::PgpParsingFailed(_msg)) => {
+                // Fall back to raw base64 format
+            }
+            Err(CryptoError::Base64DecodingFailed(_msg)) => {
+                // Fall back to raw base64 format
+            }
+            Err(e) => {
+                return Err(e); // Return other errors immediately
+            }
         }
-    }
 
-    // Fallback: try raw base64-encoded Ed25519 key (legacy format)
-    let key_bytes: Vec<u8> = STANDARD.decode(key_data.trim())
-        .map_err(|e| {
-            CryptoError::Base64DecodingFailed(format!("Private key (legacy fallback): {}", e))
-        })?;
-
-    // Verify length for raw Ed25519 key
-    if key_bytes.len() != SECRET_KEY_LENGTH {
-        let error_msg = format!(
-            "Invalid key length: expected {} bytes for Ed25519 private key, got {} bytes",
-            SECRET_KEY_LENGTH,
-            key_bytes.len()
-        );
-        return Err(CryptoError::InvalidKeyFormat(error_msg));
-    }
+        // Fallback: try raw base64-encoded Ed25519 key (legacy format)
+        let key_bytes: Vec<u8> = STANDARD.decode(key_data.trim())
+            .map_err(|e| {
+                CryptoError::Base64DecodingFailed(ErrorDetail::caused_by(format!("Private key (legacy fallback): {}", e), e))
+            })?;
+
+        // Verify length for raw Ed25519 key
+        if key_bytes.len() != SECRET_KEY_LENGTH {
+            let error_msg = format!(
+                "Invalid key length: expected {} bytes for Ed25519 private key, got {} bytes",
+                SECRET_KEY_LENGTH,
+                key_bytes.len()
+            );
+            return Err(CryptoError::InvalidKeyFormat(error_msg.into()));
+        }
 
-    let mut key_array = [0u8; SECRET_KEY_LENGTH];
-    key_array.copy_from_slice(&key_bytes);
+        let mut key_array = [0u8; SECRET_KEY_LENGTH];
+        key_array.copy_from_slice(&key_bytes);
 
-    Ok(SigningKey::from_bytes(&key_array))
+        Ok(SigningKey::from_bytes(&key_array))
+    })
 }
 
 /// Loads a public key from raw key data (for Cloudflare Workers)
@@ -736,50 +1289,55 @@ pub fn load_private_key_from_data(key_data: &str) -> Result<SigningKey, CryptoEr
 /// # Returns
 /// * `Result<VerifyingKey, CryptoError>`: The Ed25519 verifying key or an error
 pub fn load_public_key_from_data(key_data: &str) -> Result<VerifyingKey, CryptoError> {
-    // Try PGP format first
-    match parse_key_simple(key_data, false) {
-        Ok(key_array) => {
-            let verifying_key = VerifyingKey::from_bytes(&key_array)
-                .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid public key from PGP: {}", e)))?;
-            return Ok(verifying_key);
-        }
-        Err(CryptoError::PgpParsingFailed(_msg)) => {
-            // Fall back to raw base64 format
-        }
-        Err(CryptoError::Base64DecodingFailed(_msg)) => {
-            // Fall back to raw base64 format
-        }
-        Err(e) => {
-            return Err(e); // Return other errors immediately
+    traced!("load_public_key_from_data", Result<VerifyingKey, CryptoError>, {
+        key_fingerprint = tracing::field::Empty,
+    }, |key| { key_fingerprint = crate::key_fingerprint(key.as_bytes()) }, {
+        // Try PGP format first
+        #[cfg(feature = "pgp-keys")]
+        match parse_key_simple(key_data, false) {
+            Ok(key_array) => {
+                let verifying_key = VerifyingKey::from_bytes(&key_array)
+                    .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key from PGP: {}", e), e)))?;
+                return Ok(verifying_key);
+            }
+            Err(CryptoError::PgpParsingFailed(_msg)) => {
+                // Fall back to raw base64 format
+            }
+            Err(CryptoError::Base64DecodingFailed(_msg)) => {
+                // Fall back to raw base64 format
+            }
+            Err(e) => {
+                return Err(e); // Return other errors immediately
+            }
         }
-    }
 
-    // Fallback: try raw base64-encoded Ed25519 key (legacy format)
-    let key_bytes: Vec<u8> = STANDARD.decode(key_data.trim())
-        .map_err(|e| {
-            CryptoError::Base64DecodingFailed(format!("Public key (legacy fallback): {}", e))
-        })?;
-
-    // Verify length for raw Ed25519 key
-    if key_bytes.len() != PUBLIC_KEY_LENGTH {
-        let error_msg = format!(
-            "Invalid key length: expected {} bytes for Ed25519 public key, got {} bytes",
-            PUBLIC_KEY_LENGTH,
-            key_bytes.len()
-        );
-        return Err(CryptoError::InvalidKeyFormat(error_msg));
-    }
+        // Fallback: try raw base64-encoded Ed25519 key (legacy format)
+        let key_bytes: Vec<u8> = STANDARD.decode(key_data.trim())
+            .map_err(|e| {
+                CryptoError::Base64DecodingFailed(ErrorDetail::caused_by(format!("Public key (legacy fallback): {}", e), e))
+            })?;
+
+        // Verify length for raw Ed25519 key
+        if key_bytes.len() != PUBLIC_KEY_LENGTH {
+            let error_msg = format!(
+                "Invalid key length: expected {} bytes for Ed25519 public key, got {} bytes",
+                PUBLIC_KEY_LENGTH,
+                key_bytes.len()
+            );
+            return Err(CryptoError::InvalidKeyFormat(error_msg.into()));
+        }
 
-    let mut key_array = [0u8; PUBLIC_KEY_LENGTH];
-    key_array.copy_from_slice(&key_bytes);
+        let mut key_array = [0u8; PUBLIC_KEY_LENGTH];
+        key_array.copy_from_slice(&key_bytes);
 
-    let verifying_key = VerifyingKey::from_bytes(&key_array)
-        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid Ed25519 public key: {}", e)))?;
+        let verifying_key = VerifyingKey::from_bytes(&key_array)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid Ed25519 public key: {}", e), e)))?;
 
-    Ok(verifying_key)
+        Ok(verifying_key)
+    })
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rng"))]
 mod tests {
     use super::*;
     use std::env;
@@ -799,8 +1357,8 @@ mod tests {
 
         // Set environment variables with mutex protection
         let _lock = ENV_MUTEX.lock().unwrap();
-        env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key);
-        env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key);
+        unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key); }
+        unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key); }
 
         (signing_key, verifying_key)
     }
@@ -827,7 +1385,7 @@ mod tests {
 
         // Create a challenge with the public key
         let challenge = IronShieldChallenge::new(
-            "example.com".to_string(),
+            "example.com".parse().unwrap(),
             100_000,
             signing_key.clone(),
             verifying_key.to_bytes(),
@@ -835,7 +1393,7 @@ mod tests {
 
         // Create the signing message manually
         let signing_message = create_signing_message(
-            &challenge.random_nonce,
+            challenge.random_nonce.as_str(),
             challenge.created_time,
             challenge.expiration_time,
             &challenge.website_id,
@@ -846,7 +1404,7 @@ mod tests {
 
         // The challenge should already be signed, so let's verify it
         let verification_message = create_signing_message(
-            &challenge.random_nonce,
+            challenge.random_nonce.as_str(),
             challenge.created_time,
             challenge.expiration_time,
             &challenge.website_id,
@@ -892,8 +1450,8 @@ mod tests {
             let private_key: String = STANDARD.encode(signing_key.to_bytes());
             let public_key: String = STANDARD.encode(verifying_key.to_bytes());
 
-            env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key);
-            env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key);
+            unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key); }
+            unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key); }
 
             (signing_key, verifying_key)
         };
@@ -912,8 +1470,8 @@ mod tests {
         let _lock = ENV_MUTEX.lock().unwrap();
 
         // Remove environment variables for this test
-        env::remove_var("IRONSHIELD_PRIVATE_KEY");
-        env::remove_var("IRONSHIELD_PUBLIC_KEY");
+        unsafe { env::remove_var("IRONSHIELD_PRIVATE_KEY"); }
+        unsafe { env::remove_var("IRONSHIELD_PUBLIC_KEY"); }
 
         // Should fail with appropriate errors
         let private_result = load_private_key_from_env();
@@ -930,8 +1488,8 @@ mod tests {
         let _lock = ENV_MUTEX.lock().unwrap();
 
         // Set invalid keys
-        env::set_var("IRONSHIELD_PRIVATE_KEY", "invalid-base64!");
-        env::set_var("IRONSHIELD_PUBLIC_KEY", "invalid-base64!");
+        unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", "invalid-base64!"); }
+        unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", "invalid-base64!"); }
 
         let private_result = load_private_key_from_env();
         assert!(private_result.is_err());
@@ -942,6 +1500,23 @@ mod tests {
         assert!(matches!(public_result.unwrap_err(), CryptoError::Base64DecodingFailed(_)));
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_error_source_chaining() {
+        let (signing_key, verifying_key) = setup_isolated_test_keys();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, signing_key, verifying_key.to_bytes());
+        let other_key: [u8; 32] = SigningKey::from_bytes(&[9; 32]).verifying_key().to_bytes();
+
+        let error: CryptoError = verify_challenge_signature_with_key(&challenge, &other_key).unwrap_err();
+        assert!(matches!(error, CryptoError::VerificationFailed(_)));
+        assert!(error.source().unwrap().downcast_ref::<ed25519_dalek::SignatureError>().is_some());
+        assert!(error.to_string().starts_with("Verification failed: Signature verification failed: "));
+
+        let error: CryptoError = load_public_key_from_data("invalid-base64!").unwrap_err();
+        assert!(error.source().unwrap().downcast_ref::<base64::DecodeError>().is_some());
+        assert!(CryptoError::SigningFailed("no key".into()).source().is_none());
+    }
+
     #[test]
     fn test_challenge_signing_and_verification() {
         let _lock = ENV_MUTEX.lock().unwrap();
@@ -953,15 +1528,15 @@ mod tests {
             let private_key: String = STANDARD.encode(signing_key.to_bytes());
             let public_key: String = STANDARD.encode(verifying_key.to_bytes());
 
-            env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key);
-            env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key);
+            unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key); }
+            unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key); }
 
             (signing_key, verifying_key)
         };
 
         // Create a test challenge - it will be automatically signed
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             signing_key.clone(),
             verifying_key.to_bytes(),
@@ -988,15 +1563,15 @@ mod tests {
             let private_key: String = STANDARD.encode(signing_key.to_bytes());
             let public_key: String = STANDARD.encode(verifying_key.to_bytes());
 
-            env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key);
-            env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key);
+            unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key); }
+            unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key); }
 
             (signing_key, verifying_key)
         };
 
         // Create and sign a challenge - signature is generated automatically
         let mut challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             signing_key.clone(),
             verifying_key.to_bytes(),
@@ -1006,7 +1581,7 @@ mod tests {
         verify_challenge_signature(&challenge).unwrap();
 
         // Tamper with the challenge
-        challenge.random_nonce = "tampered".to_string();
+        challenge.random_nonce = "abcd".parse().unwrap();
 
         // Verification should fail
         let result = verify_challenge_signature(&challenge);
@@ -1024,14 +1599,14 @@ mod tests {
             let private_key: String = STANDARD.encode(signing_key.to_bytes());
             let public_key: String = STANDARD.encode(verifying_key.to_bytes());
 
-            env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key);
-            env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key);
+            unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key); }
+            unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key); }
         }
 
         // Create a challenge that will be properly signed
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let mut challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             dummy_key,
             [0x34; 32],
@@ -1049,14 +1624,14 @@ mod tests {
     fn test_signing_message_creation() {
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             dummy_key,
             [0x34; 32],
         );
 
         let message = create_signing_message(
-            &challenge.random_nonce,
+            challenge.random_nonce.as_str(),
             challenge.created_time,
             challenge.expiration_time,
             &challenge.website_id,
@@ -1076,6 +1651,161 @@ mod tests {
         assert!(message.ends_with(&hex::encode(challenge.public_key)));
     }
 
+    #[test]
+    fn test_validate_challenge_with_policy_grace_period() {
+        let _lock = ENV_MUTEX.lock().unwrap();
+
+        let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
+        let verifying_key: VerifyingKey = signing_key.verifying_key();
+        unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", STANDARD.encode(verifying_key.to_bytes())); }
+
+        // A challenge that expired one second ago.
+        let expired_policy = LifetimePolicy::new(1, 0, 60_000).unwrap();
+        let mut challenge = IronShieldChallenge::new_with_policy(
+            "test_website".parse().unwrap(),
+            100_000,
+            signing_key.clone(),
+            verifying_key.to_bytes(),
+            &expired_policy,
+        );
+        challenge.created_time -= 1_001;
+        challenge.expiration_time -= 1_001;
+        let message = create_signing_message(
+            challenge.random_nonce.as_str(),
+            challenge.created_time,
+            challenge.expiration_time,
+            &challenge.website_id,
+            &challenge.challenge_param,
+            &challenge.public_key
+        );
+        challenge.challenge_signature = generate_signature(&signing_key, &message).unwrap();
+
+        let strict = LifetimePolicy::default();
+        assert!(validate_challenge_with_policy(&challenge, &strict).is_err());
+        assert!(validate_challenge(&challenge).is_err());
+
+        let lenient = LifetimePolicy::new(30_000, 10_000, 60_000).unwrap();
+        assert!(validate_challenge_with_policy(&challenge, &lenient).is_ok());
+    }
+
+    #[test]
+    fn test_validate_wait_challenge_min_age() {
+        let _lock = ENV_MUTEX.lock().unwrap();
+
+        let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
+        let verifying_key: VerifyingKey = signing_key.verifying_key();
+        unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", STANDARD.encode(verifying_key.to_bytes())); }
+
+        let fresh = IronShieldChallenge::new("test_website".parse().unwrap(), 100_000, signing_key.clone(), verifying_key.to_bytes())
+            .with_min_age(10_000, &signing_key)
+            .unwrap();
+        let err = validate_challenge(&fresh).unwrap_err();
+        assert!(err.to_string().contains("too early"));
+
+        let mut held = fresh.clone();
+        held.created_time -= 10_000;
+        held.sign(&signing_key);
+        assert!(validate_challenge(&held).is_ok());
+    }
+
+    #[test]
+    fn test_issue_and_verify_token() {
+        let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new(
+            "test_website".parse().unwrap(),
+            100_000,
+            signing_key.clone(),
+            public_key,
+        );
+        let policy = LifetimePolicy::new(30_000, 0, 120_000).unwrap();
+
+        let before: i64 = now_millis();
+        let token = issue_token(&challenge, &signing_key, &policy).unwrap();
+        let after: i64 = now_millis();
+
+        assert_eq!(token.challenge_signature, challenge.challenge_signature);
+        assert_eq!(token.public_key, public_key);
+        assert!(token.valid_for >= before + 120_000 && token.valid_for <= after + 120_000);
+        verify_token_signature_with_key(&token, &public_key).unwrap();
+
+        // Extending the validity must invalidate the signature.
+        let mut tampered = token.clone();
+        tampered.valid_for += 1;
+        let result = verify_token_signature_with_key(&tampered, &public_key);
+        assert!(matches!(result.unwrap_err(), CryptoError::VerificationFailed(_)));
+    }
+
+    #[test]
+    fn test_hmac_mode() {
+        let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        let hmac_key: [u8; 32] = [0x42; 32];
+        let mut challenge = IronShieldChallenge::new("test_website".parse().unwrap(), 100_000, signing_key, public_key);
+        assert_eq!(challenge.signature_scheme(), SignatureScheme::Ed25519);
+        assert!(verify_challenge_hmac(&challenge, &hmac_key).is_err());
+
+        challenge.sign_hmac(&hmac_key);
+        assert_eq!(challenge.signature_scheme(), SignatureScheme::HmacSha256);
+        assert_eq!(challenge.challenge_signature[32..], [0u8; 32]);
+        verify_challenge_signature_with_scheme(&challenge, SignatureScheme::HmacSha256, &hmac_key).unwrap();
+        assert!(verify_challenge_hmac(&challenge, &[0x43; 32]).is_err());
+        assert!(verify_challenge_signature_with_scheme(&challenge, SignatureScheme::Ed25519, &public_key).is_err());
+
+        let mut tampered = challenge.clone();
+        tampered.expiration_time += 1;
+        assert!(matches!(verify_challenge_hmac(&tampered, &hmac_key), Err(CryptoError::VerificationFailed(_))));
+
+        let policy = LifetimePolicy::new(30_000, 0, 120_000).unwrap();
+        let token = issue_token_hmac(&challenge, &hmac_key, &policy);
+        verify_token_hmac(&token, &hmac_key).unwrap();
+        let extended = IronShieldToken { valid_for: token.valid_for + 1, ..token.clone() };
+        assert!(verify_token_hmac(&extended, &hmac_key).is_err());
+        assert!(verify_token_signature_with_key(&token, &public_key).is_err());
+    }
+
+    #[test]
+    fn test_verify_token_with_revocations() {
+        let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new(
+            "test_website".parse().unwrap(),
+            100_000,
+            signing_key.clone(),
+            public_key,
+        );
+        let token = issue_token(&challenge, &signing_key, &LifetimePolicy::default()).unwrap();
+
+        let mut revoked = RevokedTokens::new();
+        verify_token_with_revocations(&token, &public_key, &revoked).unwrap();
+
+        revoked.revoke(&token);
+        let result = verify_token_with_revocations(&token, &public_key, &revoked);
+        assert!(result.unwrap_err().to_string().contains("Token has been revoked"));
+    }
+
+    #[test]
+    fn test_issue_token_with_body_digest() {
+        let signing_key: SigningKey = SigningKey::generate(&mut OsRng);
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new(
+            "test_website".parse().unwrap(),
+            100_000,
+            signing_key.clone(),
+            public_key,
+        );
+        let digest: [u8; 32] = crate::compute_body_digest(b"comment=hello");
+
+        let token = issue_token_with_body_digest(&challenge, &signing_key, &LifetimePolicy::default(), Some(digest)).unwrap();
+        verify_token_signature_with_key(&token, &public_key).unwrap();
+        token.verify_body(b"comment=hello").unwrap();
+
+        // Swapping the digest must invalidate the signature.
+        let mut tampered = token.clone();
+        tampered.body_digest = Some(crate::compute_body_digest(b"comment=spam"));
+        assert!(verify_token_signature_with_key(&tampered, &public_key).is_err());
+    }
+
     #[test]
     fn test_sign_challenge_uses_generate_signature() {
         let _lock = ENV_MUTEX.lock().unwrap();
@@ -1087,15 +1817,15 @@ mod tests {
             let private_key: String = STANDARD.encode(signing_key.to_bytes());
             let public_key: String = STANDARD.encode(verifying_key.to_bytes());
 
-            env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key);
-            env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key);
+            unsafe { env::set_var("IRONSHIELD_PRIVATE_KEY", &private_key); }
+            unsafe { env::set_var("IRONSHIELD_PUBLIC_KEY", &public_key); }
 
             (signing_key, verifying_key)
         };
 
         // Create a test challenge - it will be automatically signed
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             signing_key.clone(),
             verifying_key.to_bytes(),
@@ -1105,7 +1835,7 @@ mod tests {
         let sign_challenge_result = sign_challenge(&challenge).unwrap();
 
         let message = create_signing_message(
-            &challenge.random_nonce,
+            challenge.random_nonce.as_str(),
             challenge.created_time,
             challenge.expiration_time,
             &challenge.website_id,
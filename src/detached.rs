@@ -0,0 +1,140 @@
+//! # Detached signatures for audits.
+//!
+//! Operators can publish the signed payload of a challenge and its
+//! signature separately, e.g. to an audit log. The signature travels in
+//! a small base64 armor so it survives copy and paste; the payload is
+//! the exact bytes that were signed (`detached_payload()`, or
+//! `canonical_cbor()` for binary-mode signatures).
+
+use base64::{
+    Engine,
+    engine::general_purpose::STANDARD
+};
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+
+use crate::{
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge
+};
+
+const ARMOR_BEGIN: &str = "-----BEGIN IRONSHIELD SIGNATURE-----";
+const ARMOR_END:   &str = "-----END IRONSHIELD SIGNATURE-----";
+
+/// Wraps a signature in base64 armor.
+///
+/// # Arguments
+/// * `signature`: The raw Ed25519 signature.
+///
+/// # Returns
+/// * `String`: The armored signature, ending in a newline.
+pub fn armor_signature(signature: &[u8; 64]) -> String {
+    format!("{}\n{}\n{}\n", ARMOR_BEGIN, STANDARD.encode(signature), ARMOR_END)
+}
+
+/// Reverses `armor_signature`. Surrounding whitespace and `\r\n` line
+/// endings are accepted.
+///
+/// # Arguments
+/// * `armored`: The armored signature.
+///
+/// # Returns
+/// * `Result<[u8; 64], String>`: The raw signature or an error message.
+pub fn dearmor_signature(armored: &str) -> Result<[u8; 64], String> {
+    let mut lines = armored.trim().lines().map(str::trim);
+
+    if lines.next() != Some(ARMOR_BEGIN) {
+        return Err("Missing signature armor header".to_string());
+    }
+    let body: &str = lines.next().ok_or("Missing signature armor body")?;
+    if lines.next() != Some(ARMOR_END) {
+        return Err("Missing signature armor footer".to_string());
+    }
+    if lines.next().is_some() {
+        return Err("Unexpected data after signature armor footer".to_string());
+    }
+
+    STANDARD.decode(body)
+        .map_err(|e| format!("Failed to decode signature armor: {}", e))?
+        .try_into()
+        .map_err(|_| "Signature must be exactly 64 bytes".to_string())
+}
+
+/// Verifies a detached signature over a published payload.
+///
+/// # Arguments
+/// * `payload`:            The exact bytes that were signed.
+/// * `armored_signature`:  The signature from `armor_signature`.
+/// * `public_key_bytes`:   The Ed25519 public key bytes to use for verification.
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if valid, error if the armor is
+///                              malformed or verification fails.
+pub fn verify_detached(
+    payload: &[u8],
+    armored_signature: &str,
+    public_key_bytes: &[u8; 32]
+) -> Result<(), CryptoError> {
+    let signature_bytes: [u8; 64] = dearmor_signature(armored_signature)
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid signature format: {}", e).into()))?;
+    let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+
+    verifying_key.verify(payload, &Signature::from_bytes(&signature_bytes))
+        .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Signature verification failed: {}", e), e)))?;
+
+    Ok(())
+}
+
+impl IronShieldChallenge {
+    /// # Returns
+    /// * `Vec<u8>`: The bytes covered by `challenge_signature`, i.e.
+    ///              `signing_message()` as UTF-8.
+    pub fn detached_payload(&self) -> Vec<u8> {
+        self.signing_message().into_bytes()
+    }
+
+    /// # Returns
+    /// * `String`: `challenge_signature` in base64 armor, to be
+    ///             published next to `detached_payload()`.
+    pub fn export_detached_signature(&self) -> String {
+        armor_signature(&self.challenge_signature)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_export_and_verify_detached() {
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key, public_key);
+
+        let payload: Vec<u8> = challenge.detached_payload();
+        let armored: String = challenge.export_detached_signature();
+        assert!(armored.starts_with(ARMOR_BEGIN));
+        assert_eq!(dearmor_signature(&armored.replace('\n', "\r\n")).unwrap(), challenge.challenge_signature);
+
+        verify_detached(&payload, &armored, &public_key).unwrap();
+        assert!(matches!(verify_detached(b"tampered", &armored, &public_key), Err(CryptoError::VerificationFailed(_))));
+        assert!(matches!(verify_detached(&payload, "garbage", &public_key), Err(CryptoError::InvalidKeyFormat(_))));
+    }
+
+    #[test]
+    fn test_dearmor_rejects_malformed_input() {
+        let armored: String = armor_signature(&[7; 64]);
+        assert!(dearmor_signature(&armored[..armored.len() - 10]).unwrap_err().contains("footer"));
+        assert!(dearmor_signature(&format!("{}trailing\n", armored)).unwrap_err().contains("after"));
+        assert!(dearmor_signature(&armor_signature(&[7; 64]).replace("BwcH", "!!!!")).is_err());
+
+        let short: String = format!("{}\n{}\n{}\n", ARMOR_BEGIN, STANDARD.encode([7; 32]), ARMOR_END);
+        assert!(dearmor_signature(&short).unwrap_err().contains("64 bytes"));
+    }
+}
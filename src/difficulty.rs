@@ -0,0 +1,232 @@
+//! # Per-website and per-path difficulty overrides.
+//!
+//! The Worker's policy engine picks a difficulty for every challenge it
+//! issues. `DifficultyOverrides` is the shared table it resolves against,
+//! so a login path can be made harder than the rest of a site without
+//! each consumer parsing its own JSON map.
+
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Named difficulty levels for configuration files.
+///
+/// * `Low`:     Barely noticeable, for low-risk pages.
+/// * `Medium`:  The default for protected pages.
+/// * `High`:    For sensitive endpoints such as login forms.
+/// * `Extreme`: For active attacks; several seconds on a desktop.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DifficultyPreset {
+    Low,
+    Medium,
+    High,
+    Extreme,
+}
+
+impl DifficultyPreset {
+    /// # Returns
+    /// * `u64`: The expected number of attempts for the preset.
+    pub fn difficulty(&self) -> u64 {
+        match self {
+            DifficultyPreset::Low     =>     10_000,
+            DifficultyPreset::Medium  =>    100_000,
+            DifficultyPreset::High    =>  1_000_000,
+            DifficultyPreset::Extreme => 10_000_000,
+        }
+    }
+}
+
+/// A single override value: either an explicit difficulty or a preset.
+///
+/// Serialized untagged, so JSON accepts `250000` as well as `"high"`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DifficultySetting {
+    /// Explicit expected number of attempts.
+    Difficulty(u64),
+    /// Named preset.
+    Preset(DifficultyPreset),
+}
+
+impl DifficultySetting {
+    /// # Returns
+    /// * `u64`: The expected number of attempts for this setting.
+    pub fn difficulty(&self) -> u64 {
+        match self {
+            DifficultySetting::Difficulty(difficulty) => *difficulty,
+            DifficultySetting::Preset(preset)         => preset.difficulty(),
+        }
+    }
+}
+
+impl From<u64> for DifficultySetting {
+    fn from(difficulty: u64) -> Self {
+        DifficultySetting::Difficulty(difficulty)
+    }
+}
+
+impl From<DifficultyPreset> for DifficultySetting {
+    fn from(preset: DifficultyPreset) -> Self {
+        DifficultySetting::Preset(preset)
+    }
+}
+
+/// Difficulty override table.
+///
+/// Lookups prefer the longest matching path prefix, then the website
+/// entry. Path prefixes match on segment boundaries, so `/api` covers
+/// `/api` and `/api/login` but not `/apis`.
+///
+/// * `websites`: Overrides keyed by `website_id`.
+/// * `paths`:    Overrides keyed by URL path prefix.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Difficulty overrides keyed by website_id and by path prefix"
+))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DifficultyOverrides {
+    /// Overrides keyed by website identifier
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub websites: BTreeMap<String, DifficultySetting>,
+    /// Overrides keyed by URL path prefix (e.g. `/login`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub paths:    BTreeMap<String, DifficultySetting>,
+}
+
+impl DifficultyOverrides {
+    /// # Returns
+    /// * `Self`: An empty override table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks that every override resolves to a usable difficulty and
+    /// every path key is an absolute path.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid entry.
+    pub fn validate(&self) -> Result<(), String> {
+        for (website_id, setting) in &self.websites {
+            if setting.difficulty() == 0 {
+                return Err(format!("Difficulty override for website {} cannot be zero", website_id));
+            }
+        }
+        for (prefix, setting) in &self.paths {
+            if !prefix.starts_with('/') {
+                return Err(format!("Path prefix must start with '/': {}", prefix));
+            }
+            if setting.difficulty() == 0 {
+                return Err(format!("Difficulty override for path {} cannot be zero", prefix));
+            }
+        }
+        Ok(())
+    }
+
+    /// # Arguments
+    /// * `website_id`: The website the challenge is issued for.
+    /// * `path`:       The URL path of the protected request.
+    ///
+    /// # Returns
+    /// * `Option<u64>`: The overriding difficulty, or `None` if no
+    ///                  entry applies.
+    pub fn lookup(&self, website_id: &str, path: &str) -> Option<u64> {
+        self.paths
+            .iter()
+            .filter(|(prefix, _)| Self::path_matches(prefix, path))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, setting)| setting)
+            .or_else(|| self.websites.get(website_id))
+            .map(DifficultySetting::difficulty)
+    }
+
+    /// # Arguments
+    /// * `website_id`: The website the challenge is issued for.
+    /// * `path`:       The URL path of the protected request.
+    /// * `default`:    The difficulty to use when nothing applies.
+    ///
+    /// # Returns
+    /// * `u64`: The overriding difficulty or `default`.
+    pub fn resolve(&self, website_id: &str, path: &str, default: u64) -> u64 {
+        self.lookup(website_id, path).unwrap_or(default)
+    }
+
+    /// Layers `other` on top of this table; entries in `other` replace
+    /// entries with the same key, all other entries are kept.
+    ///
+    /// # Arguments
+    /// * `other`: The more specific table (e.g. a tenant's overrides
+    ///            applied over global defaults).
+    pub fn merge(&mut self, other: DifficultyOverrides) {
+        self.websites.extend(other.websites);
+        self.paths.extend(other.paths);
+    }
+
+    fn path_matches(prefix: &str, path: &str) -> bool {
+        match path.strip_prefix(prefix) {
+            Some(rest) => rest.is_empty() || prefix.ends_with('/') || rest.starts_with('/'),
+            None       => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides() -> DifficultyOverrides {
+        serde_json::from_str(r#"{
+            "websites": { "example.com": "high", "quiet.example": 5000 },
+            "paths":    { "/api": 200000, "/api/login": "extreme" }
+        }"#).unwrap()
+    }
+
+    #[test]
+    fn test_deserializes_numbers_and_presets() {
+        let overrides = overrides();
+        assert_eq!(overrides.websites["example.com"], DifficultySetting::Preset(DifficultyPreset::High));
+        assert_eq!(overrides.websites["quiet.example"], DifficultySetting::Difficulty(5_000));
+        assert!(overrides.validate().is_ok());
+    }
+
+    #[test]
+    fn test_lookup_precedence() {
+        let overrides = overrides();
+        assert_eq!(overrides.lookup("example.com", "/api/login/step2"), Some(10_000_000));
+        assert_eq!(overrides.lookup("example.com", "/api/users"), Some(200_000));
+        assert_eq!(overrides.lookup("example.com", "/apis"), Some(1_000_000));
+        assert_eq!(overrides.lookup("other.example", "/"), None);
+        assert_eq!(overrides.resolve("other.example", "/", 42), 42);
+    }
+
+    #[test]
+    fn test_merge_replaces_matching_keys() {
+        let mut base = overrides();
+        let mut tenant = DifficultyOverrides::new();
+        tenant.websites.insert("example.com".to_string(), DifficultyPreset::Low.into());
+        tenant.paths.insert("/checkout".to_string(), 300_000.into());
+
+        base.merge(tenant);
+        assert_eq!(base.lookup("example.com", "/"), Some(10_000));
+        assert_eq!(base.lookup("quiet.example", "/"), Some(5_000));
+        assert_eq!(base.lookup("example.com", "/checkout"), Some(300_000));
+        assert_eq!(base.lookup("example.com", "/api"), Some(200_000));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_entries() {
+        let mut overrides = DifficultyOverrides::new();
+        overrides.websites.insert("example.com".to_string(), 0.into());
+        assert!(overrides.validate().unwrap_err().contains("cannot be zero"));
+
+        let mut overrides = DifficultyOverrides::new();
+        overrides.paths.insert("login".to_string(), 10.into());
+        assert!(overrides.validate().unwrap_err().contains("must start with '/'"));
+    }
+}
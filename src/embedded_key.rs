@@ -0,0 +1,205 @@
+//! # Verification keys baked into client binaries.
+//!
+//! `embed_public_key!` decodes a base64 Ed25519 public key while the
+//! crate using it compiles, so a typo fails the build instead of every
+//! verification at runtime. The result is an `EmbeddedPublicKey`
+//! suitable for a `static`; the curve point is decompressed once, on
+//! first use, and cached.
+
+use std::sync::OnceLock;
+
+use ed25519_dalek::VerifyingKey;
+
+use crate::{
+    ChallengeRef,
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge,
+    IronShieldToken
+};
+
+/// Length of a padded base64 encoding of a 32-byte key.
+const PADDED_KEY_LEN:   usize = 44;
+/// Length of the same encoding without its `=` padding.
+const UNPADDED_KEY_LEN: usize = 43;
+
+/// Embeds a base64-encoded (standard alphabet) 32-byte Ed25519 public
+/// key, failing compilation if the literal is not one.
+///
+/// Expands to a constant `EmbeddedPublicKey`.
+///
+/// # Example
+/// ```
+/// use ironshield_types::{embed_public_key, EmbeddedPublicKey};
+///
+/// static ISSUER_KEY: EmbeddedPublicKey = embed_public_key!("iojj3XQJ8ZX9UtstPLpdcspnCb8dlBIb83SIAbQPb1w=");
+/// assert!(ISSUER_KEY.verifying_key().is_ok());
+/// ```
+///
+/// ```compile_fail
+/// use ironshield_types::{embed_public_key, EmbeddedPublicKey};
+///
+/// static ISSUER_KEY: EmbeddedPublicKey = embed_public_key!("not a key");
+/// ```
+#[macro_export]
+macro_rules! embed_public_key {
+    ($key:expr) => {
+        const { $crate::EmbeddedPublicKey::from_bytes($crate::decode_embedded_public_key($key)) }
+    };
+}
+
+/// Decodes the literal passed to `embed_public_key!`. Panics, which is a
+/// compile error in the macro's `const` block, if it is not the base64
+/// encoding of exactly 32 bytes.
+#[doc(hidden)]
+pub const fn decode_embedded_public_key(encoded: &str) -> [u8; 32] {
+    let bytes: &[u8] = encoded.as_bytes();
+    let len: usize = match bytes.len() {
+        PADDED_KEY_LEN if bytes[UNPADDED_KEY_LEN] == b'=' => UNPADDED_KEY_LEN,
+        UNPADDED_KEY_LEN                                 => UNPADDED_KEY_LEN,
+        _ => panic!("embed_public_key!: expected the base64 encoding of a 32-byte key"),
+    };
+
+    let mut key: [u8; 32] = [0u8; 32];
+    let mut buffer: u32 = 0;
+    let mut buffered_bits: u32 = 0;
+    let mut written: usize = 0;
+    let mut i: usize = 0;
+    while i < len {
+        let value: u8 = match bytes[i] {
+            b'A'..=b'Z' => bytes[i] - b'A',
+            b'a'..=b'z' => bytes[i] - b'a' + 26,
+            b'0'..=b'9' => bytes[i] - b'0' + 52,
+            b'+'        => 62,
+            b'/'        => 63,
+            _ => panic!("embed_public_key!: invalid base64 character"),
+        };
+        buffer = (buffer << 6) | value as u32;
+        buffered_bits += 6;
+        if buffered_bits >= 8 {
+            buffered_bits -= 8;
+            key[written] = (buffer >> buffered_bits) as u8;
+            buffer &= (1 << buffered_bits) - 1;
+            written += 1;
+        }
+        i += 1;
+    }
+
+    if buffer != 0 {
+        panic!("embed_public_key!: non-canonical base64 encoding");
+    }
+
+    key
+}
+
+/// Ed25519 public key compiled into the binary, usually through
+/// `embed_public_key!`.
+#[derive(Debug)]
+pub struct EmbeddedPublicKey {
+    bytes:         [u8; 32],
+    verifying_key: OnceLock<VerifyingKey>,
+}
+
+impl EmbeddedPublicKey {
+    /// Constructor usable in `static` and `const` items.
+    ///
+    /// # Arguments
+    /// * `bytes`: The raw 32-byte Ed25519 public key.
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self {
+            bytes,
+            verifying_key: OnceLock::new(),
+        }
+    }
+
+    /// # Returns
+    /// * `&[u8; 32]`: The raw public key bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+
+    /// Decompresses the key on first call and returns the cached result
+    /// afterwards.
+    ///
+    /// # Returns
+    /// * `Result<&VerifyingKey, CryptoError>`: The key, or an error if the
+    ///                                         bytes are not a valid point.
+    pub fn verifying_key(&self) -> Result<&VerifyingKey, CryptoError> {
+        if let Some(verifying_key) = self.verifying_key.get() {
+            return Ok(verifying_key);
+        }
+
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&self.bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        Ok(self.verifying_key.get_or_init(|| verifying_key))
+    }
+
+    /// Verifies a challenge signature against the embedded key.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge to verify.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify_challenge(&self, challenge: &IronShieldChallenge) -> Result<(), CryptoError> {
+        self.verify_challenge_ref(&challenge.as_challenge_ref())
+    }
+
+    /// Same as `verify_challenge`, for a borrowed challenge view.
+    pub fn verify_challenge_ref(&self, challenge: &ChallengeRef) -> Result<(), CryptoError> {
+        challenge.verify_signature(self.verifying_key()?)
+    }
+
+    /// Verifies a token's authentication signature against the embedded key.
+    ///
+    /// # Arguments
+    /// * `token`: The token to verify.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify_token(&self, token: &IronShieldToken) -> Result<(), CryptoError> {
+        crate::crypto::verify_token_signature_with_verifying_key(token, self.verifying_key()?)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+    use base64::{
+        engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+        Engine
+    };
+
+    static ISSUER_KEY: EmbeddedPublicKey = crate::embed_public_key!("iojj3XQJ8ZX9UtstPLpdcspnCb8dlBIb83SIAbQPb1w=");
+
+    #[test]
+    fn test_const_decoder_matches_base64() {
+        for seed in 0u8..16 {
+            let public_key: [u8; 32] = SigningKey::from_bytes(&[seed; 32]).verifying_key().to_bytes();
+            assert_eq!(decode_embedded_public_key(&STANDARD.encode(public_key)), public_key);
+            assert_eq!(decode_embedded_public_key(&STANDARD_NO_PAD.encode(public_key)), public_key);
+        }
+    }
+
+    #[test]
+    fn test_embedded_key_verifies_challenges_and_tokens() {
+        let key = SigningKey::from_bytes(&[1; 32]);
+        assert_eq!(ISSUER_KEY.as_bytes(), &key.verifying_key().to_bytes());
+
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+        ISSUER_KEY.verify_challenge(&challenge).unwrap();
+
+        let token = crate::issue_token(&challenge, &key, &crate::LifetimePolicy::default()).unwrap();
+        ISSUER_KEY.verify_token(&token).unwrap();
+
+        let other = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, SigningKey::from_bytes(&[2; 32]), [0; 32]);
+        assert!(ISSUER_KEY.verify_challenge(&other).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid base64 character")]
+    fn test_rejects_invalid_characters() {
+        decode_embedded_public_key("A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdyGZBJVMb!=");
+    }
+}
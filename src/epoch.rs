@@ -0,0 +1,264 @@
+//! # Wall-clock time source for IronShield timestamps.
+//!
+//! Every timestamp in the IronShield protocol is a Unix timestamp in
+//! milliseconds (`i64`). This module is the single place the crate
+//! reads the current time from, so the backing time library can be
+//! swapped through cargo features without touching the public API.
+//! It also formats and parses timestamps as RFC 3339, so logs and
+//! error messages need no date library either.
+//!
+//! ## Backends
+//! * `chrono` (default): Uses `chrono::Utc::now()`.
+//! * `time`:             Uses `time::OffsetDateTime::now_utc()`.
+//! * `std-clock`:        Uses `std::time::SystemTime`. Build with
+//!                       `default-features = false` and
+//!                       `features = ["std-clock"]` to compile the crate
+//!                       without any date library, which noticeably
+//!                       shrinks WASM bundles for WASI targets.
+//! * `wasm` on wasm32:   Uses `js_sys::Date::now()`, which works in
+//!                       browsers and Workers without host shims.
+//!                       No other backend is needed then.
+//!
+//! On wasm32 with `wasm` enabled the JavaScript clock always wins;
+//! elsewhere `chrono` takes precedence over `time`, and `time` over
+//! `std-clock`.
+
+#[cfg(not(any(feature = "chrono", feature = "time", feature = "std-clock", all(feature = "wasm", target_arch = "wasm32"))))]
+compile_error!("ironshield-types requires the `chrono`, `time` or `std-clock` feature to be enabled (or `wasm` on wasm32).");
+
+/// Number of milliseconds in one day.
+const MILLIS_PER_DAY: i64 = 86_400_000;
+/// Length of `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+const RFC3339_MILLIS_LEN: usize = 24;
+
+/// Number of nanoseconds in one millisecond.
+#[cfg(all(feature = "time", not(feature = "chrono"), not(all(feature = "wasm", target_arch = "wasm32"))))]
+const NANOS_PER_MILLI: i128 = 1_000_000;
+
+/// Returns the current Unix timestamp in milliseconds.
+///
+/// # Returns
+/// * `i64`: Milliseconds elapsed since the Unix epoch (UTC).
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn now_millis() -> i64 {
+    js_sys::Date::now() as i64
+}
+
+/// Returns the current Unix timestamp in milliseconds.
+///
+/// # Returns
+/// * `i64`: Milliseconds elapsed since the Unix epoch (UTC).
+///
+/// # Example
+/// ```
+/// use ironshield_types::now_millis;
+///
+/// let now = now_millis();
+/// assert!(now > 1_700_000_000_000);
+/// ```
+#[cfg(all(feature = "chrono", not(all(feature = "wasm", target_arch = "wasm32"))))]
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Returns the current Unix timestamp in milliseconds.
+///
+/// # Returns
+/// * `i64`: Milliseconds elapsed since the Unix epoch (UTC).
+///
+/// # Example
+/// ```
+/// use ironshield_types::now_millis;
+///
+/// let now = now_millis();
+/// assert!(now > 1_700_000_000_000);
+/// ```
+#[cfg(all(feature = "time", not(feature = "chrono"), not(all(feature = "wasm", target_arch = "wasm32"))))]
+pub fn now_millis() -> i64 {
+    (time::OffsetDateTime::now_utc().unix_timestamp_nanos() / NANOS_PER_MILLI) as i64
+}
+
+/// Returns the current Unix timestamp in milliseconds.
+///
+/// # Returns
+/// * `i64`: Milliseconds elapsed since the Unix epoch (UTC), or 0 if
+///          the system clock is set before 1970.
+///
+/// # Example
+/// ```
+/// use ironshield_types::now_millis;
+///
+/// let now = now_millis();
+/// assert!(now > 1_700_000_000_000);
+/// ```
+#[cfg(all(
+    feature = "std-clock",
+    not(feature = "chrono"),
+    not(feature = "time"),
+    not(all(feature = "wasm", target_arch = "wasm32"))
+))]
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| i64::try_from(elapsed.as_millis()).unwrap_or(i64::MAX))
+        .unwrap_or(0)
+}
+
+/// Formats a timestamp as RFC 3339 in UTC with millisecond precision.
+///
+/// # Arguments
+/// * `millis`: Unix timestamp in milliseconds.
+///
+/// # Returns
+/// * `Result<String, String>`: `YYYY-MM-DDTHH:MM:SS.mmmZ`, or an error
+///                             if the year is outside 0000 to 9999.
+///
+/// # Example
+/// ```
+/// use ironshield_types::format_rfc3339_millis;
+///
+/// assert_eq!(format_rfc3339_millis(1_700_000_000_123).unwrap(), "2023-11-14T22:13:20.123Z");
+/// ```
+pub fn format_rfc3339_millis(millis: i64) -> Result<String, String> {
+    let (year, month, day) = civil_from_days(millis.div_euclid(MILLIS_PER_DAY));
+    if !(0..=9999).contains(&year) {
+        return Err(format!("Timestamp {} is outside the years 0000 to 9999", millis));
+    }
+    let time_of_day: i64 = millis.rem_euclid(MILLIS_PER_DAY);
+
+    Ok(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3_600_000,
+        time_of_day / 60_000 % 60,
+        time_of_day / 1_000 % 60,
+        time_of_day % 1_000
+    ))
+}
+
+/// Parses the form produced by `format_rfc3339_millis()`.
+///
+/// # Arguments
+/// * `value`: A timestamp as `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+///
+/// # Returns
+/// * `Result<i64, String>`: The Unix timestamp in milliseconds, or an
+///                          error if `value` is not in exactly that
+///                          form or names an invalid date or time.
+pub fn parse_rfc3339_millis(value: &str) -> Result<i64, String> {
+    let invalid = || format!("Expected YYYY-MM-DDTHH:MM:SS.mmmZ, got {:?}", value);
+    let bytes: &[u8] = value.as_bytes();
+    if bytes.len() != RFC3339_MILLIS_LEN
+        || [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':'), (19, b'.'), (23, b'Z')]
+            .iter()
+            .any(|&(index, separator)| bytes[index] != separator)
+    {
+        return Err(invalid());
+    }
+    let field = |start: usize, end: usize| -> Result<i64, String> {
+        let digits: &[u8] = &bytes[start..end];
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return Err(invalid());
+        }
+        Ok(digits.iter().fold(0, |value, digit| value * 10 + i64::from(digit - b'0')))
+    };
+
+    let (year, month, day) = (field(0, 4)?, field(5, 7)?, field(8, 10)?);
+    let (hour, minute, second, milli) = (field(11, 13)?, field(14, 16)?, field(17, 19)?, field(20, 23)?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(format!("Invalid date in {:?}", value));
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(format!("Invalid time in {:?}", value));
+    }
+
+    let seconds_of_day: i64 = hour * 3_600 + minute * 60 + second;
+    Ok(days_from_civil(year, month, day) * MILLIS_PER_DAY + seconds_of_day * 1_000 + milli)
+}
+
+/// # Returns
+/// * `i64`: Days in `month` of `year` in the proleptic Gregorian
+///          calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts a civil date to days since 1970-01-01, after Howard
+/// Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year: i64 = if month <= 2 { year - 1 } else { year };
+    let era: i64 = year.div_euclid(400);
+    let year_of_era: i64 = year.rem_euclid(400);
+    let day_of_year: i64 = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era: i64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Converts days since 1970-01-01 to a civil `(year, month, day)`, the
+/// inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days: i64 = days + 719_468;
+    let era: i64 = days.div_euclid(146_097);
+    let day_of_era: i64 = days.rem_euclid(146_097);
+    let year_of_era: i64 = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now_millis_is_monotonic_enough() {
+        let first: i64 = now_millis();
+        let second: i64 = now_millis();
+
+        assert!(first > 1_700_000_000_000, "Timestamp should be after November 2023");
+        assert!(second >= first, "Clock should not go backwards between two reads");
+    }
+
+    #[test]
+    fn test_now_millis_is_in_milliseconds() {
+        let millis: i64 = now_millis();
+        let secs: u64 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // Both readings should agree to within a couple of seconds.
+        assert!((millis / 1000 - secs as i64).abs() <= 2);
+    }
+
+    #[test]
+    fn test_rfc3339_roundtrip() {
+        let cases: [(i64, &str); 5] = [
+            (0,                   "1970-01-01T00:00:00.000Z"),
+            (-1,                  "1969-12-31T23:59:59.999Z"),
+            (951_782_400_000,     "2000-02-29T00:00:00.000Z"),
+            (1_700_000_000_123,   "2023-11-14T22:13:20.123Z"),
+            (253_402_300_799_999, "9999-12-31T23:59:59.999Z"),
+        ];
+        for (millis, formatted) in cases {
+            assert_eq!(format_rfc3339_millis(millis).unwrap(), formatted);
+            assert_eq!(parse_rfc3339_millis(formatted).unwrap(), millis);
+        }
+        assert!(format_rfc3339_millis(253_402_300_800_000).is_err());
+
+        for invalid in ["2023-11-14T22:13:20Z", "2023-11-14 22:13:20.123Z", "2023-02-29T00:00:00.000Z", "2023-11-14T24:00:00.000Z", "+023-11-14T22:13:20.123Z"] {
+            assert!(parse_rfc3339_millis(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! # Serializable error envelope returned by the issuer.
+//!
+//! Every failed IronShield request is answered with an
+//! `IronShieldErrorResponse`, so clients can branch on a stable
+//! `ErrorCode` and follow the attached `RetryPolicy` instead of parsing
+//! free-form messages.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::RetryPolicy;
+
+/// Stable machine-readable reason of an error response.
+///
+/// * `InvalidRequest`:     The request could not be parsed.
+/// * `InvalidSolution`:    The solution does not meet the target.
+/// * `Expired`:            The challenge or token has expired.
+/// * `VerificationFailed`: A signature or binding did not verify.
+/// * `RateLimited`:        The client exceeded an issuance quota.
+/// * `Internal`:           The issuer failed; the request may succeed later.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    InvalidSolution,
+    Expired,
+    VerificationFailed,
+    RateLimited,
+    Internal,
+}
+
+impl ErrorCode {
+    /// # Returns
+    /// * `&'static str`: The stable wire identifier of the code.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest     => "invalid_request",
+            ErrorCode::InvalidSolution    => "invalid_solution",
+            ErrorCode::Expired            => "expired",
+            ErrorCode::VerificationFailed => "verification_failed",
+            ErrorCode::RateLimited        => "rate_limited",
+            ErrorCode::Internal           => "internal",
+        }
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if repeating the request (with a fresh
+    ///           challenge) can succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorCode::Expired | ErrorCode::RateLimited | ErrorCode::Internal)
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ErrorCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "invalid_request"     => Ok(ErrorCode::InvalidRequest),
+            "invalid_solution"    => Ok(ErrorCode::InvalidSolution),
+            "expired"             => Ok(ErrorCode::Expired),
+            "verification_failed" => Ok(ErrorCode::VerificationFailed),
+            "rate_limited"        => Ok(ErrorCode::RateLimited),
+            "internal"            => Ok(ErrorCode::Internal),
+            other => Err(format!("Unknown error code: {}", other)),
+        }
+    }
+}
+
+/// Error envelope of the issuer's HTTP API.
+///
+/// * `code`:                 The machine-readable reason.
+/// * `message`:              A human-readable description.
+/// * `retry_policy`:         How the client should retry, if at all.
+/// * `retry_after_ms`:       Minimum wait before any retry, e.g. until
+///                           a quota window resets.
+/// * `suggested_difficulty`: Difficulty the next challenge is expected
+///                           to have, so the client can warn its user
+///                           or pick a stronger solver.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IronShieldErrorResponse {
+    pub code:                 ErrorCode,
+    pub message:              String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy:         Option<RetryPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms:       Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_difficulty: Option<u64>,
+}
+
+impl IronShieldErrorResponse {
+    /// Constructor for an error response without retry guidance.
+    ///
+    /// # Arguments
+    /// * `code`:    The machine-readable reason.
+    /// * `message`: A human-readable description.
+    ///
+    /// # Returns
+    /// * `Self`: The error response.
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message:              message.into(),
+            retry_policy:         None,
+            retry_after_ms:       None,
+            suggested_difficulty: None,
+        }
+    }
+
+    /// Attaches retry guidance.
+    ///
+    /// # Arguments
+    /// * `retry_policy`: How the client should retry.
+    ///
+    /// # Returns
+    /// * `Self`: The error response carrying the policy.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Tells the client to wait at least `retry_after_ms` before
+    /// retrying.
+    ///
+    /// # Arguments
+    /// * `retry_after_ms`: Milliseconds to wait.
+    ///
+    /// # Returns
+    /// * `Self`: The error response carrying the hint.
+    pub fn with_retry_after_ms(mut self, retry_after_ms: u64) -> Self {
+        self.retry_after_ms = Some(retry_after_ms);
+        self
+    }
+
+    /// Announces the difficulty of the next challenge.
+    ///
+    /// # Arguments
+    /// * `suggested_difficulty`: Expected number of attempts.
+    ///
+    /// # Returns
+    /// * `Self`: The error response carrying the hint.
+    pub fn with_suggested_difficulty(mut self, suggested_difficulty: u64) -> Self {
+        self.suggested_difficulty = Some(suggested_difficulty);
+        self
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if the client should retry at all: the attached
+    ///           policy allows a retry, or, without a policy, the code
+    ///           is retryable or a `retry_after_ms` hint is present.
+    pub fn should_retry(&self) -> bool {
+        match self.retry_policy {
+            Some(policy) => policy.max_retries > 0,
+            None         => self.code.is_retryable() || self.retry_after_ms.is_some(),
+        }
+    }
+
+    /// # Arguments
+    /// * `attempt`: Zero-based index of the retry about to be made.
+    /// * `random`:  A uniform random number in `[0, 1)`.
+    ///
+    /// # Returns
+    /// * `Option<u64>`: Milliseconds to wait before the retry, never
+    ///                  less than `retry_after_ms`, or `None` if the
+    ///                  client should give up.
+    pub fn retry_delay_ms(&self, attempt: u32, random: f64) -> Option<u64> {
+        if !self.should_retry() {
+            return None;
+        }
+        let policy: RetryPolicy = self.retry_policy.unwrap_or_default();
+        let delay: u64 = policy.delay_ms(attempt, random)?;
+        Some(delay.max(self.retry_after_ms.unwrap_or(0)))
+    }
+
+    /// # Returns
+    /// * `RetryPolicy`: The attached policy, or, when absent, the
+    ///                  default policy for retryable codes and
+    ///                  `RetryPolicy::no_retry()` for the rest.
+    pub fn effective_retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.unwrap_or_else(|| {
+            if self.code.is_retryable() { RetryPolicy::default() } else { RetryPolicy::no_retry() }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_response_serde() {
+        let response = IronShieldErrorResponse::new(ErrorCode::InvalidSolution, "Solution does not meet the target");
+        let json: String = serde_json::to_string(&response).unwrap();
+        assert_eq!(json, r#"{"code":"invalid_solution","message":"Solution does not meet the target"}"#);
+        assert_eq!(response.effective_retry_policy(), RetryPolicy::no_retry());
+
+        let policy = RetryPolicy { max_retries: 5, ..Default::default() };
+        let limited = IronShieldErrorResponse::new(ErrorCode::RateLimited, "Slow down").with_retry_policy(policy);
+        let decoded: IronShieldErrorResponse = serde_json::from_str(&serde_json::to_string(&limited).unwrap()).unwrap();
+        assert_eq!(decoded, limited);
+        assert_eq!(decoded.effective_retry_policy(), policy);
+        assert_eq!(IronShieldErrorResponse::new(ErrorCode::Internal, "").effective_retry_policy(), RetryPolicy::default());
+    }
+
+    #[test]
+    fn test_retry_hints() {
+        let rejected = IronShieldErrorResponse::new(ErrorCode::InvalidSolution, "Wrong nonce");
+        assert!(!rejected.should_retry());
+        assert_eq!(rejected.retry_delay_ms(0, 0.0), None);
+
+        let limited = IronShieldErrorResponse::new(ErrorCode::RateLimited, "Quota exhausted")
+            .with_retry_after_ms(10_000)
+            .with_suggested_difficulty(1_000_000);
+        assert_eq!(limited.retry_delay_ms(0, 0.0), Some(10_000));
+        assert_eq!(limited.retry_delay_ms(3, 0.0), None);
+
+        let json: String = serde_json::to_string(&limited).unwrap();
+        assert!(json.ends_with(r#""retry_after_ms":10000,"suggested_difficulty":1000000}"#));
+        assert_eq!(serde_json::from_str::<IronShieldErrorResponse>(&json).unwrap(), limited);
+
+        let stop = limited.with_retry_policy(RetryPolicy::no_retry());
+        assert!(!stop.should_retry());
+    }
+}
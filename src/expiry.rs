@@ -0,0 +1,178 @@
+//! # Expiry-ordered collections of challenges and tokens.
+//!
+//! Stores and pools keep issued values around until they expire. The
+//! `Expiring` trait exposes the expiry instant of both challenges and
+//! tokens so helpers in this module can work on either, and so every
+//! store sweeps expired values with the same boundary rule: a value is
+//! still valid at exactly its expiry instant.
+
+use crate::{
+    IronShieldChallenge,
+    IronShieldToken
+};
+
+/// A value that stops being valid at a fixed instant.
+pub trait Expiring {
+    /// # Returns
+    /// * `i64`: The last instant (unix millis) at which the value is
+    ///          still valid.
+    fn expires_at(&self) -> i64;
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the value is expired at `now`.
+    fn is_expired_at(&self, now: i64) -> bool {
+        now > self.expires_at()
+    }
+}
+
+impl Expiring for IronShieldChallenge {
+    fn expires_at(&self) -> i64 {
+        self.expiration_time
+    }
+}
+
+impl Expiring for IronShieldToken {
+    fn expires_at(&self) -> i64 {
+        self.valid_for
+    }
+}
+
+/// Keyed entries, e.g. drained from a `HashMap`, expire with their value.
+impl<K, T: Expiring> Expiring for (K, T) {
+    fn expires_at(&self) -> i64 {
+        self.1.expires_at()
+    }
+}
+
+/// Sorts values so the soonest to expire come first. Values expiring at
+/// the same instant keep their `Ord` order, so the result does not
+/// depend on the input order.
+///
+/// # Arguments
+/// * `items`: The values to sort in place.
+pub fn sort_by_expiry<T: Expiring + Ord>(items: &mut [T]) {
+    items.sort_by(|a, b| a.expires_at().cmp(&b.expires_at()).then_with(|| a.cmp(b)));
+}
+
+/// Removes the values expired at `now` from `items`.
+///
+/// # Arguments
+/// * `items`: The collection to sweep; live values keep their order.
+/// * `now`:   The current time in unix millis.
+///
+/// # Returns
+/// * `Vec<T>`: The removed values, in their original order.
+pub fn drain_expired<T: Expiring>(items: &mut Vec<T>, now: i64) -> Vec<T> {
+    items.extract_if(.., |item| item.is_expired_at(now)).collect()
+}
+
+/// Splits values into those still valid at `now` and those expired.
+///
+/// Works on anything iterable, so a map can be swept with
+/// `partition_by_expiry(map.drain(), now)` and the live entries put back.
+///
+/// # Arguments
+/// * `items`: The values to split.
+/// * `now`:   The current time in unix millis.
+///
+/// # Returns
+/// * `(Vec<T>, Vec<T>)`: The live values and the expired values, each
+///                       in iteration order.
+pub fn partition_by_expiry<T, I>(items: I, now: i64) -> (Vec<T>, Vec<T>)
+where
+    T: Expiring,
+    I: IntoIterator<Item = T>,
+{
+    items.into_iter().partition(|item| !item.is_expired_at(now))
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    fn challenge(created_time: i64, expiration_time: i64, website_id: &str) -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let mut challenge = IronShieldChallenge::new(website_id.parse().unwrap(), 1_000, key, [0x34; 32]);
+        challenge.created_time = created_time;
+        challenge.expiration_time = expiration_time;
+        challenge
+    }
+
+    #[test]
+    fn test_challenge_ordering() {
+        let first = challenge(1_000, 5_000, "a.example");
+        let second = challenge(2_000, 3_000, "b.example");
+        let tied = challenge(2_000, 3_000, "c.example");
+
+        assert!(first < second);
+        assert_eq!(first.clone(), first);
+        assert_ne!(second, tied);
+        assert_eq!(second.cmp(&tied), second.challenge_id().cmp(&tied.challenge_id()));
+
+        let mut by_creation = vec![tied.clone(), second.clone(), first.clone()];
+        by_creation.sort();
+        assert_eq!(by_creation[0], first);
+
+        let mut reversed = by_creation.clone();
+        reversed.reverse();
+        reversed.sort();
+        assert_eq!(reversed, by_creation);
+    }
+
+    #[test]
+    fn test_sort_by_expiry() {
+        let late = challenge(1_000, 9_000, "a.example");
+        let early = challenge(2_000, 3_000, "b.example");
+        let early_tied = challenge(2_000, 3_000, "c.example");
+
+        let mut forward = vec![late.clone(), early.clone(), early_tied.clone()];
+        let mut backward = vec![early_tied.clone(), early.clone(), late.clone()];
+        sort_by_expiry(&mut forward);
+        sort_by_expiry(&mut backward);
+
+        assert_eq!(forward, backward);
+        assert_eq!(forward[2], late);
+        assert!(forward[0].expires_at() == 3_000 && forward[0] < forward[1]);
+        assert!(late.is_expired_at(9_001) && !late.is_expired_at(9_000));
+    }
+
+    #[test]
+    fn test_drain_expired() {
+        let mut challenges = vec![
+            challenge(1_000, 3_000, "a.example"),
+            challenge(1_000, 9_000, "b.example"),
+            challenge(1_000, 5_000, "c.example"),
+            challenge(1_000, 2_000, "d.example"),
+        ];
+
+        let expired: Vec<IronShieldChallenge> = drain_expired(&mut challenges, 5_000);
+        let expired_sites: Vec<&str> = expired.iter().map(|c| c.website_id.as_str()).collect();
+        let live_sites: Vec<&str> = challenges.iter().map(|c| c.website_id.as_str()).collect();
+        assert_eq!(expired_sites, ["a.example", "d.example"]);
+        assert_eq!(live_sites, ["b.example", "c.example"]);
+
+        assert!(drain_expired(&mut challenges, 5_000).is_empty());
+    }
+
+    #[test]
+    fn test_partition_by_expiry_over_map() {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let issued = challenge(1_000, 3_000, "a.example");
+        let mut token = crate::issue_token(&issued, &key, &crate::LifetimePolicy::default()).unwrap();
+        token.valid_for = 4_000;
+
+        let mut store: std::collections::HashMap<[u8; 32], IronShieldToken> = std::collections::HashMap::new();
+        store.insert(token.jti(), token.clone());
+        let (live, expired) = partition_by_expiry(store.drain(), 4_000);
+        assert_eq!((live.len(), expired.len()), (1, 0));
+        store.extend(live);
+
+        let (live, expired) = partition_by_expiry(store.drain(), 4_001);
+        assert!(live.is_empty());
+        assert_eq!(expired[0].0, token.jti());
+    }
+}
@@ -0,0 +1,117 @@
+//! # Optional protocol features.
+//!
+//! Beyond the protocol version, client and server each announce which
+//! optional features they implement as `FeatureFlags`. A feature is used
+//! only if both sides announce it, so negotiation is the intersection of
+//! the two sets. Flags missing from a peer's JSON count as unsupported,
+//! which keeps peers that predate a feature working.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Optional protocol features a peer implements.
+///
+/// * `compact_encoding`: The compact binary wire format.
+/// * `blake3`:           BLAKE3 hashing.
+/// * `bundles`:          Several challenges issued and answered in one
+///                       bundle.
+/// * `payment_bypass`:   Payment receipts in place of proof-of-work.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FeatureFlags {
+    #[serde(default)]
+    pub compact_encoding: bool,
+    #[serde(default)]
+    pub blake3:           bool,
+    #[serde(default)]
+    pub bundles:          bool,
+    #[serde(default)]
+    pub payment_bypass:   bool,
+}
+
+impl FeatureFlags {
+    /// No optional feature.
+    pub const NONE: Self = Self {
+        compact_encoding: false,
+        blake3:           false,
+        bundles:          false,
+        payment_bypass:   false,
+    };
+
+    /// Every optional feature this crate knows.
+    pub const ALL: Self = Self {
+        compact_encoding: true,
+        blake3:           true,
+        bundles:          true,
+        payment_bypass:   true,
+    };
+
+    /// # Arguments
+    /// * `other`: The peer's flags.
+    ///
+    /// # Returns
+    /// * `Self`: The features both sides support.
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            compact_encoding: self.compact_encoding && other.compact_encoding,
+            blake3:           self.blake3 && other.blake3,
+            bundles:          self.bundles && other.bundles,
+            payment_bypass:   self.payment_bypass && other.payment_bypass,
+        }
+    }
+
+    /// # Arguments
+    /// * `required`: Features that must be present.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if every feature in `required` is set here.
+    pub fn contains(&self, required: &Self) -> bool {
+        self.intersect(required) == *required
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if no feature is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Settles the features used with a client.
+    ///
+    /// # Arguments
+    /// * `client`:   Features the client announced.
+    /// * `server`:   Features the server implements.
+    /// * `required`: Features the server will not do without.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The common features, or an error if the
+    ///                           client lacks a required one.
+    pub fn negotiate(client: &Self, server: &Self, required: &Self) -> Result<Self, String> {
+        let common: Self = client.intersect(server);
+        if !common.contains(required) {
+            return Err(format!("Client lacks required protocol features: {:?}", required));
+        }
+        Ok(common)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_intersects() {
+        let client = FeatureFlags { compact_encoding: true, blake3: true, ..Default::default() };
+        let server = FeatureFlags { blake3: true, bundles: true, payment_bypass: true, ..Default::default() };
+
+        let common = FeatureFlags::negotiate(&client, &server, &FeatureFlags::NONE).unwrap();
+        assert_eq!(common, FeatureFlags { blake3: true, ..Default::default() });
+        assert!(FeatureFlags::ALL.contains(&common) && !common.contains(&server));
+        assert!(FeatureFlags::negotiate(&client, &server, &FeatureFlags { bundles: true, ..Default::default() }).is_err());
+        assert!(client.intersect(&FeatureFlags::NONE).is_empty());
+
+        let legacy: FeatureFlags = serde_json::from_str(r#"{"blake3":true}"#).unwrap();
+        assert_eq!(legacy, FeatureFlags { blake3: true, ..Default::default() });
+    }
+}
@@ -0,0 +1,365 @@
+//! # C FFI layer for native proxy modules.
+//!
+//! Exposes `extern "C"` functions so nginx, Envoy and other native
+//! modules can parse and verify IronShield headers. Parsed values are
+//! returned as opaque handles that must be released with the matching
+//! `*_free` function; every fallible call returns an `IronShieldStatus`.
+//! The C header is generated by cbindgen into `include/ironshield.h`
+//! when building with the `ffi` feature.
+
+use std::ffi::{
+    c_char,
+    CStr
+};
+
+use crate::{
+    verify_challenge_signature_with_key,
+    verify_token_signature_with_key,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    IronShieldToken
+};
+use crate::epoch::now_millis;
+
+/// Result code of every fallible FFI call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IronShieldStatus {
+    /// The call succeeded.
+    Ok                 = 0,
+    /// A required pointer argument was null.
+    NullPointer        = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8        = 2,
+    /// A header value could not be decoded.
+    ParseError         = 3,
+    /// The public key is not a valid Ed25519 key.
+    InvalidKey         = 4,
+    /// A signature did not verify.
+    VerificationFailed = 5,
+    /// The challenge or token has expired.
+    Expired            = 6,
+    /// The proof-of-work solution does not meet the target.
+    InvalidSolution    = 7,
+}
+
+impl From<crate::CryptoError> for IronShieldStatus {
+    fn from(error: crate::CryptoError) -> Self {
+        match error {
+            crate::CryptoError::InvalidKeyFormat(_) => IronShieldStatus::InvalidKey,
+            _                                       => IronShieldStatus::VerificationFailed,
+        }
+    }
+}
+
+/// Returns a static, NUL-terminated description of a status code.
+#[unsafe(no_mangle)]
+pub extern "C" fn ironshield_status_message(status: IronShieldStatus) -> *const c_char {
+    let message: &'static CStr = match status {
+        IronShieldStatus::Ok                 => c"ok",
+        IronShieldStatus::NullPointer        => c"null pointer argument",
+        IronShieldStatus::InvalidUtf8        => c"string argument is not valid UTF-8",
+        IronShieldStatus::ParseError         => c"failed to decode header value",
+        IronShieldStatus::InvalidKey         => c"invalid Ed25519 public key",
+        IronShieldStatus::VerificationFailed => c"signature verification failed",
+        IronShieldStatus::Expired            => c"expired",
+        IronShieldStatus::InvalidSolution    => c"solution does not meet the challenge target",
+    };
+    message.as_ptr()
+}
+
+/// Reads a NUL-terminated UTF-8 string argument.
+///
+/// # Safety
+/// `value` must be null or point to a valid NUL-terminated string.
+unsafe fn read_str<'a>(value: *const c_char) -> Result<&'a str, IronShieldStatus> {
+    if value.is_null() {
+        return Err(IronShieldStatus::NullPointer);
+    }
+    unsafe { CStr::from_ptr(value) }.to_str().map_err(|_| IronShieldStatus::InvalidUtf8)
+}
+
+/// Reads a 32-byte public key argument.
+///
+/// # Safety
+/// `public_key` must be null or point to 32 readable bytes.
+unsafe fn read_public_key(public_key: *const u8) -> Result<[u8; 32], IronShieldStatus> {
+    if public_key.is_null() {
+        return Err(IronShieldStatus::NullPointer);
+    }
+    Ok(unsafe { *(public_key as *const [u8; 32]) })
+}
+
+/// Decodes a header value and stores a new handle in `out`.
+///
+/// # Safety
+/// `header` and `out` must satisfy the contract of the calling
+/// `*_parse_header` function.
+unsafe fn parse_into<T>(
+    header: *const c_char,
+    out:    *mut *mut T,
+    parse:  fn(&str) -> Result<T, String>,
+) -> IronShieldStatus {
+    if out.is_null() {
+        return IronShieldStatus::NullPointer;
+    }
+    let header: &str = match unsafe { read_str(header) } {
+        Ok(header) => header,
+        Err(status) => return status,
+    };
+    match parse(header) {
+        Ok(value) => {
+            unsafe { *out = Box::into_raw(Box::new(value)) };
+            IronShieldStatus::Ok
+        }
+        Err(_) => IronShieldStatus::ParseError,
+    }
+}
+
+/// Turns a `Result` into a status code.
+fn status_of(result: Result<(), IronShieldStatus>) -> IronShieldStatus {
+    result.err().unwrap_or(IronShieldStatus::Ok)
+}
+
+/// Decodes an `X-IronShield-Challenge` header value.
+///
+/// # Safety
+/// `header` must be a valid NUL-terminated string and `out` a valid
+/// pointer. On success `*out` owns a handle that must be released with
+/// `ironshield_challenge_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_challenge_parse_header(
+    header: *const c_char,
+    out:    *mut *mut IronShieldChallenge,
+) -> IronShieldStatus {
+    unsafe { parse_into(header, out, IronShieldChallenge::from_base64url_header) }
+}
+
+/// Releases a challenge handle. Null is ignored.
+///
+/// # Safety
+/// `challenge` must be null or a handle returned by this library that
+/// has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_challenge_free(challenge: *mut IronShieldChallenge) {
+    if !challenge.is_null() {
+        drop(unsafe { Box::from_raw(challenge) });
+    }
+}
+
+/// Verifies the signature of a challenge and that it has not expired.
+///
+/// # Safety
+/// `challenge` must be a valid handle and `public_key` must point to
+/// 32 readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_challenge_verify(
+    challenge:  *const IronShieldChallenge,
+    public_key: *const u8,
+) -> IronShieldStatus {
+    let Some(challenge) = (unsafe { challenge.as_ref() }) else {
+        return IronShieldStatus::NullPointer;
+    };
+    status_of((|| {
+        let public_key: [u8; 32] = unsafe { read_public_key(public_key) }?;
+        verify_challenge_signature_with_key(challenge, &public_key)?;
+        if !challenge.accepts_submission_at(now_millis()) {
+            return Err(IronShieldStatus::Expired);
+        }
+        Ok(())
+    })())
+}
+
+/// Checks a proof-of-work solution against a challenge.
+///
+/// # Safety
+/// `challenge` must be a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_challenge_check_solution(
+    challenge: *const IronShieldChallenge,
+    nonce:     i64,
+) -> IronShieldStatus {
+    let Some(challenge) = (unsafe { challenge.as_ref() }) else {
+        return IronShieldStatus::NullPointer;
+    };
+    match challenge.check_solution(nonce) {
+        Ok(()) => IronShieldStatus::Ok,
+        Err(_) => IronShieldStatus::InvalidSolution,
+    }
+}
+
+/// Decodes an `X-IronShield-Challenge-Response` header value.
+///
+/// # Safety
+/// `header` must be a valid NUL-terminated string and `out` a valid
+/// pointer. On success `*out` owns a handle that must be released with
+/// `ironshield_response_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_response_parse_header(
+    header: *const c_char,
+    out:    *mut *mut IronShieldChallengeResponse,
+) -> IronShieldStatus {
+    unsafe { parse_into(header, out, IronShieldChallengeResponse::from_base64url_header) }
+}
+
+/// Releases a response handle. Null is ignored.
+///
+/// # Safety
+/// `response` must be null or a handle returned by this library that
+/// has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_response_free(response: *mut IronShieldChallengeResponse) {
+    if !response.is_null() {
+        drop(unsafe { Box::from_raw(response) });
+    }
+}
+
+/// Fully verifies a challenge response: the challenge signature, the
+/// submission deadline and the proof-of-work solution.
+///
+/// # Safety
+/// `response` must be a valid handle and `public_key` must point to
+/// 32 readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_response_verify(
+    response:   *const IronShieldChallengeResponse,
+    public_key: *const u8,
+) -> IronShieldStatus {
+    let Some(response) = (unsafe { response.as_ref() }) else {
+        return IronShieldStatus::NullPointer;
+    };
+    let status: IronShieldStatus = unsafe { ironshield_challenge_verify(&response.solved_challenge, public_key) };
+    if status != IronShieldStatus::Ok {
+        return status;
+    }
+    match response.verify_solution() {
+        Ok(()) => IronShieldStatus::Ok,
+        Err(_) => IronShieldStatus::InvalidSolution,
+    }
+}
+
+/// Decodes an `X-IronShield-Token` header value.
+///
+/// # Safety
+/// `header` must be a valid NUL-terminated string and `out` a valid
+/// pointer. On success `*out` owns a handle that must be released with
+/// `ironshield_token_free`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_token_parse_header(
+    header: *const c_char,
+    out:    *mut *mut IronShieldToken,
+) -> IronShieldStatus {
+    unsafe { parse_into(header, out, IronShieldToken::from_base64url_header) }
+}
+
+/// Releases a token handle. Null is ignored.
+///
+/// # Safety
+/// `token` must be null or a handle returned by this library that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_token_free(token: *mut IronShieldToken) {
+    if !token.is_null() {
+        drop(unsafe { Box::from_raw(token) });
+    }
+}
+
+/// Verifies the signature of a token and that it has not expired.
+///
+/// # Safety
+/// `token` must be a valid handle and `public_key` must point to
+/// 32 readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_token_verify(
+    token:      *const IronShieldToken,
+    public_key: *const u8,
+) -> IronShieldStatus {
+    let Some(token) = (unsafe { token.as_ref() }) else {
+        return IronShieldStatus::NullPointer;
+    };
+    status_of((|| {
+        let public_key: [u8; 32] = unsafe { read_public_key(public_key) }?;
+        verify_token_signature_with_key(token, &public_key)?;
+        if token.is_expired() {
+            return Err(IronShieldStatus::Expired);
+        }
+        Ok(())
+    })())
+}
+
+/// Returns the Unix milli timestamp until which a token is valid, or
+/// `0` for a null handle.
+///
+/// # Safety
+/// `token` must be null or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ironshield_token_valid_for(token: *const IronShieldToken) -> i64 {
+    unsafe { token.as_ref() }.map_or(0, |token| token.valid_for)
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    use crate::{
+        issue_token,
+        LifetimePolicy,
+        SigningKey
+    };
+
+    #[test]
+    fn test_parse_and_verify_token() {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 100, key.clone(), public_key);
+        let token = issue_token(&challenge, &key, &LifetimePolicy::default()).unwrap();
+        let header = CString::new(token.to_base64url_header()).unwrap();
+
+        let mut handle: *mut IronShieldToken = ptr::null_mut();
+        unsafe {
+            assert_eq!(ironshield_token_parse_header(header.as_ptr(), &mut handle), IronShieldStatus::Ok);
+            assert_eq!(ironshield_token_verify(handle, public_key.as_ptr()), IronShieldStatus::Ok);
+            assert_eq!(ironshield_token_verify(handle, [0u8; 32].as_ptr()), IronShieldStatus::VerificationFailed);
+            assert_eq!(ironshield_token_valid_for(handle), token.valid_for);
+            ironshield_token_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_response_verify_checks_solution() {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 16, key, public_key);
+        let solution: i64 = (0..10_000).find(|nonce| challenge.check_solution(*nonce).is_ok()).unwrap();
+        let wrong: i64 = (0..10_000).find(|nonce| challenge.check_solution(*nonce).is_err()).unwrap();
+
+        for (nonce, expected) in [(solution, IronShieldStatus::Ok), (wrong, IronShieldStatus::InvalidSolution)] {
+            let response = IronShieldChallengeResponse::new(challenge.clone(), nonce);
+            let header = CString::new(response.to_base64url_header()).unwrap();
+            let mut handle: *mut IronShieldChallengeResponse = ptr::null_mut();
+            unsafe {
+                assert_eq!(ironshield_response_parse_header(header.as_ptr(), &mut handle), IronShieldStatus::Ok);
+                assert_eq!(ironshield_response_verify(handle, public_key.as_ptr()), expected);
+                ironshield_response_free(handle);
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let garbage = CString::new("not a header").unwrap();
+        let mut handle: *mut IronShieldChallenge = ptr::null_mut();
+        unsafe {
+            assert_eq!(ironshield_challenge_parse_header(garbage.as_ptr(), &mut handle), IronShieldStatus::ParseError);
+            assert_eq!(ironshield_challenge_parse_header(ptr::null(), &mut handle), IronShieldStatus::NullPointer);
+            assert_eq!(ironshield_challenge_verify(ptr::null(), ptr::null()), IronShieldStatus::NullPointer);
+            assert!(handle.is_null());
+            ironshield_challenge_free(handle);
+        }
+
+        let message = unsafe { CStr::from_ptr(ironshield_status_message(IronShieldStatus::Expired)) };
+        assert_eq!(message.to_str().unwrap(), "expired");
+    }
+}
@@ -0,0 +1,291 @@
+//! # Cross-language interop vectors.
+//!
+//! The JS, Go and Python clients must derive keys, build signing
+//! messages, verify signatures and hash solutions exactly like this
+//! crate. `InteropVectors` is a JSON file of worked examples they can
+//! run: each vector holds an Ed25519 key seed, a signed challenge in its
+//! header form, the signing message and signature, and a solution with
+//! its proof-of-work hash. Byte values are lowercase hex.
+//!
+//! The crate's reference set is published as `test-vectors/interop.json`
+//! and regenerated with `InteropVectors::reference()`.
+
+use std::path::Path;
+
+use ed25519_dalek::{
+    Signature,
+    SigningKey,
+    Verifier,
+    VerifyingKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::{
+    IronShieldChallenge,
+    Solution
+};
+#[cfg(feature = "sign")]
+use crate::{
+    RandomNonce,
+    WebsiteId
+};
+
+/// Version of the interop vector file format.
+pub const INTEROP_VECTORS_VERSION: u32 = 1;
+
+/// Nonces tried when generating a vector's solution.
+#[cfg(feature = "sign")]
+const MAX_SOLUTION_SEARCH: i64 = 1 << 24;
+
+/// One worked example.
+///
+/// * `description`:      What the vector exercises.
+/// * `private_key_seed`: The 32-byte Ed25519 seed.
+/// * `public_key`:       The public key derived from the seed.
+/// * `challenge`:        The signed challenge as `to_base64url_header()`.
+/// * `signing_message`:  The challenge's `signing_message()`.
+/// * `signature`:        The Ed25519 signature of `signing_message`.
+/// * `solution`:         The smallest non-negative nonce meeting the target.
+/// * `solution_hash`:    SHA-256 of the decoded `random_nonce` followed by
+///                       `Solution::to_bytes`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteropVector {
+    pub description:      String,
+    pub private_key_seed: String,
+    pub public_key:       String,
+    pub challenge:        String,
+    pub signing_message:  String,
+    pub signature:        String,
+    pub solution:         i64,
+    pub solution_hash:    String,
+}
+
+/// A file of interop vectors.
+///
+/// * `version`: `INTEROP_VECTORS_VERSION`.
+/// * `vectors`: The vectors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteropVectors {
+    pub version: u32,
+    pub vectors: Vec<InteropVector>,
+}
+
+/// # Arguments
+/// * `random_nonce`: The challenge's hex-encoded random nonce.
+/// * `solution`:     The solution.
+///
+/// # Returns
+/// * `Result<[u8; 32], String>`: The proof-of-work hash, or an error if
+///                               `random_nonce` is not hex.
+fn solution_hash(random_nonce: &str, solution: i64) -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    hasher.update(hex::decode(random_nonce).map_err(|_| "Failed to decode random_nonce hex string")?);
+    hasher.update(Solution::from_wire(solution).to_bytes());
+    Ok(hasher.finalize().into())
+}
+
+impl InteropVector {
+    /// Signs `challenge` with the key derived from `seed` and solves it.
+    ///
+    /// # Arguments
+    /// * `description`: What the vector exercises.
+    /// * `seed`:        The Ed25519 private key seed.
+    /// * `challenge`:   The challenge; `public_key` and
+    ///                  `challenge_signature` are overwritten.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The vector, or an error if no solution
+    ///                           was found within 2^24 nonces.
+    #[cfg(feature = "sign")]
+    pub fn generate(description: &str, seed: &[u8; 32], mut challenge: IronShieldChallenge) -> Result<Self, String> {
+        let private_key = SigningKey::from_bytes(seed);
+        challenge.public_key = private_key.verifying_key().to_bytes();
+        challenge.sign(&private_key);
+
+        let solution: i64 = (0..MAX_SOLUTION_SEARCH)
+            .find(|nonce| challenge.check_solution(*nonce).is_ok())
+            .ok_or_else(|| format!("No solution below {} for {}", MAX_SOLUTION_SEARCH, description))?;
+
+        Ok(Self {
+            description:      description.to_string(),
+            private_key_seed: hex::encode(seed),
+            public_key:       hex::encode(challenge.public_key),
+            challenge:        challenge.to_base64url_header(),
+            signing_message:  challenge.signing_message(),
+            signature:        hex::encode(challenge.challenge_signature),
+            solution,
+            solution_hash:    hex::encode(solution_hash(challenge.random_nonce.as_str(), solution)?),
+        })
+    }
+
+    /// Checks the vector against this crate, as other implementations
+    /// check it against theirs.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if every value is reproduced,
+    ///                         or the first mismatch.
+    pub fn check(&self) -> Result<(), String> {
+        let seed: [u8; 32] = hex::decode(&self.private_key_seed).ok()
+            .and_then(|seed| seed.try_into().ok())
+            .ok_or("private_key_seed must be 32 hex-encoded bytes")?;
+        let private_key = SigningKey::from_bytes(&seed);
+        if hex::encode(private_key.verifying_key().to_bytes()) != self.public_key {
+            return Err("public_key does not match private_key_seed".to_string());
+        }
+
+        let challenge = IronShieldChallenge::from_base64url_header(&self.challenge)?;
+        if hex::encode(challenge.public_key) != self.public_key {
+            return Err("Challenge public_key does not match".to_string());
+        }
+        if challenge.signing_message() != self.signing_message {
+            return Err("signing_message does not match the challenge".to_string());
+        }
+        if hex::encode(challenge.challenge_signature) != self.signature {
+            return Err("signature does not match the challenge".to_string());
+        }
+        let verifying_key: VerifyingKey = private_key.verifying_key();
+        verifying_key.verify(self.signing_message.as_bytes(), &Signature::from_bytes(&challenge.challenge_signature))
+            .map_err(|e| format!("signature does not verify: {}", e))?;
+
+        if hex::encode(solution_hash(challenge.random_nonce.as_str(), self.solution)?) != self.solution_hash {
+            return Err("solution_hash does not match".to_string());
+        }
+        challenge.check_solution(self.solution)
+    }
+}
+
+impl InteropVectors {
+    /// The crate's reference set, as published in
+    /// `test-vectors/interop.json`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The vectors, or a generation error.
+    #[cfg(feature = "sign")]
+    pub fn reference() -> Result<Self, String> {
+        let base = |random_nonce: &str, website_id: &str| -> Result<IronShieldChallenge, String> {
+            Ok(IronShieldChallenge {
+                random_nonce:         RandomNonce::try_from(random_nonce)?,
+                created_time:         1_755_401_345_880,
+                expiration_time:      1_755_401_375_880,
+                website_id:           WebsiteId::verbatim(website_id)?,
+                challenge_param:      IronShieldChallenge::difficulty_to_challenge_param(1_000),
+                recommended_attempts: IronShieldChallenge::recommended_attempts(1_000),
+                public_key:           [0; 32],
+                challenge_signature:  [0; 64],
+                submission_deadline:  None,
+                request_binding:      None,
+                issuer_node:          None,
+                min_age_ms:           None,
+                issuance_context:     None,
+                nonce_proof:          None,
+                time_attestation:     None,
+                required_solutions:   None,
+                time_lock:            None,
+                vdf:                  None,
+            })
+        };
+
+        let plain = base("a6e5f14c9622c88af274ec7247f028eb", "https://example.com")?;
+        let extended = IronShieldChallenge {
+            submission_deadline: Some(1_755_401_380_880),
+            request_binding:     Some([0x5a; 32]),
+            issuer_node:         Some("fra/worker-3".to_string()),
+            ..base("00112233445566778899aabbccddeeff", "example.com")?
+        };
+        let unicode = base("ffeeddccbbaa99887766554433221100", "bücher.de")?;
+
+        Ok(Self {
+            version: INTEROP_VECTORS_VERSION,
+            vectors: vec![
+                InteropVector::generate("Challenge without extensions", &[0x01; 32], plain)?,
+                InteropVector::generate("Challenge with submission deadline, request binding and issuer node", &[0x2a; 32], extended)?,
+                InteropVector::generate("Non-ASCII website_id is signed as UTF-8", &[0x7f; 32], unicode)?,
+            ],
+        })
+    }
+
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if every vector passes
+    ///                         `InteropVector::check`.
+    pub fn check(&self) -> Result<(), String> {
+        self.vectors.iter().try_for_each(|vector| {
+            vector.check().map_err(|e| format!("{}: {}", vector.description, e))
+        })
+    }
+
+    /// # Returns
+    /// * `Result<String, String>`: The vectors as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize interop vectors: {}", e))
+    }
+
+    /// # Arguments
+    /// * `json`: A file produced by `to_json()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The vectors, or an error if the JSON is
+    ///                           invalid or of another version.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let vectors: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse interop vectors: {}", e))?;
+        if vectors.version != INTEROP_VECTORS_VERSION {
+            return Err(format!("Unsupported interop vectors version: {}", vectors.version));
+        }
+        Ok(vectors)
+    }
+
+    /// # Arguments
+    /// * `path`: The file to write.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())`, or an I/O error.
+    pub fn dump(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        std::fs::write(path.as_ref(), self.to_json()? + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", path.as_ref().display(), e))
+    }
+
+    /// # Arguments
+    /// * `path`: The file to read.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The vectors, or an I/O or parse error.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let json: String = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Failed to read {}: {}", path.as_ref().display(), e))?;
+        Self::from_json(&json)
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_vectors() {
+        let published = InteropVectors::from_json(include_str!("../test-vectors/interop.json")).unwrap();
+        published.check().unwrap();
+        assert_eq!(published, InteropVectors::reference().unwrap());
+
+        let mut tampered = published.clone();
+        tampered.vectors[0].solution_hash = "00".repeat(32);
+        assert!(tampered.check().unwrap_err().starts_with("Challenge without extensions: solution_hash"));
+        assert!(InteropVectors::from_json(r#"{"version":2,"vectors":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_dump_load_roundtrip() {
+        let path = std::env::temp_dir().join(format!("ironshield-interop-{}.json", std::process::id()));
+        let vectors = InteropVectors::reference().unwrap();
+        vectors.dump(&path).unwrap();
+        assert_eq!(InteropVectors::load(&path).unwrap(), vectors);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
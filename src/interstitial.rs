@@ -0,0 +1,248 @@
+//! # Data model of the HTML challenge page.
+//!
+//! When a browser navigates to a protected page without a token, the
+//! edge serves an interstitial page that solves the challenge and
+//! reloads. Everything that page needs is an `InterstitialModel`: the
+//! challenge, the site's branding, the keys of the localized strings to
+//! show, the solver tuning and the ids tying client telemetry to the
+//! edge's logs.
+//!
+//! The template embeds the model once, as JSON inside a
+//! `<script type="application/json">` element, using
+//! `to_embedded_json()`. Branding values come from site configuration,
+//! so the embedding escapes everything that could end the element.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    to_html_safe_json,
+    IronShieldChallenge,
+    JsSolverConfig
+};
+
+/// Maximum length of `site_name`.
+const MAX_SITE_NAME_LEN: usize = 100;
+
+/// How the page presents the protected site.
+///
+/// * `site_name`:    Name shown in the page title and heading.
+/// * `logo_url`:     `https://` URL of the logo, if any.
+/// * `accent_color`: Accent color as `#rrggbb`, if not the default.
+/// * `support_url`:  `https://` URL users can turn to when stuck.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct InterstitialBranding {
+    pub site_name:    String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_url:     Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub support_url:  Option<String>,
+}
+
+/// Keys into the page's translation table, and the locale to use.
+///
+/// * `locale`:          BCP 47 language tag, e.g. `"de-CH"`.
+/// * `title_key`:       Key of the page title.
+/// * `description_key`: Key of the text explaining the check.
+/// * `progress_key`:    Key of the text shown while solving.
+/// * `failure_key`:     Key of the text shown if solving fails.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterstitialStrings {
+    pub locale:          String,
+    pub title_key:       String,
+    pub description_key: String,
+    pub progress_key:    String,
+    pub failure_key:     String,
+}
+
+impl Default for InterstitialStrings {
+    fn default() -> Self {
+        Self {
+            locale:          "en".to_string(),
+            title_key:       "ironshield.interstitial.title".to_string(),
+            description_key: "ironshield.interstitial.description".to_string(),
+            progress_key:    "ironshield.interstitial.progress".to_string(),
+            failure_key:     "ironshield.interstitial.failure".to_string(),
+        }
+    }
+}
+
+/// Ids the page attaches to its telemetry events.
+///
+/// * `request_id`: Id of the edge request that served the page.
+/// * `session_id`: Id of the visitor's session, if the edge tracks one.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct InterstitialTelemetry {
+    pub request_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+/// Everything the interstitial page is rendered from.
+///
+/// * `challenge`:  The challenge as `to_base64url_header()` encodes it.
+/// * `website_id`: The website the challenge was issued for.
+/// * `branding`:   How the page presents the site.
+/// * `strings`:    Which localized strings the page shows.
+/// * `solver`:     How the page's solver runs.
+/// * `telemetry`:  Ids for the page's telemetry events.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterstitialModel {
+    pub challenge:  String,
+    pub website_id: String,
+    pub branding:   InterstitialBranding,
+    #[serde(default)]
+    pub strings:    InterstitialStrings,
+    #[serde(default)]
+    pub solver:     JsSolverConfig,
+    pub telemetry:  InterstitialTelemetry,
+}
+
+impl InterstitialBranding {
+    /// Checks the values that end up in attributes and styles.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid value.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.site_name.trim().is_empty() || self.site_name.chars().count() > MAX_SITE_NAME_LEN {
+            return Err(format!("site_name must be 1 to {} characters", MAX_SITE_NAME_LEN));
+        }
+        for url in [&self.logo_url, &self.support_url].into_iter().flatten() {
+            if !url.starts_with("https://") || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err(format!("Branding URLs must be https:// URLs: {}", url));
+            }
+        }
+        if let Some(accent_color) = &self.accent_color {
+            let hex_digits: Option<&str> = accent_color.strip_prefix('#');
+            if !hex_digits.is_some_and(|digits| digits.len() == 6 && digits.bytes().all(|b| b.is_ascii_hexdigit())) {
+                return Err(format!("accent_color must be #rrggbb: {}", accent_color));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InterstitialModel {
+    /// Constructor for a model with the default strings and solver
+    /// settings.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge the page solves.
+    /// * `branding`:  How the page presents the site.
+    /// * `telemetry`: Ids for the page's telemetry events.
+    ///
+    /// # Returns
+    /// * `Self`: The model.
+    pub fn new(
+        challenge: &IronShieldChallenge,
+        branding:  InterstitialBranding,
+        telemetry: InterstitialTelemetry,
+    ) -> Self {
+        Self {
+            challenge:  challenge.to_base64url_header(),
+            website_id: challenge.website_id.to_string(),
+            branding,
+            strings:    InterstitialStrings::default(),
+            solver:     JsSolverConfig::default(),
+            telemetry,
+        }
+    }
+
+    /// # Arguments
+    /// * `strings`: The localized strings to show.
+    ///
+    /// # Returns
+    /// * `Self`: The model showing `strings`.
+    pub fn with_strings(mut self, strings: InterstitialStrings) -> Self {
+        self.strings = strings;
+        self
+    }
+
+    /// # Arguments
+    /// * `solver`: The solver settings.
+    ///
+    /// # Returns
+    /// * `Self`: The model running its solver with `solver`.
+    pub fn with_solver_config(mut self, solver: JsSolverConfig) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// # Returns
+    /// * `Result<IronShieldChallenge, String>`: The decoded challenge, or
+    ///                                          an error if `challenge`
+    ///                                          is malformed.
+    pub fn decode_challenge(&self) -> Result<IronShieldChallenge, String> {
+        IronShieldChallenge::from_base64url_header(&self.challenge)
+    }
+
+    /// Validates the branding and solver settings and serializes the
+    /// model for a `<script type="application/json">` element. See
+    /// `to_html_safe_json`.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The escaped JSON, or an error if the
+    ///                             branding or solver settings are
+    ///                             invalid.
+    pub fn to_embedded_json(&self) -> Result<String, String> {
+        self.branding.validate()?;
+        self.solver.validate()?;
+        to_html_safe_json(self)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_embedded_json_escapes_script_end() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+        let branding = InterstitialBranding {
+            site_name:    "Shop </script><script>alert(1)</script> & <!-- more".to_string(),
+            accent_color: Some("#1a2B3c".to_string()),
+            ..Default::default()
+        };
+        let telemetry = InterstitialTelemetry { request_id: "8f2e\u{2028}".to_string(), session_id: None };
+        let model = InterstitialModel::new(&challenge, branding, telemetry);
+
+        let embedded: String = model.to_embedded_json().unwrap();
+        assert!(!embedded.contains('<') && !embedded.contains('>') && !embedded.contains('&'));
+        assert!(!embedded.contains('\u{2028}'));
+        let decoded: InterstitialModel = serde_json::from_str(&embedded).unwrap();
+        assert_eq!(decoded, model);
+        assert_eq!(decoded.decode_challenge().unwrap(), challenge);
+        assert_eq!(decoded.strings.title_key, "ironshield.interstitial.title");
+
+        let misconfigured = model.with_solver_config(JsSolverConfig { yield_interval: 0, ..Default::default() });
+        assert!(misconfigured.to_embedded_json().unwrap_err().contains("yield_interval"));
+    }
+
+    #[test]
+    fn test_branding_validation() {
+        let valid = InterstitialBranding { site_name: "Shop".to_string(), ..Default::default() };
+        assert!(valid.validate().is_ok());
+
+        let invalid = [
+            InterstitialBranding { site_name: " ".to_string(), ..valid.clone() },
+            InterstitialBranding { logo_url: Some("javascript:alert(1)".to_string()), ..valid.clone() },
+            InterstitialBranding { support_url: Some("https://a b".to_string()), ..valid.clone() },
+            InterstitialBranding { accent_color: Some("red;}".to_string()), ..valid.clone() },
+            InterstitialBranding { accent_color: Some("#12345".to_string()), ..valid },
+        ];
+        for branding in invalid {
+            assert!(branding.validate().is_err(), "{:?}", branding);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! # Network and client signals behind an issuance decision.
+//!
+//! The edge knows the client's ASN, country, TLS fingerprint class and
+//! bot score when it issues a challenge. `IssuanceContext` carries
+//! those signals to the threat-to-difficulty mapping, and its
+//! `commitment()` can be signed into the challenge so an auditor can
+//! later check which signals a difficulty was based on without the
+//! challenge revealing them.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::DifficultyPreset;
+
+/// Domain separation prefix of `IssuanceContext::commitment`.
+const CONTEXT_COMMITMENT_CONTEXT: &[u8] = b"ironshield-issuance-context-v1";
+
+/// Lowest valid bot score.
+pub const MIN_BOT_SCORE: u8 = 1;
+/// Highest valid bot score.
+pub const MAX_BOT_SCORE: u8 = 99;
+
+/// Coarse classification of the client's TLS fingerprint.
+///
+/// * `Browser`:         Matches a mainstream browser.
+/// * `HeadlessBrowser`: Matches a headless or automation-driven browser.
+/// * `HttpLibrary`:     Matches an HTTP client library or command line tool.
+/// * `Unknown`:         Matches nothing known.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsFingerprintClass {
+    Browser,
+    HeadlessBrowser,
+    HttpLibrary,
+    Unknown,
+}
+
+impl TlsFingerprintClass {
+    /// # Returns
+    /// * `u8`: Stable tag used in `IssuanceContext::commitment`.
+    fn tag(&self) -> u8 {
+        match self {
+            TlsFingerprintClass::Browser         => 0,
+            TlsFingerprintClass::HeadlessBrowser => 1,
+            TlsFingerprintClass::HttpLibrary     => 2,
+            TlsFingerprintClass::Unknown         => 3,
+        }
+    }
+}
+
+/// Signals about the client a challenge is issued to. Every field is
+/// optional, since not every edge provides every signal.
+///
+/// * `asn`:       Autonomous system number of the client network.
+/// * `country`:   ISO 3166-1 alpha-2 country code, upper case.
+/// * `tls_class`: Classification of the TLS fingerprint.
+/// * `bot_score`: 1 (certainly automated) to 99 (certainly human).
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IssuanceContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asn:       Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country:   Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_class: Option<TlsFingerprintClass>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot_score: Option<u8>,
+}
+
+impl IssuanceContext {
+    /// Checks the country code and bot score ranges.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid field.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(country) = &self.country
+            && (country.len() != 2 || !country.bytes().all(|b| b.is_ascii_uppercase()))
+        {
+            return Err(format!("Country must be an upper case ISO 3166-1 alpha-2 code: {}", country));
+        }
+        if let Some(bot_score) = self.bot_score
+            && !(MIN_BOT_SCORE..=MAX_BOT_SCORE).contains(&bot_score)
+        {
+            return Err(format!("Bot score must be {} to {}: {}", MIN_BOT_SCORE, MAX_BOT_SCORE, bot_score));
+        }
+        Ok(())
+    }
+
+    /// Maps the signals to a difficulty level.
+    ///
+    /// The bot score picks the base level (`Medium` when absent), and a
+    /// headless or library TLS fingerprint raises it by one.
+    ///
+    /// # Returns
+    /// * `DifficultyPreset`: The suggested difficulty level.
+    pub fn threat_preset(&self) -> DifficultyPreset {
+        let preset: DifficultyPreset = match self.bot_score {
+            None          => DifficultyPreset::Medium,
+            Some(0..=9)   => DifficultyPreset::Extreme,
+            Some(10..=29) => DifficultyPreset::High,
+            Some(30..=69) => DifficultyPreset::Medium,
+            Some(_)       => DifficultyPreset::Low,
+        };
+
+        match self.tls_class {
+            Some(TlsFingerprintClass::HeadlessBrowser | TlsFingerprintClass::HttpLibrary) => match preset {
+                DifficultyPreset::Low    => DifficultyPreset::Medium,
+                DifficultyPreset::Medium => DifficultyPreset::High,
+                _                        => DifficultyPreset::Extreme,
+            },
+            _ => preset,
+        }
+    }
+
+    /// SHA-256 over an unambiguous encoding of every field, suitable
+    /// for `IronShieldChallenge::with_issuance_context`.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: The commitment to this context.
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(CONTEXT_COMMITMENT_CONTEXT);
+
+        match self.asn {
+            Some(asn) => { hasher.update([1]); hasher.update(asn.to_be_bytes()); }
+            None      => hasher.update([0]),
+        }
+        match &self.country {
+            Some(country) => {
+                hasher.update([1]);
+                hasher.update((country.len() as u64).to_be_bytes());
+                hasher.update(country.as_bytes());
+            }
+            None => hasher.update([0]),
+        }
+        match self.tls_class {
+            Some(class) => hasher.update([1, class.tag()]),
+            None        => hasher.update([0]),
+        }
+        match self.bot_score {
+            Some(score) => hasher.update([1, score]),
+            None        => hasher.update([0]),
+        }
+
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> IssuanceContext {
+        IssuanceContext {
+            asn:       Some(64_496),
+            country:   Some("DE".to_string()),
+            tls_class: Some(TlsFingerprintClass::Browser),
+            bot_score: Some(85),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(context().validate().is_ok());
+        assert!(IssuanceContext::default().validate().is_ok());
+        assert!(IssuanceContext { country: Some("de".to_string()), ..context() }.validate().is_err());
+        assert!(IssuanceContext { country: Some("DEU".to_string()), ..context() }.validate().is_err());
+        assert!(IssuanceContext { bot_score: Some(0), ..context() }.validate().is_err());
+        assert!(IssuanceContext { bot_score: Some(100), ..context() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_threat_preset() {
+        assert_eq!(context().threat_preset(), DifficultyPreset::Low);
+        assert_eq!(IssuanceContext::default().threat_preset(), DifficultyPreset::Medium);
+        assert_eq!(IssuanceContext { bot_score: Some(20), ..context() }.threat_preset(), DifficultyPreset::High);
+
+        let library = IssuanceContext { tls_class: Some(TlsFingerprintClass::HttpLibrary), ..context() };
+        assert_eq!(library.threat_preset(), DifficultyPreset::Medium);
+        assert_eq!(IssuanceContext { bot_score: Some(5), ..library }.threat_preset(), DifficultyPreset::Extreme);
+    }
+
+    #[test]
+    fn test_commitment() {
+        assert_eq!(context().commitment(), context().commitment());
+        assert_ne!(context().commitment(), IssuanceContext::default().commitment());
+        assert_ne!(
+            IssuanceContext { asn: None, ..context() }.commitment(),
+            IssuanceContext { bot_score: None, ..context() }.commitment()
+        );
+
+        let json: String = serde_json::to_string(&IssuanceContext { asn: None, ..context() }).unwrap();
+        assert_eq!(json, r#"{"country":"DE","tls_class":"browser","bot_score":85}"#);
+    }
+}
@@ -0,0 +1,192 @@
+//! # Per-IP and per-ASN challenge issuance quotas.
+//!
+//! An `IssuanceQuota` counts the challenges issued to one client IP or
+//! network (ASN) in a fixed window. The edge loads it, calls
+//! `check_and_increment`, and writes it back, so every node enforces
+//! the same throttle from the same persisted state.
+//!
+//! Keys are salted SHA-256 hashes, so persisted quotas do not reveal
+//! client addresses.
+
+use std::net::IpAddr;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    serialize_32_bytes
+};
+
+/// Domain separation prefix of IP quota keys.
+const IP_KEY_CONTEXT: &[u8] = b"ironshield-quota-ip-v1";
+/// Domain separation prefix of ASN quota keys.
+const ASN_KEY_CONTEXT: &[u8] = b"ironshield-quota-asn-v1";
+
+/// # Arguments
+/// * `ip`:   The client address. IPv4-mapped IPv6 addresses hash the
+///           same as the IPv4 address.
+/// * `salt`: Deployment secret mixed into the hash.
+///
+/// # Returns
+/// * `[u8; 32]`: The quota key of the address.
+pub fn ip_quota_key(ip: IpAddr, salt: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(IP_KEY_CONTEXT);
+    hasher.update((salt.len() as u64).to_be_bytes());
+    hasher.update(salt);
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => hasher.update(v4.octets()),
+        IpAddr::V6(v6) => hasher.update(v6.octets()),
+    }
+    hasher.finalize().into()
+}
+
+/// # Arguments
+/// * `asn`:  The autonomous system number of the client network.
+/// * `salt`: Deployment secret mixed into the hash.
+///
+/// # Returns
+/// * `[u8; 32]`: The quota key of the network.
+pub fn asn_quota_key(asn: u32, salt: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(ASN_KEY_CONTEXT);
+    hasher.update((salt.len() as u64).to_be_bytes());
+    hasher.update(salt);
+    hasher.update(asn.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Fixed-window issuance counter for one client IP or ASN.
+///
+/// * `key`:           `ip_quota_key` or `asn_quota_key` of the client.
+/// * `window_start`:  Unix milli timestamp the current window began.
+/// * `window_ms`:     Length of a window.
+/// * `max_issuances`: Challenges allowed per window.
+/// * `count`:         Challenges issued in the current window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IssuanceQuota {
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub key:           [u8; 32],
+    pub window_start:  i64,
+    pub window_ms:     i64,
+    pub max_issuances: u32,
+    pub count:         u32,
+}
+
+impl IssuanceQuota {
+    /// Constructor for a quota with nothing issued yet.
+    ///
+    /// # Arguments
+    /// * `key`:           The hashed client key.
+    /// * `window_ms`:     Length of a window, positive.
+    /// * `max_issuances`: Challenges allowed per window.
+    /// * `now`:           Unix milli timestamp the first window begins.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The quota, or an error for a
+    ///                           non-positive window.
+    pub fn new(key: [u8; 32], window_ms: i64, max_issuances: u32, now: i64) -> Result<Self, String> {
+        if window_ms <= 0 {
+            return Err("Issuance quota window must be positive".to_string());
+        }
+        Ok(Self {
+            key,
+            window_start: now,
+            window_ms,
+            max_issuances,
+            count: 0,
+        })
+    }
+
+    /// # Returns
+    /// * `i64`: Unix milli timestamp the current window ends and the
+    ///          count resets. Also a suitable storage expiry.
+    pub fn resets_at(&self) -> i64 {
+        self.window_start.saturating_add(self.window_ms)
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `u32`: Challenges that may still be issued in the window
+    ///          containing `now`.
+    pub fn remaining(&self, now: i64) -> u32 {
+        if now >= self.resets_at() {
+            self.max_issuances
+        } else {
+            self.max_issuances.saturating_sub(self.count)
+        }
+    }
+
+    /// Counts one issuance if the quota allows it, starting a new
+    /// window first if the current one has ended.
+    ///
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the challenge may be issued; `false` leaves
+    ///           the count unchanged.
+    pub fn check_and_increment(&mut self, now: i64) -> bool {
+        if now >= self.resets_at() {
+            // Keep windows aligned to the original grid so nodes that
+            // roll the window at different times agree on its bounds.
+            let elapsed_windows: i64 = (now - self.window_start) / self.window_ms;
+            self.window_start += elapsed_windows * self.window_ms;
+            self.count = 0;
+        }
+        if self.count >= self.max_issuances {
+            return false;
+        }
+        self.count += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_keys() {
+        let v4: IpAddr = "192.0.2.1".parse().unwrap();
+        let mapped: IpAddr = "::ffff:192.0.2.1".parse().unwrap();
+        assert_eq!(ip_quota_key(v4, b"salt"), ip_quota_key(mapped, b"salt"));
+        assert_ne!(ip_quota_key(v4, b"salt"), ip_quota_key(v4, b"pepper"));
+        assert_ne!(asn_quota_key(64_496, b"salt"), asn_quota_key(64_497, b"salt"));
+    }
+
+    #[test]
+    fn test_check_and_increment() {
+        assert!(IssuanceQuota::new([1; 32], 0, 3, 0).is_err());
+        let mut quota = IssuanceQuota::new([1; 32], 1_000, 2, 10_000).unwrap();
+
+        assert!(quota.check_and_increment(10_000));
+        assert!(quota.check_and_increment(10_500));
+        assert!(!quota.check_and_increment(10_999));
+        assert_eq!(quota.count, 2);
+        assert_eq!(quota.remaining(10_999), 0);
+        assert_eq!(quota.remaining(11_000), 2);
+
+        assert!(quota.check_and_increment(12_345));
+        assert_eq!((quota.window_start, quota.count), (12_000, 1));
+        assert_eq!(quota.resets_at(), 13_000);
+
+        let json: String = serde_json::to_string(&quota).unwrap();
+        let mut restored: IssuanceQuota = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, quota);
+        assert!(restored.check_and_increment(12_400));
+        assert!(!restored.check_and_increment(12_401));
+    }
+}
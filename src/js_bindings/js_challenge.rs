@@ -80,6 +80,33 @@ impl JsIronShieldChallenge {
             .map_err(|e: serde_json::Error| JsValue::from_str(&format!("Failed to serialize challenge to JSON: {}", e)))
     }
 
+    /// Converts the challenge to a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Returns
+    /// * `Result<JsValue, JsValue>`: Plain object with the same shape as
+    ///                               `to_json()`, or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_js_value(&self) -> Result<JsValue, JsValue> {
+        crate::js_bindings::js_value::to_js_value(&self.inner, "challenge")
+    }
+
+    /// Creates a challenge from a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Arguments
+    /// * `value`: Plain object with the same shape as `to_json()` output.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldChallenge, JsValue>`: The wrapped challenge or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_js_value(value: JsValue) -> Result<JsIronShieldChallenge, JsValue> {
+        let inner: IronShieldChallenge = crate::js_bindings::js_value::from_js_value(value, "challenge")?;
+        Ok(Self { inner })
+    }
+
     /// Converts the challenge to a JavaScript object.
     ///
     /// # Returns
@@ -108,7 +135,7 @@ impl JsIronShieldChallenge {
     #[cfg(any(feature = "wasm", rust_analyzer))]
     #[wasm_bindgen(getter)]
     pub fn random_nonce(&self) -> String {
-        self.inner.random_nonce.clone()
+        self.inner.random_nonce.to_string()
     }
 
     /// # Returns
@@ -127,12 +154,47 @@ impl JsIronShieldChallenge {
         self.inner.expiration_time
     }
 
+    /// # Returns
+    /// * `Option<i64>`: The signed submission deadline as an i64 Unix
+    ///                  timestamp, if the challenge carries one.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn submission_deadline(&self) -> Option<i64> {
+        self.inner.submission_deadline
+    }
+
+    /// # Returns
+    /// * `Option<String>`: The identifier of the issuing edge node,
+    ///                     if the challenge carries one.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn issuer_node(&self) -> Option<String> {
+        self.inner.issuer_node.clone()
+    }
+
+    /// # Returns
+    /// * `Option<i64>`: Milliseconds to hold a wait challenge before
+    ///                  redeeming it, if this is a wait challenge.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn min_age_ms(&self) -> Option<i64> {
+        self.inner.min_age_ms
+    }
+
+    /// # Returns
+    /// * `String`: The algorithm identifier, `"sha256"` or `"wait"`.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn algorithm(&self) -> String {
+        self.inner.algorithm().to_string()
+    }
+
     /// # Returns
     /// * `String` The website ID string.
     #[cfg(any(feature = "wasm", rust_analyzer))]
     #[wasm_bindgen(getter)]
     pub fn website_id(&self) -> String {
-        self.inner.website_id.clone()
+        self.inner.website_id.to_string()
     }
 
     /// # Returns
@@ -235,10 +297,10 @@ impl JsIronShieldChallenge {
     ///
     /// # Returns
     /// * `String`: Random hex-encoded nonce.
-    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[cfg(all(any(feature = "wasm", rust_analyzer), feature = "rng"))]
     #[wasm_bindgen]
     pub fn generate_random_nonce() -> String {
-        IronShieldChallenge::generate_random_nonce()
+        IronShieldChallenge::generate_random_nonce().into_inner()
     }
 
     /// Calculates recommended attempts for a given difficulty.
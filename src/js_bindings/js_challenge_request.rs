@@ -0,0 +1,190 @@
+//! # JavaScript binding functionality for the IronShield Challenge Request (IronShieldChallengeRequest struct)
+
+use crate::{
+    DeviceClass,
+    IronShieldChallengeRequest,
+    PowAlgorithm
+};
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+use wasm_bindgen::prelude::*;
+
+/// JavaScript-compatible wrapper for `IronShieldChallengeRequest`
+/// with JSON serialization.
+#[cfg(any(feature = "wasm", rust_analyzer))]
+#[wasm_bindgen]
+pub struct JsIronShieldChallengeRequest {
+    inner: IronShieldChallengeRequest,
+}
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+#[wasm_bindgen]
+impl JsIronShieldChallengeRequest {
+    /// Creates a new JavaScript constructor that can be called with
+    /// `new JsIronShieldChallengeRequest()`.
+    ///
+    /// The `IronShieldChallengeRequest` is built from individual
+    /// components on the client side before being sent to the issuer.
+    ///
+    /// # Arguments
+    /// * `website_id`:            The identifier of the website.
+    /// * `algorithm`:             The desired algorithm identifier (e.g. `"sha256"`).
+    /// * `device_class`:          The device-class hint (e.g. `"mobile"`).
+    /// * `client_public_key_hex`: Optional hex-encoded Ed25519 client public key.
+    ///
+    /// # Returns
+    /// * `Result<Self, JsValue>`: New request or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        website_id:            &str,
+        algorithm:             &str,
+        device_class:          &str,
+        client_public_key_hex: Option<String>,
+    ) -> Result<Self, JsValue> {
+        if website_id.is_empty() {
+            return Err(JsValue::from_str("Website ID cannot be empty"));
+        }
+
+        let algorithm: PowAlgorithm = algorithm.parse()
+            .map_err(|e: String| JsValue::from_str(&e))?;
+        let device_class: DeviceClass = device_class.parse()
+            .map_err(|e: String| JsValue::from_str(&e))?;
+
+        let client_public_key: Option<[u8; 32]> = match client_public_key_hex {
+            Some(key_hex) => {
+                let key_bytes: Vec<u8> = hex::decode(&key_hex)
+                    .map_err(|e| JsValue::from_str(&format!("Failed to decode client public key hex: {}", e)))?;
+                let key: [u8; 32] = key_bytes.try_into()
+                    .map_err(|_| JsValue::from_str("Client public key must be exactly 32 bytes"))?;
+                Some(key)
+            }
+            None => None,
+        };
+
+        let request = IronShieldChallengeRequest::new(
+            website_id.to_string(),
+            algorithm,
+            device_class,
+            client_public_key,
+        );
+        Ok(Self { inner: request })
+    }
+
+    /// Creates a new JavaScript binding for the `IronShieldChallengeRequest`
+    /// from a JSON string.
+    ///
+    /// # Arguments
+    /// * `json_str`: JSON representation of the challenge request.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldChallengeRequest, JsValue>`: A wrapped request
+    ///                                                    or an error if parsing fails.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_json(json_str: &str) -> Result<Self, JsValue> {
+        let request: IronShieldChallengeRequest = serde_json::from_str(json_str)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse JSON: {}", e)))?;
+
+        Ok(Self { inner: request })
+    }
+
+    /// Converts the `JsIronShieldChallengeRequest` to a JSON string.
+    ///
+    /// # Returns
+    /// * `Result<String, JsValue>`: A JSON string representation of the request
+    ///                              or an error if serialization fails.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.inner)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize challenge request: {}", e)))
+    }
+
+    /// Converts the challenge request to a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Returns
+    /// * `Result<JsValue, JsValue>`: Plain object with the same shape as
+    ///                               `to_json()`, or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_js_value(&self) -> Result<JsValue, JsValue> {
+        crate::js_bindings::js_value::to_js_value(&self.inner, "challenge request")
+    }
+
+    /// Creates a challenge request from a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Arguments
+    /// * `value`: Plain object with the same shape as `to_json()` output.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldChallengeRequest, JsValue>`: The wrapped challenge request or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_js_value(value: JsValue) -> Result<JsIronShieldChallengeRequest, JsValue> {
+        let inner: IronShieldChallengeRequest = crate::js_bindings::js_value::from_js_value(value, "challenge request")?;
+        Ok(Self { inner })
+    }
+
+    /// Encodes the challenge request as a Base64 URL-safe string
+    /// without padding.
+    ///
+    /// # Returns
+    /// * `String`: A Base64 URL-safe encoded string of the request.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    /// Creates a challenge request from a Base64 URL-safe encoded header string-value.
+    ///
+    /// # Arguments
+    /// * `encoded_header_value`: The Base64 URL-safe encoded string to decode.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldChallengeRequest, JsValue>`: Decoded request or an
+    ///                                                    error if decoding fails.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_base64url_header(encoded_header_value: &str) -> Result<Self, JsValue> {
+        let request = IronShieldChallengeRequest::from_base64url_header(encoded_header_value)
+            .map_err(|e| JsValue::from_str(&format!("Failed to decode Base64 URL-safe header: {}", e)))?;
+
+        Ok(Self { inner: request })
+    }
+
+    /// # Returns
+    /// * `String`: The website ID of the request.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn website_id(&self) -> String {
+        self.inner.website_id.clone()
+    }
+
+    /// # Returns
+    /// * `String`: The requested algorithm identifier.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn algorithm(&self) -> String {
+        self.inner.algorithm.to_string()
+    }
+
+    /// # Returns
+    /// * `String`: The device-class hint.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn device_class(&self) -> String {
+        self.inner.device_class.to_string()
+    }
+
+    /// # Returns
+    /// * `Option<String>`: The client public key as a hex string, if present.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn client_public_key_hex(&self) -> Option<String> {
+        self.inner.client_public_key.map(hex::encode)
+    }
+}
@@ -77,6 +77,33 @@ impl JsIronShieldRequest {
         serde_json::to_string(&self.inner)
             .map_err(|e| JsValue::from_str(&format!("Failed to serialize request: {}", e)))
     }
+
+    /// Converts the request to a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Returns
+    /// * `Result<JsValue, JsValue>`: Plain object with the same shape as
+    ///                               `to_json()`, or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_js_value(&self) -> Result<JsValue, JsValue> {
+        crate::js_bindings::js_value::to_js_value(&self.inner, "request")
+    }
+
+    /// Creates a request from a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Arguments
+    /// * `value`: Plain object with the same shape as `to_json()` output.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldRequest, JsValue>`: The wrapped request or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_js_value(value: JsValue) -> Result<JsIronShieldRequest, JsValue> {
+        let inner: IronShieldRequest = crate::js_bindings::js_value::from_js_value(value, "request")?;
+        Ok(Self { inner })
+    }
     
     /// Converts the request to a JavaScript object.
     /// 
@@ -73,6 +73,33 @@ impl JsIronShieldChallengeResponse {
         serde_json::to_string(&self.inner)
             .map_err(|e| JsValue::from_str(&format!("Failed to serialize response to JSON: {}", e)))
     }
+
+    /// Converts the response to a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Returns
+    /// * `Result<JsValue, JsValue>`: Plain object with the same shape as
+    ///                               `to_json()`, or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_js_value(&self) -> Result<JsValue, JsValue> {
+        crate::js_bindings::js_value::to_js_value(&self.inner, "response")
+    }
+
+    /// Creates a response from a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Arguments
+    /// * `value`: Plain object with the same shape as `to_json()` output.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldChallengeResponse, JsValue>`: The wrapped response or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_js_value(value: JsValue) -> Result<JsIronShieldChallengeResponse, JsValue> {
+        let inner: IronShieldChallengeResponse = crate::js_bindings::js_value::from_js_value(value, "response")?;
+        Ok(Self { inner })
+    }
     
     /// Converts the response to a JavaScript object.
     ///
@@ -0,0 +1,66 @@
+//! # JavaScript binding functionality for the Retry Policy (RetryPolicy struct)
+use crate::RetryPolicy;
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+use wasm_bindgen::prelude::*;
+
+/// JavaScript-compatible wrapper for `RetryPolicy`
+/// with JSON serialization.
+#[cfg(any(feature = "wasm", rust_analyzer))]
+#[wasm_bindgen]
+pub struct JsRetryPolicy {
+    inner: RetryPolicy,
+}
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+#[wasm_bindgen]
+impl JsRetryPolicy {
+    /// Creates a new JavaScript binding for the `RetryPolicy`
+    /// from a JSON string, typically the `retry_policy` of an
+    /// error response.
+    ///
+    /// # Arguments
+    /// * `json_str`: JSON representation of the policy.
+    ///
+    /// # Returns
+    /// * `Result<Self, JsValue>`: Parsed policy or error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_json(json_str: &str) -> Result<Self, JsValue> {
+        let policy: RetryPolicy = serde_json::from_str(json_str)
+            .map_err(|e: serde_json::Error| JsValue::from_str(&format!("Failed to parse retry policy JSON: {}", e)))?;
+        policy.validate().map_err(|e: String| JsValue::from_str(&e))?;
+
+        Ok(Self { inner: policy })
+    }
+
+    /// # Returns
+    /// * `JsRetryPolicy`: The default policy, for responses that
+    ///                    carry none.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn default_policy() -> Self {
+        Self { inner: RetryPolicy::default() }
+    }
+
+    /// # Arguments
+    /// * `attempt`: Zero-based index of the retry about to be made.
+    /// * `random`:  A value from `Math.random()`.
+    ///
+    /// # Returns
+    /// * `Option<f64>`: Milliseconds to wait, or `undefined` once the
+    ///                  retries are used up.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn delay_ms(&self, attempt: u32, random: f64) -> Option<f64> {
+        self.inner.delay_ms(attempt, random).map(|delay| delay as f64)
+    }
+
+    /// # Returns
+    /// * `u32`: Retries allowed after the first failure.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn max_retries(&self) -> u32 {
+        self.inner.max_retries
+    }
+}
@@ -47,6 +47,33 @@ impl JsIronShieldToken {
         serde_json::to_string(&self.inner)
             .map_err(|e: serde_json::Error| JsValue::from_str(&format!("Failed to serialize token to JSON: {}", e)))
     }
+
+    /// Converts the token to a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Returns
+    /// * `Result<JsValue, JsValue>`: Plain object with the same shape as
+    ///                               `to_json()`, or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn to_js_value(&self) -> Result<JsValue, JsValue> {
+        crate::js_bindings::js_value::to_js_value(&self.inner, "token")
+    }
+
+    /// Creates a token from a plain JavaScript object, avoiding
+    /// a round trip through a JSON string.
+    ///
+    /// # Arguments
+    /// * `value`: Plain object with the same shape as `to_json()` output.
+    ///
+    /// # Returns
+    /// * `Result<JsIronShieldToken, JsValue>`: The wrapped token or an error.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen]
+    pub fn from_js_value(value: JsValue) -> Result<JsIronShieldToken, JsValue> {
+        let inner: IronShieldToken = crate::js_bindings::js_value::from_js_value(value, "token")?;
+        Ok(Self { inner })
+    }
     
     /// Converts the token to a JavaScript object.
     /// 
@@ -119,5 +146,13 @@ impl JsIronShieldToken {
     pub fn authentication_signature_hex(&self) -> String {
         hex::encode(self.inner.auth_signature)
     }
-}
 
+    /// # Returns
+    /// * `Option<String>`: The attested request body digest as hex string,
+    ///                     if the token carries one.
+    #[cfg(any(feature = "wasm", rust_analyzer))]
+    #[wasm_bindgen(getter)]
+    pub fn body_digest_hex(&self) -> Option<String> {
+        self.inner.body_digest.map(hex::encode)
+    }
+}
@@ -0,0 +1,41 @@
+//! # Plain JavaScript object conversions shared by the bindings.
+//!
+//! Values cross the WASM boundary as plain objects instead of JSON
+//! strings. The JSON-compatible serializer is used so that maps become
+//! plain objects (not `Map`s) and the shape matches `to_json()`.
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+use serde::{
+    de::DeserializeOwned,
+    Serialize
+};
+#[cfg(any(feature = "wasm", rust_analyzer))]
+use wasm_bindgen::prelude::*;
+
+/// Converts a value into a plain JavaScript object.
+///
+/// # Arguments
+/// * `value`: The value to convert.
+/// * `what`:  Name of the value for error messages.
+///
+/// # Returns
+/// * `Result<JsValue, JsValue>`: The JavaScript object or an error.
+#[cfg(any(feature = "wasm", rust_analyzer))]
+pub(crate) fn to_js_value<T: Serialize>(value: &T, what: &str) -> Result<JsValue, JsValue> {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e: serde_wasm_bindgen::Error| JsValue::from_str(&format!("Failed to convert {} to JS value: {}", what, e)))
+}
+
+/// Converts a plain JavaScript object into a value.
+///
+/// # Arguments
+/// * `value`: The JavaScript object to convert.
+/// * `what`:  Name of the value for error messages.
+///
+/// # Returns
+/// * `Result<T, JsValue>`: The converted value or an error.
+#[cfg(any(feature = "wasm", rust_analyzer))]
+pub(crate) fn from_js_value<T: DeserializeOwned>(value: JsValue, what: &str) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|e: serde_wasm_bindgen::Error| JsValue::from_str(&format!("Failed to convert JS value to {}: {}", what, e)))
+}
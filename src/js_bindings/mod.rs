@@ -3,6 +3,9 @@
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub mod js_challenge;
 
+#[cfg(any(feature = "wasm", rust_analyzer))]
+pub mod js_challenge_request;
+
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub mod js_request;
 
@@ -12,9 +15,18 @@ pub mod js_response;
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub mod js_token;
 
+#[cfg(any(feature = "wasm", rust_analyzer))]
+pub mod js_retry;
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+mod js_value;
+
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub use js_challenge::*;
 
+#[cfg(any(feature = "wasm", rust_analyzer))]
+pub use js_challenge_request::*;
+
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub use js_request::*;
 
@@ -22,4 +34,7 @@ pub use js_request::*;
 pub use js_response::*;
 
 #[cfg(any(feature = "wasm", rust_analyzer))]
-pub use js_token::*;
\ No newline at end of file
+pub use js_token::*;
+
+#[cfg(any(feature = "wasm", rust_analyzer))]
+pub use js_retry::*;
\ No newline at end of file
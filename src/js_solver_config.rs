@@ -0,0 +1,107 @@
+//! # Tuning of the browser solver, chosen by the issuer.
+//!
+//! The JavaScript solver reads a `JsSolverConfig` from the interstitial
+//! page instead of relying on constants in its bundle, so worker counts
+//! and pacing can be tuned per site, or rolled back, without shipping
+//! new client code to browsers that have the old bundle cached.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Largest accepted `worker_count`.
+const MAX_WORKER_COUNT:         u32 = 64;
+/// Smallest accepted `progress_interval_ms`, one frame at 60 Hz.
+const MIN_PROGRESS_INTERVAL_MS: u32 = 16;
+/// Smallest accepted `max_memory_kib`, one WASM memory page.
+const MIN_MEMORY_KIB:           u32 = 64;
+
+/// How the browser solver runs.
+///
+/// * `worker_count`:         Web Workers to start, or `None` for
+///                           `navigator.hardwareConcurrency`.
+/// * `yield_interval`:       Nonces tried between yields to the event
+///                           loop, keeping the page responsive.
+/// * `progress_interval_ms`: Minimum time between two progress
+///                           callbacks.
+/// * `max_memory_kib`:       Memory each worker may allocate, if
+///                           limited.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JsSolverConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_count:         Option<u32>,
+    pub yield_interval:       u32,
+    pub progress_interval_ms: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_kib:       Option<u32>,
+}
+
+impl Default for JsSolverConfig {
+    fn default() -> Self {
+        Self {
+            worker_count:         None,
+            yield_interval:       50_000,
+            progress_interval_ms: 250,
+            max_memory_kib:       None,
+        }
+    }
+}
+
+impl JsSolverConfig {
+    /// Checks that every setting is in a range the solver can honor.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid setting.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.worker_count.is_some_and(|count| !(1..=MAX_WORKER_COUNT).contains(&count)) {
+            return Err(format!("worker_count must be between 1 and {}", MAX_WORKER_COUNT));
+        }
+        if self.yield_interval == 0 {
+            return Err("yield_interval must be positive".to_string());
+        }
+        if self.progress_interval_ms < MIN_PROGRESS_INTERVAL_MS {
+            return Err(format!("progress_interval_ms must be at least {}", MIN_PROGRESS_INTERVAL_MS));
+        }
+        if self.max_memory_kib.is_some_and(|kib| kib < MIN_MEMORY_KIB) {
+            return Err(format!("max_memory_kib must be at least {}", MIN_MEMORY_KIB));
+        }
+        Ok(())
+    }
+
+    /// # Arguments
+    /// * `hardware_concurrency`: `navigator.hardwareConcurrency`, or 0
+    ///                           if the browser does not report it.
+    ///
+    /// # Returns
+    /// * `u32`: The number of workers to start, at least 1.
+    pub fn effective_worker_count(&self, hardware_concurrency: u32) -> u32 {
+        self.worker_count
+            .unwrap_or(hardware_concurrency)
+            .clamp(1, MAX_WORKER_COUNT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_and_workers() {
+        let config = JsSolverConfig::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.effective_worker_count(8), 8);
+        assert_eq!(config.effective_worker_count(0), 1);
+        assert_eq!(JsSolverConfig { worker_count: Some(2), ..config }.effective_worker_count(8), 2);
+
+        assert!(JsSolverConfig { worker_count: Some(0), ..config }.validate().is_err());
+        assert!(JsSolverConfig { yield_interval: 0, ..config }.validate().is_err());
+        assert!(JsSolverConfig { progress_interval_ms: 1, ..config }.validate().is_err());
+        assert!(JsSolverConfig { max_memory_kib: Some(1), ..config }.validate().is_err());
+
+        let json: String = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"yield_interval":50000,"progress_interval_ms":250}"#);
+    }
+}
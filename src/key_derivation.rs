@@ -0,0 +1,218 @@
+//! # Per-website signing keys derived from a master seed.
+//!
+//! Instead of signing every website's challenges with one global key,
+//! an issuer can derive a deterministic key per website with
+//! HKDF-SHA256(master seed, website id). Only the master seed has to be
+//! stored; a leaked website key does not expose the other websites.
+//!
+//! Derived public keys cannot be computed from the master public key,
+//! so verifiers are given the derived key of each website (e.g. through
+//! a `TenantKeyRegistry`) and pick a `DerivedKeyPolicy` for the
+//! migration from the master key.
+
+#[cfg(feature = "sign")]
+use ed25519_dalek::SigningKey;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::{
+    base58check_encode,
+    verify_challenge_signature_with_key,
+    CryptoError,
+    IronShieldChallenge
+};
+#[cfg(feature = "sign")]
+use crate::WebsiteId;
+
+/// HKDF salt, fixed so that derivation is deterministic.
+#[cfg(feature = "sign")]
+const DERIVATION_SALT: &[u8] = b"ironshield-website-key-v1";
+/// Domain separation prefix of key fingerprints.
+const FINGERPRINT_CONTEXT: &[u8] = b"ironshield-key-fingerprint-v1";
+/// Number of digest bytes kept in a fingerprint.
+const FINGERPRINT_LEN: usize = 16;
+/// Block size of SHA-256, as used by HMAC.
+const SHA256_BLOCK_LEN: usize = 64;
+
+/// Which keys are accepted for a website's challenges.
+///
+/// * `MasterOnly`:      Only the master key (before migration).
+/// * `MasterOrDerived`: Either key, while issuers switch over.
+/// * `DerivedOnly`:     Only the website's derived key.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DerivedKeyPolicy {
+    MasterOnly,
+    #[default]
+    MasterOrDerived,
+    DerivedOnly,
+}
+
+/// HMAC-SHA256 (RFC 2104).
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; SHA256_BLOCK_LEN];
+    if key.len() > SHA256_BLOCK_LEN {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner_pad: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+    let outer_pad: Vec<u8> = block.iter().map(|byte| byte ^ 0x5C).collect();
+    let inner: [u8; 32] = Sha256::new()
+        .chain_update(&inner_pad)
+        .chain_update(message)
+        .finalize()
+        .into();
+
+    Sha256::new()
+        .chain_update(&outer_pad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// HKDF-SHA256 (RFC 5869) with a single 32-byte output block.
+#[cfg(any(feature = "sign", feature = "sealed-challenges"))]
+pub(crate) fn hkdf_sha256(input_key: &[u8], salt: &[u8], info: &[u8]) -> [u8; 32] {
+    let pseudo_random_key: [u8; 32] = hmac_sha256(salt, input_key);
+    let mut expand_input: Vec<u8> = Vec::with_capacity(info.len() + 1);
+    expand_input.extend_from_slice(info);
+    expand_input.push(1);
+    hmac_sha256(&pseudo_random_key, &expand_input)
+}
+
+/// Derives the signing key of a website from the master key.
+///
+/// # Arguments
+/// * `master_key`: The master signing key; its seed is the HKDF input.
+/// * `website_id`: The website, normalized so equivalent spellings
+///                 derive the same key.
+///
+/// # Returns
+/// * `SigningKey`: The website's deterministic signing key.
+#[cfg(feature = "sign")]
+pub fn derive_website_signing_key(master_key: &SigningKey, website_id: &WebsiteId) -> SigningKey {
+    let seed: [u8; 32] = hkdf_sha256(&master_key.to_bytes(), DERIVATION_SALT, website_id.as_str().as_bytes());
+    SigningKey::from_bytes(&seed)
+}
+
+/// Short, human-comparable fingerprint of a public key, e.g. to confirm
+/// a derived key published for a website.
+///
+/// # Arguments
+/// * `public_key`: The Ed25519 public key.
+///
+/// # Returns
+/// * `String`: Base58check of the first 16 bytes of a domain-separated
+///             SHA-256 of the key.
+pub fn key_fingerprint(public_key: &[u8; 32]) -> String {
+    let digest: [u8; 32] = Sha256::new()
+        .chain_update(FINGERPRINT_CONTEXT)
+        .chain_update(public_key)
+        .finalize()
+        .into();
+    base58check_encode(&digest[..FINGERPRINT_LEN])
+}
+
+/// Verifies a challenge signed with either the master key or the
+/// website's derived key, as allowed by `policy`.
+///
+/// # Arguments
+/// * `challenge`:          The challenge to verify.
+/// * `master_public_key`:  The master public key.
+/// * `derived_public_key`: The derived public key of the challenge's
+///                         website, if one was published.
+/// * `policy`:             Which of the two keys are accepted.
+///
+/// # Returns
+/// * `Result<(), CryptoError>`: `Ok(())` if the challenge was signed with
+///                              an accepted key, error otherwise.
+pub fn verify_challenge_signature_with_key_policy(
+    challenge: &IronShieldChallenge,
+    master_public_key: &[u8; 32],
+    derived_public_key: Option<&[u8; 32]>,
+    policy: DerivedKeyPolicy
+) -> Result<(), CryptoError> {
+    let master_accepted: bool = policy != DerivedKeyPolicy::DerivedOnly
+        && challenge.public_key == *master_public_key;
+    let derived_accepted: bool = policy != DerivedKeyPolicy::MasterOnly
+        && derived_public_key == Some(&challenge.public_key);
+
+    if !master_accepted && !derived_accepted {
+        return Err(CryptoError::VerificationFailed(format!(
+            "Challenge key {} is not accepted for {} under policy {:?}",
+            key_fingerprint(&challenge.public_key),
+            challenge.website_id,
+            policy
+        ).into()));
+    }
+
+    verify_challenge_signature_with_key(challenge, &challenge.public_key)
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_and_hkdf_vectors() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // RFC 5869, test case 1 (first 32 bytes of OKM).
+        let okm: [u8; 32] = hkdf_sha256(
+            &[0x0B; 22],
+            &hex::decode("000102030405060708090a0b0c").unwrap(),
+            &hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap()
+        );
+        assert_eq!(hex::encode(okm), "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf");
+    }
+
+    #[test]
+    fn test_derived_keys() {
+        let master = SigningKey::from_bytes(&[5; 32]);
+        let a: SigningKey = derive_website_signing_key(&master, &WebsiteId::parse("A.example").unwrap());
+        let a_url: SigningKey = derive_website_signing_key(&master, &WebsiteId::parse("https://a.example/").unwrap());
+        let b: SigningKey = derive_website_signing_key(&master, &WebsiteId::parse("b.example").unwrap());
+        assert_eq!(a.to_bytes(), a_url.to_bytes());
+        assert_ne!(a.to_bytes(), b.to_bytes());
+        assert_ne!(a.to_bytes(), master.to_bytes());
+
+        let fingerprint: String = key_fingerprint(&a.verifying_key().to_bytes());
+        assert_eq!(crate::base58check_decode(&fingerprint).unwrap().len(), FINGERPRINT_LEN);
+        assert_ne!(fingerprint, key_fingerprint(&b.verifying_key().to_bytes()));
+    }
+
+    #[cfg(feature = "rng")]
+    #[test]
+    fn test_verify_with_key_policy() {
+        let master = SigningKey::from_bytes(&[5; 32]);
+        let master_public: [u8; 32] = master.verifying_key().to_bytes();
+        let derived: SigningKey = derive_website_signing_key(&master, &WebsiteId::parse("a.example").unwrap());
+        let derived_public: [u8; 32] = derived.verifying_key().to_bytes();
+
+        let by_master = IronShieldChallenge::new("a.example".parse().unwrap(), 1_000, master, master_public);
+        let by_derived = IronShieldChallenge::new("a.example".parse().unwrap(), 1_000, derived, derived_public);
+        let verify = |challenge: &IronShieldChallenge, policy: DerivedKeyPolicy| {
+            verify_challenge_signature_with_key_policy(challenge, &master_public, Some(&derived_public), policy).is_ok()
+        };
+
+        assert!(verify(&by_master, DerivedKeyPolicy::MasterOnly));
+        assert!(!verify(&by_derived, DerivedKeyPolicy::MasterOnly));
+        assert!(verify(&by_master, DerivedKeyPolicy::MasterOrDerived));
+        assert!(verify(&by_derived, DerivedKeyPolicy::MasterOrDerived));
+        assert!(!verify(&by_master, DerivedKeyPolicy::DerivedOnly));
+        assert!(verify(&by_derived, DerivedKeyPolicy::DerivedOnly));
+        assert!(verify_challenge_signature_with_key_policy(&by_derived, &master_public, None, DerivedKeyPolicy::DerivedOnly).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! # Passphrase-encrypted export of the signing key.
+//!
+//! Moving a signing key between environments should not mean pasting
+//! its plaintext seed into a terminal or a secrets manager's web form.
+//! `export_private_key_encrypted` seals the seed under a passphrase:
+//! scrypt stretches the passphrase into a 32-byte key, which encrypts
+//! the seed with XChaCha20-Poly1305. The result is an ASCII-armored
+//! block that `import_private_key_encrypted` turns back into the key.
+//!
+//! The binary payload starts with a versioned header:
+//!
+//! * `magic`:      `IRONSHIELD-KEY`.
+//! * `version`:    `KEY_ENCRYPTION_VERSION`.
+//! * `log_n`, `r`, `p`: The scrypt parameters, `r` and `p` as big-endian
+//!                 `u32`s.
+//! * `salt`:       16 random bytes.
+//! * `nonce`:      24 random bytes.
+//! * `public_key`: The Ed25519 public key of the exported key.
+//!
+//! followed by the encrypted seed and its 16-byte tag. The whole header
+//! is the AEAD's associated data, so a changed parameter or public key
+//! fails authentication just like a changed ciphertext. A wrong
+//! passphrase and a tampered block cannot be told apart, and both are
+//! reported as `VerificationFailed`.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305,
+    XNonce
+};
+use ed25519_dalek::SigningKey;
+use rand::RngCore;
+use rand::rngs::OsRng;
+
+use crate::CryptoError;
+
+/// Label of the armored key block.
+const ENCRYPTED_KEY_ARMOR_LABEL: &str = "IRONSHIELD ENCRYPTED KEY";
+/// Magic bytes at the start of every encrypted key payload.
+const ENCRYPTED_KEY_MAGIC: &[u8] = b"IRONSHIELD-KEY";
+/// Version byte following the magic.
+pub const KEY_ENCRYPTION_VERSION: u8 = 1;
+/// Length of the random scrypt salt.
+const SALT_LEN: usize = 16;
+/// Length of the XChaCha20-Poly1305 nonce.
+const NONCE_LEN: usize = 24;
+/// Length of the Poly1305 tag appended to the ciphertext.
+const TAG_LEN: usize = 16;
+/// Length of the header: magic, version, log_n, r, p, salt, nonce and
+/// public key.
+const HEADER_LEN: usize = ENCRYPTED_KEY_MAGIC.len() + 1 + 1 + 4 + 4 + SALT_LEN + NONCE_LEN + 32;
+/// Largest scrypt parameters accepted on import, so a crafted header
+/// cannot make the importer allocate gigabytes (`log_n = 20` with
+/// `r = 8` already takes 1 GiB).
+const MAX_LOG_N: u8 = 20;
+const MAX_R:     u32 = 32;
+const MAX_P:     u32 = 16;
+
+/// scrypt cost parameters used to stretch the passphrase.
+///
+/// * `log_n`: Base-2 logarithm of the CPU/memory cost.
+/// * `r`:     Block size.
+/// * `p`:     Parallelization.
+///
+/// The default is scrypt's recommendation (`log_n = 17`, `r = 8`,
+/// `p = 1`), which takes 128 MiB and around a second per export or
+/// import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEncryptionParams {
+    pub log_n: u8,
+    pub r:     u32,
+    pub p:     u32,
+}
+
+impl Default for KeyEncryptionParams {
+    fn default() -> Self {
+        Self {
+            log_n: scrypt::Params::RECOMMENDED_LOG_N,
+            r:     scrypt::Params::RECOMMENDED_R,
+            p:     scrypt::Params::RECOMMENDED_P,
+        }
+    }
+}
+
+impl KeyEncryptionParams {
+    /// Derives the 32-byte encryption key from a passphrase.
+    fn derive_key(&self, passphrase: &str, salt: &[u8]) -> Result<[u8; 32], CryptoError> {
+        if self.log_n > MAX_LOG_N || self.r > MAX_R || self.p > MAX_P {
+            return Err(CryptoError::InvalidKeyFormat(format!(
+                "scrypt parameters log_n={}, r={}, p={} exceed the supported maximum",
+                self.log_n, self.r, self.p
+            ).into()));
+        }
+        let params: scrypt::Params = scrypt::Params::new(self.log_n, self.r, self.p, 32)
+            .map_err(|e| CryptoError::InvalidKeyFormat(format!("Invalid scrypt parameters: {}", e).into()))?;
+
+        let mut key = [0u8; 32];
+        scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+            .map_err(|e| CryptoError::InvalidKeyFormat(format!("scrypt failed: {}", e).into()))?;
+        Ok(key)
+    }
+}
+
+/// Encrypts a signing key under a passphrase with the default scrypt
+/// parameters.
+///
+/// # Arguments
+/// * `signing_key`: The key to export.
+/// * `passphrase`:  The passphrase the importer will need.
+///
+/// # Returns
+/// * `Result<String, CryptoError>`: The `-----BEGIN IRONSHIELD ENCRYPTED KEY-----`
+///                                  block.
+pub fn export_private_key_encrypted(signing_key: &SigningKey, passphrase: &str) -> Result<String, CryptoError> {
+    export_private_key_encrypted_with_params(signing_key, passphrase, KeyEncryptionParams::default())
+}
+
+/// Encrypts a signing key under a passphrase with explicit scrypt
+/// parameters, e.g. cheaper ones for tests.
+///
+/// # Arguments
+/// * `signing_key`: The key to export.
+/// * `passphrase`:  The passphrase the importer will need.
+/// * `params`:      The scrypt parameters, stored in the header.
+///
+/// # Returns
+/// * `Result<String, CryptoError>`: The armored block, or an error if the
+///                                  parameters are out of range.
+pub fn export_private_key_encrypted_with_params(
+    signing_key: &SigningKey,
+    passphrase: &str,
+    params: KeyEncryptionParams
+) -> Result<String, CryptoError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut header: Vec<u8> = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(ENCRYPTED_KEY_MAGIC);
+    header.push(KEY_ENCRYPTION_VERSION);
+    header.push(params.log_n);
+    header.extend_from_slice(&params.r.to_be_bytes());
+    header.extend_from_slice(&params.p.to_be_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+    header.extend_from_slice(&signing_key.verifying_key().to_bytes());
+
+    let mut key: [u8; 32] = params.derive_key(passphrase, &salt)?;
+    let mut seed: [u8; 32] = signing_key.to_bytes();
+    let sealed = XChaCha20Poly1305::new(&key.into())
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &seed, aad: &header })
+        .map_err(|_| CryptoError::SigningFailed("Failed to encrypt the signing key".into()));
+    key.fill(0);
+    seed.fill(0);
+
+    let mut payload: Vec<u8> = header;
+    payload.extend_from_slice(&sealed?);
+    Ok(crate::armor::encode_armor(ENCRYPTED_KEY_ARMOR_LABEL, &payload))
+}
+
+/// Decrypts a signing key exported by `export_private_key_encrypted()`.
+///
+/// # Arguments
+/// * `armored`:    The armored block.
+/// * `passphrase`: The passphrase it was exported with.
+///
+/// # Returns
+/// * `Result<SigningKey, CryptoError>`: The key; `VerificationFailed` if
+///                                      the passphrase is wrong or the
+///                                      block was altered, and
+///                                      `InvalidKeyFormat` if it is not
+///                                      an encrypted key of a supported
+///                                      version.
+pub fn import_private_key_encrypted(armored: &str, passphrase: &str) -> Result<SigningKey, CryptoError> {
+    let payload: Vec<u8> = crate::armor::decode_armor(ENCRYPTED_KEY_ARMOR_LABEL, armored)
+        .map_err(|e| CryptoError::InvalidKeyFormat(e.into()))?;
+    if payload.len() != HEADER_LEN + 32 + TAG_LEN {
+        return Err(CryptoError::InvalidKeyFormat(format!(
+            "Encrypted key payload must be {} bytes, got {}",
+            HEADER_LEN + 32 + TAG_LEN,
+            payload.len()
+        ).into()));
+    }
+
+    let (header, sealed) = payload.split_at(HEADER_LEN);
+    let (magic, rest) = header.split_at(ENCRYPTED_KEY_MAGIC.len());
+    if magic != ENCRYPTED_KEY_MAGIC {
+        return Err(CryptoError::InvalidKeyFormat("Not an IronShield encrypted key".into()));
+    }
+    if rest[0] != KEY_ENCRYPTION_VERSION {
+        return Err(CryptoError::InvalidKeyFormat(format!("Unsupported encrypted key version: {}", rest[0]).into()));
+    }
+
+    // The lengths were checked above, so these slices are exact.
+    let params = KeyEncryptionParams {
+        log_n: rest[1],
+        r:     u32::from_be_bytes(rest[2..6].try_into().unwrap_or_default()),
+        p:     u32::from_be_bytes(rest[6..10].try_into().unwrap_or_default()),
+    };
+    let salt: &[u8] = &rest[10..10 + SALT_LEN];
+    let nonce: &[u8] = &rest[10 + SALT_LEN..10 + SALT_LEN + NONCE_LEN];
+    let public_key: &[u8] = &rest[10 + SALT_LEN + NONCE_LEN..];
+
+    let mut key: [u8; 32] = params.derive_key(passphrase, salt)?;
+    let opened = XChaCha20Poly1305::new(&key.into())
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: sealed, aad: header });
+    key.fill(0);
+    let mut seed: Vec<u8> = opened.map_err(|_| CryptoError::VerificationFailed(
+        "Wrong passphrase, or the encrypted key was altered".into()
+    ))?;
+
+    let seed_bytes: Result<[u8; 32], CryptoError> = seed.as_slice().try_into()
+        .map_err(|_| CryptoError::InvalidKeyFormat("Decrypted seed is not 32 bytes".into()));
+    seed.fill(0);
+    let signing_key: SigningKey = SigningKey::from_bytes(&seed_bytes?);
+
+    if signing_key.verifying_key().to_bytes() != public_key {
+        return Err(CryptoError::VerificationFailed("Decrypted key does not match the exported public key".into()));
+    }
+    Ok(signing_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{
+        Engine,
+        engine::general_purpose::STANDARD
+    };
+
+    /// Cheap parameters so the tests do not spend 128 MiB per call.
+    const TEST_PARAMS: KeyEncryptionParams = KeyEncryptionParams { log_n: 4, r: 8, p: 1 };
+
+    #[test]
+    fn test_encrypted_key_round_trip() {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let armored: String = export_private_key_encrypted_with_params(&key, "correct horse", TEST_PARAMS).unwrap();
+        assert!(armored.starts_with("-----BEGIN IRONSHIELD ENCRYPTED KEY-----"));
+        assert!(!armored.contains(&STANDARD.encode([5u8; 32])));
+
+        let imported: SigningKey = import_private_key_encrypted(&armored, "correct horse").unwrap();
+        assert_eq!(imported.to_bytes(), key.to_bytes());
+
+        // Fresh salt and nonce per export.
+        let again: String = export_private_key_encrypted_with_params(&key, "correct horse", TEST_PARAMS).unwrap();
+        assert_ne!(again, armored);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let armored: String = export_private_key_encrypted_with_params(&key, "correct horse", TEST_PARAMS).unwrap();
+        assert!(matches!(
+            import_private_key_encrypted(&armored, "battery staple"),
+            Err(CryptoError::VerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_payload_is_rejected() {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let armored: String = export_private_key_encrypted_with_params(&key, "pw", TEST_PARAMS).unwrap();
+        let payload: Vec<u8> = crate::armor::decode_armor(ENCRYPTED_KEY_ARMOR_LABEL, &armored).unwrap();
+        let rearmor = |bytes: &[u8]| crate::armor::encode_armor(ENCRYPTED_KEY_ARMOR_LABEL, bytes);
+
+        // Ciphertext, tag, salt and public key.
+        for position in [HEADER_LEN, payload.len() - 1, ENCRYPTED_KEY_MAGIC.len() + 11, HEADER_LEN - 1] {
+            let mut tampered: Vec<u8> = payload.clone();
+            tampered[position] ^= 1;
+            assert!(matches!(
+                import_private_key_encrypted(&rearmor(&tampered), "pw"),
+                Err(CryptoError::VerificationFailed(_))
+            ));
+        }
+
+        let mut version: Vec<u8> = payload.clone();
+        version[ENCRYPTED_KEY_MAGIC.len()] = 2;
+        assert!(matches!(import_private_key_encrypted(&rearmor(&version), "pw"), Err(CryptoError::InvalidKeyFormat(_))));
+
+        let mut expensive: Vec<u8> = payload.clone();
+        expensive[ENCRYPTED_KEY_MAGIC.len() + 1] = 40;
+        assert!(matches!(import_private_key_encrypted(&rearmor(&expensive), "pw"), Err(CryptoError::InvalidKeyFormat(_))));
+
+        assert!(import_private_key_encrypted(&rearmor(&payload[..payload.len() - 1]), "pw").is_err());
+    }
+}
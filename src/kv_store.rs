@@ -0,0 +1,110 @@
+//! # Key-value encoding of stored challenges.
+//!
+//! Workers KV and Durable Object storage hold bytes under string keys,
+//! with an optional TTL. `ChallengeKvEntry` is the record a
+//! `ChallengeStore` adapter over such a backend writes: the key is
+//! derived from `challenge_id()`, the value is the compact binary
+//! encoding, and the TTL follows the challenge's submission deadline.
+//!
+//! KV deletes lazily and only with second granularity, so readers must
+//! still check the deadline themselves; `decode_at` does.
+
+use crate::IronShieldChallenge;
+
+/// Prefix of the keys under which challenges are stored.
+pub const CHALLENGE_KV_PREFIX: &str = "challenge:";
+/// Smallest `expirationTtl` Workers KV accepts, in seconds.
+pub const MIN_KV_TTL_SECS: u64 = 60;
+
+/// # Arguments
+/// * `id`: The `challenge_id()` of a challenge.
+///
+/// # Returns
+/// * `String`: The storage key, `challenge:<hex id>`.
+pub fn challenge_kv_key(id: &[u8; 32]) -> String {
+    format!("{}{}", CHALLENGE_KV_PREFIX, hex::encode(id))
+}
+
+/// A challenge encoded for a key-value backend.
+///
+/// * `key`:        The storage key, see `challenge_kv_key`.
+/// * `value`:      The compact binary encoding of the challenge.
+/// * `expires_at`: Unix milli timestamp after which the challenge can
+///                 no longer be redeemed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeKvEntry {
+    pub key:        String,
+    pub value:      Vec<u8>,
+    pub expires_at: i64,
+}
+
+impl ChallengeKvEntry {
+    /// # Arguments
+    /// * `challenge`: The issued challenge.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The entry, or an error if the challenge
+    ///                           cannot be encoded.
+    pub fn from_challenge(challenge: &IronShieldChallenge) -> Result<Self, String> {
+        Ok(Self {
+            key:        challenge_kv_key(&challenge.challenge_id()),
+            value:      challenge.to_compact_bytes()?,
+            expires_at: challenge.effective_submission_deadline(),
+        })
+    }
+
+    /// TTL to store the entry with, rounded up to whole seconds and
+    /// raised to the backend minimum.
+    ///
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `u64`: The TTL in seconds.
+    pub fn expiration_ttl_at(&self, now: i64) -> u64 {
+        let remaining_ms: u64 = self.expires_at.saturating_sub(now).try_into().unwrap_or(0);
+        remaining_ms.div_ceil(1_000).max(MIN_KV_TTL_SECS)
+    }
+
+    /// Decodes a stored value, treating a challenge past its deadline as
+    /// absent.
+    ///
+    /// # Arguments
+    /// * `value`: The stored bytes.
+    /// * `now`:   The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<Option<IronShieldChallenge>, String>`: The challenge if
+    ///   it is still redeemable at `now`, or an error if the value is
+    ///   corrupted.
+    pub fn decode_at(value: &[u8], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        let challenge: IronShieldChallenge = IronShieldChallenge::from_compact_bytes(value)?;
+        Ok(Some(challenge).filter(|challenge| challenge.accepts_submission_at(now)))
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_entry_roundtrip_and_ttl() {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let mut challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key, [0x34; 32]);
+        challenge.expiration_time = 200_000;
+        challenge.submission_deadline = Some(100_500);
+
+        let entry = ChallengeKvEntry::from_challenge(&challenge).unwrap();
+        assert_eq!(entry.key, format!("challenge:{}", hex::encode(challenge.challenge_id())));
+        assert_eq!(entry.expires_at, 100_500);
+
+        assert_eq!(entry.expiration_ttl_at(0), 101);
+        assert_eq!(entry.expiration_ttl_at(90_000), MIN_KV_TTL_SECS);
+        assert_eq!(entry.expiration_ttl_at(500_000), MIN_KV_TTL_SECS);
+
+        assert_eq!(ChallengeKvEntry::decode_at(&entry.value, 100_500).unwrap(), Some(challenge));
+        assert_eq!(ChallengeKvEntry::decode_at(&entry.value, 100_501).unwrap(), None);
+        assert!(ChallengeKvEntry::decode_at(&entry.value[1..], 0).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! # Progressive difficulty ladders.
+//!
+//! A `ChallengeLadder` extends a signed challenge with further steps of
+//! increasing difficulty. Step 0 is the challenge itself; the nonce of
+//! every later step is derived from the previous step's nonce and
+//! solution, so steps can only be solved in order. The edge can grant
+//! partial trust once the first, cheap rungs are climbed, while a bot
+//! wanting full trust still has to do the work of every step.
+//!
+//! The step difficulties are signed with the challenge key, so a client
+//! cannot shorten the ladder or lower a step.
+
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
+    SigningKey
+};
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::bytes::nonce_to_le_bytes;
+use crate::serde_utils::{
+    deserialize_signature,
+    serialize_signature
+};
+use crate::{
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge,
+    RandomNonce,
+    SolutionVerifier
+};
+
+/// Domain separation prefix of the ladder signing message.
+const LADDER_SIGNING_CONTEXT: &str = "ironshield-ladder-v1";
+/// Domain separation prefix of derived step nonces.
+const STEP_NONCE_CONTEXT:    &[u8] = b"ironshield-ladder-step-v1";
+/// Largest number of steps after the base challenge.
+const MAX_LADDER_STEPS:      usize = 16;
+
+/// A challenge followed by steps of increasing difficulty.
+///
+/// * `challenge`:         Step 0, a regular signed challenge.
+/// * `step_difficulties`: Difficulty of each further step, increasing.
+/// * `ladder_signature`:  Ed25519 signature of the challenge key over
+///                        `signing_message()`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChallengeLadder {
+    pub challenge:         IronShieldChallenge,
+    pub step_difficulties: Vec<u64>,
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub ladder_signature:  [u8; 64],
+}
+
+/// What a client needs to solve one step.
+///
+/// * `random_nonce`:    Hex-encoded nonce of the step.
+/// * `challenge_param`: Target the step's hash must stay below.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LadderStep {
+    pub random_nonce:    RandomNonce,
+    pub challenge_param: [u8; 32],
+}
+
+impl ChallengeLadder {
+    /// Builds and signs a ladder on top of a challenge.
+    ///
+    /// # Arguments
+    /// * `challenge`:         The signed challenge forming step 0.
+    /// * `step_difficulties`: Difficulties of the further steps, each
+    ///                        harder than the one before.
+    /// * `private_key`:       The key that signed `challenge`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The signed ladder, or an error if the
+    ///                           steps are empty, too many, or not
+    ///                           increasing in difficulty.
+    #[cfg(feature = "sign")]
+    pub fn new(
+        challenge: IronShieldChallenge,
+        step_difficulties: Vec<u64>,
+        private_key: &SigningKey
+    ) -> Result<Self, String> {
+        let mut ladder = Self {
+            challenge,
+            step_difficulties,
+            ladder_signature: [0u8; 64],
+        };
+        ladder.validate()?;
+        ladder.ladder_signature = private_key.sign(ladder.signing_message().as_bytes()).to_bytes();
+        Ok(ladder)
+    }
+
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if there are 1 to 16 further
+    ///                         steps, each with a lower target than the
+    ///                         step before.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.step_difficulties.is_empty() || self.step_difficulties.len() > MAX_LADDER_STEPS {
+            return Err(format!("A ladder needs 1 to {} further steps", MAX_LADDER_STEPS));
+        }
+
+        let mut previous: [u8; 32] = self.challenge.challenge_param;
+        for difficulty in &self.step_difficulties {
+            let param: [u8; 32] = IronShieldChallenge::difficulty_to_challenge_param(*difficulty);
+            if param >= previous {
+                return Err(format!("Ladder step with difficulty {} is not harder than the step before", difficulty));
+            }
+            previous = param;
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `usize`: The number of steps, including the base challenge.
+    pub fn len(&self) -> usize {
+        self.step_difficulties.len() + 1
+    }
+
+    /// # Returns
+    /// * `bool`: Always `false`; a ladder has at least its base step.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Creates the message covered by `ladder_signature`.
+    ///
+    /// # Returns
+    /// * `String`: The challenge id and step difficulties.
+    pub fn signing_message(&self) -> String {
+        let difficulties: Vec<String> = self.step_difficulties.iter().map(u64::to_string).collect();
+        format!(
+            "{}|{}|{}",
+            LADDER_SIGNING_CONTEXT,
+            hex::encode(self.challenge.challenge_id()),
+            difficulties.join(",")
+        )
+    }
+
+    /// Verifies the challenge signature and the ladder signature.
+    ///
+    /// # Arguments
+    /// * `public_key`: The issuer's public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if both signatures are valid.
+    pub fn verify_signature_with_key(&self, public_key: &[u8; 32]) -> Result<(), CryptoError> {
+        crate::verify_challenge_signature_with_key(&self.challenge, public_key)?;
+
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        let signature: Signature = Signature::from_slice(&self.ladder_signature)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid signature format: {}", e), e)))?;
+
+        verifying_key.verify(self.signing_message().as_bytes(), &signature)
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Ladder signature verification failed: {}", e), e)))
+    }
+
+    /// Nonce of the step after the one with `random_nonce`, once it is
+    /// solved by `solution`.
+    fn next_nonce(random_nonce: &RandomNonce, solution: i64) -> RandomNonce {
+        RandomNonce::from_bytes(&Sha256::new()
+            .chain_update(STEP_NONCE_CONTEXT)
+            .chain_update(random_nonce.as_str())
+            .chain_update(nonce_to_le_bytes(solution))
+            .finalize()
+            .into())
+    }
+
+    /// Describes the step after the given solutions.
+    ///
+    /// # Arguments
+    /// * `previous_solutions`: Solutions of the steps before, in order.
+    ///
+    /// # Returns
+    /// * `Result<LadderStep, String>`: The step at index
+    ///                                 `previous_solutions.len()`, or an
+    ///                                 error past the last step.
+    pub fn step(&self, previous_solutions: &[i64]) -> Result<LadderStep, String> {
+        let index: usize = previous_solutions.len();
+        if index >= self.len() {
+            return Err(format!("The ladder has only {} steps", self.len()));
+        }
+
+        let random_nonce: RandomNonce = previous_solutions.iter().fold(
+            self.challenge.random_nonce.clone(),
+            |nonce, solution| Self::next_nonce(&nonce, *solution)
+        );
+        let challenge_param: [u8; 32] = match index {
+            0 => self.challenge.challenge_param,
+            _ => IronShieldChallenge::difficulty_to_challenge_param(self.step_difficulties[index - 1]),
+        };
+        Ok(LadderStep { random_nonce, challenge_param })
+    }
+
+    /// Checks the solutions of the first steps. Signatures and expiry
+    /// are validated separately.
+    ///
+    /// # Arguments
+    /// * `solutions`: Solutions of steps 0, 1, ... in order.
+    ///
+    /// # Returns
+    /// * `Result<usize, String>`: The number of steps climbed, i.e. the
+    ///                            trust level to grant, or an error if
+    ///                            any solution is invalid or there are
+    ///                            more solutions than steps.
+    pub fn verify_solutions(&self, solutions: &[i64]) -> Result<usize, String> {
+        if solutions.len() > self.len() {
+            return Err(format!("The ladder has only {} steps, got {} solutions", self.len(), solutions.len()));
+        }
+
+        for (index, solution) in solutions.iter().enumerate() {
+            let step: LadderStep = self.step(&solutions[..index])?;
+            SolutionVerifier::from_parts(step.random_nonce.as_str(), &step.challenge_param)?
+                .verify(*solution)
+                .map_err(|e| format!("Ladder step {}: {}", index, e))?;
+        }
+        Ok(solutions.len())
+    }
+
+    /// Concatenates the ladder data into a string.
+    ///
+    /// Concatenates:
+    /// * `challenge`:         As its concatenated string representation.
+    /// * `step_difficulties`: As comma-separated integers.
+    /// * `ladder_signature`:  As a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        let difficulties: Vec<String> = self.step_difficulties.iter().map(u64::to_string).collect();
+        format!(
+            "{}|{}|{}",
+            self.challenge.concat_struct(),
+            difficulties.join(","),
+            hex::encode(self.ladder_signature)
+        )
+    }
+
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed ladder or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let mut parts = concat_str.rsplitn(3, '|');
+        let signature_part: &str = parts.next().unwrap_or_default();
+        let difficulties_part: &str = parts.next().ok_or("Expected ladder difficulties")?;
+        let challenge_part: &str = parts.next().ok_or("Expected a ladder challenge")?;
+
+        let mut ladder_signature = [0u8; 64];
+        hex::decode_to_slice(signature_part, &mut ladder_signature)
+            .map_err(|_| "Ladder signature must be 64 bytes of hex")?;
+        let step_difficulties: Vec<u64> = difficulties_part.split(',')
+            .map(|difficulty| difficulty.parse::<u64>().map_err(|_| format!("Failed to parse difficulty {:?} as u64", difficulty)))
+            .collect::<Result<_, _>>()?;
+
+        let ladder = Self {
+            challenge: IronShieldChallenge::from_concat_struct(challenge_part)?,
+            step_difficulties,
+            ladder_signature,
+        };
+        ladder.validate()?;
+        Ok(ladder)
+    }
+
+    /// # Returns
+    /// * `String`: The ladder, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded ladder or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+
+    fn solve(step: &LadderStep) -> i64 {
+        let verifier = SolutionVerifier::from_parts(step.random_nonce.as_str(), &step.challenge_param).unwrap();
+        (0..).find(|nonce| verifier.check(*nonce)).unwrap()
+    }
+
+    #[test]
+    fn test_climb_ladder() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 2, key.clone(), public_key);
+        let ladder = ChallengeLadder::new(challenge.clone(), vec![8, 32], &key).unwrap();
+        ladder.verify_signature_with_key(&public_key).unwrap();
+        assert_eq!(ladder.len(), 3);
+
+        let mut solutions: Vec<i64> = Vec::new();
+        for _ in 0..ladder.len() {
+            solutions.push(solve(&ladder.step(&solutions).unwrap()));
+        }
+        assert_eq!(ladder.verify_solutions(&solutions[..1]), Ok(1));
+        assert_eq!(ladder.verify_solutions(&solutions), Ok(3));
+        assert!(ladder.step(&solutions).is_err());
+        assert_ne!(ladder.step(&solutions[..1]).unwrap().random_nonce, challenge.random_nonce);
+
+        // A different first solution leads to a different second step.
+        let other_first: i64 = (solutions[0] + 1..).find(|nonce| challenge.check_solution(*nonce).is_ok()).unwrap();
+        let other_step: LadderStep = ladder.step(&[other_first]).unwrap();
+        assert_ne!(other_step, ladder.step(&solutions[..1]).unwrap());
+
+        let decoded = ChallengeLadder::from_base64url_header(&ladder.to_base64url_header()).unwrap();
+        assert_eq!(decoded, ladder);
+        let mut lowered = decoded;
+        lowered.step_difficulties[1] = 16;
+        assert!(lowered.verify_signature_with_key(&public_key).is_err());
+
+        assert!(ChallengeLadder::new(challenge.clone(), vec![32, 8], &key).is_err());
+        assert!(ChallengeLadder::new(challenge, Vec::new(), &key).is_err());
+    }
+}
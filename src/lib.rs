@@ -4,27 +4,258 @@
 //! used across ironshield-core, ironshield-cloudflare, and ironshield-wasm.
 
 mod serde_utils;
+mod trace;
+mod metrics;
 mod challenge;
+mod challenge_ref;
 mod response;
 mod token;
 mod crypto;
 mod request;
+mod epoch;
+mod algorithm;
+mod challenge_request;
+mod lifetime;
+mod request_descriptor;
+mod compact;
+mod websocket;
+mod tenant;
+mod difficulty;
+mod website_id;
+mod random_nonce;
+mod bloom;
+mod ttl_cache;
+mod verification_cache;
+mod revocation;
+mod payment;
+mod attestation;
+mod bypass;
+mod solution_verifier;
+mod solution;
+mod embedded_key;
+mod canonical_cbor;
+mod interop;
+mod detached;
+mod armor;
+mod base45;
+mod base58;
+mod expiry;
+mod challenge_store;
+mod kv_store;
+mod signed_config;
+mod config_distribution;
+mod validated;
+mod audit_chain;
+mod merkle;
+mod transparency;
+mod nonce_reservation;
+mod work_assignment;
+mod solver_progress;
+mod solution_batch;
+mod auto_scaler;
+mod issuance_quota;
+mod issuance_context;
+mod capabilities;
+mod mobile_solver_policy;
+mod negotiation;
+mod protocol_version;
+mod feature_flags;
+mod capability_bits;
+mod response_headers;
+mod challenge_response_body;
+mod interstitial;
+mod js_solver_config;
+mod retry;
+mod error_response;
+mod rejection_receipt;
+mod key_derivation;
+mod vrf_nonce;
+mod time_attestation;
+mod time_proof;
+mod solution_proof;
+mod spot_check;
+mod multi_solution;
+mod ladder;
+mod timelock;
+mod vdf;
+
+pub mod bytes;
 
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub mod js_bindings;
 
+#[cfg(feature = "uniffi")]
+mod uniffi_bindings;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "python")]
+mod python_bindings;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+
+#[cfg(feature = "redis")]
+mod redis_store;
+
+#[cfg(feature = "worker")]
+mod worker_store;
+
+#[cfg(feature = "sign")]
+mod nonce_source;
+
+#[cfg(feature = "sign")]
+mod conformance;
+
+#[cfg(feature = "shamir")]
+mod shamir;
+
+#[cfg(feature = "key-encryption")]
+mod key_encryption;
+
+#[cfg(feature = "sealed-challenges")]
+mod sealed_challenge;
+
+#[cfg(feature = "encrypted-claims")]
+mod token_claims;
+
+#[cfg(feature = "blind-tokens")]
+mod blind_token;
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+
 pub use serde_utils::*;
+pub use metrics::*;
 pub use challenge::*;
+pub use challenge_ref::*;
 pub use response::*;
 pub use request::*;
 pub use token::*;
 pub use crypto::*;
+pub use epoch::*;
+pub use algorithm::*;
+pub use challenge_request::*;
+pub use lifetime::*;
+pub use request_descriptor::*;
+pub use compact::*;
+pub use websocket::*;
+pub use tenant::*;
+pub use difficulty::*;
+pub use website_id::*;
+pub use random_nonce::*;
+pub use bloom::*;
+pub use ttl_cache::*;
+pub use verification_cache::*;
+pub use revocation::*;
+pub use payment::*;
+pub use attestation::*;
+pub use bypass::*;
+pub use solution_verifier::*;
+pub use solution::*;
+pub use embedded_key::*;
+pub use detached::*;
+pub use interop::*;
+pub use base45::*;
+pub use base58::*;
+pub use expiry::*;
+pub use challenge_store::*;
+pub use kv_store::*;
+pub use signed_config::*;
+pub use config_distribution::*;
+pub use validated::*;
+pub use audit_chain::*;
+pub use merkle::*;
+pub use transparency::*;
+pub use nonce_reservation::*;
+pub use work_assignment::*;
+pub use solver_progress::*;
+pub use solution_batch::*;
+pub use auto_scaler::*;
+pub use issuance_quota::*;
+pub use issuance_context::*;
+pub use capabilities::*;
+pub use mobile_solver_policy::*;
+pub use negotiation::*;
+pub use protocol_version::*;
+pub use feature_flags::*;
+pub use capability_bits::*;
+pub use response_headers::*;
+pub use challenge_response_body::*;
+pub use interstitial::*;
+pub use js_solver_config::*;
+pub use retry::*;
+pub use error_response::*;
+pub use rejection_receipt::*;
+pub use key_derivation::*;
+pub use vrf_nonce::*;
+pub use time_attestation::*;
+pub use time_proof::*;
+pub use solution_proof::*;
+pub use spot_check::*;
+pub use multi_solution::*;
+pub use ladder::*;
+pub use timelock::*;
+pub use vdf::*;
 
 #[cfg(any(feature = "wasm", rust_analyzer))]
 pub use js_bindings::*;
 
+#[cfg(feature = "uniffi")]
+pub use uniffi_bindings::*;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+#[cfg(feature = "python")]
+pub use python_bindings::*;
+
+#[cfg(feature = "parallel")]
+pub use parallel::*;
+
+#[cfg(feature = "redis")]
+pub use redis_store::*;
+
+#[cfg(feature = "worker")]
+pub use worker_store::*;
+
+#[cfg(feature = "sign")]
+pub use nonce_source::*;
+
+#[cfg(feature = "sign")]
+pub use conformance::*;
+
+#[cfg(feature = "shamir")]
+pub use shamir::*;
+
+#[cfg(feature = "key-encryption")]
+pub use key_encryption::*;
+
+#[cfg(feature = "sealed-challenges")]
+pub use sealed_challenge::*;
+
+#[cfg(feature = "encrypted-claims")]
+pub use token_claims::*;
+
+#[cfg(feature = "blind-tokens")]
+pub use blind_token::*;
+
+#[cfg(feature = "prometheus")]
+pub use prometheus::*;
+
 // Re-export of the library chrono for convenience.
+#[cfg(feature = "chrono")]
 pub use chrono;
 
+// Re-export of the library time for convenience.
+#[cfg(feature = "time")]
+pub use time;
+
+// Re-export of the library publicsuffix for convenience.
+#[cfg(feature = "public-suffix")]
+pub use publicsuffix;
+
 // Re-export of the ed25519_dalek library for convenience.
+#[cfg(feature = "sign")]
 pub use ed25519_dalek::SigningKey;
\ No newline at end of file
@@ -0,0 +1,220 @@
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::IronShieldChallenge;
+
+/// Default challenge validity window (30 seconds).
+pub const DEFAULT_CHALLENGE_VALIDITY_MS: i64 = 30_000;
+/// Default grace period for solution submission after expiry.
+pub const DEFAULT_SUBMISSION_GRACE_MS:   i64 = 0;
+/// Default token lifetime (1 hour).
+pub const DEFAULT_TOKEN_TTL_MS:          i64 = 3_600_000;
+
+/// Lifetime policy for challenges and the tokens issued for them.
+///
+/// Collects every timing rule of the protocol in one serializable
+/// object so that issuance, solution validation and token issuance
+/// all agree on the same windows.
+///
+/// * `challenge_validity_ms`: How long a challenge is valid after
+///                            `created_time`.
+/// * `submission_grace_ms`:   Extra time after `expiration_time`
+///                            during which a solution is still
+///                            accepted.
+/// * `token_ttl_ms`:          How long an issued token is valid.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Timing rules for challenge issuance, solution submission and token validity"
+))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimePolicy {
+    /// Milliseconds a challenge stays valid after creation
+    #[cfg_attr(feature = "openapi", schema(example = 30000i64))]
+    pub challenge_validity_ms: i64,
+    /// Milliseconds after expiry during which solutions are still accepted
+    #[cfg_attr(feature = "openapi", schema(example = 5000i64))]
+    pub submission_grace_ms:   i64,
+    /// Milliseconds an issued token stays valid
+    #[cfg_attr(feature = "openapi", schema(example = 3600000i64))]
+    pub token_ttl_ms:          i64,
+}
+
+impl Default for LifetimePolicy {
+    fn default() -> Self {
+        Self {
+            challenge_validity_ms: DEFAULT_CHALLENGE_VALIDITY_MS,
+            submission_grace_ms:   DEFAULT_SUBMISSION_GRACE_MS,
+            token_ttl_ms:          DEFAULT_TOKEN_TTL_MS,
+        }
+    }
+}
+
+impl LifetimePolicy {
+    /// Constructor for creating a new `LifetimePolicy` instance.
+    ///
+    /// # Arguments
+    /// * `challenge_validity_ms`: Challenge validity window in millis.
+    /// * `submission_grace_ms`:   Grace period after expiry in millis.
+    /// * `token_ttl_ms`:          Token lifetime in millis.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The policy, or an error if any
+    ///                           window is out of range.
+    pub fn new(
+        challenge_validity_ms: i64,
+        submission_grace_ms:   i64,
+        token_ttl_ms:          i64,
+    ) -> Result<Self, String> {
+        let policy = Self {
+            challenge_validity_ms,
+            submission_grace_ms,
+            token_ttl_ms,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Checks that every window of the policy is usable.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid window.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.challenge_validity_ms <= 0 {
+            return Err("challenge_validity_ms must be positive".to_string());
+        }
+        if self.submission_grace_ms < 0 {
+            return Err("submission_grace_ms cannot be negative".to_string());
+        }
+        if self.token_ttl_ms <= 0 {
+            return Err("token_ttl_ms must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Arguments
+    /// * `created_time`: Challenge creation time in unix millis.
+    ///
+    /// # Returns
+    /// * `i64`: The expiration time for a challenge created at
+    ///          `created_time`.
+    pub fn challenge_expiration(&self, created_time: i64) -> i64 {
+        created_time.saturating_add(self.challenge_validity_ms)
+    }
+
+    /// A signed `submission_deadline` on the challenge takes
+    /// precedence over the policy's grace period, since the issuer
+    /// already committed to it.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge to compute the deadline for.
+    ///
+    /// # Returns
+    /// * `i64`: The last instant (unix millis) at which a solution
+    ///          for `challenge` is accepted.
+    pub fn submission_deadline(&self, challenge: &IronShieldChallenge) -> i64 {
+        challenge.submission_deadline
+            .unwrap_or_else(|| challenge.expiration_time.saturating_add(self.submission_grace_ms))
+    }
+
+    /// # Arguments
+    /// * `challenge`: The challenge a solution was submitted for.
+    /// * `now`:       The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if a solution submitted at `now` is still
+    ///           within the validity window plus grace period.
+    pub fn accepts_submission_at(&self, challenge: &IronShieldChallenge, now: i64) -> bool {
+        now <= self.submission_deadline(challenge)
+    }
+
+    /// # Arguments
+    /// * `issued_at`: Token issuance time in unix millis.
+    ///
+    /// # Returns
+    /// * `i64`: The `valid_for` timestamp for a token issued at
+    ///          `issued_at`.
+    pub fn token_expiration(&self, issued_at: i64) -> i64 {
+        issued_at.saturating_add(self.token_ttl_ms)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_default_policy_matches_protocol_defaults() {
+        let policy = LifetimePolicy::default();
+        assert_eq!(policy.challenge_validity_ms, 30_000);
+        assert_eq!(policy.submission_grace_ms, 0);
+        assert_eq!(policy.token_ttl_ms, 3_600_000);
+        assert!(policy.validate().is_ok());
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_windows() {
+        assert!(LifetimePolicy::new(0, 0, 1).unwrap_err().contains("challenge_validity_ms"));
+        assert!(LifetimePolicy::new(1, -1, 1).unwrap_err().contains("submission_grace_ms"));
+        assert!(LifetimePolicy::new(1, 0, 0).unwrap_err().contains("token_ttl_ms"));
+        assert!(LifetimePolicy::new(1, 0, 1).is_ok());
+    }
+
+    #[test]
+    fn test_submission_grace_period() {
+        let policy = LifetimePolicy::new(30_000, 5_000, 60_000).unwrap();
+        let signing_key = SigningKey::from_bytes(&[0u8; 32]);
+        let challenge = IronShieldChallenge::new(
+            "example.com".parse().unwrap(),
+            1_000,
+            signing_key.clone(),
+            signing_key.verifying_key().to_bytes(),
+        );
+
+        let expiration: i64 = challenge.expiration_time;
+        assert_eq!(challenge.submission_deadline, None);
+        assert_eq!(policy.submission_deadline(&challenge), expiration + 5_000);
+        assert!(policy.accepts_submission_at(&challenge, expiration));
+        assert!(policy.accepts_submission_at(&challenge, expiration + 5_000));
+        assert!(!policy.accepts_submission_at(&challenge, expiration + 5_001));
+    }
+
+    #[test]
+    fn test_signed_deadline_takes_precedence() {
+        let issuing_policy = LifetimePolicy::new(30_000, 20_000, 60_000).unwrap();
+        let signing_key = SigningKey::from_bytes(&[0u8; 32]);
+        let challenge = IronShieldChallenge::new_with_policy(
+            "example.com".parse().unwrap(),
+            1_000,
+            signing_key.clone(),
+            signing_key.verifying_key().to_bytes(),
+            &issuing_policy,
+        );
+
+        let validating_policy = LifetimePolicy::default();
+        assert_eq!(
+            validating_policy.submission_deadline(&challenge),
+            challenge.expiration_time + 20_000
+        );
+    }
+
+    #[test]
+    fn test_expiration_helpers_saturate() {
+        let policy = LifetimePolicy::default();
+        assert_eq!(policy.challenge_expiration(1_000), 31_000);
+        assert_eq!(policy.token_expiration(1_000), 3_601_000);
+        assert_eq!(policy.challenge_expiration(i64::MAX), i64::MAX);
+    }
+
+    #[test]
+    fn test_serde_fills_missing_fields_with_defaults() {
+        let policy: LifetimePolicy = serde_json::from_str(r#"{"submission_grace_ms":2000}"#).unwrap();
+        assert_eq!(policy.challenge_validity_ms, DEFAULT_CHALLENGE_VALIDITY_MS);
+        assert_eq!(policy.submission_grace_ms, 2_000);
+        assert_eq!(policy.token_ttl_ms, DEFAULT_TOKEN_TTL_MS);
+    }
+}
@@ -0,0 +1,369 @@
+//! # Merkle-tree batch attestation of issued challenges.
+//!
+//! A busy edge issues far too many challenges to sign a log record for
+//! each. Instead it collects the `challenge_id()`s of a batch into a
+//! `MerkleBatch` and signs only the root. An auditor holding the
+//! `SignedBatchRoot` can then check that one specific challenge was
+//! issued from a short `InclusionProof`, without the rest of the batch.
+//!
+//! Hashing follows RFC 6962 (Certificate Transparency): leaves are
+//! hashed as `SHA-256(0x00 || data)` and interior nodes as
+//! `SHA-256(0x01 || left || right)`, so a leaf can never be passed off
+//! as a node.
+
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
+    SigningKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_signature,
+    serialize_32_bytes,
+    serialize_signature
+};
+use crate::{
+    CryptoError,
+    ErrorDetail
+};
+
+/// Domain separation prefix of batch root signatures.
+const BATCH_ROOT_CONTEXT: &[u8] = b"ironshield-merkle-batch-v1";
+
+/// RFC 6962 leaf hash.
+pub(crate) fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// RFC 6962 interior node hash.
+pub(crate) fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Largest power of two strictly below `n` (`n >= 2`).
+pub(crate) fn split_point(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// RFC 6962 Merkle tree hash over already hashed leaves.
+pub(crate) fn tree_hash(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaves[0],
+        n => {
+            let (left, right) = leaves.split_at(split_point(n));
+            node_hash(&tree_hash(left), &tree_hash(right))
+        }
+    }
+}
+
+/// RFC 6962 audit path of leaf `index` (`index < leaves.len()`).
+pub(crate) fn audit_path(index: usize, leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+    let (left, right) = leaves.split_at(split_point(leaves.len()));
+    if index < left.len() {
+        let mut path: Vec<[u8; 32]> = audit_path(index, left);
+        path.push(tree_hash(right));
+        path
+    } else {
+        let mut path: Vec<[u8; 32]> = audit_path(index - left.len(), right);
+        path.push(tree_hash(left));
+        path
+    }
+}
+
+/// Recomputes the root from a leaf hash and its audit path
+/// (RFC 9162, section 2.1.3.2).
+///
+/// # Returns
+/// * `Option<[u8; 32]>`: The root, or `None` if the path does not fit
+///                       a tree of `tree_size` leaves.
+pub(crate) fn root_from_audit_path(
+    leaf_hash: [u8; 32],
+    index: u64,
+    tree_size: u64,
+    path: &[[u8; 32]]
+) -> Option<[u8; 32]> {
+    if index >= tree_size {
+        return None;
+    }
+    let mut fn_: u64 = index;
+    let mut sn: u64 = tree_size - 1;
+    let mut root: [u8; 32] = leaf_hash;
+    for sibling in path {
+        if sn == 0 {
+            return None;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            root = node_hash(sibling, &root);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            root = node_hash(&root, sibling);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    (sn == 0).then_some(root)
+}
+
+/// Proof that a challenge id is a leaf of a batch.
+///
+/// * `leaf_index`: Position of the challenge id in the batch.
+/// * `tree_size`:  Number of challenge ids in the batch.
+/// * `audit_path`: Sibling hashes from the leaf up to the root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub tree_size:  u64,
+    pub audit_path: Vec<[u8; 32]>,
+}
+
+/// Challenge ids issued together, committed to by one Merkle root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleBatch {
+    challenge_ids: Vec<[u8; 32]>,
+    leaves:        Vec<[u8; 32]>,
+    root:          [u8; 32],
+}
+
+impl MerkleBatch {
+    /// # Arguments
+    /// * `challenge_ids`: The `challenge_id()`s of the batch, in issue
+    ///                    order.
+    pub fn new(challenge_ids: Vec<[u8; 32]>) -> Self {
+        let leaves: Vec<[u8; 32]> = challenge_ids.iter().map(|id| leaf_hash(id)).collect();
+        let root: [u8; 32] = tree_hash(&leaves);
+        Self { challenge_ids, leaves, root }
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The Merkle root of the batch.
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// # Returns
+    /// * `&[[u8; 32]]`: The challenge ids, in batch order.
+    pub fn challenge_ids(&self) -> &[[u8; 32]] {
+        &self.challenge_ids
+    }
+
+    /// # Returns
+    /// * `usize`: The number of challenge ids.
+    pub fn len(&self) -> usize {
+        self.challenge_ids.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if the batch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.challenge_ids.is_empty()
+    }
+
+    /// # Arguments
+    /// * `challenge_id`: The challenge id to prove.
+    ///
+    /// # Returns
+    /// * `Option<InclusionProof>`: The proof, or `None` if the id is not
+    ///                             in the batch.
+    pub fn inclusion_proof(&self, challenge_id: &[u8; 32]) -> Option<InclusionProof> {
+        let index: usize = self.challenge_ids.iter().position(|id| id == challenge_id)?;
+        Some(InclusionProof {
+            leaf_index: index as u64,
+            tree_size:  self.leaves.len() as u64,
+            audit_path: audit_path(index, &self.leaves),
+        })
+    }
+
+    /// Signs the batch root.
+    ///
+    /// # Arguments
+    /// * `batch_time`:  Unix milli timestamp the batch was closed at.
+    /// * `signing_key`: The issuer key.
+    ///
+    /// # Returns
+    /// * `SignedBatchRoot`: The root to publish to auditors.
+    #[cfg(feature = "sign")]
+    pub fn sign(&self, batch_time: i64, signing_key: &SigningKey) -> SignedBatchRoot {
+        let mut signed = SignedBatchRoot {
+            tree_size:  self.leaves.len() as u64,
+            root:       self.root,
+            batch_time,
+            signature:  [0u8; 64],
+        };
+        signed.signature = signing_key.sign(&signed.signing_message()).to_bytes();
+        signed
+    }
+}
+
+/// Issuer-signed commitment to a `MerkleBatch`.
+///
+/// * `tree_size`:  Number of challenge ids in the batch.
+/// * `root`:       The Merkle root.
+/// * `batch_time`: Unix milli timestamp the batch was closed at.
+/// * `signature`:  Ed25519 signature over the fields above.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedBatchRoot {
+    pub tree_size:  u64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub root:       [u8; 32],
+    pub batch_time: i64,
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature:  [u8; 64],
+}
+
+impl SignedBatchRoot {
+    /// # Returns
+    /// * `Vec<u8>`: The bytes covered by `signature`.
+    fn signing_message(&self) -> Vec<u8> {
+        [
+            BATCH_ROOT_CONTEXT,
+            &self.tree_size.to_be_bytes(),
+            &self.root,
+            &self.batch_time.to_be_bytes(),
+        ].concat()
+    }
+
+    /// Verifies the root signature.
+    ///
+    /// # Arguments
+    /// * `public_key_bytes`: The issuer's Ed25519 public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify(&self, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        verifying_key.verify(&self.signing_message(), &Signature::from_bytes(&self.signature))
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Batch root signature verification failed: {}", e), e)))
+    }
+
+    /// Verifies the root signature and that `challenge_id` is in the
+    /// batch.
+    ///
+    /// # Arguments
+    /// * `challenge_id`:     The challenge id in question.
+    /// * `proof`:            Its inclusion proof.
+    /// * `public_key_bytes`: The issuer's Ed25519 public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the challenge was issued in
+    ///                              this batch.
+    pub fn verify_inclusion(
+        &self,
+        challenge_id: &[u8; 32],
+        proof: &InclusionProof,
+        public_key_bytes: &[u8; 32]
+    ) -> Result<(), CryptoError> {
+        self.verify(public_key_bytes)?;
+
+        if proof.tree_size != self.tree_size {
+            return Err(CryptoError::VerificationFailed("Inclusion proof is for a different batch size".into()));
+        }
+        let root: Option<[u8; 32]> = root_from_audit_path(
+            leaf_hash(challenge_id), proof.leaf_index, proof.tree_size, &proof.audit_path
+        );
+        if root != Some(self.root) {
+            return Err(CryptoError::VerificationFailed("Inclusion proof does not match the batch root".into()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    fn ids(count: u8) -> Vec<[u8; 32]> {
+        (0..count).map(|i| [i; 32]).collect()
+    }
+
+    #[test]
+    fn test_tree_hash_matches_rfc6962_shape() {
+        let leaves: Vec<[u8; 32]> = ids(3).iter().map(|id| leaf_hash(id)).collect();
+        let expected: [u8; 32] = node_hash(&node_hash(&leaves[0], &leaves[1]), &leaves[2]);
+        assert_eq!(tree_hash(&leaves), expected);
+        assert_eq!(
+            hex::encode(tree_hash(&[])),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(split_point(2), 1);
+        assert_eq!(split_point(5), 4);
+        assert_eq!(split_point(8), 4);
+    }
+
+    #[test]
+    fn test_every_leaf_proves_for_every_size() {
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+
+        for size in 1..=17u8 {
+            let batch = MerkleBatch::new(ids(size));
+            let signed: SignedBatchRoot = batch.sign(1_000, &key);
+            for id in batch.challenge_ids() {
+                let proof: InclusionProof = batch.inclusion_proof(id).unwrap();
+                signed.verify_inclusion(id, &proof, &public_key).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_forged_inclusion() {
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let batch = MerkleBatch::new(ids(6));
+        let signed: SignedBatchRoot = batch.sign(1_000, &key);
+        let proof: InclusionProof = batch.inclusion_proof(&[2; 32]).unwrap();
+
+        assert!(batch.inclusion_proof(&[99; 32]).is_none());
+        assert!(signed.verify_inclusion(&[3; 32], &proof, &public_key).is_err());
+
+        let mut moved = proof.clone();
+        moved.leaf_index = 7;
+        assert!(signed.verify_inclusion(&[2; 32], &moved, &public_key).is_err());
+
+        let mut shortened = proof.clone();
+        shortened.audit_path.pop();
+        assert!(signed.verify_inclusion(&[2; 32], &shortened, &public_key).is_err());
+
+        let mut resized = signed.clone();
+        resized.tree_size = 7;
+        assert!(matches!(resized.verify(&public_key), Err(CryptoError::VerificationFailed(_))));
+
+        let json: String = serde_json::to_string(&signed).unwrap();
+        let restored: SignedBatchRoot = serde_json::from_str(&json).unwrap();
+        restored.verify_inclusion(&[2; 32], &proof, &public_key).unwrap();
+    }
+}
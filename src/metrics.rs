@@ -0,0 +1,214 @@
+//! # Metrics hook for signing and verification.
+//!
+//! This crate does not pick a metrics backend. Deployments register a
+//! `MetricsSink` once at startup with `set_metrics_sink`, and challenge
+//! and token signing, signature verification and challenge validation
+//! report to it through the counter and histogram names below. Until a
+//! sink is registered every report is a no-op.
+//!
+//! ```
+//! use ironshield_types::{set_metrics_sink, MetricsSink};
+//!
+//! struct StatsD;
+//!
+//! impl MetricsSink for StatsD {
+//!     fn incr(&self, counter: &'static str) {
+//!         // statsd.incr(counter)
+//!     }
+//! }
+//!
+//! set_metrics_sink(StatsD).unwrap();
+//! ```
+
+use std::sync::{
+    Arc,
+    Mutex,
+    OnceLock
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Signatures created with `generate_signature`.
+pub const SIGNATURES_CREATED:              &str = "ironshield_signatures_created_total";
+/// Challenge signatures that verified.
+pub const CHALLENGE_SIGNATURES_VERIFIED:   &str = "ironshield_challenge_signatures_verified_total";
+/// Challenge signatures that did not verify.
+pub const CHALLENGE_SIGNATURES_REJECTED:   &str = "ironshield_challenge_signatures_rejected_total";
+/// Token signatures that verified.
+pub const TOKEN_SIGNATURES_VERIFIED:       &str = "ironshield_token_signatures_verified_total";
+/// Token signatures that did not verify.
+pub const TOKEN_SIGNATURES_REJECTED:       &str = "ironshield_token_signatures_rejected_total";
+/// Challenges that passed `validate_challenge_with_policy`.
+pub const CHALLENGES_VALIDATED:            &str = "ironshield_challenges_validated_total";
+/// Challenges that failed `validate_challenge_with_policy`.
+pub const CHALLENGES_REJECTED:             &str = "ironshield_challenges_rejected_total";
+/// Histogram of the age, in milliseconds, of challenges when they are
+/// validated; roughly the time clients took to solve them.
+pub const VALIDATED_CHALLENGE_AGE_MS:      &str = "ironshield_validated_challenge_age_ms";
+
+/// Receiver of the crate's metrics. Both methods default to doing
+/// nothing, so a sink only implements what its backend supports.
+pub trait MetricsSink {
+    /// # Arguments
+    /// * `counter`: Name of the counter to increment by one.
+    fn incr(&self, counter: &'static str) {
+        let _ = counter;
+    }
+
+    /// # Arguments
+    /// * `histogram`: Name of the histogram.
+    /// * `value`:     The observed value.
+    fn observe(&self, histogram: &'static str, value: f64) {
+        let _ = (histogram, value);
+    }
+}
+
+impl<S: MetricsSink + ?Sized> MetricsSink for Arc<S> {
+    fn incr(&self, counter: &'static str) {
+        (**self).incr(counter);
+    }
+
+    fn observe(&self, histogram: &'static str, value: f64) {
+        (**self).observe(histogram, value);
+    }
+}
+
+/// Totals of the crate's counters, for servers without a metrics
+/// backend. Register an `Arc<Mutex<ChallengeStats>>` as the sink and
+/// read the totals through the other handle.
+///
+/// * `signatures_created`:            `SIGNATURES_CREATED`
+/// * `challenge_signatures_verified`: `CHALLENGE_SIGNATURES_VERIFIED`
+/// * `challenge_signatures_rejected`: `CHALLENGE_SIGNATURES_REJECTED`
+/// * `token_signatures_verified`:     `TOKEN_SIGNATURES_VERIFIED`
+/// * `token_signatures_rejected`:     `TOKEN_SIGNATURES_REJECTED`
+/// * `challenges_validated`:          `CHALLENGES_VALIDATED`
+/// * `challenges_rejected`:           `CHALLENGES_REJECTED`
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeStats {
+    pub signatures_created:            u64,
+    pub challenge_signatures_verified: u64,
+    pub challenge_signatures_rejected: u64,
+    pub token_signatures_verified:     u64,
+    pub token_signatures_rejected:     u64,
+    pub challenges_validated:          u64,
+    pub challenges_rejected:           u64,
+}
+
+impl ChallengeStats {
+    /// # Returns
+    /// * `[(&'static str, u64); 7]`: Each counter with its metric name.
+    pub fn counters(&self) -> [(&'static str, u64); 7] {
+        [
+            (SIGNATURES_CREATED,            self.signatures_created),
+            (CHALLENGE_SIGNATURES_VERIFIED, self.challenge_signatures_verified),
+            (CHALLENGE_SIGNATURES_REJECTED, self.challenge_signatures_rejected),
+            (TOKEN_SIGNATURES_VERIFIED,     self.token_signatures_verified),
+            (TOKEN_SIGNATURES_REJECTED,     self.token_signatures_rejected),
+            (CHALLENGES_VALIDATED,          self.challenges_validated),
+            (CHALLENGES_REJECTED,           self.challenges_rejected),
+        ]
+    }
+}
+
+impl MetricsSink for Mutex<ChallengeStats> {
+    fn incr(&self, counter: &'static str) {
+        let Ok(mut stats) = self.lock() else {
+            return;
+        };
+        let field: &mut u64 = match counter {
+            SIGNATURES_CREATED            => &mut stats.signatures_created,
+            CHALLENGE_SIGNATURES_VERIFIED => &mut stats.challenge_signatures_verified,
+            CHALLENGE_SIGNATURES_REJECTED => &mut stats.challenge_signatures_rejected,
+            TOKEN_SIGNATURES_VERIFIED     => &mut stats.token_signatures_verified,
+            TOKEN_SIGNATURES_REJECTED     => &mut stats.token_signatures_rejected,
+            CHALLENGES_VALIDATED          => &mut stats.challenges_validated,
+            CHALLENGES_REJECTED           => &mut stats.challenges_rejected,
+            _ => return,
+        };
+        *field += 1;
+    }
+}
+
+/// `MetricsSink` that discards everything, used until a sink is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {}
+
+static METRICS_SINK: OnceLock<Box<dyn MetricsSink + Send + Sync>> = OnceLock::new();
+
+/// Registers the process-wide metrics sink.
+///
+/// # Arguments
+/// * `sink`: The sink to report to.
+///
+/// # Returns
+/// * `Result<(), String>`: `Ok(())`, or an error if a sink was
+///                         already registered.
+pub fn set_metrics_sink(sink: impl MetricsSink + Send + Sync + 'static) -> Result<(), String> {
+    METRICS_SINK.set(Box::new(sink))
+        .map_err(|_| "A metrics sink is already registered".to_string())
+}
+
+/// # Returns
+/// * `&'static dyn MetricsSink`: The registered sink, or
+///                               `NoopMetricsSink`.
+pub(crate) fn metrics() -> &'static dyn MetricsSink {
+    match METRICS_SINK.get() {
+        Some(sink) => sink.as_ref(),
+        None => &NoopMetricsSink,
+    }
+}
+
+/// Counts the outcome of a check.
+///
+/// # Arguments
+/// * `result`:   The check's result.
+/// * `passed`:   Counter incremented on `Ok`.
+/// * `rejected`: Counter incremented on `Err`.
+///
+/// # Returns
+/// * `Result<T, E>`: `result`, unchanged.
+pub(crate) fn count_outcome<T, E>(
+    result:   Result<T, E>,
+    passed:   &'static str,
+    rejected: &'static str,
+) -> Result<T, E> {
+    metrics().incr(if result.is_ok() { passed } else { rejected });
+    result
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        verify_challenge_signature_with_key,
+        IronShieldChallenge,
+        SigningKey
+    };
+
+    #[test]
+    fn test_sink_receives_counters() {
+        let stats = Arc::new(Mutex::new(ChallengeStats::default()));
+        set_metrics_sink(stats.clone()).unwrap();
+        assert!(set_metrics_sink(NoopMetricsSink).is_err());
+
+        let before: ChallengeStats = *stats.lock().unwrap();
+
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+        verify_challenge_signature_with_key(&challenge, &key.verifying_key().to_bytes()).unwrap();
+        assert!(verify_challenge_signature_with_key(&challenge, &SigningKey::from_bytes(&[7; 32]).verifying_key().to_bytes()).is_err());
+
+        // Other tests report concurrently, so counts only grow by at least ours.
+        let after: ChallengeStats = *stats.lock().unwrap();
+        assert!(after.signatures_created > before.signatures_created);
+        assert!(after.challenge_signatures_verified > before.challenge_signatures_verified);
+        assert!(after.challenge_signatures_rejected > before.challenge_signatures_rejected);
+    }
+}
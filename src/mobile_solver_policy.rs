@@ -0,0 +1,196 @@
+//! # Solver policy shared by the iOS and Android SDKs.
+//!
+//! Phones should not drain their battery or overheat solving a
+//! challenge. The SDKs read a `MobileSolverPolicy` from the issuer and
+//! compare it with the device's `DeviceConditions` before and while
+//! solving. A constrained device only tells the issuer that it is
+//! constrained, through `ClientCapabilities::constrained`, not its
+//! battery level or thermal state, and the issuer lowers the difficulty
+//! in `ClientCapabilities::negotiate`.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::ClientCapabilities;
+
+/// Thermal pressure reported by the operating system, from coolest to
+/// hottest. Mirrors iOS `ProcessInfo.ThermalState`; Android's
+/// `PowerManager` thermal statuses map onto it.
+///
+/// * `Nominal`:  No throttling.
+/// * `Fair`:     Slightly elevated; no user-visible effect.
+/// * `Serious`:  The system throttles; heavy work should be reduced.
+/// * `Critical`: Heavy work should stop.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThermalState {
+    #[default]
+    Nominal,
+    Fair,
+    Serious,
+    Critical,
+}
+
+/// What the solver does when the app moves to the background.
+///
+/// * `Pause`:    Stop and resume in the foreground if the challenge is
+///               still valid.
+/// * `Continue`: Keep solving within the time the OS grants.
+/// * `Abort`:    Give up; a fresh challenge is requested later.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundBehavior {
+    #[default]
+    Pause,
+    Continue,
+    Abort,
+}
+
+/// State of the device as read by the SDK. Never sent to the issuer.
+///
+/// * `battery_percent`: Charge from 0 to 100, if known.
+/// * `charging`:        The device is plugged in.
+/// * `low_power_mode`:  Low Power Mode or Battery Saver is on.
+/// * `thermal_state`:   The current thermal pressure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DeviceConditions {
+    pub battery_percent: Option<u8>,
+    pub charging:        bool,
+    pub low_power_mode:  bool,
+    pub thermal_state:   ThermalState,
+}
+
+/// Limits the mobile solver works within.
+///
+/// * `max_solve_time_ms`:      Solving stops after this long.
+/// * `low_battery_percent`:    Below this charge, and not charging, the
+///                             device counts as constrained.
+/// * `throttle_thermal_state`: From this state on, the device counts as
+///                             constrained.
+/// * `stop_thermal_state`:     From this state on, the solver does not
+///                             run at all.
+/// * `background`:             What to do in the background.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MobileSolverPolicy {
+    pub max_solve_time_ms:      u64,
+    pub low_battery_percent:    u8,
+    pub throttle_thermal_state: ThermalState,
+    pub stop_thermal_state:     ThermalState,
+    #[serde(default)]
+    pub background:             BackgroundBehavior,
+}
+
+impl Default for MobileSolverPolicy {
+    fn default() -> Self {
+        Self {
+            max_solve_time_ms:      10_000,
+            low_battery_percent:    20,
+            throttle_thermal_state: ThermalState::Serious,
+            stop_thermal_state:     ThermalState::Critical,
+            background:             BackgroundBehavior::Pause,
+        }
+    }
+}
+
+impl MobileSolverPolicy {
+    /// Checks that the limits are usable.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid setting.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_solve_time_ms == 0 {
+            return Err("max_solve_time_ms must be positive".to_string());
+        }
+        if self.low_battery_percent > 100 {
+            return Err("low_battery_percent must be at most 100".to_string());
+        }
+        if self.stop_thermal_state < self.throttle_thermal_state {
+            return Err("stop_thermal_state cannot be below throttle_thermal_state".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Arguments
+    /// * `conditions`: The device's current state.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the device should ask for an easier
+    ///           challenge: power saving is on, the battery is low and
+    ///           not charging, or the device is throttled.
+    pub fn is_constrained(&self, conditions: &DeviceConditions) -> bool {
+        let low_battery: bool = !conditions.charging
+            && conditions.battery_percent.is_some_and(|percent| percent < self.low_battery_percent);
+
+        conditions.low_power_mode || low_battery || conditions.thermal_state >= self.throttle_thermal_state
+    }
+
+    /// # Arguments
+    /// * `conditions`: The device's current state.
+    /// * `elapsed_ms`: Time spent solving so far.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the solver may keep running.
+    pub fn may_solve(&self, conditions: &DeviceConditions, elapsed_ms: u64) -> bool {
+        conditions.thermal_state < self.stop_thermal_state && elapsed_ms < self.max_solve_time_ms
+    }
+
+    /// Marks the capabilities sent to the issuer as constrained if the
+    /// device is.
+    ///
+    /// # Arguments
+    /// * `capabilities`: The solver's capabilities.
+    /// * `conditions`:   The device's current state.
+    ///
+    /// # Returns
+    /// * `ClientCapabilities`: The capabilities with `constrained` set.
+    pub fn advertise(&self, capabilities: ClientCapabilities, conditions: &DeviceConditions) -> ClientCapabilities {
+        ClientCapabilities {
+            constrained: capabilities.constrained || self.is_constrained(conditions),
+            ..capabilities
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PowAlgorithm;
+
+    #[test]
+    fn test_constrained_conditions() {
+        let policy = MobileSolverPolicy::default();
+        assert!(policy.validate().is_ok());
+
+        let healthy = DeviceConditions { battery_percent: Some(80), ..Default::default() };
+        assert!(!policy.is_constrained(&healthy));
+        assert!(!policy.is_constrained(&DeviceConditions { battery_percent: Some(10), charging: true, ..healthy }));
+        assert!(policy.is_constrained(&DeviceConditions { battery_percent: Some(10), ..healthy }));
+        assert!(policy.is_constrained(&DeviceConditions { low_power_mode: true, ..healthy }));
+        assert!(policy.is_constrained(&DeviceConditions { thermal_state: ThermalState::Serious, ..healthy }));
+
+        assert!(policy.may_solve(&healthy, 9_999) && !policy.may_solve(&healthy, 10_000));
+        assert!(!policy.may_solve(&DeviceConditions { thermal_state: ThermalState::Critical, ..healthy }, 0));
+        assert!(MobileSolverPolicy { stop_thermal_state: ThermalState::Fair, ..policy }.validate().is_err());
+    }
+
+    #[test]
+    fn test_advertised_capabilities_lower_difficulty() {
+        let policy = MobileSolverPolicy::default();
+        let capabilities = ClientCapabilities { wasm_simd: true, wasm_threads: true, ..Default::default() };
+        let low_battery = DeviceConditions { battery_percent: Some(5), ..Default::default() };
+
+        let advertised = policy.advertise(capabilities.clone(), &low_battery);
+        assert!(advertised.constrained);
+        assert_eq!(capabilities.negotiate(&[PowAlgorithm::Sha256], 100_000).unwrap().difficulty, 100_000);
+        assert_eq!(advertised.negotiate(&[PowAlgorithm::Sha256], 100_000).unwrap().difficulty, 25_000);
+
+        let json: String = serde_json::to_string(&policy).unwrap();
+        assert!(json.contains(r#""throttle_thermal_state":"serious","stop_thermal_state":"critical","background":"pause""#));
+    }
+}
@@ -0,0 +1,171 @@
+//! # Responses to challenges requiring several solutions.
+//!
+//! A challenge with `required_solutions = k` is only redeemed with `k`
+//! distinct nonces that each meet the target.
+//! `MultiSolutionResponse` carries them; its encodings mirror those of
+//! `IronShieldChallengeResponse`, with the nonces comma-separated in
+//! the concatenated format and count-prefixed in the compact one.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::compact::{
+    CompactReader,
+    CompactWriter
+};
+use crate::IronShieldChallenge;
+
+/// Response carrying every solution of a k-of-n challenge.
+///
+/// * `solved_challenge`: The complete original challenge.
+/// * `solutions`:        The distinct nonces found for it.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiSolutionResponse {
+    pub solved_challenge: IronShieldChallenge,
+    pub solutions:        Vec<i64>,
+}
+
+impl MultiSolutionResponse {
+    /// # Arguments
+    /// * `solved_challenge`: The solved challenge.
+    /// * `solutions`:        The nonces found for it.
+    ///
+    /// # Returns
+    /// * `Self`: The response.
+    pub fn new(solved_challenge: IronShieldChallenge, solutions: Vec<i64>) -> Self {
+        Self { solved_challenge, solutions }
+    }
+
+    /// Checks that `solutions` are exactly the required number of
+    /// distinct nonces, each meeting the target. Signature and expiry
+    /// are validated separately.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the solutions are valid.
+    pub fn verify_solutions(&self) -> Result<(), String> {
+        self.solved_challenge.check_solutions(&self.solutions)
+    }
+
+    /// Concatenates the response data into a string.
+    ///
+    /// Concatenates:
+    /// * `solved_challenge`: As its concatenated string representation.
+    /// * `solutions`:        As comma-separated integers.
+    pub fn concat_struct(&self) -> String {
+        let solutions: Vec<String> = self.solutions.iter().map(i64::to_string).collect();
+        format!("{}|{}", self.solved_challenge.concat_struct(), solutions.join(","))
+    }
+
+    /// # Arguments
+    /// * `concat_string`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed response or an error message.
+    pub fn from_concat_struct(concat_string: &str) -> Result<Self, String> {
+        let (challenge_part, solutions_part) = concat_string.rsplit_once('|')
+            .ok_or("Expected at least one '|' separator")?;
+
+        let solutions: Vec<i64> = solutions_part.split(',')
+            .map(|solution| solution.parse::<i64>().map_err(|_| format!("Failed to parse solution {:?} as i64", solution)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            solved_challenge: IronShieldChallenge::from_concat_struct(challenge_part)?,
+            solutions,
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The response, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded response or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+
+    /// Encodes the response in the compact binary format: the compact
+    /// challenge, the number of solutions as a `u8`, then each solution
+    /// as a big-endian `i64`.
+    ///
+    /// # Returns
+    /// * `Result<Vec<u8>, String>`: The encoded bytes, or an error if a
+    ///                              field is too long to encode.
+    pub fn to_compact_bytes(&self) -> Result<Vec<u8>, String> {
+        let count: u8 = u8::try_from(self.solutions.len())
+            .map_err(|_| "Too many solutions to encode")?;
+
+        let mut writer = CompactWriter::new();
+        self.solved_challenge.write_compact(&mut writer)?;
+        writer.write_u8(count);
+        for solution in &self.solutions {
+            writer.write_i64(*solution);
+        }
+        Ok(writer.finish())
+    }
+
+    /// # Arguments
+    /// * `bytes`: The payload produced by `to_compact_bytes()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The decoded response or an error
+    ///                           describing the malformed field.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = CompactReader::new(bytes)?;
+        let solved_challenge: IronShieldChallenge = IronShieldChallenge::read_compact(&mut reader)?;
+        let count: u8 = reader.read_u8("solution count")?;
+        let solutions: Vec<i64> = (0..count)
+            .map(|_| reader.read_i64("solution"))
+            .collect::<Result<_, _>>()?;
+        reader.finish()?;
+
+        Ok(Self { solved_challenge, solutions })
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        IronShieldChallengeResponse,
+        SigningKey
+    };
+
+    #[test]
+    fn test_k_of_n_solutions() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let plain = IronShieldChallenge::new("example.com".parse().unwrap(), 8, key.clone(), key.verifying_key().to_bytes());
+        let challenge = plain.clone().with_required_solutions(3, &key).unwrap();
+        assert_eq!(challenge.solution_count(), 3);
+        assert_eq!(challenge.recommended_attempts, plain.recommended_attempts * 3);
+        crate::verify_challenge_signature_with_key(&challenge, &key.verifying_key().to_bytes()).unwrap();
+
+        let found: Vec<i64> = (0..).filter(|nonce| challenge.check_solution(*nonce).is_ok()).take(4).collect();
+        let response = MultiSolutionResponse::new(challenge.clone(), found[..3].to_vec());
+        response.verify_solutions().unwrap();
+        assert!(MultiSolutionResponse::new(challenge.clone(), found[..2].to_vec()).verify_solutions().is_err());
+        assert!(MultiSolutionResponse::new(challenge.clone(), found.clone()).verify_solutions().is_err());
+        let repeated: Vec<i64> = vec![found[0], found[1], found[0]];
+        assert!(MultiSolutionResponse::new(challenge.clone(), repeated).verify_solutions().unwrap_err().contains("Duplicate"));
+        assert!(IronShieldChallengeResponse::new(challenge.clone(), found[0]).verify_solution().is_err());
+
+        let decoded = MultiSolutionResponse::from_base64url_header(&response.to_base64url_header()).unwrap();
+        assert_eq!(decoded, response);
+        assert_eq!(decoded.solved_challenge.required_solutions, Some(3));
+        assert_eq!(MultiSolutionResponse::from_compact_bytes(&response.to_compact_bytes().unwrap()).unwrap(), response);
+        assert!(MultiSolutionResponse::from_concat_struct(&format!("{}|1,x", challenge.concat_struct())).is_err());
+
+        assert!(plain.clone().with_required_solutions(1, &key).is_err());
+        assert!(plain.with_required_solutions(33, &key).is_err());
+    }
+}
@@ -0,0 +1,467 @@
+//! # One round trip negotiation of challenge parameters.
+//!
+//! The client sends a `NegotiationOffer` listing its solver
+//! capabilities and the transports it can receive challenges over. The
+//! issuer answers with a `NegotiationAccept` naming the algorithm,
+//! difficulty and transport it settled on. The accept is signed and
+//! commits to the hash of the offer, so a middlebox can neither weaken
+//! the accepted parameters nor replay an accept made for another offer.
+
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+#[cfg(feature = "sign")]
+use ed25519_dalek::SigningKey;
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_signature,
+    serialize_32_bytes,
+    serialize_signature
+};
+use crate::{
+    ClientCapabilities,
+    CryptoError,
+    ErrorDetail,
+    Expiring,
+    PowAlgorithm
+};
+
+/// Domain separation prefix of the accept signing message, so accept
+/// signatures can never be mistaken for challenge or token signatures.
+const ACCEPT_SIGNING_CONTEXT: &str = "ironshield-negotiation-accept-v1";
+
+/// Channel a challenge is delivered and answered over.
+///
+/// * `Header`:    `X-IronShield-*` HTTP headers.
+/// * `WebSocket`: `Sec-WebSocket-Protocol` entries or query values.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeTransport {
+    #[default]
+    Header,
+    WebSocket,
+}
+
+impl ChallengeTransport {
+    /// # Returns
+    /// * `&'static str`: The stable wire identifier of the transport.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengeTransport::Header    => "header",
+            ChallengeTransport::WebSocket => "web_socket",
+        }
+    }
+}
+
+impl std::fmt::Display for ChallengeTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ChallengeTransport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "header"     => Ok(ChallengeTransport::Header),
+            "web_socket" => Ok(ChallengeTransport::WebSocket),
+            other => Err(format!("Unknown challenge transport: {}", other)),
+        }
+    }
+}
+
+/// Client half of the negotiation.
+///
+/// * `website_id`:   The website the client wants a challenge for.
+/// * `capabilities`: What the client's solver supports.
+/// * `transports`:   Transports the client accepts, most preferred
+///                   first. Empty means `Header` only.
+/// * `offer_nonce`:  Random value making every offer unique.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NegotiationOffer {
+    pub website_id:   String,
+    #[serde(default)]
+    pub capabilities: ClientCapabilities,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transports:   Vec<ChallengeTransport>,
+    pub offer_nonce:  [u8; 16],
+}
+
+impl NegotiationOffer {
+    /// Constructor for creating a new `NegotiationOffer` instance.
+    ///
+    /// # Arguments
+    /// * `website_id`:   The identifier of the website.
+    /// * `capabilities`: What the client's solver supports.
+    /// * `transports`:   Accepted transports, most preferred first.
+    /// * `offer_nonce`:  Fresh random bytes.
+    ///
+    /// # Returns
+    /// * `Self`:         A new offer.
+    pub fn new(
+        website_id:   String,
+        capabilities: ClientCapabilities,
+        transports:   Vec<ChallengeTransport>,
+        offer_nonce:  [u8; 16],
+    ) -> Self {
+        Self {
+            website_id,
+            capabilities,
+            transports,
+            offer_nonce,
+        }
+    }
+
+    /// # Arguments
+    /// * `transport`: A candidate transport.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the client accepts `transport`.
+    pub fn accepts_transport(&self, transport: ChallengeTransport) -> bool {
+        if self.transports.is_empty() {
+            transport == ChallengeTransport::Header
+        } else {
+            self.transports.contains(&transport)
+        }
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: SHA-256 of `concat_struct()`, which the accept
+    ///               commits to.
+    pub fn offer_hash(&self) -> [u8; 32] {
+        Sha256::digest(self.concat_struct().as_bytes()).into()
+    }
+
+    /// Concatenates the offer data into a string.
+    ///
+    /// Concatenates:
+    /// * `website_id`:   as a string.
+    /// * `capabilities`: in `ClientCapabilities::to_wire_string()` form.
+    /// * `transports`:   as comma-separated wire identifiers.
+    /// * `offer_nonce`:  as a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        let transports: Vec<&str> = self.transports.iter().map(ChallengeTransport::as_str).collect();
+        format!(
+            "{}|{}|{}|{}",
+            self.website_id,
+            self.capabilities.to_wire_string(),
+            transports.join(","),
+            hex::encode(self.offer_nonce)
+        )
+    }
+
+    /// Creates a `NegotiationOffer` from a concatenated string.
+    ///
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed offer or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+        if parts.len() != 4 {
+            return Err(format!("Expected 4 parts, got {}", parts.len()));
+        }
+
+        if parts[0].is_empty() {
+            return Err("website_id cannot be empty".to_string());
+        }
+        let capabilities: ClientCapabilities = ClientCapabilities::from_wire_string(parts[1])?;
+        let transports: Vec<ChallengeTransport> = parts[2].split(',')
+            .filter(|transport| !transport.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, String>>()?;
+
+        let mut offer_nonce: [u8; 16] = [0u8; 16];
+        hex::decode_to_slice(parts[3], &mut offer_nonce)
+            .map_err(|_| "Offer nonce must be 16 bytes of hex")?;
+
+        Ok(Self::new(parts[0].to_string(), capabilities, transports, offer_nonce))
+    }
+
+    /// # Returns
+    /// * `String`: The offer, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded offer or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+/// Issuer half of the negotiation, signed by the issuer.
+///
+/// * `website_id`: The website of the offer.
+/// * `offer_hash`: `offer_hash()` of the offer being answered.
+/// * `algorithm`:  The algorithm the challenge will use.
+/// * `difficulty`: The difficulty the challenge will use.
+/// * `transport`:  The transport the challenge will be delivered over.
+/// * `expires_at`: Unix milli timestamp after which the accept is void.
+/// * `public_key`: Ed25519 public key of the issuer.
+/// * `signature`:  Ed25519 signature over `signing_message()`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NegotiationAccept {
+    pub website_id: String,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub offer_hash: [u8; 32],
+    pub algorithm:  PowAlgorithm,
+    pub difficulty: u64,
+    pub transport:  ChallengeTransport,
+    pub expires_at: i64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub public_key: [u8; 32],
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature:  [u8; 64],
+}
+
+impl NegotiationAccept {
+    /// Answers an offer: negotiates the algorithm and difficulty with
+    /// `ClientCapabilities::negotiate`, picks the first issuer transport
+    /// the client accepts, and signs the result.
+    ///
+    /// # Arguments
+    /// * `offer`:             The client's offer.
+    /// * `issuer_algorithms`: Accepted algorithms, most preferred first.
+    /// * `difficulty`:        Difficulty for a fully capable client.
+    /// * `issuer_transports`: Offered transports, most preferred first.
+    /// * `expires_at`:        Unix milli timestamp the accept is valid until.
+    /// * `private_key`:       The issuer's Ed25519 signing key.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The signed accept, or an error if no
+    ///                           algorithm or transport is compatible.
+    #[cfg(feature = "sign")]
+    pub fn accept(
+        offer:             &NegotiationOffer,
+        issuer_algorithms: &[PowAlgorithm],
+        difficulty:        u64,
+        issuer_transports: &[ChallengeTransport],
+        expires_at:        i64,
+        private_key:       &SigningKey,
+    ) -> Result<Self, String> {
+        let parameters = offer.capabilities.negotiate(issuer_algorithms, difficulty)?;
+        let transport: ChallengeTransport = issuer_transports.iter()
+            .copied()
+            .find(|transport| offer.accepts_transport(*transport))
+            .ok_or("Client accepts none of the offered transports")?;
+
+        let mut accept = Self {
+            website_id: offer.website_id.clone(),
+            offer_hash: offer.offer_hash(),
+            algorithm:  parameters.algorithm,
+            difficulty: parameters.difficulty,
+            transport,
+            expires_at,
+            public_key: private_key.verifying_key().to_bytes(),
+            signature:  [0u8; 64],
+        };
+        accept.signature = crate::crypto::generate_signature(private_key, &accept.signing_message())
+            .map_err(|e| e.to_string())?;
+        Ok(accept)
+    }
+
+    /// Creates the message covered by `signature`: a context prefix
+    /// followed by every other field, `|`-separated.
+    ///
+    /// # Returns
+    /// * `String`: Canonical string representation for signing.
+    pub fn signing_message(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}",
+            ACCEPT_SIGNING_CONTEXT,
+            self.website_id,
+            hex::encode(self.offer_hash),
+            self.algorithm,
+            self.difficulty,
+            self.transport,
+            self.expires_at,
+            hex::encode(self.public_key)
+        )
+    }
+
+    /// Checks that the accept answers `offer` and is signed by the
+    /// expected issuer. Expiry is checked separately via `Expiring`.
+    ///
+    /// # Arguments
+    /// * `offer`:            The offer the client sent.
+    /// * `public_key_bytes`: The issuer's Ed25519 public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if the
+    ///                              accept belongs to another offer,
+    ///                              another key, or was altered.
+    pub fn verify(&self, offer: &NegotiationOffer, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        if self.offer_hash != offer.offer_hash() || self.website_id != offer.website_id {
+            return Err(CryptoError::VerificationFailed("Accept does not answer this offer".into()));
+        }
+        if &self.public_key != public_key_bytes {
+            return Err(CryptoError::VerificationFailed("Accept is signed by an unexpected key".into()));
+        }
+
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        verifying_key.verify(self.signing_message().as_bytes(), &Signature::from_bytes(&self.signature))
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Accept signature verification failed: {}", e), e)))
+    }
+
+    /// Concatenates the accept data into a string: the fields of
+    /// `signing_message()` without the context prefix, followed by
+    /// `signature` as a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        let message: String = self.signing_message();
+        format!(
+            "{}|{}",
+            &message[ACCEPT_SIGNING_CONTEXT.len() + 1..],
+            hex::encode(self.signature)
+        )
+    }
+
+    /// Creates a `NegotiationAccept` from a concatenated string.
+    ///
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed accept or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+        if parts.len() != 8 {
+            return Err(format!("Expected 8 parts, got {}", parts.len()));
+        }
+
+        let mut offer_hash: [u8; 32] = [0u8; 32];
+        hex::decode_to_slice(parts[1], &mut offer_hash)
+            .map_err(|_| "Offer hash must be 32 bytes of hex")?;
+        let mut public_key: [u8; 32] = [0u8; 32];
+        hex::decode_to_slice(parts[6], &mut public_key)
+            .map_err(|_| "Public key must be 32 bytes of hex")?;
+        let mut signature: [u8; 64] = [0u8; 64];
+        hex::decode_to_slice(parts[7], &mut signature)
+            .map_err(|_| "Signature must be 64 bytes of hex")?;
+
+        Ok(Self {
+            website_id: parts[0].to_string(),
+            offer_hash,
+            algorithm:  parts[2].parse()?,
+            difficulty: parts[3].parse::<u64>().map_err(|_| "Failed to parse difficulty as u64")?,
+            transport:  parts[4].parse()?,
+            expires_at: parts[5].parse::<i64>().map_err(|_| "Failed to parse expires_at as i64")?,
+            public_key,
+            signature,
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The accept, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded accept or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+impl Expiring for NegotiationAccept {
+    fn expires_at(&self) -> i64 {
+        self.expires_at
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    fn offer() -> NegotiationOffer {
+        let capabilities = ClientCapabilities {
+            algorithms:          vec![PowAlgorithm::Sha256, PowAlgorithm::Wait],
+            wasm_simd:           true,
+            wasm_threads:        false,
+            verifies_signatures: false,
+            constrained:         false,
+        };
+        NegotiationOffer::new("example.com".to_string(), capabilities, vec![ChallengeTransport::WebSocket, ChallengeTransport::Header], [5; 16])
+    }
+
+    #[test]
+    fn test_offer_header_roundtrip() {
+        let offer = offer();
+        assert_eq!(NegotiationOffer::from_base64url_header(&offer.to_base64url_header()).unwrap(), offer);
+        assert_ne!(offer.offer_hash(), NegotiationOffer { offer_nonce: [6; 16], ..offer.clone() }.offer_hash());
+
+        let legacy = NegotiationOffer::new("example.com".to_string(), ClientCapabilities::default(), Vec::new(), [0; 16]);
+        assert!(legacy.accepts_transport(ChallengeTransport::Header));
+        assert!(!legacy.accepts_transport(ChallengeTransport::WebSocket));
+        assert!(NegotiationOffer::from_concat_struct("example.com|;|smtp|00").unwrap_err().contains("Unknown challenge transport"));
+    }
+
+    #[test]
+    fn test_accept_and_verify() {
+        let key = SigningKey::from_bytes(&[2; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let offer = offer();
+
+        let accept = NegotiationAccept::accept(
+            &offer,
+            &[PowAlgorithm::Sha256],
+            100_000,
+            &[ChallengeTransport::Header, ChallengeTransport::WebSocket],
+            5_000,
+            &key,
+        ).unwrap();
+        assert_eq!((accept.algorithm, accept.difficulty, accept.transport), (PowAlgorithm::Sha256, 50_000, ChallengeTransport::Header));
+        accept.verify(&offer, &public_key).unwrap();
+        assert!(!accept.is_expired_at(5_000) && accept.is_expired_at(5_001));
+
+        let decoded = NegotiationAccept::from_base64url_header(&accept.to_base64url_header()).unwrap();
+        assert_eq!(decoded, accept);
+        let json: String = serde_json::to_string(&accept).unwrap();
+        assert_eq!(serde_json::from_str::<NegotiationAccept>(&json).unwrap(), accept);
+
+        // The accepted parameters and the offer are covered by the signature.
+        let weakened = NegotiationAccept { difficulty: 1, ..accept.clone() };
+        assert!(weakened.verify(&offer, &public_key).is_err());
+        let other_offer = NegotiationOffer { offer_nonce: [9; 16], ..offer.clone() };
+        assert!(accept.verify(&other_offer, &public_key).is_err());
+        assert!(accept.verify(&offer, &SigningKey::from_bytes(&[3; 32]).verifying_key().to_bytes()).is_err());
+
+        assert!(NegotiationAccept::accept(&offer, &[PowAlgorithm::Sha256], 1, &[], 0, &key).is_err());
+    }
+}
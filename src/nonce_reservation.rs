@@ -0,0 +1,262 @@
+//! # Splitting the nonce search space between solvers.
+//!
+//! A user with several tabs or devices open may solve the same
+//! challenge in each of them. Handing every solver a disjoint
+//! `NonceReservation` avoids searching the same nonces twice. Splits
+//! and merges are deterministic, and the `reservation_id` is derived
+//! from the range itself, so solvers that split the same challenge the
+//! same way agree on the ids without talking to each other.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    serialize_32_bytes
+};
+use crate::IronShieldChallenge;
+
+/// Number of non-negative `i64` nonces.
+const NONCE_SPACE_LEN: u64 = i64::MAX as u64 + 1;
+
+/// A contiguous range of solution nonces reserved by one solver.
+///
+/// * `challenge_id`:   `challenge_id()` of the challenge being solved.
+/// * `reservation_id`: Identifier derived from the challenge and range.
+/// * `start`:          First nonce of the range.
+/// * `len`:            Number of nonces in the range (at least 1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct NonceReservation {
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub challenge_id:   [u8; 32],
+    pub start:          i64,
+    pub len:            u64,
+    pub reservation_id: [u8; 16],
+}
+
+impl NonceReservation {
+    /// # Arguments
+    /// * `challenge_id`: `challenge_id()` of the challenge being solved.
+    /// * `start`:        First nonce, non-negative.
+    /// * `len`:          Number of nonces; the range must end at or
+    ///                   before `i64::MAX`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The reservation, or an error for an
+    ///                           empty or out-of-range span.
+    pub fn new(challenge_id: [u8; 32], start: i64, len: u64) -> Result<Self, String> {
+        if start < 0 {
+            return Err("Nonce reservation must start at a non-negative nonce".to_string());
+        }
+        if len == 0 || len > NONCE_SPACE_LEN - start as u64 {
+            return Err(format!("Invalid nonce reservation length {} from {}", len, start));
+        }
+        Ok(Self::new_unchecked(challenge_id, start, len))
+    }
+
+    fn new_unchecked(challenge_id: [u8; 32], start: i64, len: u64) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(challenge_id);
+        hasher.update(start.to_be_bytes());
+        hasher.update(len.to_be_bytes());
+        let digest: [u8; 32] = hasher.finalize().into();
+
+        let mut reservation_id: [u8; 16] = [0u8; 16];
+        reservation_id.copy_from_slice(&digest[..16]);
+        Self { challenge_id, start, len, reservation_id }
+    }
+
+    /// # Arguments
+    /// * `challenge`: The challenge being solved.
+    ///
+    /// # Returns
+    /// * `Self`: A reservation of every non-negative nonce.
+    pub fn whole(challenge: &IronShieldChallenge) -> Self {
+        Self::new_unchecked(challenge.challenge_id(), 0, NONCE_SPACE_LEN)
+    }
+
+    /// # Returns
+    /// * `i64`: The last nonce of the range (inclusive).
+    pub fn last(&self) -> i64 {
+        self.start + (self.len - 1) as i64
+    }
+
+    /// # Arguments
+    /// * `nonce`: A candidate solution.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if `nonce` lies in the range.
+    pub fn contains(&self, nonce: i64) -> bool {
+        (self.start..=self.last()).contains(&nonce)
+    }
+
+    /// # Returns
+    /// * `impl Iterator<Item = i64>`: The nonces of the range, in order.
+    pub fn nonces(&self) -> impl Iterator<Item = i64> {
+        self.start..=self.last()
+    }
+
+    /// Splits the range into `parts` contiguous reservations whose
+    /// lengths differ by at most one, longer ones first.
+    ///
+    /// # Arguments
+    /// * `parts`: Number of solvers; clamped to `1..=len`.
+    ///
+    /// # Returns
+    /// * `Vec<Self>`: The reservations, in nonce order.
+    pub fn split(&self, parts: u64) -> Vec<Self> {
+        let parts: u64 = parts.clamp(1, self.len);
+        let base: u64 = self.len / parts;
+        let remainder: u64 = self.len % parts;
+
+        let mut start: i64 = self.start;
+        (0..parts)
+            .map(|i| {
+                let len: u64 = base + u64::from(i < remainder);
+                let part: Self = Self::new_unchecked(self.challenge_id, start, len);
+                start = start.wrapping_add(len as i64);
+                part
+            })
+            .collect()
+    }
+
+    /// Splits off the nonces from `offset` on, e.g. to hand the rest of
+    /// a partly searched range to another solver.
+    ///
+    /// # Arguments
+    /// * `offset`: Number of nonces kept in the first half.
+    ///
+    /// # Returns
+    /// * `Option<(Self, Self)>`: Both halves, or `None` unless
+    ///                           `0 < offset < len`.
+    pub fn split_at(&self, offset: u64) -> Option<(Self, Self)> {
+        if offset == 0 || offset >= self.len {
+            return None;
+        }
+        Some((
+            Self::new_unchecked(self.challenge_id, self.start, offset),
+            Self::new_unchecked(self.challenge_id, self.start + offset as i64, self.len - offset),
+        ))
+    }
+
+    /// Joins two adjacent reservations of the same challenge.
+    ///
+    /// # Arguments
+    /// * `other`: The reservation to join, before or after this one.
+    ///
+    /// # Returns
+    /// * `Option<Self>`: The combined reservation, or `None` if the
+    ///                   ranges belong to different challenges or are
+    ///                   not adjacent.
+    pub fn merge(&self, other: &Self) -> Option<Self> {
+        if self.challenge_id != other.challenge_id {
+            return None;
+        }
+        let (first, second) = if self.start <= other.start { (self, other) } else { (other, self) };
+        if first.last().checked_add(1) != Some(second.start) {
+            return None;
+        }
+        Some(Self::new_unchecked(first.challenge_id, first.start, first.len + second.len))
+    }
+
+    /// Coalesces reservations, e.g. those returned by solvers that
+    /// stopped early, into as few ranges as possible. Overlapping
+    /// ranges are joined as well.
+    ///
+    /// # Arguments
+    /// * `reservations`: The reservations, in any order.
+    ///
+    /// # Returns
+    /// * `Vec<Self>`: Disjoint, non-adjacent reservations, ordered by
+    ///                challenge id and start.
+    pub fn merge_all(mut reservations: Vec<Self>) -> Vec<Self> {
+        reservations.sort_by_key(|reservation| (reservation.challenge_id, reservation.start));
+
+        let mut merged: Vec<Self> = Vec::with_capacity(reservations.len());
+        for reservation in reservations {
+            match merged.last_mut() {
+                Some(previous)
+                    if previous.challenge_id == reservation.challenge_id
+                        && reservation.start <= previous.last().saturating_add(1) =>
+                {
+                    let last: i64 = previous.last().max(reservation.last());
+                    *previous = Self::new_unchecked(previous.challenge_id, previous.start, (last - previous.start) as u64 + 1);
+                }
+                _ => merged.push(reservation),
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHALLENGE: [u8; 32] = [3; 32];
+
+    #[test]
+    fn test_new_validates_range() {
+        assert!(NonceReservation::new(CHALLENGE, -1, 10).is_err());
+        assert!(NonceReservation::new(CHALLENGE, 0, 0).is_err());
+        assert!(NonceReservation::new(CHALLENGE, i64::MAX, 2).is_err());
+
+        let tail = NonceReservation::new(CHALLENGE, i64::MAX, 1).unwrap();
+        assert_eq!(tail.last(), i64::MAX);
+        assert!(tail.contains(i64::MAX) && !tail.contains(i64::MAX - 1));
+    }
+
+    #[test]
+    fn test_split_is_deterministic_and_covers_range() {
+        let range = NonceReservation::new(CHALLENGE, 100, 10).unwrap();
+        let parts: Vec<NonceReservation> = range.split(3);
+
+        let spans: Vec<(i64, u64)> = parts.iter().map(|p| (p.start, p.len)).collect();
+        assert_eq!(spans, [(100, 4), (104, 3), (107, 3)]);
+        assert_eq!(parts, range.split(3));
+        assert_eq!(range.split(50).len(), 10);
+        assert_eq!(range.split(0), [range]);
+
+        let all: Vec<i64> = parts.iter().flat_map(NonceReservation::nonces).collect();
+        assert_eq!(all, range.nonces().collect::<Vec<_>>());
+
+        let ids: std::collections::HashSet<[u8; 16]> = parts.iter().map(|p| p.reservation_id).collect();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn test_whole_space_splits_without_overflow() {
+        let whole = NonceReservation::new(CHALLENGE, 0, NONCE_SPACE_LEN).unwrap();
+        let parts: Vec<NonceReservation> = whole.split(7);
+        assert_eq!(parts.last().unwrap().last(), i64::MAX);
+        assert_eq!(NonceReservation::merge_all(parts.clone()), [whole]);
+
+        let (kept, rest) = parts[6].split_at(1_000).unwrap();
+        assert_eq!(kept.merge(&rest), Some(parts[6]));
+        assert!(parts[6].split_at(0).is_none());
+    }
+
+    #[test]
+    fn test_merge() {
+        let parts: Vec<NonceReservation> = NonceReservation::new(CHALLENGE, 0, 100).unwrap().split(4);
+        assert_eq!(parts[1].merge(&parts[0]), parts[0].merge(&parts[1]));
+        assert!(parts[0].merge(&parts[2]).is_none());
+
+        let other_challenge = NonceReservation::new([4; 32], 25, 25).unwrap();
+        assert!(parts[0].merge(&other_challenge).is_none());
+
+        let overlapping = NonceReservation::new(CHALLENGE, 60, 20).unwrap();
+        let merged: Vec<NonceReservation> = NonceReservation::merge_all(vec![parts[3], overlapping, other_challenge, parts[0], parts[2]]);
+        let spans: Vec<([u8; 32], i64, u64)> = merged.iter().map(|p| (p.challenge_id, p.start, p.len)).collect();
+        assert_eq!(spans, [(CHALLENGE, 0, 25), (CHALLENGE, 50, 50), ([4; 32], 25, 25)]);
+    }
+}
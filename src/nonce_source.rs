@@ -0,0 +1,189 @@
+//! # Randomness for challenge nonces and keys.
+//!
+//! `IronShieldChallenge::new` draws its nonce from the system RNG (the
+//! `rng` feature), which on wasm32 needs a getrandom backend:
+//!
+//! * Browsers and Workers: enable `wasm-rng`, which wires getrandom to
+//!   `crypto.getRandomValues`.
+//! * WASI: `rng` works as is.
+//! * Other hosts: build without `rng` and pass a `NonceSource` backed by
+//!   the host's randomness to `IronShieldChallenge::new_with_nonce_source`.
+//!
+//! Key generation takes an `EntropySource`, the general form of
+//! `NonceSource` that fills buffers of any length, so such hosts can
+//! also create keys with `generate_signing_key_with`.
+
+use ed25519_dalek::{
+    SigningKey,
+    SECRET_KEY_LENGTH
+};
+
+/// Number of random bytes in a challenge nonce.
+pub const RANDOM_NONCE_LEN: usize = 16;
+
+/// Source of the random bytes behind `random_nonce`.
+///
+/// Implemented for closures, so host randomness can be passed inline:
+///
+/// ```
+/// use ironshield_types::{IronShieldChallenge, LifetimePolicy, SigningKey};
+///
+/// let host_random = |nonce: &mut [u8; 16]| -> Result<(), String> {
+///     nonce.fill(0x5a); // call the host's CSPRNG here
+///     Ok(())
+/// };
+/// let key = SigningKey::from_bytes(&[1; 32]);
+/// let challenge = IronShieldChallenge::new_with_nonce_source(
+///     "example.com".parse().unwrap(),
+///     1_000,
+///     &key,
+///     key.verifying_key().to_bytes(),
+///     &LifetimePolicy::default(),
+///     &host_random,
+/// ).unwrap();
+/// assert_eq!(challenge.random_nonce, "5a".repeat(16));
+/// ```
+pub trait NonceSource {
+    /// Fills `nonce` with cryptographically secure random bytes.
+    ///
+    /// # Arguments
+    /// * `nonce`: The buffer to fill.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())`, or an error if no randomness
+    ///                         is available.
+    fn fill_nonce(&self, nonce: &mut [u8; RANDOM_NONCE_LEN]) -> Result<(), String>;
+}
+
+impl<F> NonceSource for F
+where
+    F: Fn(&mut [u8; RANDOM_NONCE_LEN]) -> Result<(), String>,
+{
+    fn fill_nonce(&self, nonce: &mut [u8; RANDOM_NONCE_LEN]) -> Result<(), String> {
+        self(nonce)
+    }
+}
+
+/// `NonceSource` backed by the operating system RNG.
+#[cfg(feature = "rng")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemNonceSource;
+
+#[cfg(feature = "rng")]
+impl NonceSource for SystemNonceSource {
+    fn fill_nonce(&self, nonce: &mut [u8; RANDOM_NONCE_LEN]) -> Result<(), String> {
+        use rand::RngCore;
+
+        rand::rngs::OsRng.try_fill_bytes(nonce)
+            .map_err(|e| format!("System randomness is unavailable: {}", e))
+    }
+}
+
+/// Source of cryptographically secure random bytes.
+///
+/// Implemented for closures, like `NonceSource`. To draw challenge
+/// nonces from the same source, pass
+/// `|nonce: &mut [u8; RANDOM_NONCE_LEN]| entropy.fill_entropy(nonce)`.
+pub trait EntropySource {
+    /// Fills `dest` with cryptographically secure random bytes.
+    ///
+    /// # Arguments
+    /// * `dest`: The buffer to fill.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())`, or an error if no randomness
+    ///                         is available.
+    fn fill_entropy(&self, dest: &mut [u8]) -> Result<(), String>;
+}
+
+impl<F> EntropySource for F
+where
+    F: Fn(&mut [u8]) -> Result<(), String>,
+{
+    fn fill_entropy(&self, dest: &mut [u8]) -> Result<(), String> {
+        self(dest)
+    }
+}
+
+#[cfg(feature = "rng")]
+impl EntropySource for SystemNonceSource {
+    fn fill_entropy(&self, dest: &mut [u8]) -> Result<(), String> {
+        use rand::RngCore;
+
+        rand::rngs::OsRng.try_fill_bytes(dest)
+            .map_err(|e| format!("System randomness is unavailable: {}", e))
+    }
+}
+
+/// Generates an Ed25519 signing key from an entropy source.
+///
+/// # Arguments
+/// * `entropy`: Where the secret key bytes come from.
+///
+/// # Returns
+/// * `Result<SigningKey, String>`: The key, or the entropy source's
+///                                 error.
+pub fn generate_signing_key_with(entropy: &impl EntropySource) -> Result<SigningKey, String> {
+    let mut secret: [u8; SECRET_KEY_LENGTH] = [0u8; SECRET_KEY_LENGTH];
+    entropy.fill_entropy(&mut secret)?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        verify_challenge_signature_with_key,
+        IronShieldChallenge,
+        LifetimePolicy,
+        SigningKey
+    };
+
+    #[test]
+    fn test_challenge_from_nonce_source() {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let counter = |nonce: &mut [u8; RANDOM_NONCE_LEN]| -> Result<(), String> {
+            for (i, byte) in nonce.iter_mut().enumerate() {
+                *byte = i as u8;
+            }
+            Ok(())
+        };
+
+        let challenge = IronShieldChallenge::new_with_nonce_source(
+            "example.com".parse().unwrap(), 1_000, &key, key.verifying_key().to_bytes(), &LifetimePolicy::default(), &counter
+        ).unwrap();
+        assert_eq!(challenge.random_nonce, "000102030405060708090a0b0c0d0e0f");
+        verify_challenge_signature_with_key(&challenge, &key.verifying_key().to_bytes()).unwrap();
+
+        let failing = |_: &mut [u8; RANDOM_NONCE_LEN]| -> Result<(), String> { Err("no entropy".to_string()) };
+        assert_eq!(IronShieldChallenge::generate_random_nonce_with(&failing).unwrap_err(), "no entropy");
+    }
+
+    #[cfg(feature = "rng")]
+    #[test]
+    fn test_system_nonce_source() {
+        let first: crate::RandomNonce = IronShieldChallenge::generate_random_nonce_with(&SystemNonceSource).unwrap();
+        let second: crate::RandomNonce = IronShieldChallenge::generate_random_nonce_with(&SystemNonceSource).unwrap();
+        assert_eq!(first.as_str().len(), RANDOM_NONCE_LEN * 2);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_signing_key_from_entropy_source() {
+        let host_random = |dest: &mut [u8]| -> Result<(), String> {
+            dest.fill(0x11);
+            Ok(())
+        };
+        let key: SigningKey = generate_signing_key_with(&host_random).unwrap();
+        assert_eq!(key.to_bytes(), [0x11; 32]);
+
+        let challenge = IronShieldChallenge::new_with_nonce_source(
+            "example.com".parse().unwrap(), 1_000, &key, key.verifying_key().to_bytes(), &LifetimePolicy::default(),
+            &|nonce: &mut [u8; RANDOM_NONCE_LEN]| host_random.fill_entropy(nonce)
+        ).unwrap();
+        assert_eq!(challenge.random_nonce, "11".repeat(RANDOM_NONCE_LEN));
+
+        let failing = |_: &mut [u8]| -> Result<(), String> { Err("no entropy".to_string()) };
+        assert_eq!(generate_signing_key_with(&failing).unwrap_err(), "no entropy");
+    }
+}
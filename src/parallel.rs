@@ -0,0 +1,137 @@
+//! # Rayon-parallel batch validation for native targets.
+//!
+//! Origin servers outside the Workers runtime sometimes verify large
+//! batches at once, e.g. when replaying logs. These helpers spread the
+//! work over rayon's thread pool and return one result per input, in
+//! input order.
+
+use ed25519_dalek::VerifyingKey;
+use rayon::prelude::*;
+
+use crate::crypto::validate_challenge_with_verifying_key;
+use crate::{
+    load_public_key_from_env,
+    CryptoError,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    LifetimePolicy,
+    SolutionVerifier
+};
+
+/// Validates many challenges in parallel with the checks of
+/// `validate_challenge_with_policy`.
+///
+/// `IRONSHIELD_PUBLIC_KEY` is loaded once for the whole batch; if it
+/// cannot be loaded, every result is that error.
+///
+/// # Arguments
+/// * `challenges`: The challenges to validate.
+/// * `policy`:     The lifetime policy to honor.
+///
+/// # Returns
+/// * `Vec<Result<(), CryptoError>>`: One result per challenge.
+pub fn validate_challenges_par(
+    challenges: &[IronShieldChallenge],
+    policy:     &LifetimePolicy,
+) -> Vec<Result<(), CryptoError>> {
+    match load_public_key_from_env() {
+        Ok(verifying_key) => validate_challenges_with_key_par(challenges, &verifying_key, policy),
+        Err(e) => vec![Err(e); challenges.len()],
+    }
+}
+
+/// Validates many challenges in parallel against an explicit key.
+///
+/// # Arguments
+/// * `challenges`:    The challenges to validate.
+/// * `verifying_key`: The key the challenges must be signed with.
+/// * `policy`:        The lifetime policy to honor.
+///
+/// # Returns
+/// * `Vec<Result<(), CryptoError>>`: One result per challenge.
+pub fn validate_challenges_with_key_par(
+    challenges:    &[IronShieldChallenge],
+    verifying_key: &VerifyingKey,
+    policy:        &LifetimePolicy,
+) -> Vec<Result<(), CryptoError>> {
+    challenges
+        .par_iter()
+        .map(|challenge| validate_challenge_with_verifying_key(challenge, verifying_key, policy))
+        .collect()
+}
+
+/// Checks the proof-of-work solutions of many responses in parallel.
+///
+/// Signatures and expiry are not checked; combine with
+/// `validate_challenges_par` for full verification.
+///
+/// # Arguments
+/// * `responses`: The responses to check.
+///
+/// # Returns
+/// * `Vec<Result<(), String>>`: One result per response.
+pub fn verify_solutions_par(responses: &[IronShieldChallengeResponse]) -> Vec<Result<(), String>> {
+    responses
+        .par_iter()
+        .map(IronShieldChallengeResponse::verify_solution)
+        .collect()
+}
+
+impl SolutionVerifier {
+    /// Checks many candidate solutions of this verifier's challenge
+    /// in parallel.
+    ///
+    /// # Arguments
+    /// * `nonces`: The candidate solutions.
+    ///
+    /// # Returns
+    /// * `Vec<bool>`: For each nonce, whether it meets the target.
+    pub fn check_par(&self, nonces: &[i64]) -> Vec<bool> {
+        nonces.par_iter().map(|nonce| self.check(*nonce)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_verify_solutions_par_matches_sequential() {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 8, key.clone(), key.verifying_key().to_bytes());
+        let responses: Vec<IronShieldChallengeResponse> = (0..256)
+            .map(|nonce| IronShieldChallengeResponse::new(challenge.clone(), nonce))
+            .collect();
+
+        let parallel: Vec<Result<(), String>> = verify_solutions_par(&responses);
+        let sequential: Vec<Result<(), String>> = responses.iter().map(|r| r.verify_solution()).collect();
+        assert_eq!(parallel, sequential);
+        assert!(parallel.iter().any(Result::is_ok));
+
+        let verifier = SolutionVerifier::new(&challenge).unwrap();
+        let nonces: Vec<i64> = (0..256).collect();
+        let checks: Vec<bool> = verifier.check_par(&nonces);
+        assert_eq!(checks, sequential.iter().map(Result::is_ok).collect::<Vec<bool>>());
+    }
+
+    #[test]
+    fn test_validate_challenges_par_keeps_order() {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let good = IronShieldChallenge::new("example.com".parse().unwrap(), 8, key.clone(), key.verifying_key().to_bytes());
+        let mut expired = good.clone();
+        expired.created_time = 0;
+        expired.expiration_time = 1;
+        expired.sign(&key);
+        let mut tampered = good.clone();
+        tampered.website_id = "other.example".parse().unwrap();
+        let other_key = SigningKey::from_bytes(&[9; 32]);
+        let foreign = IronShieldChallenge::new("example.com".parse().unwrap(), 8, other_key.clone(), other_key.verifying_key().to_bytes());
+
+        let challenges = [good.clone(), expired, good.clone(), tampered, foreign, good];
+        let results = validate_challenges_with_key_par(&challenges, &key.verifying_key(), &LifetimePolicy::default());
+        let outcomes: Vec<bool> = results.iter().map(Result::is_ok).collect();
+        assert_eq!(outcomes, vec![true, false, true, false, false, true]);
+        assert!(results[1].as_ref().unwrap_err().to_string().contains("expired"));
+    }
+}
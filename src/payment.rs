@@ -0,0 +1,383 @@
+//! # Payment receipts as an alternative to proof-of-work.
+//!
+//! Instead of burning CPU on a challenge, a client may pay a small fee
+//! (x402-style). The payment facilitator then issues a `PaymentReceipt`
+//! that names the challenge it pays for and is signed with the
+//! facilitator's Ed25519 key. `PaymentRequirements` describes the price
+//! and the facilitators an issuer trusts.
+
+#[cfg(feature = "sign")]
+use ed25519_dalek::SigningKey;
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_signature,
+    serialize_32_bytes,
+    serialize_signature
+};
+#[cfg(feature = "sign")]
+use crate::epoch::now_millis;
+#[cfg(feature = "sign")]
+use crate::generate_signature;
+use crate::{
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge
+};
+
+/// Number of `|`-delimited parts in the concatenated format.
+const PART_COUNT: usize = 8;
+
+/// Payment receipt that bypasses a proof-of-work challenge.
+///
+/// * `payment_id`:          Facilitator-assigned payment identifier.
+/// * `amount`:              Amount paid in minor units of `currency`.
+/// * `currency`:            Currency or asset code (e.g. `USDC`).
+/// * `payer_proof`:         Opaque proof from the payer (e.g. a settlement
+///                          transaction hash or payer signature).
+/// * `challenge_signature`: Signature of the challenge being paid for.
+/// * `issued_at`:           Unix milli timestamp of receipt issuance.
+/// * `public_key`:          Ed25519 public key of the facilitator.
+/// * `signature`:           Facilitator signature over all other fields.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Signed payment receipt accepted in place of a proof-of-work solution"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentReceipt {
+    /// Facilitator-assigned payment identifier
+    #[cfg_attr(feature = "openapi", schema(example = "pay_01J8Z7K2"))]
+    pub payment_id:          String,
+    /// Amount paid in minor units of `currency`
+    #[cfg_attr(feature = "openapi", schema(example = 100u64))]
+    pub amount:              u64,
+    /// Currency or asset code
+    #[cfg_attr(feature = "openapi", schema(example = "USDC"))]
+    pub currency:            String,
+    /// Opaque payer proof (e.g. settlement transaction hash)
+    pub payer_proof:         Vec<u8>,
+    /// Signature of the challenge this payment bypasses (64 bytes)
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub challenge_signature: [u8; 64],
+    /// Unix timestamp in milliseconds when the receipt was issued
+    #[cfg_attr(feature = "openapi", schema(example = 1755401345880i64))]
+    pub issued_at:           i64,
+    /// Ed25519 public key of the payment facilitator (32 bytes)
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub public_key:          [u8; 32],
+    /// Facilitator signature over the receipt (64 bytes)
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature:           [u8; 64],
+}
+
+impl PaymentReceipt {
+    /// Constructor for creating a new, signed `PaymentReceipt`.
+    ///
+    /// # Arguments
+    /// * `payment_id`:      Facilitator-assigned payment identifier.
+    /// * `amount`:          Amount paid in minor units.
+    /// * `currency`:        Currency or asset code.
+    /// * `payer_proof`:     Opaque payer proof.
+    /// * `challenge`:       The challenge being paid for.
+    /// * `facilitator_key`: Ed25519 private key of the facilitator.
+    ///
+    /// # Returns
+    /// * `Result<Self, CryptoError>`: The signed receipt, or an error if
+    ///                                a field is invalid or signing fails.
+    #[cfg(feature = "sign")]
+    pub fn new(
+        payment_id:      String,
+        amount:          u64,
+        currency:        String,
+        payer_proof:     Vec<u8>,
+        challenge:       &IronShieldChallenge,
+        facilitator_key: &SigningKey,
+    ) -> Result<Self, CryptoError> {
+        Self::validate_text_field("payment_id", &payment_id).map_err(|e| CryptoError::SigningFailed(e.into()))?;
+        Self::validate_text_field("currency", &currency).map_err(|e| CryptoError::SigningFailed(e.into()))?;
+
+        let mut receipt = Self {
+            payment_id,
+            amount,
+            currency,
+            payer_proof,
+            challenge_signature: challenge.challenge_signature,
+            issued_at:           now_millis(),
+            public_key:          facilitator_key.verifying_key().to_bytes(),
+            signature:           [0u8; 64],
+        };
+        receipt.signature = generate_signature(facilitator_key, &receipt.signing_message())?;
+
+        Ok(receipt)
+    }
+
+    /// Text fields must be non-empty and must not contain the
+    /// concatenation delimiter.
+    fn validate_text_field(name: &str, value: &str) -> Result<(), String> {
+        if value.is_empty() {
+            return Err(format!("{} cannot be empty", name));
+        }
+        if value.contains('|') {
+            return Err(format!("{} cannot contain '|'", name));
+        }
+        Ok(())
+    }
+
+    /// Creates the message covered by `signature`: every other field
+    /// in concatenated form.
+    ///
+    /// # Returns
+    /// * `String`: Canonical string representation for signing.
+    pub fn signing_message(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}",
+            self.payment_id,
+            self.amount,
+            self.currency,
+            hex::encode(&self.payer_proof),
+            hex::encode(self.challenge_signature),
+            self.issued_at,
+            hex::encode(self.public_key)
+        )
+    }
+
+    /// Verifies the facilitator signature with the embedded public key.
+    ///
+    /// Callers must separately check that `public_key` belongs to a
+    /// trusted facilitator, e.g. with `PaymentRequirements::check`.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the signature is valid.
+    pub fn verify_signature(&self) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&self.public_key)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        let signature: Signature = Signature::from_slice(&self.signature)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid signature format: {}", e), e)))?;
+
+        verifying_key.verify(self.signing_message().as_bytes(), &signature)
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Payment receipt signature verification failed: {}", e), e)))
+    }
+
+    /// Concatenates the receipt data into a string: the signing
+    /// message followed by the signature as a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        format!("{}|{}", self.signing_message(), hex::encode(self.signature))
+    }
+
+    /// Creates a `PaymentReceipt` from a concatenated string.
+    ///
+    /// This function reverses the operation of
+    /// `PaymentReceipt::concat_struct`.
+    /// Expects a string in the format:
+    /// "payment_id|amount|currency|payer_proof|challenge_signature|issued_at|public_key|signature"
+    ///
+    /// # Arguments
+    /// * `concat_str`: The concatenated string to parse.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed receipt or an error
+    ///                           message if parsing fails.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+
+        if parts.len() != PART_COUNT {
+            return Err(format!("Expected {} parts, got {}", PART_COUNT, parts.len()));
+        }
+
+        let payment_id: String = parts[0].to_string();
+        Self::validate_text_field("payment_id", &payment_id)?;
+
+        let amount: u64 = parts[1].parse::<u64>()
+            .map_err(|_| "Failed to parse amount as u64")?;
+
+        let currency: String = parts[2].to_string();
+        Self::validate_text_field("currency", &currency)?;
+
+        let payer_proof: Vec<u8> = hex::decode(parts[3])
+            .map_err(|_| "Failed to decode payer_proof hex string")?;
+
+        let challenge_signature: [u8; 64] = hex::decode(parts[4])
+            .map_err(|_| "Failed to decode challenge_signature hex string")?
+            .try_into()
+            .map_err(|_| "Challenge signature must be exactly 64 bytes")?;
+
+        let issued_at: i64 = parts[5].parse::<i64>()
+            .map_err(|_| "Failed to parse issued_at as i64")?;
+
+        let public_key: [u8; 32] = hex::decode(parts[6])
+            .map_err(|_| "Failed to decode public_key hex string")?
+            .try_into()
+            .map_err(|_| "Public key must be exactly 32 bytes")?;
+
+        let signature: [u8; 64] = hex::decode(parts[7])
+            .map_err(|_| "Failed to decode signature hex string")?
+            .try_into()
+            .map_err(|_| "Signature must be exactly 64 bytes")?;
+
+        Ok(Self {
+            payment_id,
+            amount,
+            currency,
+            payer_proof,
+            challenge_signature,
+            issued_at,
+            public_key,
+            signature,
+        })
+    }
+
+    /// Encodes the receipt as a base64url string for HTTP header transport.
+    ///
+    /// # Returns
+    /// * `String`: Base64url-encoded string ready for HTTP header use.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// Decodes a base64url-encoded receipt from an HTTP header.
+    ///
+    /// # Arguments
+    /// * `encoded_header`: The base64url-encoded string from the HTTP header.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded receipt or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+/// Price and trusted facilitators for payment bypasses.
+///
+/// * `min_amount`:              Minimum amount in minor units of `currency`.
+/// * `currency`:                Accepted currency or asset code.
+/// * `facilitator_public_keys`: Ed25519 keys of trusted facilitators.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Price and trusted facilitators for paying instead of solving a challenge"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequirements {
+    /// Minimum amount in minor units of `currency`
+    #[cfg_attr(feature = "openapi", schema(example = 100u64))]
+    pub min_amount:              u64,
+    /// Accepted currency or asset code
+    #[cfg_attr(feature = "openapi", schema(example = "USDC"))]
+    pub currency:                String,
+    /// Ed25519 public keys of trusted payment facilitators
+    pub facilitator_public_keys: Vec<[u8; 32]>,
+}
+
+impl PaymentRequirements {
+    /// Checks a receipt against these requirements and the challenge
+    /// it claims to pay for.
+    ///
+    /// # Arguments
+    /// * `receipt`:   The presented receipt.
+    /// * `challenge`: The challenge being bypassed.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the receipt is valid, or a
+    ///                         description of the first failed check.
+    pub fn check(&self, receipt: &PaymentReceipt, challenge: &IronShieldChallenge) -> Result<(), String> {
+        if receipt.challenge_signature != challenge.challenge_signature {
+            return Err("Payment receipt is for a different challenge".to_string());
+        }
+        if receipt.currency != self.currency {
+            return Err(format!("Payment currency {} is not accepted", receipt.currency));
+        }
+        if receipt.amount < self.min_amount {
+            return Err(format!("Payment of {} is below the required {}", receipt.amount, self.min_amount));
+        }
+        if !self.facilitator_public_keys.contains(&receipt.public_key) {
+            return Err("Payment facilitator is not trusted".to_string());
+        }
+        receipt.verify_signature().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> (IronShieldChallenge, SigningKey, PaymentRequirements) {
+        let issuer_key = SigningKey::from_bytes(&[1; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, issuer_key.clone(), issuer_key.verifying_key().to_bytes());
+        let facilitator_key = SigningKey::from_bytes(&[2; 32]);
+        let requirements = PaymentRequirements {
+            min_amount:              100,
+            currency:                "USDC".to_string(),
+            facilitator_public_keys: vec![facilitator_key.verifying_key().to_bytes()],
+        };
+        (challenge, facilitator_key, requirements)
+    }
+
+    #[test]
+    fn test_signed_receipt_passes_requirements() {
+        let (challenge, facilitator_key, requirements) = fixtures();
+        let receipt = PaymentReceipt::new("pay_1".to_string(), 150, "USDC".to_string(), vec![0xAA; 32], &challenge, &facilitator_key).unwrap();
+
+        receipt.verify_signature().unwrap();
+        requirements.check(&receipt, &challenge).unwrap();
+    }
+
+    #[test]
+    fn test_requirements_reject_bad_receipts() {
+        let (challenge, facilitator_key, requirements) = fixtures();
+        let other_challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, facilitator_key.clone(), [0; 32]);
+
+        let cheap = PaymentReceipt::new("pay_1".to_string(), 99, "USDC".to_string(), vec![], &challenge, &facilitator_key).unwrap();
+        assert!(requirements.check(&cheap, &challenge).unwrap_err().contains("below the required"));
+
+        let euros = PaymentReceipt::new("pay_2".to_string(), 500, "EUR".to_string(), vec![], &challenge, &facilitator_key).unwrap();
+        assert!(requirements.check(&euros, &challenge).unwrap_err().contains("not accepted"));
+
+        let wrong = PaymentReceipt::new("pay_3".to_string(), 500, "USDC".to_string(), vec![], &other_challenge, &facilitator_key).unwrap();
+        assert!(requirements.check(&wrong, &challenge).unwrap_err().contains("different challenge"));
+
+        let rogue_key = SigningKey::from_bytes(&[3; 32]);
+        let rogue = PaymentReceipt::new("pay_4".to_string(), 500, "USDC".to_string(), vec![], &challenge, &rogue_key).unwrap();
+        assert!(requirements.check(&rogue, &challenge).unwrap_err().contains("not trusted"));
+
+        let mut inflated = PaymentReceipt::new("pay_5".to_string(), 100, "USDC".to_string(), vec![], &challenge, &facilitator_key).unwrap();
+        inflated.amount = 1_000_000;
+        assert!(requirements.check(&inflated, &challenge).unwrap_err().contains("signature verification failed"));
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let (challenge, facilitator_key, _) = fixtures();
+        let receipt = PaymentReceipt::new("pay_1".to_string(), 150, "USDC".to_string(), vec![1, 2, 3], &challenge, &facilitator_key).unwrap();
+
+        let decoded = PaymentReceipt::from_base64url_header(&receipt.to_base64url_header()).unwrap();
+        assert_eq!(decoded, receipt);
+        decoded.verify_signature().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let (challenge, facilitator_key, _) = fixtures();
+        assert!(PaymentReceipt::new("a|b".to_string(), 1, "USDC".to_string(), vec![], &challenge, &facilitator_key).is_err());
+        assert!(PaymentReceipt::new("pay".to_string(), 1, String::new(), vec![], &challenge, &facilitator_key).is_err());
+
+        assert!(PaymentReceipt::from_concat_struct("a|b").unwrap_err().contains("Expected 8 parts, got 2"));
+    }
+}
@@ -0,0 +1,168 @@
+//! # Prometheus text exposition of the stats types.
+//!
+//! Self-hosted servers without a metrics library can serve
+//! `PrometheusEncoder` output on their `/metrics` endpoint. Metric names
+//! are fixed here, and match the `MetricsSink` counter names, so
+//! dashboards work across deployments. The only label is `cache`, naming
+//! the cache a `CacheStats` belongs to.
+//!
+//! ```
+//! use ironshield_types::{CacheStats, ChallengeStats, PrometheusEncoder};
+//!
+//! let text: String = PrometheusEncoder::new()
+//!     .with_challenge_stats(&ChallengeStats::default())
+//!     .with_cache_stats("tokens", &CacheStats { hits: 9, misses: 1 })
+//!     .encode();
+//! assert!(text.contains("ironshield_cache_hits_total{cache=\"tokens\"} 9\n"));
+//! ```
+
+use std::fmt::Write;
+
+use crate::metrics::*;
+use crate::{
+    CacheStats,
+    SpotCheckStats
+};
+
+/// A metric family: name, help text and type.
+type Family = (&'static str, &'static str, &'static str);
+
+const CACHE_HITS:          Family = ("ironshield_cache_hits_total",          "Lookups answered from the cache.",             "counter");
+const CACHE_MISSES:        Family = ("ironshield_cache_misses_total",        "Lookups that required full verification.",    "counter");
+const SPOT_CHECKED:        Family = ("ironshield_spot_checked_total",        "Solutions whose hash was recomputed.",        "counter");
+const SPOT_CHECK_ACCEPTED: Family = ("ironshield_spot_check_accepted_total", "Solutions accepted, checked or not.",         "counter");
+const SPOT_CHECK_REJECTED: Family = ("ironshield_spot_check_rejected_total", "Spot-checked solutions that failed.",         "counter");
+
+/// # Arguments
+/// * `name`: A `ChallengeStats` counter name.
+///
+/// # Returns
+/// * `&'static str`: Its help text.
+fn challenge_help(name: &str) -> &'static str {
+    match name {
+        SIGNATURES_CREATED            => "Signatures created.",
+        CHALLENGE_SIGNATURES_VERIFIED => "Challenge signatures that verified.",
+        CHALLENGE_SIGNATURES_REJECTED => "Challenge signatures that did not verify.",
+        TOKEN_SIGNATURES_VERIFIED     => "Token signatures that verified.",
+        TOKEN_SIGNATURES_REJECTED     => "Token signatures that did not verify.",
+        CHALLENGES_VALIDATED          => "Challenges that passed validation.",
+        _                             => "Challenges that failed validation.",
+    }
+}
+
+/// Collects stats and renders them in the Prometheus text format.
+///
+/// Samples of one family are grouped under a single `# HELP` and
+/// `# TYPE` header however they were added, so several caches can be
+/// added under different names.
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusEncoder {
+    /// (family, `cache` label, value), in insertion order.
+    samples: Vec<(Family, Option<String>, u64)>,
+}
+
+impl PrometheusEncoder {
+    /// # Returns
+    /// * `Self`: An encoder without samples.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Arguments
+    /// * `stats`: The crate's counter totals.
+    ///
+    /// # Returns
+    /// * `Self`: The encoder with the counters added.
+    pub fn with_challenge_stats(mut self, stats: &ChallengeStats) -> Self {
+        for (name, value) in stats.counters() {
+            self.samples.push(((name, challenge_help(name), "counter"), None, value));
+        }
+        self
+    }
+
+    /// # Arguments
+    /// * `cache`: Name of the cache, used as the `cache` label.
+    /// * `stats`: The cache's counters.
+    ///
+    /// # Returns
+    /// * `Self`: The encoder with the counters added.
+    pub fn with_cache_stats(mut self, cache: &str, stats: &CacheStats) -> Self {
+        self.samples.push((CACHE_HITS,   Some(cache.to_string()), stats.hits));
+        self.samples.push((CACHE_MISSES, Some(cache.to_string()), stats.misses));
+        self
+    }
+
+    /// # Arguments
+    /// * `stats`: A `SpotChecker`'s counters.
+    ///
+    /// # Returns
+    /// * `Self`: The encoder with the counters added.
+    pub fn with_spot_check_stats(mut self, stats: &SpotCheckStats) -> Self {
+        self.samples.push((SPOT_CHECKED,        None, stats.spot_checked));
+        self.samples.push((SPOT_CHECK_ACCEPTED, None, stats.accepted));
+        self.samples.push((SPOT_CHECK_REJECTED, None, stats.rejected));
+        self
+    }
+
+    /// # Returns
+    /// * `String`: The samples in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut families: Vec<Family> = Vec::new();
+        for (family, _, _) in &self.samples {
+            if !families.contains(family) {
+                families.push(*family);
+            }
+        }
+
+        let mut out = String::new();
+        for family in families {
+            let (name, help, kind) = family;
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (_, cache, value) in self.samples.iter().filter(|(f, _, _)| *f == family) {
+                match cache {
+                    Some(cache) => { let _ = writeln!(out, "{}{{cache=\"{}\"}} {}", name, escape_label_value(cache), value); }
+                    None        => { let _ = writeln!(out, "{} {}", name, value); }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// # Arguments
+/// * `value`: A label value.
+///
+/// # Returns
+/// * `String`: `value` with `\`, `"` and newlines escaped.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_groups_families() {
+        let stats = ChallengeStats { challenges_validated: 5, challenges_rejected: 2, ..Default::default() };
+        let text: String = PrometheusEncoder::new()
+            .with_cache_stats("tokens", &CacheStats { hits: 3, misses: 1 })
+            .with_challenge_stats(&stats)
+            .with_cache_stats("edge \"a\"", &CacheStats { hits: 7, misses: 0 })
+            .with_spot_check_stats(&SpotCheckStats { spot_checked: 4, accepted: 10, rejected: 1 })
+            .encode();
+
+        assert!(text.starts_with(
+            "# HELP ironshield_cache_hits_total Lookups answered from the cache.\n\
+             # TYPE ironshield_cache_hits_total counter\n\
+             ironshield_cache_hits_total{cache=\"tokens\"} 3\n\
+             ironshield_cache_hits_total{cache=\"edge \\\"a\\\"\"} 7\n"
+        ));
+        assert_eq!(text.matches("# TYPE ironshield_cache_misses_total").count(), 1);
+        assert!(text.contains("\nironshield_challenges_validated_total 5\n"));
+        assert!(text.contains("\nironshield_challenges_rejected_total 2\n"));
+        assert!(text.ends_with("ironshield_spot_check_rejected_total 1\n"));
+        assert_eq!(PrometheusEncoder::new().encode(), "");
+    }
+}
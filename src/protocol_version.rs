@@ -0,0 +1,227 @@
+//! # Protocol version negotiation.
+//!
+//! WASM clients are cached in browsers long after an edge has been
+//! upgraded, so both sides send the protocol version they speak in the
+//! `X-IronShield-Version` header as `<major>.<minor>`, e.g. `1.2`. A
+//! request without the header is treated as `1.0`, the protocol before
+//! versioning.
+//!
+//! Minor versions only add optional fields and extensions, which older
+//! peers ignore. Major versions change the wire format. Compatibility
+//! of a client with a server therefore is:
+//!
+//! | Client vs. server        | `Compatibility` | Meaning                                          |
+//! |--------------------------|-----------------|--------------------------------------------------|
+//! | Same major, minor ≤      | `Full`          | Everything the client uses is understood.        |
+//! | Same major, minor >      | `Degraded`      | Works; the client's newer features are ignored.  |
+//! | Different major          | `Incompatible`  | The client must be reloaded or the edge upgraded. |
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Name of the header carrying the protocol version.
+pub const PROTOCOL_VERSION_HEADER: &str = "X-IronShield-Version";
+
+/// A protocol version.
+///
+/// * `major`: Incremented for wire-incompatible changes.
+/// * `minor`: Incremented for backwards-compatible additions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IronShieldProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+/// How well a client and a server understand each other.
+///
+/// * `Full`:         The server understands everything the client sends.
+/// * `Degraded`:     Same major version, but the client is newer; its
+///                   newer fields are ignored by the server.
+/// * `Incompatible`: Different major versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compatibility {
+    Full,
+    Degraded,
+    Incompatible,
+}
+
+impl IronShieldProtocolVersion {
+    /// The version implemented by this crate.
+    pub const CURRENT: Self = Self::new(1, 0);
+    /// The version assumed when the header is missing.
+    pub const LEGACY:  Self = Self::new(1, 0);
+
+    /// # Arguments
+    /// * `major`: The major version.
+    /// * `minor`: The minor version.
+    ///
+    /// # Returns
+    /// * `Self`: The version.
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// # Returns
+    /// * `String`: The header value, e.g. `"1.0"`.
+    pub fn to_header_value(&self) -> String {
+        format!("{}.{}", self.major, self.minor)
+    }
+
+    /// Parses an `X-IronShield-Version` header value. Both parts must be
+    /// decimal numbers without sign or leading zeros.
+    ///
+    /// # Arguments
+    /// * `value`: The header value, surrounding whitespace allowed.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The version, or an error if the value
+    ///                           is not `<major>.<minor>`.
+    pub fn from_header_value(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid protocol version: {}", value);
+        let parse_part = |part: &str| -> Result<u16, String> {
+            let canonical: bool = !part.is_empty()
+                && part.bytes().all(|byte| byte.is_ascii_digit())
+                && (part == "0" || !part.starts_with('0'));
+            if !canonical {
+                return Err(invalid());
+            }
+            part.parse().map_err(|_| invalid())
+        };
+
+        let (major, minor) = value.trim().split_once('.').ok_or_else(invalid)?;
+        Ok(Self::new(parse_part(major)?, parse_part(minor)?))
+    }
+
+    /// Same as `from_header_value`, treating a missing header as
+    /// `LEGACY`.
+    ///
+    /// # Arguments
+    /// * `value`: The header value, if the header was sent.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The version, or an error if a header
+    ///                           was sent but is malformed.
+    pub fn from_optional_header(value: Option<&str>) -> Result<Self, String> {
+        value.map_or(Ok(Self::LEGACY), Self::from_header_value)
+    }
+
+    /// Looks up the compatibility matrix in the module documentation.
+    ///
+    /// # Arguments
+    /// * `client`: The version sent by the client.
+    /// * `server`: The version the server speaks.
+    ///
+    /// # Returns
+    /// * `Compatibility`: How well they understand each other.
+    pub fn compatibility(client: Self, server: Self) -> Compatibility {
+        if client.major != server.major {
+            Compatibility::Incompatible
+        } else if client.minor <= server.minor {
+            Compatibility::Full
+        } else {
+            Compatibility::Degraded
+        }
+    }
+
+    /// # Arguments
+    /// * `client`: The version sent by the client.
+    /// * `server`: The version the server speaks.
+    ///
+    /// # Returns
+    /// * `bool`: `true` unless the major versions differ.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::IronShieldProtocolVersion as Version;
+    /// assert!(Version::is_compatible(Version::new(1, 3), Version::new(1, 1)));
+    /// assert!(!Version::is_compatible(Version::new(2, 0), Version::new(1, 9)));
+    /// ```
+    pub fn is_compatible(client: Self, server: Self) -> bool {
+        Self::compatibility(client, server) != Compatibility::Incompatible
+    }
+
+    /// # Arguments
+    /// * `client`: The version sent by the client.
+    /// * `server`: The version the server speaks.
+    ///
+    /// # Returns
+    /// * `Option<Self>`: The version both sides should speak, the older
+    ///                   of the two, or `None` if they are incompatible.
+    pub fn negotiate(client: Self, server: Self) -> Option<Self> {
+        Self::is_compatible(client, server).then(|| client.min(server))
+    }
+}
+
+impl Default for IronShieldProtocolVersion {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
+
+impl std::fmt::Display for IronShieldProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl std::str::FromStr for IronShieldProtocolVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_header_value(s)
+    }
+}
+
+impl TryFrom<String> for IronShieldProtocolVersion {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_header_value(&value)
+    }
+}
+
+impl From<IronShieldProtocolVersion> for String {
+    fn from(version: IronShieldProtocolVersion) -> Self {
+        version.to_header_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let version = IronShieldProtocolVersion::new(1, 12);
+        assert_eq!(version.to_header_value(), "1.12");
+        assert_eq!(IronShieldProtocolVersion::from_header_value(" 1.12 "), Ok(version));
+        assert_eq!(IronShieldProtocolVersion::from_optional_header(None), Ok(IronShieldProtocolVersion::LEGACY));
+        assert_eq!(serde_json::to_string(&version).unwrap(), r#""1.12""#);
+
+        for invalid in ["", "1", "1.", ".1", "01.0", "1.+2", "1.2.3", "70000.0", "v1.0"] {
+            assert!(IronShieldProtocolVersion::from_header_value(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_compatibility_matrix() {
+        let server = IronShieldProtocolVersion::new(1, 2);
+        let cases = [
+            ((1, 1), Compatibility::Full),
+            ((1, 2), Compatibility::Full),
+            ((1, 3), Compatibility::Degraded),
+            ((0, 9), Compatibility::Incompatible),
+            ((2, 0), Compatibility::Incompatible),
+        ];
+        for ((major, minor), expected) in cases {
+            let client = IronShieldProtocolVersion::new(major, minor);
+            assert_eq!(IronShieldProtocolVersion::compatibility(client, server), expected);
+        }
+
+        assert_eq!(IronShieldProtocolVersion::negotiate(IronShieldProtocolVersion::new(1, 3), server), Some(server));
+        assert_eq!(IronShieldProtocolVersion::negotiate(IronShieldProtocolVersion::new(2, 0), server), None);
+    }
+}
@@ -0,0 +1,287 @@
+//! # PyO3 bindings for replaying traffic in Python.
+//!
+//! Exposes the challenge, response and token types and their
+//! verification helpers as the `ironshield_types` Python module.
+//! Byte arrays are passed as `bytes` and length-checked on the way in.
+//! Malformed input raises `ValueError`; failed signature or solution
+//! checks raise `ironshield_types.VerificationError`.
+
+use pyo3::create_exception;
+use pyo3::exceptions::{
+    PyException,
+    PyValueError
+};
+use pyo3::prelude::*;
+
+use crate::{
+    verify_challenge_signature_with_key,
+    verify_token_signature_with_key,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    IronShieldToken,
+    SigningKey,
+    WebsiteId
+};
+
+create_exception!(ironshield_types, VerificationError, PyException);
+
+fn value_error(message: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(message.to_string())
+}
+
+fn verification_error(message: impl std::fmt::Display) -> PyErr {
+    VerificationError::new_err(message.to_string())
+}
+
+fn array_from_bytes<const N: usize>(bytes: &[u8], name: &str) -> PyResult<[u8; N]> {
+    bytes.try_into().map_err(|_| value_error(format!("{} must be exactly {} bytes", name, N)))
+}
+
+/// Python wrapper for `IronShieldChallenge`.
+#[pyclass(name = "IronShieldChallenge", module = "ironshield_types")]
+#[derive(Clone)]
+pub struct PyIronShieldChallenge {
+    inner: IronShieldChallenge,
+}
+
+#[pymethods]
+impl PyIronShieldChallenge {
+    /// Creates and signs a new challenge.
+    ///
+    /// # Arguments
+    /// * `website_id`:  The identifier of the website, signed as given.
+    /// * `difficulty`:  Expected number of attempts.
+    /// * `private_key`: 32-byte Ed25519 private key.
+    #[new]
+    fn new(website_id: String, difficulty: u64, private_key: &[u8]) -> PyResult<Self> {
+        let website_id: WebsiteId = WebsiteId::verbatim(website_id).map_err(value_error)?;
+        let signing_key = SigningKey::from_bytes(&array_from_bytes::<32>(private_key, "private_key")?);
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        Ok(Self { inner: IronShieldChallenge::new(website_id, difficulty, signing_key, public_key) })
+    }
+
+    /// Decodes a challenge from a Base64 URL-safe header value.
+    #[staticmethod]
+    fn from_base64url_header(header: &str) -> PyResult<Self> {
+        let inner: IronShieldChallenge = IronShieldChallenge::from_base64url_header(header).map_err(value_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Parses a challenge from its JSON representation.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let inner: IronShieldChallenge = serde_json::from_str(json).map_err(value_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Serializes the challenge to JSON.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner).map_err(value_error)
+    }
+
+    /// Encodes the challenge as a Base64 URL-safe header value.
+    fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    #[getter]
+    fn random_nonce(&self) -> String {
+        self.inner.random_nonce.to_string()
+    }
+
+    #[getter]
+    fn created_time(&self) -> i64 {
+        self.inner.created_time
+    }
+
+    #[getter]
+    fn expiration_time(&self) -> i64 {
+        self.inner.expiration_time
+    }
+
+    #[getter]
+    fn website_id(&self) -> String {
+        self.inner.website_id.to_string()
+    }
+
+    #[getter]
+    fn challenge_param(&self) -> Vec<u8> {
+        self.inner.challenge_param.to_vec()
+    }
+
+    #[getter]
+    fn public_key(&self) -> Vec<u8> {
+        self.inner.public_key.to_vec()
+    }
+
+    /// `True` if the challenge has expired.
+    fn is_expired(&self) -> bool {
+        self.inner.is_expired()
+    }
+
+    /// Verifies the challenge signature against a 32-byte Ed25519 key.
+    fn verify_signature(&self, public_key: &[u8]) -> PyResult<()> {
+        let public_key: [u8; 32] = array_from_bytes(public_key, "public_key")?;
+        verify_challenge_signature_with_key(&self.inner, &public_key).map_err(verification_error)
+    }
+
+    /// Checks a proof-of-work solution against the challenge.
+    fn check_solution(&self, nonce: i64) -> PyResult<()> {
+        self.inner.check_solution(nonce).map_err(verification_error)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("IronShieldChallenge(website_id={:?}, random_nonce={:?})", self.inner.website_id, self.inner.random_nonce.as_str())
+    }
+}
+
+/// Python wrapper for `IronShieldChallengeResponse`.
+#[pyclass(name = "IronShieldChallengeResponse", module = "ironshield_types")]
+pub struct PyIronShieldChallengeResponse {
+    inner: IronShieldChallengeResponse,
+}
+
+#[pymethods]
+impl PyIronShieldChallengeResponse {
+    /// Creates a response for a solved challenge.
+    #[new]
+    fn new(challenge: PyRef<'_, PyIronShieldChallenge>, solution: i64) -> Self {
+        Self { inner: IronShieldChallengeResponse::new(challenge.inner.clone(), solution) }
+    }
+
+    /// Decodes a response from a Base64 URL-safe header value.
+    #[staticmethod]
+    fn from_base64url_header(header: &str) -> PyResult<Self> {
+        let inner: IronShieldChallengeResponse = IronShieldChallengeResponse::from_base64url_header(header).map_err(value_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Serializes the response to JSON.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner).map_err(value_error)
+    }
+
+    /// Encodes the response as a Base64 URL-safe header value.
+    fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    #[getter]
+    fn solved_challenge(&self) -> PyIronShieldChallenge {
+        PyIronShieldChallenge { inner: self.inner.solved_challenge.clone() }
+    }
+
+    #[getter]
+    fn solution(&self) -> i64 {
+        self.inner.solution
+    }
+
+    /// Checks that the solution solves the challenge.
+    fn verify_solution(&self) -> PyResult<()> {
+        self.inner.verify_solution().map_err(verification_error)
+    }
+}
+
+/// Python wrapper for `IronShieldToken`.
+#[pyclass(name = "IronShieldToken", module = "ironshield_types")]
+pub struct PyIronShieldToken {
+    inner: IronShieldToken,
+}
+
+#[pymethods]
+impl PyIronShieldToken {
+    /// Decodes a token from a Base64 URL-safe header value.
+    #[staticmethod]
+    fn from_base64url_header(header: &str) -> PyResult<Self> {
+        let inner: IronShieldToken = IronShieldToken::from_base64url_header(header).map_err(value_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Parses a token from its JSON representation.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let inner: IronShieldToken = serde_json::from_str(json).map_err(value_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Serializes the token to JSON.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner).map_err(value_error)
+    }
+
+    /// Encodes the token as a Base64 URL-safe header value.
+    fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    #[getter]
+    fn valid_for(&self) -> i64 {
+        self.inner.valid_for
+    }
+
+    /// `True` if the token has expired.
+    fn is_expired(&self) -> bool {
+        self.inner.is_expired()
+    }
+
+    /// Verifies the token signature against a 32-byte Ed25519 key.
+    fn verify_signature(&self, public_key: &[u8]) -> PyResult<()> {
+        let public_key: [u8; 32] = array_from_bytes(public_key, "public_key")?;
+        verify_token_signature_with_key(&self.inner, &public_key).map_err(verification_error)
+    }
+}
+
+/// The `ironshield_types` Python module.
+#[pymodule]
+fn ironshield_types(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyIronShieldChallenge>()?;
+    m.add_class::<PyIronShieldChallengeResponse>()?;
+    m.add_class::<PyIronShieldToken>()?;
+    m.add("VerificationError", m.py().get_type::<VerificationError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_construct_and_verify_from_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "ironshield_types").unwrap();
+            ironshield_types(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("ist", module).unwrap();
+
+            let script = c"
+key = bytes([3] * 32)
+challenge = ist.IronShieldChallenge('example.com', 16, key)
+decoded = ist.IronShieldChallenge.from_base64url_header(challenge.to_base64url_header())
+solution = next(n for n in range(10000) if not _fails(decoded, n))
+response = ist.IronShieldChallengeResponse(decoded, solution)
+response.verify_solution()
+try:
+    decoded.verify_signature(bytes(32))
+    raised = False
+except ist.VerificationError:
+    raised = True
+";
+            let prelude = c"
+def _fails(challenge, nonce):
+    try:
+        challenge.check_solution(nonce)
+        return False
+    except ist.VerificationError:
+        return True
+";
+            py.run(prelude, Some(&globals), None).unwrap();
+            py.run(script, Some(&globals), None).unwrap();
+            assert!(globals.get_item("raised").unwrap().unwrap().extract::<bool>().unwrap());
+
+            let err = py.run(c"ist.IronShieldToken.from_base64url_header('bad')", Some(&globals), None).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
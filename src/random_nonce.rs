@@ -0,0 +1,172 @@
+//! # Typed challenge nonces.
+//!
+//! `random_nonce` and `website_id` used to both be `String`, so assigning
+//! one to the other compiled. `RandomNonce` gives the nonce its own type:
+//! it can only be built from 1 to `MAX_RANDOM_NONCE_LEN` hex digits, holds
+//! them exactly as issued and signed, and serializes as that string, so
+//! the wire format is unchanged. It derefs to `str` for reading; it can
+//! only be built through the validating constructors.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Maximum length of a random nonce in hex digits.
+pub(crate) const MAX_RANDOM_NONCE_LEN: usize = 128;
+
+/// Hex-encoded random nonce of a challenge.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    value_type = String,
+    description = "Hex-encoded random nonce"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RandomNonce(String);
+
+impl RandomNonce {
+    /// Checks that `value` is 1 to `MAX_RANDOM_NONCE_LEN` hex digits.
+    pub(crate) fn validate_str(value: &str) -> Result<(), String> {
+        if value.is_empty() || value.len() > MAX_RANDOM_NONCE_LEN {
+            return Err(format!("random_nonce must be 1 to {} hex digits", MAX_RANDOM_NONCE_LEN));
+        }
+        if !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err("random_nonce must be hex".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Arguments
+    /// * `bytes`: The raw nonce bytes, 1 to `MAX_RANDOM_NONCE_LEN / 2`
+    ///            of them; other lengths do not compile.
+    ///
+    /// # Returns
+    /// * `Self`: The nonce as lowercase hex.
+    pub fn from_bytes<const N: usize>(bytes: &[u8; N]) -> Self {
+        const { assert!(N > 0 && 2 * N <= MAX_RANDOM_NONCE_LEN) };
+        Self(hex::encode(bytes))
+    }
+
+    /// # Returns
+    /// * `Result<Vec<u8>, String>`: The decoded bytes, or an error if
+    ///                              the nonce has an odd length.
+    pub fn decode(&self) -> Result<Vec<u8>, String> {
+        hex::decode(&self.0).map_err(|_| "Failed to decode random_nonce hex string".to_string())
+    }
+
+    /// # Returns
+    /// * `&str`: The hex string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// # Returns
+    /// * `String`: The hex string, consuming `self`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl AsRef<str> for RandomNonce {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for RandomNonce {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RandomNonce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for RandomNonce {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl TryFrom<String> for RandomNonce {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::validate_str(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl TryFrom<&str> for RandomNonce {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::validate_str(value)?;
+        Ok(Self(value.to_string()))
+    }
+}
+
+impl From<RandomNonce> for String {
+    fn from(nonce: RandomNonce) -> Self {
+        nonce.0
+    }
+}
+
+impl PartialEq<str> for RandomNonce {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for RandomNonce {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for RandomNonce {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<RandomNonce> for String {
+    fn eq(&self, other: &RandomNonce) -> bool {
+        *self == other.0
+    }
+}
+
+impl PartialEq<RandomNonce> for str {
+    fn eq(&self, other: &RandomNonce) -> bool {
+        self == other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_nonce_is_a_validated_string() {
+        let nonce = RandomNonce::from_bytes(&[0xab, 0x01]);
+        assert_eq!(nonce, "ab01");
+        assert_eq!(nonce.decode().unwrap(), vec![0xab, 0x01]);
+        assert_eq!(nonce.len(), 4);
+        assert_eq!(serde_json::to_string(&nonce).unwrap(), r#""ab01""#);
+        assert_eq!(serde_json::from_str::<RandomNonce>(r#""AB01""#).unwrap().as_str(), "AB01");
+
+        assert!(RandomNonce::try_from("").is_err());
+        assert!(RandomNonce::try_from("xyz").unwrap_err().contains("hex"));
+        assert!(RandomNonce::try_from("a".repeat(MAX_RANDOM_NONCE_LEN + 1)).is_err());
+        assert!(serde_json::from_str::<RandomNonce>(r#""not hex""#).is_err());
+        assert!("abc".parse::<RandomNonce>().unwrap().decode().is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! # Redis-backed stores for self-hosted deployments.
+//!
+//! `RedisChallengeStore` and `RedisTokenConsumptionStore` give servers
+//! outside Cloudflare replay protection over any redis connection
+//! (`redis::Connection`, a cluster connection, a pooled one, ...).
+//! Keys expire through redis itself (`PXAT`, redis 6.2 or newer), so
+//! `purge_expired` has nothing to do. Single use is enforced atomically
+//! by the server: challenges are redeemed with `GETDEL` and tokens are
+//! consumed with `SET ... NX`.
+//!
+//! Challenges are stored as `ChallengeKvEntry` records, the same layout
+//! the key-value adapters use.
+
+use std::sync::{
+    Mutex,
+    MutexGuard
+};
+
+use redis::ConnectionLike;
+
+use crate::{
+    ChallengeKvEntry,
+    ChallengeStore,
+    IronShieldChallenge,
+    IronShieldToken,
+    TokenConsumptionStore,
+    challenge_kv_key
+};
+
+/// Prefix of the keys under which consumed tokens are recorded.
+pub const TOKEN_KV_PREFIX: &str = "token:";
+
+fn redis_error(error: redis::RedisError) -> String {
+    format!("Redis error: {}", error)
+}
+
+fn lock<C>(connection: &Mutex<C>) -> Result<MutexGuard<'_, C>, String> {
+    connection.lock().map_err(|_| "Redis connection lock poisoned".to_string())
+}
+
+/// `ChallengeStore` over a redis connection.
+///
+/// The connection is behind a mutex so lookups work through `&self`.
+#[derive(Debug)]
+pub struct RedisChallengeStore<C> {
+    connection: Mutex<C>,
+}
+
+impl<C: ConnectionLike> RedisChallengeStore<C> {
+    /// # Arguments
+    /// * `connection`: An open redis connection.
+    pub fn new(connection: C) -> Self {
+        Self {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    /// # Returns
+    /// * `C`: The wrapped connection.
+    pub fn into_inner(self) -> C {
+        self.connection.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<C: ConnectionLike> ChallengeStore for RedisChallengeStore<C> {
+    fn put(&mut self, challenge: IronShieldChallenge) -> Result<[u8; 32], String> {
+        let entry: ChallengeKvEntry = ChallengeKvEntry::from_challenge(&challenge)?;
+        redis::cmd("SET")
+            .arg(&entry.key)
+            .arg(&entry.value)
+            // PXAT deletes the key at that instant; the challenge is
+            // still redeemable at exactly `expires_at`.
+            .arg("PXAT")
+            .arg(entry.expires_at.saturating_add(1))
+            .query::<()>(&mut *lock(&self.connection)?)
+            .map_err(redis_error)?;
+        Ok(challenge.challenge_id())
+    }
+
+    fn get_by_id(&self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        let value: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(challenge_kv_key(id))
+            .query(&mut *lock(&self.connection)?)
+            .map_err(redis_error)?;
+        value.map_or(Ok(None), |value| ChallengeKvEntry::decode_at(&value, now))
+    }
+
+    fn take(&mut self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        let value: Option<Vec<u8>> = redis::cmd("GETDEL")
+            .arg(challenge_kv_key(id))
+            .query(&mut *lock(&self.connection)?)
+            .map_err(redis_error)?;
+        value.map_or(Ok(None), |value| ChallengeKvEntry::decode_at(&value, now))
+    }
+
+    fn purge_expired(&mut self, _now: i64) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+/// `TokenConsumptionStore` over a redis connection.
+#[derive(Debug)]
+pub struct RedisTokenConsumptionStore<C> {
+    connection: Mutex<C>,
+}
+
+impl<C: ConnectionLike> RedisTokenConsumptionStore<C> {
+    /// # Arguments
+    /// * `connection`: An open redis connection.
+    pub fn new(connection: C) -> Self {
+        Self {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    /// # Returns
+    /// * `C`: The wrapped connection.
+    pub fn into_inner(self) -> C {
+        self.connection.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<C: ConnectionLike> TokenConsumptionStore for RedisTokenConsumptionStore<C> {
+    fn consume(&mut self, token: &IronShieldToken, now: i64) -> Result<bool, String> {
+        if now > token.valid_for {
+            return Ok(false);
+        }
+        let stored: Option<String> = redis::cmd("SET")
+            .arg(format!("{}{}", TOKEN_KV_PREFIX, hex::encode(token.jti())))
+            .arg(1)
+            .arg("NX")
+            .arg("PXAT")
+            .arg(token.valid_for.saturating_add(1))
+            .query(&mut *lock(&self.connection)?)
+            .map_err(redis_error)?;
+        Ok(stored.is_some())
+    }
+
+    fn purge_expired(&mut self, _now: i64) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+    use redis::{
+        Cmd,
+        RedisResult,
+        Value
+    };
+    use std::collections::HashMap;
+
+    /// Just enough of redis for the commands above; expiry is ignored.
+    #[derive(Default)]
+    struct FakeRedis {
+        data: HashMap<Vec<u8>, Vec<u8>>,
+    }
+
+    impl ConnectionLike for FakeRedis {
+        fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+            let args: Vec<Vec<u8>> = cmd.args_iter()
+                .map(|arg| match arg {
+                    redis::Arg::Simple(bytes) => bytes.to_vec(),
+                    redis::Arg::Cursor => Vec::new(),
+                })
+                .collect();
+            let found = |value: Option<Vec<u8>>| value.map_or(Value::Nil, Value::BulkString);
+
+            Ok(match args[0].as_slice() {
+                b"GET"    => found(self.data.get(&args[1]).cloned()),
+                b"GETDEL" => found(self.data.remove(&args[1])),
+                b"SET" if args.contains(&b"NX".to_vec()) && self.data.contains_key(&args[1]) => Value::Nil,
+                b"SET"    => {
+                    self.data.insert(args[1].clone(), args[2].clone());
+                    Value::Okay
+                }
+                other => panic!("unexpected command {:?}", String::from_utf8_lossy(other)),
+            })
+        }
+
+        fn req_packed_command(&mut self, _cmd: &[u8]) -> RedisResult<Value> {
+            unimplemented!()
+        }
+
+        fn req_packed_commands(&mut self, _cmd: &[u8], _offset: usize, _count: usize) -> RedisResult<Vec<Value>> {
+            unimplemented!()
+        }
+
+        fn get_db(&self) -> i64 {
+            0
+        }
+
+        fn check_connection(&mut self) -> bool {
+            true
+        }
+
+        fn is_open(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_challenge_store_over_redis() {
+        let key = SigningKey::from_bytes(&[2; 32]);
+        let mut challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key, [0x34; 32]);
+        challenge.expiration_time = 5_000;
+
+        let mut store = RedisChallengeStore::new(FakeRedis::default());
+        let id: [u8; 32] = store.put(challenge.clone()).unwrap();
+
+        assert_eq!(store.get_by_id(&id, 5_001).unwrap(), None);
+        assert_eq!(store.get_by_id(&id, 2_000).unwrap(), Some(challenge.clone()));
+        assert_eq!(store.take(&id, 2_000).unwrap(), Some(challenge));
+        assert_eq!(store.take(&id, 2_000).unwrap(), None);
+        assert!(store.into_inner().data.is_empty());
+    }
+
+    #[test]
+    fn test_token_consumption_over_redis() {
+        let key = SigningKey::from_bytes(&[2; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), [0x34; 32]);
+        let mut token = crate::issue_token(&challenge, &key, &crate::LifetimePolicy::default()).unwrap();
+        token.valid_for = 10_000;
+
+        let mut store = RedisTokenConsumptionStore::new(FakeRedis::default());
+        assert!(!store.consume(&token, 10_001).unwrap());
+        assert!(store.consume(&token, 2_000).unwrap());
+        assert!(!store.consume(&token, 2_000).unwrap());
+    }
+}
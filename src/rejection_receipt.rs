@@ -0,0 +1,170 @@
+//! # Signed proof that the edge rejected a solution.
+//!
+//! A `RejectionReceipt` records which challenge was rejected, why and
+//! when, signed with the edge key. Clients can show it to support
+//! staff, who can verify it against the published key instead of
+//! trusting a screenshot of an error page. Receipts are issued with
+//! `crypto::issue_rejection_receipt`.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_signature,
+    serialize_32_bytes,
+    serialize_signature
+};
+use crate::ErrorCode;
+
+/// Domain separation prefix of the receipt signing message.
+const RECEIPT_SIGNING_CONTEXT: &str = "ironshield-rejection-v1";
+
+/// Signed record of a rejected solution.
+///
+/// * `challenge_id`: `challenge_id()` of the rejected challenge.
+/// * `reason`:       Why the solution was rejected.
+/// * `rejected_at`:  Unix milli timestamp of the rejection.
+/// * `public_key`:   Ed25519 public key of the edge.
+/// * `signature`:    Ed25519 signature over `signing_message()`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectionReceipt {
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub challenge_id: [u8; 32],
+    pub reason:       ErrorCode,
+    pub rejected_at:  i64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub public_key:   [u8; 32],
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature:    [u8; 64],
+}
+
+impl RejectionReceipt {
+    /// Creates the message covered by `signature`.
+    ///
+    /// # Returns
+    /// * `String`: Canonical string representation for signing.
+    pub fn signing_message(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}",
+            RECEIPT_SIGNING_CONTEXT,
+            hex::encode(self.challenge_id),
+            self.reason,
+            self.rejected_at,
+            hex::encode(self.public_key)
+        )
+    }
+
+    /// Concatenates the receipt data into a string.
+    ///
+    /// Concatenates:
+    /// * `challenge_id`: as a lowercase hex string.
+    /// * `reason`:       as its wire identifier.
+    /// * `rejected_at`:  as `i64`.
+    /// * `public_key`:   as a lowercase hex string.
+    /// * `signature`:    as a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}",
+            hex::encode(self.challenge_id),
+            self.reason,
+            self.rejected_at,
+            hex::encode(self.public_key),
+            hex::encode(self.signature)
+        )
+    }
+
+    /// Creates a `RejectionReceipt` from a concatenated string.
+    ///
+    /// # Arguments
+    /// * `concat_str`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed receipt or an error message.
+    pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = concat_str.split('|').collect();
+        if parts.len() != 5 {
+            return Err(format!("Expected 5 parts, got {}", parts.len()));
+        }
+
+        let mut challenge_id: [u8; 32] = [0u8; 32];
+        hex::decode_to_slice(parts[0], &mut challenge_id)
+            .map_err(|_| "Challenge id must be 32 bytes of hex")?;
+        let mut public_key: [u8; 32] = [0u8; 32];
+        hex::decode_to_slice(parts[3], &mut public_key)
+            .map_err(|_| "Public key must be 32 bytes of hex")?;
+        let mut signature: [u8; 64] = [0u8; 64];
+        hex::decode_to_slice(parts[4], &mut signature)
+            .map_err(|_| "Signature must be 64 bytes of hex")?;
+
+        Ok(Self {
+            challenge_id,
+            reason:      parts[1].parse()?,
+            rejected_at: parts[2].parse::<i64>().map_err(|_| "Failed to parse rejected_at as i64")?,
+            public_key,
+            signature,
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The receipt, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded receipt or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        issue_rejection_receipt,
+        verify_rejection_receipt_with_key,
+        IronShieldChallenge,
+        SigningKey
+    };
+
+    #[test]
+    fn test_issue_and_verify() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), public_key);
+
+        let receipt = issue_rejection_receipt(&challenge, ErrorCode::InvalidSolution, &key).unwrap();
+        assert_eq!(receipt.challenge_id, challenge.challenge_id());
+        verify_rejection_receipt_with_key(&receipt, &public_key).unwrap();
+
+        let decoded = RejectionReceipt::from_base64url_header(&receipt.to_base64url_header()).unwrap();
+        assert_eq!(decoded, receipt);
+        let json: String = serde_json::to_string(&receipt).unwrap();
+        assert!(json.contains(r#""reason":"invalid_solution""#));
+        assert_eq!(serde_json::from_str::<RejectionReceipt>(&json).unwrap(), receipt);
+
+        let altered = RejectionReceipt { reason: ErrorCode::Expired, ..receipt.clone() };
+        assert!(verify_rejection_receipt_with_key(&altered, &public_key).is_err());
+        let other_key: [u8; 32] = SigningKey::from_bytes(&[7; 32]).verifying_key().to_bytes();
+        assert!(verify_rejection_receipt_with_key(&receipt, &other_key).is_err());
+        assert!(RejectionReceipt::from_concat_struct("00|expired|1").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! # Request descriptors for binding challenges to a specific request.
+//!
+//! A challenge fetched ahead of time could otherwise be solved once and
+//! redeemed against any endpoint. Committing the hash of a
+//! `RequestDescriptor` into the signed challenge ties the solution to the
+//! request (method, URL and optionally body) it was issued for.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::serde_utils::{
+    deserialize_optional_32_bytes,
+    serialize_optional_32_bytes
+};
+
+#[cfg(feature = "openapi")]
+#[allow(unused_imports)]
+use serde_json::json;
+
+/// Normalized description of the HTTP request a challenge is bound to.
+///
+/// * `method`:      Uppercase HTTP method.
+/// * `url`:         Normalized absolute URL (lowercase scheme and host,
+///                  default port and fragment removed, `/` for an empty path).
+/// * `body_sha256`: Optional SHA-256 digest of the request body.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Normalized HTTP request description whose hash can be committed into a challenge"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestDescriptor {
+    /// Uppercase HTTP method
+    #[cfg_attr(feature = "openapi", schema(example = "POST"))]
+    pub method:      String,
+    /// Normalized absolute URL of the protected request
+    #[cfg_attr(feature = "openapi", schema(example = "https://example.com/api/login?next=%2F"))]
+    pub url:         String,
+    /// SHA-256 digest of the request body (32 bytes), if bound
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_32_bytes",
+        deserialize_with = "deserialize_optional_32_bytes"
+    )]
+    pub body_sha256: Option<[u8; 32]>,
+}
+
+impl RequestDescriptor {
+    /// Constructor for creating a new `RequestDescriptor` from raw
+    /// request components.
+    ///
+    /// # Arguments
+    /// * `method`: The HTTP method (any case).
+    /// * `url`:    The absolute request URL.
+    /// * `body`:   The request body to bind, if any.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The normalized descriptor, or an error
+    ///                           if the method or URL is invalid.
+    pub fn new(
+        method: &str,
+        url:    &str,
+        body:   Option<&[u8]>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            method:      Self::normalize_method(method)?,
+            url:         Self::normalize_url(url)?,
+            body_sha256: body.map(|bytes| Sha256::digest(bytes).into()),
+        })
+    }
+
+    /// Normalizes an HTTP method to its uppercase token form.
+    ///
+    /// # Arguments
+    /// * `method`: The HTTP method.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The uppercase method or an error
+    ///                             if it is not a valid token.
+    pub fn normalize_method(method: &str) -> Result<String, String> {
+        let method: &str = method.trim();
+        if method.is_empty() {
+            return Err("HTTP method cannot be empty".to_string());
+        }
+        if !method.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!("Invalid HTTP method: {}", method));
+        }
+        Ok(method.to_ascii_uppercase())
+    }
+
+    /// Normalizes an absolute URL so equivalent spellings hash the same.
+    ///
+    /// * The scheme and host are lowercased.
+    /// * Default ports (`:80` for http, `:443` for https) are removed.
+    /// * The fragment is dropped, since it is never sent to the server.
+    /// * An empty path becomes `/`.
+    /// * Path and query are otherwise kept byte-for-byte.
+    ///
+    /// # Arguments
+    /// * `url`: The absolute URL to normalize.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The normalized URL or an error if the
+    ///                             URL is not an absolute http(s) URL.
+    pub fn normalize_url(url: &str) -> Result<String, String> {
+        let url: &str = url.trim();
+        let without_fragment: &str = url.split('#').next().unwrap_or_default();
+
+        let (scheme, rest) = without_fragment.split_once("://")
+            .ok_or_else(|| format!("URL must be absolute: {}", url))?;
+        let scheme: String = scheme.to_ascii_lowercase();
+        if scheme != "http" && scheme != "https" {
+            return Err(format!("Unsupported URL scheme: {}", scheme));
+        }
+
+        let authority_end: usize = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path_and_query) = rest.split_at(authority_end);
+        if authority.is_empty() {
+            return Err(format!("URL has no host: {}", url));
+        }
+
+        let mut host: String = authority.to_ascii_lowercase();
+        let default_port: &str = if scheme == "https" { ":443" } else { ":80" };
+        if host.ends_with(default_port) {
+            host.truncate(host.len() - default_port.len());
+        }
+
+        let path_and_query: String = if path_and_query.starts_with('/') {
+            path_and_query.to_string()
+        } else {
+            format!("/{}", path_and_query)
+        };
+
+        Ok(format!("{}://{}{}", scheme, host, path_and_query))
+    }
+
+    /// Canonical string the descriptor hash is computed over.
+    ///
+    /// Format: "METHOD|url|body_sha256_hex" (empty body digest when
+    /// no body is bound).
+    ///
+    /// # Returns
+    /// * `String`: The canonical representation.
+    pub fn canonical_string(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.method,
+            self.url,
+            self.body_sha256.map(hex::encode).unwrap_or_default()
+        )
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: SHA-256 of `canonical_string()`, the value committed
+    ///               into a bound challenge.
+    pub fn hash(&self) -> [u8; 32] {
+        Sha256::digest(self.canonical_string().as_bytes()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_normalization() {
+        assert_eq!(
+            RequestDescriptor::normalize_url("HTTPS://Example.COM:443/Path?q=1#frag").unwrap(),
+            "https://example.com/Path?q=1"
+        );
+        assert_eq!(
+            RequestDescriptor::normalize_url("http://example.com:80").unwrap(),
+            "http://example.com/"
+        );
+        assert_eq!(
+            RequestDescriptor::normalize_url("https://example.com?x=y").unwrap(),
+            "https://example.com/?x=y"
+        );
+        assert_eq!(
+            RequestDescriptor::normalize_url("https://example.com:8443/").unwrap(),
+            "https://example.com:8443/"
+        );
+    }
+
+    #[test]
+    fn test_url_normalization_errors() {
+        assert!(RequestDescriptor::normalize_url("/relative").unwrap_err().contains("absolute"));
+        assert!(RequestDescriptor::normalize_url("ftp://example.com").unwrap_err().contains("Unsupported URL scheme"));
+        assert!(RequestDescriptor::normalize_url("https:///path").unwrap_err().contains("no host"));
+    }
+
+    #[test]
+    fn test_method_normalization() {
+        assert_eq!(RequestDescriptor::normalize_method(" post ").unwrap(), "POST");
+        assert!(RequestDescriptor::normalize_method("").is_err());
+        assert!(RequestDescriptor::normalize_method("GET|POST").is_err());
+    }
+
+    #[test]
+    fn test_equivalent_requests_hash_identically() {
+        let a = RequestDescriptor::new("post", "https://EXAMPLE.com:443/login#top", Some(b"user=a")).unwrap();
+        let b = RequestDescriptor::new("POST", "https://example.com/login", Some(b"user=a")).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.hash(), b.hash());
+    }
+
+    #[test]
+    fn test_distinct_requests_hash_differently() {
+        let base = RequestDescriptor::new("POST", "https://example.com/login", Some(b"user=a")).unwrap();
+        let other_body = RequestDescriptor::new("POST", "https://example.com/login", Some(b"user=b")).unwrap();
+        let no_body = RequestDescriptor::new("POST", "https://example.com/login", None).unwrap();
+        let other_method = RequestDescriptor::new("PUT", "https://example.com/login", Some(b"user=a")).unwrap();
+
+        assert_ne!(base.hash(), other_body.hash());
+        assert_ne!(base.hash(), no_body.hash());
+        assert_ne!(base.hash(), other_method.hash());
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let descriptor = RequestDescriptor::new("POST", "https://example.com/login", Some(b"body")).unwrap();
+        let json: String = serde_json::to_string(&descriptor).unwrap();
+        let parsed: RequestDescriptor = serde_json::from_str(&json).unwrap();
+        assert_eq!(descriptor, parsed);
+    }
+}
@@ -3,12 +3,107 @@ use serde::{
     Serialize
 };
 
+use crate::trace::traced;
+use crate::compact::{
+    CompactReader,
+    CompactWriter
+};
 use crate::IronShieldChallenge;
 
+/// Prefix of the metadata part in the concatenated format.
+const METADATA_PREFIX:        &str = "sm=";
+/// Maximum length of `SolutionMetadata::solver_version`.
+const MAX_SOLVER_VERSION_LEN: usize = 64;
+
+/// Solver telemetry reported alongside a solution, so difficulty
+/// tuning can be based on real-world solve times.
+///
+/// The values are self-reported by the client and unsigned; use them
+/// for statistics only, never for access decisions.
+///
+/// * `solver_version`: Name and version of the solver, e.g. `"wasm-1.4.0"`.
+/// * `worker_count`:   Number of threads or workers that searched.
+/// * `attempts`:       Number of nonces tried across all workers.
+/// * `wall_time_ms`:   Milliseconds from receiving the challenge to the solution.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolutionMetadata {
+    #[cfg_attr(feature = "openapi", schema(example = "wasm-1.4.0"))]
+    pub solver_version: String,
+    #[cfg_attr(feature = "openapi", schema(example = 4u32))]
+    pub worker_count:   u32,
+    #[cfg_attr(feature = "openapi", schema(example = 412345u64))]
+    pub attempts:       u64,
+    #[cfg_attr(feature = "openapi", schema(example = 1830u64))]
+    pub wall_time_ms:   u64,
+}
+
+impl SolutionMetadata {
+    /// Checks that the solver version is short and free of characters
+    /// that are meaningful in the concatenated format, and that at
+    /// least one worker is reported.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid field.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.solver_version.is_empty() || self.solver_version.len() > MAX_SOLVER_VERSION_LEN {
+            return Err(format!("solver_version must be 1 to {} characters", MAX_SOLVER_VERSION_LEN));
+        }
+        if !self.solver_version.chars().all(|c| c.is_ascii_alphanumeric() || "-_.+/".contains(c)) {
+            return Err(format!("Invalid character in solver_version: {}", self.solver_version));
+        }
+        if self.worker_count == 0 {
+            return Err("worker_count must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `Option<f64>`: Attempts per second across all workers, or
+    ///                  `None` if no time was reported.
+    pub fn hash_rate(&self) -> Option<f64> {
+        (self.wall_time_ms > 0).then(|| self.attempts as f64 * 1_000.0 / self.wall_time_ms as f64)
+    }
+
+    /// Formats the metadata as the `sm=` part of the concatenated format.
+    fn to_concat_part(&self) -> String {
+        format!(
+            "{}{},{},{},{}",
+            METADATA_PREFIX,
+            self.solver_version,
+            self.worker_count,
+            self.attempts,
+            self.wall_time_ms
+        )
+    }
+
+    /// Parses the value of an `sm=` part.
+    fn from_concat_value(value: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = value.split(',').collect();
+        let [solver_version, worker_count, attempts, wall_time_ms] = fields[..] else {
+            return Err(format!("Expected 4 solution metadata fields, got {}", fields.len()));
+        };
+
+        let metadata = Self {
+            solver_version: solver_version.to_string(),
+            worker_count:   worker_count.parse::<u32>()
+                .map_err(|_| "Failed to parse worker_count as u32")?,
+            attempts:       attempts.parse::<u64>()
+                .map_err(|_| "Failed to parse attempts as u64")?,
+            wall_time_ms:   wall_time_ms.parse::<u64>()
+                .map_err(|_| "Failed to parse wall_time_ms as u64")?,
+        };
+        metadata.validate()?;
+        Ok(metadata)
+    }
+}
+
 /// IronShield Challenge Response structure
 /// 
 /// * `solved_challenge`: The complete original IronShieldChallenge that was solved.
 /// * `solution`:         The nonce solution found by the proof-of-work algorithm.
+/// * `metadata`:         Optional solver telemetry.
 #[cfg(feature = "openapi")]
 #[allow(unused_imports)]
 use serde_json::json;
@@ -24,6 +119,9 @@ pub struct IronShieldChallengeResponse {
     /// The nonce solution found by the proof-of-work algorithm
     #[cfg_attr(feature = "openapi", schema(example = 187453i64))]
     pub solution:         i64,
+    /// Optional solver telemetry for difficulty tuning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata:         Option<SolutionMetadata>,
 }
 
 impl IronShieldChallengeResponse {
@@ -43,27 +141,66 @@ impl IronShieldChallengeResponse {
         Self {
             solved_challenge,
             solution,
+            metadata: None,
         }
     }
 
+    /// Attaches solver telemetry to the response.
+    ///
+    /// # Arguments
+    /// * `metadata`: The solver's report.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The response, or an error if the
+    ///                           metadata is invalid.
+    pub fn with_metadata(mut self, metadata: SolutionMetadata) -> Result<Self, String> {
+        metadata.validate()?;
+        self.metadata = Some(metadata);
+        Ok(self)
+    }
+
+    /// Checks that `solution` solves `solved_challenge`.
+    ///
+    /// Only the proof-of-work is checked; the challenge signature and
+    /// expiry are validated separately. Challenges requiring several
+    /// solutions are answered with a `MultiSolutionResponse` and fail
+    /// here.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the solution meets the target.
+    pub fn verify_solution(&self) -> Result<(), String> {
+        self.solved_challenge.check_solutions(&[self.solution])
+    }
+
     /// Concatenates the response data into a string.
     ///
     /// Concatenates:
     /// * `solved_challenge`: As its concatenated string representation.
     /// * `solution`:         As a string.
+    ///
+    /// Followed, when present, by `metadata` as
+    /// `sm=solver_version,worker_count,attempts,wall_time_ms`.
     pub fn concat_struct(&self) -> String {
-        format!(
+        let mut concat: String = format!(
             "{}|{}",
             self.solved_challenge.concat_struct(),
             self.solution
-        )
+        );
+
+        if let Some(metadata) = &self.metadata {
+            concat.push('|');
+            concat.push_str(&metadata.to_concat_part());
+        }
+
+        concat
     }
 
     /// Creates an `IronShieldChallengeResponse` from a concatenated string.
     ///
     /// This function reverses the operation of
     /// `IronShieldChallengeResponse::concat_struct`.
-    /// Expects a string in the format: "challenge_concat_string|solution".
+    /// Expects a string in the format: "challenge_concat_string|solution",
+    /// optionally followed by a `|sm=...` metadata part.
     ///
     /// # Arguments
     /// * `concat_string`: The concatenated string to parse, typically
@@ -74,6 +211,14 @@ impl IronShieldChallengeResponse {
     ///                           `IronShieldChallengeResponse`
     ///                           or an error message if parsing fails.
     pub fn from_concat_struct(concat_string: &str) -> Result<Self, String> {
+        // Split off the metadata part, which is always last when present.
+        let (concat_string, metadata) = match concat_string.rsplit_once('|') {
+            Some((rest, part)) if part.starts_with(METADATA_PREFIX) => {
+                (rest, Some(SolutionMetadata::from_concat_value(&part[METADATA_PREFIX.len()..])?))
+            }
+            _ => (concat_string, None),
+        };
+
         // Split on the last '|' to separate challenge from solution
         let last_pipe_pos = concat_string.rfind('|')
             .ok_or("Expected at least one '|' separator")?;
@@ -88,6 +233,7 @@ impl IronShieldChallengeResponse {
         Ok(Self {
             solved_challenge,
             solution,
+            metadata,
         })
     }
 
@@ -100,10 +246,11 @@ impl IronShieldChallengeResponse {
     /// * `String` - Base64url-encoded string ready for HTTP header use
     /// 
     /// # Example
-    /// ```
+    #[cfg_attr(feature = "rng", doc = " ```")]
+    #[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
     /// use ironshield_types::{IronShieldChallengeResponse, IronShieldChallenge, SigningKey};
     /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
-    /// let challenge = IronShieldChallenge::new("test".to_string(), 100_000, dummy_key, [0x34; 32]);
+    /// let challenge = IronShieldChallenge::new("test".parse().unwrap(), 100_000, dummy_key, [0x34; 32]);
     /// let response = IronShieldChallengeResponse::new(challenge, 12345);
     /// let header_value = response.to_base64url_header();
     /// // Use header_value in HTTP header: "X-IronShield-Challenge-Response: {header_value}"
@@ -124,11 +271,12 @@ impl IronShieldChallengeResponse {
     /// * `Result<Self, String>` - Decoded response or detailed error message
     /// 
     /// # Example
-    /// ```
+    #[cfg_attr(feature = "rng", doc = " ```")]
+    #[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
     /// use ironshield_types::{IronShieldChallengeResponse, IronShieldChallenge, SigningKey};
     /// // Create a response and encode it
     /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
-    /// let challenge = IronShieldChallenge::new("test".to_string(), 100_000, dummy_key, [0x34; 32]);
+    /// let challenge = IronShieldChallenge::new("test".parse().unwrap(), 100_000, dummy_key, [0x34; 32]);
     /// let original = IronShieldChallengeResponse::new(challenge, 12345);
     /// let header_value = original.to_base64url_header();
     /// // Decode it back
@@ -136,15 +284,76 @@ impl IronShieldChallengeResponse {
     /// assert_eq!(original.solution, decoded.solution);
     /// ```
     pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
-        // Decode using the existing serde_utils function.
-        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        traced!("decode_response_header", Result<Self, String>, {
+            header_len = encoded_header.len(),
+            website_id = tracing::field::Empty,
+        }, |response| { website_id = &response.solved_challenge.website_id }, {
+            // Decode using the existing serde_utils function.
+            let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
         
-        // Parse using the existing concat_struct format.
-        Self::from_concat_struct(&concat_str)
+            // Parse using the existing concat_struct format.
+            Self::from_concat_struct(&concat_str)
+        })
+    }
+
+    /// Encodes the response in the compact binary format: the compact
+    /// challenge followed by `solution` as a big-endian `i64`, then,
+    /// when present, the metadata fields. Responses without metadata
+    /// keep the original layout.
+    ///
+    /// # Returns
+    /// * `Result<Vec<u8>, String>`: The encoded bytes, or an error if a
+    ///                              string field is too long to encode.
+    pub fn to_compact_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut writer = CompactWriter::new();
+        self.solved_challenge.write_compact(&mut writer)?;
+        writer.write_i64(self.solution);
+        if let Some(metadata) = &self.metadata {
+            writer.write_str(&metadata.solver_version)?;
+            writer.write_u64(metadata.worker_count as u64);
+            writer.write_u64(metadata.attempts);
+            writer.write_u64(metadata.wall_time_ms);
+        }
+        Ok(writer.finish())
+    }
+
+    /// Decodes a response produced by `to_compact_bytes()`.
+    ///
+    /// # Arguments
+    /// * `bytes`: The compact binary payload.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The decoded response or an error
+    ///                           describing the malformed field.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = CompactReader::new(bytes)?;
+        let solved_challenge: IronShieldChallenge = IronShieldChallenge::read_compact(&mut reader)?;
+        let solution: i64 = reader.read_i64("solution")?;
+
+        let metadata: Option<SolutionMetadata> = if reader.has_remaining() {
+            let metadata = SolutionMetadata {
+                solver_version: reader.read_str("solver_version")?.to_string(),
+                worker_count:   u32::try_from(reader.read_u64("worker_count")?)
+                    .map_err(|_| "worker_count does not fit in u32")?,
+                attempts:       reader.read_u64("attempts")?,
+                wall_time_ms:   reader.read_u64("wall_time_ms")?,
+            };
+            metadata.validate()?;
+            Some(metadata)
+        } else {
+            None
+        };
+        reader.finish()?;
+
+        Ok(Self {
+            solved_challenge,
+            solution,
+            metadata,
+        })
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rng"))]
 mod tests {
     use super::*;
     use crate::SigningKey;
@@ -154,7 +363,7 @@ mod tests {
         // Create a test challenge and response.
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             dummy_key,
             [0x34; 32],
@@ -193,7 +402,7 @@ mod tests {
     fn test_concat_struct() {
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             dummy_key,
             [0x34; 32],
@@ -208,7 +417,7 @@ mod tests {
     fn test_from_concat_struct() {
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             100_000,
             dummy_key,
             [0x34; 32],
@@ -224,7 +433,7 @@ mod tests {
     fn test_from_concat_struct_edge_cases() {
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             1,
             dummy_key,
             [0x00; 32],
@@ -252,6 +461,57 @@ mod tests {
         assert_eq!(parsed.solution, i64::MAX);
     }
 
+    #[test]
+    fn test_compact_bytes_roundtrip() {
+        let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
+        let challenge = IronShieldChallenge::new(
+            "test_website".parse().unwrap(),
+            100_000,
+            dummy_key,
+            [0x34; 32],
+        );
+        let response = IronShieldChallengeResponse::new(challenge, -7);
+
+        let bytes: Vec<u8> = response.to_compact_bytes().unwrap();
+        let decoded = IronShieldChallengeResponse::from_compact_bytes(&bytes).unwrap();
+        assert_eq!(decoded.concat_struct(), response.concat_struct());
+
+        let result = IronShieldChallengeResponse::from_compact_bytes(&bytes[..bytes.len() - 1]);
+        assert!(result.unwrap_err().starts_with(crate::CORRUPTED_PAYLOAD_ERROR));
+    }
+
+    #[test]
+    fn test_solution_metadata() {
+        let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
+        let challenge = IronShieldChallenge::new("test_website".parse().unwrap(), 1_000, dummy_key, [0x34; 32]);
+        let metadata = SolutionMetadata {
+            solver_version: "wasm-1.4.0".to_string(),
+            worker_count:   4,
+            attempts:       3_000,
+            wall_time_ms:   1_500,
+        };
+        assert_eq!(metadata.hash_rate(), Some(2_000.0));
+        assert!(IronShieldChallengeResponse::new(challenge.clone(), 1)
+            .with_metadata(SolutionMetadata { solver_version: "a|b".to_string(), ..metadata.clone() })
+            .is_err());
+
+        let response = IronShieldChallengeResponse::new(challenge.clone(), -3).with_metadata(metadata.clone()).unwrap();
+        assert!(response.concat_struct().ends_with("|-3|sm=wasm-1.4.0,4,3000,1500"));
+
+        let decoded = IronShieldChallengeResponse::from_base64url_header(&response.to_base64url_header()).unwrap();
+        assert_eq!((decoded.solution, decoded.metadata.as_ref()), (-3, Some(&metadata)));
+        let decoded = IronShieldChallengeResponse::from_compact_bytes(&response.to_compact_bytes().unwrap()).unwrap();
+        assert_eq!((decoded.solution, decoded.metadata.as_ref()), (-3, Some(&metadata)));
+
+        let json: String = serde_json::to_string(&IronShieldChallengeResponse::new(challenge, 7)).unwrap();
+        assert!(!json.contains("metadata"));
+        let decoded: IronShieldChallengeResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.metadata, None);
+
+        let result = IronShieldChallengeResponse::from_concat_struct(&format!("{}|sm=v1,0,1,1", response.concat_struct().rsplit_once('|').unwrap().0));
+        assert!(result.unwrap_err().contains("worker_count must be positive"));
+    }
+
     #[test]
     fn test_from_concat_struct_error_cases() {
         // Test with no pipe separator
@@ -262,7 +522,7 @@ mod tests {
         // Test with invalid solution
         let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
         let challenge = IronShieldChallenge::new(
-            "test_website".to_string(),
+            "test_website".parse().unwrap(),
             1,
             dummy_key,
             [0x00; 32],
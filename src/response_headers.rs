@@ -0,0 +1,245 @@
+//! # The `X-IronShield-*` headers of a challenge response.
+//!
+//! A challenge response carries several headers that have to agree with
+//! each other: the encoded challenge, the protocol version, a retry
+//! hint and the client's remaining issuance quota. `ResponseHeaderSet`
+//! collects them, checks that together they stay below the header size
+//! proxies accept, and hands them out as plain name/value pairs, so any
+//! HTTP framework can copy them onto its response.
+//!
+//! | Header                             | Value                                  |
+//! |------------------------------------|----------------------------------------|
+//! | `X-IronShield-Challenge-Data`      | `to_base64url_header()` of a challenge |
+//! | `X-IronShield-Version`             | `<major>.<minor>`                      |
+//! | `Retry-After`                      | Whole seconds, rounded up              |
+//! | `X-IronShield-RateLimit-Limit`     | Challenges allowed per window          |
+//! | `X-IronShield-RateLimit-Remaining` | Challenges left in the window          |
+//! | `X-IronShield-RateLimit-Reset`     | Seconds until the window resets        |
+
+use crate::{
+    IronShieldChallenge,
+    IronShieldErrorResponse,
+    IronShieldProtocolVersion,
+    IssuanceQuota,
+    PROTOCOL_VERSION_HEADER
+};
+
+/// Name of the header carrying the encoded challenge.
+pub const CHALLENGE_DATA_HEADER:       &str = "X-IronShield-Challenge-Data";
+/// Name of the standard retry hint header.
+pub const RETRY_AFTER_HEADER:          &str = "Retry-After";
+/// Name of the header carrying the quota size.
+pub const RATE_LIMIT_LIMIT_HEADER:     &str = "X-IronShield-RateLimit-Limit";
+/// Name of the header carrying the issuances left.
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "X-IronShield-RateLimit-Remaining";
+/// Name of the header carrying the seconds until the quota resets.
+pub const RATE_LIMIT_RESET_HEADER:     &str = "X-IronShield-RateLimit-Reset";
+
+/// Largest combined size of the set, counted as `name: value\r\n` per
+/// header. Proxies commonly reject response headers above 8 KiB in
+/// total, and the set has to leave room for the application's own.
+pub const MAX_RESPONSE_HEADER_BYTES: usize = 4096;
+
+/// Bytes added per header line by `": "` and `"\r\n"`.
+const HEADER_LINE_OVERHEAD: usize = 4;
+
+/// Quota figures sent with a response.
+///
+/// * `limit`:     Challenges allowed per window.
+/// * `remaining`: Challenges left in the current window.
+/// * `reset_s`:   Seconds until the window resets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateLimitHeaders {
+    pub limit:     u32,
+    pub remaining: u32,
+    pub reset_s:   u64,
+}
+
+/// The IronShield headers of one response.
+///
+/// * `challenge_data`: The encoded challenge, if one is issued.
+/// * `version`:        The protocol version spoken by the server.
+/// * `retry_after_ms`: Minimum wait before the client retries.
+/// * `rate_limit`:     The client's issuance quota.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResponseHeaderSet {
+    pub challenge_data: Option<String>,
+    pub version:        IronShieldProtocolVersion,
+    pub retry_after_ms: Option<u64>,
+    pub rate_limit:     Option<RateLimitHeaders>,
+}
+
+impl ResponseHeaderSet {
+    /// # Returns
+    /// * `Self`: A set carrying only the current protocol version.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Arguments
+    /// * `challenge`: The challenge to issue.
+    ///
+    /// # Returns
+    /// * `Self`: The set carrying the encoded challenge.
+    pub fn with_challenge(mut self, challenge: &IronShieldChallenge) -> Self {
+        self.challenge_data = Some(challenge.to_base64url_header());
+        self
+    }
+
+    /// # Arguments
+    /// * `version`: The protocol version to announce.
+    ///
+    /// # Returns
+    /// * `Self`: The set announcing `version`.
+    pub fn with_version(mut self, version: IronShieldProtocolVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// # Arguments
+    /// * `retry_after_ms`: Milliseconds the client should wait.
+    ///
+    /// # Returns
+    /// * `Self`: The set carrying the retry hint.
+    pub fn with_retry_after_ms(mut self, retry_after_ms: u64) -> Self {
+        self.retry_after_ms = Some(retry_after_ms);
+        self
+    }
+
+    /// Copies the retry hint of an error response, if it has one.
+    ///
+    /// # Arguments
+    /// * `error`: The error returned in the body.
+    ///
+    /// # Returns
+    /// * `Self`: The set carrying the error's `retry_after_ms`.
+    pub fn with_error_response(mut self, error: &IronShieldErrorResponse) -> Self {
+        self.retry_after_ms = error.retry_after_ms.or(self.retry_after_ms);
+        self
+    }
+
+    /// # Arguments
+    /// * `quota`: The client's quota after this request was counted.
+    /// * `now`:   The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Self`: The set carrying the quota figures at `now`.
+    pub fn with_issuance_quota(mut self, quota: &IssuanceQuota, now: i64) -> Self {
+        let reset_ms: u64 = quota.resets_at().saturating_sub(now).max(0) as u64;
+        self.rate_limit = Some(RateLimitHeaders {
+            limit:     quota.max_issuances,
+            remaining: quota.remaining(now),
+            reset_s:   reset_ms.div_ceil(1_000),
+        });
+        self
+    }
+
+    /// # Returns
+    /// * `Vec<(&'static str, String)>`: The headers in a fixed order,
+    ///                                  without checking their size.
+    fn pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs: Vec<(&'static str, String)> = Vec::new();
+        if let Some(challenge_data) = &self.challenge_data {
+            pairs.push((CHALLENGE_DATA_HEADER, challenge_data.clone()));
+        }
+        pairs.push((PROTOCOL_VERSION_HEADER, self.version.to_header_value()));
+        if let Some(retry_after_ms) = self.retry_after_ms {
+            pairs.push((RETRY_AFTER_HEADER, retry_after_ms.div_ceil(1_000).to_string()));
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            pairs.push((RATE_LIMIT_LIMIT_HEADER, rate_limit.limit.to_string()));
+            pairs.push((RATE_LIMIT_REMAINING_HEADER, rate_limit.remaining.to_string()));
+            pairs.push((RATE_LIMIT_RESET_HEADER, rate_limit.reset_s.to_string()));
+        }
+        pairs
+    }
+
+    /// # Returns
+    /// * `usize`: Combined size of the headers as `name: value\r\n`
+    ///            lines.
+    pub fn encoded_len(&self) -> usize {
+        self.pairs()
+            .iter()
+            .map(|(name, value)| name.len() + value.len() + HEADER_LINE_OVERHEAD)
+            .sum()
+    }
+
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the headers fit within
+    ///                         `MAX_RESPONSE_HEADER_BYTES` and every
+    ///                         value is a valid header value.
+    pub fn validate(&self) -> Result<(), String> {
+        let pairs: Vec<(&'static str, String)> = self.pairs();
+        if let Some((name, _)) = pairs.iter()
+            .find(|(_, value)| !value.bytes().all(|byte| byte == b'\t' || (0x20..0x7F).contains(&byte)))
+        {
+            return Err(format!("Invalid characters in {} header", name));
+        }
+
+        let encoded_len: usize = self.encoded_len();
+        if encoded_len > MAX_RESPONSE_HEADER_BYTES {
+            return Err(format!(
+                "Response headers are {} bytes, more than the limit of {}",
+                encoded_len,
+                MAX_RESPONSE_HEADER_BYTES
+            ));
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `Result<Vec<(&'static str, String)>, String>`: The headers as
+    ///   name/value pairs, or the error from `validate()`.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::ResponseHeaderSet;
+    /// let headers = ResponseHeaderSet::new().with_retry_after_ms(1_500).to_pairs().unwrap();
+    /// assert_eq!(headers[1], ("Retry-After", "2".to_string()));
+    /// ```
+    pub fn to_pairs(&self) -> Result<Vec<(&'static str, String)>, String> {
+        self.validate()?;
+        Ok(self.pairs())
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_to_pairs() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+        let mut quota = IssuanceQuota::new([1; 32], 60_000, 10, 0).unwrap();
+        quota.check_and_increment(0);
+
+        let headers = ResponseHeaderSet::new()
+            .with_challenge(&challenge)
+            .with_version(IronShieldProtocolVersion::new(1, 2))
+            .with_issuance_quota(&quota, 30_500)
+            .to_pairs()
+            .unwrap();
+        assert_eq!(headers, [
+            (CHALLENGE_DATA_HEADER, challenge.to_base64url_header()),
+            (PROTOCOL_VERSION_HEADER, "1.2".to_string()),
+            (RATE_LIMIT_LIMIT_HEADER, "10".to_string()),
+            (RATE_LIMIT_REMAINING_HEADER, "9".to_string()),
+            (RATE_LIMIT_RESET_HEADER, "30".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let error = IronShieldErrorResponse::new(crate::ErrorCode::RateLimited, "Slow down").with_retry_after_ms(999);
+        let headers = ResponseHeaderSet::new().with_error_response(&error);
+        assert_eq!(headers.to_pairs().unwrap()[1], (RETRY_AFTER_HEADER, "1".to_string()));
+        assert_eq!(headers.encoded_len(), "X-IronShield-Version: 1.0\r\n".len() + "Retry-After: 1\r\n".len());
+
+        let oversized = ResponseHeaderSet { challenge_data: Some("a".repeat(MAX_RESPONSE_HEADER_BYTES)), ..headers.clone() };
+        assert!(oversized.to_pairs().unwrap_err().contains("more than the limit"));
+        let injected = ResponseHeaderSet { challenge_data: Some("a\r\nSet-Cookie: x".to_string()), ..headers };
+        assert!(injected.validate().unwrap_err().contains(CHALLENGE_DATA_HEADER));
+    }
+}
@@ -0,0 +1,117 @@
+//! # Client retry behavior chosen by the issuer.
+//!
+//! The issuer sends a `RetryPolicy` with its error responses and the
+//! client follows it, so retry pacing can be tuned in one place instead
+//! of in constants baked into every client build.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Exponential backoff with jitter.
+///
+/// The delay before retry `n` (counting from 0) is
+/// `base_delay_ms * 2^n`, capped at `max_delay_ms`, then reduced by up
+/// to `jitter` of itself at random so that rejected clients do not all
+/// come back at once.
+///
+/// * `max_retries`:   Retries allowed after the first failure.
+/// * `base_delay_ms`: Delay before the first retry.
+/// * `max_delay_ms`:  Upper bound of any delay.
+/// * `jitter`:        Fraction (0 to 1) of each delay that is randomized.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries:   u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms:  u64,
+    pub jitter:        f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries:   3,
+            base_delay_ms: 500,
+            max_delay_ms:  30_000,
+            jitter:        0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that forbids retrying, e.g. for a revoked key.
+    ///
+    /// # Returns
+    /// * `Self`: A policy with `max_retries` 0.
+    pub fn no_retry() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Checks that the delays are ordered and the jitter is a fraction.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid setting.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.base_delay_ms > self.max_delay_ms {
+            return Err("base_delay_ms cannot exceed max_delay_ms".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err("jitter must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Arguments
+    /// * `attempt`: Zero-based index of the retry about to be made.
+    /// * `random`:  A uniform random number in `[0, 1)`, e.g. from
+    ///              `Math.random()`; values outside are clamped.
+    ///
+    /// # Returns
+    /// * `Option<u64>`: Milliseconds to wait before the retry, or `None`
+    ///                  once the retries are used up.
+    pub fn delay_ms(&self, attempt: u32, random: f64) -> Option<u64> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+
+        let backoff: u64 = 2u64.checked_pow(attempt)
+            .and_then(|factor| self.base_delay_ms.checked_mul(factor))
+            .map_or(self.max_delay_ms, |delay| delay.min(self.max_delay_ms));
+        let random: f64 = if random.is_nan() { 0.0 } else { random.clamp(0.0, 1.0) };
+        let reduction: u64 = (backoff as f64 * self.jitter.clamp(0.0, 1.0) * random) as u64;
+
+        Some(backoff - reduction.min(backoff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_ms() {
+        let policy = RetryPolicy { max_retries: 4, base_delay_ms: 1_000, max_delay_ms: 5_000, jitter: 0.5 };
+        let delays: Vec<Option<u64>> = (0..5).map(|attempt| policy.delay_ms(attempt, 0.0)).collect();
+        assert_eq!(delays, [Some(1_000), Some(2_000), Some(4_000), Some(5_000), None]);
+
+        assert_eq!(policy.delay_ms(1, 0.5), Some(1_500));
+        assert_eq!(policy.delay_ms(1, 7.0), Some(1_000));
+        assert_eq!(policy.delay_ms(1, f64::NAN), Some(2_000));
+        assert_eq!(RetryPolicy { max_retries: 100, ..policy }.delay_ms(90, 0.0), Some(5_000));
+        assert_eq!(RetryPolicy::no_retry().delay_ms(0, 0.0), None);
+    }
+
+    #[test]
+    fn test_validate_and_serde() {
+        assert!(RetryPolicy::default().validate().is_ok());
+        assert!(RetryPolicy { base_delay_ms: 10, max_delay_ms: 5, ..Default::default() }.validate().is_err());
+        assert!(RetryPolicy { jitter: 1.5, ..Default::default() }.validate().is_err());
+
+        let json: String = serde_json::to_string(&RetryPolicy::default()).unwrap();
+        assert_eq!(json, r#"{"max_retries":3,"base_delay_ms":500,"max_delay_ms":30000,"jitter":0.5}"#);
+        assert_eq!(serde_json::from_str::<RetryPolicy>(&json).unwrap(), RetryPolicy::default());
+    }
+}
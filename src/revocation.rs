@@ -0,0 +1,204 @@
+//! # Token revocation set.
+//!
+//! A token stays valid until its `valid_for` passes. `RevokedTokens`
+//! cuts a compromised token off early: it holds the `jti` of every
+//! revoked token together with that token's natural expiry, after which
+//! the entry is useless and can be pruned. Sets from several sources
+//! (admin API, abuse detection, other edges) merge by union.
+
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer
+};
+
+use crate::epoch::now_millis;
+use crate::IronShieldToken;
+
+/// Set of revoked token identifiers with expiry-based pruning.
+///
+/// Serializes as a JSON object mapping the hex `jti` to the Unix milli
+/// timestamp after which the entry may be pruned.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    value_type = std::collections::HashMap<String, i64>,
+    description = "Revoked token identifiers (hex jti) mapped to their expiry"
+))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedTokens(
+    #[serde(
+        serialize_with = "serialize_revocations",
+        deserialize_with = "deserialize_revocations"
+    )]
+    BTreeMap<[u8; 32], i64>,
+);
+
+impl RevokedTokens {
+    /// # Returns
+    /// * `Self`: An empty revocation set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Returns
+    /// * `usize`: The number of revoked identifiers.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if nothing is revoked.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Revokes a token until its natural expiry.
+    ///
+    /// # Arguments
+    /// * `token`: The token to revoke.
+    pub fn revoke(&mut self, token: &IronShieldToken) {
+        self.revoke_jti(token.jti(), token.valid_for);
+    }
+
+    /// Revokes a token identifier.
+    ///
+    /// # Arguments
+    /// * `jti`:        The token identifier.
+    /// * `expires_at`: Unix milli timestamp after which the entry may
+    ///                 be pruned (the token's `valid_for`).
+    pub fn revoke_jti(&mut self, jti: [u8; 32], expires_at: i64) {
+        let entry: &mut i64 = self.0.entry(jti).or_insert(expires_at);
+        *entry = (*entry).max(expires_at);
+    }
+
+    /// # Arguments
+    /// * `jti`: The token identifier.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the identifier is revoked.
+    pub fn is_revoked(&self, jti: &[u8; 32]) -> bool {
+        self.0.contains_key(jti)
+    }
+
+    /// # Arguments
+    /// * `token`: The presented token.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: An error if the token is revoked.
+    pub fn check(&self, token: &IronShieldToken) -> Result<(), String> {
+        if self.is_revoked(&token.jti()) {
+            return Err("Token has been revoked".to_string());
+        }
+        Ok(())
+    }
+
+    /// Adds every revocation of `other` to this set.
+    ///
+    /// # Arguments
+    /// * `other`: Another revocation set.
+    pub fn merge(&mut self, other: &RevokedTokens) {
+        for (jti, expires_at) in &other.0 {
+            self.revoke_jti(*jti, *expires_at);
+        }
+    }
+
+    /// Drops entries whose tokens have expired on their own.
+    ///
+    /// # Returns
+    /// * `usize`: The number of entries removed.
+    pub fn prune(&mut self) -> usize {
+        self.prune_at(now_millis())
+    }
+
+    /// Same as `prune`, evaluated at `now`.
+    pub fn prune_at(&mut self, now: i64) -> usize {
+        let before: usize = self.0.len();
+        self.0.retain(|_, expires_at| *expires_at >= now);
+        before - self.0.len()
+    }
+}
+
+fn serialize_revocations<S>(entries: &BTreeMap<[u8; 32], i64>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(entries.iter().map(|(jti, expires_at)| (hex::encode(jti), expires_at)))
+}
+
+fn deserialize_revocations<'de, D>(deserializer: D) -> Result<BTreeMap<[u8; 32], i64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded: BTreeMap<String, i64> = BTreeMap::deserialize(deserializer)?;
+
+    encoded
+        .into_iter()
+        .map(|(jti_hex, expires_at)| {
+            let jti_bytes: Vec<u8> = hex::decode(&jti_hex)
+                .map_err(|_| serde::de::Error::custom(format!("Invalid jti hex: {}", jti_hex)))?;
+            let jti: [u8; 32] = jti_bytes.try_into()
+                .map_err(|_| serde::de::Error::custom("jti must be exactly 32 bytes"))?;
+            Ok((jti, expires_at))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(seed: u8, valid_for: i64) -> IronShieldToken {
+        IronShieldToken::new([seed; 64], valid_for, [seed; 32], [seed; 64])
+    }
+
+    #[test]
+    fn test_revoke_and_check() {
+        let mut revoked = RevokedTokens::new();
+        let (a, b) = (token(1, 1_000), token(2, 1_000));
+        revoked.revoke(&a);
+
+        assert!(revoked.check(&a).unwrap_err().contains("revoked"));
+        assert!(revoked.check(&b).is_ok());
+    }
+
+    #[test]
+    fn test_prune_drops_naturally_expired_entries() {
+        let mut revoked = RevokedTokens::new();
+        revoked.revoke(&token(1, 1_000));
+        revoked.revoke(&token(2, 5_000));
+
+        assert_eq!(revoked.prune_at(2_000), 1);
+        assert_eq!(revoked.len(), 1);
+        assert!(revoked.is_revoked(&token(2, 5_000).jti()));
+    }
+
+    #[test]
+    fn test_merge_keeps_latest_expiry() {
+        let mut a = RevokedTokens::new();
+        let mut b = RevokedTokens::new();
+        a.revoke_jti([1; 32], 1_000);
+        b.revoke_jti([1; 32], 3_000);
+        b.revoke_jti([2; 32], 2_000);
+
+        a.merge(&b);
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.prune_at(2_500), 1);
+        assert!(a.is_revoked(&[1; 32]));
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut revoked = RevokedTokens::new();
+        revoked.revoke_jti([0xAB; 32], 1_000);
+
+        let json: String = serde_json::to_string(&revoked).unwrap();
+        assert_eq!(json, format!(r#"{{"{}":1000}}"#, "ab".repeat(32)));
+
+        let parsed: RevokedTokens = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, revoked);
+        assert!(serde_json::from_str::<RevokedTokens>(r#"{"abcd":1}"#).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! # Sealed-box encryption of compact challenges.
+//!
+//! Signed challenges are readable by anyone who relays them. Where the
+//! contents (website, difficulty, bindings) should stay opaque to
+//! intermediaries, the issuer seals the compact encoding to the X25519
+//! recipient key of the party that opens it, published next to its
+//! Ed25519 verification key (e.g. as `TenantKeyring::recipient_key`).
+//!
+//! A sealed challenge is laid out as:
+//!
+//! * `version`:       `SEALED_CHALLENGE_VERSION`.
+//! * `ephemeral_key`: The sender's one-time X25519 public key.
+//! * `ciphertext`:    `to_compact_bytes()` encrypted with
+//!                    XChaCha20-Poly1305, followed by its 16-byte tag.
+//!
+//! The encryption key is HKDF-SHA256 of the X25519 shared secret with
+//! both public keys as context. Every seal uses a fresh ephemeral key,
+//! so each encryption key encrypts exactly one message and the nonce is
+//! fixed at zero. Sealing only hides the challenge: the opened challenge
+//! still has to be verified against the issuer's verification key.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305,
+    XNonce
+};
+#[cfg(feature = "rng")]
+use rand::RngCore;
+#[cfg(feature = "rng")]
+use rand::rngs::OsRng;
+use x25519_dalek::{
+    PublicKey,
+    StaticSecret
+};
+
+use crate::key_derivation::hkdf_sha256;
+use crate::{
+    CryptoError,
+    IronShieldChallenge
+};
+
+/// Version byte at the start of every sealed challenge.
+pub const SEALED_CHALLENGE_VERSION: u8 = 1;
+/// HKDF salt separating sealed challenge keys from other derivations.
+const SEAL_KDF_SALT: &[u8] = b"ironshield-sealed-challenge-v1";
+/// Length of the version byte and the ephemeral public key.
+const SEAL_HEADER_LEN: usize = 1 + 32;
+/// Length of the Poly1305 tag appended to the ciphertext.
+const TAG_LEN: usize = 16;
+
+/// Generates an X25519 recipient key pair for sealed challenges.
+///
+/// # Returns
+/// * `([u8; 32], [u8; 32])`: The secret key, kept by the recipient, and
+///                           the public key to publish.
+#[cfg(feature = "rng")]
+pub fn generate_recipient_keypair() -> ([u8; 32], [u8; 32]) {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let public_key: [u8; 32] = recipient_public_key(&secret);
+    (secret, public_key)
+}
+
+/// # Arguments
+/// * `recipient_secret`: The recipient's X25519 secret key.
+///
+/// # Returns
+/// * `[u8; 32]`: Its public key.
+pub fn recipient_public_key(recipient_secret: &[u8; 32]) -> [u8; 32] {
+    PublicKey::from(&StaticSecret::from(*recipient_secret)).to_bytes()
+}
+
+/// Derives the one-message key for a sealed challenge.
+fn seal_key(
+    secret: &StaticSecret,
+    their_public: &PublicKey,
+    ephemeral_public: &[u8; 32],
+    recipient_public: &[u8; 32]
+) -> Result<[u8; 32], CryptoError> {
+    let shared = secret.diffie_hellman(their_public);
+    // A low-order public key forces the shared secret to zero, which
+    // anyone could compute.
+    if !shared.was_contributory() {
+        return Err(CryptoError::InvalidKeyFormat("X25519 public key has low order".into()));
+    }
+
+    let mut info = [0u8; 64];
+    info[..32].copy_from_slice(ephemeral_public);
+    info[32..].copy_from_slice(recipient_public);
+    Ok(hkdf_sha256(shared.as_bytes(), SEAL_KDF_SALT, &info))
+}
+
+/// Seals a challenge's compact encoding to a recipient key.
+///
+/// # Arguments
+/// * `challenge`:     The signed challenge.
+/// * `recipient_key`: The recipient's X25519 public key.
+///
+/// # Returns
+/// * `Result<Vec<u8>, CryptoError>`: The sealed challenge, or an error if
+///                                   the challenge cannot be encoded or
+///                                   the recipient key is invalid.
+#[cfg(feature = "rng")]
+pub fn seal_challenge(challenge: &IronShieldChallenge, recipient_key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    let (ephemeral_secret, ephemeral_public) = generate_recipient_keypair();
+    let mut key: [u8; 32] = seal_key(
+        &StaticSecret::from(ephemeral_secret),
+        &PublicKey::from(*recipient_key),
+        &ephemeral_public,
+        recipient_key
+    )?;
+
+    let mut header: Vec<u8> = Vec::with_capacity(SEAL_HEADER_LEN);
+    header.push(SEALED_CHALLENGE_VERSION);
+    header.extend_from_slice(&ephemeral_public);
+
+    let compact: Vec<u8> = challenge.to_compact_bytes()
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Failed to encode challenge: {}", e).into()))?;
+    let sealed = XChaCha20Poly1305::new(&key.into())
+        .encrypt(&XNonce::default(), Payload { msg: &compact, aad: &header })
+        .map_err(|_| CryptoError::SigningFailed("Failed to seal the challenge".into()));
+    key.fill(0);
+
+    let mut payload: Vec<u8> = header;
+    payload.extend_from_slice(&sealed?);
+    Ok(payload)
+}
+
+/// Opens a challenge sealed with `seal_challenge()`.
+///
+/// # Arguments
+/// * `sealed`:           The sealed challenge.
+/// * `recipient_secret`: The recipient's X25519 secret key.
+///
+/// # Returns
+/// * `Result<IronShieldChallenge, CryptoError>`: The challenge, not yet
+///                                               signature-checked;
+///                                               `VerificationFailed` if
+///                                               it was sealed to another
+///                                               key or altered.
+pub fn open_sealed_challenge(sealed: &[u8], recipient_secret: &[u8; 32]) -> Result<IronShieldChallenge, CryptoError> {
+    if sealed.len() < SEAL_HEADER_LEN + TAG_LEN {
+        return Err(CryptoError::InvalidKeyFormat(format!("Sealed challenge is too short: {} bytes", sealed.len()).into()));
+    }
+    if sealed[0] != SEALED_CHALLENGE_VERSION {
+        return Err(CryptoError::InvalidKeyFormat(format!("Unsupported sealed challenge version: {}", sealed[0]).into()));
+    }
+
+    let (header, ciphertext) = sealed.split_at(SEAL_HEADER_LEN);
+    let mut ephemeral_public = [0u8; 32];
+    ephemeral_public.copy_from_slice(&header[1..]);
+
+    let mut key: [u8; 32] = seal_key(
+        &StaticSecret::from(*recipient_secret),
+        &PublicKey::from(ephemeral_public),
+        &ephemeral_public,
+        &recipient_public_key(recipient_secret)
+    )?;
+    let opened = XChaCha20Poly1305::new(&key.into())
+        .decrypt(&XNonce::default(), Payload { msg: ciphertext, aad: header });
+    key.fill(0);
+
+    let compact: Vec<u8> = opened.map_err(|_| CryptoError::VerificationFailed(
+        "Challenge was sealed to a different recipient key or altered".into()
+    ))?;
+    IronShieldChallenge::from_compact_bytes(&compact)
+        .map_err(|e| CryptoError::InvalidKeyFormat(format!("Failed to decode sealed challenge: {}", e).into()))
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    fn challenge() -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key, public_key)
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let issued = challenge();
+        let (secret, public_key) = generate_recipient_keypair();
+        let sealed: Vec<u8> = seal_challenge(&issued, &public_key).unwrap();
+
+        let compact: Vec<u8> = issued.to_compact_bytes().unwrap();
+        assert_eq!(sealed.len(), SEAL_HEADER_LEN + compact.len() + TAG_LEN);
+        assert!(!sealed.windows(b"example.com".len()).any(|window| window == b"example.com"));
+
+        let opened: IronShieldChallenge = open_sealed_challenge(&sealed, &secret).unwrap();
+        assert_eq!(opened, issued);
+        assert!(crate::verify_challenge_signature_with_key(&opened, &issued.public_key).is_ok());
+
+        // A fresh ephemeral key per seal.
+        assert_ne!(seal_challenge(&issued, &public_key).unwrap(), sealed);
+    }
+
+    #[test]
+    fn test_wrong_recipient_and_tampering_are_rejected() {
+        let issued = challenge();
+        let (_, public_key) = generate_recipient_keypair();
+        let (other_secret, _) = generate_recipient_keypair();
+        let sealed: Vec<u8> = seal_challenge(&issued, &public_key).unwrap();
+
+        assert!(matches!(open_sealed_challenge(&sealed, &other_secret), Err(CryptoError::VerificationFailed(_))));
+
+        let (secret, public_key) = generate_recipient_keypair();
+        let sealed: Vec<u8> = seal_challenge(&issued, &public_key).unwrap();
+        for position in [1, SEAL_HEADER_LEN, sealed.len() - 1] {
+            let mut tampered: Vec<u8> = sealed.clone();
+            tampered[position] ^= 1;
+            assert!(open_sealed_challenge(&tampered, &secret).is_err());
+        }
+
+        let mut version: Vec<u8> = sealed.clone();
+        version[0] = 2;
+        assert!(matches!(open_sealed_challenge(&version, &secret), Err(CryptoError::InvalidKeyFormat(_))));
+        assert!(seal_challenge(&issued, &[0; 32]).is_err());
+    }
+}
@@ -180,6 +180,184 @@ where
     Ok(array)
 }
 
+/// Custom serialization for optional 32-byte arrays (digests, bindings).
+///
+/// Serializes `Some` as the same byte sequence `serialize_32_bytes`
+/// produces and `None` as a null value.
+///
+/// # Arguments
+/// * `bytes`:      A reference to an optional 32-byte array.
+/// * `serializer`: The serde serializer instance that will
+///                 handle the actual serialization format.
+///
+/// # Returns
+/// * `Result<S::Ok, S::Error>`: Success value from the serializer
+///                              or a serialization error if
+///                              the operation fails.
+///
+/// # Example
+/// ```
+/// use ironshield_types::serialize_optional_32_bytes;
+///
+/// #[derive(serde::Serialize)]
+/// struct Binding {
+///     #[serde(serialize_with = "serialize_optional_32_bytes")]
+///     digest: Option<[u8; 32]>,
+/// }
+/// ```
+pub fn serialize_optional_32_bytes<S>(bytes: &Option<[u8; 32]>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match bytes {
+        Some(bytes) => serializer.serialize_some(&serde_bytes_32(bytes)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Custom deserialization for optional 32-byte arrays (digests, bindings).
+///
+/// Deserializes a null value into `None` and a byte sequence into
+/// `Some`, with the same strict length validation as
+/// `deserialize_32_bytes`.
+///
+/// # Arguments
+/// * `deserializer`: The serde deserializer instance that will
+///                   handle the actual deserialization.
+///
+/// # Returns
+/// * `Result<Option<[u8; 32]>, D::Error>`: The optional 32-byte array,
+///                                         or a deserialization error
+///                                         if the byte length is incorrect.
+///
+/// # Example
+/// ```
+/// use ironshield_types::deserialize_optional_32_bytes;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Binding {
+///     #[serde(default, deserialize_with = "deserialize_optional_32_bytes")]
+///     digest: Option<[u8; 32]>,
+/// }
+/// ```
+pub fn deserialize_optional_32_bytes<'de, D>(deserializer: D) -> Result<Option<[u8; 32]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes: Option<Vec<u8>> = Option::deserialize(deserializer)?;
+
+    match bytes {
+        Some(bytes) => {
+            let array: [u8; 32] = bytes.as_slice().try_into()
+                .map_err(|_| Error::custom(format!("Expected 32 bytes, got {}", bytes.len())))?;
+            Ok(Some(array))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Custom serialization for optional 64-byte arrays (signatures that
+/// are only present on some records).
+///
+/// Serializes `None` as null and `Some` like `serialize_signature`.
+///
+/// # Arguments
+/// * `signature`:  The optional signature.
+/// * `serializer`: The serde serializer instance that will
+///                 handle the actual serialization format.
+///
+/// # Returns
+/// * `Result<S::Ok, S::Error>`: Success value from the serializer
+///                              or a serialization error if
+///                              the operation fails.
+///
+/// # Example
+/// ```
+/// use ironshield_types::serialize_optional_signature;
+///
+/// #[derive(serde::Serialize)]
+/// struct Checkpoint {
+///     #[serde(serialize_with = "serialize_optional_signature")]
+///     signature: Option<[u8; 64]>,
+/// }
+/// ```
+pub fn serialize_optional_signature<S>(signature: &Option<[u8; 64]>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match signature {
+        Some(signature) => serializer.serialize_some(&serde_signature(signature)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Custom deserialization for optional 64-byte arrays.
+///
+/// Deserializes a null value into `None` and a byte sequence into
+/// `Some`, with the same strict length validation as
+/// `deserialize_signature`.
+///
+/// # Arguments
+/// * `deserializer`: The serde deserializer instance that will
+///                   handle the actual deserialization.
+///
+/// # Returns
+/// * `Result<Option<[u8; 64]>, D::Error>`: The optional signature, or a
+///                                         deserialization error if the
+///                                         byte length is incorrect.
+///
+/// # Example
+/// ```
+/// use ironshield_types::deserialize_optional_signature;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Checkpoint {
+///     #[serde(default, deserialize_with = "deserialize_optional_signature")]
+///     signature: Option<[u8; 64]>,
+/// }
+/// ```
+pub fn deserialize_optional_signature<'de, D>(deserializer: D) -> Result<Option<[u8; 64]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes: Option<Vec<u8>> = Option::deserialize(deserializer)?;
+
+    match bytes {
+        Some(bytes) => {
+            let array: [u8; 64] = bytes.as_slice().try_into()
+                .map_err(|_| Error::custom(format!("Expected 64 bytes, got {}", bytes.len())))?;
+            Ok(Some(array))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Wraps a 64-byte array so it serializes through `serialize_signature`.
+fn serde_signature(signature: &[u8; 64]) -> impl serde::Serialize + '_ {
+    struct Signature<'a>(&'a [u8; 64]);
+
+    impl serde::Serialize for Signature<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_signature(self.0, serializer)
+        }
+    }
+
+    Signature(signature)
+}
+
+/// Wraps a 32-byte array so it serializes through `serialize_32_bytes`.
+fn serde_bytes_32(bytes: &[u8; 32]) -> impl serde::Serialize + '_ {
+    struct Bytes32<'a>(&'a [u8; 32]);
+
+    impl serde::Serialize for Bytes32<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_32_bytes(self.0, serializer)
+        }
+    }
+
+    Bytes32(bytes)
+}
+
 /// Encodes a concatenated string into a Base64 URL-safe
 /// format without padding.
 ///
@@ -224,3 +402,43 @@ pub fn concat_struct_base64url_decode(encoded_string: String) -> Result<String,
     String::from_utf8(decoded_bytes)
         .map_err(|e: std::string::FromUtf8Error| format!("UTF-8 conversion error: {}", e))
 }
+
+/// Serializes a value to JSON that can be embedded in an HTML
+/// `<script>` element.
+///
+/// `<`, `>` and `&` are written as `\u003c`, `\u003e` and `\u0026`, so
+/// no string value can close the element with `</script>` or open a
+/// comment with `<!--`. U+2028 and U+2029 are escaped as well, since
+/// older JavaScript engines treat them as line terminators. The result
+/// is still valid JSON and parses to the same value.
+///
+/// # Arguments
+/// * `value`: The value to serialize.
+///
+/// # Returns
+/// * `Result<String, String>`: The escaped JSON, or an error if the
+///                             value cannot be serialized.
+///
+/// # Example
+/// ```
+/// use ironshield_types::to_html_safe_json;
+/// let json = to_html_safe_json(&"</script><script>alert(1)").unwrap();
+/// assert_eq!(json, r#""\u003c/script\u003e\u003cscript\u003ealert(1)""#);
+/// ```
+pub fn to_html_safe_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, String> {
+    let json: String = serde_json::to_string(value)
+        .map_err(|e: serde_json::Error| format!("JSON serialization error: {}", e))?;
+
+    let mut escaped: String = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<'        => escaped.push_str("\\u003c"),
+            '>'        => escaped.push_str("\\u003e"),
+            '&'        => escaped.push_str("\\u0026"),
+            '\u{2028}' => escaped.push_str("\\u2028"),
+            '\u{2029}' => escaped.push_str("\\u2029"),
+            c          => escaped.push(c),
+        }
+    }
+    Ok(escaped)
+}
@@ -0,0 +1,292 @@
+//! # Shamir secret sharing of the signing key.
+//!
+//! For key ceremonies the 32-byte Ed25519 seed is split into `n` shares
+//! of which any `k` recover it (Shamir's scheme over GF(2^8), applied to
+//! each seed byte independently). Fewer than `k` shares reveal nothing
+//! about the seed.
+//!
+//! Each share carries the threshold and the public key of the split
+//! key. Its compact and armored encodings are checksummed, so a share
+//! mistyped during the ceremony is rejected before recovery, and
+//! `recover_signing_key` checks the recovered seed against the public
+//! key, so a wrong or tampered share cannot silently yield a different
+//! key.
+
+use ed25519_dalek::SigningKey;
+use rand::RngCore;
+use rand::rngs::OsRng;
+
+use crate::compact::{
+    CompactReader,
+    CompactWriter
+};
+use crate::CryptoError;
+
+/// Label of the armored share block.
+const SHARE_ARMOR_LABEL: &str = "IRONSHIELD KEY SHARE";
+/// Smallest threshold worth splitting for: with 1 every share is the seed.
+const MIN_SHARE_THRESHOLD: u8 = 2;
+
+/// One share of a split signing key.
+///
+/// * `threshold`:  Number of shares needed to recover the key.
+/// * `index`:      Evaluation point of the share, from 1 to 255.
+/// * `value`:      The share data, one byte per seed byte.
+/// * `public_key`: Public key of the split key, checked on recovery.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SigningKeyShare {
+    pub threshold:  u8,
+    pub index:      u8,
+    pub value:      [u8; 32],
+    pub public_key: [u8; 32],
+}
+
+/// Leaves out `value`, so shares do not end up in logs.
+impl std::fmt::Debug for SigningKeyShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKeyShare")
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("public_key", &hex::encode(self.public_key))
+            .finish_non_exhaustive()
+    }
+}
+
+impl SigningKeyShare {
+    /// # Returns
+    /// * `Result<Vec<u8>, String>`: The version byte, threshold, index,
+    ///                              value and public key, followed by a
+    ///                              CRC-32 trailer.
+    pub fn to_compact_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut writer = CompactWriter::new();
+        writer.write_u8(self.threshold);
+        writer.write_u8(self.index);
+        writer.write_bytes(&self.value);
+        writer.write_bytes(&self.public_key);
+        Ok(writer.finish())
+    }
+
+    /// # Arguments
+    /// * `bytes`: The payload produced by `to_compact_bytes()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The share, or an error if the payload
+    ///                           is corrupted or the share is invalid.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = CompactReader::new(bytes)?;
+        let share = Self {
+            threshold:  reader.read_u8("threshold")?,
+            index:      reader.read_u8("index")?,
+            value:      reader.read_array("value")?,
+            public_key: reader.read_array("public_key")?,
+        };
+        reader.finish()?;
+
+        if share.threshold < MIN_SHARE_THRESHOLD {
+            return Err(format!("Share threshold must be at least {}", MIN_SHARE_THRESHOLD));
+        }
+        if share.index == 0 {
+            return Err("Share index cannot be 0".to_string());
+        }
+        Ok(share)
+    }
+
+    /// Encodes the share as an ASCII-armored block for printing or
+    /// storing on removable media during a key ceremony.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The `-----BEGIN IRONSHIELD KEY SHARE-----`
+    ///                             block.
+    pub fn to_armored_string(&self) -> Result<String, String> {
+        Ok(crate::armor::encode_armor(SHARE_ARMOR_LABEL, &self.to_compact_bytes()?))
+    }
+
+    /// # Arguments
+    /// * `armored`: The block produced by `to_armored_string()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The share, or an error if the armor is
+    ///                           malformed or a checksum does not match.
+    pub fn from_armored_string(armored: &str) -> Result<Self, String> {
+        Self::from_compact_bytes(&crate::armor::decode_armor(SHARE_ARMOR_LABEL, armored)?)
+    }
+}
+
+/// Multiplication in GF(2^8) modulo the AES polynomial
+/// x^8 + x^4 + x^3 + x + 1.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product: u8 = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry: bool = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1B;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(2^8), as `a^254`. `a` must be nonzero.
+fn gf_inv(a: u8) -> u8 {
+    let mut result: u8 = 1;
+    for _ in 0..254 {
+        result = gf_mul(result, a);
+    }
+    result
+}
+
+/// Splits a signing key into `shares` shares, any `threshold` of which
+/// recover it.
+///
+/// # Arguments
+/// * `signing_key`: The key to split.
+/// * `shares`:      Number of shares to create (`n`).
+/// * `threshold`:   Number of shares needed for recovery (`k`), at
+///                  least 2 and at most `shares`.
+///
+/// # Returns
+/// * `Result<Vec<SigningKeyShare>, CryptoError>`: The shares, with
+///                                                indices 1 to `shares`.
+pub fn split_signing_key(
+    signing_key: &SigningKey,
+    shares: u8,
+    threshold: u8
+) -> Result<Vec<SigningKeyShare>, CryptoError> {
+    if threshold < MIN_SHARE_THRESHOLD || threshold > shares {
+        return Err(CryptoError::InvalidKeyFormat(format!(
+            "Threshold must be between {} and the number of shares ({}), got {}",
+            MIN_SHARE_THRESHOLD, shares, threshold
+        ).into()));
+    }
+
+    let seed: [u8; 32] = signing_key.to_bytes();
+    let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+
+    // One random polynomial of degree `threshold - 1` per seed byte,
+    // with the seed byte as its constant term.
+    let mut coefficients: Vec<[u8; 32]> = vec![seed; threshold as usize];
+    for row in coefficients.iter_mut().skip(1) {
+        OsRng.fill_bytes(row);
+    }
+
+    let split: Vec<SigningKeyShare> = (1..=shares).map(|index| {
+        let mut value = [0u8; 32];
+        for (position, byte) in value.iter_mut().enumerate() {
+            // Horner's rule, highest coefficient first.
+            *byte = coefficients.iter()
+                .rev()
+                .fold(0, |acc, row| gf_mul(acc, index) ^ row[position]);
+        }
+        SigningKeyShare { threshold, index, value, public_key }
+    }).collect();
+
+    coefficients.iter_mut().for_each(|row| row.fill(0));
+    Ok(split)
+}
+
+/// Recovers a signing key from at least `threshold` of its shares.
+///
+/// # Arguments
+/// * `shares`: Shares from one `split_signing_key()` call, in any order.
+///
+/// # Returns
+/// * `Result<SigningKey, CryptoError>`: The recovered key, or an error if
+///                                      the shares are too few, mixed
+///                                      from different splits, or
+///                                      recover a key that does not
+///                                      match their public key.
+pub fn recover_signing_key(shares: &[SigningKeyShare]) -> Result<SigningKey, CryptoError> {
+    let first: &SigningKeyShare = shares.first()
+        .ok_or_else(|| CryptoError::InvalidKeyFormat("No key shares provided".into()))?;
+
+    for (position, share) in shares.iter().enumerate() {
+        if share.threshold != first.threshold || share.public_key != first.public_key {
+            return Err(CryptoError::InvalidKeyFormat("Key shares belong to different splits".into()));
+        }
+        if share.index == 0 || shares[..position].iter().any(|other| other.index == share.index) {
+            return Err(CryptoError::InvalidKeyFormat(format!("Invalid or duplicate share index {}", share.index).into()));
+        }
+    }
+    if shares.len() < first.threshold as usize {
+        return Err(CryptoError::InvalidKeyFormat(format!(
+            "Need {} key shares, got {}",
+            first.threshold,
+            shares.len()
+        ).into()));
+    }
+
+    // Lagrange interpolation at x = 0; subtraction in GF(2^8) is XOR.
+    let used: &[SigningKeyShare] = &shares[..first.threshold as usize];
+    let mut seed = [0u8; 32];
+    for share in used {
+        let basis: u8 = used.iter()
+            .filter(|other| other.index != share.index)
+            .fold(1, |acc, other| gf_mul(acc, gf_mul(other.index, gf_inv(other.index ^ share.index))));
+        for (byte, value) in seed.iter_mut().zip(share.value) {
+            *byte ^= gf_mul(basis, value);
+        }
+    }
+
+    let signing_key: SigningKey = SigningKey::from_bytes(&seed);
+    seed.fill(0);
+    if signing_key.verifying_key().to_bytes() != first.public_key {
+        return Err(CryptoError::VerificationFailed("Recovered key does not match the public key of its shares".into()));
+    }
+
+    Ok(signing_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn test_split_and_recover() {
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let shares: Vec<SigningKeyShare> = split_signing_key(&key, 5, 3).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<SigningKeyShare> = subset.iter().map(|i| shares[*i].clone()).collect();
+            assert_eq!(recover_signing_key(&picked).unwrap().to_bytes(), key.to_bytes());
+        }
+        assert!(recover_signing_key(&shares[..2]).is_err());
+        assert!(recover_signing_key(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).is_err());
+
+        let mut tampered: Vec<SigningKeyShare> = shares[..3].to_vec();
+        tampered[1].value[0] ^= 1;
+        assert!(matches!(recover_signing_key(&tampered), Err(CryptoError::VerificationFailed(_))));
+
+        let other: Vec<SigningKeyShare> = split_signing_key(&SigningKey::from_bytes(&[8; 32]), 5, 3).unwrap();
+        assert!(recover_signing_key(&[shares[0].clone(), shares[1].clone(), other[2].clone()]).is_err());
+
+        assert!(split_signing_key(&key, 3, 4).is_err());
+        assert!(split_signing_key(&key, 3, 1).is_err());
+        assert!(!format!("{:?}", shares[0]).contains(&hex::encode(shares[0].value)));
+    }
+
+    #[test]
+    fn test_share_encoding() {
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let share: SigningKeyShare = split_signing_key(&key, 2, 2).unwrap().remove(1);
+
+        let armored: String = share.to_armored_string().unwrap();
+        assert!(armored.starts_with("-----BEGIN IRONSHIELD KEY SHARE-----"));
+        assert_eq!(SigningKeyShare::from_armored_string(&armored).unwrap(), share);
+
+        let mut bytes: Vec<u8> = share.to_compact_bytes().unwrap();
+        bytes[3] ^= 1;
+        assert!(SigningKeyShare::from_compact_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! # Signed configuration snapshots.
+//!
+//! Edges pull their policy configuration (difficulty overrides, solver
+//! settings, ...) from a KV store. Anyone able to write to that store
+//! could otherwise weaken every edge at once, so the control plane
+//! publishes a `SignedConfig<T>`: the configuration serialized to JSON,
+//! signed with the root key together with its issue time and maximum
+//! age. Edges call `load()`, which checks the signature and freshness
+//! before deserializing anything.
+//!
+//! The signature covers the JSON text exactly as published, so the
+//! configuration type does not need a canonical encoding.
+
+use std::marker::PhantomData;
+
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
+    SigningKey
+};
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+use serde::{
+    Deserialize,
+    Serialize,
+    de::DeserializeOwned
+};
+
+use crate::serde_utils::{
+    deserialize_signature,
+    serialize_signature
+};
+use crate::{
+    CryptoError,
+    ErrorDetail
+};
+
+/// Domain separation prefix of the config signing message, so config
+/// signatures can never be mistaken for challenge or token signatures.
+const CONFIG_SIGNING_CONTEXT: &str = "ironshield-config-v1";
+
+/// A configuration signed by the root key.
+///
+/// * `payload`:    The configuration as JSON.
+/// * `issued_at`:  Unix millis at which the snapshot was signed.
+/// * `max_age_ms`: How long after `issued_at` edges may use it.
+/// * `signature`:  Ed25519 signature over `signing_message()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SignedConfig<T> {
+    pub payload:    String,
+    pub issued_at:  i64,
+    pub max_age_ms: i64,
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature:  [u8; 64],
+    #[serde(skip)]
+    config_type:    PhantomData<fn() -> T>,
+}
+
+impl<T> SignedConfig<T> {
+    /// Serializes and signs a configuration.
+    ///
+    /// # Arguments
+    /// * `config`:      The configuration to publish.
+    /// * `issued_at`:   The current time in unix millis.
+    /// * `max_age_ms`:  How long edges may use the snapshot, positive.
+    /// * `private_key`: The root signing key.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The signed snapshot, or an error if
+    ///                           `max_age_ms` is not positive or the
+    ///                           configuration cannot be serialized.
+    #[cfg(feature = "sign")]
+    pub fn sign(
+        config:      &T,
+        issued_at:   i64,
+        max_age_ms:  i64,
+        private_key: &SigningKey,
+    ) -> Result<Self, String>
+    where
+        T: Serialize,
+    {
+        if max_age_ms <= 0 {
+            return Err("max_age_ms must be positive".to_string());
+        }
+        let payload: String = serde_json::to_string(config)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+        let mut signed = Self {
+            payload,
+            issued_at,
+            max_age_ms,
+            signature: [0u8; 64],
+            config_type: PhantomData,
+        };
+        signed.signature = private_key.sign(signed.signing_message().as_bytes()).to_bytes();
+        Ok(signed)
+    }
+
+    /// Creates the message covered by `signature`.
+    ///
+    /// # Returns
+    /// * `String`: `context|issued_at|max_age_ms|payload`.
+    pub fn signing_message(&self) -> String {
+        format!("{}|{}|{}|{}", CONFIG_SIGNING_CONTEXT, self.issued_at, self.max_age_ms, self.payload)
+    }
+
+    /// # Returns
+    /// * `i64`: Unix millis after which edges must not use the snapshot.
+    pub fn expires_at(&self) -> i64 {
+        self.issued_at.saturating_add(self.max_age_ms)
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if `now` lies between `issued_at` and
+    ///           `expires_at()`, both included.
+    pub fn is_fresh_at(&self, now: i64) -> bool {
+        self.max_age_ms > 0 && (self.issued_at..=self.expires_at()).contains(&now)
+    }
+
+    /// # Arguments
+    /// * `public_key`: The root public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the signature is valid.
+    pub fn verify_signature_with_key(&self, public_key: &[u8; 32]) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+
+        verifying_key.verify(self.signing_message().as_bytes(), &Signature::from_bytes(&self.signature))
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Config signature verification failed: {}", e), e)))
+    }
+
+    /// Verifies the snapshot and returns the configuration.
+    ///
+    /// # Arguments
+    /// * `public_key`: The root public key.
+    /// * `now`:        The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<T, CryptoError>`: The configuration, or an error if the
+    ///                             signature is invalid, the snapshot is
+    ///                             stale or issued in the future, or the
+    ///                             payload does not parse as `T`.
+    pub fn load(&self, public_key: &[u8; 32], now: i64) -> Result<T, CryptoError>
+    where
+        T: DeserializeOwned,
+    {
+        self.verify_signature_with_key(public_key)?;
+        if !self.is_fresh_at(now) {
+            return Err(CryptoError::VerificationFailed(format!(
+                "Config issued at {} is not valid at {}",
+                self.issued_at,
+                now
+            ).into()));
+        }
+
+        serde_json::from_str(&self.payload)
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Signed config does not parse: {}", e), e)))
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+    use crate::{
+        DifficultyOverrides,
+        DifficultySetting
+    };
+
+    fn overrides() -> DifficultyOverrides {
+        let mut overrides = DifficultyOverrides::new();
+        overrides.websites.insert("example.com".to_string(), DifficultySetting::Difficulty(50_000));
+        overrides
+    }
+
+    #[test]
+    fn test_sign_and_load() {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let signed = SignedConfig::sign(&overrides(), 1_000, 60_000, &key).unwrap();
+
+        let json: String = serde_json::to_string(&signed).unwrap();
+        let stored: SignedConfig<DifficultyOverrides> = serde_json::from_str(&json).unwrap();
+        assert_eq!(stored.load(&public_key, 30_000).unwrap(), overrides());
+        assert!(stored.load(&public_key, 61_001).unwrap_err().to_string().contains("not valid at"));
+        assert!(stored.load(&public_key, 999).is_err());
+        assert!(SignedConfig::sign(&overrides(), 1_000, 0, &key).is_err());
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let public_key: [u8; 32] = key.verifying_key().to_bytes();
+        let signed = SignedConfig::sign(&overrides(), 1_000, 60_000, &key).unwrap();
+
+        let weakened = SignedConfig { payload: signed.payload.replace("50000", "1"), ..signed.clone() };
+        assert!(matches!(weakened.load(&public_key, 2_000), Err(CryptoError::VerificationFailed(_))));
+        let extended = SignedConfig { max_age_ms: i64::MAX, ..signed.clone() };
+        assert!(extended.load(&public_key, 2_000).is_err());
+
+        let other_key: [u8; 32] = SigningKey::from_bytes(&[9; 32]).verifying_key().to_bytes();
+        assert!(signed.load(&other_key, 2_000).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! # The proof-of-work solution and its byte encoding.
+//!
+//! Solutions travel as signed `i64` in JSON and headers, but the hash
+//! only ever sees 8 raw bytes. `Solution` pins that encoding down in
+//! one place: the nonce is taken as a `u64` with the same bits as the
+//! wire `i64` and written little-endian, so `-1` hashes as eight `0xFF`
+//! bytes. `SolutionVerifier` and `SolutionBatch` hash through
+//! `Solution::to_bytes`, and solvers in other languages must match it.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+/// Length of an encoded `Solution`.
+pub const SOLUTION_NONCE_LEN: usize = 8;
+
+/// A proof-of-work nonce with a fixed byte encoding.
+///
+/// * `nonce`: The nonce, bit-for-bit the same as the wire `i64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct Solution {
+    pub nonce: u64,
+}
+
+impl Solution {
+    /// # Arguments
+    /// * `nonce`: The nonce.
+    ///
+    /// # Returns
+    /// * `Self`: The solution.
+    pub const fn new(nonce: u64) -> Self {
+        Self { nonce }
+    }
+
+    /// # Arguments
+    /// * `nonce`: A nonce as carried in `IronShieldChallengeResponse`.
+    ///
+    /// # Returns
+    /// * `Self`: The solution with the same bits.
+    pub const fn from_wire(nonce: i64) -> Self {
+        Self { nonce: nonce as u64 }
+    }
+
+    /// # Returns
+    /// * `i64`: The nonce as carried in `IronShieldChallengeResponse`.
+    pub const fn to_wire(self) -> i64 {
+        self.nonce as i64
+    }
+
+    /// The bytes appended to the decoded `random_nonce` when hashing:
+    /// the nonce as 8 little-endian bytes.
+    ///
+    /// # Returns
+    /// * `[u8; 8]`: The encoded nonce.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::Solution;
+    /// assert_eq!(Solution::new(0x0102).to_bytes(), [0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+    /// assert_eq!(Solution::from_wire(-1).to_bytes(), [0xFF; 8]);
+    /// ```
+    pub const fn to_bytes(self) -> [u8; SOLUTION_NONCE_LEN] {
+        self.nonce.to_le_bytes()
+    }
+
+    /// # Arguments
+    /// * `bytes`: A nonce encoded by `to_bytes`.
+    ///
+    /// # Returns
+    /// * `Self`: The decoded solution.
+    pub const fn from_bytes(bytes: [u8; SOLUTION_NONCE_LEN]) -> Self {
+        Self { nonce: u64::from_le_bytes(bytes) }
+    }
+}
+
+impl From<i64> for Solution {
+    fn from(nonce: i64) -> Self {
+        Self::from_wire(nonce)
+    }
+}
+
+impl From<Solution> for i64 {
+    fn from(solution: Solution) -> Self {
+        solution.to_wire()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_matches_wire_nonce() {
+        for nonce in [0i64, 1, -1, i64::MIN, i64::MAX, 0x0102_0304_0506_0708] {
+            let solution = Solution::from(nonce);
+            assert_eq!(solution.to_bytes(), nonce.to_le_bytes());
+            assert_eq!(i64::from(Solution::from_bytes(solution.to_bytes())), nonce);
+        }
+        assert_eq!(serde_json::to_string(&Solution::new(7)).unwrap(), r#"{"nonce":7}"#);
+    }
+}
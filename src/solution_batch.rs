@@ -0,0 +1,188 @@
+//! # Structure-of-arrays batches for bulk solution verification.
+//!
+//! Verifying a queue of responses one `IronShieldChallengeResponse` at
+//! a time chases pointers through every challenge. `SolutionBatch`
+//! copies only what the proof-of-work check needs into three parallel
+//! arrays: the decoded 16-byte random nonces, the solution nonces and
+//! the targets. Every message hashed is then exactly 24 bytes, a single
+//! SHA-256 block, and `verify_all()` walks the arrays in fixed-size
+//! lanes, which is the shape multi-buffer and SIMD SHA-256
+//! implementations want.
+
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::bytes::{
+    meets_target,
+    nonce_to_le_bytes
+};
+use crate::{
+    IronShieldChallengeResponse,
+    SOLUTION_NONCE_LEN
+};
+
+/// Length of the decoded `random_nonce` of issued challenges.
+const BATCH_RANDOM_NONCE_LEN: usize = 16;
+/// Number of solutions hashed per lane group in `verify_all()`.
+const BATCH_LANES: usize = 8;
+
+/// Solutions to check, stored column-wise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolutionBatch {
+    random_nonces: Vec<[u8; BATCH_RANDOM_NONCE_LEN]>,
+    nonces:        Vec<i64>,
+    targets:       Vec<[u8; 32]>,
+}
+
+impl SolutionBatch {
+    /// # Arguments
+    /// * `capacity`: Number of solutions to reserve room for.
+    ///
+    /// # Returns
+    /// * `Self`: An empty batch.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            random_nonces: Vec::with_capacity(capacity),
+            nonces:        Vec::with_capacity(capacity),
+            targets:       Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a solution from its parts.
+    ///
+    /// # Arguments
+    /// * `random_nonce`:    The challenge's hex-encoded random nonce.
+    /// * `challenge_param`: The challenge's target.
+    /// * `nonce`:           The candidate solution.
+    ///
+    /// # Returns
+    /// * `Result<usize, String>`: The index of the solution in the
+    ///                            batch, or an error if `random_nonce`
+    ///                            is not 16 bytes of hex.
+    pub fn push(&mut self, random_nonce: &str, challenge_param: &[u8; 32], nonce: i64) -> Result<usize, String> {
+        let mut decoded: [u8; BATCH_RANDOM_NONCE_LEN] = [0u8; BATCH_RANDOM_NONCE_LEN];
+        hex::decode_to_slice(random_nonce, &mut decoded)
+            .map_err(|_| format!("Batched random_nonce must be {} bytes of hex", BATCH_RANDOM_NONCE_LEN))?;
+
+        self.random_nonces.push(decoded);
+        self.nonces.push(nonce);
+        self.targets.push(*challenge_param);
+        Ok(self.nonces.len() - 1)
+    }
+
+    /// Adds a response's solution. Signature and expiry are validated
+    /// separately.
+    ///
+    /// # Arguments
+    /// * `response`: The response to check.
+    ///
+    /// # Returns
+    /// * `Result<usize, String>`: The index of the solution in the
+    ///                            batch, or an error if the challenge is
+    ///                            not answered by a single nonce or its
+    ///                            `random_nonce` cannot be batched.
+    pub fn push_response(&mut self, response: &IronShieldChallengeResponse) -> Result<usize, String> {
+        let challenge = &response.solved_challenge;
+        if challenge.solution_count() != 1 || challenge.time_lock.is_some() || challenge.vdf.is_some() {
+            return Err("Only single-nonce challenges can be batched".to_string());
+        }
+        self.push(challenge.random_nonce.as_str(), &challenge.challenge_param, response.solution)
+    }
+
+    /// # Returns
+    /// * `usize`: Number of solutions in the batch.
+    pub fn len(&self) -> usize {
+        self.nonces.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if the batch holds no solutions.
+    pub fn is_empty(&self) -> bool {
+        self.nonces.is_empty()
+    }
+
+    /// Removes every solution, keeping the allocations for reuse.
+    pub fn clear(&mut self) {
+        self.random_nonces.clear();
+        self.nonces.clear();
+        self.targets.clear();
+    }
+
+    /// Checks every solution: SHA256(random_nonce_bytes + nonce_bytes)
+    /// must be less than the target, as in
+    /// `IronShieldChallenge::check_solution`.
+    ///
+    /// # Returns
+    /// * `Vec<bool>`: For each index, whether the solution meets its
+    ///                target.
+    pub fn verify_all(&self) -> Vec<bool> {
+        let mut results: Vec<bool> = Vec::with_capacity(self.len());
+        let mut messages: [[u8; BATCH_RANDOM_NONCE_LEN + SOLUTION_NONCE_LEN]; BATCH_LANES] = [[0u8; BATCH_RANDOM_NONCE_LEN + SOLUTION_NONCE_LEN]; BATCH_LANES];
+        let mut hashes: [[u8; 32]; BATCH_LANES] = [[0u8; 32]; BATCH_LANES];
+
+        let groups = self.random_nonces.chunks(BATCH_LANES)
+            .zip(self.nonces.chunks(BATCH_LANES))
+            .zip(self.targets.chunks(BATCH_LANES));
+
+        for ((random_nonces, nonces), targets) in groups {
+            for ((message, random_nonce), nonce) in messages.iter_mut().zip(random_nonces).zip(nonces) {
+                message[..BATCH_RANDOM_NONCE_LEN].copy_from_slice(random_nonce);
+                message[BATCH_RANDOM_NONCE_LEN..].copy_from_slice(&nonce_to_le_bytes(*nonce));
+            }
+            for (hash, message) in hashes.iter_mut().zip(&messages[..nonces.len()]) {
+                *hash = Sha256::digest(message).into();
+            }
+            results.extend(hashes.iter().zip(targets).map(|(hash, target)| meets_target(hash, target)));
+        }
+        results
+    }
+
+    /// # Returns
+    /// * `Vec<usize>`: Indices of the solutions that fail `verify_all()`.
+    pub fn failed_indices(&self) -> Vec<usize> {
+        self.verify_all()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, passed)| (!passed).then_some(index))
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        IronShieldChallenge,
+        SigningKey
+    };
+
+    #[test]
+    fn test_verify_all_matches_single_checks() {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let challenges: Vec<IronShieldChallenge> = (0..3)
+            .map(|_| IronShieldChallenge::new("example.com".parse().unwrap(), 4, key.clone(), key.verifying_key().to_bytes()))
+            .collect();
+
+        let mut batch = SolutionBatch::with_capacity(60);
+        let mut expected: Vec<bool> = Vec::new();
+        for nonce in 0..20i64 {
+            for challenge in &challenges {
+                batch.push_response(&IronShieldChallengeResponse::new(challenge.clone(), nonce)).unwrap();
+                expected.push(challenge.check_solution(nonce).is_ok());
+            }
+        }
+
+        assert_eq!(batch.len(), 60);
+        assert_eq!(batch.verify_all(), expected);
+        let failed: Vec<usize> = expected.iter().enumerate().filter(|(_, passed)| !**passed).map(|(index, _)| index).collect();
+        assert_eq!(batch.failed_indices(), failed);
+
+        assert!(batch.push("abcd", &[0xFF; 32], 0).is_err());
+        let k_of_n = challenges[0].clone().with_required_solutions(2, &key).unwrap();
+        assert!(batch.push_response(&IronShieldChallengeResponse::new(k_of_n, 0)).is_err());
+        batch.clear();
+        assert!(batch.is_empty() && batch.verify_all().is_empty());
+    }
+}
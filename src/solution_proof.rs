@@ -0,0 +1,201 @@
+//! # Minimal, nonce-only solution submissions.
+//!
+//! An `IronShieldChallengeResponse` echoes the whole signed challenge
+//! back, which is several hundred bytes once encoded. An issuer that
+//! keeps its challenges in a `ChallengeStore` already has them, so a
+//! client can submit a `SolutionProof` instead: the challenge id and
+//! the 8-byte nonce, 40 bytes in total. The verifier looks the
+//! challenge up by id and recomputes the hash itself.
+//!
+//! Encoding rules, so every proof has exactly one encoding:
+//! * The binary form is `challenge_id` (32 bytes) followed by the nonce
+//!   as the 8 little-endian bytes that are hashed; no version byte,
+//!   length prefix or trailer. Any other length is rejected.
+//! * The header form is that binary form in unpadded base64url, always
+//!   54 characters. Padding and non-canonical trailing bits are
+//!   rejected.
+
+use base64::{
+    Engine,
+    engine::general_purpose::URL_SAFE_NO_PAD
+};
+
+use crate::bytes::{
+    nonce_from_le_bytes,
+    nonce_to_le_bytes
+};
+use crate::{
+    ChallengeStore,
+    IronShieldChallenge,
+    IronShieldChallengeResponse
+};
+
+/// Length of the binary encoding.
+pub const SOLUTION_PROOF_LEN: usize = 40;
+/// Length of the challenge id at the start of the binary encoding.
+const CHALLENGE_ID_LEN: usize = 32;
+
+/// A solution submitted as the challenge id and nonce only.
+///
+/// * `challenge_id`: `challenge_id()` of the solved challenge.
+/// * `nonce`:        The proof-of-work solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SolutionProof {
+    pub challenge_id: [u8; 32],
+    pub nonce:        i64,
+}
+
+impl SolutionProof {
+    /// # Arguments
+    /// * `challenge`: The solved challenge.
+    /// * `nonce`:     The solution found for it.
+    ///
+    /// # Returns
+    /// * `Self`: The proof to submit.
+    pub fn new(challenge: &IronShieldChallenge, nonce: i64) -> Self {
+        Self {
+            challenge_id: challenge.challenge_id(),
+            nonce,
+        }
+    }
+
+    /// # Arguments
+    /// * `response`: A full challenge response.
+    ///
+    /// # Returns
+    /// * `Self`: The same solution without the echoed challenge.
+    pub fn from_response(response: &IronShieldChallengeResponse) -> Self {
+        Self::new(&response.solved_challenge, response.solution)
+    }
+
+    /// # Returns
+    /// * `[u8; 40]`: `challenge_id` followed by the little-endian nonce.
+    pub fn to_bytes(&self) -> [u8; SOLUTION_PROOF_LEN] {
+        let mut bytes = [0u8; SOLUTION_PROOF_LEN];
+        bytes[..CHALLENGE_ID_LEN].copy_from_slice(&self.challenge_id);
+        bytes[CHALLENGE_ID_LEN..].copy_from_slice(&nonce_to_le_bytes(self.nonce));
+        bytes
+    }
+
+    /// # Arguments
+    /// * `bytes`: Exactly 40 bytes produced by `to_bytes()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The proof, or an error if the length is
+    ///                           not exactly 40 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != SOLUTION_PROOF_LEN {
+            return Err(format!("Solution proof must be {} bytes, got {}", SOLUTION_PROOF_LEN, bytes.len()));
+        }
+
+        let (challenge_id, nonce) = bytes.split_at(CHALLENGE_ID_LEN);
+        Ok(Self {
+            challenge_id: challenge_id.try_into().map_err(|_| "Invalid challenge id")?,
+            nonce:        nonce_from_le_bytes(nonce)?,
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The proof as 54 characters of unpadded base64url.
+    pub fn to_base64url_header(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_bytes())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The proof, or an error if the header is
+    ///                           not the canonical encoding of 40 bytes.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let bytes: Vec<u8> = URL_SAFE_NO_PAD.decode(encoded_header)
+            .map_err(|e| format!("Invalid base64url solution proof: {}", e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Checks the proof against the challenge it names.
+    ///
+    /// # Arguments
+    /// * `challenge`: The stored challenge.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if `challenge` is the one the
+    ///                         proof names and `nonce` solves it.
+    pub fn verify(&self, challenge: &IronShieldChallenge) -> Result<(), String> {
+        if challenge.challenge_id() != self.challenge_id {
+            return Err("Solution proof names a different challenge".to_string());
+        }
+        challenge.check_solutions(&[self.nonce])
+    }
+
+    /// Looks the challenge up, checks the solution and redeems the
+    /// challenge. The challenge is only removed from `store` once the
+    /// solution is valid, so a wrong guess does not burn it.
+    ///
+    /// # Arguments
+    /// * `store`: The store holding issued challenges.
+    /// * `now`:   The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `Result<IronShieldChallengeResponse, String>`: The equivalent full
+    ///   response, or an error if the challenge is unknown, no longer
+    ///   redeemable, already redeemed, or not solved by `nonce`.
+    pub fn redeem(
+        &self,
+        store: &mut impl ChallengeStore,
+        now: i64
+    ) -> Result<IronShieldChallengeResponse, String> {
+        let challenge: IronShieldChallenge = store.get_by_id(&self.challenge_id, now)?
+            .ok_or("Unknown or expired challenge")?;
+        self.verify(&challenge)?;
+
+        let challenge: IronShieldChallenge = store.take(&self.challenge_id, now)?
+            .ok_or("Challenge was already redeemed")?;
+        Ok(IronShieldChallengeResponse::new(challenge, self.nonce))
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        MemoryChallengeStore,
+        SigningKey
+    };
+
+    fn solved() -> (IronShieldChallenge, i64) {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 4, key.clone(), key.verifying_key().to_bytes());
+        let nonce: i64 = (0..).find(|nonce| challenge.check_solution(*nonce).is_ok()).unwrap();
+        (challenge, nonce)
+    }
+
+    #[test]
+    fn test_encoding() {
+        let (challenge, _) = solved();
+        let proof = SolutionProof::from_response(&IronShieldChallengeResponse::new(challenge, -2));
+        assert_eq!(&proof.to_bytes()[32..], &[0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let header: String = proof.to_base64url_header();
+        assert_eq!(header.len(), 54);
+        assert_eq!(SolutionProof::from_base64url_header(&header).unwrap(), proof);
+        assert!(SolutionProof::from_base64url_header(&format!("{}==", header)).is_err());
+        assert!(SolutionProof::from_base64url_header(&header[..53]).is_err());
+        assert!(SolutionProof::from_bytes(&[0; 41]).is_err());
+    }
+
+    #[test]
+    fn test_redeem() {
+        let (challenge, nonce) = solved();
+        let mut store = MemoryChallengeStore::new(8);
+        store.put(challenge.clone()).unwrap();
+        let now: i64 = challenge.created_time;
+
+        let wrong_nonce: i64 = (0..).find(|nonce| challenge.check_solution(*nonce).is_err()).unwrap();
+        assert!(SolutionProof::new(&challenge, wrong_nonce).redeem(&mut store, now).is_err());
+        let response = SolutionProof::new(&challenge, nonce).redeem(&mut store, now).unwrap();
+        assert_eq!(response.solved_challenge, challenge);
+        assert_eq!(response.solution, nonce);
+        assert!(SolutionProof::new(&challenge, nonce).redeem(&mut store, now).is_err());
+    }
+}
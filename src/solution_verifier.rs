@@ -0,0 +1,168 @@
+//! # Fast repeated solution checks for one challenge.
+//!
+//! Every proof-of-work hash of a challenge starts with the same
+//! `random_nonce` bytes. `SolutionVerifier` hashes that prefix once and
+//! clones the SHA-256 state for each candidate, so batch-verifying many
+//! solutions only pays for the 8 nonce bytes each.
+
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::bytes::meets_target;
+use crate::{
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    Solution
+};
+
+/// Precomputed proof-of-work check for a single challenge.
+#[derive(Debug, Clone)]
+pub struct SolutionVerifier {
+    /// SHA-256 state after absorbing the decoded `random_nonce`.
+    prefix:          Sha256,
+    challenge_param: [u8; 32],
+}
+
+impl SolutionVerifier {
+    /// Constructor for creating a `SolutionVerifier` for a challenge.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge whose solutions will be checked.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The verifier, or an error if the
+    ///                           `random_nonce` is not valid hex.
+    pub fn new(challenge: &IronShieldChallenge) -> Result<Self, String> {
+        Self::from_parts(challenge.random_nonce.as_str(), &challenge.challenge_param)
+    }
+
+    /// Same as `new`, from the two fields the check depends on. The
+    /// nonce is decoded in stack-sized chunks, so nothing is allocated.
+    ///
+    /// # Arguments
+    /// * `random_nonce`:    The challenge's hex-encoded random nonce.
+    /// * `challenge_param`: The challenge's target.
+    pub fn from_parts(random_nonce: &str, challenge_param: &[u8; 32]) -> Result<Self, String> {
+        let hex_bytes: &[u8] = random_nonce.as_bytes();
+        if !hex_bytes.len().is_multiple_of(2) {
+            return Err("Failed to decode random_nonce hex string".to_string());
+        }
+
+        let mut prefix = Sha256::new();
+        let mut buffer: [u8; 32] = [0u8; 32];
+        for chunk in hex_bytes.chunks(buffer.len() * 2) {
+            let decoded: &mut [u8] = &mut buffer[..chunk.len() / 2];
+            hex::decode_to_slice(chunk, decoded)
+                .map_err(|_| "Failed to decode random_nonce hex string")?;
+            prefix.update(&*decoded);
+        }
+
+        Ok(Self {
+            prefix,
+            challenge_param: *challenge_param,
+        })
+    }
+
+    /// # Arguments
+    /// * `nonce`: The candidate solution.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if SHA256(random_nonce_bytes + nonce_bytes)
+    ///           is less than `challenge_param`.
+    pub fn check(&self, nonce: i64) -> bool {
+        self.check_solution(Solution::from_wire(nonce))
+    }
+
+    /// The shared proof-of-work hash: the `random_nonce` prefix followed
+    /// by `Solution::to_bytes`.
+    ///
+    /// # Arguments
+    /// * `solution`: The candidate solution.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the hash is less than `challenge_param`.
+    pub fn check_solution(&self, solution: Solution) -> bool {
+        let mut hasher: Sha256 = self.prefix.clone();
+        hasher.update(solution.to_bytes());
+        let hash: [u8; 32] = hasher.finalize().into();
+        meets_target(&hash, &self.challenge_param)
+    }
+
+    /// Same as `check`, with the error message used across the crate.
+    pub fn verify(&self, nonce: i64) -> Result<(), String> {
+        if self.check(nonce) {
+            Ok(())
+        } else {
+            Err("Solution does not meet the challenge target".to_string())
+        }
+    }
+
+    /// Checks a response's solution with this verifier.
+    ///
+    /// The caller must make sure the response is for the challenge the
+    /// verifier was built from, e.g. by comparing challenge signatures.
+    ///
+    /// # Arguments
+    /// * `response`: The response to check.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the solution meets the target.
+    pub fn verify_response(&self, response: &IronShieldChallengeResponse) -> Result<(), String> {
+        self.verify(response.solution)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::bytes::nonce_to_le_bytes;
+    use crate::SigningKey;
+
+    fn challenge() -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        IronShieldChallenge::new("example.com".parse().unwrap(), 64, key.clone(), key.verifying_key().to_bytes())
+    }
+
+    #[test]
+    fn test_matches_full_hash() {
+        let challenge = challenge();
+        let verifier = SolutionVerifier::new(&challenge).unwrap();
+
+        for nonce in -500i64..500 {
+            let mut hasher = Sha256::new();
+            hasher.update(challenge.random_nonce.decode().unwrap());
+            hasher.update(nonce_to_le_bytes(nonce));
+            let expected: bool = meets_target(&hasher.finalize().into(), &challenge.challenge_param);
+            assert_eq!(verifier.check(nonce), expected);
+        }
+    }
+
+    #[test]
+    fn test_long_random_nonce_is_chunked() {
+        let random_nonce: String = "ab".repeat(100);
+        let verifier = SolutionVerifier::from_parts(&random_nonce, &[0x80; 32]).unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(hex::decode(&random_nonce).unwrap());
+        hasher.update(nonce_to_le_bytes(7));
+        assert_eq!(verifier.check(7), meets_target(&hasher.finalize().into(), &[0x80; 32]));
+    }
+
+    #[test]
+    fn test_verify_response() {
+        let challenge = challenge();
+        let verifier = SolutionVerifier::new(&challenge).unwrap();
+        let solution: i64 = (0..100_000).find(|nonce| verifier.check(*nonce)).unwrap();
+
+        assert!(verifier.verify_response(&IronShieldChallengeResponse::new(challenge.clone(), solution)).is_ok());
+        for nonce in solution..solution + 100 {
+            assert_eq!(verifier.verify(nonce).is_ok(), verifier.check(nonce));
+        }
+
+        let malformed = IronShieldChallenge { random_nonce: "abc".parse().unwrap(), ..challenge.clone() };
+        assert!(SolutionVerifier::new(&malformed).is_err());
+        assert!(SolutionVerifier::from_parts("abc", &challenge.challenge_param).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! # Fixed-layout solver progress shared between threads.
+//!
+//! Threaded WASM solvers run one worker per core on a shared
+//! `WebAssembly.Memory`, so a `SolverProgress` placed in that memory is
+//! a `SharedArrayBuffer` region the page can poll with `Atomics`
+//! without a `postMessage` round trip. The struct is `#[repr(C)]` with
+//! the offsets below, checked at compile time:
+//!
+//! | Offset | Size | Field          | JS view                    |
+//! |--------|------|----------------|----------------------------|
+//! | 0      | 4    | `state`        | `Int32Array`, index 0      |
+//! | 4      | 4    | `worker_count` | `Int32Array`, index 1      |
+//! | 8      | 8    | `attempts`     | `BigInt64Array`, index 1   |
+//! | 16     | 8    | `solution`     | `BigInt64Array`, index 2   |
+//! | 24     | 4    | `found_by`     | `Int32Array`, index 6      |
+//! | 28     | 4    | reserved, zero | `Int32Array`, index 7      |
+//!
+//! Indices are relative to a view starting at the struct. The block is
+//! 32 bytes with 8-byte alignment, and all fields are little-endian, as
+//! WASM memory always is. `state` holds a `SolverState`; `solution`
+//! and `found_by` are only meaningful once `state` reads `Solved`,
+//! because the winning worker writes them before publishing the state
+//! with release ordering.
+
+use std::sync::atomic::{
+    AtomicI64,
+    AtomicU32,
+    AtomicU64,
+    Ordering
+};
+
+/// Size in bytes of `SolverProgress`.
+pub const SOLVER_PROGRESS_SIZE:     usize = 32;
+/// Byte offset of `state`.
+pub const PROGRESS_STATE_OFFSET:    usize = 0;
+/// Byte offset of `worker_count`.
+pub const PROGRESS_WORKERS_OFFSET:  usize = 4;
+/// Byte offset of `attempts`.
+pub const PROGRESS_ATTEMPTS_OFFSET: usize = 8;
+/// Byte offset of `solution`.
+pub const PROGRESS_SOLUTION_OFFSET: usize = 16;
+/// Byte offset of `found_by`.
+pub const PROGRESS_FOUND_BY_OFFSET: usize = 24;
+
+/// State of a shared solve.
+///
+/// * `Running`:   Workers are searching.
+/// * `Solved`:    A worker published a solution.
+/// * `Exhausted`: The nonce space was searched without a solution.
+/// * `Cancelled`: The page stopped the solve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum SolverState {
+    Running   = 0,
+    Solved    = 1,
+    Exhausted = 2,
+    Cancelled = 3,
+}
+
+/// Internal state while the winning worker writes its solution; reads
+/// as `Running` from outside.
+const STATE_PUBLISHING: u32 = 4;
+
+/// Progress and result of one solve, shared by workers and the page.
+#[derive(Debug, Default)]
+#[repr(C)]
+pub struct SolverProgress {
+    state:        AtomicU32,
+    worker_count: AtomicU32,
+    attempts:     AtomicU64,
+    solution:     AtomicI64,
+    found_by:     AtomicU32,
+    _reserved:    AtomicU32,
+}
+
+const _: () = {
+    assert!(std::mem::size_of::<SolverProgress>() == SOLVER_PROGRESS_SIZE);
+    assert!(std::mem::align_of::<SolverProgress>() == 8);
+    assert!(std::mem::offset_of!(SolverProgress, state) == PROGRESS_STATE_OFFSET);
+    assert!(std::mem::offset_of!(SolverProgress, worker_count) == PROGRESS_WORKERS_OFFSET);
+    assert!(std::mem::offset_of!(SolverProgress, attempts) == PROGRESS_ATTEMPTS_OFFSET);
+    assert!(std::mem::offset_of!(SolverProgress, solution) == PROGRESS_SOLUTION_OFFSET);
+    assert!(std::mem::offset_of!(SolverProgress, found_by) == PROGRESS_FOUND_BY_OFFSET);
+};
+
+impl SolverProgress {
+    /// # Returns
+    /// * `Self`: A `Running` block with zeroed counters.
+    pub const fn new() -> Self {
+        Self {
+            state:        AtomicU32::new(SolverState::Running as u32),
+            worker_count: AtomicU32::new(0),
+            attempts:     AtomicU64::new(0),
+            solution:     AtomicI64::new(0),
+            found_by:     AtomicU32::new(0),
+            _reserved:    AtomicU32::new(0),
+        }
+    }
+
+    /// # Returns
+    /// * `SolverState`: The current state.
+    pub fn state(&self) -> SolverState {
+        match self.state.load(Ordering::Acquire) {
+            1 => SolverState::Solved,
+            2 => SolverState::Exhausted,
+            3 => SolverState::Cancelled,
+            _ => SolverState::Running,
+        }
+    }
+
+    /// # Returns
+    /// * `bool`: `true` once the solve has ended in any way; workers
+    ///           poll this between batches.
+    pub fn is_finished(&self) -> bool {
+        self.state() != SolverState::Running
+    }
+
+    /// # Arguments
+    /// * `worker_count`: Number of workers taking part.
+    pub fn set_worker_count(&self, worker_count: u32) {
+        self.worker_count.store(worker_count, Ordering::Relaxed);
+    }
+
+    /// # Returns
+    /// * `u32`: Number of workers taking part.
+    pub fn worker_count(&self) -> u32 {
+        self.worker_count.load(Ordering::Relaxed)
+    }
+
+    /// Adds a worker's batch to the attempt counter.
+    ///
+    /// # Arguments
+    /// * `attempts`: Nonces tried since the last call.
+    pub fn add_attempts(&self, attempts: u64) {
+        self.attempts.fetch_add(attempts, Ordering::Relaxed);
+    }
+
+    /// # Returns
+    /// * `u64`: Nonces tried so far by all workers.
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    /// Publishes a solution unless the solve has already ended. Only
+    /// the first of several racing workers succeeds.
+    ///
+    /// # Arguments
+    /// * `nonce`:  The solution found.
+    /// * `worker`: Index of the worker that found it.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if this call published the solution.
+    pub fn publish_solution(&self, nonce: i64, worker: u32) -> bool {
+        if self.state.compare_exchange(
+            SolverState::Running as u32,
+            STATE_PUBLISHING,
+            Ordering::Acquire,
+            Ordering::Relaxed
+        ).is_err() {
+            return false;
+        }
+
+        self.solution.store(nonce, Ordering::Relaxed);
+        self.found_by.store(worker, Ordering::Relaxed);
+        self.state.store(SolverState::Solved as u32, Ordering::Release);
+        true
+    }
+
+    /// Ends a running solve without a solution.
+    ///
+    /// # Arguments
+    /// * `state`: `Exhausted` or `Cancelled`.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the solve was running and now has `state`;
+    ///           `false` if it had already ended or `state` is not a
+    ///           final state without a solution.
+    pub fn finish(&self, state: SolverState) -> bool {
+        if !matches!(state, SolverState::Exhausted | SolverState::Cancelled) {
+            return false;
+        }
+        self.state.compare_exchange(
+            SolverState::Running as u32,
+            state as u32,
+            Ordering::Release,
+            Ordering::Relaxed
+        ).is_ok()
+    }
+
+    /// # Returns
+    /// * `Option<(i64, u32)>`: The solution and the index of the worker
+    ///                         that found it, once `Solved`.
+    pub fn solution(&self) -> Option<(i64, u32)> {
+        (self.state() == SolverState::Solved).then(|| (
+            self.solution.load(Ordering::Relaxed),
+            self.found_by.load(Ordering::Relaxed),
+        ))
+    }
+
+    /// Returns the block to `Running` with zeroed counters for the next
+    /// challenge. Only call it while no worker is running.
+    pub fn reset(&self) {
+        self.worker_count.store(0, Ordering::Relaxed);
+        self.attempts.store(0, Ordering::Relaxed);
+        self.solution.store(0, Ordering::Relaxed);
+        self.found_by.store(0, Ordering::Relaxed);
+        self.state.store(SolverState::Running as u32, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_matches_js_views() {
+        let progress = SolverProgress::new();
+        progress.set_worker_count(3);
+        progress.add_attempts(0x0102);
+        assert!(progress.publish_solution(-2, 7));
+
+        let bytes: &[u8; SOLVER_PROGRESS_SIZE] = unsafe { &*(&progress as *const SolverProgress).cast() };
+        assert_eq!(bytes[PROGRESS_STATE_OFFSET], SolverState::Solved as u8);
+        assert_eq!(bytes[PROGRESS_WORKERS_OFFSET], 3);
+        assert_eq!(&bytes[PROGRESS_ATTEMPTS_OFFSET..PROGRESS_ATTEMPTS_OFFSET + 2], &[0x02, 0x01]);
+        assert_eq!(&bytes[PROGRESS_SOLUTION_OFFSET..PROGRESS_SOLUTION_OFFSET + 8], &(-2i64).to_ne_bytes());
+        assert_eq!(bytes[PROGRESS_FOUND_BY_OFFSET], 7);
+    }
+
+    #[test]
+    fn test_first_solution_wins() {
+        let progress = SolverProgress::new();
+        std::thread::scope(|scope| {
+            for worker in 0..8u32 {
+                let progress = &progress;
+                scope.spawn(move || {
+                    progress.add_attempts(10);
+                    progress.publish_solution(worker as i64 * 100, worker);
+                });
+            }
+        });
+
+        let (nonce, worker) = progress.solution().unwrap();
+        assert_eq!(nonce, worker as i64 * 100);
+        assert_eq!(progress.attempts(), 80);
+        assert!(!progress.finish(SolverState::Cancelled));
+
+        progress.reset();
+        assert_eq!(progress.solution(), None);
+        assert!(!progress.finish(SolverState::Solved));
+        assert!(progress.finish(SolverState::Cancelled));
+        assert!(progress.is_finished() && !progress.publish_solution(1, 0));
+    }
+}
@@ -0,0 +1,202 @@
+//! # Probabilistic spot-check verification of solutions.
+//!
+//! At very high request rates, hashing every submitted solution can
+//! cost more than it protects. With `VerificationPolicy::Sampled`, the
+//! `SpotChecker` recomputes the hash for only a fraction of challenges
+//! and accepts the rest unchecked; a client submitting garbage is still
+//! caught with probability `rate` per challenge.
+//!
+//! Sampling is deterministic per challenge: HMAC-SHA256 of the
+//! challenge id under a verifier secret decides, so every edge node
+//! sharing the secret reaches the same decision, while clients cannot
+//! predict which of their challenges will be checked. Counters report
+//! how many solutions were spot-checked and how many were accepted.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::key_derivation::hmac_sha256;
+use crate::IronShieldChallengeResponse;
+
+/// How many solutions get their proof-of-work hash recomputed.
+///
+/// * `Full`:    Every solution (default).
+/// * `Sampled`: A fraction `rate` (from 0.0 to 1.0) of challenges.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum VerificationPolicy {
+    #[default]
+    Full,
+    Sampled { rate: f64 },
+}
+
+impl VerificationPolicy {
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if a sampling rate is a finite
+    ///                         number between 0.0 and 1.0.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Full => Ok(()),
+            Self::Sampled { rate } if (0.0..=1.0).contains(rate) => Ok(()),
+            Self::Sampled { rate } => Err(format!("Sampling rate must be between 0.0 and 1.0, got {}", rate)),
+        }
+    }
+}
+
+/// Counters of a `SpotChecker`.
+///
+/// * `spot_checked`: Solutions whose hash was recomputed.
+/// * `accepted`:     Solutions accepted, checked or not.
+/// * `rejected`:     Spot-checked solutions that failed.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpotCheckStats {
+    pub spot_checked: u64,
+    pub accepted:     u64,
+    pub rejected:     u64,
+}
+
+impl SpotCheckStats {
+    /// # Returns
+    /// * `u64`: Solutions accepted without recomputing their hash.
+    pub fn accepted_unchecked(&self) -> u64 {
+        (self.accepted + self.rejected).saturating_sub(self.spot_checked)
+    }
+}
+
+/// Solution verifier applying a `VerificationPolicy`.
+#[derive(Debug, Clone)]
+pub struct SpotChecker {
+    policy:       VerificationPolicy,
+    sampling_key: [u8; 32],
+    stats:        SpotCheckStats,
+}
+
+impl SpotChecker {
+    /// # Arguments
+    /// * `policy`:       Which solutions to check.
+    /// * `sampling_key`: Verifier secret keying the sampling decision;
+    ///                   share it between nodes for consistent decisions.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The checker, or an error if the policy
+    ///                           is invalid.
+    pub fn new(policy: VerificationPolicy, sampling_key: [u8; 32]) -> Result<Self, String> {
+        policy.validate()?;
+        Ok(Self {
+            policy,
+            sampling_key,
+            stats: SpotCheckStats::default(),
+        })
+    }
+
+    /// # Returns
+    /// * `SpotCheckStats`: The counters.
+    pub fn stats(&self) -> SpotCheckStats {
+        self.stats
+    }
+
+    /// Resets the counters, e.g. after exporting them.
+    pub fn reset_stats(&mut self) {
+        self.stats = SpotCheckStats::default();
+    }
+
+    /// # Arguments
+    /// * `challenge_id`: `challenge_id()` of the solved challenge.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if solutions of this challenge are checked.
+    pub fn is_sampled(&self, challenge_id: &[u8; 32]) -> bool {
+        match self.policy {
+            VerificationPolicy::Full => true,
+            VerificationPolicy::Sampled { rate } => {
+                let digest: [u8; 32] = hmac_sha256(&self.sampling_key, challenge_id);
+                let draw: u64 = u64::from_be_bytes([
+                    digest[0], digest[1], digest[2], digest[3],
+                    digest[4], digest[5], digest[6], digest[7],
+                ]);
+                // Uniform in [0, 1); `rate` 1.0 always samples.
+                (draw as f64 / 2f64.powi(64)) < rate
+            }
+        }
+    }
+
+    /// Checks a response's solution if its challenge is sampled, and
+    /// accepts it unchecked otherwise. Signature and expiry are
+    /// validated separately.
+    ///
+    /// # Arguments
+    /// * `response`: The submitted response.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the solution is accepted.
+    pub fn verify_response(&mut self, response: &IronShieldChallengeResponse) -> Result<(), String> {
+        if self.is_sampled(&response.solved_challenge.challenge_id()) {
+            self.stats.spot_checked += 1;
+            if let Err(e) = response.verify_solution() {
+                self.stats.rejected += 1;
+                return Err(e);
+            }
+        }
+        self.stats.accepted += 1;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        IronShieldChallenge,
+        SigningKey
+    };
+
+    fn challenge() -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        IronShieldChallenge::new("example.com".parse().unwrap(), 1_000_000, key.clone(), key.verifying_key().to_bytes())
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(VerificationPolicy::Sampled { rate: 0.25 }.validate().is_ok());
+        assert!(VerificationPolicy::Sampled { rate: 1.5 }.validate().is_err());
+        assert!(SpotChecker::new(VerificationPolicy::Sampled { rate: f64::NAN }, [0; 32]).is_err());
+        let json: String = serde_json::to_string(&VerificationPolicy::Sampled { rate: 0.5 }).unwrap();
+        assert_eq!(json, r#"{"mode":"sampled","rate":0.5}"#);
+    }
+
+    #[test]
+    fn test_sampling() {
+        let full = SpotChecker::new(VerificationPolicy::Full, [1; 32]).unwrap();
+        let never = SpotChecker::new(VerificationPolicy::Sampled { rate: 0.0 }, [1; 32]).unwrap();
+        let quarter = SpotChecker::new(VerificationPolicy::Sampled { rate: 0.25 }, [1; 32]).unwrap();
+        let ids: Vec<[u8; 32]> = (0..2_000u32).map(|i| crate::key_derivation::hmac_sha256(b"id", &i.to_be_bytes())).collect();
+
+        assert!(ids.iter().all(|id| full.is_sampled(id)));
+        assert!(!ids.iter().any(|id| never.is_sampled(id)));
+        let sampled: usize = ids.iter().filter(|id| quarter.is_sampled(id)).count();
+        assert!((400..600).contains(&sampled), "{}", sampled);
+        let same_key = SpotChecker::new(VerificationPolicy::Sampled { rate: 0.25 }, [1; 32]).unwrap();
+        let other_key = SpotChecker::new(VerificationPolicy::Sampled { rate: 0.25 }, [2; 32]).unwrap();
+        assert!(ids.iter().all(|id| quarter.is_sampled(id) == same_key.is_sampled(id)));
+        assert!(ids.iter().any(|id| quarter.is_sampled(id) != other_key.is_sampled(id)));
+    }
+
+    #[test]
+    fn test_counters() {
+        let bogus = IronShieldChallengeResponse::new(challenge(), 0);
+        let mut full = SpotChecker::new(VerificationPolicy::Full, [1; 32]).unwrap();
+        assert!(full.verify_response(&bogus).is_err());
+        assert_eq!(full.stats(), SpotCheckStats { spot_checked: 1, accepted: 0, rejected: 1 });
+
+        let mut never = SpotChecker::new(VerificationPolicy::Sampled { rate: 0.0 }, [1; 32]).unwrap();
+        never.verify_response(&bogus).unwrap();
+        never.verify_response(&bogus).unwrap();
+        assert_eq!(never.stats().accepted_unchecked(), 2);
+        never.reset_stats();
+        assert_eq!(never.stats(), SpotCheckStats::default());
+    }
+}
@@ -0,0 +1,345 @@
+//! # Multi-tenant key registry.
+//!
+//! A SaaS issuer signs challenges for many customer websites, each with
+//! its own keys. `TenantKeyRegistry` maps a `website_id` to the keyring
+//! of public keys accepted for it, so a challenge signed with one
+//! tenant's key cannot be replayed against another tenant.
+//!
+//! The registry deserializes from the JSON stored in KV, with public
+//! keys in the same base64 formats (raw Ed25519 or PGP) accepted by
+//! `load_public_key_from_data()`:
+//!
+//! ```json
+//! {
+//!   "example.com": { "public_keys": ["<base64 key>", "<rotated key>"] },
+//!   "shop.example": { "public_keys": ["<base64 key>"], "recipient_key": "<base64 X25519 key>" }
+//! }
+//! ```
+//!
+//! The optional `recipient_key` is the X25519 key that challenges for
+//! the tenant are sealed to (see the `sealed-challenges` feature), so
+//! it is distributed together with the verification keys.
+
+use std::collections::BTreeMap;
+
+use base64::{
+    Engine,
+    engine::general_purpose::STANDARD
+};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer
+};
+
+use crate::{
+    load_public_key_from_data,
+    verify_challenge_signature_with_key,
+    verify_token_signature_with_key,
+    CryptoError,
+    IronShieldChallenge,
+    IronShieldToken
+};
+
+/// Public keys accepted for a single tenant.
+///
+/// Holding several keys allows rotation: the new key is added, issuers
+/// switch over, and the old key is removed once its challenges and
+/// tokens have expired.
+///
+/// * `public_keys`:   Ed25519 public keys, newest first by convention.
+/// * `recipient_key`: X25519 public key that challenges are sealed to,
+///                    if the tenant opens sealed challenges.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Ed25519 public keys accepted for one website"
+))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantKeyring {
+    /// Base64-encoded Ed25519 public keys (raw or PGP)
+    #[serde(
+        serialize_with = "serialize_public_keys",
+        deserialize_with = "deserialize_public_keys"
+    )]
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub public_keys: Vec<[u8; 32]>,
+    /// Base64-encoded X25519 public key for sealed challenges
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_recipient_key",
+        deserialize_with = "deserialize_recipient_key"
+    )]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub recipient_key: Option<[u8; 32]>,
+}
+
+impl TenantKeyring {
+    /// Constructor for creating a new `TenantKeyring` instance.
+    ///
+    /// # Arguments
+    /// * `public_keys`: The accepted Ed25519 public keys.
+    ///
+    /// # Returns
+    /// * `Self`: A keyring holding `public_keys`.
+    pub fn new(public_keys: Vec<[u8; 32]>) -> Self {
+        Self { public_keys, recipient_key: None }
+    }
+
+    /// # Arguments
+    /// * `recipient_key`: The X25519 public key challenges for the
+    ///                    tenant are sealed to.
+    ///
+    /// # Returns
+    /// * `Self`: The keyring publishing `recipient_key`.
+    pub fn with_recipient_key(mut self, recipient_key: [u8; 32]) -> Self {
+        self.recipient_key = Some(recipient_key);
+        self
+    }
+
+    /// # Arguments
+    /// * `public_key`: The key to look up.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the key belongs to this keyring.
+    pub fn contains(&self, public_key: &[u8; 32]) -> bool {
+        self.public_keys.contains(public_key)
+    }
+}
+
+/// Registry of tenant keyrings keyed by `website_id`.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    description = "Mapping of website_id to the keyring accepted for it"
+))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TenantKeyRegistry {
+    tenants: BTreeMap<String, TenantKeyring>,
+}
+
+impl TenantKeyRegistry {
+    /// # Returns
+    /// * `Self`: An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers or replaces the keyring of a tenant.
+    ///
+    /// # Arguments
+    /// * `website_id`: The tenant's website identifier.
+    /// * `keyring`:    The keys accepted for it.
+    ///
+    /// # Returns
+    /// * `Option<TenantKeyring>`: The keyring previously registered, if any.
+    pub fn insert(&mut self, website_id: String, keyring: TenantKeyring) -> Option<TenantKeyring> {
+        self.tenants.insert(website_id, keyring)
+    }
+
+    /// # Arguments
+    /// * `website_id`: The tenant's website identifier.
+    ///
+    /// # Returns
+    /// * `Option<TenantKeyring>`: The removed keyring, if any.
+    pub fn remove(&mut self, website_id: &str) -> Option<TenantKeyring> {
+        self.tenants.remove(website_id)
+    }
+
+    /// # Arguments
+    /// * `website_id`: The tenant's website identifier.
+    ///
+    /// # Returns
+    /// * `Option<&TenantKeyring>`: The tenant's keyring, if registered.
+    pub fn keyring(&self, website_id: &str) -> Option<&TenantKeyring> {
+        self.tenants.get(website_id)
+    }
+
+    /// # Returns
+    /// * `usize`: The number of registered tenants.
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if no tenant is registered.
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    /// Looks up the keyring for `website_id` and checks that it
+    /// contains `public_key`.
+    fn require_key(&self, website_id: &str, public_key: &[u8; 32]) -> Result<(), CryptoError> {
+        let keyring: &TenantKeyring = self.keyring(website_id)
+            .ok_or_else(|| CryptoError::VerificationFailed(format!("No keyring registered for website_id: {}", website_id).into()))?;
+
+        if !keyring.contains(public_key) {
+            return Err(CryptoError::VerificationFailed(format!(
+                "Public key is not registered for website_id: {}",
+                website_id
+            ).into()));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a challenge against the keyring of its own `website_id`.
+    ///
+    /// The public key embedded in the challenge is only trusted if the
+    /// tenant's keyring contains it.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge to verify.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the challenge was signed
+    ///                              by one of the tenant's keys.
+    pub fn verify_challenge_signature(&self, challenge: &IronShieldChallenge) -> Result<(), CryptoError> {
+        self.require_key(&challenge.website_id, &challenge.public_key)?;
+        verify_challenge_signature_with_key(challenge, &challenge.public_key)
+    }
+
+    /// Verifies a token against the keyring of the tenant it is
+    /// presented to.
+    ///
+    /// # Arguments
+    /// * `token`:      The token to verify.
+    /// * `website_id`: The website the token is presented to.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the token was signed
+    ///                              by one of the tenant's keys.
+    pub fn verify_token_signature(&self, token: &IronShieldToken, website_id: &str) -> Result<(), CryptoError> {
+        self.require_key(website_id, &token.public_key)?;
+        verify_token_signature_with_key(token, &token.public_key)
+    }
+}
+
+fn serialize_public_keys<S>(keys: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(keys.iter().map(|key| STANDARD.encode(key)))
+}
+
+fn deserialize_public_keys<'de, D>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded: Vec<String> = Vec::deserialize(deserializer)?;
+
+    encoded
+        .iter()
+        .map(|key_data| {
+            load_public_key_from_data(key_data)
+                .map(|key| key.to_bytes())
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+fn serialize_recipient_key<S>(key: &Option<[u8; 32]>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match key {
+        Some(key) => serializer.serialize_some(&STANDARD.encode(key)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_recipient_key<'de, D>(deserializer: D) -> Result<Option<[u8; 32]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(encoded) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    let bytes: Vec<u8> = STANDARD.decode(encoded.trim())
+        .map_err(|e| serde::de::Error::custom(format!("Invalid recipient key: {}", e)))?;
+    bytes.as_slice()
+        .try_into()
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("Recipient key must be 32 bytes, got {}", bytes.len())))
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        IronShieldChallenge,
+        LifetimePolicy,
+        SigningKey,
+        issue_token
+    };
+
+    fn tenant_key(seed: u8) -> (SigningKey, [u8; 32]) {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        (signing_key, public_key)
+    }
+
+    fn registry() -> TenantKeyRegistry {
+        let mut registry = TenantKeyRegistry::new();
+        registry.insert("a.example".to_string(), TenantKeyring::new(vec![tenant_key(1).1, tenant_key(2).1]));
+        registry.insert("b.example".to_string(), TenantKeyring::new(vec![tenant_key(3).1]).with_recipient_key([9; 32]));
+        registry
+    }
+
+    #[test]
+    fn test_challenge_verified_against_own_tenant() {
+        let registry = registry();
+        let (key_a, public_a) = tenant_key(2);
+        let (key_b, public_b) = tenant_key(3);
+
+        let challenge = IronShieldChallenge::new("a.example".parse().unwrap(), 1_000, key_a, public_a);
+        assert!(registry.verify_challenge_signature(&challenge).is_ok());
+
+        // Signed with tenant B's key but claiming to be for tenant A.
+        let forged = IronShieldChallenge::new("a.example".parse().unwrap(), 1_000, key_b, public_b);
+        let error = registry.verify_challenge_signature(&forged).unwrap_err().to_string();
+        assert!(error.contains("not registered for website_id: a.example"));
+
+        let (key_c, public_c) = tenant_key(4);
+        let unknown = IronShieldChallenge::new("c.example".parse().unwrap(), 1_000, key_c, public_c);
+        let error = registry.verify_challenge_signature(&unknown).unwrap_err().to_string();
+        assert!(error.contains("No keyring registered"));
+    }
+
+    #[test]
+    fn test_token_verified_against_tenant() {
+        let registry = registry();
+        let (key_b, public_b) = tenant_key(3);
+        let challenge = IronShieldChallenge::new("b.example".parse().unwrap(), 1_000, key_b.clone(), public_b);
+        let token = issue_token(&challenge, &key_b, &LifetimePolicy::default()).unwrap();
+
+        assert!(registry.verify_token_signature(&token, "b.example").is_ok());
+        assert!(registry.verify_token_signature(&token, "a.example").is_err());
+    }
+
+    #[test]
+    fn test_json_roundtrip_with_base64_keys() {
+        let registry = registry();
+        let json: String = serde_json::to_string(&registry).unwrap();
+        assert!(json.contains(&STANDARD.encode(tenant_key(3).1)));
+
+        let parsed: TenantKeyRegistry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, registry);
+        assert_eq!(parsed.len(), 2);
+        assert!(parsed.keyring("a.example").unwrap().contains(&tenant_key(1).1));
+        assert_eq!(parsed.keyring("a.example").unwrap().recipient_key, None);
+        assert_eq!(parsed.keyring("b.example").unwrap().recipient_key, Some([9; 32]));
+        assert!(json.contains(&format!("\"recipient_key\":\"{}\"", STANDARD.encode([9u8; 32]))));
+    }
+
+    #[test]
+    fn test_json_rejects_invalid_keys() {
+        let result = serde_json::from_str::<TenantKeyRegistry>(r#"{"a.example":{"public_keys":["AAAA"]}}"#);
+        assert!(result.is_err());
+
+        let result = serde_json::from_str::<TenantKeyRegistry>(r#"{"a.example":{"public_keys":[],"recipient_key":"AAAA"}}"#);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! # Time attested by an external time authority.
+//!
+//! Expiration is normally decided against the issuer's own clock, which
+//! a verifier has to trust. An issuer can instead ask a time authority
+//! for a Roughtime-style attestation: the authority signs its current
+//! time, an uncertainty radius and a nonce derived from the challenge.
+//! Embedded in the challenge as the `ta` signed extension, it proves
+//! that `created_time` was not backdated or postdated beyond the
+//! radius, and lets verifiers ground expiration in attested time via
+//! `TimeAuthorities`.
+
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
+    SigningKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::{
+    CryptoError,
+    ErrorDetail
+};
+
+/// Domain separation prefix of the attestation signing message.
+const ATTESTATION_SIGNING_CONTEXT: &[u8] = b"ironshield-time-attestation-v1";
+
+/// Signed statement of a time authority that the time was
+/// `midpoint ± radius_ms` when it saw a nonce.
+///
+/// * `authority_key`: Ed25519 public key of the time authority.
+/// * `midpoint`:      Attested time in unix millis.
+/// * `radius_ms`:     Uncertainty of `midpoint` in milliseconds.
+/// * `signature`:     Ed25519 signature over the nonce, `midpoint`
+///                    and `radius_ms`.
+///
+/// The nonce is not carried along: verifiers recompute it from the
+/// value the attestation is bound to. Serialized as
+/// `<hex authority key>,<midpoint>,<radius_ms>,<hex signature>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeAttestation {
+    pub authority_key: [u8; 32],
+    pub midpoint:      i64,
+    pub radius_ms:     u32,
+    pub signature:     [u8; 64],
+}
+
+impl TimeAttestation {
+    /// Creates the message covered by `signature`.
+    fn signing_message(nonce: &[u8; 32], midpoint: i64, radius_ms: u32) -> Vec<u8> {
+        [
+            ATTESTATION_SIGNING_CONTEXT,
+            nonce,
+            &midpoint.to_be_bytes(),
+            &radius_ms.to_be_bytes(),
+        ].concat()
+    }
+
+    /// Attests the time, as done by a time authority.
+    ///
+    /// # Arguments
+    /// * `authority_key`: The time authority's signing key.
+    /// * `nonce`:         The nonce to bind the attestation to.
+    /// * `midpoint`:      The authority's current time in unix millis.
+    /// * `radius_ms`:     Uncertainty of `midpoint` in milliseconds.
+    ///
+    /// # Returns
+    /// * `Self`: The signed attestation.
+    #[cfg(feature = "sign")]
+    pub fn sign(authority_key: &SigningKey, nonce: &[u8; 32], midpoint: i64, radius_ms: u32) -> Self {
+        let message: Vec<u8> = Self::signing_message(nonce, midpoint, radius_ms);
+        Self {
+            authority_key: authority_key.verifying_key().to_bytes(),
+            midpoint,
+            radius_ms,
+            signature: authority_key.sign(&message).to_bytes(),
+        }
+    }
+
+    /// Checks the authority's signature. Whether the authority is
+    /// trusted is up to the caller, see `TimeAuthorities`.
+    ///
+    /// # Arguments
+    /// * `nonce`: The nonce the attestation must be bound to.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the signature is valid
+    ///                              for `nonce`, error otherwise.
+    pub fn verify(&self, nonce: &[u8; 32]) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(&self.authority_key)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        let signature: Signature = Signature::from_slice(&self.signature)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid signature format: {}", e), e)))?;
+
+        verifying_key.verify(&Self::signing_message(nonce, self.midpoint, self.radius_ms), &signature)
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Time attestation verification failed: {}", e), e)))
+    }
+
+    /// # Returns
+    /// * `i64`: The earliest instant (unix millis) the attested time
+    ///          can stand for.
+    pub fn earliest(&self) -> i64 {
+        self.midpoint.saturating_sub(self.radius_ms as i64)
+    }
+
+    /// # Returns
+    /// * `i64`: The latest instant (unix millis) the attested time
+    ///          can stand for.
+    pub fn latest(&self) -> i64 {
+        self.midpoint.saturating_add(self.radius_ms as i64)
+    }
+
+    /// # Returns
+    /// * `String`: The attestation as
+    ///             `<hex authority key>,<midpoint>,<radius_ms>,<hex signature>`.
+    pub fn to_wire_string(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            hex::encode(self.authority_key),
+            self.midpoint,
+            self.radius_ms,
+            hex::encode(self.signature)
+        )
+    }
+
+    /// # Arguments
+    /// * `wire`: The string produced by `to_wire_string()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The attestation, or an error describing
+    ///                           the malformed field.
+    pub fn from_wire_string(wire: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = wire.split(',').collect();
+        if parts.len() != 4 {
+            return Err(format!("Expected 4 time attestation parts, got {}", parts.len()));
+        }
+
+        let mut authority_key = [0u8; 32];
+        hex::decode_to_slice(parts[0], &mut authority_key)
+            .map_err(|_| "Time authority key must be 32 bytes of hex")?;
+        let mut signature = [0u8; 64];
+        hex::decode_to_slice(parts[3], &mut signature)
+            .map_err(|_| "Time attestation signature must be 64 bytes of hex")?;
+
+        Ok(Self {
+            authority_key,
+            midpoint:  parts[1].parse::<i64>().map_err(|_| "Failed to parse midpoint as i64")?,
+            radius_ms: parts[2].parse::<u32>().map_err(|_| "Failed to parse radius_ms as u32")?,
+            signature,
+        })
+    }
+}
+
+impl From<TimeAttestation> for String {
+    fn from(attestation: TimeAttestation) -> Self {
+        attestation.to_wire_string()
+    }
+}
+
+impl TryFrom<String> for TimeAttestation {
+    type Error = String;
+
+    fn try_from(wire: String) -> Result<Self, Self::Error> {
+        Self::from_wire_string(&wire)
+    }
+}
+
+/// Time authorities a verifier trusts, and how precise their
+/// attestations must be.
+///
+/// * `trusted_keys`:  Public keys of the trusted authorities.
+/// * `max_radius_ms`: Largest accepted uncertainty radius.
+/// * `max_skew_ms`:   How far a claimed time may lie outside the
+///                    attested interval, e.g. to cover the round trip
+///                    to the authority.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeAuthorities {
+    pub trusted_keys:  Vec<[u8; 32]>,
+    pub max_radius_ms: u32,
+    pub max_skew_ms:   u32,
+}
+
+impl TimeAuthorities {
+    /// # Arguments
+    /// * `trusted_keys`:  Public keys of the trusted authorities.
+    /// * `max_radius_ms`: Largest accepted uncertainty radius.
+    /// * `max_skew_ms`:   Tolerance around the attested interval.
+    ///
+    /// # Returns
+    /// * `Self`: The trust configuration.
+    pub fn new(trusted_keys: Vec<[u8; 32]>, max_radius_ms: u32, max_skew_ms: u32) -> Self {
+        Self { trusted_keys, max_radius_ms, max_skew_ms }
+    }
+
+    /// Validation hook for attested times: checks that `attestation`
+    /// comes from a trusted authority, is bound to `nonce`, is precise
+    /// enough, and backs `claimed_time`.
+    ///
+    /// # Arguments
+    /// * `attestation`:  The attestation to check.
+    /// * `nonce`:        The nonce the attestation must be bound to.
+    /// * `claimed_time`: The time (unix millis) the attestation should
+    ///                   back, e.g. a challenge's `created_time`.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if `claimed_time` is attested,
+    ///                         or an error describing the failed check.
+    pub fn check(&self, attestation: &TimeAttestation, nonce: &[u8; 32], claimed_time: i64) -> Result<(), String> {
+        if !self.trusted_keys.contains(&attestation.authority_key) {
+            return Err(format!(
+                "Time authority {} is not trusted",
+                crate::key_fingerprint(&attestation.authority_key)
+            ));
+        }
+        if attestation.radius_ms > self.max_radius_ms {
+            return Err(format!(
+                "Time attestation radius {}ms exceeds {}ms",
+                attestation.radius_ms,
+                self.max_radius_ms
+            ));
+        }
+        attestation.verify(nonce).map_err(|e| e.to_string())?;
+
+        let skew: i64 = self.max_skew_ms as i64;
+        if claimed_time < attestation.earliest().saturating_sub(skew)
+            || claimed_time > attestation.latest().saturating_add(skew) {
+            return Err(format!(
+                "Claimed time {} lies outside the attested interval [{}, {}]",
+                claimed_time,
+                attestation.earliest(),
+                attestation.latest()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_check() {
+        let authority = SigningKey::from_bytes(&[4; 32]);
+        let nonce = [1u8; 32];
+        let attestation = TimeAttestation::sign(&authority, &nonce, 1_000_000, 500);
+        assert_eq!((attestation.earliest(), attestation.latest()), (999_500, 1_000_500));
+        attestation.verify(&nonce).unwrap();
+        assert!(attestation.verify(&[2; 32]).is_err());
+        assert!(TimeAttestation { midpoint: 2_000_000, ..attestation }.verify(&nonce).is_err());
+
+        let authorities = TimeAuthorities::new(vec![attestation.authority_key], 1_000, 100);
+        authorities.check(&attestation, &nonce, 1_000_600).unwrap();
+        assert!(authorities.check(&attestation, &nonce, 1_000_601).is_err());
+        assert!(authorities.check(&attestation, &nonce, 999_399).is_err());
+        assert!(TimeAuthorities::new(vec![attestation.authority_key], 100, 100)
+            .check(&attestation, &nonce, 1_000_000).unwrap_err().contains("radius"));
+        assert!(TimeAuthorities::new(vec![[3; 32]], 1_000, 100)
+            .check(&attestation, &nonce, 1_000_000).unwrap_err().contains("not trusted"));
+    }
+
+    #[test]
+    fn test_wire_string() {
+        let attestation = TimeAttestation::sign(&SigningKey::from_bytes(&[4; 32]), &[1; 32], -5, 7);
+        let wire: String = attestation.to_wire_string();
+        assert_eq!(TimeAttestation::from_wire_string(&wire).unwrap(), attestation);
+        let json: String = serde_json::to_string(&attestation).unwrap();
+        assert_eq!(serde_json::from_str::<TimeAttestation>(&json).unwrap(), attestation);
+        assert!(TimeAttestation::from_wire_string("00,1,2").is_err());
+        assert!(TimeAttestation::from_wire_string(&wire.replace(",7,", ",-7,")).is_err());
+    }
+}
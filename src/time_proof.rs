@@ -0,0 +1,452 @@
+//! # Roughtime proofs of redemption time.
+//!
+//! A client whose clock is wrong cannot tell whether it is still inside
+//! a challenge's validity window, and a verifier receiving a delayed
+//! submission cannot tell when it was solved. The client can settle
+//! both by sending a Roughtime request whose nonce commits to the
+//! challenge and its solution (`TimeProof::redemption_nonce`): the
+//! signed response proves that the solution existed by the attested
+//! time, independently of either clock.
+//!
+//! `TimeProof` parses and verifies responses in the Roughtime wire
+//! format (tag-value messages with `SIG`, `PATH`, `SREP`, `CERT` and
+//! `INDX`, SHA-512 Merkle tree, Ed25519 delegation to an online key).
+//! Which root keys are trusted is configured with `TimeAuthorities`.
+
+use base64::{
+    Engine,
+    engine::general_purpose::URL_SAFE_NO_PAD
+};
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+use sha2::{
+    Digest,
+    Sha512
+};
+
+use crate::bytes::nonce_to_le_bytes;
+use crate::{
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    TimeAuthorities
+};
+
+/// Tags of the Roughtime message format, as little-endian `u32`s.
+const TAG_SIG:  u32 = u32::from_le_bytes(*b"SIG\0");
+const TAG_PATH: u32 = u32::from_le_bytes(*b"PATH");
+const TAG_SREP: u32 = u32::from_le_bytes(*b"SREP");
+const TAG_CERT: u32 = u32::from_le_bytes(*b"CERT");
+const TAG_INDX: u32 = u32::from_le_bytes(*b"INDX");
+const TAG_ROOT: u32 = u32::from_le_bytes(*b"ROOT");
+const TAG_MIDP: u32 = u32::from_le_bytes(*b"MIDP");
+const TAG_RADI: u32 = u32::from_le_bytes(*b"RADI");
+const TAG_DELE: u32 = u32::from_le_bytes(*b"DELE");
+const TAG_MINT: u32 = u32::from_le_bytes(*b"MINT");
+const TAG_MAXT: u32 = u32::from_le_bytes(*b"MAXT");
+const TAG_PUBK: u32 = u32::from_le_bytes(*b"PUBK");
+
+/// Context prepended to `DELE` before the root key signs it.
+const DELEGATION_CONTEXT: &[u8] = b"RoughTime v1 delegation signature--\0";
+/// Context prepended to `SREP` before the online key signs it.
+const RESPONSE_CONTEXT:   &[u8] = b"RoughTime v1 response signature\0";
+/// Domain separation prefix of `redemption_nonce()`.
+const REDEMPTION_CONTEXT: &[u8] = b"ironshield-redemption-time-v1";
+/// Length of Roughtime nonces and Merkle tree hashes (SHA-512).
+pub const ROUGHTIME_NONCE_LEN: usize = 64;
+/// Roughtime timestamps are in microseconds.
+const MICROS_PER_MILLI: u64 = 1_000;
+
+/// Fields of a Roughtime response, borrowed from the raw bytes.
+struct ParsedResponse<'a> {
+    signature:            &'a [u8],
+    signed_response:      &'a [u8],
+    delegation:           &'a [u8],
+    delegation_signature: &'a [u8],
+    root:                 &'a [u8],
+    midpoint_us:          u64,
+    radius_us:            u32,
+    min_time_us:          u64,
+    max_time_us:          u64,
+    online_key:           &'a [u8],
+    index:                u32,
+    path:                 &'a [u8],
+}
+
+/// A Roughtime response proving the time at which a nonce was seen.
+///
+/// Keeps the raw response, which is what gets verified and transported,
+/// next to the attested `midpoint ± radius`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeProof {
+    response:    Vec<u8>,
+    midpoint_us: u64,
+    radius_us:   u32,
+}
+
+impl TimeProof {
+    /// # Arguments
+    /// * `response`: A Roughtime response as received from the server.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The proof, or an error if the response
+    ///                           is malformed. Signatures are checked
+    ///                           separately by `verify()`.
+    pub fn from_roughtime_response(response: &[u8]) -> Result<Self, String> {
+        let parsed: ParsedResponse = parse_response(response)?;
+        Ok(Self {
+            response:    response.to_vec(),
+            midpoint_us: parsed.midpoint_us,
+            radius_us:   parsed.radius_us,
+        })
+    }
+
+    /// # Returns
+    /// * `&[u8]`: The raw Roughtime response.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.response
+    }
+
+    /// # Returns
+    /// * `i64`: The attested time in unix millis.
+    pub fn midpoint(&self) -> i64 {
+        i64::try_from(self.midpoint_us / MICROS_PER_MILLI).unwrap_or(i64::MAX)
+    }
+
+    /// # Returns
+    /// * `u32`: The uncertainty of `midpoint()` in milliseconds,
+    ///          rounded up.
+    pub fn radius_ms(&self) -> u32 {
+        self.radius_us.div_ceil(MICROS_PER_MILLI as u32)
+    }
+
+    /// # Returns
+    /// * `i64`: The earliest instant (unix millis) the proof can stand for.
+    pub fn earliest(&self) -> i64 {
+        self.midpoint().saturating_sub(self.radius_ms() as i64)
+    }
+
+    /// # Returns
+    /// * `i64`: The latest instant (unix millis) the proof can stand for.
+    pub fn latest(&self) -> i64 {
+        self.midpoint().saturating_add(self.radius_ms() as i64)
+    }
+
+    /// Nonce a client sends in its Roughtime request to prove when it
+    /// solved a challenge.
+    ///
+    /// # Arguments
+    /// * `challenge`: The solved challenge.
+    /// * `solution`:  The nonce solving it.
+    ///
+    /// # Returns
+    /// * `[u8; 64]`: SHA-512 of the challenge id and solution.
+    pub fn redemption_nonce(challenge: &IronShieldChallenge, solution: i64) -> [u8; ROUGHTIME_NONCE_LEN] {
+        Sha512::new()
+            .chain_update(REDEMPTION_CONTEXT)
+            .chain_update(challenge.challenge_id())
+            .chain_update(nonce_to_le_bytes(solution))
+            .finalize()
+            .into()
+    }
+
+    /// Verifies the delegation, the response signature, the Merkle
+    /// path to `nonce` and that the midpoint lies within the
+    /// delegation's validity.
+    ///
+    /// # Arguments
+    /// * `root_public_key`: The Roughtime server's long-term public key.
+    /// * `nonce`:           The nonce of the Roughtime request.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the response proves the
+    ///                              time `nonce` was seen, error otherwise.
+    pub fn verify(&self, root_public_key: &[u8; 32], nonce: &[u8; ROUGHTIME_NONCE_LEN]) -> Result<(), CryptoError> {
+        let parsed: ParsedResponse = parse_response(&self.response)
+            .map_err(|e| CryptoError::VerificationFailed(e.into()))?;
+
+        verify_signature(root_public_key, DELEGATION_CONTEXT, parsed.delegation, parsed.delegation_signature)?;
+        let online_key: [u8; 32] = parsed.online_key.try_into()
+            .map_err(|_| CryptoError::InvalidKeyFormat("Roughtime online key must be 32 bytes".into()))?;
+        verify_signature(&online_key, RESPONSE_CONTEXT, parsed.signed_response, parsed.signature)?;
+
+        if parsed.midpoint_us < parsed.min_time_us || parsed.midpoint_us > parsed.max_time_us {
+            return Err(CryptoError::VerificationFailed("Roughtime midpoint lies outside the delegation validity".into()));
+        }
+
+        let mut hash: [u8; ROUGHTIME_NONCE_LEN] = Sha512::new()
+            .chain_update([0u8])
+            .chain_update(nonce)
+            .finalize()
+            .into();
+        let mut index: u32 = parsed.index;
+        for sibling in parsed.path.chunks_exact(ROUGHTIME_NONCE_LEN) {
+            let (left, right) = if index & 1 == 0 { (&hash[..], sibling) } else { (sibling, &hash[..]) };
+            hash = Sha512::new()
+                .chain_update([1u8])
+                .chain_update(left)
+                .chain_update(right)
+                .finalize()
+                .into();
+            index >>= 1;
+        }
+        if index != 0 || hash[..] != *parsed.root {
+            return Err(CryptoError::VerificationFailed("Roughtime response does not cover the nonce".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a challenge response was solved within its validity
+    /// window: the proof must come from a trusted root key, cover
+    /// `redemption_nonce()` of the response, and attest a time no later
+    /// than the submission deadline plus `authorities.max_skew_ms`.
+    ///
+    /// # Arguments
+    /// * `response`:    The submitted challenge response.
+    /// * `authorities`: Trusted Roughtime root keys and precision limits.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the redemption time is proven
+    ///                         to lie within the window, or an error
+    ///                         describing the failed check.
+    pub fn check_redemption(
+        &self,
+        response: &IronShieldChallengeResponse,
+        authorities: &TimeAuthorities
+    ) -> Result<(), String> {
+        if self.radius_ms() > authorities.max_radius_ms {
+            return Err(format!(
+                "Roughtime radius {}ms exceeds {}ms",
+                self.radius_ms(),
+                authorities.max_radius_ms
+            ));
+        }
+
+        let nonce: [u8; ROUGHTIME_NONCE_LEN] = Self::redemption_nonce(&response.solved_challenge, response.solution);
+        if !authorities.trusted_keys.iter().any(|root_key| self.verify(root_key, &nonce).is_ok()) {
+            return Err("Roughtime response is not signed by a trusted root key for this redemption".to_string());
+        }
+
+        let deadline: i64 = response.solved_challenge.effective_submission_deadline()
+            .saturating_add(authorities.max_skew_ms as i64);
+        if self.latest() > deadline {
+            return Err(format!("Redemption at {} is past the deadline {}", self.latest(), deadline));
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `String`: The raw response, base64url-encoded for HTTP header
+    ///             transport.
+    pub fn to_base64url_header(&self) -> String {
+        URL_SAFE_NO_PAD.encode(&self.response)
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The proof, or an error if the header is
+    ///                           not base64url or the response is malformed.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let response: Vec<u8> = URL_SAFE_NO_PAD.decode(encoded_header)
+            .map_err(|e| format!("Invalid base64url time proof: {}", e))?;
+        Self::from_roughtime_response(&response)
+    }
+}
+
+fn verify_signature(public_key: &[u8; 32], context: &[u8], message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+    let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+    let signature: Signature = Signature::from_slice(signature)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid signature format: {}", e), e)))?;
+
+    verifying_key.verify(&[context, message].concat(), &signature)
+        .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Roughtime signature verification failed: {}", e), e)))
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Parses a Roughtime message: a tag count, `count - 1` value offsets
+/// and `count` strictly increasing tags, all little-endian `u32`,
+/// followed by the values.
+fn parse_message(message: &[u8]) -> Result<Vec<(u32, &[u8])>, String> {
+    if message.len() < 4 || !message.len().is_multiple_of(4) {
+        return Err("Roughtime message length must be a nonzero multiple of 4".to_string());
+    }
+    let count: usize = read_u32(message) as usize;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let header_len: usize = count.checked_mul(8)
+        .filter(|len| *len <= message.len())
+        .ok_or("Roughtime message header exceeds the message")?;
+    let values: &[u8] = &message[header_len..];
+
+    let offset_at = |position: usize| read_u32(&message[4 + 4 * position..]) as usize;
+    let tag_at = |position: usize| read_u32(&message[4 * count + 4 * position..]);
+    let mut entries: Vec<(u32, &[u8])> = Vec::with_capacity(count);
+    for position in 0..count {
+        let start: usize = if position == 0 { 0 } else { offset_at(position - 1) };
+        let end: usize = if position + 1 == count { values.len() } else { offset_at(position) };
+        if !start.is_multiple_of(4) || start > end || end > values.len() {
+            return Err("Invalid Roughtime value offset".to_string());
+        }
+        if position > 0 && tag_at(position) <= tag_at(position - 1) {
+            return Err("Roughtime tags must be strictly increasing".to_string());
+        }
+        entries.push((tag_at(position), &values[start..end]));
+    }
+
+    Ok(entries)
+}
+
+/// Looks up a tag of a parsed message, checking its length if `len` is
+/// given.
+fn find<'a>(entries: &[(u32, &'a [u8])], tag: u32, len: Option<usize>) -> Result<&'a [u8], String> {
+    let name: String = String::from_utf8_lossy(&tag.to_le_bytes()).trim_end_matches('\0').to_string();
+    let value: &[u8] = entries.iter()
+        .find(|(entry_tag, _)| *entry_tag == tag)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| format!("Roughtime message lacks {}", name))?;
+
+    match len {
+        Some(len) if value.len() != len => Err(format!("Roughtime {} must be {} bytes", name, len)),
+        _ => Ok(value),
+    }
+}
+
+fn parse_response(response: &[u8]) -> Result<ParsedResponse<'_>, String> {
+    let top: Vec<(u32, &[u8])> = parse_message(response)?;
+    let signed_response: &[u8] = find(&top, TAG_SREP, None)?;
+    let certificate: Vec<(u32, &[u8])> = parse_message(find(&top, TAG_CERT, None)?)?;
+    let delegation: &[u8] = find(&certificate, TAG_DELE, None)?;
+    let signed: Vec<(u32, &[u8])> = parse_message(signed_response)?;
+    let delegated: Vec<(u32, &[u8])> = parse_message(delegation)?;
+
+    let path: &[u8] = find(&top, TAG_PATH, None)?;
+    if !path.len().is_multiple_of(ROUGHTIME_NONCE_LEN) || path.len() / ROUGHTIME_NONCE_LEN > 32 {
+        return Err("Roughtime PATH must be at most 32 hashes of 64 bytes".to_string());
+    }
+    let read_u64 = |value: &[u8]| u64::from_le_bytes(value.try_into().unwrap_or_default());
+
+    Ok(ParsedResponse {
+        signature:            find(&top, TAG_SIG, Some(64))?,
+        signed_response,
+        delegation,
+        delegation_signature: find(&certificate, TAG_SIG, Some(64))?,
+        root:                 find(&signed, TAG_ROOT, Some(ROUGHTIME_NONCE_LEN))?,
+        midpoint_us:          read_u64(find(&signed, TAG_MIDP, Some(8))?),
+        radius_us:            read_u32(find(&signed, TAG_RADI, Some(4))?),
+        min_time_us:          read_u64(find(&delegated, TAG_MINT, Some(8))?),
+        max_time_us:          read_u64(find(&delegated, TAG_MAXT, Some(8))?),
+        online_key:           find(&delegated, TAG_PUBK, Some(32))?,
+        index:                read_u32(find(&top, TAG_INDX, Some(4))?),
+        path,
+    })
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{
+        Signer,
+        SigningKey
+    };
+
+    /// Encodes a Roughtime message; `entries` must be sorted by tag.
+    fn encode(entries: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut out: Vec<u8> = (entries.len() as u32).to_le_bytes().to_vec();
+        let mut offset: u32 = 0;
+        for (_, value) in &entries[..entries.len() - 1] {
+            offset += value.len() as u32;
+            out.extend_from_slice(&offset.to_le_bytes());
+        }
+        for (tag, _) in entries {
+            out.extend_from_slice(&tag.to_le_bytes());
+        }
+        for (_, value) in entries {
+            out.extend_from_slice(value);
+        }
+        out
+    }
+
+    /// A response for the second leaf of a two-leaf tree.
+    fn respond(root_key: &SigningKey, nonce: &[u8; 64], midpoint_us: u64) -> Vec<u8> {
+        let online_key = SigningKey::from_bytes(&[3; 32]);
+        let other_leaf: [u8; 64] = Sha512::new().chain_update([0u8]).chain_update([7u8; 64]).finalize().into();
+        let own_leaf: [u8; 64] = Sha512::new().chain_update([0u8]).chain_update(nonce).finalize().into();
+        let root: [u8; 64] = Sha512::new().chain_update([1u8]).chain_update(other_leaf).chain_update(own_leaf).finalize().into();
+
+        let signed_response: Vec<u8> = encode(&[
+            (TAG_RADI, 1_500_000u32.to_le_bytes().to_vec()),
+            (TAG_MIDP, midpoint_us.to_le_bytes().to_vec()),
+            (TAG_ROOT, root.to_vec()),
+        ]);
+        let delegation: Vec<u8> = encode(&[
+            (TAG_PUBK, online_key.verifying_key().to_bytes().to_vec()),
+            (TAG_MINT, 0u64.to_le_bytes().to_vec()),
+            (TAG_MAXT, u64::MAX.to_le_bytes().to_vec()),
+        ]);
+        let certificate: Vec<u8> = encode(&[
+            (TAG_SIG, root_key.sign(&[DELEGATION_CONTEXT, &delegation].concat()).to_bytes().to_vec()),
+            (TAG_DELE, delegation),
+        ]);
+        encode(&[
+            (TAG_SIG, online_key.sign(&[RESPONSE_CONTEXT, &signed_response].concat()).to_bytes().to_vec()),
+            (TAG_PATH, other_leaf.to_vec()),
+            (TAG_SREP, signed_response),
+            (TAG_CERT, certificate),
+            (TAG_INDX, 1u32.to_le_bytes().to_vec()),
+        ])
+    }
+
+    #[test]
+    fn test_parse_and_verify() {
+        let root_key = SigningKey::from_bytes(&[2; 32]);
+        let root_public: [u8; 32] = root_key.verifying_key().to_bytes();
+        let nonce = [9u8; 64];
+        let proof = TimeProof::from_roughtime_response(&respond(&root_key, &nonce, 1_700_000_000_123_456)).unwrap();
+
+        assert_eq!((proof.midpoint(), proof.radius_ms()), (1_700_000_000_123, 1_500));
+        assert_eq!((proof.earliest(), proof.latest()), (1_699_999_998_623, 1_700_000_001_623));
+        proof.verify(&root_public, &nonce).unwrap();
+        assert!(proof.verify(&root_public, &[8; 64]).is_err());
+        assert!(proof.verify(&SigningKey::from_bytes(&[4; 32]).verifying_key().to_bytes(), &nonce).is_err());
+        assert_eq!(TimeProof::from_base64url_header(&proof.to_base64url_header()).unwrap(), proof);
+
+        let mut tampered: Vec<u8> = proof.as_bytes().to_vec();
+        let midpoint_at: usize = tampered.windows(8)
+            .position(|window| window == 1_700_000_000_123_456u64.to_le_bytes())
+            .unwrap();
+        tampered[midpoint_at] ^= 1;
+        assert!(TimeProof::from_roughtime_response(&tampered).unwrap().verify(&root_public, &nonce).is_err());
+        assert!(TimeProof::from_roughtime_response(&proof.as_bytes()[..40]).is_err());
+        assert!(TimeProof::from_roughtime_response(&[1, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_check_redemption() {
+        let root_key = SigningKey::from_bytes(&[2; 32]);
+        let authorities = TimeAuthorities::new(vec![root_key.verifying_key().to_bytes()], 2_000, 0);
+        let signing_key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1, signing_key.clone(), signing_key.verifying_key().to_bytes());
+        let response = IronShieldChallengeResponse::new(challenge.clone(), 0);
+        let nonce: [u8; 64] = TimeProof::redemption_nonce(&challenge, 0);
+        let at = |millis: i64| TimeProof::from_roughtime_response(&respond(&root_key, &nonce, millis as u64 * 1_000)).unwrap();
+
+        at(challenge.created_time).check_redemption(&response, &authorities).unwrap();
+        assert!(at(challenge.expiration_time).check_redemption(&response, &authorities).unwrap_err().contains("deadline"));
+        let other = IronShieldChallengeResponse::new(challenge.clone(), 1);
+        assert!(at(challenge.created_time).check_redemption(&other, &authorities).is_err());
+        let strict = TimeAuthorities::new(authorities.trusted_keys.clone(), 1_000, 0);
+        assert!(at(challenge.created_time).check_redemption(&response, &strict).unwrap_err().contains("radius"));
+    }
+}
@@ -0,0 +1,607 @@
+//! # Rivest-Shamir-Wagner time-lock puzzles.
+//!
+//! Hashcash-style challenges parallelize perfectly, so a botnet with
+//! thousands of cores pays far less wall-clock time per challenge than
+//! a phone. A time-lock puzzle asks for `y = x^(2^t) mod N`, which is
+//! only known to be computable by `t` sequential squarings: extra cores
+//! do not help.
+//!
+//! The issuer knows the factorization of `N` and computes `y` with two
+//! short exponentiations, then commits to it in `target`. The puzzle is
+//! embedded in the challenge as the `tl` signed extension and answered
+//! with a `TimeLockResponse`; checking the answer is a single hash, so
+//! verifiers need neither the factorization nor the `timelock`
+//! feature. Only issuing (`TimeLockKey`) and solving need big-integer
+//! arithmetic.
+//!
+//! The base `x` is derived from the challenge's `random_nonce`, so one
+//! modulus can safely serve many challenges.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+#[cfg(any(feature = "timelock", feature = "vdf"))]
+use num_bigint::BigUint;
+
+use crate::IronShieldChallenge;
+
+/// Domain separation prefix of the base derivation.
+#[cfg(feature = "timelock")]
+const BASE_CONTEXT:   &[u8] = b"ironshield-timelock-base-v1";
+/// Domain separation prefix of the output commitment.
+const TARGET_CONTEXT: &[u8] = b"ironshield-timelock-target-v1";
+/// Smallest modulus `TimeLockKey::generate` accepts.
+pub const MIN_TIMELOCK_MODULUS_BITS: u64 = 1024;
+/// Largest modulus a time-lock puzzle or VDF challenge may carry.
+pub const MAX_TIMELOCK_MODULUS_BITS: u64 = 8192;
+
+/// Checks that a modulus is canonical lowercase big-endian hex without
+/// leading zero bytes, and at most `MAX_TIMELOCK_MODULUS_BITS` long.
+pub(crate) fn validate_modulus_hex(modulus: &str) -> Result<(), String> {
+    let modulus: &[u8] = modulus.as_bytes();
+    if modulus.is_empty() || !modulus.len().is_multiple_of(2) {
+        return Err("Modulus must be a non-empty whole number of hex bytes".to_string());
+    }
+    if !modulus.iter().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f')) {
+        return Err("Modulus must be lowercase hex".to_string());
+    }
+    if modulus.starts_with(b"00") {
+        return Err("Modulus must not have leading zero bytes".to_string());
+    }
+    if modulus.len() as u64 * 4 > MAX_TIMELOCK_MODULUS_BITS {
+        return Err(format!("Modulus exceeds {} bits", MAX_TIMELOCK_MODULUS_BITS));
+    }
+    Ok(())
+}
+
+/// # Arguments
+/// * `output`: The puzzle output, big-endian and padded to the modulus
+///             length.
+///
+/// # Returns
+/// * `[u8; 32]`: The commitment to `output` stored as `target`.
+fn commit_output(output: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(TARGET_CONTEXT)
+        .chain_update(output)
+        .finalize()
+        .into()
+}
+
+/// A time-lock puzzle: square the base `iterations` times modulo
+/// `modulus`.
+///
+/// * `modulus`:    The RSA modulus as lowercase big-endian hex, without
+///                 leading zero bytes.
+/// * `iterations`: Number of sequential squarings.
+/// * `target`:     SHA-256 commitment to the output.
+///
+/// Serialized as `<hex modulus>,<iterations>,<hex target>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeLockPuzzle {
+    pub modulus:    String,
+    pub iterations: u64,
+    pub target:     [u8; 32],
+}
+
+/// Borrowed view of a `TimeLockPuzzle`, as held by `ChallengeRef`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeLockPuzzleRef<'a> {
+    pub modulus:    &'a str,
+    pub iterations: u64,
+    pub target:     [u8; 32],
+}
+
+impl<'a> TimeLockPuzzleRef<'a> {
+    /// # Arguments
+    /// * `wire`: A puzzle as `<hex modulus>,<iterations>,<hex target>`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The puzzle borrowing its modulus from
+    ///                           `wire`, or an error describing the
+    ///                           malformed field.
+    pub fn from_wire_string(wire: &'a str) -> Result<Self, String> {
+        let parts: Vec<&str> = wire.split(',').collect();
+        if parts.len() != 3 {
+            return Err(format!("Expected 3 time-lock puzzle parts, got {}", parts.len()));
+        }
+
+        let mut target = [0u8; 32];
+        hex::decode_to_slice(parts[2], &mut target)
+            .map_err(|_| "Time-lock target must be 32 bytes of hex")?;
+        let puzzle = Self {
+            modulus:    parts[0],
+            iterations: parts[1].parse::<u64>().map_err(|_| "Failed to parse iterations as u64")?,
+            target,
+        };
+        puzzle.validate()?;
+        Ok(puzzle)
+    }
+
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the modulus is canonical
+    ///                         lowercase hex within the size limits and
+    ///                         at least one squaring is required.
+    pub fn validate(&self) -> Result<(), String> {
+        validate_modulus_hex(self.modulus)?;
+        if self.iterations == 0 {
+            return Err("Time-lock puzzle needs at least one iteration".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `usize`: Length in bytes of the modulus and of every output.
+    pub fn modulus_len(&self) -> usize {
+        self.modulus.len() / 2
+    }
+
+    /// Checks a claimed output against the committed target. No
+    /// big-integer arithmetic is involved.
+    ///
+    /// # Arguments
+    /// * `output`: The output as hex, padded to the modulus length.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if `output` is the puzzle's
+    ///                         solution.
+    pub fn verify_output(&self, output: &str) -> Result<(), String> {
+        if output.len() != self.modulus.len() {
+            return Err(format!(
+                "Time-lock output must be {} bytes of hex, got {} characters",
+                self.modulus_len(),
+                output.len()
+            ));
+        }
+        let output: Vec<u8> = hex::decode(output)
+            .map_err(|_| "Failed to decode time-lock output hex string")?;
+
+        if commit_output(&output) != self.target {
+            return Err("Time-lock output does not match the target".to_string());
+        }
+        Ok(())
+    }
+
+    /// Solves the puzzle by repeated squaring. Takes time proportional
+    /// to `iterations` whatever the number of cores.
+    ///
+    /// # Arguments
+    /// * `random_nonce`: The challenge's `random_nonce`.
+    ///
+    /// # Returns
+    /// * `String`: The output as hex, ready for a `TimeLockResponse`.
+    #[cfg(feature = "timelock")]
+    pub fn solve(&self, random_nonce: &str) -> String {
+        let modulus = BigUint::parse_bytes(self.modulus.as_bytes(), 16)
+            .expect("validated modulus is hex");
+        let mut value: BigUint = derive_base(BASE_CONTEXT, &modulus, random_nonce);
+        for _ in 0..self.iterations {
+            value = &value * &value % &modulus;
+        }
+        hex::encode(to_padded_bytes(&value, self.modulus_len()))
+    }
+
+    /// # Returns
+    /// * `TimeLockPuzzle`: An owned copy of the puzzle.
+    pub fn to_owned_puzzle(&self) -> TimeLockPuzzle {
+        TimeLockPuzzle {
+            modulus:    self.modulus.to_string(),
+            iterations: self.iterations,
+            target:     self.target,
+        }
+    }
+
+    /// # Returns
+    /// * `String`: The puzzle as `<hex modulus>,<iterations>,<hex target>`.
+    pub fn to_wire_string(&self) -> String {
+        format!("{},{},{}", self.modulus, self.iterations, hex::encode(self.target))
+    }
+}
+
+impl TimeLockPuzzle {
+    /// # Returns
+    /// * `TimeLockPuzzleRef`: The puzzle borrowing its modulus.
+    pub fn as_puzzle_ref(&self) -> TimeLockPuzzleRef<'_> {
+        TimeLockPuzzleRef {
+            modulus:    &self.modulus,
+            iterations: self.iterations,
+            target:     self.target,
+        }
+    }
+
+    /// See `TimeLockPuzzleRef::verify_output`.
+    pub fn verify_output(&self, output: &str) -> Result<(), String> {
+        self.as_puzzle_ref().verify_output(output)
+    }
+
+    /// See `TimeLockPuzzleRef::solve`.
+    #[cfg(feature = "timelock")]
+    pub fn solve(&self, random_nonce: &str) -> String {
+        self.as_puzzle_ref().solve(random_nonce)
+    }
+
+    /// # Returns
+    /// * `String`: The puzzle as `<hex modulus>,<iterations>,<hex target>`.
+    pub fn to_wire_string(&self) -> String {
+        self.as_puzzle_ref().to_wire_string()
+    }
+
+    /// # Arguments
+    /// * `wire`: The string produced by `to_wire_string()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The puzzle, or an error describing the
+    ///                           malformed field.
+    pub fn from_wire_string(wire: &str) -> Result<Self, String> {
+        TimeLockPuzzleRef::from_wire_string(wire).map(|puzzle| puzzle.to_owned_puzzle())
+    }
+}
+
+impl From<TimeLockPuzzle> for String {
+    fn from(puzzle: TimeLockPuzzle) -> Self {
+        puzzle.to_wire_string()
+    }
+}
+
+impl TryFrom<String> for TimeLockPuzzle {
+    type Error = String;
+
+    fn try_from(wire: String) -> Result<Self, Self::Error> {
+        Self::from_wire_string(&wire)
+    }
+}
+
+/// Derives a group element from a challenge nonce: counter-mode SHA-256
+/// of `context`, expanded 16 bytes past the modulus length, reduced
+/// modulo `modulus`.
+#[cfg(any(feature = "timelock", feature = "vdf"))]
+pub(crate) fn derive_base(context: &[u8], modulus: &BigUint, random_nonce: &str) -> BigUint {
+    let length: usize = modulus.bits().div_ceil(8) as usize + 16;
+    let mut expanded: Vec<u8> = Vec::with_capacity(length + 32);
+    let mut counter: u32 = 0;
+    while expanded.len() < length {
+        expanded.extend_from_slice(&Sha256::new()
+            .chain_update(context)
+            .chain_update(counter.to_be_bytes())
+            .chain_update(random_nonce.as_bytes())
+            .finalize());
+        counter += 1;
+    }
+    BigUint::from_bytes_be(&expanded[..length]) % modulus
+}
+
+/// # Returns
+/// * `Vec<u8>`: `value` big-endian, left-padded with zeros to `length`.
+#[cfg(any(feature = "timelock", feature = "vdf"))]
+pub(crate) fn to_padded_bytes(value: &BigUint, length: usize) -> Vec<u8> {
+    let bytes: Vec<u8> = value.to_bytes_be();
+    let mut padded: Vec<u8> = vec![0u8; length.saturating_sub(bytes.len())];
+    padded.extend_from_slice(&bytes);
+    padded
+}
+
+/// Small primes for trial division and as Miller-Rabin witnesses.
+#[cfg(any(feature = "timelock", feature = "vdf"))]
+const SMALL_PRIMES: [u32; 20] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71];
+
+/// Miller-Rabin test with the first 20 primes as witnesses.
+///
+/// # Arguments
+/// * `candidate`: The number to test.
+///
+/// # Returns
+/// * `bool`: `true` if `candidate` is prime with overwhelming
+///           probability for non-adversarial inputs.
+#[cfg(any(feature = "timelock", feature = "vdf"))]
+pub(crate) fn is_probable_prime(candidate: &BigUint) -> bool {
+    let one = BigUint::from(1u32);
+    if *candidate <= one {
+        return false;
+    }
+    for prime in SMALL_PRIMES {
+        let prime = BigUint::from(prime);
+        if *candidate == prime {
+            return true;
+        }
+        if (candidate % &prime).bits() == 0 {
+            return false;
+        }
+    }
+
+    let candidate_minus_one: BigUint = candidate - &one;
+    let shift: u64 = candidate_minus_one.trailing_zeros().unwrap_or(0);
+    let odd_part: BigUint = &candidate_minus_one >> shift;
+
+    SMALL_PRIMES.iter().all(|witness| {
+        let mut x: BigUint = BigUint::from(*witness).modpow(&odd_part, candidate);
+        if x == one || x == candidate_minus_one {
+            return true;
+        }
+        for _ in 1..shift {
+            x = x.modpow(&BigUint::from(2u32), candidate);
+            if x == candidate_minus_one {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// The issuer's trapdoor: the two prime factors of the modulus.
+///
+/// Whoever holds it can compute any puzzle's output without the
+/// squarings, so it never leaves the issuer.
+#[cfg(feature = "timelock")]
+#[derive(Clone)]
+pub struct TimeLockKey {
+    p: BigUint,
+    q: BigUint,
+}
+
+#[cfg(feature = "timelock")]
+impl std::fmt::Debug for TimeLockKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimeLockKey")
+            .field("modulus_bits", &self.modulus().bits())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "timelock")]
+impl TimeLockKey {
+    /// # Arguments
+    /// * `p`, `q`: Two distinct odd primes, e.g. from an existing
+    ///             RSA key.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The key, or an error if either factor
+    ///                           is not an odd prime or they are equal.
+    pub fn from_primes(p: BigUint, q: BigUint) -> Result<Self, String> {
+        if p == q {
+            return Err("Time-lock primes must be distinct".to_string());
+        }
+        if !p.bit(0) || !q.bit(0) || !is_probable_prime(&p) || !is_probable_prime(&q) {
+            return Err("Time-lock factors must be odd primes".to_string());
+        }
+        if (&p * &q).bits() > MAX_TIMELOCK_MODULUS_BITS {
+            return Err(format!("Time-lock modulus exceeds {} bits", MAX_TIMELOCK_MODULUS_BITS));
+        }
+        Ok(Self { p, q })
+    }
+
+    /// Generates two random primes of `modulus_bits / 2` bits each.
+    /// Takes a noticeable time for large moduli; generate once and
+    /// reuse the key for many puzzles.
+    ///
+    /// # Arguments
+    /// * `modulus_bits`: Size of the modulus, at least 1024; 2048 is
+    ///                   recommended.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The key, or an error if the size is out
+    ///                           of range.
+    #[cfg(feature = "rng")]
+    pub fn generate(modulus_bits: u64) -> Result<Self, String> {
+        if !(MIN_TIMELOCK_MODULUS_BITS..=MAX_TIMELOCK_MODULUS_BITS).contains(&modulus_bits) || !modulus_bits.is_multiple_of(16) {
+            return Err(format!(
+                "Time-lock modulus must be a multiple of 16 bits between {} and {}",
+                MIN_TIMELOCK_MODULUS_BITS,
+                MAX_TIMELOCK_MODULUS_BITS
+            ));
+        }
+
+        let p: BigUint = Self::random_prime(modulus_bits / 2);
+        let q: BigUint = loop {
+            let q: BigUint = Self::random_prime(modulus_bits / 2);
+            if q != p {
+                break q;
+            }
+        };
+        Ok(Self { p, q })
+    }
+
+    /// Draws odd candidates with the two top bits set, so the product
+    /// of two of them has exactly twice the bits, until one is prime.
+    #[cfg(feature = "rng")]
+    fn random_prime(bits: u64) -> BigUint {
+        use rand::RngCore;
+
+        let mut bytes: Vec<u8> = vec![0u8; (bits / 8) as usize];
+        loop {
+            rand::thread_rng().fill_bytes(&mut bytes);
+            bytes[0] |= 0xC0;
+            *bytes.last_mut().expect("at least 64 bytes") |= 1;
+            let candidate = BigUint::from_bytes_be(&bytes);
+            if is_probable_prime(&candidate) {
+                return candidate;
+            }
+        }
+    }
+
+    /// # Returns
+    /// * `BigUint`: The public modulus `p * q`.
+    pub fn modulus(&self) -> BigUint {
+        &self.p * &self.q
+    }
+
+    /// Creates the puzzle for a challenge nonce, computing the output
+    /// through the factorization: `x^(2^t mod (p-1)) mod p` and the same
+    /// modulo `q`, recombined with the CRT.
+    ///
+    /// # Arguments
+    /// * `random_nonce`: The challenge's `random_nonce`.
+    /// * `iterations`:   Number of squarings the solver must perform.
+    ///
+    /// # Returns
+    /// * `Result<TimeLockPuzzle, String>`: The puzzle, or an error if
+    ///                                     `iterations` is zero.
+    pub fn puzzle(&self, random_nonce: &str, iterations: u64) -> Result<TimeLockPuzzle, String> {
+        if iterations == 0 {
+            return Err("Time-lock puzzle needs at least one iteration".to_string());
+        }
+
+        let modulus: BigUint = self.modulus();
+        let modulus_len: usize = modulus.bits().div_ceil(8) as usize;
+        let base: BigUint = derive_base(BASE_CONTEXT, &modulus, random_nonce);
+
+        let one = BigUint::from(1u32);
+        let two = BigUint::from(2u32);
+        let power = |prime: &BigUint| -> BigUint {
+            let base: BigUint = &base % prime;
+            if base.bits() == 0 {
+                return base;
+            }
+            let exponent: BigUint = two.modpow(&BigUint::from(iterations), &(prime - &one));
+            base.modpow(&exponent, prime)
+        };
+        let output_p: BigUint = power(&self.p);
+        let output_q: BigUint = power(&self.q);
+
+        // output = output_q + q * ((output_p - output_q) * q^-1 mod p)
+        let q_inverse: BigUint = self.q.modpow(&(&self.p - &two), &self.p);
+        let difference: BigUint = (&output_p + &self.p - (&output_q % &self.p)) % &self.p;
+        let output: BigUint = &output_q + &self.q * (difference * q_inverse % &self.p);
+
+        Ok(TimeLockPuzzle {
+            modulus: hex::encode(to_padded_bytes(&modulus, modulus_len)),
+            iterations,
+            target:  commit_output(&to_padded_bytes(&output, modulus_len)),
+        })
+    }
+}
+
+/// Response to a time-lock challenge.
+///
+/// * `solved_challenge`: The complete original challenge.
+/// * `output`:           The puzzle output as hex, padded to the
+///                       modulus length.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeLockResponse {
+    pub solved_challenge: IronShieldChallenge,
+    pub output:           String,
+}
+
+impl TimeLockResponse {
+    /// # Arguments
+    /// * `solved_challenge`: The solved challenge.
+    /// * `output`:           The output from `TimeLockPuzzle::solve`.
+    ///
+    /// # Returns
+    /// * `Self`: The response.
+    pub fn new(solved_challenge: IronShieldChallenge, output: String) -> Self {
+        Self { solved_challenge, output }
+    }
+
+    /// Checks the output against the challenge's puzzle. Signature and
+    /// expiry are validated separately.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the output solves the puzzle,
+    ///                         or an error if it does not or the
+    ///                         challenge carries no puzzle.
+    pub fn verify_output(&self) -> Result<(), String> {
+        self.solved_challenge.time_lock.as_ref()
+            .ok_or("Challenge is not a time-lock challenge")?
+            .verify_output(&self.output)
+    }
+
+    /// Concatenates the response data into a string.
+    ///
+    /// Concatenates:
+    /// * `solved_challenge`: As its concatenated string representation.
+    /// * `output`:           As a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        format!("{}|{}", self.solved_challenge.concat_struct(), self.output)
+    }
+
+    /// # Arguments
+    /// * `concat_string`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed response or an error message.
+    pub fn from_concat_struct(concat_string: &str) -> Result<Self, String> {
+        let (challenge_part, output) = concat_string.rsplit_once('|')
+            .ok_or("Expected at least one '|' separator")?;
+
+        Ok(Self {
+            solved_challenge: IronShieldChallenge::from_concat_struct(challenge_part)?,
+            output:           output.to_string(),
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The response, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded response or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+#[cfg(all(test, feature = "rng", feature = "timelock"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    /// 2^61 - 1 and 2^31 - 1, two Mersenne primes; far too small for
+    /// real use but quick to test with.
+    fn key() -> TimeLockKey {
+        TimeLockKey::from_primes(BigUint::from((1u64 << 61) - 1), BigUint::from((1u64 << 31) - 1)).unwrap()
+    }
+
+    #[test]
+    fn test_trapdoor_matches_squaring() {
+        let key = key();
+        for iterations in [1, 2, 1_000] {
+            let puzzle = key.puzzle("00112233", iterations).unwrap();
+            puzzle.verify_output(&puzzle.solve("00112233")).unwrap();
+            assert!(puzzle.verify_output(&puzzle.solve("00112234")).is_err());
+        }
+        let wire: String = key.puzzle("00", 7).unwrap().to_wire_string();
+        assert_eq!(TimeLockPuzzle::from_wire_string(&wire).unwrap().to_wire_string(), wire);
+        assert!(TimeLockPuzzle::from_wire_string(&wire.replacen(",7,", ",0,", 1)).is_err());
+        assert!(TimeLockPuzzle::from_wire_string(&format!("00{}", wire)).is_err());
+
+        assert!(TimeLockKey::from_primes(BigUint::from(91u32), BigUint::from(7u32)).is_err());
+        assert!(TimeLockKey::generate(512).is_err());
+        let generated = TimeLockKey::generate(MIN_TIMELOCK_MODULUS_BITS).unwrap();
+        assert_eq!(generated.modulus().bits(), MIN_TIMELOCK_MODULUS_BITS);
+    }
+
+    #[test]
+    fn test_time_lock_challenge() {
+        let signing_key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, signing_key.clone(), signing_key.verifying_key().to_bytes())
+            .with_time_lock(&key(), 500, &signing_key)
+            .unwrap();
+        assert_eq!(challenge.algorithm(), crate::PowAlgorithm::TimeLock);
+        crate::verify_challenge_signature_with_key(&challenge, &signing_key.verifying_key().to_bytes()).unwrap();
+        assert!(challenge.check_solutions(&[0]).is_err());
+
+        let decoded = IronShieldChallenge::from_base64url_header(&challenge.to_base64url_header()).unwrap();
+        assert_eq!(decoded.time_lock, challenge.time_lock);
+
+        let puzzle: &TimeLockPuzzle = challenge.time_lock.as_ref().unwrap();
+        let response = TimeLockResponse::new(challenge.clone(), puzzle.solve(challenge.random_nonce.as_str()));
+        response.verify_output().unwrap();
+        let decoded = TimeLockResponse::from_base64url_header(&response.to_base64url_header()).unwrap();
+        assert_eq!(decoded, response);
+        let forged = TimeLockResponse::new(challenge.clone(), "00".repeat(puzzle.as_puzzle_ref().modulus_len()));
+        assert!(forged.verify_output().is_err());
+    }
+}
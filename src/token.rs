@@ -1,13 +1,21 @@
-use chrono::Utc;
 use serde::{
     Deserialize, 
     Serialize
 };
 
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::trace::traced;
 use crate::serde_utils::{
     serialize_signature, 
-    deserialize_signature
+    deserialize_signature,
+    serialize_optional_32_bytes,
+    deserialize_optional_32_bytes
 };
+use crate::epoch::now_millis;
 
 /// IronShield Token structure
 ///
@@ -16,7 +24,12 @@ use crate::serde_utils::{
 /// * `public_key`:               The Ed25519 public key corresponding
 ///                               to the central private key (32 bytes).
 /// * `authentication_signature`: The signature over (challenge_signature
-///                               || valid_for).
+///                               || valid_for || body_digest).
+/// * `body_digest`:              Optional SHA-256 of the request body the
+///                               token attests to.
+/// * `encrypted_claims`:         Optional claims (e.g. IP and user agent
+///                               bindings) encrypted for the issuer; the
+///                               ciphertext is covered by the signature.
 #[cfg(feature = "openapi")]
 #[allow(unused_imports)]
 use serde_json::json;
@@ -47,6 +60,68 @@ pub struct IronShieldToken {
     )]
     #[cfg_attr(feature = "openapi", schema(example = json!([156, 23, 45, 67, 89, 123, 210, 98, 76, 54, 32, 187, 145, 67, 89, 210, 123, 45, 67, 89, 210, 98, 76, 54, 32, 187, 145, 67, 89, 210, 123, 45, 67, 89, 210, 98, 76, 54, 32, 187, 145, 67, 89, 210, 123, 45, 67, 89, 210, 98, 76, 54, 32, 187, 145, 67, 89, 210, 123, 45, 67, 89, 210, 98])))]
     pub auth_signature:      [u8; 64],
+    /// SHA-256 digest of the request body this token attests to (32 bytes)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_32_bytes",
+        deserialize_with = "deserialize_optional_32_bytes"
+    )]
+    pub body_digest:         Option<[u8; 32]>,
+    /// Claims encrypted with the issuer's claims key (see `TokenClaims`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Vec<u8>>))]
+    pub encrypted_claims:    Option<Vec<u8>>,
+}
+
+/// Number of `|`-delimited parts before the optional extensions.
+const BASE_PART_COUNT: usize = 4;
+
+/// Extension key for `body_digest` in the concatenated format.
+const EXT_BODY_DIGEST: &str = "bd";
+/// Extension key for `encrypted_claims` in the concatenated format.
+const EXT_ENCRYPTED_CLAIMS: &str = "ec";
+
+/// Incremental SHA-256 hasher for request bodies.
+///
+/// Lets a verifier hash a body as it streams in, without buffering
+/// it, and compare the result with a token's `body_digest`.
+#[derive(Debug, Clone, Default)]
+pub struct BodyDigestHasher {
+    hasher: Sha256,
+}
+
+impl BodyDigestHasher {
+    /// # Returns
+    /// * `Self`: A hasher with no data consumed yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next chunk of the body into the hasher.
+    ///
+    /// # Arguments
+    /// * `chunk`: The next bytes of the body.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The SHA-256 digest of every chunk fed so far.
+    pub fn finalize(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+/// Computes the body digest committed into a token.
+///
+/// # Arguments
+/// * `body`: The complete request body.
+///
+/// # Returns
+/// * `[u8; 32]`: The SHA-256 digest of `body`.
+pub fn compute_body_digest(body: &[u8]) -> [u8; 32] {
+    Sha256::digest(body).into()
 }
 
 impl IronShieldToken {
@@ -61,13 +136,123 @@ impl IronShieldToken {
             valid_for,
             public_key,
             auth_signature,
+            body_digest: None,
+            encrypted_claims: None,
+        }
+    }
+
+    /// Creates the message covered by `auth_signature`.
+    ///
+    /// This is `create_token_signing_message` followed by `|bd=<hex>`
+    /// when the token carries a body digest and `|ec=<hex>` when it
+    /// carries encrypted claims, so tokens without them keep their
+    /// original signing message. The signature covers the ciphertext,
+    /// so it verifies without the claims key.
+    ///
+    /// # Returns
+    /// * `String`: Canonical string representation for signing.
+    pub fn signing_message(&self) -> String {
+        let mut message: String = crate::crypto::create_token_signing_message(
+            &self.challenge_signature,
+            self.valid_for
+        );
+
+        if let Some(digest) = self.body_digest {
+            message.push_str(&format!("|{}={}", EXT_BODY_DIGEST, hex::encode(digest)));
+        }
+        if let Some(claims) = &self.encrypted_claims {
+            message.push_str(&format!("|{}={}", EXT_ENCRYPTED_CLAIMS, hex::encode(claims)));
+        }
+
+        message
+    }
+
+    /// Checks a fully buffered request body against `body_digest`.
+    ///
+    /// # Arguments
+    /// * `body`: The request body received alongside the token.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the body matches, or an error
+    ///                         if the token has no digest or it differs.
+    pub fn verify_body(&self, body: &[u8]) -> Result<(), String> {
+        self.verify_body_digest(&compute_body_digest(body))
+    }
+
+    /// Checks a streamed request body against `body_digest`.
+    ///
+    /// # Arguments
+    /// * `reader`: The request body stream.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the body matches, or an error
+    ///                         if reading fails, the token has no digest,
+    ///                         or the digest differs.
+    pub fn verify_body_reader<R: std::io::Read>(&self, mut reader: R) -> Result<(), String> {
+        let mut hasher = BodyDigestHasher::new();
+        let mut buffer = [0u8; 8192];
+
+        loop {
+            let read: usize = reader.read(&mut buffer)
+                .map_err(|e| format!("Failed to read request body: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
         }
+
+        self.verify_body_digest(&hasher.finalize())
+    }
+
+    /// Compares a precomputed body digest with `body_digest`.
+    ///
+    /// # Arguments
+    /// * `digest`: The SHA-256 digest of the received body,
+    ///             e.g. from `BodyDigestHasher`.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the digests match.
+    pub fn verify_body_digest(&self, digest: &[u8; 32]) -> Result<(), String> {
+        match self.body_digest {
+            Some(expected) if &expected == digest => Ok(()),
+            Some(_) => Err("Request body does not match the token body digest".to_string()),
+            None => Err("Token does not carry a body digest".to_string()),
+        }
+    }
+
+    /// Token identifier (JWT `jti` equivalent) used by caches and
+    /// revocation lists.
+    ///
+    /// The identifier is the SHA-256 of `concat_struct()`, so it covers
+    /// every field: a token altered after issuance never shares the
+    /// identifier of the genuine one.
+    ///
+    /// # Returns
+    /// * `[u8; 32]`: The token identifier.
+    pub fn jti(&self) -> [u8; 32] {
+        Sha256::digest(self.concat_struct().as_bytes()).into()
+    }
+
+    /// Checks the fields of a token received from untrusted input. This
+    /// does not verify the signature.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid field.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.valid_for <= 0 {
+            return Err("valid_for must be positive".to_string());
+        }
+        if self.challenge_signature == [0u8; 64] || self.auth_signature == [0u8; 64] {
+            return Err("Token signatures must not be empty".to_string());
+        }
+        Ok(())
     }
 
     /// # Returns
     /// * `bool`: 
     pub fn is_expired(&self) -> bool {
-        Utc::now().timestamp_millis() > self.valid_for
+        now_millis() > self.valid_for
     }
 
     /// Concatenates the token data into a string.
@@ -77,8 +262,10 @@ impl IronShieldToken {
     /// - `valid_for`:                as a string.
     /// - `public_key`:               as a lowercase hex string.
     /// - `authentication_signature`: as a lowercase hex string.
+    /// - `body_digest`:              as `bd=<hex>`, only when present.
+    /// - `encrypted_claims`:         as `ec=<hex>`, only when present.
     pub fn concat_struct(&self) -> String {
-        format!(
+        let mut concat: String = format!(
             "{}|{}|{}|{}",
             // Use of hex::encode to convert the arrays to hex strings
             // "Encodes data as hex string using lowercase characters."
@@ -87,7 +274,16 @@ impl IronShieldToken {
             self.valid_for,
             hex::encode(self.public_key),
             hex::encode(self.auth_signature)
-        )
+        );
+
+        if let Some(digest) = self.body_digest {
+            concat.push_str(&format!("|{}={}", EXT_BODY_DIGEST, hex::encode(digest)));
+        }
+        if let Some(claims) = &self.encrypted_claims {
+            concat.push_str(&format!("|{}={}", EXT_ENCRYPTED_CLAIMS, hex::encode(claims)));
+        }
+
+        concat
     }
 
     /// Creates an `IronShieldToken` from a concatenated string.
@@ -95,6 +291,7 @@ impl IronShieldToken {
     /// This function reverses the operation of `IronShieldToken::concat_struct`.
     /// Expects a string in the format:
     /// "challenge_signature|valid_for|public_key|authentication_signature"
+    /// optionally followed by `|key=value` extension parts.
     ///
     /// # Arguments
     ///
@@ -109,8 +306,12 @@ impl IronShieldToken {
     pub fn from_concat_struct(concat_str: &str) -> Result<Self, String> {
         let parts: Vec<&str> = concat_str.split('|').collect();
 
-        if parts.len() != 4 {
-            return Err(format!("Expected 4 parts, got {}", parts.len()));
+        // Trailing parts are only valid as `key=value` extensions.
+        let has_malformed_extension: bool = parts.iter()
+            .skip(BASE_PART_COUNT)
+            .any(|part| !part.contains('='));
+        if parts.len() < BASE_PART_COUNT || has_malformed_extension {
+            return Err(format!("Expected {} parts, got {}", BASE_PART_COUNT, parts.len()));
         }
 
         let challenge_signature_bytes = hex::decode(parts[0])
@@ -131,11 +332,37 @@ impl IronShieldToken {
         let authentication_signature: [u8; 64] = auth_signature_bytes.try_into()
             .map_err(|_| "Authentication signature must be exactly 64 bytes")?;
 
+        let mut body_digest: Option<[u8; 32]> = None;
+        let mut encrypted_claims: Option<Vec<u8>> = None;
+        for part in &parts[BASE_PART_COUNT..] {
+            let (key, value) = part.split_once('=')
+                .ok_or_else(|| format!("Malformed token extension: {}", part))?;
+
+            match key {
+                EXT_BODY_DIGEST if body_digest.is_none() => {
+                    let digest_bytes: Vec<u8> = hex::decode(value)
+                        .map_err(|_| "Failed to decode body_digest hex string")?;
+                    let digest: [u8; 32] = digest_bytes.try_into()
+                        .map_err(|_| "Body digest must be exactly 32 bytes")?;
+                    body_digest = Some(digest);
+                }
+                EXT_ENCRYPTED_CLAIMS if encrypted_claims.is_none() => {
+                    let claims: Vec<u8> = hex::decode(value)
+                        .map_err(|_| "Failed to decode encrypted_claims hex string")?;
+                    encrypted_claims = Some(claims);
+                }
+                EXT_BODY_DIGEST | EXT_ENCRYPTED_CLAIMS => return Err(format!("Duplicate token extension: {}", key)),
+                _ => return Err(format!("Unknown token extension: {}", key)),
+            }
+        }
+
         Ok(Self {
             challenge_signature,
             valid_for,
             public_key,
             auth_signature: authentication_signature,
+            body_digest,
+            encrypted_claims,
         })
     }
 
@@ -180,11 +407,16 @@ impl IronShieldToken {
     /// assert_eq!(original.challenge_signature, decoded.challenge_signature);
     /// ```
     pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
-        // Decode using the existing serde_utils function.
-        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
-
-        // Parse using the existing concat_struct format.
-        Self::from_concat_struct(&concat_str)
+        traced!("decode_token_header", Result<Self, String>, {
+            header_len = encoded_header.len(),
+            valid_for = tracing::field::Empty,
+        }, |token| { valid_for = token.valid_for }, {
+            // Decode using the existing serde_utils function.
+            let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+
+            // Parse using the existing concat_struct format.
+            Self::from_concat_struct(&concat_str)
+        })
     }
 }
 
@@ -265,6 +497,72 @@ mod tests {
     }
 
 
+    #[test]
+    fn test_body_digest_roundtrip() {
+        let mut token = IronShieldToken::new([0xAB; 64], 1700000000000, [0xCD; 32], [0xEF; 64]);
+        token.body_digest = Some(compute_body_digest(b"name=value"));
+
+        let concat_str = token.concat_struct();
+        assert!(concat_str.ends_with(&format!("|bd={}", hex::encode(token.body_digest.unwrap()))));
+
+        let parsed = IronShieldToken::from_base64url_header(&token.to_base64url_header()).unwrap();
+        assert_eq!(parsed.body_digest, token.body_digest);
+        assert!(parsed.signing_message().ends_with(&format!("|bd={}", hex::encode(token.body_digest.unwrap()))));
+    }
+
+    #[test]
+    fn test_verify_body() {
+        let mut token = IronShieldToken::new([0xAB; 64], 1700000000000, [0xCD; 32], [0xEF; 64]);
+        assert!(token.verify_body(b"anything").unwrap_err().contains("does not carry a body digest"));
+
+        token.body_digest = Some(compute_body_digest(b"name=value&other=1"));
+        assert!(token.verify_body(b"name=value&other=1").is_ok());
+        assert!(token.verify_body(b"name=value&other=2").unwrap_err().contains("does not match"));
+
+        // Streaming in small chunks yields the same digest.
+        let reader = std::io::Cursor::new(b"name=value&other=1".to_vec());
+        assert!(token.verify_body_reader(reader).is_ok());
+
+        let mut hasher = BodyDigestHasher::new();
+        hasher.update(b"name=value");
+        hasher.update(b"&other=1");
+        assert!(token.verify_body_digest(&hasher.finalize()).is_ok());
+    }
+
+    #[test]
+    fn test_body_digest_extension_errors() {
+        let valid_32_hex = "0".repeat(64);
+        let valid_64_hex = "0".repeat(128);
+        let base = format!("{}|1000000|{}|{}", valid_64_hex, valid_32_hex, valid_64_hex);
+
+        let result = IronShieldToken::from_concat_struct(&format!("{}|bd=abcd", base));
+        assert!(result.unwrap_err().contains("Body digest must be exactly 32 bytes"));
+
+        let result = IronShieldToken::from_concat_struct(&format!("{}|bd={}|bd={}", base, valid_32_hex, valid_32_hex));
+        assert!(result.unwrap_err().contains("Duplicate token extension"));
+
+        let result = IronShieldToken::from_concat_struct(&format!("{}|xx=1", base));
+        assert!(result.unwrap_err().contains("Unknown token extension"));
+    }
+
+    #[test]
+    fn test_encrypted_claims_extension() {
+        let mut token = IronShieldToken::new([0xAB; 64], 1700000000000, [0xCD; 32], [0xEF; 64]);
+        token.body_digest = Some([0x11; 32]);
+        token.encrypted_claims = Some(vec![1, 2, 3]);
+        assert!(token.signing_message().ends_with(&format!("|bd={}|ec=010203", hex::encode([0x11; 32]))));
+
+        let parsed = IronShieldToken::from_base64url_header(&token.to_base64url_header()).unwrap();
+        assert_eq!(parsed.encrypted_claims, Some(vec![1, 2, 3]));
+        assert_eq!(parsed.body_digest, token.body_digest);
+
+        let base = token.concat_struct();
+        let result = IronShieldToken::from_concat_struct(&format!("{}|ec=04", base));
+        assert!(result.unwrap_err().contains("Duplicate token extension"));
+        let result = IronShieldToken::from_concat_struct(&format!("{}|ec=zz", IronShieldToken::new([0xAB; 64], 1, [0xCD; 32], [0xEF; 64]).concat_struct()));
+        assert!(result.unwrap_err().contains("encrypted_claims"));
+    }
+
     #[test]
     fn test_from_concat_struct_error_cases() {
         // Test with the wrong number of parts.
@@ -0,0 +1,235 @@
+//! # Encrypted token claims.
+//!
+//! A token can bind itself to the client it was issued to, e.g. by IP
+//! address and user agent. Those bindings are personal data that the
+//! downstream proxies relaying the token have no need to read, so they
+//! travel encrypted in `IronShieldToken::encrypted_claims`:
+//!
+//! * `version`:    `TOKEN_CLAIMS_VERSION`.
+//! * `nonce`:      24 random bytes.
+//! * `ciphertext`: The JSON of `TokenClaims` encrypted with
+//!                 XChaCha20-Poly1305, followed by its 16-byte tag.
+//!
+//! The key is HKDF-SHA256 of the issuer's signing seed, so whoever
+//! issues tokens can read their claims, and `derive_token_claims_key`
+//! lets the issuer hand that key to an origin without the seed. The
+//! token's challenge signature and expiry are the AEAD's associated
+//! data, so claims cannot be moved to another token.
+//!
+//! The ciphertext, not the plaintext, is part of the token's signing
+//! message, so `verify_token_signature_with_key()` still works for
+//! holders of the public key alone, and any change to the ciphertext
+//! breaks the signature as well as decryption.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305,
+    XNonce
+};
+use ed25519_dalek::SigningKey;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::key_derivation::hkdf_sha256;
+use crate::{
+    generate_signature,
+    CryptoError,
+    ErrorDetail,
+    IronShieldChallenge,
+    IronShieldToken,
+    LifetimePolicy
+};
+use crate::epoch::now_millis;
+
+/// Version byte at the start of every encrypted claims section.
+pub const TOKEN_CLAIMS_VERSION: u8 = 1;
+/// HKDF salt separating the claims key from other seed derivations.
+const CLAIMS_KDF_SALT: &[u8] = b"ironshield-token-claims-v1";
+/// Length of the XChaCha20-Poly1305 nonce.
+const NONCE_LEN: usize = 24;
+/// Length of the Poly1305 tag appended to the ciphertext.
+const TAG_LEN: usize = 16;
+
+/// Client bindings carried encrypted inside a token.
+///
+/// * `client_ip`:  IP address the token was issued to.
+/// * `user_agent`: User agent the token was issued to.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ip:  Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+/// Derives the key that encrypts token claims from the signing seed.
+///
+/// # Arguments
+/// * `signing_key`: The issuer's token signing key.
+///
+/// # Returns
+/// * `[u8; 32]`: The claims key, which reveals nothing about the seed.
+pub fn derive_token_claims_key(signing_key: &SigningKey) -> [u8; 32] {
+    hkdf_sha256(&signing_key.to_bytes(), CLAIMS_KDF_SALT, &signing_key.verifying_key().to_bytes())
+}
+
+/// Associated data binding a claims section to its token.
+fn claims_aad(token: &IronShieldToken) -> [u8; 72] {
+    let mut aad = [0u8; 72];
+    aad[..64].copy_from_slice(&token.challenge_signature);
+    aad[64..].copy_from_slice(&token.valid_for.to_be_bytes());
+    aad
+}
+
+/// Issues a signed token carrying encrypted claims.
+///
+/// # Arguments
+/// * `challenge`:   The challenge that was solved.
+/// * `signing_key`: The Ed25519 key used to sign the token; the claims
+///                  key is derived from it.
+/// * `policy`:      The lifetime policy defining the token TTL.
+/// * `body_digest`: SHA-256 of the body the client intends to send, if any.
+/// * `claims`:      The client bindings to encrypt.
+///
+/// # Returns
+/// * `Result<IronShieldToken, CryptoError>`: The signed token or an error.
+pub fn issue_token_with_claims(
+    challenge: &IronShieldChallenge,
+    signing_key: &SigningKey,
+    policy: &LifetimePolicy,
+    body_digest: Option<[u8; 32]>,
+    claims: &TokenClaims
+) -> Result<IronShieldToken, CryptoError> {
+    let mut token = IronShieldToken::new(
+        challenge.challenge_signature,
+        policy.token_expiration(now_millis()),
+        signing_key.verifying_key().to_bytes(),
+        [0u8; 64],
+    );
+    token.body_digest = body_digest;
+
+    let plaintext: Vec<u8> = serde_json::to_vec(claims)
+        .map_err(|e| CryptoError::SigningFailed(format!("Failed to encode token claims: {}", e).into()))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut key: [u8; 32] = derive_token_claims_key(signing_key);
+    let sealed = XChaCha20Poly1305::new(&key.into())
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &plaintext, aad: &claims_aad(&token) })
+        .map_err(|_| CryptoError::SigningFailed("Failed to encrypt token claims".into()));
+    key.fill(0);
+
+    let mut section: Vec<u8> = Vec::with_capacity(1 + NONCE_LEN + plaintext.len() + TAG_LEN);
+    section.push(TOKEN_CLAIMS_VERSION);
+    section.extend_from_slice(&nonce);
+    section.extend_from_slice(&sealed?);
+    token.encrypted_claims = Some(section);
+
+    token.auth_signature = generate_signature(signing_key, &token.signing_message())?;
+    Ok(token)
+}
+
+/// Decrypts the claims of a token.
+///
+/// This does not verify the token's signature; verify it first.
+///
+/// # Arguments
+/// * `token`:      The token.
+/// * `claims_key`: The key from `derive_token_claims_key()`.
+///
+/// # Returns
+/// * `Result<TokenClaims, CryptoError>`: The claims; `VerificationFailed`
+///                                       if the key is wrong or the
+///                                       section was altered, and
+///                                       `InvalidKeyFormat` if the token
+///                                       has no claims or an unsupported
+///                                       version.
+pub fn decrypt_token_claims(token: &IronShieldToken, claims_key: &[u8; 32]) -> Result<TokenClaims, CryptoError> {
+    let section: &[u8] = token.encrypted_claims.as_deref()
+        .ok_or_else(|| CryptoError::InvalidKeyFormat("Token does not carry encrypted claims".into()))?;
+    if section.len() < 1 + NONCE_LEN + TAG_LEN {
+        return Err(CryptoError::InvalidKeyFormat(format!("Encrypted claims are too short: {} bytes", section.len()).into()));
+    }
+    if section[0] != TOKEN_CLAIMS_VERSION {
+        return Err(CryptoError::InvalidKeyFormat(format!("Unsupported token claims version: {}", section[0]).into()));
+    }
+
+    let (nonce, ciphertext) = section[1..].split_at(NONCE_LEN);
+    let plaintext: Vec<u8> = XChaCha20Poly1305::new(claims_key.into())
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: &claims_aad(token) })
+        .map_err(|_| CryptoError::VerificationFailed("Wrong claims key, or the token claims were altered".into()))?;
+
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid token claims: {}", e), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify_token_signature_with_key;
+
+    fn issue(signing_key: &SigningKey, claims: &TokenClaims) -> IronShieldToken {
+        let public_key: [u8; 32] = signing_key.verifying_key().to_bytes();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, signing_key.clone(), public_key);
+        issue_token_with_claims(&challenge, signing_key, &LifetimePolicy::default(), None, claims).unwrap()
+    }
+
+    fn claims() -> TokenClaims {
+        TokenClaims {
+            client_ip:  Some("203.0.113.7".to_string()),
+            user_agent: Some("Mozilla/5.0 (X11; Linux x86_64)".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_claims_round_trip_with_verifiable_signature() {
+        let signing_key = SigningKey::from_bytes(&[6; 32]);
+        let token: IronShieldToken = issue(&signing_key, &claims());
+
+        // Proxies check the signature with the public key alone.
+        assert!(verify_token_signature_with_key(&token, &token.public_key).is_ok());
+
+        let header: String = token.to_base64url_header();
+        let concat: String = token.concat_struct();
+        assert!(!concat.contains("203.0.113.7") && !concat.contains(&hex::encode("203.0.113.7")));
+
+        let received = IronShieldToken::from_base64url_header(&header).unwrap();
+        assert!(verify_token_signature_with_key(&received, &received.public_key).is_ok());
+        assert_eq!(decrypt_token_claims(&received, &derive_token_claims_key(&signing_key)).unwrap(), claims());
+    }
+
+    #[test]
+    fn test_wrong_key_and_tampering_are_rejected() {
+        let signing_key = SigningKey::from_bytes(&[6; 32]);
+        let claims_key: [u8; 32] = derive_token_claims_key(&signing_key);
+        let token: IronShieldToken = issue(&signing_key, &claims());
+
+        let other_key: [u8; 32] = derive_token_claims_key(&SigningKey::from_bytes(&[7; 32]));
+        assert!(matches!(decrypt_token_claims(&token, &other_key), Err(CryptoError::VerificationFailed(_))));
+
+        let section_len: usize = token.encrypted_claims.as_ref().unwrap().len();
+        for position in [1, NONCE_LEN + 1, section_len - 1] {
+            let mut tampered: IronShieldToken = token.clone();
+            tampered.encrypted_claims.as_mut().unwrap()[position] ^= 1;
+            assert!(verify_token_signature_with_key(&tampered, &tampered.public_key).is_err());
+            assert!(matches!(decrypt_token_claims(&tampered, &claims_key), Err(CryptoError::VerificationFailed(_))));
+        }
+
+        // Claims moved to a token with a different expiry do not decrypt.
+        let mut moved: IronShieldToken = token.clone();
+        moved.valid_for += 1;
+        assert!(decrypt_token_claims(&moved, &claims_key).is_err());
+
+        let mut version: IronShieldToken = token.clone();
+        version.encrypted_claims.as_mut().unwrap()[0] = 2;
+        assert!(matches!(decrypt_token_claims(&version, &claims_key), Err(CryptoError::InvalidKeyFormat(_))));
+
+        let plain = IronShieldToken::new([0xAB; 64], 1_000, [0xCD; 32], [0xEF; 64]);
+        assert!(decrypt_token_claims(&plain, &claims_key).is_err());
+    }
+}
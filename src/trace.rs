@@ -0,0 +1,77 @@
+//! # Optional `tracing` instrumentation.
+//!
+//! With the `tracing` feature, challenge signing and verification, key
+//! loading and header decoding each run in a `debug` span named after
+//! the function. Spans carry structured fields such as `website_id`
+//! and `key_fingerprint` (see `key_fingerprint`), plus an `outcome` of
+//! `ok` or `error` and, on failure, the `error` text. Without the
+//! feature `traced!` expands to the function body alone.
+
+/// Runs `$body` in a span and records its outcome.
+///
+/// * `$name`:   Span name.
+/// * `$ty`:     The `Result` type `$body` evaluates to.
+/// * `$fields`: Span fields in `tracing` syntax, each followed by a
+///              comma. Only evaluated with the `tracing` feature.
+/// * `$ok`:     Optional `|value| { field = expr, ... }` recorded on
+///              success, for fields only known from the result.
+/// * `$body`:   The function body. `?` and `return` leave the body,
+///              not the enclosing function, so the outcome is always
+///              recorded.
+macro_rules! traced {
+    ($name:literal, $ty:ty, { $($fields:tt)* }, $body:block) => {
+        $crate::trace::traced!($name, $ty, { $($fields)* }, |_value| {}, $body)
+    };
+    ($name:literal, $ty:ty, { $($fields:tt)* }, |$value:ident| { $($ok_field:ident = $ok_expr:expr),* $(,)? }, $body:block) => {{
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            $name,
+            $($fields)*
+            outcome = tracing::field::Empty,
+            error = tracing::field::Empty
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        #[allow(clippy::redundant_closure_call)]
+        let result: $ty = (|| $body)();
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok($value) => {
+                $(span.record(stringify!($ok_field), tracing::field::display($ok_expr));)*
+                span.record("outcome", "ok");
+            }
+            Err(e) => {
+                span.record("outcome", "error");
+                span.record("error", tracing::field::display(e));
+            }
+        }
+        result
+    }};
+}
+
+pub(crate) use traced;
+
+#[cfg(test)]
+mod tests {
+    fn parse_doubled(value: &str) -> Result<u32, String> {
+        traced!("parse_doubled", Result<u32, String>, {
+            value_len = value.len(),
+            doubled = tracing::field::Empty,
+        }, |doubled| { doubled = doubled }, {
+            let parsed: u32 = value.parse().map_err(|_| format!("Not a number: {}", value))?;
+            if parsed == 0 {
+                return Err("Zero".to_string());
+            }
+            Ok(parsed * 2)
+        })
+    }
+
+    #[test]
+    fn test_traced_returns_body_result() {
+        assert_eq!(parse_doubled("21"), Ok(42));
+        assert_eq!(parse_doubled("x").unwrap_err(), "Not a number: x");
+        assert_eq!(parse_doubled("0").unwrap_err(), "Zero");
+    }
+}
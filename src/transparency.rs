@@ -0,0 +1,501 @@
+//! # Transparency log of keys, policies and issuance batches.
+//!
+//! Deployments publish the events third parties care about (which keys
+//! sign challenges, which policies apply, which issuance batches were
+//! closed) to an append-only log in the style of Certificate
+//! Transparency (RFC 6962 / RFC 9162). The operator signs a
+//! `SignedTreeHead` over the log; monitors check that each new head is
+//! consistent with the last one they saw, so history cannot be
+//! rewritten without detection, and that individual entries are
+//! included.
+//!
+//! Leaves and nodes are hashed as in `MerkleBatch`.
+
+use ed25519_dalek::{
+    Signature,
+    Verifier,
+    VerifyingKey
+};
+#[cfg(feature = "sign")]
+use ed25519_dalek::{
+    Signer,
+    SigningKey
+};
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::merkle::{
+    audit_path,
+    leaf_hash,
+    node_hash,
+    root_from_audit_path,
+    split_point,
+    tree_hash
+};
+use crate::serde_utils::{
+    deserialize_32_bytes,
+    deserialize_signature,
+    serialize_32_bytes,
+    serialize_signature
+};
+use crate::{
+    CryptoError,
+    ErrorDetail,
+    InclusionProof,
+    SignedBatchRoot
+};
+
+/// Domain separation prefix of tree head signatures.
+const TREE_HEAD_CONTEXT: &[u8] = b"ironshield-tree-head-v1";
+
+/// Event recorded in the transparency log.
+///
+/// * `KeyPublished`:    A challenge signing key was put into service.
+/// * `KeyRevoked`:      A signing key was withdrawn.
+/// * `PolicyPublished`: The SHA-256 of a site's policy document.
+/// * `BatchRoot`:       A signed issuance batch root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LogEvent {
+    KeyPublished {
+        #[serde(
+            serialize_with = "serialize_32_bytes",
+            deserialize_with = "deserialize_32_bytes"
+        )]
+        public_key: [u8; 32],
+        valid_from: i64,
+    },
+    KeyRevoked {
+        #[serde(
+            serialize_with = "serialize_32_bytes",
+            deserialize_with = "deserialize_32_bytes"
+        )]
+        public_key: [u8; 32],
+        revoked_at: i64,
+    },
+    PolicyPublished {
+        website_id:    String,
+        #[serde(
+            serialize_with = "serialize_32_bytes",
+            deserialize_with = "deserialize_32_bytes"
+        )]
+        policy_digest: [u8; 32],
+    },
+    BatchRoot(SignedBatchRoot),
+}
+
+/// One entry of the transparency log.
+///
+/// * `index`:     Position in the log, starting at 0.
+/// * `timestamp`: Unix milli timestamp the entry was logged at.
+/// * `event`:     The logged event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub index:     u64,
+    pub timestamp: i64,
+    pub event:     LogEvent,
+}
+
+impl LogEntry {
+    /// Canonical binary form of the entry, the data of its Merkle leaf.
+    ///
+    /// # Returns
+    /// * `Vec<u8>`: Big-endian integers, length-prefixed strings and raw
+    ///              byte arrays, after a one-byte event tag.
+    pub fn leaf_input(&self) -> Vec<u8> {
+        let mut input: Vec<u8> = Vec::new();
+        input.extend_from_slice(&self.index.to_be_bytes());
+        input.extend_from_slice(&self.timestamp.to_be_bytes());
+        match &self.event {
+            LogEvent::KeyPublished { public_key, valid_from } => {
+                input.push(0);
+                input.extend_from_slice(public_key);
+                input.extend_from_slice(&valid_from.to_be_bytes());
+            }
+            LogEvent::KeyRevoked { public_key, revoked_at } => {
+                input.push(1);
+                input.extend_from_slice(public_key);
+                input.extend_from_slice(&revoked_at.to_be_bytes());
+            }
+            LogEvent::PolicyPublished { website_id, policy_digest } => {
+                input.push(2);
+                input.extend_from_slice(&(website_id.len() as u64).to_be_bytes());
+                input.extend_from_slice(website_id.as_bytes());
+                input.extend_from_slice(policy_digest);
+            }
+            LogEvent::BatchRoot(batch) => {
+                input.push(3);
+                input.extend_from_slice(&batch.tree_size.to_be_bytes());
+                input.extend_from_slice(&batch.root);
+                input.extend_from_slice(&batch.batch_time.to_be_bytes());
+                input.extend_from_slice(&batch.signature);
+            }
+        }
+        input
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The Merkle leaf hash of the entry.
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        leaf_hash(&self.leaf_input())
+    }
+}
+
+/// Log operator's signed commitment to the first `tree_size` entries.
+///
+/// * `tree_size`: Number of entries covered.
+/// * `timestamp`: Unix milli timestamp the head was signed at.
+/// * `root_hash`: Merkle root over those entries.
+/// * `signature`: Ed25519 signature over the fields above.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    pub tree_size: u64,
+    pub timestamp: i64,
+    #[serde(
+        serialize_with = "serialize_32_bytes",
+        deserialize_with = "deserialize_32_bytes"
+    )]
+    pub root_hash: [u8; 32],
+    #[serde(
+        serialize_with = "serialize_signature",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature: [u8; 64],
+}
+
+impl SignedTreeHead {
+    /// # Returns
+    /// * `Vec<u8>`: The bytes covered by `signature`.
+    fn signing_message(&self) -> Vec<u8> {
+        [
+            TREE_HEAD_CONTEXT,
+            &self.tree_size.to_be_bytes(),
+            &self.timestamp.to_be_bytes(),
+            &self.root_hash,
+        ].concat()
+    }
+
+    /// Verifies the operator signature.
+    ///
+    /// # Arguments
+    /// * `public_key_bytes`: The log operator's Ed25519 public key.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if valid, error if verification fails.
+    pub fn verify(&self, public_key_bytes: &[u8; 32]) -> Result<(), CryptoError> {
+        let verifying_key: VerifyingKey = VerifyingKey::from_bytes(public_key_bytes)
+            .map_err(|e| CryptoError::InvalidKeyFormat(ErrorDetail::caused_by(format!("Invalid public key: {}", e), e)))?;
+        verifying_key.verify(&self.signing_message(), &Signature::from_bytes(&self.signature))
+            .map_err(|e| CryptoError::VerificationFailed(ErrorDetail::caused_by(format!("Tree head signature verification failed: {}", e), e)))
+    }
+
+    /// Checks that `entry` is covered by this tree head. The signature
+    /// is checked separately with `verify`.
+    ///
+    /// # Arguments
+    /// * `entry`: The entry in question.
+    /// * `proof`: Its inclusion proof for this tree size.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the entry is in the log.
+    pub fn verify_inclusion(&self, entry: &LogEntry, proof: &InclusionProof) -> Result<(), CryptoError> {
+        if proof.tree_size != self.tree_size || proof.leaf_index != entry.index {
+            return Err(CryptoError::VerificationFailed("Inclusion proof does not match the tree head".into()));
+        }
+        let root: Option<[u8; 32]> = root_from_audit_path(
+            entry.leaf_hash(), proof.leaf_index, proof.tree_size, &proof.audit_path
+        );
+        if root != Some(self.root_hash) {
+            return Err(CryptoError::VerificationFailed("Inclusion proof does not match the tree root".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Proof that a log of `first_size` entries is a prefix of a log of
+/// `second_size` entries.
+///
+/// * `first_size`:  Size of the older tree.
+/// * `second_size`: Size of the newer tree.
+/// * `path`:        RFC 6962 consistency proof nodes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    pub first_size:  u64,
+    pub second_size: u64,
+    pub path:        Vec<[u8; 32]>,
+}
+
+impl ConsistencyProof {
+    /// Checks that `newer` extends `older` without rewriting it
+    /// (RFC 9162, section 2.1.4.2). Signatures are checked separately
+    /// with `SignedTreeHead::verify`.
+    ///
+    /// # Arguments
+    /// * `older`: The tree head a monitor saw before.
+    /// * `newer`: The current tree head.
+    ///
+    /// # Returns
+    /// * `Result<(), CryptoError>`: `Ok(())` if the heads are consistent.
+    pub fn verify(&self, older: &SignedTreeHead, newer: &SignedTreeHead) -> Result<(), CryptoError> {
+        let inconsistent = |reason: &str| CryptoError::VerificationFailed(format!("Inconsistent tree heads: {}", reason).into());
+
+        if self.first_size != older.tree_size || self.second_size != newer.tree_size {
+            return Err(inconsistent("proof is for different tree sizes"));
+        }
+        if older.tree_size > newer.tree_size {
+            return Err(inconsistent("older tree is larger than newer tree"));
+        }
+        if older.tree_size == newer.tree_size || older.tree_size == 0 {
+            let trivially_consistent: bool = self.path.is_empty()
+                && (older.tree_size == 0 || older.root_hash == newer.root_hash);
+            return if trivially_consistent { Ok(()) } else { Err(inconsistent("root hashes differ")) };
+        }
+
+        let mut path: Vec<[u8; 32]> = Vec::with_capacity(self.path.len() + 1);
+        if older.tree_size.is_power_of_two() {
+            path.push(older.root_hash);
+        }
+        path.extend_from_slice(&self.path);
+
+        let mut fn_: u64 = older.tree_size - 1;
+        let mut sn: u64 = newer.tree_size - 1;
+        while fn_ & 1 == 1 {
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        let (first, rest) = path.split_first().ok_or_else(|| inconsistent("empty proof"))?;
+        let mut first_root: [u8; 32] = *first;
+        let mut second_root: [u8; 32] = *first;
+        for node in rest {
+            if sn == 0 {
+                return Err(inconsistent("proof is too long"));
+            }
+            if fn_ & 1 == 1 || fn_ == sn {
+                first_root = node_hash(node, &first_root);
+                second_root = node_hash(node, &second_root);
+                while fn_ & 1 == 0 && fn_ != 0 {
+                    fn_ >>= 1;
+                    sn >>= 1;
+                }
+            } else {
+                second_root = node_hash(&second_root, node);
+            }
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        if sn != 0 || first_root != older.root_hash || second_root != newer.root_hash {
+            return Err(inconsistent("proof does not match the root hashes"));
+        }
+        Ok(())
+    }
+}
+
+/// RFC 6962 `SUBPROOF` over already hashed leaves.
+fn consistency_subproof(first_size: usize, leaves: &[[u8; 32]], complete: bool) -> Vec<[u8; 32]> {
+    if first_size == leaves.len() {
+        return if complete { Vec::new() } else { vec![tree_hash(leaves)] };
+    }
+    let (left, right) = leaves.split_at(split_point(leaves.len()));
+    if first_size <= left.len() {
+        let mut proof: Vec<[u8; 32]> = consistency_subproof(first_size, left, complete);
+        proof.push(tree_hash(right));
+        proof
+    } else {
+        let mut proof: Vec<[u8; 32]> = consistency_subproof(first_size - left.len(), right, false);
+        proof.push(tree_hash(left));
+        proof
+    }
+}
+
+/// In-memory transparency log, as kept by a log operator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransparencyLog {
+    entries: Vec<LogEntry>,
+    leaves:  Vec<[u8; 32]>,
+}
+
+impl TransparencyLog {
+    /// Constructor for creating an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Returns
+    /// * `&[LogEntry]`: The entries, oldest first.
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// # Returns
+    /// * `u64`: The number of entries.
+    pub fn tree_size(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Appends an event.
+    ///
+    /// # Arguments
+    /// * `timestamp`: Unix milli timestamp of the entry.
+    /// * `event`:     The event to log.
+    ///
+    /// # Returns
+    /// * `&LogEntry`: The appended entry.
+    pub fn append(&mut self, timestamp: i64, event: LogEvent) -> &LogEntry {
+        let entry = LogEntry {
+            index: self.entries.len() as u64,
+            timestamp,
+            event,
+        };
+        self.leaves.push(entry.leaf_hash());
+        self.entries.push(entry);
+        &self.entries[self.entries.len() - 1]
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The Merkle root over all entries.
+    pub fn root_hash(&self) -> [u8; 32] {
+        tree_hash(&self.leaves)
+    }
+
+    /// Signs a tree head over all current entries.
+    ///
+    /// # Arguments
+    /// * `timestamp`:   Unix milli timestamp of the head.
+    /// * `signing_key`: The log operator's key.
+    ///
+    /// # Returns
+    /// * `SignedTreeHead`: The head to publish.
+    #[cfg(feature = "sign")]
+    pub fn sign_tree_head(&self, timestamp: i64, signing_key: &SigningKey) -> SignedTreeHead {
+        let mut head = SignedTreeHead {
+            tree_size: self.tree_size(),
+            timestamp,
+            root_hash: self.root_hash(),
+            signature: [0u8; 64],
+        };
+        head.signature = signing_key.sign(&head.signing_message()).to_bytes();
+        head
+    }
+
+    /// # Arguments
+    /// * `index`: Index of the entry to prove.
+    ///
+    /// # Returns
+    /// * `Option<InclusionProof>`: The proof against the current tree
+    ///                             size, or `None` for an unknown index.
+    pub fn inclusion_proof(&self, index: u64) -> Option<InclusionProof> {
+        let position: usize = usize::try_from(index).ok().filter(|i| *i < self.leaves.len())?;
+        Some(InclusionProof {
+            leaf_index: index,
+            tree_size:  self.tree_size(),
+            audit_path: audit_path(position, &self.leaves),
+        })
+    }
+
+    /// # Arguments
+    /// * `first_size`: Size of an earlier tree head.
+    ///
+    /// # Returns
+    /// * `Option<ConsistencyProof>`: The proof from `first_size` to the
+    ///                               current size, or `None` if
+    ///                               `first_size` is larger.
+    pub fn consistency_proof(&self, first_size: u64) -> Option<ConsistencyProof> {
+        let first: usize = usize::try_from(first_size).ok().filter(|size| *size <= self.leaves.len())?;
+        let path: Vec<[u8; 32]> = if first == 0 {
+            Vec::new()
+        } else {
+            consistency_subproof(first, &self.leaves, true)
+        };
+        Some(ConsistencyProof {
+            first_size,
+            second_size: self.tree_size(),
+            path,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    fn event(i: u8) -> LogEvent {
+        match i % 3 {
+            0 => LogEvent::KeyPublished { public_key: [i; 32], valid_from: i as i64 },
+            1 => LogEvent::KeyRevoked { public_key: [i; 32], revoked_at: i as i64 },
+            _ => LogEvent::PolicyPublished { website_id: format!("site-{}.example", i), policy_digest: [i; 32] },
+        }
+    }
+
+    #[test]
+    fn test_consistency_between_all_sizes() {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let mut log = TransparencyLog::new();
+        let mut heads: Vec<SignedTreeHead> = vec![log.sign_tree_head(0, &key)];
+        for i in 0..12u8 {
+            log.append(i as i64, event(i));
+            heads.push(log.sign_tree_head(i as i64, &key));
+        }
+
+        let mut replay = TransparencyLog::new();
+        for (second, newer) in heads.iter().enumerate() {
+            newer.verify(&key.verifying_key().to_bytes()).unwrap();
+            for older in &heads[..=second] {
+                let proof: ConsistencyProof = replay.consistency_proof(older.tree_size).unwrap();
+                proof.verify(older, newer).unwrap();
+            }
+            if let Some(entry) = log.entries().get(second) {
+                replay.append(entry.timestamp, entry.event.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rewritten_history_is_inconsistent() {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let mut log = TransparencyLog::new();
+        let mut forked = TransparencyLog::new();
+        for i in 0..5u8 {
+            log.append(i as i64, event(i));
+            forked.append(i as i64, if i == 2 { event(7) } else { event(i) });
+        }
+        let older: SignedTreeHead = log.sign_tree_head(5, &key);
+
+        for i in 5..9u8 {
+            forked.append(i as i64, event(i));
+        }
+        let newer: SignedTreeHead = forked.sign_tree_head(9, &key);
+        let proof: ConsistencyProof = forked.consistency_proof(5).unwrap();
+        assert!(proof.verify(&older, &newer).is_err());
+        assert!(proof.verify(&newer, &older).is_err());
+        assert!(forked.consistency_proof(10).is_none());
+    }
+
+    #[test]
+    fn test_entry_inclusion() {
+        let key = SigningKey::from_bytes(&[4; 32]);
+        let batch_root: SignedBatchRoot = crate::MerkleBatch::new(vec![[1; 32], [2; 32]]).sign(50, &key);
+        let mut log = TransparencyLog::new();
+        for i in 0..6u8 {
+            log.append(i as i64, event(i));
+        }
+        log.append(6, LogEvent::BatchRoot(batch_root));
+        let head: SignedTreeHead = log.sign_tree_head(7, &key);
+
+        for entry in log.entries() {
+            head.verify_inclusion(entry, &log.inclusion_proof(entry.index).unwrap()).unwrap();
+        }
+
+        let mut altered: LogEntry = log.entries()[3].clone();
+        altered.timestamp += 1;
+        assert!(head.verify_inclusion(&altered, &log.inclusion_proof(3).unwrap()).is_err());
+        assert!(log.inclusion_proof(7).is_none());
+
+        let json: String = serde_json::to_string(&log.entries()[6]).unwrap();
+        assert!(json.contains("\"type\":\"batch_root\""));
+        assert_eq!(serde_json::from_str::<LogEntry>(&json).unwrap(), log.entries()[6]);
+    }
+}
@@ -0,0 +1,364 @@
+//! # Expiring cache for issued challenges.
+//!
+//! The Worker's Durable Object and self-hosted servers both keep the
+//! challenges they issued until the challenge can no longer be redeemed.
+//! `TtlCache` is the shared structure for that: every entry carries its
+//! own expiry, expired entries are dropped lazily on lookup and in bulk by
+//! `sweep()`, and the cache never grows beyond `max_entries`.
+//!
+//! The cache serializes to a flat list of entries, so a Durable Object can
+//! persist a snapshot and restore it after eviction.
+
+use std::collections::{
+    BTreeSet,
+    HashMap
+};
+use std::hash::Hash;
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::epoch::now_millis;
+use crate::{
+    IronShieldChallenge,
+    RandomNonce
+};
+
+/// A cached value and its expiry.
+///
+/// * `key`:        The cache key.
+/// * `value`:      The cached value.
+/// * `expires_at`: Unix milli timestamp after which the entry is dropped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TtlEntry<K, V> {
+    pub key:        K,
+    pub value:      V,
+    pub expires_at: i64,
+}
+
+/// Serialized form of a `TtlCache`.
+#[derive(Serialize, Deserialize)]
+struct TtlCacheSnapshot<K, V> {
+    max_entries: usize,
+    entries:     Vec<TtlEntry<K, V>>,
+}
+
+/// Bounded cache whose entries expire at individual timestamps.
+///
+/// When an insert would exceed `max_entries`, expired entries are swept
+/// first and then the entries closest to expiry are evicted.
+///
+/// The methods without an `_at` suffix use the current time; the `_at`
+/// variants take an explicit Unix milli timestamp.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    into = "TtlCacheSnapshot<K, V>",
+    from = "TtlCacheSnapshot<K, V>",
+    bound(
+        serialize = "K: Serialize + Eq + Hash + Clone, V: Serialize + Clone",
+        deserialize = "K: Deserialize<'de> + Eq + Hash + Ord + Clone, V: Deserialize<'de>"
+    )
+)]
+pub struct TtlCache<K, V> {
+    max_entries: usize,
+    entries:     HashMap<K, (V, i64)>,
+    /// Expiry index ordered by `(expires_at, key)`.
+    expiry:      BTreeSet<(i64, K)>,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash + Ord + Clone,
+{
+    /// Constructor for creating an empty `TtlCache`.
+    ///
+    /// # Arguments
+    /// * `max_entries`: Maximum number of entries held at once
+    ///                  (at least 1).
+    ///
+    /// # Returns
+    /// * `Self`: An empty cache.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            entries:     HashMap::new(),
+            expiry:      BTreeSet::new(),
+        }
+    }
+
+    /// # Returns
+    /// * `usize`: The maximum number of entries.
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// # Returns
+    /// * `usize`: The number of stored entries, including expired
+    ///            entries that have not been swept yet.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts or replaces an entry, evicting if the cache is full.
+    ///
+    /// # Arguments
+    /// * `key`:        The cache key.
+    /// * `value`:      The value to store.
+    /// * `expires_at`: Unix milli timestamp after which the entry expires.
+    ///
+    /// # Returns
+    /// * `Option<V>`: The value previously stored under `key`, if any.
+    pub fn insert(&mut self, key: K, value: V, expires_at: i64) -> Option<V> {
+        self.insert_at(key, value, expires_at, now_millis())
+    }
+
+    /// Same as `insert`, evaluated at `now`.
+    pub fn insert_at(&mut self, key: K, value: V, expires_at: i64, now: i64) -> Option<V> {
+        let previous: Option<V> = self.remove(&key);
+
+        if self.entries.len() >= self.max_entries {
+            self.sweep_at(now);
+        }
+        while self.entries.len() >= self.max_entries {
+            match self.expiry.pop_first() {
+                Some((_, oldest)) => { self.entries.remove(&oldest); }
+                None => break,
+            }
+        }
+
+        self.expiry.insert((expires_at, key.clone()));
+        self.entries.insert(key, (value, expires_at));
+        previous
+    }
+
+    /// # Arguments
+    /// * `key`: The cache key.
+    ///
+    /// # Returns
+    /// * `Option<&V>`: The value, if present and not expired.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_at(key, now_millis())
+    }
+
+    /// Same as `get`, evaluated at `now`.
+    pub fn get_at(&self, key: &K, now: i64) -> Option<&V> {
+        self.entries
+            .get(key)
+            .filter(|(_, expires_at)| now <= *expires_at)
+            .map(|(value, _)| value)
+    }
+
+    /// Removes an entry regardless of its expiry.
+    ///
+    /// # Arguments
+    /// * `key`: The cache key.
+    ///
+    /// # Returns
+    /// * `Option<V>`: The removed value, if any.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (value, expires_at) = self.entries.remove(key)?;
+        self.expiry.remove(&(expires_at, key.clone()));
+        Some(value)
+    }
+
+    /// Removes an entry and returns it only if it has not expired,
+    /// e.g. to redeem a challenge exactly once.
+    ///
+    /// # Arguments
+    /// * `key`: The cache key.
+    ///
+    /// # Returns
+    /// * `Option<V>`: The value, if present and not expired.
+    pub fn take(&mut self, key: &K) -> Option<V> {
+        self.take_at(key, now_millis())
+    }
+
+    /// Same as `take`, evaluated at `now`.
+    pub fn take_at(&mut self, key: &K, now: i64) -> Option<V> {
+        let expires_at: i64 = self.entries.get(key)?.1;
+        let value: Option<V> = self.remove(key);
+        value.filter(|_| now <= expires_at)
+    }
+
+    /// Drops every expired entry.
+    ///
+    /// # Returns
+    /// * `usize`: The number of entries removed.
+    pub fn sweep(&mut self) -> usize {
+        self.sweep_at(now_millis())
+    }
+
+    /// Same as `sweep`, evaluated at `now`.
+    pub fn sweep_at(&mut self, now: i64) -> usize {
+        let mut removed: usize = 0;
+        while let Some((expires_at, _)) = self.expiry.first() {
+            if *expires_at >= now {
+                break;
+            }
+            if let Some((_, key)) = self.expiry.pop_first() {
+                self.entries.remove(&key);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Removes every entry.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.expiry.clear();
+    }
+}
+
+impl TtlCache<RandomNonce, IronShieldChallenge> {
+    /// Stores an issued challenge under its `random_nonce` until its
+    /// submission deadline.
+    ///
+    /// # Arguments
+    /// * `challenge`: The issued challenge.
+    ///
+    /// # Returns
+    /// * `Option<IronShieldChallenge>`: A previous challenge with the
+    ///                                  same nonce, if any.
+    pub fn insert_challenge(&mut self, challenge: IronShieldChallenge) -> Option<IronShieldChallenge> {
+        let expires_at: i64 = challenge.effective_submission_deadline();
+        self.insert(challenge.random_nonce.clone(), challenge, expires_at)
+    }
+}
+
+impl<K, V> From<TtlCache<K, V>> for TtlCacheSnapshot<K, V>
+where
+    K: Eq + Hash,
+{
+    fn from(cache: TtlCache<K, V>) -> Self {
+        let mut entries: HashMap<K, (V, i64)> = cache.entries;
+        let entries: Vec<TtlEntry<K, V>> = cache.expiry
+            .into_iter()
+            .filter_map(|(_, key)| {
+                let (value, expires_at) = entries.remove(&key)?;
+                Some(TtlEntry { key, value, expires_at })
+            })
+            .collect();
+
+        Self {
+            max_entries: cache.max_entries,
+            entries,
+        }
+    }
+}
+
+impl<K, V> From<TtlCacheSnapshot<K, V>> for TtlCache<K, V>
+where
+    K: Eq + Hash + Ord + Clone,
+{
+    fn from(snapshot: TtlCacheSnapshot<K, V>) -> Self {
+        let mut cache: TtlCache<K, V> = TtlCache::new(snapshot.max_entries);
+        for entry in snapshot.entries {
+            // Use the oldest possible "now" so restoring never sweeps;
+            // expired entries are dropped on the next lookup or sweep.
+            cache.insert_at(entry.key, entry.value, entry.expires_at, i64::MIN);
+        }
+        cache
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_get_respects_expiry() {
+        let mut cache: TtlCache<&str, u32> = TtlCache::new(10);
+        cache.insert_at("a", 1, 1_000, 0);
+
+        assert_eq!(cache.get_at(&"a", 1_000), Some(&1));
+        assert_eq!(cache.get_at(&"a", 1_001), None);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_take_redeems_once() {
+        let mut cache: TtlCache<&str, u32> = TtlCache::new(10);
+        cache.insert_at("a", 1, 1_000, 0);
+        cache.insert_at("b", 2, 1_000, 0);
+
+        assert_eq!(cache.take_at(&"a", 500), Some(1));
+        assert_eq!(cache.take_at(&"a", 500), None);
+        assert_eq!(cache.take_at(&"b", 2_000), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_sweep_removes_only_expired() {
+        let mut cache: TtlCache<u32, ()> = TtlCache::new(10);
+        for i in 0..5 {
+            cache.insert_at(i, (), i as i64 * 100, 0);
+        }
+
+        assert_eq!(cache.sweep_at(250), 3);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_at(&3, 250).is_some());
+    }
+
+    #[test]
+    fn test_eviction_prefers_expired_then_soonest() {
+        let mut cache: TtlCache<&str, u32> = TtlCache::new(2);
+        cache.insert_at("expired", 1, 100, 0);
+        cache.insert_at("late", 2, 10_000, 0);
+        cache.insert_at("new", 3, 5_000, 200);
+        assert!(cache.get_at(&"late", 200).is_some());
+        assert!(cache.get_at(&"new", 200).is_some());
+
+        cache.insert_at("newest", 4, 20_000, 200);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_at(&"new", 200).is_none());
+        assert!(cache.get_at(&"late", 200).is_some());
+    }
+
+    #[test]
+    fn test_replacing_key_updates_expiry() {
+        let mut cache: TtlCache<&str, u32> = TtlCache::new(10);
+        cache.insert_at("a", 1, 100, 0);
+        assert_eq!(cache.insert_at("a", 2, 1_000, 0), Some(1));
+
+        assert_eq!(cache.sweep_at(500), 0);
+        assert_eq!(cache.get_at(&"a", 500), Some(&2));
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut cache: TtlCache<String, u32> = TtlCache::new(3);
+        cache.insert_at("a".to_string(), 1, 100, 0);
+        cache.insert_at("b".to_string(), 2, 200, 0);
+
+        let json: String = serde_json::to_string(&cache).unwrap();
+        let restored: TtlCache<String, u32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.max_entries(), 3);
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.get_at(&"b".to_string(), 150), Some(&2));
+    }
+
+    #[test]
+    fn test_insert_challenge_uses_submission_deadline() {
+        let signing_key = SigningKey::from_bytes(&[1; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 100, signing_key.clone(), signing_key.verifying_key().to_bytes());
+        let nonce: RandomNonce = challenge.random_nonce.clone();
+        let deadline: i64 = challenge.effective_submission_deadline();
+
+        let mut cache: TtlCache<RandomNonce, IronShieldChallenge> = TtlCache::new(100);
+        cache.insert_challenge(challenge);
+
+        assert!(cache.get_at(&nonce, deadline).is_some());
+        assert!(cache.get_at(&nonce, deadline + 1).is_none());
+    }
+}
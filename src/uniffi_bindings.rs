@@ -0,0 +1,250 @@
+//! # UniFFI bindings for the iOS and Android SDKs.
+//!
+//! Exposes the challenge, response and token types as UniFFI objects
+//! together with their verification helpers, so Swift and Kotlin work
+//! with the exact same types as the servers. Byte arrays cross the
+//! boundary as `Vec<u8>` and are length-checked on the way in.
+
+use std::sync::Arc;
+
+use crate::{
+    verify_challenge_signature_with_key,
+    verify_token_signature_with_key,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    IronShieldToken
+};
+
+/// Error returned across the UniFFI boundary.
+///
+/// * `InvalidInput`:       A value could not be parsed or has the
+///                         wrong length.
+/// * `VerificationFailed`: A signature or validity check failed.
+#[derive(Debug, uniffi::Error)]
+pub enum UniffiIronShieldError {
+    InvalidInput { message: String },
+    VerificationFailed { message: String },
+}
+
+impl std::fmt::Display for UniffiIronShieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniffiIronShieldError::InvalidInput { message }       => write!(f, "Invalid input: {}", message),
+            UniffiIronShieldError::VerificationFailed { message } => write!(f, "Verification failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for UniffiIronShieldError {}
+
+impl From<crate::CryptoError> for UniffiIronShieldError {
+    fn from(error: crate::CryptoError) -> Self {
+        UniffiIronShieldError::VerificationFailed { message: error.to_string() }
+    }
+}
+
+fn invalid_input(message: impl std::fmt::Display) -> UniffiIronShieldError {
+    UniffiIronShieldError::InvalidInput { message: message.to_string() }
+}
+
+fn public_key_from_vec(public_key: Vec<u8>) -> Result<[u8; 32], UniffiIronShieldError> {
+    public_key.try_into().map_err(|_| invalid_input("Public key must be exactly 32 bytes"))
+}
+
+/// UniFFI wrapper for `IronShieldChallenge`.
+#[derive(uniffi::Object)]
+pub struct UniffiIronShieldChallenge {
+    inner: IronShieldChallenge,
+}
+
+#[uniffi::export]
+impl UniffiIronShieldChallenge {
+    /// Parses a challenge from its JSON representation.
+    #[uniffi::constructor]
+    pub fn from_json(json: String) -> Result<Arc<Self>, UniffiIronShieldError> {
+        let inner: IronShieldChallenge = serde_json::from_str(&json).map_err(invalid_input)?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Decodes a challenge from a Base64 URL-safe header value.
+    #[uniffi::constructor]
+    pub fn from_base64url_header(header: String) -> Result<Arc<Self>, UniffiIronShieldError> {
+        let inner: IronShieldChallenge = IronShieldChallenge::from_base64url_header(&header).map_err(invalid_input)?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Serializes the challenge to JSON.
+    pub fn to_json(&self) -> Result<String, UniffiIronShieldError> {
+        serde_json::to_string(&self.inner).map_err(invalid_input)
+    }
+
+    /// Encodes the challenge as a Base64 URL-safe header value.
+    pub fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    /// The random nonce of the challenge.
+    pub fn random_nonce(&self) -> String {
+        self.inner.random_nonce.to_string()
+    }
+
+    /// Unix milli timestamp of challenge creation.
+    pub fn created_time(&self) -> i64 {
+        self.inner.created_time
+    }
+
+    /// Unix milli timestamp of challenge expiration.
+    pub fn expiration_time(&self) -> i64 {
+        self.inner.expiration_time
+    }
+
+    /// The identifier of the website.
+    pub fn website_id(&self) -> String {
+        self.inner.website_id.to_string()
+    }
+
+    /// The 32-byte proof-of-work target.
+    pub fn challenge_param(&self) -> Vec<u8> {
+        self.inner.challenge_param.to_vec()
+    }
+
+    /// Expected number of attempts for user guidance.
+    pub fn recommended_attempts(&self) -> u64 {
+        self.inner.recommended_attempts
+    }
+
+    /// The 32-byte Ed25519 public key of the issuer.
+    pub fn public_key(&self) -> Vec<u8> {
+        self.inner.public_key.to_vec()
+    }
+
+    /// The algorithm identifier, `"sha256"` or `"wait"`.
+    pub fn algorithm(&self) -> String {
+        self.inner.algorithm().to_string()
+    }
+
+    /// `true` if the challenge has expired.
+    pub fn is_expired(&self) -> bool {
+        self.inner.is_expired()
+    }
+
+    /// Verifies the challenge signature against a 32-byte Ed25519 key.
+    pub fn verify_signature(&self, public_key: Vec<u8>) -> Result<(), UniffiIronShieldError> {
+        let public_key: [u8; 32] = public_key_from_vec(public_key)?;
+        Ok(verify_challenge_signature_with_key(&self.inner, &public_key)?)
+    }
+}
+
+/// UniFFI wrapper for `IronShieldChallengeResponse`.
+#[derive(uniffi::Object)]
+pub struct UniffiIronShieldChallengeResponse {
+    inner: IronShieldChallengeResponse,
+}
+
+#[uniffi::export]
+impl UniffiIronShieldChallengeResponse {
+    /// Creates a response for a solved challenge.
+    #[uniffi::constructor]
+    pub fn new(challenge: Arc<UniffiIronShieldChallenge>, solution: i64) -> Arc<Self> {
+        Arc::new(Self { inner: IronShieldChallengeResponse::new(challenge.inner.clone(), solution) })
+    }
+
+    /// Decodes a response from a Base64 URL-safe header value.
+    #[uniffi::constructor]
+    pub fn from_base64url_header(header: String) -> Result<Arc<Self>, UniffiIronShieldError> {
+        let inner: IronShieldChallengeResponse = IronShieldChallengeResponse::from_base64url_header(&header).map_err(invalid_input)?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Serializes the response to JSON.
+    pub fn to_json(&self) -> Result<String, UniffiIronShieldError> {
+        serde_json::to_string(&self.inner).map_err(invalid_input)
+    }
+
+    /// Encodes the response as a Base64 URL-safe header value.
+    pub fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    /// The challenge that was solved.
+    pub fn solved_challenge(&self) -> Arc<UniffiIronShieldChallenge> {
+        Arc::new(UniffiIronShieldChallenge { inner: self.inner.solved_challenge.clone() })
+    }
+
+    /// The proof-of-work solution.
+    pub fn solution(&self) -> i64 {
+        self.inner.solution
+    }
+}
+
+/// UniFFI wrapper for `IronShieldToken`.
+#[derive(uniffi::Object)]
+pub struct UniffiIronShieldToken {
+    inner: IronShieldToken,
+}
+
+#[uniffi::export]
+impl UniffiIronShieldToken {
+    /// Parses a token from its JSON representation.
+    #[uniffi::constructor]
+    pub fn from_json(json: String) -> Result<Arc<Self>, UniffiIronShieldError> {
+        let inner: IronShieldToken = serde_json::from_str(&json).map_err(invalid_input)?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Decodes a token from a Base64 URL-safe header value.
+    #[uniffi::constructor]
+    pub fn from_base64url_header(header: String) -> Result<Arc<Self>, UniffiIronShieldError> {
+        let inner: IronShieldToken = IronShieldToken::from_base64url_header(&header).map_err(invalid_input)?;
+        Ok(Arc::new(Self { inner }))
+    }
+
+    /// Serializes the token to JSON.
+    pub fn to_json(&self) -> Result<String, UniffiIronShieldError> {
+        serde_json::to_string(&self.inner).map_err(invalid_input)
+    }
+
+    /// Encodes the token as a Base64 URL-safe header value.
+    pub fn to_base64url_header(&self) -> String {
+        self.inner.to_base64url_header()
+    }
+
+    /// Unix milli timestamp until which the token is valid.
+    pub fn valid_for(&self) -> i64 {
+        self.inner.valid_for
+    }
+
+    /// `true` if the token has expired.
+    pub fn is_expired(&self) -> bool {
+        self.inner.is_expired()
+    }
+
+    /// Verifies the token signature against a 32-byte Ed25519 key.
+    pub fn verify_signature(&self, public_key: Vec<u8>) -> Result<(), UniffiIronShieldError> {
+        let public_key: [u8; 32] = public_key_from_vec(public_key)?;
+        Ok(verify_token_signature_with_key(&self.inner, &public_key)?)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_challenge_roundtrip_and_verify() {
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let public_key: Vec<u8> = key.verifying_key().to_bytes().to_vec();
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+
+        let wrapped = UniffiIronShieldChallenge::from_base64url_header(challenge.to_base64url_header()).unwrap();
+        wrapped.verify_signature(public_key.clone()).unwrap();
+        assert!(matches!(wrapped.verify_signature(vec![0; 31]), Err(UniffiIronShieldError::InvalidInput { .. })));
+        assert!(matches!(wrapped.verify_signature(vec![0; 32]), Err(UniffiIronShieldError::VerificationFailed { .. })));
+
+        let response = UniffiIronShieldChallengeResponse::new(wrapped.clone(), 42);
+        let decoded = UniffiIronShieldChallengeResponse::from_base64url_header(response.to_base64url_header()).unwrap();
+        assert_eq!(decoded.solution(), 42);
+        assert_eq!(decoded.solved_challenge().random_nonce(), challenge.random_nonce);
+    }
+}
@@ -0,0 +1,154 @@
+//! # Validation on deserialization.
+//!
+//! Deserializing only checks that fields have the right types; a
+//! challenge with an empty `website_id` or a token with `valid_for` of
+//! zero still parses. Wrapping the target in `Validated<T>` runs the
+//! type's field validation as part of deserialization, so structurally
+//! invalid values are rejected with a serde error before they reach
+//! business logic. Signatures are not checked.
+//!
+//! ```
+//! use ironshield_types::{SolutionMetadata, Validated};
+//!
+//! let json = r#"{"solver_version":"wasm-1.4.0","worker_count":0,"attempts":1,"wall_time_ms":1}"#;
+//! assert!(serde_json::from_str::<SolutionMetadata>(json).is_ok());
+//! assert!(serde_json::from_str::<Validated<SolutionMetadata>>(json).is_err());
+//! ```
+
+use std::ops::Deref;
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize
+};
+
+use crate::{
+    ConfigRequest,
+    IronShieldChallenge,
+    IronShieldToken,
+    SolutionMetadata
+};
+
+/// Types with field validation beyond what deserialization checks.
+pub trait Validate {
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if valid, or a description
+    ///                         of the first invalid field.
+    fn validate(&self) -> Result<(), String>;
+}
+
+impl Validate for IronShieldChallenge {
+    fn validate(&self) -> Result<(), String> {
+        IronShieldChallenge::validate(self)
+    }
+}
+
+impl Validate for IronShieldToken {
+    fn validate(&self) -> Result<(), String> {
+        IronShieldToken::validate(self)
+    }
+}
+
+impl Validate for SolutionMetadata {
+    fn validate(&self) -> Result<(), String> {
+        SolutionMetadata::validate(self)
+    }
+}
+
+impl Validate for ConfigRequest {
+    fn validate(&self) -> Result<(), String> {
+        ConfigRequest::validate(self)
+    }
+}
+
+/// A value that passed `Validate::validate`. Serializes as the inner
+/// value; deserializing fails if validation fails.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Validated<T>(T);
+
+impl<T: Validate> Validated<T> {
+    /// # Arguments
+    /// * `value`: The value to validate.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The wrapped value, or the validation
+    ///                           error.
+    pub fn new(value: T) -> Result<Self, String> {
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+impl<T> Validated<T> {
+    /// # Returns
+    /// * `T`: The validated value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for Validated<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Validated<T>
+where
+    T: Deserialize<'de> + Validate,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(T::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    #[test]
+    fn test_deserialize_rejects_invalid_challenge() {
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key.clone(), key.verifying_key().to_bytes());
+        let json: String = serde_json::to_string(&challenge).unwrap();
+        let validated: Validated<IronShieldChallenge> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&validated).unwrap(), json);
+        assert_eq!(validated.website_id, "example.com");
+
+        for (field, value) in [
+            ("created_time", serde_json::json!(i64::MAX)),
+            ("min_age_ms",   serde_json::json!(0)),
+        ] {
+            let mut tampered: serde_json::Value = serde_json::from_str(&json).unwrap();
+            tampered[field] = value;
+            let err = serde_json::from_value::<Validated<IronShieldChallenge>>(tampered.clone()).unwrap_err();
+            assert!(err.to_string().contains(field), "{}: {}", field, err);
+            assert!(serde_json::from_value::<IronShieldChallenge>(tampered).is_ok());
+        }
+
+        // `RandomNonce` and `WebsiteId` check themselves, with or
+        // without `Validated`.
+        for (field, value) in [
+            ("random_nonce", serde_json::json!("not hex")),
+            ("website_id",   serde_json::json!("a|b")),
+        ] {
+            let mut tampered: serde_json::Value = serde_json::from_str(&json).unwrap();
+            tampered[field] = value;
+            assert!(serde_json::from_value::<IronShieldChallenge>(tampered).unwrap_err().to_string().contains(field));
+        }
+    }
+}
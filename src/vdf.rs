@@ -0,0 +1,441 @@
+//! # Wesolowski verifiable delay function challenges.
+//!
+//! Like a time-lock puzzle, a VDF challenge asks for
+//! `y = x^(2^t) mod N`, computable only by `t` sequential squarings. The
+//! solver also returns a Wesolowski proof `π = x^floor(2^t / l)` for a
+//! prime `l` derived from `x` and `y`, and anyone can check
+//! `π^l * x^(2^t mod l) = y` with two short exponentiations. Unlike a
+//! time-lock puzzle, the issuer does no per-challenge work and holds no
+//! secret: the challenge only names the modulus and `t`.
+//!
+//! The modulus is an RSA modulus whose factorization the solver must
+//! not know, e.g. from `TimeLockKey::generate` with the factors
+//! discarded. An issuer that kept them could skip the squarings, but
+//! only for challenges it verifies itself.
+//!
+//! `VdfParams` travel in the challenge as the `vd` signed extension and
+//! are always available; proofs, proving and verification need the
+//! `vdf` feature.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+#[cfg(feature = "vdf")]
+use num_bigint::BigUint;
+#[cfg(feature = "vdf")]
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::timelock::validate_modulus_hex;
+#[cfg(feature = "vdf")]
+use crate::timelock::{
+    derive_base,
+    is_probable_prime,
+    to_padded_bytes
+};
+#[cfg(feature = "vdf")]
+use crate::IronShieldChallenge;
+
+/// Domain separation prefix of the input derivation.
+#[cfg(feature = "vdf")]
+const INPUT_CONTEXT:  &[u8] = b"ironshield-vdf-input-v1";
+/// Domain separation prefix of the Fiat-Shamir prime derivation.
+#[cfg(feature = "vdf")]
+const CHALLENGE_CONTEXT: &[u8] = b"ironshield-vdf-prime-v1";
+
+/// Delay parameters of a VDF challenge.
+///
+/// * `modulus`:    The RSA modulus as lowercase big-endian hex, without
+///                 leading zero bytes.
+/// * `iterations`: Number of sequential squarings `t`.
+///
+/// Serialized as `<hex modulus>,<iterations>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VdfParams {
+    pub modulus:    String,
+    pub iterations: u64,
+}
+
+/// Borrowed view of `VdfParams`, as held by `ChallengeRef`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VdfParamsRef<'a> {
+    pub modulus:    &'a str,
+    pub iterations: u64,
+}
+
+impl<'a> VdfParamsRef<'a> {
+    /// # Arguments
+    /// * `wire`: Parameters as `<hex modulus>,<iterations>`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parameters borrowing the modulus
+    ///                           from `wire`, or an error describing the
+    ///                           malformed field.
+    pub fn from_wire_string(wire: &'a str) -> Result<Self, String> {
+        let (modulus, iterations) = wire.split_once(',')
+            .ok_or("Expected 2 VDF parameter parts")?;
+        let params = Self {
+            modulus,
+            iterations: iterations.parse::<u64>().map_err(|_| "Failed to parse iterations as u64")?,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the modulus is canonical
+    ///                         lowercase hex within the size limits and
+    ///                         at least one squaring is required.
+    pub fn validate(&self) -> Result<(), String> {
+        validate_modulus_hex(self.modulus)?;
+        if self.iterations == 0 {
+            return Err("VDF needs at least one iteration".to_string());
+        }
+        Ok(())
+    }
+
+    /// # Returns
+    /// * `usize`: Length in bytes of the modulus, the output and the
+    ///            proof.
+    pub fn modulus_len(&self) -> usize {
+        self.modulus.len() / 2
+    }
+
+    /// # Returns
+    /// * `VdfParams`: An owned copy of the parameters.
+    pub fn to_owned_params(&self) -> VdfParams {
+        VdfParams {
+            modulus:    self.modulus.to_string(),
+            iterations: self.iterations,
+        }
+    }
+
+    /// # Returns
+    /// * `String`: The parameters as `<hex modulus>,<iterations>`.
+    pub fn to_wire_string(&self) -> String {
+        format!("{},{}", self.modulus, self.iterations)
+    }
+
+    /// # Returns
+    /// * `BigUint`: The modulus.
+    #[cfg(feature = "vdf")]
+    fn modulus_value(&self) -> BigUint {
+        BigUint::parse_bytes(self.modulus.as_bytes(), 16).expect("validated modulus is hex")
+    }
+
+    /// Derives the Fiat-Shamir prime `l` from the input and output:
+    /// the first probable prime among 256-bit SHA-256 candidates with
+    /// the top and bottom bits set.
+    #[cfg(feature = "vdf")]
+    fn challenge_prime(&self, input: &BigUint, output: &BigUint) -> BigUint {
+        let input: Vec<u8> = to_padded_bytes(input, self.modulus_len());
+        let output: Vec<u8> = to_padded_bytes(output, self.modulus_len());
+        (0u32..).find_map(|counter| {
+            let mut candidate: [u8; 32] = Sha256::new()
+                .chain_update(CHALLENGE_CONTEXT)
+                .chain_update(self.iterations.to_be_bytes())
+                .chain_update(&input)
+                .chain_update(&output)
+                .chain_update(counter.to_be_bytes())
+                .finalize()
+                .into();
+            candidate[0] |= 0x80;
+            candidate[31] |= 1;
+            let candidate = BigUint::from_bytes_be(&candidate);
+            is_probable_prime(&candidate).then_some(candidate)
+        }).expect("primes are dense enough")
+    }
+
+    /// Evaluates the VDF and proves the result. Performs `2 * iterations`
+    /// sequential modular squarings; the proof is computed by long
+    /// division of `2^t` by `l`, one bit per squaring.
+    ///
+    /// # Arguments
+    /// * `random_nonce`: The challenge's `random_nonce`.
+    ///
+    /// # Returns
+    /// * `VdfProof`: The output and its proof.
+    #[cfg(feature = "vdf")]
+    pub fn prove(&self, random_nonce: &str) -> VdfProof {
+        let modulus: BigUint = self.modulus_value();
+        let input: BigUint = derive_base(INPUT_CONTEXT, &modulus, random_nonce);
+
+        let mut output: BigUint = input.clone();
+        for _ in 0..self.iterations {
+            output = &output * &output % &modulus;
+        }
+
+        let prime: BigUint = self.challenge_prime(&input, &output);
+        let mut remainder = BigUint::from(1u32);
+        let mut proof = BigUint::from(1u32);
+        for _ in 0..self.iterations {
+            remainder <<= 1;
+            proof = &proof * &proof % &modulus;
+            if remainder >= prime {
+                remainder -= &prime;
+                proof = proof * &input % &modulus;
+            }
+        }
+
+        VdfProof {
+            output: hex::encode(to_padded_bytes(&output, self.modulus_len())),
+            proof:  hex::encode(to_padded_bytes(&proof, self.modulus_len())),
+        }
+    }
+
+    /// Checks a proof: `proof^l * x^(2^t mod l) = output (mod N)`.
+    /// Costs two exponentiations with 256-bit exponents, whatever
+    /// `iterations` is.
+    ///
+    /// # Arguments
+    /// * `random_nonce`: The challenge's `random_nonce`.
+    /// * `proof`:        The solver's output and proof.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the proof is valid.
+    #[cfg(feature = "vdf")]
+    pub fn verify(&self, random_nonce: &str, proof: &VdfProof) -> Result<(), String> {
+        let modulus: BigUint = self.modulus_value();
+        let parse = |name: &str, value: &str| -> Result<BigUint, String> {
+            if value.len() != self.modulus.len() {
+                return Err(format!(
+                    "VDF {} must be {} bytes of hex, got {} characters",
+                    name,
+                    self.modulus_len(),
+                    value.len()
+                ));
+            }
+            let value = BigUint::from_bytes_be(&hex::decode(value)
+                .map_err(|_| format!("Failed to decode VDF {} hex string", name))?);
+            if value.bits() == 0 || value >= modulus {
+                return Err(format!("VDF {} is not an element of the group", name));
+            }
+            Ok(value)
+        };
+        let output: BigUint = parse("output", &proof.output)?;
+        let pi: BigUint = parse("proof", &proof.proof)?;
+
+        let input: BigUint = derive_base(INPUT_CONTEXT, &modulus, random_nonce);
+        let prime: BigUint = self.challenge_prime(&input, &output);
+        let remainder: BigUint = BigUint::from(2u32).modpow(&BigUint::from(self.iterations), &prime);
+
+        if pi.modpow(&prime, &modulus) * input.modpow(&remainder, &modulus) % &modulus != output {
+            return Err("VDF proof verification failed".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl VdfParams {
+    /// # Arguments
+    /// * `modulus`:    The RSA modulus as lowercase big-endian hex.
+    /// * `iterations`: Number of sequential squarings.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parameters, or an error if the
+    ///                           modulus is malformed or `iterations`
+    ///                           is zero.
+    pub fn new(modulus: &str, iterations: u64) -> Result<Self, String> {
+        let params = VdfParamsRef { modulus, iterations };
+        params.validate()?;
+        Ok(params.to_owned_params())
+    }
+
+    /// # Returns
+    /// * `VdfParamsRef`: The parameters borrowing the modulus.
+    pub fn as_params_ref(&self) -> VdfParamsRef<'_> {
+        VdfParamsRef {
+            modulus:    &self.modulus,
+            iterations: self.iterations,
+        }
+    }
+
+    /// See `VdfParamsRef::prove`.
+    #[cfg(feature = "vdf")]
+    pub fn prove(&self, random_nonce: &str) -> VdfProof {
+        self.as_params_ref().prove(random_nonce)
+    }
+
+    /// See `VdfParamsRef::verify`.
+    #[cfg(feature = "vdf")]
+    pub fn verify(&self, random_nonce: &str, proof: &VdfProof) -> Result<(), String> {
+        self.as_params_ref().verify(random_nonce, proof)
+    }
+
+    /// # Returns
+    /// * `String`: The parameters as `<hex modulus>,<iterations>`.
+    pub fn to_wire_string(&self) -> String {
+        self.as_params_ref().to_wire_string()
+    }
+
+    /// # Arguments
+    /// * `wire`: The string produced by `to_wire_string()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parameters, or an error describing
+    ///                           the malformed field.
+    pub fn from_wire_string(wire: &str) -> Result<Self, String> {
+        VdfParamsRef::from_wire_string(wire).map(|params| params.to_owned_params())
+    }
+}
+
+impl From<VdfParams> for String {
+    fn from(params: VdfParams) -> Self {
+        params.to_wire_string()
+    }
+}
+
+impl TryFrom<String> for VdfParams {
+    type Error = String;
+
+    fn try_from(wire: String) -> Result<Self, Self::Error> {
+        Self::from_wire_string(&wire)
+    }
+}
+
+/// A VDF output with its Wesolowski proof.
+///
+/// * `output`: `x^(2^t) mod N` as hex, padded to the modulus length.
+/// * `proof`:  `x^floor(2^t / l) mod N` as hex, padded the same way.
+#[cfg(feature = "vdf")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VdfProof {
+    pub output: String,
+    pub proof:  String,
+}
+
+/// Response to a VDF challenge.
+///
+/// * `solved_challenge`: The complete original challenge.
+/// * `proof`:            The VDF output and proof.
+#[cfg(feature = "vdf")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VdfResponse {
+    pub solved_challenge: IronShieldChallenge,
+    pub proof:            VdfProof,
+}
+
+#[cfg(feature = "vdf")]
+impl VdfResponse {
+    /// # Arguments
+    /// * `solved_challenge`: The solved challenge.
+    /// * `proof`:            The proof from `VdfParams::prove`.
+    ///
+    /// # Returns
+    /// * `Self`: The response.
+    pub fn new(solved_challenge: IronShieldChallenge, proof: VdfProof) -> Self {
+        Self { solved_challenge, proof }
+    }
+
+    /// Checks the proof against the challenge's VDF parameters.
+    /// Signature and expiry are validated separately.
+    ///
+    /// # Returns
+    /// * `Result<(), String>`: `Ok(())` if the proof is valid, or an
+    ///                         error if it is not or the challenge is
+    ///                         not a VDF challenge.
+    pub fn verify_proof(&self) -> Result<(), String> {
+        self.solved_challenge.vdf.as_ref()
+            .ok_or("Challenge is not a VDF challenge")?
+            .verify(self.solved_challenge.random_nonce.as_str(), &self.proof)
+    }
+
+    /// Concatenates the response data into a string.
+    ///
+    /// Concatenates:
+    /// * `solved_challenge`: As its concatenated string representation.
+    /// * `proof.output`:     As a lowercase hex string.
+    /// * `proof.proof`:      As a lowercase hex string.
+    pub fn concat_struct(&self) -> String {
+        format!("{}|{}|{}", self.solved_challenge.concat_struct(), self.proof.output, self.proof.proof)
+    }
+
+    /// # Arguments
+    /// * `concat_string`: The string produced by `concat_struct()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The parsed response or an error message.
+    pub fn from_concat_struct(concat_string: &str) -> Result<Self, String> {
+        let mut parts = concat_string.rsplitn(3, '|');
+        let proof: &str = parts.next().ok_or("Missing VDF proof")?;
+        let output: &str = parts.next().ok_or("Missing VDF output")?;
+        let challenge_part: &str = parts.next().ok_or("Expected at least two '|' separators")?;
+
+        Ok(Self {
+            solved_challenge: IronShieldChallenge::from_concat_struct(challenge_part)?,
+            proof:            VdfProof {
+                output: output.to_string(),
+                proof:  proof.to_string(),
+            },
+        })
+    }
+
+    /// # Returns
+    /// * `String`: The response, base64url-encoded for HTTP header transport.
+    pub fn to_base64url_header(&self) -> String {
+        crate::serde_utils::concat_struct_base64url_encode(&self.concat_struct())
+    }
+
+    /// # Arguments
+    /// * `encoded_header`: The value produced by `to_base64url_header()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded response or detailed error message.
+    pub fn from_base64url_header(encoded_header: &str) -> Result<Self, String> {
+        let concat_str: String = crate::serde_utils::concat_struct_base64url_decode(encoded_header.to_string())?;
+        Self::from_concat_struct(&concat_str)
+    }
+}
+
+#[cfg(all(test, feature = "rng", feature = "vdf"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    /// Product of the Mersenne primes 2^127 - 1 and 2^89 - 1; far too
+    /// small for real use but quick to test with.
+    fn modulus() -> String {
+        let p: BigUint = (BigUint::from(1u32) << 127u32) - 1u32;
+        let q: BigUint = (BigUint::from(1u32) << 89u32) - 1u32;
+        (p * q).to_str_radix(16)
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let params = VdfParams::new(&modulus(), 1_000).unwrap();
+        let proof: VdfProof = params.prove("00112233");
+        params.verify("00112233", &proof).unwrap();
+        assert!(params.verify("00112234", &proof).is_err());
+        assert!(VdfParams::new(&modulus(), 999).unwrap().verify("00112233", &proof).is_err());
+        let swapped = VdfProof { output: proof.proof.clone(), proof: proof.output.clone() };
+        assert!(params.verify("00112233", &swapped).is_err());
+
+        assert_eq!(VdfParams::from_wire_string(&params.to_wire_string()).unwrap(), params);
+        assert!(VdfParams::new(&modulus(), 0).is_err());
+        assert!(VdfParams::new(&modulus().to_uppercase(), 1).is_err());
+    }
+
+    #[test]
+    fn test_vdf_challenge() {
+        let signing_key = SigningKey::from_bytes(&[6; 32]);
+        let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, signing_key.clone(), signing_key.verifying_key().to_bytes())
+            .with_vdf(&modulus(), 200, &signing_key)
+            .unwrap();
+        assert_eq!(challenge.algorithm(), crate::PowAlgorithm::Vdf);
+        crate::verify_challenge_signature_with_key(&challenge, &signing_key.verifying_key().to_bytes()).unwrap();
+        assert!(challenge.check_solutions(&[0]).is_err());
+
+        let proof: VdfProof = challenge.vdf.as_ref().unwrap().prove(challenge.random_nonce.as_str());
+        let response = VdfResponse::new(challenge.clone(), proof);
+        response.verify_proof().unwrap();
+        let decoded = VdfResponse::from_base64url_header(&response.to_base64url_header()).unwrap();
+        assert_eq!(decoded, response);
+        decoded.verify_proof().unwrap();
+    }
+}
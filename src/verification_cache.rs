@@ -0,0 +1,283 @@
+//! # LRU cache of verified tokens.
+//!
+//! Verifying an Ed25519 signature on every request carrying the same
+//! token is wasteful. `LruVerificationCache` remembers tokens that passed
+//! verification, keyed by `IronShieldToken::jti()`, until the token's own
+//! `valid_for` passes. When full, the least recently used token is
+//! dropped. Hit and miss counters are kept for metrics.
+
+use std::collections::{
+    BTreeMap,
+    HashMap
+};
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+
+use crate::epoch::now_millis;
+use crate::IronShieldToken;
+
+/// Hit and miss counters of a verification cache.
+///
+/// * `hits`:   Lookups answered from the cache.
+/// * `misses`: Lookups that required full verification.
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits:   u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// # Returns
+    /// * `f64`: The fraction of lookups that were hits, or `0.0`
+    ///          before the first lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        let total: u64 = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+/// Bounded LRU set of verified token identifiers.
+#[derive(Debug, Clone)]
+pub struct LruVerificationCache {
+    capacity: usize,
+    /// `jti` → (`valid_for`, recency tick)
+    entries:  HashMap<[u8; 32], (i64, u64)>,
+    /// recency tick → `jti`, oldest first
+    recency:  BTreeMap<u64, [u8; 32]>,
+    tick:     u64,
+    stats:    CacheStats,
+}
+
+impl LruVerificationCache {
+    /// Constructor for creating an empty `LruVerificationCache`.
+    ///
+    /// # Arguments
+    /// * `capacity`: Maximum number of tokens remembered (at least 1).
+    ///
+    /// # Returns
+    /// * `Self`: An empty cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries:  HashMap::new(),
+            recency:  BTreeMap::new(),
+            tick:     0,
+            stats:    CacheStats::default(),
+        }
+    }
+
+    /// # Returns
+    /// * `usize`: The number of remembered tokens.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// # Returns
+    /// * `bool`: `true` if no token is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// # Returns
+    /// * `CacheStats`: The hit and miss counters.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Resets the hit and miss counters, e.g. after exporting them.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// # Arguments
+    /// * `token`: The presented token.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the token was verified before and has not
+    ///           expired. Counts as a hit or a miss.
+    pub fn is_verified(&mut self, token: &IronShieldToken) -> bool {
+        self.is_verified_at(token, now_millis())
+    }
+
+    /// Same as `is_verified`, evaluated at `now`.
+    pub fn is_verified_at(&mut self, token: &IronShieldToken, now: i64) -> bool {
+        let jti: [u8; 32] = token.jti();
+
+        let Some(&(valid_for, last_used)) = self.entries.get(&jti) else {
+            self.stats.misses += 1;
+            return false;
+        };
+
+        self.recency.remove(&last_used);
+        if now > valid_for {
+            self.entries.remove(&jti);
+            self.stats.misses += 1;
+            return false;
+        }
+
+        let tick: u64 = self.next_tick();
+        self.recency.insert(tick, jti);
+        self.entries.insert(jti, (valid_for, tick));
+        self.stats.hits += 1;
+        true
+    }
+
+    /// Remembers a token that has just passed verification.
+    ///
+    /// # Arguments
+    /// * `token`: The verified token.
+    pub fn record_verified(&mut self, token: &IronShieldToken) {
+        let jti: [u8; 32] = token.jti();
+
+        if let Some((_, last_used)) = self.entries.remove(&jti) {
+            self.recency.remove(&last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => { self.entries.remove(&oldest); }
+                None => break,
+            }
+        }
+
+        let tick: u64 = self.next_tick();
+        self.recency.insert(tick, jti);
+        self.entries.insert(jti, (token.valid_for, tick));
+    }
+
+    /// Forgets a token, e.g. after it was revoked.
+    ///
+    /// # Arguments
+    /// * `jti`: The token identifier.
+    pub fn invalidate(&mut self, jti: &[u8; 32]) {
+        if let Some((_, last_used)) = self.entries.remove(jti) {
+            self.recency.remove(&last_used);
+        }
+    }
+
+    /// Answers from the cache, or runs `verify` on a miss and remembers
+    /// the token if it succeeds.
+    ///
+    /// # Arguments
+    /// * `token`:  The presented token.
+    /// * `verify`: The full verification, e.g. a call to
+    ///             `verify_token_signature_with_key`.
+    ///
+    /// # Returns
+    /// * `Result<(), E>`: `Ok(())` on a hit or successful verification,
+    ///                    otherwise the error returned by `verify`.
+    ///
+    /// # Example
+    #[cfg_attr(feature = "rng", doc = " ```")]
+    #[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
+    /// use ironshield_types::*;
+    /// let key = SigningKey::from_bytes(&[7u8; 32]);
+    /// let public_key = key.verifying_key().to_bytes();
+    /// let challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 100, key.clone(), public_key);
+    /// let token = issue_token(&challenge, &key, &LifetimePolicy::default()).unwrap();
+    ///
+    /// let mut cache = LruVerificationCache::new(1_000);
+    /// cache.verify_with(&token, |t| verify_token_signature_with_key(t, &public_key)).unwrap();
+    /// cache.verify_with(&token, |t| verify_token_signature_with_key(t, &public_key)).unwrap();
+    /// assert_eq!(cache.stats().hits, 1);
+    /// ```
+    pub fn verify_with<F, E>(&mut self, token: &IronShieldToken, verify: F) -> Result<(), E>
+    where
+        F: FnOnce(&IronShieldToken) -> Result<(), E>,
+    {
+        if self.is_verified(token) {
+            return Ok(());
+        }
+        verify(token)?;
+        self.record_verified(token);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(seed: u8, valid_for: i64) -> IronShieldToken {
+        IronShieldToken::new([seed; 64], valid_for, [seed; 32], [seed; 64])
+    }
+
+    #[test]
+    fn test_hits_and_misses() {
+        let mut cache = LruVerificationCache::new(10);
+        let token = token(1, 1_000);
+
+        assert!(!cache.is_verified_at(&token, 0));
+        cache.record_verified(&token);
+        assert!(cache.is_verified_at(&token, 500));
+        assert!(cache.is_verified_at(&token, 1_000));
+
+        let stats: CacheStats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert!((stats.hit_ratio() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entries_expire_with_token() {
+        let mut cache = LruVerificationCache::new(10);
+        let token = token(1, 1_000);
+        cache.record_verified(&token);
+
+        assert!(!cache.is_verified_at(&token, 1_001));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_tampered_token_misses() {
+        let mut cache = LruVerificationCache::new(10);
+        let token = token(1, 1_000);
+        cache.record_verified(&token);
+
+        let mut extended = token.clone();
+        extended.valid_for = i64::MAX;
+        assert!(!cache.is_verified_at(&extended, 500));
+    }
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut cache = LruVerificationCache::new(2);
+        let (a, b, c) = (token(1, 1_000), token(2, 1_000), token(3, 1_000));
+        cache.record_verified(&a);
+        cache.record_verified(&b);
+
+        // Touch `a`, so `b` becomes the least recently used.
+        assert!(cache.is_verified_at(&a, 0));
+        cache.record_verified(&c);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.is_verified_at(&a, 0));
+        assert!(!cache.is_verified_at(&b, 0));
+        assert!(cache.is_verified_at(&c, 0));
+    }
+
+    #[test]
+    fn test_verify_with_only_records_success() {
+        let mut cache = LruVerificationCache::new(10);
+        let token = token(1, i64::MAX);
+
+        assert_eq!(cache.verify_with(&token, |_| Err("bad signature")), Err("bad signature"));
+        assert!(cache.is_empty());
+
+        assert_eq!(cache.verify_with(&token, |_| Ok::<(), &str>(())), Ok(()));
+        assert_eq!(cache.verify_with(&token, |_| Err("not called")), Ok(()));
+
+        cache.invalidate(&token.jti());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+//! # Verifiable challenge nonces.
+//!
+//! With plain random nonces an auditor has to trust the issuer's RNG.
+//! With the `vrf` feature, an issuer can instead derive each nonce from
+//! a verifiable random function (VRF) over `(website_id, counter)`:
+//! the nonce is unpredictable without the VRF key, yet anyone holding
+//! the VRF public key can check that it was computed honestly, i.e.
+//! not picked to favor some client. Auditors should also check that
+//! counters never repeat.
+//!
+//! The VRF is ECVRF-style over ristretto255: `Gamma = x * H(input)`
+//! with a Chaum-Pedersen proof that `Gamma` and the public key share
+//! the secret `x`. The nonce is a hash of `Gamma`. The proof travels
+//! in the challenge as the `vn` signed extension.
+
+#[cfg(feature = "vrf")]
+use curve25519_dalek::ristretto::{
+    CompressedRistretto,
+    RistrettoPoint
+};
+#[cfg(feature = "vrf")]
+use curve25519_dalek::scalar::Scalar;
+use serde::{
+    Deserialize,
+    Serialize
+};
+#[cfg(feature = "vrf")]
+use sha2::{
+    Digest,
+    Sha512
+};
+
+#[cfg(feature = "vrf")]
+use crate::{
+    CryptoError,
+    RANDOM_NONCE_LEN
+};
+#[cfg(feature = "vrf")]
+use crate::RandomNonce;
+
+/// Length of a VRF proof: `Gamma`, challenge and response, 32 bytes each.
+pub const VRF_PROOF_LEN: usize = 96;
+
+/// Domain separation tag for hashing the VRF input to the group.
+#[cfg(feature = "vrf")]
+const HASH_TO_GROUP_DST: &[u8] = b"ironshield-vrf-nonce-v1-hash-to-group";
+/// Domain separation tag of the proof challenge.
+#[cfg(feature = "vrf")]
+const CHALLENGE_DST:     &[u8] = b"ironshield-vrf-nonce-v1-challenge";
+/// Domain separation tag of the deterministic proof nonce.
+#[cfg(feature = "vrf")]
+const PROOF_NONCE_DST:   &[u8] = b"ironshield-vrf-nonce-v1-proof-nonce";
+/// Domain separation tag of the VRF output.
+#[cfg(feature = "vrf")]
+const OUTPUT_DST:        &[u8] = b"ironshield-vrf-nonce-v1-output";
+
+/// Proof that a challenge nonce is the VRF output for
+/// `(website_id, counter)`.
+///
+/// * `counter`: Issuer-side sequence number; unique per VRF key and
+///              website.
+/// * `proof`:   `Gamma || c || s`.
+///
+/// Serialized as `<counter>,<hex proof>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct VrfNonceProof {
+    pub counter: u64,
+    pub proof:   [u8; VRF_PROOF_LEN],
+}
+
+impl VrfNonceProof {
+    /// # Returns
+    /// * `String`: The proof as `<counter>,<hex proof>`.
+    pub fn to_wire_string(&self) -> String {
+        format!("{},{}", self.counter, hex::encode(self.proof))
+    }
+
+    /// # Arguments
+    /// * `wire`: The string produced by `to_wire_string()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The proof, or an error describing the
+    ///                           malformed field.
+    pub fn from_wire_string(wire: &str) -> Result<Self, String> {
+        let (counter, proof_hex) = wire.split_once(',')
+            .ok_or("VRF nonce proof must be <counter>,<hex proof>")?;
+        let counter: u64 = counter.parse::<u64>()
+            .map_err(|_| "Failed to parse VRF counter as u64")?;
+        let mut proof = [0u8; VRF_PROOF_LEN];
+        hex::decode_to_slice(proof_hex, &mut proof)
+            .map_err(|_| format!("VRF proof must be {} bytes of hex", VRF_PROOF_LEN))?;
+
+        Ok(Self { counter, proof })
+    }
+
+    /// Checks the proof and returns the nonce it proves.
+    ///
+    /// # Arguments
+    /// * `website_id`:     The website the nonce was issued for.
+    /// * `vrf_public_key`: The issuer's `VrfNonceKey::public_key()`.
+    ///
+    /// # Returns
+    /// * `Result<RandomNonce, CryptoError>`: The nonce, or an error if
+    ///                                       the proof does not verify.
+    #[cfg(feature = "vrf")]
+    pub fn verify(&self, website_id: &str, vrf_public_key: &[u8; 32]) -> Result<RandomNonce, CryptoError> {
+        let invalid = |what: &str| CryptoError::VerificationFailed(format!("Invalid VRF proof: {}", what).into());
+
+        let public_key: RistrettoPoint = decompress(vrf_public_key)
+            .ok_or_else(|| CryptoError::InvalidKeyFormat("VRF public key is not a valid group element".into()))?;
+        let gamma_bytes: [u8; 32] = self.proof[..32].try_into().unwrap_or_default();
+        let gamma: RistrettoPoint = decompress(&gamma_bytes).ok_or_else(|| invalid("gamma"))?;
+        let challenge: Scalar = canonical_scalar(&self.proof[32..64]).ok_or_else(|| invalid("challenge"))?;
+        let response: Scalar = canonical_scalar(&self.proof[64..]).ok_or_else(|| invalid("response"))?;
+
+        let base: RistrettoPoint = hash_to_group(vrf_public_key, website_id, self.counter);
+        let expected: Scalar = proof_challenge(
+            &public_key,
+            &base,
+            &gamma,
+            &(RistrettoPoint::mul_base(&response) - challenge * public_key),
+            &(response * base - challenge * gamma),
+        );
+        if expected != challenge {
+            return Err(invalid("challenge mismatch"));
+        }
+
+        Ok(nonce_from_gamma(&gamma))
+    }
+}
+
+impl From<VrfNonceProof> for String {
+    fn from(proof: VrfNonceProof) -> Self {
+        proof.to_wire_string()
+    }
+}
+
+impl TryFrom<String> for VrfNonceProof {
+    type Error = String;
+
+    fn try_from(wire: String) -> Result<Self, Self::Error> {
+        Self::from_wire_string(&wire)
+    }
+}
+
+/// Decompresses a group element, rejecting invalid encodings and the
+/// identity.
+#[cfg(feature = "vrf")]
+fn decompress(bytes: &[u8; 32]) -> Option<RistrettoPoint> {
+    CompressedRistretto(*bytes).decompress()
+        .filter(|point| *point != RistrettoPoint::default())
+}
+
+#[cfg(feature = "vrf")]
+fn canonical_scalar(bytes: &[u8]) -> Option<Scalar> {
+    let bytes: [u8; 32] = bytes.try_into().ok()?;
+    Option::from(Scalar::from_canonical_bytes(bytes))
+}
+
+/// `H(public_key, website_id, counter)`, with the website id length
+/// prefixed so distinct inputs never share an encoding.
+#[cfg(feature = "vrf")]
+fn hash_to_group(public_key: &[u8; 32], website_id: &str, counter: u64) -> RistrettoPoint {
+    RistrettoPoint::hash_from_bytes::<Sha512>(&[
+        HASH_TO_GROUP_DST,
+        public_key,
+        &(website_id.len() as u64).to_be_bytes(),
+        website_id.as_bytes(),
+        &counter.to_be_bytes(),
+    ].concat())
+}
+
+#[cfg(feature = "vrf")]
+fn proof_challenge(
+    public_key: &RistrettoPoint,
+    base: &RistrettoPoint,
+    gamma: &RistrettoPoint,
+    u: &RistrettoPoint,
+    v: &RistrettoPoint
+) -> Scalar {
+    let mut transcript: Vec<u8> = CHALLENGE_DST.to_vec();
+    for point in [public_key, base, gamma, u, v] {
+        transcript.extend_from_slice(point.compress().as_bytes());
+    }
+    Scalar::hash_from_bytes::<Sha512>(&transcript)
+}
+
+/// First `RANDOM_NONCE_LEN` bytes of the hashed VRF output, hex-encoded
+/// like `IronShieldChallenge::generate_random_nonce()`.
+#[cfg(feature = "vrf")]
+fn nonce_from_gamma(gamma: &RistrettoPoint) -> RandomNonce {
+    let output = Sha512::new()
+        .chain_update(OUTPUT_DST)
+        .chain_update(gamma.compress().as_bytes())
+        .finalize();
+    let mut nonce: [u8; RANDOM_NONCE_LEN] = [0u8; RANDOM_NONCE_LEN];
+    nonce.copy_from_slice(&output[..RANDOM_NONCE_LEN]);
+    RandomNonce::from_bytes(&nonce)
+}
+
+/// Secret key of the nonce VRF.
+#[cfg(feature = "vrf")]
+#[derive(Clone)]
+pub struct VrfNonceKey {
+    secret: Scalar,
+}
+
+/// Leaves out the secret scalar.
+#[cfg(feature = "vrf")]
+impl std::fmt::Debug for VrfNonceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VrfNonceKey")
+            .field("public_key", &hex::encode(self.public_key()))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "vrf")]
+impl VrfNonceKey {
+    /// # Returns
+    /// * `Self`: A fresh random key.
+    #[cfg(feature = "rng")]
+    pub fn generate() -> Self {
+        use rand::RngCore;
+
+        loop {
+            let mut wide = [0u8; 64];
+            rand::rngs::OsRng.fill_bytes(&mut wide);
+            let secret: Scalar = Scalar::from_bytes_mod_order_wide(&wide);
+            if secret != Scalar::ZERO {
+                return Self { secret };
+            }
+        }
+    }
+
+    /// # Arguments
+    /// * `bytes`: A canonical, nonzero scalar from `to_bytes()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, CryptoError>`: The key, or an error if the bytes
+    ///                                are not a valid secret.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, CryptoError> {
+        canonical_scalar(bytes)
+            .filter(|secret| *secret != Scalar::ZERO)
+            .map(|secret| Self { secret })
+            .ok_or_else(|| CryptoError::InvalidKeyFormat("VRF key is not a canonical nonzero scalar".into()))
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The secret scalar, for storage.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    /// # Returns
+    /// * `[u8; 32]`: The public key auditors verify nonces against.
+    pub fn public_key(&self) -> [u8; 32] {
+        RistrettoPoint::mul_base(&self.secret).compress().to_bytes()
+    }
+
+    /// Computes the nonce for `(website_id, counter)` and its proof.
+    ///
+    /// # Arguments
+    /// * `website_id`: The website the challenge is issued for.
+    /// * `counter`:    A sequence number never reused with this key
+    ///                 and website.
+    ///
+    /// # Returns
+    /// * `(RandomNonce, VrfNonceProof)`: The nonce and its proof.
+    pub fn evaluate(&self, website_id: &str, counter: u64) -> (RandomNonce, VrfNonceProof) {
+        let public_key: [u8; 32] = self.public_key();
+        let base: RistrettoPoint = hash_to_group(&public_key, website_id, counter);
+        let gamma: RistrettoPoint = self.secret * base;
+
+        let nonce: Scalar = Scalar::hash_from_bytes::<Sha512>(
+            &[PROOF_NONCE_DST, self.secret.as_bytes(), base.compress().as_bytes()].concat()
+        );
+        let challenge: Scalar = proof_challenge(
+            &RistrettoPoint::mul_base(&self.secret),
+            &base,
+            &gamma,
+            &RistrettoPoint::mul_base(&nonce),
+            &(nonce * base),
+        );
+        let response: Scalar = nonce + challenge * self.secret;
+
+        let mut proof = [0u8; VRF_PROOF_LEN];
+        proof[..32].copy_from_slice(gamma.compress().as_bytes());
+        proof[32..64].copy_from_slice(challenge.as_bytes());
+        proof[64..].copy_from_slice(response.as_bytes());
+
+        (nonce_from_gamma(&gamma), VrfNonceProof { counter, proof })
+    }
+}
+
+#[cfg(all(test, feature = "vrf"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_and_verify() {
+        let key = VrfNonceKey::from_bytes(&VrfNonceKey::generate().to_bytes()).unwrap();
+        let public_key: [u8; 32] = key.public_key();
+
+        let (nonce, proof) = key.evaluate("example.com", 7);
+        assert_eq!(nonce.as_str().len(), RANDOM_NONCE_LEN * 2);
+        assert_eq!(proof.verify("example.com", &public_key).unwrap(), nonce);
+        assert_eq!(key.evaluate("example.com", 7), (nonce.clone(), proof));
+        assert_ne!(key.evaluate("example.com", 8).0, nonce);
+
+        assert!(proof.verify("other.example", &public_key).is_err());
+        assert!(VrfNonceProof { counter: 8, ..proof }.verify("example.com", &public_key).is_err());
+        assert!(proof.verify("example.com", &VrfNonceKey::generate().public_key()).is_err());
+        let mut tampered = proof;
+        tampered.proof[70] ^= 1;
+        assert!(tampered.verify("example.com", &public_key).is_err());
+    }
+
+    #[test]
+    fn test_wire_string() {
+        let (_, proof) = VrfNonceKey::generate().evaluate("example.com", 42);
+        let wire: String = proof.to_wire_string();
+        assert!(wire.starts_with("42,"));
+        assert_eq!(VrfNonceProof::from_wire_string(&wire).unwrap(), proof);
+        assert_eq!(serde_json::from_str::<VrfNonceProof>(&serde_json::to_string(&proof).unwrap()).unwrap(), proof);
+        assert!(VrfNonceProof::from_wire_string("42").is_err());
+        assert!(VrfNonceProof::from_wire_string("42,00").is_err());
+        assert!(VrfNonceKey::from_bytes(&[0; 32]).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! # Normalized website identifiers.
+//!
+//! `website_id` is a free-form string on the wire, so `"Example.COM."`
+//! and `"example.com"` would otherwise be treated as two different sites
+//! by key registries, override tables and caches. `WebsiteId` parses and
+//! normalizes the identifier once so equivalent spellings compare equal.
+//!
+//! Challenges carry their `website_id` as a `WebsiteId` too, but sign
+//! it byte for byte, so a challenge read back from the wire keeps the
+//! spelling it was issued with; see `WebsiteId::verbatim`.
+//!
+//! With the `public-suffix` feature, a `WebsiteId` can also be reduced to
+//! its registrable domain (eTLD+1) using a caller-supplied public suffix
+//! list, so `www.example.co.uk` and `shop.example.co.uk` share an identity.
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize
+};
+
+/// Maximum length of a hostname in its ASCII form.
+const MAX_HOST_LEN:  usize = 253;
+/// Maximum length of a single hostname label.
+const MAX_LABEL_LEN: usize = 63;
+/// Maximum length of a `website_id` carried in a challenge.
+pub(crate) const MAX_WEBSITE_ID_LEN: usize = 2048;
+
+/// Normalized website identifier.
+///
+/// Parsing accepts a bare host (`Example.COM.`) or a URL
+/// (`https://example.com:8443/path`) and keeps only the host:
+/// * The scheme, port, path, query and fragment are removed.
+/// * A trailing root dot is removed.
+/// * The host is lowercased and internationalized labels are
+///   converted to punycode (`bücher.de` → `xn--bcher-kva.de`).
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "openapi", schema(
+    value_type = String,
+    description = "Normalized, punycode-encoded website host name"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WebsiteId(String);
+
+impl WebsiteId {
+    /// Checks that `value` can be carried in a challenge: 1 to
+    /// `MAX_WEBSITE_ID_LEN` bytes, without control characters or `|`.
+    pub(crate) fn validate_str(value: &str) -> Result<(), String> {
+        if value.is_empty() || value.len() > MAX_WEBSITE_ID_LEN {
+            return Err(format!("website_id must be 1 to {} bytes", MAX_WEBSITE_ID_LEN));
+        }
+        if value.chars().any(|c| c.is_control() || c == '|') {
+            return Err("website_id must not contain control characters or '|'".to_string());
+        }
+        Ok(())
+    }
+
+    /// Parses and normalizes a website identifier.
+    ///
+    /// # Arguments
+    /// * `input`: A host name or URL.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The normalized identifier, or an error
+    ///                           if no valid host could be extracted.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::WebsiteId;
+    /// let a = WebsiteId::parse("Example.COM.").unwrap();
+    /// let b = WebsiteId::parse("https://example.com/login").unwrap();
+    /// assert_eq!(a, b);
+    /// assert_eq!(a.as_str(), "example.com");
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input: &str = input.trim();
+        let without_scheme: &str = input.split_once("://").map_or(input, |(_, rest)| rest);
+        let authority: &str = without_scheme
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default();
+        let host_and_port: &str = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let host: &str = match host_and_port.rsplit_once(':') {
+            Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+            _ => host_and_port,
+        };
+        let host: &str = host.strip_suffix('.').unwrap_or(host);
+
+        if host.is_empty() {
+            return Err(format!("Website ID has no host: {}", input));
+        }
+
+        let ascii: String = idna::domain_to_ascii_cow(host.as_bytes(), idna::AsciiDenyList::URL)
+            .map_err(|_| format!("Website ID is not a valid host name: {}", input))?
+            .into_owned();
+
+        if ascii.is_empty() {
+            return Err(format!("Website ID has no host: {}", input));
+        }
+        if ascii.len() > MAX_HOST_LEN {
+            return Err(format!("Website ID exceeds {} characters", MAX_HOST_LEN));
+        }
+        if ascii.split('.').any(|label| label.is_empty() || label.len() > MAX_LABEL_LEN) {
+            return Err(format!("Website ID has an empty or oversized label: {}", input));
+        }
+
+        Ok(Self(ascii))
+    }
+
+    /// Takes an identifier exactly as given, without normalizing it.
+    ///
+    /// Challenge signatures cover the `website_id` byte for byte, so
+    /// identifiers read back from a challenge keep the issued spelling,
+    /// which may be a URL or differ in case from what `parse` returns.
+    /// Such an identifier only equals its parsed form if it was already
+    /// normalized.
+    ///
+    /// # Arguments
+    /// * `value`: The identifier.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The identifier, or an error if it is
+    ///                           empty, too long, or contains control
+    ///                           characters or `|`.
+    ///
+    /// # Example
+    /// ```
+    /// use ironshield_types::WebsiteId;
+    /// let website_id = WebsiteId::verbatim("https://Example.com/api").unwrap();
+    /// assert_eq!(website_id, "https://Example.com/api");
+    /// assert!(WebsiteId::verbatim("a|b").is_err());
+    /// ```
+    pub fn verbatim(value: impl Into<String>) -> Result<Self, String> {
+        let value: String = value.into();
+        Self::validate_str(&value)?;
+        Ok(Self(value))
+    }
+
+    /// Wraps a string without any checks, for conformance vectors that
+    /// must carry identifiers a verifier has to reject.
+    #[cfg(feature = "sign")]
+    pub(crate) fn unchecked(value: String) -> Self {
+        Self(value)
+    }
+
+    /// # Returns
+    /// * `&str`: The identifier.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// # Returns
+    /// * `String`: The identifier, consuming `self`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+
+    /// Reduces the identifier to its registrable domain (eTLD+1).
+    ///
+    /// # Arguments
+    /// * `list`: The public suffix list to resolve against.
+    ///
+    /// # Returns
+    /// * `Result<WebsiteId, String>`: The registrable domain, or an error
+    ///                                if the host is itself a public suffix.
+    #[cfg(feature = "public-suffix")]
+    pub fn registrable_domain(&self, list: &publicsuffix::List) -> Result<WebsiteId, String> {
+        use publicsuffix::Psl;
+
+        let domain = list.domain(self.0.as_bytes())
+            .ok_or_else(|| format!("Website ID has no registrable domain: {}", self.0))?;
+        let domain: &str = std::str::from_utf8(domain.as_bytes())
+            .map_err(|_| format!("Website ID has no registrable domain: {}", self.0))?;
+
+        Ok(Self(domain.to_string()))
+    }
+}
+
+/// Deserializes a challenge's `website_id` with `WebsiteId::verbatim`.
+pub(crate) fn deserialize_verbatim_website_id<'de, D>(deserializer: D) -> Result<WebsiteId, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    WebsiteId::verbatim(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+impl std::ops::Deref for WebsiteId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for WebsiteId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for WebsiteId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for WebsiteId {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<WebsiteId> for String {
+    fn from(website_id: WebsiteId) -> Self {
+        website_id.0
+    }
+}
+
+impl AsRef<str> for WebsiteId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for WebsiteId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for WebsiteId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for WebsiteId {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<WebsiteId> for String {
+    fn eq(&self, other: &WebsiteId) -> bool {
+        *self == other.0
+    }
+}
+
+impl PartialEq<WebsiteId> for str {
+    fn eq(&self, other: &WebsiteId) -> bool {
+        self == other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equivalent_spellings_normalize_identically() {
+        let expected = WebsiteId::parse("example.com").unwrap();
+        for spelling in [
+            "Example.COM.",
+            " example.com ",
+            "https://EXAMPLE.com:443/path?q=1#frag",
+            "http://user@example.com",
+            "example.com:8080",
+        ] {
+            assert_eq!(WebsiteId::parse(spelling).unwrap(), expected, "{}", spelling);
+        }
+    }
+
+    #[test]
+    fn test_internationalized_hosts_use_punycode() {
+        let website_id = WebsiteId::parse("Bücher.de").unwrap();
+        assert_eq!(website_id.as_str(), "xn--bcher-kva.de");
+        assert_eq!(WebsiteId::parse("xn--bcher-kva.de").unwrap(), website_id);
+    }
+
+    #[test]
+    fn test_invalid_hosts_are_rejected() {
+        assert!(WebsiteId::parse("").unwrap_err().contains("no host"));
+        assert!(WebsiteId::parse("https:///path").unwrap_err().contains("no host"));
+        assert!(WebsiteId::parse("exa mple.com").unwrap_err().contains("not a valid host"));
+        assert!(WebsiteId::parse("a..b").unwrap_err().contains("empty or oversized label"));
+        assert!(WebsiteId::parse(&format!("{}.com", "a".repeat(64))).unwrap_err().contains("oversized label"));
+    }
+
+    #[test]
+    fn test_serde_normalizes_and_validates() {
+        let website_id: WebsiteId = serde_json::from_str(r#""WWW.Example.com.""#).unwrap();
+        assert_eq!(website_id.as_str(), "www.example.com");
+        assert_eq!(serde_json::to_string(&website_id).unwrap(), r#""www.example.com""#);
+        assert!(serde_json::from_str::<WebsiteId>(r#""""#).is_err());
+    }
+
+    #[test]
+    fn test_verbatim_keeps_spelling() {
+        let website_id = WebsiteId::verbatim("Example.COM.").unwrap();
+        assert_eq!(website_id, "Example.COM.");
+        assert_ne!(website_id, WebsiteId::parse("example.com").unwrap());
+        assert_eq!(website_id.to_lowercase(), "example.com.");
+
+        assert!(WebsiteId::verbatim("").is_err());
+        assert!(WebsiteId::verbatim("a\nb").unwrap_err().contains("control"));
+        assert!(WebsiteId::verbatim("x".repeat(MAX_WEBSITE_ID_LEN + 1)).is_err());
+    }
+
+    #[cfg(feature = "public-suffix")]
+    #[test]
+    fn test_registrable_domain() {
+        let list: publicsuffix::List = "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n".parse().unwrap();
+
+        let website_id = WebsiteId::parse("shop.Example.co.uk").unwrap();
+        assert_eq!(website_id.registrable_domain(&list).unwrap().as_str(), "example.co.uk");
+
+        let website_id = WebsiteId::parse("www.example.com").unwrap();
+        assert_eq!(website_id.registrable_domain(&list).unwrap().as_str(), "example.com");
+
+        let suffix_only = WebsiteId::parse("co.uk").unwrap();
+        assert!(suffix_only.registrable_domain(&list).unwrap_err().contains("no registrable domain"));
+    }
+}
@@ -0,0 +1,236 @@
+//! # WebSocket upgrade transport for challenges and responses.
+//!
+//! Browsers cannot attach custom headers to a WebSocket handshake, so the
+//! `X-IronShield-*` headers are unavailable. The two channels left are the
+//! `Sec-WebSocket-Protocol` header and the URL query string. Both carry
+//! the compact binary encoding, base64url-encoded without padding, which
+//! is valid in an RFC 7230 token and in a query-string value.
+//!
+//! Values are capped at `MAX_WEBSOCKET_PAYLOAD_LEN` characters, which is
+//! far stricter than the HTTP header path. Proxies and servers often limit
+//! the handshake size, and an oversized value is rejected before decoding.
+//!
+//! A client offers `WEBSOCKET_SUBPROTOCOL` alongside the encoded value,
+//! e.g. `Sec-WebSocket-Protocol: ironshield, ironshield-r.AQAg...`, so the
+//! server can echo the short name back instead of the payload.
+
+use base64::Engine;
+
+use crate::{
+    IronShieldChallenge,
+    IronShieldChallengeResponse
+};
+
+/// Subprotocol name a server echoes back to accept the handshake.
+pub const WEBSOCKET_SUBPROTOCOL:                &str = "ironshield";
+/// `Sec-WebSocket-Protocol` entry prefix for an encoded challenge.
+pub const WEBSOCKET_CHALLENGE_PROTOCOL_PREFIX:  &str = "ironshield-c.";
+/// `Sec-WebSocket-Protocol` entry prefix for an encoded response.
+pub const WEBSOCKET_RESPONSE_PROTOCOL_PREFIX:   &str = "ironshield-r.";
+/// Maximum length of an encoded value, excluding the protocol prefix.
+pub const MAX_WEBSOCKET_PAYLOAD_LEN:          usize = 512;
+
+/// Base64url-encodes a compact payload and enforces the size limit.
+fn encode_websocket_payload(bytes: &[u8]) -> Result<String, String> {
+    let encoded: String = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    if encoded.len() > MAX_WEBSOCKET_PAYLOAD_LEN {
+        return Err(format!(
+            "WebSocket payload is {} characters, limit is {}",
+            encoded.len(),
+            MAX_WEBSOCKET_PAYLOAD_LEN
+        ));
+    }
+    Ok(encoded)
+}
+
+/// Enforces the size limit, then base64url-decodes a payload.
+fn decode_websocket_payload(encoded: &str) -> Result<Vec<u8>, String> {
+    if encoded.len() > MAX_WEBSOCKET_PAYLOAD_LEN {
+        return Err(format!(
+            "WebSocket payload is {} characters, limit is {}",
+            encoded.len(),
+            MAX_WEBSOCKET_PAYLOAD_LEN
+        ));
+    }
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|e: base64::DecodeError| format!("Base64 decode error: {}", e))
+}
+
+/// Finds the entry starting with `prefix` in a comma-separated
+/// `Sec-WebSocket-Protocol` header value and strips the prefix.
+fn find_protocol_payload<'a>(header_value: &'a str, prefix: &str) -> Result<&'a str, String> {
+    header_value
+        .split(',')
+        .map(str::trim)
+        .find_map(|entry| entry.strip_prefix(prefix))
+        .ok_or_else(|| format!("No {}* entry in Sec-WebSocket-Protocol", prefix))
+}
+
+impl IronShieldChallenge {
+    /// Encodes the challenge as a `Sec-WebSocket-Protocol` entry.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The prefixed entry, or an error if
+    ///                             the challenge exceeds the size limit.
+    pub fn to_websocket_protocol(&self) -> Result<String, String> {
+        Ok(format!("{}{}", WEBSOCKET_CHALLENGE_PROTOCOL_PREFIX, self.to_websocket_query_value()?))
+    }
+
+    /// Decodes a challenge from a `Sec-WebSocket-Protocol` header value,
+    /// which may list other subprotocols as well.
+    ///
+    /// # Arguments
+    /// * `header_value`: The full header value.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded challenge or detailed error message.
+    pub fn from_websocket_protocol(header_value: &str) -> Result<Self, String> {
+        Self::from_websocket_query_value(find_protocol_payload(header_value, WEBSOCKET_CHALLENGE_PROTOCOL_PREFIX)?)
+    }
+
+    /// Encodes the challenge as a URL query-string value.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The encoded value, or an error if
+    ///                             the challenge exceeds the size limit.
+    pub fn to_websocket_query_value(&self) -> Result<String, String> {
+        encode_websocket_payload(&self.to_compact_bytes()?)
+    }
+
+    /// Decodes a challenge from a URL query-string value.
+    ///
+    /// # Arguments
+    /// * `value`: The value produced by `to_websocket_query_value()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded challenge or detailed error message.
+    pub fn from_websocket_query_value(value: &str) -> Result<Self, String> {
+        Self::from_compact_bytes(&decode_websocket_payload(value)?)
+    }
+}
+
+impl IronShieldChallengeResponse {
+    /// Encodes the response as a `Sec-WebSocket-Protocol` entry.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The prefixed entry, or an error if
+    ///                             the response exceeds the size limit.
+    ///
+    /// # Example
+    #[cfg_attr(feature = "rng", doc = " ```")]
+    #[cfg_attr(not(feature = "rng"), doc = " ```ignore")]
+    /// use ironshield_types::{IronShieldChallenge, IronShieldChallengeResponse, SigningKey, WEBSOCKET_SUBPROTOCOL};
+    /// let dummy_key = SigningKey::from_bytes(&[0u8; 32]);
+    /// let challenge = IronShieldChallenge::new("test".parse().unwrap(), 100_000, dummy_key, [0x34; 32]);
+    /// let response = IronShieldChallengeResponse::new(challenge, 12345);
+    /// let header_value = format!("{}, {}", WEBSOCKET_SUBPROTOCOL, response.to_websocket_protocol().unwrap());
+    /// let decoded = IronShieldChallengeResponse::from_websocket_protocol(&header_value).unwrap();
+    /// assert_eq!(decoded.solution, 12345);
+    /// ```
+    pub fn to_websocket_protocol(&self) -> Result<String, String> {
+        Ok(format!("{}{}", WEBSOCKET_RESPONSE_PROTOCOL_PREFIX, self.to_websocket_query_value()?))
+    }
+
+    /// Decodes a response from a `Sec-WebSocket-Protocol` header value,
+    /// which may list other subprotocols as well.
+    ///
+    /// # Arguments
+    /// * `header_value`: The full header value.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded response or detailed error message.
+    pub fn from_websocket_protocol(header_value: &str) -> Result<Self, String> {
+        Self::from_websocket_query_value(find_protocol_payload(header_value, WEBSOCKET_RESPONSE_PROTOCOL_PREFIX)?)
+    }
+
+    /// Encodes the response as a URL query-string value.
+    ///
+    /// # Returns
+    /// * `Result<String, String>`: The encoded value, or an error if
+    ///                             the response exceeds the size limit.
+    pub fn to_websocket_query_value(&self) -> Result<String, String> {
+        encode_websocket_payload(&self.to_compact_bytes()?)
+    }
+
+    /// Decodes a response from a URL query-string value.
+    ///
+    /// # Arguments
+    /// * `value`: The value produced by `to_websocket_query_value()`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: Decoded response or detailed error message.
+    pub fn from_websocket_query_value(value: &str) -> Result<Self, String> {
+        Self::from_compact_bytes(&decode_websocket_payload(value)?)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::{
+        LifetimePolicy,
+        RequestDescriptor,
+        SigningKey,
+        WebsiteId
+    };
+
+    fn extended_challenge() -> IronShieldChallenge {
+        let private_key = SigningKey::from_bytes(&[3; 32]);
+        let public_key = private_key.verifying_key().to_bytes();
+        let policy = LifetimePolicy::new(30_000, 5_000, 60_000).unwrap();
+        let descriptor = RequestDescriptor::new("GET", "https://example.com/socket", None).unwrap();
+        IronShieldChallenge::new_with_policy("example.com".parse().unwrap(), 50_000, private_key.clone(), public_key, &policy)
+            .with_request_binding(&descriptor, &private_key)
+    }
+
+    fn is_token_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+    }
+
+    #[test]
+    fn test_protocol_entry_is_valid_token_within_limit() {
+        let response = IronShieldChallengeResponse::new(extended_challenge(), 99);
+        let entry: String = response.to_websocket_protocol().unwrap();
+
+        assert!(entry.starts_with(WEBSOCKET_RESPONSE_PROTOCOL_PREFIX));
+        assert!(entry.chars().all(is_token_char));
+        assert!(entry.len() - WEBSOCKET_RESPONSE_PROTOCOL_PREFIX.len() <= MAX_WEBSOCKET_PAYLOAD_LEN);
+        assert!(entry.len() < response.to_base64url_header().len());
+    }
+
+    #[test]
+    fn test_protocol_roundtrip_among_other_entries() {
+        let challenge = extended_challenge();
+        let header_value = format!(
+            "chat, {}, {}",
+            WEBSOCKET_SUBPROTOCOL,
+            challenge.to_websocket_protocol().unwrap()
+        );
+
+        let decoded = IronShieldChallenge::from_websocket_protocol(&header_value).unwrap();
+        assert_eq!(decoded.concat_struct(), challenge.concat_struct());
+
+        let error = IronShieldChallengeResponse::from_websocket_protocol(&header_value).unwrap_err();
+        assert!(error.contains("No ironshield-r.* entry"));
+    }
+
+    #[test]
+    fn test_query_value_roundtrip() {
+        let response = IronShieldChallengeResponse::new(extended_challenge(), i64::MIN);
+        let value: String = response.to_websocket_query_value().unwrap();
+
+        let decoded = IronShieldChallengeResponse::from_websocket_query_value(&value).unwrap();
+        assert_eq!(decoded.concat_struct(), response.concat_struct());
+    }
+
+    #[test]
+    fn test_size_limit_is_enforced() {
+        let mut challenge = extended_challenge();
+        challenge.website_id = WebsiteId::verbatim("a".repeat(MAX_WEBSOCKET_PAYLOAD_LEN)).unwrap();
+        assert!(challenge.to_websocket_query_value().unwrap_err().contains("limit is 512"));
+
+        let oversized: String = "A".repeat(MAX_WEBSOCKET_PAYLOAD_LEN + 1);
+        assert!(IronShieldChallenge::from_websocket_query_value(&oversized).unwrap_err().contains("limit is 512"));
+    }
+}
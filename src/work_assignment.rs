@@ -0,0 +1,252 @@
+//! # Leasing a challenge's nonce space to several tabs or devices.
+//!
+//! A user with several tabs or devices open can solve one challenge
+//! together: a coordinating tab splits the nonce space into
+//! `WorkAssignment`s, each leasing a `NonceReservation` until
+//! `lease_expires_at`. Solvers send back a `WorkReport`, and
+//! `WorkAssignment::merge` folds the reports into either the winning
+//! solution or the ranges still to be searched. Ranges of solvers that
+//! stopped early or whose lease ran out go back into the pool, so a
+//! closed tab never leaves a hole in the search.
+//!
+//! The `assignment_id` covers the lease expiry, so a renewed lease gets
+//! a new id and late reports about the old lease are ignored.
+
+use serde::{
+    Deserialize,
+    Serialize
+};
+use sha2::{
+    Digest,
+    Sha256
+};
+
+use crate::{
+    IronShieldChallenge,
+    NonceReservation
+};
+
+/// A nonce range leased to one solver.
+///
+/// * `assignment_id`:    Identifier derived from the range and the
+///                       lease expiry.
+/// * `range`:            The nonces to search.
+/// * `lease_expires_at`: Unix millis after which the range may be
+///                       handed to another solver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WorkAssignment {
+    pub assignment_id:    [u8; 16],
+    pub range:            NonceReservation,
+    pub lease_expires_at: i64,
+}
+
+/// What a solver reports about its assignment.
+///
+/// * `Solved`:    It found `nonce`.
+/// * `Exhausted`: It searched the whole range without a solution.
+/// * `Stopped`:   It gave up after the first `searched` nonces, e.g.
+///                because the tab is closing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum WorkOutcome {
+    Solved { nonce: i64 },
+    Exhausted,
+    Stopped { searched: u64 },
+}
+
+/// A solver's report about one assignment.
+///
+/// * `assignment_id`: `assignment_id` of the assignment reported on.
+/// * `outcome`:       What the solver did with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkReport {
+    pub assignment_id: [u8; 16],
+    pub outcome:       WorkOutcome,
+}
+
+/// Result of merging reports into the current assignments.
+///
+/// * `Solved`:  A solver found a valid solution; every other
+///              assignment can be cancelled.
+/// * `Pending`: No solution yet. `active` are the leases still
+///              running, `unassigned` the coalesced ranges to lease
+///              again. Both empty means the space was exhausted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WorkMerge {
+    Solved {
+        assignment_id: [u8; 16],
+        nonce:         i64,
+    },
+    Pending {
+        active:     Vec<WorkAssignment>,
+        unassigned: Vec<NonceReservation>,
+    },
+}
+
+impl WorkAssignment {
+    /// # Arguments
+    /// * `range`:            The nonces to lease.
+    /// * `lease_expires_at`: Unix millis at which the lease ends.
+    ///
+    /// # Returns
+    /// * `Self`: The assignment.
+    pub fn new(range: NonceReservation, lease_expires_at: i64) -> Self {
+        let digest: [u8; 32] = Sha256::new()
+            .chain_update(range.reservation_id)
+            .chain_update(lease_expires_at.to_be_bytes())
+            .finalize()
+            .into();
+
+        let mut assignment_id: [u8; 16] = [0u8; 16];
+        assignment_id.copy_from_slice(&digest[..16]);
+        Self { assignment_id, range, lease_expires_at }
+    }
+
+    /// Splits a challenge's whole nonce space between `solvers`.
+    ///
+    /// # Arguments
+    /// * `challenge`:        The challenge to solve.
+    /// * `solvers`:          Number of tabs or devices.
+    /// * `lease_expires_at`: Unix millis at which the leases end.
+    ///
+    /// # Returns
+    /// * `Vec<Self>`: One assignment per solver, in nonce order.
+    pub fn split(challenge: &IronShieldChallenge, solvers: u64, lease_expires_at: i64) -> Vec<Self> {
+        NonceReservation::whole(challenge)
+            .split(solvers)
+            .into_iter()
+            .map(|range| Self::new(range, lease_expires_at))
+            .collect()
+    }
+
+    /// # Arguments
+    /// * `now`: The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if the lease has run out at `now`.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        now > self.lease_expires_at
+    }
+
+    /// # Arguments
+    /// * `lease_expires_at`: The new end of the lease.
+    ///
+    /// # Returns
+    /// * `Self`: The same range under a new lease and `assignment_id`.
+    pub fn renew(&self, lease_expires_at: i64) -> Self {
+        Self::new(self.range, lease_expires_at)
+    }
+
+    /// Folds solver reports into the current assignments.
+    ///
+    /// Only the first report per assignment counts. A solution wins if
+    /// it is valid and lies in the reporting assignment's range; other
+    /// claimed solutions are treated as if the range had not been
+    /// searched. Reports naming no current assignment are stale and
+    /// ignored. Assignments without a report stay active until their
+    /// lease expires.
+    ///
+    /// # Arguments
+    /// * `challenge`:   The challenge being solved.
+    /// * `assignments`: The assignments handed out.
+    /// * `reports`:     Reports received since, in arrival order.
+    /// * `now`:         The current time in unix millis.
+    ///
+    /// # Returns
+    /// * `WorkMerge`: The solution, or what remains to be searched.
+    pub fn merge(
+        challenge:   &IronShieldChallenge,
+        assignments: &[Self],
+        reports:     &[WorkReport],
+        now:         i64,
+    ) -> WorkMerge {
+        let mut active: Vec<Self> = Vec::new();
+        let mut unassigned: Vec<NonceReservation> = Vec::new();
+
+        for assignment in assignments {
+            let report: Option<&WorkReport> = reports.iter()
+                .find(|report| report.assignment_id == assignment.assignment_id);
+
+            match report.map(|report| report.outcome) {
+                Some(WorkOutcome::Solved { nonce }) => {
+                    if assignment.range.contains(nonce) && challenge.check_solution(nonce).is_ok() {
+                        return WorkMerge::Solved { assignment_id: assignment.assignment_id, nonce };
+                    }
+                    unassigned.push(assignment.range);
+                }
+                Some(WorkOutcome::Exhausted) => {}
+                Some(WorkOutcome::Stopped { searched }) => {
+                    if searched == 0 {
+                        unassigned.push(assignment.range);
+                    } else if let Some((_, rest)) = assignment.range.split_at(searched) {
+                        unassigned.push(rest);
+                    }
+                }
+                None if assignment.is_expired_at(now) => unassigned.push(assignment.range),
+                None => active.push(*assignment),
+            }
+        }
+
+        WorkMerge::Pending {
+            active,
+            unassigned: NonceReservation::merge_all(unassigned),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+
+    fn challenge() -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[6; 32]);
+        IronShieldChallenge::new("example.com".parse().unwrap(), 4, key.clone(), key.verifying_key().to_bytes())
+    }
+
+    #[test]
+    fn test_split_and_renew() {
+        let assignments: Vec<WorkAssignment> = WorkAssignment::split(&challenge(), 3, 1_000);
+        assert_eq!(assignments.len(), 3);
+        assert_eq!(assignments[2].range.last(), i64::MAX);
+        assert!(!assignments[0].is_expired_at(1_000) && assignments[0].is_expired_at(1_001));
+
+        let renewed: WorkAssignment = assignments[0].renew(2_000);
+        assert_eq!(renewed.range, assignments[0].range);
+        assert_ne!(renewed.assignment_id, assignments[0].assignment_id);
+        let json: String = serde_json::to_string(&WorkReport { assignment_id: [0; 16], outcome: WorkOutcome::Stopped { searched: 5 } }).unwrap();
+        assert!(json.contains(r#""outcome":"stopped","searched":5"#));
+    }
+
+    #[test]
+    fn test_merge() {
+        let challenge = challenge();
+        let assignments: Vec<WorkAssignment> = WorkAssignment::split(&challenge, 4, 1_000);
+        let report = |index: usize, outcome: WorkOutcome| WorkReport { assignment_id: assignments[index].assignment_id, outcome };
+
+        let reports = [
+            report(0, WorkOutcome::Stopped { searched: 10 }),
+            report(1, WorkOutcome::Exhausted),
+            WorkReport { assignment_id: assignments[2].renew(5).assignment_id, outcome: WorkOutcome::Exhausted },
+        ];
+        let WorkMerge::Pending { active, unassigned } = WorkAssignment::merge(&challenge, &assignments, &reports, 500) else {
+            panic!("no solution was reported");
+        };
+        assert_eq!(active, [assignments[2], assignments[3]]);
+        assert_eq!(unassigned, [assignments[0].range.split_at(10).unwrap().1]);
+
+        let WorkMerge::Pending { active, unassigned } = WorkAssignment::merge(&challenge, &assignments, &reports, 1_001) else {
+            panic!("no solution was reported");
+        };
+        assert!(active.is_empty());
+        assert_eq!(unassigned.len(), 2);
+
+        let nonce: i64 = assignments[0].range.nonces().find(|nonce| challenge.check_solution(*nonce).is_ok()).unwrap();
+        let wrong: i64 = assignments[0].range.nonces().find(|nonce| challenge.check_solution(*nonce).is_err()).unwrap();
+        let merged = WorkAssignment::merge(&challenge, &assignments, &[report(0, WorkOutcome::Solved { nonce: wrong }), report(0, WorkOutcome::Solved { nonce })], 0);
+        assert!(matches!(merged, WorkMerge::Pending { .. }));
+        let merged = WorkAssignment::merge(&challenge, &assignments, &[report(1, WorkOutcome::Solved { nonce }), report(0, WorkOutcome::Solved { nonce })], 0);
+        assert_eq!(merged, WorkMerge::Solved { assignment_id: assignments[0].assignment_id, nonce });
+    }
+}
@@ -0,0 +1,223 @@
+//! # Workers KV and Durable Object storage adapters.
+//!
+//! `KvChallengeStore` and `KvNonceRegistry` implement the storage
+//! contracts over any `KvNamespace`, the small get/put/delete surface
+//! shared by a Workers KV binding and Durable Object storage. The Worker
+//! implements `KvNamespace` over its binding; this crate does not
+//! depend on the `worker` runtime, so the adapters also run in tests
+//! and on native targets.
+//!
+//! Challenges are stored as `ChallengeKvEntry` records in the compact
+//! binary encoding, and every write carries a TTL that follows the
+//! challenge's submission deadline. Workers KV only deletes at that TTL
+//! lazily and is eventually consistent, so reads check the deadline
+//! themselves, and single use is only guaranteed when the namespace is
+//! backed by a Durable Object, which runs one request at a time.
+
+use crate::{
+    ChallengeKvEntry,
+    ChallengeStore,
+    IronShieldChallenge,
+    MIN_KV_TTL_SECS,
+    NonceRegistry,
+    challenge_kv_key
+};
+
+/// Prefix of the keys under which registered nonces are recorded.
+pub const NONCE_KV_PREFIX: &str = "nonce:";
+
+/// Byte storage under string keys with per-key expiry.
+///
+/// Implementations over Durable Object storage, which has no per-key
+/// expiry, may ignore `expiration_ttl`; expired records are then
+/// skipped on read and can be deleted from an alarm.
+pub trait KvNamespace {
+    /// # Arguments
+    /// * `key`: The storage key.
+    ///
+    /// # Returns
+    /// * `Result<Option<Vec<u8>>, String>`: The stored value, if any.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Stores a value, replacing any previous one.
+    ///
+    /// # Arguments
+    /// * `key`:            The storage key.
+    /// * `value`:          The value to store.
+    /// * `expiration_ttl`: Seconds after which the key may be deleted,
+    ///                     at least `MIN_KV_TTL_SECS`.
+    fn put(&mut self, key: &str, value: &[u8], expiration_ttl: u64) -> Result<(), String>;
+
+    /// Deletes a key; deleting a missing key is not an error.
+    ///
+    /// # Arguments
+    /// * `key`: The storage key.
+    fn delete(&mut self, key: &str) -> Result<(), String>;
+}
+
+/// `ChallengeStore` over a `KvNamespace`.
+#[derive(Debug)]
+pub struct KvChallengeStore<N> {
+    namespace: N,
+}
+
+impl<N: KvNamespace> KvChallengeStore<N> {
+    /// # Arguments
+    /// * `namespace`: The KV or Durable Object storage binding.
+    pub fn new(namespace: N) -> Self {
+        Self { namespace }
+    }
+
+    /// # Returns
+    /// * `N`: The wrapped namespace.
+    pub fn into_inner(self) -> N {
+        self.namespace
+    }
+}
+
+impl<N: KvNamespace> ChallengeStore for KvChallengeStore<N> {
+    fn put(&mut self, challenge: IronShieldChallenge) -> Result<[u8; 32], String> {
+        let entry: ChallengeKvEntry = ChallengeKvEntry::from_challenge(&challenge)?;
+        // Challenges are stored as they are issued, so the TTL counts
+        // from their creation time.
+        let ttl: u64 = entry.expiration_ttl_at(challenge.created_time);
+        self.namespace.put(&entry.key, &entry.value, ttl)?;
+        Ok(challenge.challenge_id())
+    }
+
+    fn get_by_id(&self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        self.namespace
+            .get(&challenge_kv_key(id))?
+            .map_or(Ok(None), |value| ChallengeKvEntry::decode_at(&value, now))
+    }
+
+    fn take(&mut self, id: &[u8; 32], now: i64) -> Result<Option<IronShieldChallenge>, String> {
+        let key: String = challenge_kv_key(id);
+        let Some(value) = self.namespace.get(&key)? else {
+            return Ok(None);
+        };
+        self.namespace.delete(&key)?;
+        ChallengeKvEntry::decode_at(&value, now)
+    }
+
+    fn purge_expired(&mut self, _now: i64) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+/// `NonceRegistry` over a `KvNamespace`.
+///
+/// Each nonce is recorded under `nonce:<hex nonce>` with the submission
+/// deadline of its challenge as an 8-byte big-endian value.
+#[derive(Debug)]
+pub struct KvNonceRegistry<N> {
+    namespace: N,
+}
+
+impl<N: KvNamespace> KvNonceRegistry<N> {
+    /// # Arguments
+    /// * `namespace`: The KV or Durable Object storage binding.
+    pub fn new(namespace: N) -> Self {
+        Self { namespace }
+    }
+
+    /// # Returns
+    /// * `N`: The wrapped namespace.
+    pub fn into_inner(self) -> N {
+        self.namespace
+    }
+}
+
+impl<N: KvNamespace> NonceRegistry for KvNonceRegistry<N> {
+    fn register(&mut self, challenge: &IronShieldChallenge, now: i64) -> Result<bool, String> {
+        let key: String = format!("{}{}", NONCE_KV_PREFIX, challenge.random_nonce);
+        if let Some(value) = self.namespace.get(&key)? {
+            let deadline: [u8; 8] = value.as_slice()
+                .try_into()
+                .map_err(|_| format!("Corrupted nonce record under {}", key))?;
+            if now <= i64::from_be_bytes(deadline) {
+                return Ok(false);
+            }
+        }
+
+        let deadline: i64 = challenge.effective_submission_deadline();
+        let remaining_ms: u64 = deadline.saturating_sub(now).try_into().unwrap_or(0);
+        let ttl: u64 = remaining_ms.div_ceil(1_000).max(MIN_KV_TTL_SECS);
+        self.namespace.put(&key, &deadline.to_be_bytes(), ttl)?;
+        Ok(true)
+    }
+
+    fn purge_expired(&mut self, _now: i64) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+#[cfg(all(test, feature = "rng"))]
+mod tests {
+    use super::*;
+    use crate::SigningKey;
+    use std::collections::HashMap;
+
+    /// In-memory namespace that records TTLs but never expires keys,
+    /// like a KV namespace that has not caught up yet.
+    #[derive(Default)]
+    struct FakeKv {
+        data: HashMap<String, (Vec<u8>, u64)>,
+    }
+
+    impl KvNamespace for FakeKv {
+        fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+            Ok(self.data.get(key).map(|(value, _)| value.clone()))
+        }
+
+        fn put(&mut self, key: &str, value: &[u8], expiration_ttl: u64) -> Result<(), String> {
+            self.data.insert(key.to_string(), (value.to_vec(), expiration_ttl));
+            Ok(())
+        }
+
+        fn delete(&mut self, key: &str) -> Result<(), String> {
+            self.data.remove(key);
+            Ok(())
+        }
+    }
+
+    fn challenge(expiration_time: i64) -> IronShieldChallenge {
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let mut challenge = IronShieldChallenge::new("example.com".parse().unwrap(), 1_000, key, [0x34; 32]);
+        challenge.created_time = 1_000;
+        challenge.expiration_time = expiration_time;
+        challenge
+    }
+
+    #[test]
+    fn test_challenge_store_over_kv() {
+        let issued = challenge(200_000);
+        let mut store = KvChallengeStore::new(FakeKv::default());
+        let id: [u8; 32] = store.put(issued.clone()).unwrap();
+
+        let namespace: &FakeKv = &store.namespace;
+        assert_eq!(namespace.data[&challenge_kv_key(&id)].1, 199);
+
+        assert_eq!(store.get_by_id(&id, 200_001).unwrap(), None);
+        assert_eq!(store.get_by_id(&id, 2_000).unwrap(), Some(issued.clone()));
+        assert_eq!(store.take(&id, 2_000).unwrap(), Some(issued));
+        assert_eq!(store.take(&id, 2_000).unwrap(), None);
+        assert!(store.into_inner().data.is_empty());
+    }
+
+    #[test]
+    fn test_nonce_registry_over_kv() {
+        let issued = challenge(5_000);
+        let mut registry = KvNonceRegistry::new(FakeKv::default());
+        assert!(registry.register(&issued, 2_000).unwrap());
+        assert!(!registry.register(&issued, 5_000).unwrap());
+        assert!(registry.register(&issued, 5_001).unwrap());
+
+        let mut namespace: FakeKv = registry.into_inner();
+        let key: String = format!("nonce:{}", issued.random_nonce);
+        assert_eq!(namespace.data[&key].1, MIN_KV_TTL_SECS);
+
+        namespace.put(&key, b"short", MIN_KV_TTL_SECS).unwrap();
+        assert!(KvNonceRegistry::new(namespace).register(&issued, 2_000).is_err());
+    }
+}